@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
-use bodycam_client::*;
+use bodycam_core::*;
 use tempfile::TempDir;
 use std::time::Duration;
 
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use patrol_client::{
+use bodycam_core::{
     api::{ApiClient, AddPlivoNumberRequest, AllocateNumberRequest, AddToWhitelistRequest},
     config::Config,
 };
@@ -0,0 +1,358 @@
+use anyhow::{Context, Result};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::SaltString};
+use chrono::{DateTime, Duration, Utc};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::backend::PlatformBackend;
+
+/// Wrong PINs or badges allowed before attempts are locked out
+const FREE_ATTEMPTS: u32 = 3;
+/// Lockout after the first failure past the free ones, doubling with each
+/// further failure up to `MAX_LOCKOUT_SECONDS`
+const BASE_LOCKOUT_SECONDS: i64 = 30;
+const MAX_LOCKOUT_SECONDS: i64 = 15 * 60;
+
+/// Local roles, lowest first; each role may do everything the ones below it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Operator,
+    Supervisor,
+    Admin,
+}
+
+/// Local operations that need more than physical access to the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivilegedOperation {
+    ExportRedacted,
+    /// Decrypt or export an original recording
+    ExportOriginal,
+    /// Review a recording on the device's screen
+    PlayRecording,
+    SwitchSite,
+    ClearStorage,
+    /// Leave the kiosk-mode UI
+    ExitKiosk,
+    Update,
+    Rollback,
+    Decommission,
+    /// Write an encrypted backup of the configuration and credentials
+    BackupConfig,
+    /// Replace the configuration from a backup
+    RestoreConfig,
+}
+
+impl PrivilegedOperation {
+    pub fn required_role(self) -> Role {
+        match self {
+            PrivilegedOperation::ExportRedacted => Role::Operator,
+            PrivilegedOperation::ExportOriginal
+            | PrivilegedOperation::PlayRecording
+            | PrivilegedOperation::SwitchSite
+            | PrivilegedOperation::ClearStorage
+            | PrivilegedOperation::ExitKiosk => Role::Supervisor,
+            PrivilegedOperation::Update
+            | PrivilegedOperation::Rollback
+            | PrivilegedOperation::Decommission
+            | PrivilegedOperation::BackupConfig
+            | PrivilegedOperation::RestoreConfig => Role::Admin,
+        }
+    }
+}
+
+/// Someone allowed to run privileged operations on this device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalUser {
+    pub name: String,
+    pub role: Role,
+    /// Argon2 hash of the user's PIN, from `hash-pin`
+    pub pin_hash: Option<String>,
+    /// ID read from the user's badge
+    pub badge_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessConfig {
+    /// Require a credential for privileged operations. Off by default so
+    /// devices without configured users aren't locked out.
+    pub enabled: bool,
+    pub users: Vec<LocalUser>,
+    /// Accept operator tokens issued by the backend, checked online
+    pub allow_backend_tokens: bool,
+    /// How long a UI login lasts
+    pub session_timeout_seconds: u64,
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            users: Vec::new(),
+            allow_backend_tokens: true,
+            session_timeout_seconds: 300,
+        }
+    }
+}
+
+/// What the person at the device presented
+#[derive(Debug, Clone)]
+pub enum Credential {
+    Pin(String),
+    Badge(String),
+    /// A short-lived operator token from the platform
+    BackendToken(String),
+}
+
+impl Credential {
+    fn kind(&self) -> &'static str {
+        match self {
+            Credential::Pin(_) => "pin",
+            Credential::Badge(_) => "badge",
+            Credential::BackendToken(_) => "backend_token",
+        }
+    }
+}
+
+/// An authenticated person
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operator {
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub operator: Operator,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Failed PIN and badge attempts, kept on disk so starting the CLI again
+/// doesn't reset them
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Lockout {
+    failures: u32,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl Lockout {
+    async fn load(path: &PathBuf) -> Self {
+        tokio::fs::read_to_string(path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    async fn save(&self, path: &PathBuf) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string(self)?).await?;
+        Ok(())
+    }
+
+    fn check(&self, now: DateTime<Utc>) -> Result<()> {
+        match self.locked_until {
+            Some(until) if until > now => Err(anyhow::anyhow!(
+                "Too many failed attempts; try again in {} s", (until - now).num_seconds().max(1)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn record(&mut self, succeeded: bool, now: DateTime<Utc>) {
+        if succeeded {
+            *self = Self::default();
+            return;
+        }
+        self.failures += 1;
+        if self.failures > FREE_ATTEMPTS {
+            let doublings = (self.failures - FREE_ATTEMPTS - 1).min(16);
+            let seconds = (BASE_LOCKOUT_SECONDS << doublings).min(MAX_LOCKOUT_SECONDS);
+            self.locked_until = Some(now + Duration::seconds(seconds));
+        }
+    }
+}
+
+/// Checks credentials against the configured users and keeps the UI session
+pub struct AccessControl {
+    config: AccessConfig,
+    session: Option<Session>,
+    lockout_path: PathBuf,
+}
+
+impl AccessControl {
+    pub fn new(config: AccessConfig) -> Self {
+        let lockout_path = std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("access_lockout.json");
+        Self { config, session: None, lockout_path }
+    }
+
+    pub fn with_lockout_path(mut self, path: PathBuf) -> Self {
+        self.lockout_path = path;
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Hash a PIN for `LocalUser::pin_hash`
+    pub fn hash_pin(pin: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(pin.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| anyhow::anyhow!("Failed to hash PIN: {}", e))
+    }
+
+    /// The operator `credential` belongs to. After `FREE_ATTEMPTS` wrong
+    /// PINs or badges in a row, further attempts are refused for a while
+    /// that doubles with each failure.
+    pub async fn authenticate(&self, credential: &Credential, backend: &dyn PlatformBackend) -> Result<Operator> {
+        if matches!(credential, Credential::BackendToken(_)) {
+            return self.verify(credential, backend).await;
+        }
+
+        let mut lockout = Lockout::load(&self.lockout_path).await;
+        lockout.check(Utc::now())?;
+        let result = self.verify(credential, backend).await;
+        lockout.record(result.is_ok(), Utc::now());
+        if let Err(e) = lockout.save(&self.lockout_path).await {
+            tracing::warn!("Failed to save access lockout: {}", e);
+        }
+        result
+    }
+
+    async fn verify(&self, credential: &Credential, backend: &dyn PlatformBackend) -> Result<Operator> {
+        let user = match credential {
+            Credential::Pin(pin) => self.config.users.iter().find(|user| {
+                user.pin_hash.as_deref()
+                    .and_then(|hash| PasswordHash::new(hash).ok())
+                    .is_some_and(|hash| Argon2::default().verify_password(pin.as_bytes(), &hash).is_ok())
+            }),
+            Credential::Badge(badge) => self.config.users.iter()
+                .find(|user| user.badge_id.as_deref() == Some(badge.as_str())),
+            Credential::BackendToken(token) => {
+                if !self.config.allow_backend_tokens {
+                    return Err(anyhow::anyhow!("Backend operator tokens are not accepted on this device"));
+                }
+                return backend.verify_operator_token(token).await
+                    .context("Operator token rejected");
+            }
+        };
+
+        user.map(|user| Operator { name: user.name.clone(), role: user.role })
+            .ok_or_else(|| anyhow::anyhow!("Unknown {}", credential.kind()))
+    }
+
+    /// Start a UI session for `operator`
+    pub fn login(&mut self, operator: Operator) -> &Session {
+        let timeout = Duration::seconds(self.config.session_timeout_seconds as i64);
+        self.session.insert(Session { operator, expires_at: Utc::now() + timeout })
+    }
+
+    pub fn logout(&mut self) {
+        self.session = None;
+    }
+
+    /// The logged in operator, if the session hasn't expired
+    pub fn current(&self) -> Option<&Operator> {
+        self.session.as_ref()
+            .filter(|session| session.expires_at > Utc::now())
+            .map(|session| &session.operator)
+    }
+}
+
+/// Whether `operator` may run `operation`
+pub fn check(operator: &Operator, operation: PrivilegedOperation) -> Result<()> {
+    if operator.role < operation.required_role() {
+        return Err(anyhow::anyhow!(
+            "{:?} requires the {:?} role; {} is {:?}",
+            operation, operation.required_role(), operator.name, operator.role
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn access(dir: &tempfile::TempDir) -> AccessControl {
+        AccessControl::new(AccessConfig {
+            enabled: true,
+            users: vec![
+                LocalUser {
+                    name: "guard".to_string(),
+                    role: Role::Operator,
+                    pin_hash: Some(AccessControl::hash_pin("1234").unwrap()),
+                    badge_id: None,
+                },
+                LocalUser {
+                    name: "sergeant".to_string(),
+                    role: Role::Supervisor,
+                    pin_hash: None,
+                    badge_id: Some("badge-77".to_string()),
+                },
+            ],
+            allow_backend_tokens: false,
+            session_timeout_seconds: 60,
+        }).with_lockout_path(dir.path().join("lockout.json"))
+    }
+
+    #[tokio::test]
+    async fn test_credentials_map_to_users() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = access(&dir);
+        let backend = crate::backend::create_backend(&Config::default()).unwrap();
+
+        let guard = access.authenticate(&Credential::Pin("1234".to_string()), backend.as_ref()).await.unwrap();
+        assert_eq!(guard.role, Role::Operator);
+        let sergeant = access.authenticate(&Credential::Badge("badge-77".to_string()), backend.as_ref()).await.unwrap();
+        assert_eq!(sergeant.name, "sergeant");
+
+        assert!(access.authenticate(&Credential::Pin("0000".to_string()), backend.as_ref()).await.is_err());
+        assert!(access.authenticate(&Credential::BackendToken("t".to_string()), backend.as_ref()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_repeated_failures_lock_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = access(&dir);
+        let backend = crate::backend::create_backend(&Config::default()).unwrap();
+        let wrong = Credential::Pin("0000".to_string());
+        let right = Credential::Pin("1234".to_string());
+
+        for _ in 0..FREE_ATTEMPTS {
+            assert!(access.authenticate(&wrong, backend.as_ref()).await.is_err());
+        }
+        assert!(access.authenticate(&right, backend.as_ref()).await.is_ok());
+
+        for _ in 0..=FREE_ATTEMPTS {
+            assert!(access.authenticate(&wrong, backend.as_ref()).await.is_err());
+        }
+        let error = access.authenticate(&right, backend.as_ref()).await.unwrap_err();
+        assert!(error.to_string().contains("Too many failed attempts"));
+    }
+
+    #[test]
+    fn test_roles_are_hierarchical() {
+        let supervisor = Operator { name: "sergeant".to_string(), role: Role::Supervisor };
+        assert!(check(&supervisor, PrivilegedOperation::ExportRedacted).is_ok());
+        assert!(check(&supervisor, PrivilegedOperation::ClearStorage).is_ok());
+        assert!(check(&supervisor, PrivilegedOperation::Decommission).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut access = access(&dir);
+        assert!(access.current().is_none());
+        access.login(supervisor.clone());
+        assert_eq!(access.current(), Some(&supervisor));
+        access.logout();
+        assert!(access.current().is_none());
+    }
+}
@@ -2,12 +2,70 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
 use std::collections::HashMap;
+use tokio_util::sync::CancellationToken;
 
 use crate::config::Config;
-use crate::device::{DeviceStatus, DiagnosticsReport};
+use crate::cancellation::cancellable;
+use crate::circuit_breaker::{endpoint_key, BreakerSettings, CircuitBreakers};
+use crate::error_handling::ApiError;
+use crate::retry::{is_retryable_status, send_with_retry, RetryPolicy};
+use crate::device::DeviceStatus;
+use crate::diagnostics::ComprehensiveDiagnostics;
+use crate::status_report::StatusDelta;
 use crate::media::RecordingSegment;
-use crate::integrity::{VideoIntegrity, IntegrityVerification};
-use std::path::PathBuf;
+use crate::integrity::{VideoIntegrity, IntegrityVerification, IntegrityManager, ChecksumConfirmation, UploadChecksums};
+use crate::streaming::local_copy::TimeRange;
+use crate::streaming::StreamQuality;
+use std::path::{Path, PathBuf};
+
+/// Header carrying the idempotency key on REST mutations
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// Identifies one logical mutation. The same key goes out on every retry of
+/// that mutation so the backend can drop duplicates from flaky links.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// A key fixed by what the mutation acts on, e.g. confirming upload
+    /// `id`, so separate attempts at the same operation share it
+    pub fn derived(operation: &str, id: &str) -> Self {
+        Self(format!("{}:{}", operation, id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for IdempotencyKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Add `key` to a set of request headers
+pub fn with_idempotency_key(
+    mut headers: reqwest::header::HeaderMap,
+    key: &IdempotencyKey,
+) -> Result<reqwest::header::HeaderMap> {
+    headers.insert(
+        IDEMPOTENCY_HEADER,
+        reqwest::header::HeaderValue::from_str(key.as_str()).context("Invalid idempotency key")?,
+    );
+    Ok(headers)
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeviceRegistrationRequest {
@@ -44,6 +102,8 @@ pub struct MediaUploadRequest {
     pub duration: u64,
     pub checksum: String,
     pub metadata: serde_json::Value,
+    /// Sidecar GPS track recorded with the segment
+    pub location_track: Option<crate::location_track::LocationTrackRef>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,11 +113,23 @@ pub struct MediaUploadResponse {
     pub expires_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Piece size for the per-chunk checksums sent when confirming an upload,
+/// so the server can point at the damaged part of a file
+pub const CHECKSUM_CHUNK_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadConfirmRequest {
+    pub checksum_algorithm: String,
+    pub checksums: UploadChecksums,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StreamingStartRequest {
     pub incident_id: Option<String>,
-    pub quality: String,
+    pub quality: StreamQuality,
     pub include_audio: bool,
+    /// Ingest protocols this client can publish with
+    pub supported_protocols: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -65,10 +137,34 @@ pub struct StreamingStartResponse {
     pub stream_id: String,
     pub rtmp_url: String,
     pub stream_key: String,
+    /// SRT ingest endpoint, when the backend offers one
+    pub srt_url: Option<String>,
     pub expires_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Parts of a live stream the server received intact
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamReceivedResponse {
+    pub stream_id: String,
+    pub received: Vec<TimeRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerTimeResponse {
+    pub server_time_ms: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+pub struct StreamGapUploadRequest {
+    pub stream_id: String,
+    pub incident_id: Option<String>,
+    pub start: chrono::DateTime<chrono::Utc>,
+    pub end: chrono::DateTime<chrono::Utc>,
+    pub file_size: u64,
+    pub checksum: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceMetrics {
     pub device_id: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
@@ -79,6 +175,9 @@ pub struct DeviceMetrics {
     pub temperature: f32,
     pub network_quality: String,
     pub active_incidents: u32,
+    /// Missing from metrics journaled before usage was tracked
+    #[serde(default)]
+    pub data_usage: Option<crate::data_usage::DataUsageReport>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -117,7 +216,7 @@ pub struct MakeCallResponse {
     pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommunicationContact {
     pub id: String,
     pub name: String,
@@ -260,28 +359,37 @@ pub struct ApiClient {
     config: Config,
     client: Client,
     base_url: String,
+    /// Built once; the credentials don't change for a client's lifetime
+    auth_headers: std::result::Result<reqwest::header::HeaderMap, String>,
+    /// Interrupts uploads in progress
+    cancel: CancellationToken,
 }
 
 impl ApiClient {
     pub fn new(config: Config) -> Self {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(config.network.timeout))
-            .https_only(true)
-            .danger_accept_invalid_certs(false)
-            .build()
+        let client = crate::http::shared_client(&config.network)
             .expect("Failed to create HTTP client");
+        let auth_headers = Self::build_auth_headers(&config).map_err(|e| format!("{:#}", e));
 
         Self {
             config,
             client,
             base_url: String::new(),
+            auth_headers,
+            cancel: CancellationToken::new(),
         }
     }
 
-    fn get_auth_headers(&self) -> Result<reqwest::header::HeaderMap> {
+    /// Abort uploads when `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn build_auth_headers(config: &Config) -> Result<reqwest::header::HeaderMap> {
         let mut headers = reqwest::header::HeaderMap::new();
         
-        if let Some(token) = &self.config.auth_token {
+        if let Some(token) = &config.auth_token {
             headers.insert(
                 reqwest::header::AUTHORIZATION,
                 reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
@@ -289,7 +397,7 @@ impl ApiClient {
             );
         }
         
-        if let Some(api_key) = &self.config.api_key {
+        if let Some(api_key) = &config.api_key {
             headers.insert(
                 "X-API-Key",
                 reqwest::header::HeaderValue::from_str(api_key)
@@ -305,35 +413,65 @@ impl ApiClient {
         Ok(headers)
     }
 
-    async fn make_request_with_retry<F, Fut, T>(
+    fn get_auth_headers(&self) -> Result<reqwest::header::HeaderMap> {
+        self.auth_headers.clone().map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// `value` as a JSON body, compressed per `network.compression`. Used for
+    /// the status and metrics payloads sent every few seconds.
+    fn compressed_json<T: Serialize>(&self, value: &T) -> Result<(reqwest::header::HeaderMap, Vec<u8>)> {
+        let mut headers = self.get_auth_headers()?;
+        let body = serde_json::to_vec(value)?;
+        if !self.config.network.compression {
+            return Ok((headers, body));
+        }
+
+        let algorithm = self.config.network.compression_algorithm;
+        headers.insert(
+            reqwest::header::CONTENT_ENCODING,
+            reqwest::header::HeaderValue::from_static(algorithm.content_encoding()),
+        );
+        Ok((headers, algorithm.encode(&body)?))
+    }
+
+    /// Send a request to `url` with retries, unless that endpoint's circuit
+    /// breaker is open, in which case fail at once
+    async fn make_request_with_retry<F, Fut>(
         &self,
+        url: &str,
         make_request: F,
         max_retries: u32,
-    ) -> Result<T>
+    ) -> Result<reqwest::Response>
     where
         F: Fn() -> Fut,
-        Fut: std::future::Future<Output = Result<T>>,
+        Fut: std::future::Future<Output = Result<reqwest::Response>>,
     {
-        let mut retries = 0;
-        let mut last_error = None;
-
-        while retries <= max_retries {
-            match make_request().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    last_error = Some(e);
-                    retries += 1;
-                    
-                    if retries <= max_retries {
-                        let delay = std::time::Duration::from_millis(500 * retries as u64);
-                        tokio::time::sleep(delay).await;
-                        tracing::warn!("Request failed, retrying {}/{}: {}", retries, max_retries, last_error.as_ref().unwrap());
-                    }
-                }
+        let endpoint = endpoint_key(url);
+        let breakers = CircuitBreakers::global();
+        let Some(permit) = breakers.try_acquire(&endpoint) else {
+            return Err(ApiError::CircuitOpen { endpoint }.into());
+        };
+
+        let settings = BreakerSettings {
+            failure_threshold: self.config.network.circuit_failure_threshold,
+            open_for: std::time::Duration::from_secs(self.config.network.circuit_open_seconds),
+        };
+        let policy = RetryPolicy::from_config(&self.config.network).with_max_retries(max_retries);
+        let timeout = crate::http::endpoint_timeout(&self.config.network, &endpoint);
+        let result = send_with_retry(&policy, || {
+            let request = make_request();
+            async move {
+                tokio::time::timeout(timeout, request).await
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("Request timed out after {:?}", timeout)))
             }
-        }
+        }).await;
 
-        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Request failed after retries")))
+        // Only an unreachable or failing server counts; a 4xx means it's up
+        match &result {
+            Ok(response) if !is_retryable_status(response.status()) => permit.succeeded(),
+            _ => permit.failed(&settings),
+        }
+        ApiError::check(&endpoint, result).await
     }
 
     // Device Management Endpoints
@@ -342,6 +480,7 @@ impl ApiClient {
         device_name: &str,
         site_id: &str,
         hardware_info: HardwareInfo,
+        idempotency_key: &IdempotencyKey,
     ) -> Result<DeviceRegistrationResponse> {
         let url = format!("{}/api/devices/register", self.config.server_url);
         
@@ -352,8 +491,8 @@ impl ApiClient {
             hardware_info,
         };
 
-        let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let headers = with_idempotency_key(self.get_auth_headers()?, idempotency_key)?;
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -379,20 +518,66 @@ impl ApiClient {
     ) -> Result<()> {
         let url = format!("{}/api/devices/{}/status", self.config.server_url, device_id);
         
-        let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let (headers, body) = self.compressed_json(status)?;
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
-                .json(status)
+                .body(body.clone())
                 .send()
                 .await
                 .context("Failed to update device status")
         }, self.config.network.retry_attempts).await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!("Device status update failed: {}", error_text));
+            return Err(ApiError::from_response("Device status update", response).await);
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_device_status_delta(&self, delta: &StatusDelta) -> Result<()> {
+        let url = format!("{}/api/devices/{}/status/delta", self.config.server_url, delta.device_id);
+
+        let (headers, body) = self.compressed_json(delta)?;
+        let response = self.make_request_with_retry(&url, || async {
+            self.client
+                .patch(&url)
+                .headers(headers.clone())
+                .body(body.clone())
+                .send()
+                .await
+                .context("Failed to send status delta")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response("Device status delta", response).await);
+        }
+
+        Ok(())
+    }
+
+    /// Statuses recorded while offline, sent as one compressed request
+    pub async fn update_device_status_batch(
+        &self,
+        device_id: &str,
+        statuses: &[DeviceStatus],
+    ) -> Result<()> {
+        let url = format!("{}/api/devices/{}/status/batch", self.config.server_url, device_id);
+
+        let (headers, body) = self.compressed_json(&serde_json::json!({ "statuses": statuses }))?;
+        let response = self.make_request_with_retry(&url, || async {
+            self.client
+                .post(&url)
+                .headers(headers.clone())
+                .body(body.clone())
+                .send()
+                .await
+                .context("Failed to send status batch")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response("Device status batch", response).await);
         }
 
         Ok(())
@@ -400,12 +585,12 @@ impl ApiClient {
 
     pub async fn report_diagnostics(
         &self,
-        diagnostics: &DiagnosticsReport,
+        diagnostics: &ComprehensiveDiagnostics,
     ) -> Result<()> {
-        let url = format!("{}/api/devices/{}/diagnostics", self.config.server_url, diagnostics.device_id);
+        let url = format!("{}/api/devices/{}/diagnostics", self.config.server_url, diagnostics.device_info.device_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -416,8 +601,7 @@ impl ApiClient {
         }, self.config.network.retry_attempts).await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!("Diagnostics report failed: {}", error_text));
+            return Err(ApiError::from_response("Diagnostics report", response).await);
         }
 
         Ok(())
@@ -444,10 +628,11 @@ impl ApiClient {
             duration: segment.duration.unwrap_or(0),
             checksum,
             metadata: serde_json::to_value(&segment.metadata)?,
+            location_track: segment.metadata.location_track.clone(),
         };
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -471,6 +656,10 @@ impl ApiClient {
         segment: &RecordingSegment,
         upload_url: &str,
     ) -> Result<()> {
+        cancellable(&self.cancel, "segment upload", self.put_segment(segment, upload_url)).await
+    }
+
+    async fn put_segment(&self, segment: &RecordingSegment, upload_url: &str) -> Result<()> {
         let file_path = PathBuf::from(&segment.file_path);
         if !file_path.exists() {
             return Err(anyhow::anyhow!("Segment file not found: {}", segment.file_path));
@@ -480,7 +669,7 @@ impl ApiClient {
             .context("Failed to read segment file")?;
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(upload_url, || async {
             self.client
                 .put(upload_url)
                 .headers(headers.clone())
@@ -498,17 +687,28 @@ impl ApiClient {
         Ok(())
     }
 
+    /// Confirm an upload by sending its checksums. Only succeeds once the
+    /// server reports that the stored object hashes to the same SHA-256.
     pub async fn confirm_upload(
         &self,
         segment_id: &str,
+        checksums: &UploadChecksums,
     ) -> Result<()> {
         let url = format!("{}/api/media/{}/confirm", self.config.server_url, segment_id);
-        
-        let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+
+        let request = UploadConfirmRequest {
+            checksum_algorithm: "sha256".to_string(),
+            checksums: checksums.clone(),
+        };
+
+        // Keyed by upload so a repeated confirmation is recognised as the same one
+        let key = IdempotencyKey::derived("confirm_upload", segment_id);
+        let headers = with_idempotency_key(self.get_auth_headers()?, &key)?;
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
+                .json(&request)
                 .send()
                 .await
                 .context("Failed to confirm upload")
@@ -519,26 +719,58 @@ impl ApiClient {
             return Err(anyhow::anyhow!("Upload confirmation failed: {}", error_text));
         }
 
-        Ok(())
+        let confirmation: ChecksumConfirmation = response.json().await
+            .context("Invalid upload confirmation response")?;
+        confirmation.ensure_matches(checksums)
+            .with_context(|| format!("Upload {} failed server verification", segment_id))
+    }
+
+    /// Upload a finished recording segment and wait for the server to verify
+    /// its checksum. The segment is only safe to delete locally when this
+    /// returns the verified checksums.
+    pub async fn upload_recording_segment(
+        &self,
+        segment: &RecordingSegment,
+    ) -> Result<UploadChecksums> {
+        let checksums = cancellable(&self.cancel, "segment upload", IntegrityManager::calculate_upload_checksums(
+            Path::new(&segment.file_path),
+            CHECKSUM_CHUNK_SIZE,
+        )).await?;
+
+        // Don't ship a file that no longer matches what was recorded
+        if let Some(integrity) = &segment.integrity {
+            if !integrity.sha256_hash.eq_ignore_ascii_case(&checksums.sha256) {
+                return Err(anyhow::anyhow!(
+                    "Segment {} changed since it was recorded: expected {}, found {}",
+                    segment.id, integrity.sha256_hash, checksums.sha256
+                ));
+            }
+        }
+
+        let upload = self.request_upload_url(segment).await?;
+        self.upload_segment(segment, &upload.upload_url).await?;
+        self.confirm_upload(&upload.upload_id, &checksums).await?;
+        Ok(checksums)
     }
 
     // Streaming Endpoints
     pub async fn start_streaming(
         &self,
         incident_id: Option<String>,
-        quality: &str,
+        quality: StreamQuality,
         include_audio: bool,
     ) -> Result<StreamingStartResponse> {
         let url = format!("{}/api/streaming/start", self.config.server_url);
         
         let request = StreamingStartRequest {
             incident_id,
-            quality: quality.to_string(),
+            quality,
             include_audio,
+            supported_protocols: vec!["rtmp".to_string(), "srt".to_string()],
         };
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -564,7 +796,7 @@ impl ApiClient {
         let url = format!("{}/api/streaming/{}/stop", self.config.server_url, stream_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -581,6 +813,133 @@ impl ApiClient {
         Ok(())
     }
 
+    /// Open the server-sent event feed for a live stream (viewer counts and
+    /// talk-back). The feed is long-lived, so the client timeout is lifted.
+    pub async fn open_stream_events(
+        &self,
+        stream_id: &str,
+    ) -> Result<reqwest::Response> {
+        let url = format!("{}/api/streaming/{}/events", self.config.server_url, stream_id);
+
+        let headers = self.get_auth_headers()?;
+        let response = self.client
+            .get(&url)
+            .headers(headers)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .timeout(std::time::Duration::from_secs(24 * 60 * 60))
+            .send()
+            .await
+            .context("Failed to open stream event feed")?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Stream event feed request failed: {}", error_text));
+        }
+
+        Ok(response)
+    }
+
+    /// Ranges of a stream the ingest actually received, used to find
+    /// what has to be filled in from the local copy
+    pub async fn get_stream_received_ranges(
+        &self,
+        stream_id: &str,
+    ) -> Result<Vec<TimeRange>> {
+        let url = format!("{}/api/streaming/{}/received", self.config.server_url, stream_id);
+
+        let headers = self.get_auth_headers()?;
+        let response = self.make_request_with_retry(&url, || async {
+            self.client
+                .get(&url)
+                .headers(headers.clone())
+                .send()
+                .await
+                .context("Failed to get received stream ranges")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Received ranges request failed: {}", error_text));
+        }
+
+        let received: StreamReceivedResponse = response.json().await?;
+        Ok(received.received)
+    }
+
+    /// The server's clock, for reconciling stream timestamps. Not retried,
+    /// since a slow answer is no use for clock sync.
+    pub async fn get_server_time(&self) -> Result<chrono::DateTime<chrono::Utc>> {
+        let url = format!("{}/api/time", self.config.server_url);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.get_auth_headers()?)
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .context("Failed to get server time")?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Server time request failed: {}", error_text));
+        }
+
+        let time: ServerTimeResponse = response.json().await?;
+        chrono::DateTime::from_timestamp_millis(time.server_time_ms)
+            .ok_or_else(|| anyhow::anyhow!("Invalid server time {}", time.server_time_ms))
+    }
+
+    /// Upload a locally recorded piece of a stream the server never received
+    pub async fn upload_stream_gap(
+        &self,
+        request: &StreamGapUploadRequest,
+        file_path: &Path,
+    ) -> Result<()> {
+        cancellable(&self.cancel, "stream gap upload", self.send_stream_gap(request, file_path)).await
+    }
+
+    async fn send_stream_gap(&self, request: &StreamGapUploadRequest, file_path: &Path) -> Result<()> {
+        let url = format!("{}/api/streaming/{}/gap-upload", self.config.server_url, request.stream_id);
+
+        let headers = self.get_auth_headers()?;
+        let response = self.make_request_with_retry(&url, || async {
+            self.client
+                .post(&url)
+                .headers(headers.clone())
+                .json(request)
+                .send()
+                .await
+                .context("Failed to request stream gap upload")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Stream gap upload request failed: {}", error_text));
+        }
+
+        let upload: MediaUploadResponse = response.json().await?;
+        let checksums = IntegrityManager::calculate_upload_checksums(file_path, CHECKSUM_CHUNK_SIZE).await?;
+        let file_data = tokio::fs::read(file_path).await
+            .context("Failed to read stream segment")?;
+
+        let response = self.make_request_with_retry(&upload.upload_url, || async {
+            self.client
+                .put(&upload.upload_url)
+                .headers(headers.clone())
+                .body(file_data.clone())
+                .send()
+                .await
+                .context("Failed to upload stream segment")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Stream segment upload failed: {}", error_text));
+        }
+
+        self.confirm_upload(&upload.upload_id, &checksums).await
+    }
+
     // Metrics Endpoints
     pub async fn send_metrics(
         &self,
@@ -588,20 +947,19 @@ impl ApiClient {
     ) -> Result<()> {
         let url = format!("{}/api/devices/{}/metrics", self.config.server_url, metrics.device_id);
         
-        let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let (headers, body) = self.compressed_json(metrics)?;
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
-                .json(metrics)
+                .body(body.clone())
                 .send()
                 .await
                 .context("Failed to send metrics")
         }, self.config.network.retry_attempts).await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!("Metrics send failed: {}", error_text));
+            return Err(ApiError::from_response("Metrics send", response).await);
         }
 
         Ok(())
@@ -614,7 +972,7 @@ impl ApiClient {
         let url = format!("{}/api/devices/{}/config", self.config.server_url, device_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -654,7 +1012,7 @@ impl ApiClient {
         };
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -694,7 +1052,7 @@ impl ApiClient {
         };
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -738,7 +1096,7 @@ impl ApiClient {
         }
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -781,7 +1139,7 @@ impl ApiClient {
         }
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -820,7 +1178,7 @@ impl ApiClient {
         }
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -838,6 +1196,47 @@ impl ApiClient {
         Ok(contacts)
     }
 
+    /// The alert sound library assigned to this device
+    pub async fn get_audio_presets(&self, device_id: &str) -> Result<Vec<crate::audio_presets::AudioPreset>> {
+        let url = format!("{}/api/devices/{}/audio-presets", self.config.server_url, device_id);
+
+        let headers = self.get_auth_headers()?;
+        let response = self.make_request_with_retry(&url, || async {
+            self.client
+                .get(&url)
+                .headers(headers.clone())
+                .send()
+                .await
+                .context("Failed to get audio presets")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Audio preset fetch failed: {}", error_text));
+        }
+
+        let presets = response.json().await?;
+        Ok(presets)
+    }
+
+    pub async fn download_audio_preset(&self, preset: &crate::audio_presets::AudioPreset) -> Result<Vec<u8>> {
+        let headers = self.get_auth_headers()?;
+        let response = self.make_request_with_retry(&preset.url, || async {
+            self.client
+                .get(&preset.url)
+                .headers(headers.clone())
+                .send()
+                .await
+                .context("Failed to download audio preset")
+        }, self.config.network.retry_attempts).await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Audio preset download failed: HTTP {}", response.status()));
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
     // Convenience methods for emergency communications
     pub async fn send_emergency_sms(
         &self,
@@ -898,7 +1297,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/add-number", self.config.server_url);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -924,7 +1323,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/allocate-number", self.config.server_url);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -953,7 +1352,7 @@ impl ApiClient {
         });
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -992,7 +1391,7 @@ impl ApiClient {
         }
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -1017,7 +1416,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/device/{}/number", self.config.server_url, device_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -1053,7 +1452,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/device/{}/capabilities", self.config.server_url, device_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
@@ -1112,7 +1511,7 @@ impl ApiClient {
         }
 
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .patch(&url)
                 .headers(headers.clone())
@@ -1138,7 +1537,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/whitelist/add", self.config.server_url);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
@@ -1164,7 +1563,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/whitelist/{}", self.config.server_url, whitelist_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .delete(&url)
                 .headers(headers.clone())
@@ -1188,7 +1587,7 @@ impl ApiClient {
         let url = format!("{}/api/plivo-management/whitelist/{}", self.config.server_url, plivo_number_id);
         
         let headers = self.get_auth_headers()?;
-        let response = self.make_request_with_retry(|| async {
+        let response = self.make_request_with_retry(&url, || async {
             self.client
                 .get(&url)
                 .headers(headers.clone())
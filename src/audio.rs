@@ -1,14 +1,20 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
+use tokio::sync::Notify;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use crate::audio_mixer::{AudioMixerConfig, Mixer, TrackState};
 use crate::config::Config;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioPlaybackRequest {
     pub source: AudioSource,
+    /// Level of this sound from 0 to 1, under the system volume
     pub volume: Option<f32>,
     pub loop_playback: Option<bool>,
     pub priority: AudioPriority,
@@ -43,7 +49,8 @@ pub enum TtsProvider {
     OpenAI,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Decides which sound is heard when several play at once; see `audio_mixer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum AudioPriority {
     Low,
     Normal,
@@ -57,11 +64,104 @@ pub struct AudioStatus {
     pub current_source: Option<String>,
     pub volume: f32,
     pub playback_id: Option<String>,
+    /// Live microphone input level in dBFS
+    pub input_level_db: Option<f32>,
+    pub input_peak_db: Option<f32>,
+    /// Seconds the microphone has been below the silence threshold
+    pub input_silent_seconds: Option<u64>,
+    /// Every sound playing or waiting to resume, oldest first
+    #[serde(default)]
+    pub active: Vec<ActivePlayback>,
+    /// Calibration and ambient adjustment applied to new sounds
+    #[serde(default)]
+    pub output_trim_db: f32,
+    /// Ambient noise at the microphone, measured while the speaker is quiet
+    #[serde(default)]
+    pub ambient_db: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivePlayback {
+    pub playback_id: String,
+    pub source: String,
+    pub priority: AudioPriority,
+    pub state: TrackState,
+}
+
+/// A sound resolved to a file, ready to hand to the player
+struct PlayerInput {
+    file: PathBuf,
+    /// Rendered speech, deleted once played
+    temporary: bool,
+    looping: bool,
+    gain_db: f32,
+    simulated: bool,
+}
+
+impl PlayerInput {
+    /// ffmpeg playing the file from `position` at `gain_db`. In simulation
+    /// the output is discarded, in real time so the mixer behaves the same.
+    fn command(&self, position: Duration, gain_db: f32) -> Command {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-hide_banner", "-loglevel", "error", "-nostdin"]);
+        if self.looping {
+            cmd.args(["-stream_loop", "-1"]);
+        }
+        if self.simulated {
+            cmd.arg("-re");
+        }
+        cmd.arg("-ss").arg(format!("{:.3}", position.as_secs_f64()))
+            .arg("-i").arg(&self.file)
+            .arg("-af").arg(format!("volume={:.1}dB", gain_db));
+        if self.simulated {
+            cmd.args(["-f", "null", "-"]);
+        } else {
+            cmd.args(["-f", "alsa", "default"]);
+        }
+        cmd.kill_on_drop(true);
+        cmd
+    }
+}
+
+struct Playback {
+    source: String,
+    priority: AudioPriority,
+    /// Signalled when the mixer changes this sound's state or it's stopped
+    waker: Arc<Notify>,
+}
+
+struct MixerState {
+    mixer: Mixer,
+    playbacks: HashMap<String, Playback>,
+    /// Speaker calibration plus the ambient adjustment, applied to every sound
+    output_trim_db: f32,
+    ambient_db: Option<f32>,
+}
+
+impl MixerState {
+    fn wake(&self, changes: &[(String, TrackState)]) {
+        for (id, _) in changes {
+            if let Some(playback) = self.playbacks.get(id) {
+                playback.waker.notify_one();
+            }
+        }
+    }
+
+    fn remove(&mut self, id: &str) {
+        let changes = self.mixer.finish(id);
+        if let Some(playback) = self.playbacks.remove(id) {
+            playback.waker.notify_one();
+        }
+        self.wake(&changes);
+    }
 }
 
 pub struct AudioManager {
     config: Config,
     preset_files: std::collections::HashMap<String, PathBuf>,
+    state: Arc<Mutex<MixerState>>,
+    /// Signalled whenever a sound finishes
+    ended: Arc<Notify>,
 }
 
 impl AudioManager {
@@ -76,34 +176,124 @@ impl AudioManager {
         preset_files.insert("start".to_string(), PathBuf::from("/usr/share/sounds/start.wav"));
         preset_files.insert("stop".to_string(), PathBuf::from("/usr/share/sounds/stop.wav"));
         
+        let state = MixerState {
+            mixer: Mixer::new(&config.audio.mixer),
+            playbacks: HashMap::new(),
+            output_trim_db: 0.0,
+            ambient_db: None,
+        };
+
         Self {
             config,
             preset_files,
+            state: Arc::new(Mutex::new(state)),
+            ended: Arc::new(Notify::new()),
         }
     }
 
+    /// Start a sound and return its playback id without waiting for it to
+    /// finish. Sounds already playing are ducked or paused if it outranks them.
     pub async fn play_audio(&self, request: AudioPlaybackRequest) -> Result<String> {
         let playback_id = Uuid::new_v4().to_string();
         
-        match request.source {
+        let (file, source, temporary) = match request.source {
             AudioSource::CustomFile { file_path } => {
-                self.play_custom_file(&file_path, request.volume, request.loop_playback).await?;
+                (self.custom_file(&file_path)?, file_path, false)
             }
             AudioSource::PresetFile { file_id } => {
-                self.play_preset_file(&file_id, request.volume, request.loop_playback).await?;
+                (self.preset_file(&file_id)?, format!("preset:{}", file_id), false)
             }
             AudioSource::TtsLocal { text, voice, rate } => {
-                self.play_tts_local(&text, voice.as_deref(), rate, request.volume).await?;
+                (self.render_tts_local(&text, voice.as_deref(), rate).await?, format!("tts:{}", text), true)
             }
             AudioSource::TtsRemote { text, provider, voice, api_key } => {
-                self.play_tts_remote(&text, provider, voice.as_deref(), api_key.as_deref(), request.volume).await?;
+                (self.render_tts_remote(&text, provider, voice.as_deref(), api_key.as_deref()).await?, format!("tts:{}", text), true)
             }
-        }
+        };
+
+        let input = PlayerInput {
+            file,
+            temporary,
+            looping: request.loop_playback.unwrap_or(false),
+            gain_db: request.volume.map_or(0.0, |volume| 20.0 * volume.clamp(0.01, 1.0).log10()),
+            simulated: self.config.simulation.enabled,
+        };
+        self.start_playback(playback_id.clone(), source, request.priority, input);
         
         Ok(playback_id)
     }
 
+    fn start_playback(&self, playback_id: String, source: String, priority: AudioPriority, input: PlayerInput) {
+        let waker = Arc::new(Notify::new());
+        {
+            let mut state = self.state.lock().unwrap();
+            state.playbacks.insert(playback_id.clone(), Playback { source, priority, waker: waker.clone() });
+            let (_, changes) = state.mixer.start(&playback_id, priority);
+            state.wake(&changes);
+        }
+
+        let state = self.state.clone();
+        let ended = self.ended.clone();
+        let mixer_config = self.config.audio.mixer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = run_playback(&state, &playback_id, priority, &input, &waker, &mixer_config).await {
+                tracing::warn!("Audio playback {} failed: {}", playback_id, e);
+            }
+            state.lock().unwrap().remove(&playback_id);
+            if input.temporary {
+                let _ = tokio::fs::remove_file(&input.file).await;
+            }
+            ended.notify_waiters();
+        });
+    }
+
+    /// Wait until `playback_id` has finished or been stopped
+    pub async fn wait(&self, playback_id: &str) {
+        loop {
+            let ended = self.ended.notified();
+            if !self.state.lock().unwrap().playbacks.contains_key(playback_id) {
+                return;
+            }
+            ended.await;
+        }
+    }
+
+    /// Stop one sound; anything it was ducking or pausing carries on
+    pub fn stop_playback(&self, playback_id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.playbacks.contains_key(playback_id) {
+            return Err(anyhow::anyhow!("No such playback: {}", playback_id));
+        }
+        state.remove(playback_id);
+        Ok(())
+    }
+
+    /// Level change for every sound, and the ambient level it was chosen
+    /// for. Sounds already playing pick it up when the mixer next changes
+    /// their level.
+    pub fn set_output_trim(&self, trim_db: f32, ambient_db: Option<f32>) {
+        let mut state = self.state.lock().unwrap();
+        state.output_trim_db = trim_db;
+        state.ambient_db = ambient_db;
+    }
+
+    pub fn output_trim_db(&self) -> f32 {
+        self.state.lock().unwrap().output_trim_db
+    }
+
+    /// Nothing is playing through the speaker
+    pub fn is_idle(&self) -> bool {
+        self.state.lock().unwrap().playbacks.is_empty()
+    }
+
     pub async fn stop_audio(&self) -> Result<()> {
+        {
+            let mut state = self.state.lock().unwrap();
+            for playback_id in state.mixer.ids() {
+                state.remove(&playback_id);
+            }
+        }
+
         // Use pkill to stop any running audio players
         let _ = Command::new("pkill")
             .arg("aplay")
@@ -123,15 +313,39 @@ impl AudioManager {
         Ok(())
     }
 
+    /// Stop everything on shutdown
+    pub async fn stop_all(&self) -> Result<()> {
+        self.stop_audio().await
+    }
+
     pub async fn get_status(&self) -> Result<AudioStatus> {
-        // Check if any audio is currently playing
-        let is_playing = self.is_audio_playing().await?;
+        let state = self.state.lock().unwrap();
+        let foreground = state.mixer.foreground().map(|id| id.to_string());
+        let active = state.mixer.ids().into_iter()
+            .filter_map(|id| {
+                let playback = state.playbacks.get(&id)?;
+                Some(ActivePlayback {
+                    source: playback.source.clone(),
+                    priority: playback.priority,
+                    state: state.mixer.state(&id)?,
+                    playback_id: id,
+                })
+            })
+            .collect::<Vec<_>>();
         
         Ok(AudioStatus {
-            is_playing,
-            current_source: None, // In a real implementation, track the current source
+            is_playing: !active.is_empty(),
+            current_source: foreground.as_ref()
+                .and_then(|id| state.playbacks.get(id))
+                .map(|playback| playback.source.clone()),
             volume: 1.0, // Default volume
-            playback_id: None,
+            playback_id: foreground,
+            input_level_db: None,
+            input_peak_db: None,
+            input_silent_seconds: None,
+            active,
+            output_trim_db: state.output_trim_db,
+            ambient_db: state.ambient_db,
         })
     }
 
@@ -155,43 +369,26 @@ impl AudioManager {
         Ok(())
     }
 
-    async fn play_custom_file(&self, file_path: &str, volume: Option<f32>, loop_playback: Option<bool>) -> Result<()> {
+    fn custom_file(&self, file_path: &str) -> Result<PathBuf> {
         let path = PathBuf::from(file_path);
         
         if !path.exists() {
             return Err(anyhow::anyhow!("Audio file not found: {}", file_path));
         }
         
-        if let Some(vol) = volume {
-            self.set_volume(vol).await?;
-        }
-        
-        let mut cmd = Command::new("aplay");
-        
-        if loop_playback.unwrap_or(false) {
-            cmd.arg("--repeat");
-        }
-        
-        cmd.arg(path);
-        
-        let status = cmd.status().await?;
-        
-        if !status.success() {
-            return Err(anyhow::anyhow!("Failed to play audio file"));
-        }
-        
-        Ok(())
+        Ok(path)
     }
 
-    async fn play_preset_file(&self, file_id: &str, volume: Option<f32>, loop_playback: Option<bool>) -> Result<()> {
+    fn preset_file(&self, file_id: &str) -> Result<PathBuf> {
         let file_path = self.preset_files.get(file_id)
             .ok_or_else(|| anyhow::anyhow!("Preset file not found: {}", file_id))?;
         
-        self.play_custom_file(file_path.to_string_lossy().as_ref(), volume, loop_playback).await
+        self.custom_file(file_path.to_string_lossy().as_ref())
     }
 
-    async fn play_tts_local(&self, text: &str, voice: Option<&str>, rate: Option<u32>, volume: Option<f32>) -> Result<()> {
-        // Use espeak for local TTS
+    async fn render_tts_local(&self, text: &str, voice: Option<&str>, rate: Option<u32>) -> Result<PathBuf> {
+        // Use espeak for local TTS, rendered to a file so it can be mixed
+        let temp_path = std::env::temp_dir().join(format!("tts_{}.wav", Uuid::new_v4()));
         let mut cmd = Command::new("espeak");
         
         if let Some(voice) = voice {
@@ -202,18 +399,18 @@ impl AudioManager {
             cmd.arg("-s").arg(rate.to_string());
         }
         
-        cmd.arg(text);
+        cmd.arg("-w").arg(&temp_path).arg(text);
         
-        let status = cmd.status().await?;
+        let status = cmd.status().await.context("Failed to run espeak")?;
         
         if !status.success() {
-            return Err(anyhow::anyhow!("Failed to play TTS"));
+            return Err(anyhow::anyhow!("Failed to render TTS"));
         }
         
-        Ok(())
+        Ok(temp_path)
     }
 
-    async fn play_tts_remote(&self, text: &str, provider: TtsProvider, voice: Option<&str>, api_key: Option<&str>, volume: Option<f32>) -> Result<()> {
+    async fn render_tts_remote(&self, text: &str, provider: TtsProvider, voice: Option<&str>, api_key: Option<&str>) -> Result<PathBuf> {
         // Generate TTS audio based on provider
         let audio_data = match provider {
             TtsProvider::Google => self.generate_google_tts(text, voice, api_key).await?,
@@ -222,16 +419,11 @@ impl AudioManager {
             TtsProvider::OpenAI => self.generate_openai_tts(text, voice, api_key).await?,
         };
         
-        // Save to temporary file and play
+        // Save to a temporary file, removed once played
         let temp_path = std::env::temp_dir().join(format!("tts_{}.mp3", Uuid::new_v4()));
         tokio::fs::write(&temp_path, audio_data).await?;
         
-        self.play_custom_file(temp_path.to_string_lossy().as_ref(), volume, None).await?;
-        
-        // Clean up
-        tokio::fs::remove_file(temp_path).await?;
-        
-        Ok(())
+        Ok(temp_path)
     }
 
     async fn generate_google_tts(&self, text: &str, voice: Option<&str>, api_key: Option<&str>) -> Result<Vec<u8>> {
@@ -256,15 +448,53 @@ impl AudioManager {
         // Placeholder for OpenAI TTS
         Err(anyhow::anyhow!("OpenAI TTS implementation pending"))
     }
+}
 
-    async fn is_audio_playing(&self) -> Result<bool> {
-        // Check if any audio players are running
-        let output = Command::new("pgrep")
-            .arg("-f")
-            .arg("aplay|mpg123|ffplay")
-            .output()
-            .await?;
-            
-        Ok(!output.stdout.is_empty())
+/// Play `input` as the mixer directs until it ends or is stopped. A change
+/// of state restarts the player at a new level from where it got to; while
+/// paused, nothing plays.
+async fn run_playback(
+    state: &Mutex<MixerState>,
+    playback_id: &str,
+    priority: AudioPriority,
+    input: &PlayerInput,
+    waker: &Notify,
+    mixer: &AudioMixerConfig,
+) -> Result<()> {
+    let mut position = Duration::ZERO;
+    loop {
+        let (track_state, trim_db) = {
+            let state = state.lock().unwrap();
+            (state.mixer.state(playback_id), state.output_trim_db)
+        };
+        let Some(track_state) = track_state else {
+            // Stopped
+            return Ok(());
+        };
+        if track_state == TrackState::Paused {
+            waker.notified().await;
+            continue;
+        }
+
+        let gain_db = input.gain_db + mixer.gain_db(priority, track_state) + trim_db;
+        let mut child = input.command(position, gain_db).spawn()
+            .context("Failed to start audio player")?;
+        let started = Instant::now();
+        tokio::select! {
+            status = child.wait() => {
+                let status = status?;
+                if !status.success() {
+                    return Err(anyhow::anyhow!("Audio player exited with {}", status));
+                }
+                return Ok(());
+            }
+            _ = waker.notified() => {
+                let _ = child.kill().await;
+                // A looping sound starts over rather than seeking past its end
+                if !input.looping {
+                    position += started.elapsed();
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
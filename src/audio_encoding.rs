@@ -0,0 +1,218 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities::AudioInputCapability;
+use crate::config::AudioConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+}
+
+const AAC_SAMPLE_RATES: &[u32] = &[8000, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 64000, 88200, 96000];
+const OPUS_SAMPLE_RATES: &[u32] = &[8000, 12000, 16000, 24000, 48000];
+
+impl AudioCodec {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "opus",
+        }
+    }
+
+    pub fn ffmpeg_encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "libopus",
+        }
+    }
+
+    /// Extension for audio-only files
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "m4a",
+            AudioCodec::Opus => "ogg",
+        }
+    }
+
+    pub fn supported_sample_rates(&self) -> &'static [u32] {
+        match self {
+            AudioCodec::Aac => AAC_SAMPLE_RATES,
+            AudioCodec::Opus => OPUS_SAMPLE_RATES,
+        }
+    }
+
+    /// Usable bitrate range in bits per second
+    pub fn bitrate_range(&self) -> (u32, u32) {
+        match self {
+            AudioCodec::Aac => (8_000, 320_000),
+            AudioCodec::Opus => (6_000, 510_000),
+        }
+    }
+}
+
+impl std::str::FromStr for AudioCodec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "aac" => Ok(AudioCodec::Aac),
+            "opus" => Ok(AudioCodec::Opus),
+            _ => Err(anyhow::anyhow!("Unsupported audio codec: {}", s)),
+        }
+    }
+}
+
+/// Encoder settings for one audio output (recording or a live stream)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioEncodingConfig {
+    pub codec: AudioCodec,
+    pub sample_rate: u32,
+    pub bitrate: u32,
+    pub channels: u8,
+}
+
+impl From<&AudioConfig> for AudioEncodingConfig {
+    fn from(audio: &AudioConfig) -> Self {
+        Self {
+            codec: audio.codec,
+            sample_rate: audio.sample_rate,
+            bitrate: audio.bitrate,
+            channels: audio.channels,
+        }
+    }
+}
+
+impl AudioEncodingConfig {
+    /// Check the settings are valid for the codec and, when known, supported
+    /// by the capture device
+    pub fn validate(&self, device: Option<&AudioInputCapability>) -> Result<()> {
+        if !self.codec.supported_sample_rates().contains(&self.sample_rate) {
+            return Err(anyhow::anyhow!(
+                "{} does not support a sample rate of {} Hz (supported: {:?})",
+                self.codec.name(), self.sample_rate, self.codec.supported_sample_rates()
+            ));
+        }
+
+        let (min_bitrate, max_bitrate) = self.codec.bitrate_range();
+        if self.bitrate < min_bitrate || self.bitrate > max_bitrate {
+            return Err(anyhow::anyhow!(
+                "{} bitrate must be between {} and {} bps, got {}",
+                self.codec.name(), min_bitrate, max_bitrate, self.bitrate
+            ));
+        }
+
+        if self.channels == 0 || self.channels > 2 {
+            return Err(anyhow::anyhow!("Audio must be mono or stereo, got {} channels", self.channels));
+        }
+
+        if let Some(device) = device {
+            if !device.sample_rates.is_empty() && !device.sample_rates.contains(&self.sample_rate) {
+                return Err(anyhow::anyhow!(
+                    "Audio device {} does not support {} Hz (supported: {:?})",
+                    device.default_device, self.sample_rate, device.sample_rates
+                ));
+            }
+
+            if !device.channels.is_empty() && !device.channels.iter().any(|c| *c >= self.channels) {
+                return Err(anyhow::anyhow!(
+                    "Audio device {} cannot capture {} channels", device.default_device, self.channels
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        vec![
+            "-c:a".to_string(),
+            self.codec.ffmpeg_encoder().to_string(),
+            "-b:a".to_string(),
+            self.bitrate.to_string(),
+            "-ar".to_string(),
+            self.sample_rate.to_string(),
+            "-ac".to_string(),
+            self.channels.to_string(),
+        ]
+    }
+}
+
+/// Query the default (or named) input device through cpal for the sample
+/// rates and channel counts it can capture
+pub fn query_input_capability(device_name: Option<&str>) -> Option<AudioInputCapability> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => host.input_devices().ok()?.find(|d| d.name().map(|n| n == name).unwrap_or(false))?,
+        None => host.default_input_device()?,
+    };
+
+    let mut sample_rates = Vec::new();
+    let mut channels = Vec::new();
+
+    for range in device.supported_input_configs().ok()? {
+        for rate in AAC_SAMPLE_RATES {
+            if (range.min_sample_rate().0..=range.max_sample_rate().0).contains(rate) && !sample_rates.contains(rate) {
+                sample_rates.push(*rate);
+            }
+        }
+        let count = range.channels().min(u8::MAX as u16) as u8;
+        if !channels.contains(&count) {
+            channels.push(count);
+        }
+    }
+
+    sample_rates.sort_unstable();
+    channels.sort_unstable();
+
+    Some(AudioInputCapability {
+        enabled: true,
+        default_device: device.name().unwrap_or_default(),
+        sample_rates,
+        bit_depths: Vec::new(),
+        channels,
+        formats: vec![AudioCodec::Aac.name().to_string(), AudioCodec::Opus.name().to_string()],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opus() -> AudioEncodingConfig {
+        AudioEncodingConfig {
+            codec: AudioCodec::Opus,
+            sample_rate: 48000,
+            bitrate: 24_000,
+            channels: 1,
+        }
+    }
+
+    #[test]
+    fn test_codec_limits() {
+        assert!(opus().validate(None).is_ok());
+        assert!(AudioEncodingConfig { sample_rate: 44100, ..opus() }.validate(None).is_err());
+        assert!(AudioEncodingConfig { bitrate: 1_000_000, ..opus() }.validate(None).is_err());
+        assert!(AudioEncodingConfig { codec: AudioCodec::Aac, sample_rate: 44100, ..opus() }.validate(None).is_ok());
+    }
+
+    #[test]
+    fn test_device_capability() {
+        let device = AudioInputCapability {
+            enabled: true,
+            default_device: "hw:0,0".to_string(),
+            sample_rates: vec![16000, 44100],
+            bit_depths: vec![16],
+            channels: vec![1],
+            formats: Vec::new(),
+        };
+
+        assert!(opus().validate(Some(&device)).is_err());
+        assert!(AudioEncodingConfig { sample_rate: 16000, ..opus() }.validate(Some(&device)).is_ok());
+        assert!(AudioEncodingConfig { sample_rate: 16000, channels: 2, ..opus() }.validate(Some(&device)).is_err());
+    }
+}
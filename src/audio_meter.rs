@@ -0,0 +1,234 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Floor used for silence and for mapping levels onto the UI meter
+pub const MIN_LEVEL_DB: f32 = -90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AudioLevel {
+    pub rms_db: f32,
+    pub peak_db: f32,
+}
+
+impl Default for AudioLevel {
+    fn default() -> Self {
+        Self {
+            rms_db: MIN_LEVEL_DB,
+            peak_db: MIN_LEVEL_DB,
+        }
+    }
+}
+
+impl AudioLevel {
+    /// Level on a 0.0-1.0 scale for meters, linear in dB over the bottom 60 dB
+    pub fn normalized(&self) -> f32 {
+        ((self.rms_db + 60.0) / 60.0).clamp(0.0, 1.0)
+    }
+}
+
+/// RMS and peak level of a block of samples in dBFS
+pub fn compute_level(samples: &[f32]) -> AudioLevel {
+    if samples.is_empty() {
+        return AudioLevel::default();
+    }
+
+    let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
+    let rms = (sum_squares / samples.len() as f32).sqrt();
+    let peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+
+    AudioLevel {
+        rms_db: to_db(rms),
+        peak_db: to_db(peak),
+    }
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        MIN_LEVEL_DB
+    } else {
+        (20.0 * amplitude.log10()).max(MIN_LEVEL_DB)
+    }
+}
+
+#[derive(Debug)]
+struct MeterState {
+    level: AudioLevel,
+    silent_since: Option<Instant>,
+    last_update: Option<Instant>,
+}
+
+/// Cheap, cloneable view of the live input level
+#[derive(Debug, Clone)]
+pub struct AudioLevelHandle {
+    state: Arc<Mutex<MeterState>>,
+    silence_threshold_db: f32,
+}
+
+impl AudioLevelHandle {
+    fn new(silence_threshold_db: f32) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MeterState {
+                level: AudioLevel::default(),
+                silent_since: None,
+                last_update: None,
+            })),
+            silence_threshold_db,
+        }
+    }
+
+    fn update(&self, level: AudioLevel, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.level = level;
+        state.last_update = Some(now);
+        if level.peak_db < self.silence_threshold_db {
+            state.silent_since.get_or_insert(now);
+        } else {
+            state.silent_since = None;
+        }
+    }
+
+    pub fn level(&self) -> AudioLevel {
+        self.state.lock().unwrap().level
+    }
+
+    /// How long the input has been below the silence threshold. A meter that
+    /// has stopped receiving samples counts as silent too.
+    pub fn silent_for(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        let stalled = state.last_update
+            .map(|t| t.elapsed() > Duration::from_secs(5))
+            .unwrap_or(false);
+
+        match (state.silent_since, state.last_update) {
+            (Some(since), _) => Some(since.elapsed()),
+            (None, Some(last)) if stalled => Some(last.elapsed()),
+            _ => None,
+        }
+    }
+}
+
+/// Continuously measures microphone input level. cpal streams aren't Send,
+/// so capture runs on its own thread and publishes into a shared handle.
+pub struct AudioLevelMeter {
+    handle: AudioLevelHandle,
+}
+
+impl AudioLevelMeter {
+    pub fn start(device_name: Option<String>, silence_threshold_db: f32, simulation: bool) -> Result<Self> {
+        let handle = AudioLevelHandle::new(silence_threshold_db);
+
+        if simulation {
+            Self::spawn_simulated(handle.clone());
+        } else {
+            Self::spawn_capture(handle.clone(), device_name)?;
+        }
+
+        Ok(Self { handle })
+    }
+
+    pub fn handle(&self) -> AudioLevelHandle {
+        self.handle.clone()
+    }
+
+    pub fn level(&self) -> AudioLevel {
+        self.handle.level()
+    }
+
+    pub fn silent_for(&self) -> Option<Duration> {
+        self.handle.silent_for()
+    }
+
+    fn spawn_capture(handle: AudioLevelHandle, device_name: Option<String>) -> Result<()> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<Result<()>>();
+
+        std::thread::Builder::new()
+            .name("audio-meter".to_string())
+            .spawn(move || {
+                let host = cpal::default_host();
+                let device = match &device_name {
+                    Some(name) => host.input_devices().ok()
+                        .and_then(|mut devices| devices.find(|d| d.name().map(|n| &n == name).unwrap_or(false))),
+                    None => host.default_input_device(),
+                };
+
+                let stream = device
+                    .ok_or_else(|| anyhow::anyhow!("No audio input device available for metering"))
+                    .and_then(|device| {
+                        let config = device.default_input_config()?;
+                        let meter = handle.clone();
+                        let stream = device.build_input_stream(
+                            &config.into(),
+                            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                                meter.update(compute_level(data), Instant::now());
+                            },
+                            |e| tracing::warn!("Audio meter stream error: {}", e),
+                            None,
+                        )?;
+                        stream.play()?;
+                        Ok(stream)
+                    });
+
+                match stream {
+                    Ok(_stream) => {
+                        let _ = ready_tx.send(Ok(()));
+                        // Keep the stream alive for the life of the process
+                        loop {
+                            std::thread::park();
+                        }
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                    }
+                }
+            })?;
+
+        ready_rx.recv()
+            .map_err(|_| anyhow::anyhow!("Audio meter thread exited"))?
+    }
+
+    fn spawn_simulated(handle: AudioLevelHandle) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(100));
+            loop {
+                interval.tick().await;
+                let rms_db = -35.0 + rand::random::<f32>() * 20.0;
+                handle.update(AudioLevel { rms_db, peak_db: rms_db + 6.0 }, Instant::now());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_level() {
+        let full_scale = compute_level(&[1.0, -1.0, 1.0, -1.0]);
+        assert!(full_scale.rms_db.abs() < 0.01);
+        assert!(full_scale.peak_db.abs() < 0.01);
+
+        let half = compute_level(&[0.5, -0.5]);
+        assert!((half.rms_db + 6.02).abs() < 0.01);
+
+        assert_eq!(compute_level(&[0.0; 16]).rms_db, MIN_LEVEL_DB);
+        assert_eq!(compute_level(&[]), AudioLevel::default());
+    }
+
+    #[test]
+    fn test_silence_tracking() {
+        let handle = AudioLevelHandle::new(-60.0);
+        let now = Instant::now();
+
+        handle.update(AudioLevel { rms_db: -75.0, peak_db: -70.0 }, now);
+        assert!(handle.silent_for().is_some());
+
+        handle.update(AudioLevel { rms_db: -30.0, peak_db: -20.0 }, now);
+        assert!(handle.silent_for().is_none());
+        assert!((handle.level().normalized() - 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,192 @@
+//! Playback arbitration for the speaker. Any number of sounds may be
+//! active. The highest-priority one plays at full level. Lower-priority
+//! sounds are ducked under it, or paused while a sound at or above the
+//! pre-emption priority plays, and pick up where they left off afterwards.
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioPriority;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioMixerConfig {
+    /// Level change for sounds playing under a higher-priority one
+    pub duck_db: f32,
+    /// Sounds at or above this priority pause everything below them
+    /// instead of ducking it
+    pub preempt_priority: AudioPriority,
+    pub priority_offsets_db: PriorityOffsets,
+}
+
+impl Default for AudioMixerConfig {
+    fn default() -> Self {
+        Self {
+            duck_db: -15.0,
+            preempt_priority: AudioPriority::Critical,
+            priority_offsets_db: PriorityOffsets::default(),
+        }
+    }
+}
+
+/// Level offset applied to every sound of each priority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityOffsets {
+    pub low: f32,
+    pub normal: f32,
+    pub high: f32,
+    pub critical: f32,
+}
+
+impl Default for PriorityOffsets {
+    fn default() -> Self {
+        Self {
+            low: -6.0,
+            normal: 0.0,
+            high: 3.0,
+            critical: 6.0,
+        }
+    }
+}
+
+impl AudioMixerConfig {
+    /// Gain for a sound of `priority` in `state`, before any per-request volume
+    pub fn gain_db(&self, priority: AudioPriority, state: TrackState) -> f32 {
+        let offsets = &self.priority_offsets_db;
+        let offset = match priority {
+            AudioPriority::Low => offsets.low,
+            AudioPriority::Normal => offsets.normal,
+            AudioPriority::High => offsets.high,
+            AudioPriority::Critical => offsets.critical,
+        };
+        match state {
+            TrackState::Ducked => offset + self.duck_db,
+            _ => offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackState {
+    Playing,
+    Ducked,
+    Paused,
+}
+
+#[derive(Debug, Clone)]
+struct Track {
+    id: String,
+    priority: AudioPriority,
+    state: TrackState,
+}
+
+/// The active sounds and the state each should be in
+#[derive(Debug)]
+pub struct Mixer {
+    preempt_priority: AudioPriority,
+    tracks: Vec<Track>,
+}
+
+impl Mixer {
+    pub fn new(config: &AudioMixerConfig) -> Self {
+        Self {
+            preempt_priority: config.preempt_priority,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Add a sound. Returns its starting state and the other sounds whose
+    /// state changed because of it.
+    pub fn start(&mut self, id: &str, priority: AudioPriority) -> (TrackState, Vec<(String, TrackState)>) {
+        self.tracks.push(Track { id: id.to_string(), priority, state: TrackState::Playing });
+        let mut changes = self.settle();
+        let state = self.state(id).unwrap_or(TrackState::Playing);
+        changes.retain(|(changed, _)| changed != id);
+        (state, changes)
+    }
+
+    /// Remove a finished or stopped sound. Returns the sounds whose state
+    /// changed, e.g. those it had paused.
+    pub fn finish(&mut self, id: &str) -> Vec<(String, TrackState)> {
+        self.tracks.retain(|track| track.id != id);
+        self.settle()
+    }
+
+    pub fn state(&self, id: &str) -> Option<TrackState> {
+        self.tracks.iter().find(|track| track.id == id).map(|track| track.state)
+    }
+
+    /// The ids of every active sound
+    pub fn ids(&self) -> Vec<String> {
+        self.tracks.iter().map(|track| track.id.clone()).collect()
+    }
+
+    /// The sound heard at full level: the newest of the highest priority
+    pub fn foreground(&self) -> Option<&str> {
+        self.tracks.iter()
+            .max_by_key(|track| track.priority)
+            .map(|track| track.id.as_str())
+    }
+
+    fn settle(&mut self) -> Vec<(String, TrackState)> {
+        let Some(top) = self.tracks.iter().map(|track| track.priority).max() else {
+            return Vec::new();
+        };
+        let preempting = top >= self.preempt_priority;
+
+        let mut changes = Vec::new();
+        for track in &mut self.tracks {
+            let state = if track.priority == top {
+                TrackState::Playing
+            } else if preempting {
+                TrackState::Paused
+            } else {
+                TrackState::Ducked
+            };
+            if track.state != state {
+                track.state = state;
+                changes.push((track.id.clone(), state));
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_critical_preempts_and_lower_priorities_duck() {
+        let config = AudioMixerConfig::default();
+        let mut mixer = Mixer::new(&config);
+
+        assert_eq!(mixer.start("music", AudioPriority::Low), (TrackState::Playing, vec![]));
+        let (state, changes) = mixer.start("prompt", AudioPriority::Normal);
+        assert_eq!(state, TrackState::Playing);
+        assert_eq!(changes, vec![("music".to_string(), TrackState::Ducked)]);
+
+        // An emergency prompt pauses everything below it
+        let (state, changes) = mixer.start("emergency", AudioPriority::Critical);
+        assert_eq!(state, TrackState::Playing);
+        assert_eq!(changes, vec![
+            ("music".to_string(), TrackState::Paused),
+            ("prompt".to_string(), TrackState::Paused),
+        ]);
+        assert_eq!(mixer.foreground(), Some("emergency"));
+
+        // A sound arriving under it waits its turn
+        assert_eq!(mixer.start("tone", AudioPriority::High).0, TrackState::Paused);
+
+        let changes = mixer.finish("emergency");
+        assert_eq!(changes, vec![
+            ("music".to_string(), TrackState::Ducked),
+            ("prompt".to_string(), TrackState::Ducked),
+            ("tone".to_string(), TrackState::Playing),
+        ]);
+        assert_eq!(mixer.finish("tone"), vec![("prompt".to_string(), TrackState::Playing)]);
+        assert_eq!(mixer.finish("prompt"), vec![("music".to_string(), TrackState::Playing)]);
+
+        assert_eq!(config.gain_db(AudioPriority::Low, TrackState::Ducked), -21.0);
+        assert_eq!(config.gain_db(AudioPriority::Critical, TrackState::Playing), 6.0);
+    }
+}
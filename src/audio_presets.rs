@@ -0,0 +1,244 @@
+//! Alert sounds managed on the platform: warnings, announcements and the
+//! like, in each language the fleet uses. The library is downloaded to the
+//! device and checked against the platform's checksums. It plays from the
+//! local copy, so alerts still work offline.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Presets in this language are played when none exists in the device's
+const FALLBACK_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioPresetConfig {
+    /// How often the library is checked for changes
+    pub sync_interval_minutes: u64,
+}
+
+impl Default for AudioPresetConfig {
+    fn default() -> Self {
+        Self {
+            sync_interval_minutes: 360,
+        }
+    }
+}
+
+/// One sound in the platform's library. `id` is what `AudioSource::PresetFile`
+/// refers to; the same id exists once per language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioPreset {
+    pub id: String,
+    pub name: String,
+    /// e.g. `warning` or `announcement`
+    pub category: String,
+    pub language: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub url: String,
+}
+
+impl AudioPreset {
+    fn file_name(&self) -> String {
+        let extension = Path::new(self.url.split(['?', '#']).next().unwrap_or(""))
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .filter(|extension| extension.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or("wav");
+        let safe = |value: &str| value.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_', "_");
+        format!("{}.{}.{}", safe(&self.id), safe(&self.language), extension)
+    }
+
+    fn same_sound(&self, other: &AudioPreset) -> bool {
+        self.id == other.id && self.language == other.language
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    synced_at: Option<DateTime<Utc>>,
+    presets: Vec<AudioPreset>,
+}
+
+/// What a sync must change to match the platform's library
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// New or changed presets, and local copies that fail their checksum
+    pub download: Vec<AudioPreset>,
+    /// Presets the platform no longer has
+    pub remove: Vec<AudioPreset>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PresetSyncReport {
+    pub downloaded: Vec<String>,
+    pub removed: Vec<String>,
+    /// Presets that failed to download or verify, with the reason
+    pub failed: Vec<(String, String)>,
+    pub presets: usize,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// The presets cached on the device, with a manifest of what each file is
+pub struct AudioPresetLibrary {
+    config: AudioPresetConfig,
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl AudioPresetLibrary {
+    pub fn default_dir() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("audio_presets")
+    }
+
+    pub async fn load(config: AudioPresetConfig) -> Self {
+        Self::load_from(Self::default_dir(), config).await
+    }
+
+    pub async fn load_from(dir: PathBuf, config: AudioPresetConfig) -> Self {
+        let manifest = tokio::fs::read_to_string(dir.join("manifest.json")).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { config, dir, manifest }
+    }
+
+    async fn save(&self) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.dir.join("manifest.json"), serde_json::to_string_pretty(&self.manifest)?).await?;
+        Ok(())
+    }
+
+    pub fn sync_due(&self, now: DateTime<Utc>) -> bool {
+        self.manifest.synced_at
+            .map_or(true, |synced_at| now - synced_at >= Duration::minutes(self.config.sync_interval_minutes as i64))
+    }
+
+    pub fn synced_at(&self) -> Option<DateTime<Utc>> {
+        self.manifest.synced_at
+    }
+
+    pub fn list(&self) -> &[AudioPreset] {
+        &self.manifest.presets
+    }
+
+    /// Compare the local copies with `remote`, rehashing each one so a
+    /// damaged file is downloaded again
+    pub async fn plan(&self, remote: &[AudioPreset]) -> SyncPlan {
+        let mut plan = SyncPlan::default();
+        for preset in remote {
+            let cached = self.manifest.presets.iter()
+                .find(|local| local.same_sound(preset) && local.sha256.eq_ignore_ascii_case(&preset.sha256));
+            let intact = match cached {
+                Some(local) => tokio::fs::read(self.dir.join(local.file_name())).await
+                    .is_ok_and(|bytes| sha256_hex(&bytes).eq_ignore_ascii_case(&local.sha256)),
+                None => false,
+            };
+            if !intact {
+                plan.download.push(preset.clone());
+            }
+        }
+        plan.remove = self.manifest.presets.iter()
+            .filter(|local| !remote.iter().any(|preset| preset.same_sound(local)))
+            .cloned()
+            .collect();
+        plan
+    }
+
+    /// Store a downloaded preset once it matches its checksum
+    pub async fn install(&mut self, preset: &AudioPreset, bytes: &[u8]) -> Result<()> {
+        let actual = sha256_hex(bytes);
+        if !actual.eq_ignore_ascii_case(&preset.sha256) {
+            return Err(anyhow::anyhow!("Checksum mismatch for preset {}: expected {}, got {}", preset.id, preset.sha256, actual));
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(preset.file_name());
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, bytes).await?;
+        tokio::fs::rename(&partial, &path).await
+            .with_context(|| format!("Failed to store preset {}", preset.id))?;
+
+        self.manifest.presets.retain(|local| !local.same_sound(preset));
+        self.manifest.presets.push(preset.clone());
+        self.save().await
+    }
+
+    pub async fn remove(&mut self, preset: &AudioPreset) -> Result<()> {
+        let _ = tokio::fs::remove_file(self.dir.join(preset.file_name())).await;
+        self.manifest.presets.retain(|local| !local.same_sound(preset));
+        self.save().await
+    }
+
+    /// Record a completed sync, so the next waits a full interval
+    pub async fn synced(&mut self, now: DateTime<Utc>) -> Result<()> {
+        self.manifest.synced_at = Some(now);
+        self.save().await
+    }
+
+    /// The local file for preset `id` in `language`, or else in English,
+    /// or else in any language
+    pub fn resolve(&self, id: &str, language: &str) -> Option<PathBuf> {
+        let matching = || self.manifest.presets.iter().filter(move |preset| preset.id == id);
+        matching().find(|preset| preset.language == language)
+            .or_else(|| matching().find(|preset| preset.language == FALLBACK_LANGUAGE))
+            .or_else(|| matching().next())
+            .map(|preset| self.dir.join(preset.file_name()))
+            .filter(|path| path.exists())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(id: &str, language: &str, bytes: &[u8]) -> AudioPreset {
+        AudioPreset {
+            id: id.to_string(),
+            name: id.to_string(),
+            category: "warning".to_string(),
+            language: language.to_string(),
+            sha256: sha256_hex(bytes),
+            size_bytes: bytes.len() as u64,
+            url: format!("https://example.com/presets/{}-{}.mp3?sig=abc", id, language),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_verifies_and_resolves_by_language() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut library = AudioPresetLibrary::load_from(dir.path().to_path_buf(), AudioPresetConfig::default()).await;
+        let stop_en = preset("stop", "en", b"stop");
+        let stop_es = preset("stop", "es", b"alto");
+        let leave = preset("leave", "en", b"leave the area");
+
+        let plan = library.plan(&[stop_en.clone(), stop_es.clone()]).await;
+        assert_eq!(plan.download.len(), 2);
+        assert!(library.install(&stop_es, b"tampered").await.is_err());
+        library.install(&stop_en, b"stop").await.unwrap();
+        library.install(&stop_es, b"alto").await.unwrap();
+        library.install(&leave, b"leave the area").await.unwrap();
+        assert!(library.resolve("stop", "es").unwrap().ends_with("stop.es.mp3"));
+        assert!(library.resolve("stop", "fr").unwrap().ends_with("stop.en.mp3"));
+
+        // A damaged copy is fetched again; a preset dropped upstream goes
+        tokio::fs::write(dir.path().join("stop.es.mp3"), b"damaged").await.unwrap();
+        let plan = library.plan(&[stop_en.clone(), stop_es.clone()]).await;
+        assert_eq!(plan.download, vec![stop_es]);
+        assert_eq!(plan.remove, vec![leave.clone()]);
+        library.remove(&leave).await.unwrap();
+        assert_eq!(library.resolve("leave", "en"), None);
+
+        library.synced(Utc::now()).await.unwrap();
+        let reloaded = AudioPresetLibrary::load_from(dir.path().to_path_buf(), AudioPresetConfig::default()).await;
+        assert_eq!(reloaded.list().len(), 2);
+        assert!(!reloaded.sync_due(Utc::now()));
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Microphone pre-processing applied between capture and encode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioProcessingConfig {
+    pub noise_suppression: bool,
+    /// RNNoise model file; without one ffmpeg's FFT denoiser is used instead
+    pub rnnoise_model: Option<String>,
+    pub automatic_gain_control: bool,
+    /// High-pass cutoff for wind and road rumble, None to disable
+    pub high_pass_hz: Option<u32>,
+}
+
+impl Default for AudioProcessingConfig {
+    fn default() -> Self {
+        Self {
+            noise_suppression: true,
+            rnnoise_model: None,
+            automatic_gain_control: true,
+            high_pass_hz: Some(100),
+        }
+    }
+}
+
+impl AudioProcessingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.noise_suppression || self.automatic_gain_control || self.high_pass_hz.is_some()
+    }
+
+    /// ffmpeg audio filter chain, or None when no processing is enabled.
+    /// High-pass runs first so rumble doesn't skew the denoiser, and gain
+    /// control runs last so it doesn't amplify the noise floor.
+    pub fn filter_chain(&self) -> Option<String> {
+        let mut filters = Vec::new();
+
+        if let Some(cutoff) = self.high_pass_hz {
+            filters.push(format!("highpass=f={}", cutoff));
+        }
+
+        if self.noise_suppression {
+            filters.push(match &self.rnnoise_model {
+                Some(model) => format!("arnndn=m='{}'", model.replace('\'', "\\'")),
+                None => "afftdn=nf=-25".to_string(),
+            });
+        }
+
+        if self.automatic_gain_control {
+            filters.push("speechnorm=e=12.5:r=0.0001:l=1".to_string());
+        }
+
+        if filters.is_empty() {
+            None
+        } else {
+            Some(filters.join(","))
+        }
+    }
+
+    /// ffmpeg arguments applying the filter chain to the audio stream
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        match self.filter_chain() {
+            Some(chain) => vec!["-af".to_string(), chain],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Input RMS before and after processing, in dBFS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputLevels {
+    pub rms_before_db: f64,
+    pub rms_after_db: f64,
+}
+
+/// Capture a short sample from the microphone and measure RMS level of the
+/// raw and processed signal side by side
+pub async fn measure_input_levels(
+    device: &str,
+    processing: &AudioProcessingConfig,
+    seconds: u32,
+) -> Result<InputLevels> {
+    let chain = processing.filter_chain().unwrap_or_else(|| "anull".to_string());
+    let graph = format!(
+        "[0:a]asplit=2[raw][proc];[raw]astats@raw=measure_perchannel=none[a];[proc]{},astats@processed=measure_perchannel=none[b]",
+        chain
+    );
+
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-f", "alsa", "-i", device])
+        .args(["-t", &seconds.to_string()])
+        .args(["-filter_complex", &graph])
+        .args(["-map", "[a]", "-f", "null", "-"])
+        .args(["-map", "[b]", "-f", "null", "-"])
+        .output()
+        .await
+        .context("Failed to run ffmpeg for input level measurement")?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Ok(InputLevels {
+        rms_before_db: parse_rms_level(&stderr, "astats@raw")
+            .ok_or_else(|| anyhow::anyhow!("No raw RMS level in ffmpeg output"))?,
+        rms_after_db: parse_rms_level(&stderr, "astats@processed")
+            .ok_or_else(|| anyhow::anyhow!("No processed RMS level in ffmpeg output"))?,
+    })
+}
+
+/// Find `RMS level dB:` in the astats summary printed by the named filter
+fn parse_rms_level(output: &str, filter: &str) -> Option<f64> {
+    let prefix = format!("[{} @", filter);
+    output
+        .lines()
+        .filter(|line| line.starts_with(&prefix))
+        .find_map(|line| line.split("RMS level dB:").nth(1))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_chain_order() {
+        let config = AudioProcessingConfig::default();
+        assert_eq!(
+            config.filter_chain().unwrap(),
+            "highpass=f=100,afftdn=nf=-25,speechnorm=e=12.5:r=0.0001:l=1"
+        );
+
+        let rnnoise = AudioProcessingConfig {
+            rnnoise_model: Some("/usr/share/rnnoise/sh.rnnn".to_string()),
+            automatic_gain_control: false,
+            high_pass_hz: None,
+            ..config
+        };
+        assert_eq!(rnnoise.filter_chain().unwrap(), "arnndn=m='/usr/share/rnnoise/sh.rnnn'");
+
+        let off = AudioProcessingConfig {
+            noise_suppression: false,
+            ..rnnoise
+        };
+        assert!(off.ffmpeg_args().is_empty());
+    }
+
+    #[test]
+    fn test_parse_rms_level() {
+        let output = "\
+[astats@raw @ 0x5581] Overall
+[astats@raw @ 0x5581] RMS level dB: -31.402817
+[astats@processed @ 0x5582] Overall
+[astats@processed @ 0x5582] RMS level dB: -24.118220
+";
+        assert_eq!(parse_rms_level(output, "astats@raw"), Some(-31.402817));
+        assert_eq!(parse_rms_level(output, "astats@processed"), Some(-24.11822));
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+use crate::sentry_integration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub device_id: String,
+    pub action: String,
+    pub source: String,
+    pub details: serde_json::Value,
+}
+
+/// Append-only audit trail for security relevant device actions.
+/// Entries are written as JSON lines to `logs/audit_<date>.jsonl`.
+#[derive(Clone)]
+pub struct AuditLog {
+    device_id: String,
+    log_dir: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(device_id: String) -> Result<Self> {
+        let log_dir = std::env::current_dir()?.join("logs");
+        Ok(Self { device_id, log_dir })
+    }
+
+    pub fn set_device_id(&mut self, device_id: String) {
+        self.device_id = device_id;
+    }
+
+    pub async fn record(&self, action: &str, source: &str, details: serde_json::Value) -> Result<AuditEntry> {
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            device_id: self.device_id.clone(),
+            action: action.to_string(),
+            source: source.to_string(),
+            details,
+        };
+
+        fs::create_dir_all(&self.log_dir).await?;
+        let file_path = self.log_dir.join(format!("audit_{}.jsonl", entry.timestamp.format("%Y-%m-%d")));
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)
+            .await?;
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        tracing::info!("Audit: {} (source: {})", action, source);
+        sentry_integration::add_device_breadcrumb(&format!("audit.{}", action), Some(source));
+
+        Ok(entry)
+    }
+
+    pub async fn read_entries(&self, date: DateTime<Utc>) -> Result<Vec<AuditEntry>> {
+        let file_path = self.log_dir.join(format!("audit_{}.jsonl", date.format("%Y-%m-%d")));
+        if !file_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&file_path).await?;
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+
+        Ok(entries)
+    }
+}
@@ -8,6 +8,7 @@ use ed25519_dalek::{SigningKey, VerifyingKey};
 use rand::rngs::OsRng;
 
 use crate::config::Config;
+use crate::hardware_identity::{attestation, HardwareIdentity};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeviceCredentials {
@@ -23,6 +24,8 @@ pub struct ProvisionRequest {
     pub site_id: String,
     pub hardware_info: HardwareInfo,
     pub public_key: String,
+    /// Fingerprint the credentials are bound to
+    pub hardware_fingerprint: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -68,8 +71,14 @@ impl Authenticator {
         }
     }
 
-    pub async fn provision_device(&self, device_name: &str, site_id: &str) -> Result<DeviceCredentials> {
-        let hardware_info = self.get_hardware_info();
+    pub async fn provision_device(
+        &self,
+        device_name: &str,
+        site_id: &str,
+        idempotency_key: &crate::api::IdempotencyKey,
+    ) -> Result<DeviceCredentials> {
+        let identity = HardwareIdentity::read().await;
+        let hardware_info = self.get_hardware_info(&identity);
         let keypair = self.generate_keypair();
         
         let request = ProvisionRequest {
@@ -77,10 +86,12 @@ impl Authenticator {
             site_id: site_id.to_string(),
             hardware_info,
             public_key: keypair.public_key,
+            hardware_fingerprint: identity.fingerprint(),
         };
 
         let response = self.http_client
             .post(format!("{}/api/devices/provision", self.config.server_url))
+            .header(crate::api::IDEMPOTENCY_HEADER, idempotency_key.as_str())
             .json(&request)
             .send()
             .await
@@ -105,18 +116,25 @@ impl Authenticator {
     }
 
     pub async fn authenticate(&self, device_id: &str, device_key: &str) -> Result<AuthToken> {
-        let timestamp = chrono::Utc::now().timestamp();
+        let timestamp = crate::simulation::faults::now().timestamp();
         let nonce = Uuid::new_v4().to_string();
         
         let message = format!("{}:{}:{}", device_id, timestamp, nonce);
         let signature = self.sign_message(&message, device_key);
 
-        let auth_request = serde_json::json!({
+        let mut auth_request = serde_json::json!({
             "device_id": device_id,
             "timestamp": timestamp,
             "nonce": nonce,
             "signature": signature,
         });
+        // Lets the platform refuse credentials copied to other hardware
+        if let Some(fingerprint) = HardwareIdentity::read().await.fingerprint() {
+            auth_request["hardware_fingerprint"] = serde_json::json!(fingerprint);
+            auth_request["hardware_attestation"] = serde_json::json!(
+                attestation(device_key, device_id, timestamp, &nonce, &fingerprint)
+            );
+        }
 
         let response = self.http_client
             .post(format!("{}/api/devices/auth", self.config.server_url))
@@ -146,7 +164,7 @@ impl Authenticator {
         general_purpose::STANDARD.encode(result.into_bytes())
     }
 
-    fn get_hardware_info(&self) -> HardwareInfo {
+    fn get_hardware_info(&self, identity: &HardwareIdentity) -> HardwareInfo {
         let capabilities = vec![
             "video_recording".to_string(),
             "audio_recording".to_string(),
@@ -157,7 +175,9 @@ impl Authenticator {
 
         HardwareInfo {
             model: "PatrolSight BodyCam Pro".to_string(),
-            serial_number: Uuid::new_v4().to_string(),
+            serial_number: identity.serial_number()
+                .map(str::to_string)
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
             firmware_version: env!("CARGO_PKG_VERSION").to_string(),
             capabilities,
         }
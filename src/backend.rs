@@ -0,0 +1,550 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::access::Operator;
+use crate::api::{ApiClient, DeviceMetrics, IdempotencyKey};
+use crate::auth::Authenticator;
+use crate::certificates::{CertificateRequest, IssuedCertificate};
+use crate::config::Config;
+use crate::error_handling::DeviceError;
+use crate::convex_api::{DeviceCredentials, DeviceSettings, VideoCreateRequest, VideoMetadata};
+use crate::convex_auth::ConvexAuthenticator;
+use crate::convex_tenant::TenantManager;
+use crate::custody::RecordingAccess;
+use crate::device::DeviceStatus;
+use crate::diagnostics::ComprehensiveDiagnostics;
+use crate::event_bus::EventBus;
+use crate::incident::{IncidentCreateRequest, IncidentManager};
+use crate::integrity::UploadChecksums;
+use crate::media::RecordingSegment;
+use crate::location::{AccessPoint, WifiPosition};
+use crate::pairing::{PairingRequest, PairingStatus};
+use crate::realtime::CommandResponse;
+use crate::sites::SiteProfile;
+use crate::status_report::StatusDelta;
+use crate::storage_manager::{record_confirmed_upload, DeletedFileRecord};
+use crate::video_upload::VideoUploader;
+
+/// Which platform backend the device talks to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Convex when `convex_url` is set, the REST API otherwise
+    #[default]
+    Auto,
+    Rest,
+    Convex,
+}
+
+impl BackendKind {
+    /// The concrete backend this setting selects for `config`
+    pub fn resolve(self, config: &Config) -> Self {
+        match self {
+            BackendKind::Auto if config.convex_url.is_some() => BackendKind::Convex,
+            BackendKind::Auto => BackendKind::Rest,
+            kind => kind,
+        }
+    }
+}
+
+/// Everything the device needs from the platform. New backends implement
+/// this and are picked up by `create_backend` without touching `BodycamDevice`.
+#[async_trait]
+pub trait PlatformBackend: Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    /// Provision this device and return the credentials to store
+    async fn register(&mut self, device_name: &str, site_id: &str) -> Result<DeviceCredentials>;
+
+    /// Ask for this device to be added to a site; an administrator approves
+    /// the request from the console
+    async fn request_pairing(&self, device_name: &str) -> Result<PairingRequest>;
+
+    /// Whether a pairing request has been approved yet
+    async fn pairing_status(&self, code: &str) -> Result<PairingStatus>;
+
+    /// Position of the device from nearby Wi-Fi access points, or None if
+    /// the backend doesn't know them
+    async fn locate_wifi(&self, access_points: &[AccessPoint]) -> Result<Option<WifiPosition>>;
+
+    /// Check that `site`'s credentials are still valid before the device
+    /// switches to it, returning them refreshed where the backend issues tokens
+    async fn validate_site(&self, site: &SiteProfile) -> Result<SiteProfile>;
+
+    async fn report_status(&self, status: &DeviceStatus) -> Result<()>;
+
+    /// Whether `report_status_delta` is supported
+    fn supports_status_deltas(&self) -> bool {
+        false
+    }
+
+    /// Report only the status fields that changed since an acknowledged report
+    async fn report_status_delta(&self, _delta: &StatusDelta) -> Result<()> {
+        Err(anyhow::anyhow!("The {:?} backend doesn't accept status deltas", self.kind()))
+    }
+
+    /// Report statuses recorded while offline, oldest first
+    async fn report_status_batch(&self, statuses: &[DeviceStatus]) -> Result<()> {
+        for status in statuses {
+            self.report_status(status).await?;
+        }
+        Ok(())
+    }
+
+    /// Upload a finished segment, returning the checksums the server
+    /// verified. Progress is published on `events` where given.
+    async fn upload_segment(&self, segment: &RecordingSegment, events: Option<&EventBus>) -> Result<UploadChecksums>;
+
+    /// Finish uploads interrupted by a restart or lost connectivity,
+    /// returning how many completed
+    async fn resume_uploads(&self, _events: &EventBus) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Create an incident, returning the id the backend knows it by. The
+    /// backend drops repeats carrying the same `idempotency_key`.
+    async fn create_incident(
+        &self,
+        incident_id: &str,
+        incident: &IncidentCreateRequest,
+        idempotency_key: &IdempotencyKey,
+    ) -> Result<String>;
+
+    async fn get_settings(&self, device_id: &str) -> Result<DeviceSettings>;
+
+    async fn send_metrics(&self, metrics: &DeviceMetrics) -> Result<()>;
+
+    /// Upload a comprehensive diagnostics run, e.g. one support asked for
+    async fn report_diagnostics(&self, diagnostics: &ComprehensiveDiagnostics) -> Result<()>;
+
+    /// Tell the platform which local recordings were deleted and why
+    async fn report_deletions(&self, records: &[DeletedFileRecord]) -> Result<()>;
+
+    /// Tell the platform a recording was decrypted, exported or played on the device
+    async fn report_recording_access(&self, access: &RecordingAccess) -> Result<()>;
+
+    /// Report the outcome of a server command
+    async fn send_command_response(&self, response: &CommandResponse) -> Result<()>;
+
+    /// Revoke this device's credentials, e.g. when it is decommissioned
+    async fn revoke_credentials(&self, device_id: &str) -> Result<()>;
+
+    /// The operator an on-device login token was issued to
+    async fn verify_operator_token(&self, token: &str) -> Result<Operator>;
+
+    /// Have the platform sign a certificate request for this device
+    async fn enroll_certificate(&self, request: &CertificateRequest) -> Result<IssuedCertificate>;
+}
+
+pub fn create_backend(config: &Config) -> Result<Box<dyn PlatformBackend>> {
+    match config.backend.resolve(config) {
+        BackendKind::Convex => Ok(Box::new(ConvexBackend::new(config.clone())?)),
+        _ => Ok(Box::new(RestBackend::new(config.clone()))),
+    }
+}
+
+/// The legacy REST API at `server_url`
+pub struct RestBackend {
+    config: Config,
+    auth: Authenticator,
+    api: ApiClient,
+    incidents: IncidentManager,
+    client: reqwest::Client,
+}
+
+impl RestBackend {
+    pub fn new(config: Config) -> Self {
+        let client = crate::http::shared_client(&config.network)
+            .expect("Failed to create HTTP client");
+
+        Self {
+            auth: Authenticator::new(config.clone()),
+            api: ApiClient::new(config.clone()),
+            incidents: IncidentManager::new(config.clone()),
+            client,
+            config,
+        }
+    }
+
+    async fn post_json<T: Serialize + ?Sized>(&self, path: &str, body: &T) -> Result<reqwest::Response> {
+        let url = format!("{}{}", self.config.server_url, path);
+        let mut request = self.client.post(&url).json(body);
+        if let Some(token) = &self.config.auth_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await
+            .with_context(|| format!("Failed to reach {}", url))?;
+        if !response.status().is_success() {
+            return Err(crate::error_handling::ApiError::from_response(path, response).await);
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl PlatformBackend for RestBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Rest
+    }
+
+    async fn register(&mut self, device_name: &str, site_id: &str) -> Result<DeviceCredentials> {
+        // Keyed by the hardware, so a retried registration gets back the
+        // device the first attempt created instead of a second one
+        let device = match self.config.device_serial.clone() {
+            Some(serial) => serial,
+            None => crate::hardware_identity::HardwareIdentity::read().await.fingerprint()
+                .unwrap_or_else(|| format!("{}/{}", site_id, device_name)),
+        };
+        let key = IdempotencyKey::derived("register", &device);
+        let credentials = self.auth.provision_device(device_name, site_id, &key).await?;
+        Ok(DeviceCredentials {
+            device_id: credentials.device_id,
+            device_key: credentials.device_key,
+            site_id: credentials.site_id,
+            tenant_id: credentials.tenant_id,
+            auth_token: "legacy".to_string(), // Placeholder
+        })
+    }
+
+    async fn request_pairing(&self, device_name: &str) -> Result<PairingRequest> {
+        let response = self.post_json("/api/devices/pairing", &serde_json::json!({ "device_name": device_name })).await?;
+        response.json().await.context("Invalid pairing request in response")
+    }
+
+    async fn pairing_status(&self, code: &str) -> Result<PairingStatus> {
+        let response = self.post_json(&format!("/api/devices/pairing/{}/status", code), &serde_json::json!({})).await?;
+        response.json().await.context("Invalid pairing status in response")
+    }
+
+    async fn locate_wifi(&self, access_points: &[AccessPoint]) -> Result<Option<WifiPosition>> {
+        let response = self.post_json("/api/location/wifi", &serde_json::json!({ "access_points": access_points })).await?;
+        response.json().await.context("Invalid Wi-Fi location in response")
+    }
+
+    async fn validate_site(&self, site: &SiteProfile) -> Result<SiteProfile> {
+        let token = self.auth.authenticate(&site.device_id, &site.device_key).await
+            .with_context(|| format!("Site {} rejected this device's credentials", site.site_id))?;
+        Ok(SiteProfile {
+            auth_token: Some(token.token),
+            ..site.clone()
+        })
+    }
+
+    async fn report_status(&self, status: &DeviceStatus) -> Result<()> {
+        if !self.config.is_provisioned() {
+            return Ok(());
+        }
+        // Through ApiClient so a down server trips its circuit breaker
+        self.api.update_device_status(&status.device_id, status).await
+    }
+
+    fn supports_status_deltas(&self) -> bool {
+        true
+    }
+
+    async fn report_status_delta(&self, delta: &StatusDelta) -> Result<()> {
+        if !self.config.is_provisioned() {
+            return Ok(());
+        }
+        self.api.update_device_status_delta(delta).await
+    }
+
+    async fn report_status_batch(&self, statuses: &[DeviceStatus]) -> Result<()> {
+        let Some(first) = statuses.first() else {
+            return Ok(());
+        };
+        if !self.config.is_provisioned() {
+            return Ok(());
+        }
+        self.api.update_device_status_batch(&first.device_id, statuses).await
+    }
+
+    async fn upload_segment(&self, segment: &RecordingSegment, _events: Option<&EventBus>) -> Result<UploadChecksums> {
+        self.api.upload_recording_segment(segment).await
+    }
+
+    async fn create_incident(
+        &self,
+        incident_id: &str,
+        incident: &IncidentCreateRequest,
+        idempotency_key: &IdempotencyKey,
+    ) -> Result<String> {
+        self.incidents.submit(incident, idempotency_key).await?;
+        Ok(incident_id.to_string())
+    }
+
+    async fn get_settings(&self, device_id: &str) -> Result<DeviceSettings> {
+        let url = format!("{}/api/devices/{}/settings", self.config.server_url, device_id);
+        let mut request = self.client.get(&url);
+        if let Some(token) = &self.config.auth_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.context("Failed to get device settings")?;
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Settings fetch failed: {}", error_text));
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn send_metrics(&self, metrics: &DeviceMetrics) -> Result<()> {
+        self.api.send_metrics(metrics).await
+    }
+
+    async fn report_diagnostics(&self, diagnostics: &ComprehensiveDiagnostics) -> Result<()> {
+        self.api.report_diagnostics(diagnostics).await
+    }
+
+    async fn report_deletions(&self, records: &[DeletedFileRecord]) -> Result<()> {
+        let Some(device_id) = records.first().map(|r| r.device_id.clone()) else {
+            return Ok(());
+        };
+        self.post_json(&format!("/api/devices/{}/deletions", device_id), records).await?;
+        Ok(())
+    }
+
+    async fn report_recording_access(&self, access: &RecordingAccess) -> Result<()> {
+        self.post_json(&format!("/api/devices/{}/recording-access", access.device_id), access).await?;
+        Ok(())
+    }
+
+    async fn send_command_response(&self, response: &CommandResponse) -> Result<()> {
+        let path = format!("/api/devices/{}/commands/{}/response", response.device_id, response.request_id);
+        self.post_json(&path, response).await?;
+        Ok(())
+    }
+
+    async fn revoke_credentials(&self, device_id: &str) -> Result<()> {
+        let path = format!("/api/devices/{}/revoke", device_id);
+        self.post_json(&path, &serde_json::json!({ "reason": "decommissioned" })).await?;
+        Ok(())
+    }
+
+    async fn verify_operator_token(&self, token: &str) -> Result<Operator> {
+        let device_id = self.config.device_id.as_deref()
+            .ok_or(DeviceError::NotProvisioned)?;
+        let path = format!("/api/devices/{}/operators/verify", device_id);
+        let response = self.post_json(&path, &serde_json::json!({ "token": token })).await?;
+        response.json().await.context("Invalid operator in response")
+    }
+
+    async fn enroll_certificate(&self, request: &CertificateRequest) -> Result<IssuedCertificate> {
+        let device_id = self.config.device_id.as_deref()
+            .ok_or(DeviceError::NotProvisioned)?;
+        let path = format!("/api/devices/{}/certificates/enroll", device_id);
+        let response = self.post_json(&path, request).await?;
+        response.json().await.context("Invalid certificate in response")
+    }
+}
+
+/// The Convex deployment at `convex_url`
+pub struct ConvexBackend {
+    config: Config,
+    auth: ConvexAuthenticator,
+}
+
+impl ConvexBackend {
+    pub fn new(config: Config) -> Result<Self> {
+        if config.convex_url.is_none() {
+            return Err(anyhow::anyhow!("Convex backend selected but convex_url is not set"));
+        }
+
+        Ok(Self {
+            auth: ConvexAuthenticator::new(config.clone())?,
+            config,
+        })
+    }
+
+    async fn client(&self) -> Result<crate::convex_api::ConvexApiClient> {
+        let convex_url = self.config.convex_url.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Convex URL not configured"))?;
+        crate::convex_api::ConvexApiClient::new(convex_url, self.config.clone()).await
+    }
+}
+
+#[async_trait]
+impl PlatformBackend for ConvexBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Convex
+    }
+
+    async fn register(&mut self, device_name: &str, site_id: &str) -> Result<DeviceCredentials> {
+        self.auth.factory_provision(device_name, site_id).await
+    }
+
+    async fn request_pairing(&self, device_name: &str) -> Result<PairingRequest> {
+        self.client().await?.request_pairing(device_name).await
+    }
+
+    async fn pairing_status(&self, code: &str) -> Result<PairingStatus> {
+        self.client().await?.pairing_status(code).await
+    }
+
+    async fn locate_wifi(&self, access_points: &[AccessPoint]) -> Result<Option<WifiPosition>> {
+        self.client().await?.locate_wifi(access_points).await
+    }
+
+    async fn validate_site(&self, site: &SiteProfile) -> Result<SiteProfile> {
+        let mut config = self.config.clone();
+        site.apply(&mut config);
+        let convex_url = config.convex_url.clone()
+            .ok_or_else(|| anyhow::anyhow!("Convex URL not configured"))?;
+        let client = crate::convex_api::ConvexApiClient::new(&convex_url, config.clone()).await?;
+
+        let tenants = TenantManager::new(Arc::new(RwLock::new(client)), Arc::new(RwLock::new(config)));
+        tenants.validate_and_get_context(&site.tenant_id, &site.device_id, &site.site_id).await?;
+        Ok(site.clone())
+    }
+
+    async fn report_status(&self, status: &DeviceStatus) -> Result<()> {
+        let mut convex_status = crate::convex_api::ConvexDeviceStatus::from(status.clone());
+        if let Some(tenant_id) = &self.config.tenant_id {
+            convex_status.tenant_id = tenant_id.clone();
+        }
+        self.client().await?.record_device_status(&convex_status).await
+    }
+
+    async fn upload_segment(&self, segment: &RecordingSegment, events: Option<&EventBus>) -> Result<UploadChecksums> {
+        let path = Path::new(&segment.file_path);
+        let request = VideoCreateRequest {
+            device_id: segment.device_id.clone(),
+            filename: path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| segment.id.clone()),
+            duration: segment.duration,
+            quality: serde_json::to_value(&segment.quality)?.as_str().unwrap_or_default().to_string(),
+            incident_id: Some(segment.incident_id.clone()),
+            metadata: VideoMetadata {
+                codec: segment.metadata.codec.clone(),
+                bitrate: segment.metadata.bitrate,
+                resolution: segment.metadata.resolution.clone(),
+                is_encrypted: segment.metadata.encryption_key.is_some(),
+                encryption_algorithm: segment.metadata.encryption_key.clone(),
+            },
+        };
+
+        let client = self.client().await?;
+        let mut uploader = VideoUploader::new(&client, &self.config);
+        if let Some(events) = events {
+            uploader = uploader.with_event_bus(events.clone());
+        }
+        let upload = uploader
+            .upload(&segment.file_path, &request)
+            .await
+            .with_context(|| format!("Upload {} failed", segment.id))?;
+        Ok(upload.checksums)
+    }
+
+    async fn resume_uploads(&self, events: &EventBus) -> Result<usize> {
+        let client = self.client().await?;
+        let completed = VideoUploader::new(&client, &self.config)
+            .with_event_bus(events.clone())
+            .resume_pending()
+            .await?;
+
+        for upload in &completed {
+            record_confirmed_upload(Path::new(&upload.file_path), &upload.checksums.sha256, Vec::new()).await?;
+        }
+        Ok(completed.len())
+    }
+
+    async fn create_incident(
+        &self,
+        _incident_id: &str,
+        incident: &IncidentCreateRequest,
+        idempotency_key: &IdempotencyKey,
+    ) -> Result<String> {
+        let request = crate::convex_api::IncidentCreateRequest {
+            device_id: incident.device_id.clone(),
+            incident_type: incident.incident_type,
+            button_type: incident.metadata.get("button_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("single")
+                .to_string(),
+            gps_latitude: incident.location.as_ref().map(|l| l.latitude),
+            gps_longitude: incident.location.as_ref().map(|l| l.longitude),
+            gps_accuracy: incident.location.as_ref().and_then(|l| l.accuracy),
+            location_source: incident.location.as_ref().map(|l| l.source.as_str().to_string()),
+            metadata: serde_json::json!({
+                "severity": incident.severity,
+                "description": incident.description,
+                "details": incident.metadata,
+            }),
+        };
+        self.client().await?.create_incident(&request, idempotency_key).await
+    }
+
+    async fn get_settings(&self, device_id: &str) -> Result<DeviceSettings> {
+        self.client().await?.get_device_settings(device_id).await
+    }
+
+    async fn send_metrics(&self, metrics: &DeviceMetrics) -> Result<()> {
+        self.client().await?.record_device_metrics(metrics).await
+    }
+
+    async fn report_diagnostics(&self, diagnostics: &ComprehensiveDiagnostics) -> Result<()> {
+        self.client().await?.record_diagnostics(diagnostics).await
+    }
+
+    async fn report_deletions(&self, records: &[DeletedFileRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.client().await?.record_deletions(records).await
+    }
+
+    async fn report_recording_access(&self, access: &RecordingAccess) -> Result<()> {
+        self.client().await?.record_recording_access(access).await
+    }
+
+    async fn send_command_response(&self, response: &CommandResponse) -> Result<()> {
+        self.client().await?.acknowledge_command(response).await
+    }
+
+    async fn revoke_credentials(&self, device_id: &str) -> Result<()> {
+        self.client().await?.revoke_device(device_id, "decommissioned").await
+    }
+
+    async fn verify_operator_token(&self, token: &str) -> Result<Operator> {
+        let device_id = self.config.device_id.as_deref()
+            .ok_or(DeviceError::NotProvisioned)?;
+        self.client().await?.verify_operator_token(device_id, token).await
+    }
+
+    async fn enroll_certificate(&self, request: &CertificateRequest) -> Result<IssuedCertificate> {
+        let device_id = self.config.device_id.as_deref()
+            .ok_or(DeviceError::NotProvisioned)?;
+        self.client().await?.enroll_certificate(device_id, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_prefers_convex_when_configured() {
+        let mut config = Config::default();
+        assert_eq!(BackendKind::Auto.resolve(&config), BackendKind::Rest);
+
+        config.convex_url = Some("https://example.convex.cloud".to_string());
+        assert_eq!(BackendKind::Auto.resolve(&config), BackendKind::Convex);
+        assert_eq!(BackendKind::Rest.resolve(&config), BackendKind::Rest);
+    }
+
+    #[test]
+    fn test_convex_backend_requires_url() {
+        let mut config = Config::default();
+        config.backend = BackendKind::Convex;
+        assert!(create_backend(&config).is_err());
+
+        config.backend = BackendKind::Rest;
+        assert_eq!(create_backend(&config).unwrap().kind(), BackendKind::Rest);
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+use tracing::info;
+
+use bodycam_core::{sentry_integration, ui, BodycamDevice, Config};
+
+#[derive(Parser)]
+#[command(name = "bodycam-ui")]
+#[command(about = "Touchscreen UI for the body camera client")]
+struct Cli {
+    #[arg(short, long, default_value = "config.toml")]
+    config: String,
+
+    #[arg(short, long)]
+    verbose: bool,
+
+    #[arg(long)]
+    config_dir: Option<String>,
+
+    /// Run fullscreen and require a supervisor PIN to exit
+    #[arg(long)]
+    kiosk: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let log_level = if cli.verbose { "debug" } else { "info" };
+    tracing_subscriber::fmt()
+        .with_env_filter(log_level)
+        .init();
+
+    let config_dir = cli.config_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    let config_path = config_dir.join(&cli.config);
+
+    let mut config = Config::load(config_path.to_str().unwrap()).await?;
+    config.kiosk.enabled |= cli.kiosk;
+
+    let sentry_config = sentry_integration::SentryConfig::from_config(&config);
+    let _sentry_guard = sentry_integration::init_sentry(&sentry_config)?;
+
+    info!("Starting UI mode");
+    let device = BodycamDevice::new(config.clone()).await?;
+    ui::run_ui(config, device, Some(config_path)).await
+}
@@ -1,13 +1,15 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::capture::VideoSource;
 use crate::config::{Config, VideoQuality};
+use crate::gop_buffer::{BufferStorage, MemoryBuffer};
 use crate::integrity::{IntegrityManager, VideoIntegrity};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +43,22 @@ pub struct LocationData {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Buffer index saved to disk so buffered footage survives a restart
+#[derive(Debug, Serialize, Deserialize)]
+struct BufferCheckpoint {
+    saved_at: DateTime<Utc>,
+    segments: Vec<BufferSegment>,
+}
+
+/// Checkpointed segments still inside the buffer window at `now`
+fn restorable_segments(segments: Vec<BufferSegment>, window_seconds: u64, now: DateTime<Utc>) -> Vec<BufferSegment> {
+    let cutoff = now - chrono::Duration::seconds(window_seconds as i64);
+    segments.into_iter()
+        .filter(|segment| segment.end_time >= cutoff && segment.start_time <= now)
+        .collect()
+}
+
+#[derive(Clone)]
 pub struct CircularBuffer {
     config: Config,
     device_id: String,
@@ -50,11 +68,13 @@ pub struct CircularBuffer {
     active: Arc<Mutex<bool>>,
     cleanup_task: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
     last_cleanup: Arc<Mutex<DateTime<Utc>>>,
+    /// Set while buffering to RAM instead of segment files
+    memory: Arc<Mutex<Option<MemoryBuffer>>>,
 }
 
 impl CircularBuffer {
     pub fn new(config: Config, device_id: String) -> Self {
-        let buffer_duration = config.recording.pre_incident_buffer_seconds;
+        let buffer_duration = config.pre_incident_buffer_seconds();
         Self {
             config,
             device_id,
@@ -64,6 +84,7 @@ impl CircularBuffer {
             active: Arc::new(Mutex::new(false)),
             cleanup_task: Arc::new(Mutex::new(None)),
             last_cleanup: Arc::new(Mutex::new(Utc::now())),
+            memory: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -75,6 +96,10 @@ impl CircularBuffer {
         *active = true;
         drop(active);
 
+        if self.config.recording.pre_incident_buffer.storage == BufferStorage::Memory {
+            return self.start_memory_buffering().await;
+        }
+
         let config = self.config.clone();
         let device_id = self.device_id.clone();
         let segments = self.segments.clone();
@@ -108,8 +133,12 @@ impl CircularBuffer {
         *cleanup_task.lock().await = Some(cleanup_handle);
 
         // Start recording task
+        let checkpoint_interval = chrono::Duration::seconds(
+            config.recording.pre_incident_buffer.checkpoint_interval_seconds as i64
+        );
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+            let mut last_checkpoint = Utc::now();
             
             loop {
                 interval.tick().await;
@@ -127,15 +156,181 @@ impl CircularBuffer {
                 ).await {
                     tracing::error!("Failed to record buffer segment: {}", e);
                 }
+
+                if Utc::now() - last_checkpoint >= checkpoint_interval {
+                    if let Err(e) = Self::write_checkpoint(&segments).await {
+                        tracing::warn!("Failed to checkpoint buffer index: {}", e);
+                    }
+                    last_checkpoint = Utc::now();
+                }
             }
         });
 
         Ok(())
     }
 
+    /// Capture the default quality into a RAM ring instead of writing a
+    /// segment file every few seconds, which wears out the eMMC
+    async fn start_memory_buffering(&self) -> Result<()> {
+        let quality = self.memory_quality()?;
+        let source = VideoSource::for_config(&self.config, &quality.device_path);
+        if source.is_placeholder() {
+            return Ok(());
+        }
+
+        let mut memory = self.memory.lock().await;
+        let buffer = memory.get_or_insert(MemoryBuffer::new(
+            self.config.recording.pre_incident_buffer.clone(),
+            self.buffer_duration,
+        )?);
+        buffer.start(&quality, &source).await
+    }
+
+    fn memory_quality(&self) -> Result<crate::config::VideoQualityConfig> {
+        self.config.recording.available_qualities.iter()
+            .find(|q| q.quality == self.config.recording.default_quality)
+            .or_else(|| self.config.recording.available_qualities.first())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No video quality configured for the pre-incident buffer"))
+    }
+
+    /// Write up to `pre_roll_seconds` of the RAM buffer to `path` ahead of
+    /// an incident recording. The capture is stopped so the recorder can
+    /// open the camera; call `start_buffering` again once recording ends.
+    pub async fn flush_pre_incident(&self, path: &Path, pre_roll_seconds: u64) -> Result<Option<BufferSegment>> {
+        let mut memory = self.memory.lock().await;
+        let Some(buffer) = memory.as_mut() else {
+            return Ok(None);
+        };
+
+        *self.active.lock().await = false;
+        let pre_roll = chrono::Duration::seconds(pre_roll_seconds as i64);
+        let Some(flush) = buffer.flush_to(path, pre_roll).await? else {
+            return Ok(None);
+        };
+
+        let quality_config = self.memory_quality()?;
+        let end_time = Utc::now();
+        let metadata = BufferMetadata {
+            resolution: quality_config.resolution.clone(),
+            fps: quality_config.fps,
+            bitrate: quality_config.bitrate,
+            codec: quality_config.codec.clone(),
+            audio_enabled: false,
+            location: None,
+        };
+        let integrity = IntegrityManager::create_integrity_record(&flush.path, &serde_json::to_value(&metadata)?)
+            .await
+            .ok();
+
+        Ok(Some(BufferSegment {
+            id: Uuid::new_v4().to_string(),
+            start_time: flush.started_at,
+            end_time,
+            duration: (end_time - flush.started_at).num_seconds().max(0) as u64,
+            file_path: flush.path.to_string_lossy().to_string(),
+            file_size: Some(flush.size_bytes),
+            quality: quality_config.quality.clone(),
+            metadata,
+            integrity,
+        }))
+    }
+
+    fn checkpoint_path() -> Result<PathBuf> {
+        Ok(std::env::current_dir()?.join("buffer").join("index.json"))
+    }
+
+    async fn write_checkpoint(segments: &Arc<Mutex<VecDeque<BufferSegment>>>) -> Result<()> {
+        let checkpoint = BufferCheckpoint {
+            saved_at: Utc::now(),
+            segments: segments.lock().await.iter().cloned().collect(),
+        };
+
+        let path = Self::checkpoint_path()?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_string(&checkpoint)?).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    /// Reload segments from the last checkpoint, deleting files that are
+    /// now outside the window. Call before `start_buffering`.
+    pub async fn restore(&self) -> Result<usize> {
+        let path = Self::checkpoint_path()?;
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).context("Failed to read buffer checkpoint"),
+        };
+        let checkpoint: BufferCheckpoint = match serde_json::from_str(&content) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                tracing::warn!("Discarding corrupt buffer checkpoint: {}", e);
+                let _ = tokio::fs::remove_file(&path).await;
+                return Ok(0);
+            }
+        };
+
+        let saved: Vec<String> = checkpoint.segments.iter().map(|s| s.file_path.clone()).collect();
+        let mut restored = Vec::new();
+        for segment in restorable_segments(checkpoint.segments, self.buffer_duration, Utc::now()) {
+            if tokio::fs::try_exists(&segment.file_path).await.unwrap_or(false) {
+                restored.push(segment);
+            }
+        }
+        for file_path in saved.iter().filter(|p| !restored.iter().any(|s| &s.file_path == *p)) {
+            let _ = tokio::fs::remove_file(file_path).await;
+        }
+
+        let count = restored.len();
+        let mut segments = self.segments.lock().await;
+        for segment in restored.into_iter().rev() {
+            segments.push_front(segment);
+        }
+
+        if count > 0 {
+            tracing::info!("Restored {} pre-incident buffer segments from {}", count, checkpoint.saved_at);
+        }
+        Ok(count)
+    }
+
+    /// Stop buffering for a planned restart, keeping what's buffered on
+    /// disk for `restore` instead of deleting it
+    pub async fn suspend(&self) -> Result<()> {
+        *self.active.lock().await = false;
+
+        for (_, mut process) in self.recording_processes.lock().await.drain(..) {
+            let _ = process.kill().await;
+        }
+        if let Some(handle) = self.cleanup_task.lock().await.take() {
+            handle.abort();
+        }
+
+        if let Some(buffer) = self.memory.lock().await.as_mut() {
+            buffer.persist().await?;
+        }
+        Self::write_checkpoint(&self.segments).await
+    }
+
+    /// Newest buffered GOP, for sampling frames without opening the camera
+    pub async fn latest_gop(&self) -> Option<Vec<u8>> {
+        self.memory.lock().await.as_ref()?.latest_gop()
+    }
+
+    pub fn is_memory_backed(&self) -> bool {
+        self.config.recording.pre_incident_buffer.storage == BufferStorage::Memory
+    }
+
     pub async fn stop_buffering(&self) -> Result<()> {
         let mut active = self.active.lock().await;
         *active = false;
+
+        if let Some(buffer) = self.memory.lock().await.as_mut() {
+            buffer.stop().await;
+        }
         
         let mut processes = self.recording_processes.lock().await;
         for (_, mut process) in processes.drain(..) {
@@ -157,7 +352,7 @@ impl CircularBuffer {
         config: Config,
         segments: Arc<Mutex<VecDeque<BufferSegment>>>,
     ) -> Result<()> {
-        let max_age = chrono::Duration::seconds(config.recording.pre_incident_buffer_seconds as i64 * 2);
+        let max_age = chrono::Duration::seconds(config.pre_incident_buffer_seconds() as i64 * 2);
         let cutoff_time = Utc::now() - max_age;
         
         let mut segments_lock = segments.lock().await;
@@ -261,18 +456,20 @@ impl CircularBuffer {
                 file_size: None,
                 quality: quality_config.quality.clone(),
                 metadata,
+                integrity: None,
             };
 
             // Start recording for this quality
-            if !config.simulation.enabled {
-                Self::start_buffer_recording(&quality_config, &file_path, segment_duration).await?;
+            let source = VideoSource::for_config(&config, &quality_config.device_path);
+            if !source.is_placeholder() {
+                Self::start_buffer_recording(&quality_config, &source, &file_path, segment_duration).await?;
             }
 
             let mut segments_lock = segments.lock().await;
             segments_lock.push_back(segment);
             
             // Maintain buffer size
-            let max_segments = (config.recording.pre_incident_buffer_seconds / segment_duration) as usize;
+            let max_segments = (config.pre_incident_buffer_seconds() / segment_duration) as usize;
             while segments_lock.len() > max_segments {
                 if let Some(old_segment) = segments_lock.pop_front() {
                     // Clean up old file
@@ -286,19 +483,14 @@ impl CircularBuffer {
 
     async fn start_buffer_recording(
         quality_config: &crate::config::VideoQualityConfig,
+        source: &VideoSource,
         file_path: &PathBuf,
         duration: u64,
     ) -> Result<()> {
         let mut cmd = tokio::process::Command::new("ffmpeg");
         
-        cmd.arg("-f")
-           .arg("v4l2")
-           .arg("-i")
-           .arg(&quality_config.device_path)
-           .arg("-framerate")
-           .arg(quality_config.fps.to_string())
-           .arg("-video_size")
-           .arg(&quality_config.resolution)
+        cmd.args(source.input_args(quality_config.fps, &quality_config.resolution))
+           .args(source.output_args(quality_config.fps, &quality_config.resolution))
            .arg("-b:v")
            .arg(quality_config.bitrate.to_string())
            .arg("-c:v")
@@ -328,4 +520,41 @@ impl CircularBuffer {
         tokio::fs::create_dir_all(&storage_path).await?;
         Ok(storage_path)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: DateTime<Utc>, seconds: i64) -> BufferSegment {
+        BufferSegment {
+            id: Uuid::new_v4().to_string(),
+            start_time: start,
+            end_time: start + chrono::Duration::seconds(seconds),
+            duration: seconds as u64,
+            file_path: format!("buffer/{}.mp4", start.timestamp()),
+            file_size: None,
+            quality: VideoQuality::Low,
+            metadata: BufferMetadata {
+                resolution: "640x480".to_string(),
+                fps: 15,
+                bitrate: 500_000,
+                codec: "h264".to_string(),
+                audio_enabled: false,
+                location: None,
+            },
+            integrity: None,
+        }
+    }
+
+    #[test]
+    fn test_restore_keeps_segments_inside_window() {
+        let now = Utc::now();
+        let stale = segment(now - chrono::Duration::seconds(60), 5);
+        let recent = segment(now - chrono::Duration::seconds(20), 5);
+        let overlapping = segment(now - chrono::Duration::seconds(33), 5);
+
+        let restored = restorable_segments(vec![stale, overlapping.clone(), recent.clone()], 30, now);
+        let ids: Vec<String> = restored.into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![overlapping.id, recent.id]);
+    }
+}
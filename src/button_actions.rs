@@ -0,0 +1,250 @@
+//! Button presses mapped to actions by the platform's `button_actions`
+//! setting. Keys name a button and a press, e.g. `menu.double_press`; a bare
+//! press such as `long_press` is the record button. Presses that aren't
+//! mapped keep the button's built-in behaviour. The emergency button always
+//! keeps its own, so SOS can't be delayed or mapped away.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::hardware::ButtonType;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonActionsConfig {
+    /// How long to wait for another press before a single press counts
+    pub multi_press_ms: u64,
+    /// Action per button and press, as synced from the platform
+    pub actions: BTreeMap<String, String>,
+}
+
+impl Default for ButtonActionsConfig {
+    fn default() -> Self {
+        Self {
+            multi_press_ms: 400,
+            actions: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressKind {
+    Single,
+    Double,
+    Triple,
+    Long,
+}
+
+impl PressKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name.strip_suffix("_press").unwrap_or(name) {
+            "single" => Some(PressKind::Single),
+            "double" => Some(PressKind::Double),
+            "triple" => Some(PressKind::Triple),
+            "long" => Some(PressKind::Long),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PressKind::Single => "single",
+            PressKind::Double => "double",
+            PressKind::Triple => "triple",
+            PressKind::Long => "long",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonAction {
+    /// Start recording, or stop the recording in progress
+    RecordToggle,
+    /// Pause the recording in progress, or resume it
+    PauseToggle,
+    /// Mark the current point in the recording
+    Bookmark,
+    /// Raise an emergency incident
+    Sos,
+    /// Silence the speaker, or turn it back on
+    Mute,
+    Stealth,
+    /// Do nothing, not even the built-in behaviour
+    None,
+}
+
+impl ButtonAction {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "record_toggle" | "toggle_recording" => Some(ButtonAction::RecordToggle),
+            "pause_toggle" | "toggle_pause" => Some(ButtonAction::PauseToggle),
+            "bookmark" => Some(ButtonAction::Bookmark),
+            "sos" | "start_sos" => Some(ButtonAction::Sos),
+            "mute" => Some(ButtonAction::Mute),
+            "stealth" => Some(ButtonAction::Stealth),
+            "none" => Some(ButtonAction::None),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ButtonAction::RecordToggle => "record_toggle",
+            ButtonAction::PauseToggle => "pause_toggle",
+            ButtonAction::Bookmark => "bookmark",
+            ButtonAction::Sos => "sos",
+            ButtonAction::Mute => "mute",
+            ButtonAction::Stealth => "stealth",
+            ButtonAction::None => "none",
+        }
+    }
+}
+
+fn parse_button(name: &str) -> Option<ButtonType> {
+    match name {
+        "record" => Some(ButtonType::Record),
+        "emergency" => Some(ButtonType::Emergency),
+        "power" => Some(ButtonType::Power),
+        "menu" => Some(ButtonType::Menu),
+        "zoom_in" => Some(ButtonType::ZoomIn),
+        "zoom_out" => Some(ButtonType::ZoomOut),
+        _ => None,
+    }
+}
+
+/// The parsed `button_actions` setting
+#[derive(Debug, Clone, Default)]
+pub struct ButtonActionMap {
+    actions: HashMap<(ButtonType, PressKind), ButtonAction>,
+}
+
+impl ButtonActionMap {
+    /// Entries that don't parse are skipped with a warning, so one bad key
+    /// from the platform doesn't disable the rest
+    pub fn from_settings(settings: &BTreeMap<String, String>) -> Self {
+        let mut actions = HashMap::new();
+        for (key, action) in settings {
+            let (button, press) = match key.split_once('.') {
+                Some((button, press)) => (parse_button(button), PressKind::parse(press)),
+                None => (Some(ButtonType::Record), PressKind::parse(key)),
+            };
+            let (Some(button), Some(press)) = (button, press) else {
+                tracing::warn!("Ignoring button action for unknown button or press: {}", key);
+                continue;
+            };
+            // SOS must never wait on a multi-press window or be mapped away
+            if button == ButtonType::Emergency {
+                tracing::warn!("Ignoring button action for {}: the emergency button can't be remapped", key);
+                continue;
+            }
+            let Some(action) = ButtonAction::parse(action) else {
+                tracing::warn!("Ignoring unsupported button action {} for {}", action, key);
+                continue;
+            };
+            actions.insert((button, press), action);
+        }
+        Self { actions }
+    }
+
+    pub fn get(&self, button: ButtonType, press: PressKind) -> Option<ButtonAction> {
+        self.actions.get(&(button, press)).copied()
+    }
+
+    /// Whether a single press has to wait out the multi-press window
+    pub fn has_multi_press(&self, button: ButtonType) -> bool {
+        self.get(button, PressKind::Double).is_some() || self.get(button, PressKind::Triple).is_some()
+    }
+}
+
+/// Counts short presses of each button that follow each other within the
+/// multi-press window
+#[derive(Debug)]
+pub struct PressTracker {
+    window: Duration,
+    pending: HashMap<ButtonType, (u32, Instant, u64)>,
+    next_seq: u64,
+}
+
+impl PressTracker {
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: HashMap::new(), next_seq: 0 }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Count a short press. Settle it with the returned number once the
+    /// window has passed.
+    pub fn press(&mut self, button: ButtonType, now: Instant) -> u64 {
+        self.next_seq += 1;
+        let count = match self.pending.get(&button) {
+            Some((count, last, _)) if now.duration_since(*last) <= self.window => count + 1,
+            _ => 1,
+        };
+        self.pending.insert(button, (count, now, self.next_seq));
+        self.next_seq
+    }
+
+    /// The press the run of presses adds up to, or None if another press
+    /// has come since `seq` and will settle it instead
+    pub fn settle(&mut self, button: ButtonType, seq: u64) -> Option<PressKind> {
+        match self.pending.get(&button) {
+            Some((count, _, latest)) if *latest == seq => {
+                let count = *count;
+                self.pending.remove(&button);
+                Some(match count {
+                    1 => PressKind::Single,
+                    2 => PressKind::Double,
+                    _ => PressKind::Triple,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presses_map_to_actions() {
+        let settings: BTreeMap<String, String> = [
+            ("long_press", "sos"),
+            ("record.double_press", "bookmark"),
+            ("menu.triple", "stealth"),
+            ("power.single_press", "none"),
+            ("zoom_in.single_press", "toggle_pause"),
+            ("double_press", "take_photo"),
+            ("camera.single_press", "mute"),
+            ("emergency.single_press", "none"),
+            ("emergency.double_press", "bookmark"),
+        ].into_iter().map(|(key, action)| (key.to_string(), action.to_string())).collect();
+        let map = ButtonActionMap::from_settings(&settings);
+        assert_eq!(map.get(ButtonType::Record, PressKind::Long), Some(ButtonAction::Sos));
+        assert_eq!(map.get(ButtonType::Record, PressKind::Double), Some(ButtonAction::Bookmark));
+        assert_eq!(map.get(ButtonType::Menu, PressKind::Triple), Some(ButtonAction::Stealth));
+        assert_eq!(map.get(ButtonType::Power, PressKind::Single), Some(ButtonAction::None));
+        assert_eq!(map.get(ButtonType::ZoomIn, PressKind::Single), Some(ButtonAction::PauseToggle));
+        assert_eq!(map.get(ButtonType::Record, PressKind::Single), None);
+        assert!(map.has_multi_press(ButtonType::Record));
+        assert!(!map.has_multi_press(ButtonType::Power));
+        assert_eq!(map.get(ButtonType::Emergency, PressKind::Single), None);
+        assert!(!map.has_multi_press(ButtonType::Emergency));
+
+        let mut tracker = PressTracker::new(Duration::from_millis(400));
+        let start = Instant::now();
+        let first = tracker.press(ButtonType::Record, start);
+        let second = tracker.press(ButtonType::Record, start + Duration::from_millis(300));
+        assert_eq!(tracker.settle(ButtonType::Record, first), None);
+        assert_eq!(tracker.settle(ButtonType::Record, second), Some(PressKind::Double));
+
+        let late = tracker.press(ButtonType::Record, start + Duration::from_secs(2));
+        assert_eq!(tracker.settle(ButtonType::Record, late), Some(PressKind::Single));
+        let seqs: Vec<_> = (0..4).map(|i| tracker.press(ButtonType::Menu, start + Duration::from_millis(i * 100))).collect();
+        assert_eq!(tracker.settle(ButtonType::Menu, seqs[3]), Some(PressKind::Triple));
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+use crate::capabilities::CameraControl;
+
+/// Camera controls that can be adjusted at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CameraControlKind {
+    Brightness,
+    Exposure,
+    Gain,
+    Focus,
+    Zoom,
+    IrCut,
+}
+
+impl CameraControlKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraControlKind::Brightness => "brightness",
+            CameraControlKind::Exposure => "exposure",
+            CameraControlKind::Gain => "gain",
+            CameraControlKind::Focus => "focus",
+            CameraControlKind::Zoom => "zoom",
+            CameraControlKind::IrCut => "ir_cut",
+        }
+    }
+
+    /// V4L2 control names, newest kernel naming first
+    fn v4l2_names(&self) -> &'static [&'static str] {
+        match self {
+            CameraControlKind::Brightness => &["brightness"],
+            CameraControlKind::Exposure => &["exposure_time_absolute", "exposure_absolute"],
+            CameraControlKind::Gain => &["gain"],
+            CameraControlKind::Focus => &["focus_absolute"],
+            CameraControlKind::Zoom => &["zoom_absolute"],
+            CameraControlKind::IrCut => &[],
+        }
+    }
+
+    /// Control that switches this one between automatic and manual
+    fn v4l2_auto_control(&self) -> Option<(&'static [&'static str], i32, i32)> {
+        // (names, auto value, manual value)
+        match self {
+            CameraControlKind::Exposure => Some((&["auto_exposure", "exposure_auto"], 3, 1)),
+            CameraControlKind::Focus => Some((&["focus_automatic_continuous", "focus_auto"], 1, 0)),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [CameraControlKind; 6] {
+        [
+            CameraControlKind::Brightness,
+            CameraControlKind::Exposure,
+            CameraControlKind::Gain,
+            CameraControlKind::Focus,
+            CameraControlKind::Zoom,
+            CameraControlKind::IrCut,
+        ]
+    }
+}
+
+impl std::str::FromStr for CameraControlKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase().replace('-', "_");
+        CameraControlKind::all()
+            .into_iter()
+            .find(|kind| kind.name() == s || (s == "ir" && *kind == CameraControlKind::IrCut))
+            .ok_or_else(|| anyhow::anyhow!("Unknown camera control: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlValue {
+    Auto,
+    Manual(i32),
+}
+
+impl std::str::FromStr for ControlValue {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ControlValue::Auto),
+            "on" | "day" => Ok(ControlValue::Manual(1)),
+            "off" | "night" => Ok(ControlValue::Manual(0)),
+            other => other.parse::<i32>()
+                .map(ControlValue::Manual)
+                .map_err(|_| anyhow::anyhow!("Invalid control value: {}", s)),
+        }
+    }
+}
+
+/// Get/set UVC camera controls through `v4l2-ctl`. The IR-cut filter is not
+/// a UVC control on most modules, so it is switched through a sysfs GPIO
+/// value file when one is configured.
+pub struct CameraControls {
+    device_path: String,
+    ir_cut_path: Option<String>,
+    simulation: bool,
+    simulated_values: Mutex<HashMap<CameraControlKind, i32>>,
+}
+
+impl CameraControls {
+    pub fn new(device_path: String, ir_cut_path: Option<String>, simulation: bool) -> Self {
+        Self {
+            device_path,
+            ir_cut_path,
+            simulation,
+            simulated_values: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn device_path(&self) -> &str {
+        &self.device_path
+    }
+
+    pub async fn list(&self) -> Result<Vec<CameraControl>> {
+        if self.simulation || !cfg!(target_os = "linux") {
+            return Ok(CameraControlKind::all()
+                .iter()
+                .map(|kind| CameraControl {
+                    name: kind.name().to_string(),
+                    control_type: if *kind == CameraControlKind::IrCut { "bool" } else { "int" }.to_string(),
+                    min_value: 0,
+                    max_value: if *kind == CameraControlKind::IrCut { 1 } else { 255 },
+                    default_value: if *kind == CameraControlKind::IrCut { 1 } else { 128 },
+                    step: 1,
+                })
+                .collect());
+        }
+
+        let output = self.v4l2_ctl(&["--list-ctrls"]).await?;
+        Ok(parse_control_list(&output))
+    }
+
+    pub async fn get(&self, kind: CameraControlKind) -> Result<i32> {
+        if self.simulation {
+            let values = self.simulated_values.lock().await;
+            return Ok(values.get(&kind).copied().unwrap_or(if kind == CameraControlKind::IrCut { 1 } else { 128 }));
+        }
+
+        if kind == CameraControlKind::IrCut {
+            let path = self.ir_cut_path.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No IR-cut control configured"))?;
+            let value = tokio::fs::read_to_string(path).await
+                .context("Failed to read IR-cut state")?;
+            return Ok(value.trim().parse()?);
+        }
+
+        let name = self.resolve_name(kind).await?;
+        let output = self.v4l2_ctl(&[&format!("--get-ctrl={}", name)]).await?;
+        output
+            .split(':')
+            .nth(1)
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Unexpected v4l2-ctl output: {}", output.trim()))
+    }
+
+    pub async fn set(&self, kind: CameraControlKind, value: ControlValue) -> Result<()> {
+        tracing::info!("Setting camera {} on {} to {:?}", kind.name(), self.device_path, value);
+
+        if self.simulation {
+            if let ControlValue::Manual(v) = value {
+                self.simulated_values.lock().await.insert(kind, v);
+            }
+            return Ok(());
+        }
+
+        if !cfg!(target_os = "linux") {
+            return Err(anyhow::anyhow!("Camera controls are only supported via V4L2 on Linux"));
+        }
+
+        if kind == CameraControlKind::IrCut {
+            let path = self.ir_cut_path.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No IR-cut control configured"))?;
+            let value = match value {
+                ControlValue::Manual(v) => if v != 0 { "1" } else { "0" },
+                ControlValue::Auto => return Err(anyhow::anyhow!("IR-cut does not support auto mode")),
+            };
+            tokio::fs::write(path, value).await.context("Failed to switch IR-cut filter")?;
+            return Ok(());
+        }
+
+        let controls = self.list().await?;
+
+        if let Some((names, auto_value, manual_value)) = kind.v4l2_auto_control() {
+            if let Some(auto_name) = names.iter().find(|n| controls.iter().any(|c| c.name == **n)) {
+                let mode = if value == ControlValue::Auto { auto_value } else { manual_value };
+                self.v4l2_ctl(&[&format!("--set-ctrl={}={}", auto_name, mode)]).await?;
+            }
+        }
+
+        match value {
+            ControlValue::Auto => {
+                if kind.v4l2_auto_control().is_none() {
+                    return Err(anyhow::anyhow!("{} does not support auto mode", kind.name()));
+                }
+            }
+            ControlValue::Manual(v) => {
+                let control = kind.v4l2_names()
+                    .iter()
+                    .find_map(|n| controls.iter().find(|c| c.name == *n))
+                    .ok_or_else(|| anyhow::anyhow!("Camera does not support {}", kind.name()))?;
+
+                if v < control.min_value || v > control.max_value {
+                    return Err(anyhow::anyhow!(
+                        "{} must be between {} and {}", kind.name(), control.min_value, control.max_value
+                    ));
+                }
+
+                self.v4l2_ctl(&[&format!("--set-ctrl={}={}", control.name, v)]).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn resolve_name(&self, kind: CameraControlKind) -> Result<&'static str> {
+        let controls = self.list().await?;
+        kind.v4l2_names()
+            .iter()
+            .copied()
+            .find(|n| controls.iter().any(|c| c.name == *n))
+            .ok_or_else(|| anyhow::anyhow!("Camera does not support {}", kind.name()))
+    }
+
+    async fn v4l2_ctl(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("v4l2-ctl")
+            .arg("-d")
+            .arg(&self.device_path)
+            .args(args)
+            .output()
+            .await
+            .context("Failed to run v4l2-ctl")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "v4l2-ctl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Parse `v4l2-ctl --list-ctrls` output, e.g.
+/// `brightness 0x00980900 (int)    : min=-64 max=64 step=1 default=0 value=0`
+pub fn parse_control_list(output: &str) -> Vec<CameraControl> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (head, attrs) = line.split_once(':')?;
+            let mut head = head.split_whitespace();
+            let name = head.next()?.to_string();
+            let control_type = head
+                .find(|part| part.starts_with('('))?
+                .trim_matches(|c| c == '(' || c == ')')
+                .to_string();
+
+            let attrs: HashMap<&str, i32> = attrs
+                .split_whitespace()
+                .filter_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    Some((key, value.parse().ok()?))
+                })
+                .collect();
+
+            let (min_value, max_value) = match control_type.as_str() {
+                "bool" => (0, 1),
+                _ => (*attrs.get("min")?, *attrs.get("max")?),
+            };
+
+            Some(CameraControl {
+                name,
+                control_type,
+                min_value,
+                max_value,
+                default_value: attrs.get("default").copied().unwrap_or(min_value),
+                step: attrs.get("step").copied().unwrap_or(1),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_control_list() {
+        let output = "\
+User Controls
+
+                     brightness 0x00980900 (int)    : min=-64 max=64 step=1 default=0 value=0
+      white_balance_automatic 0x0098090c (bool)   : default=1 value=1
+
+Camera Controls
+
+                  auto_exposure 0x009a0901 (menu)   : min=0 max=3 default=3 value=3 (Aperture Priority Mode)
+         exposure_time_absolute 0x009a0902 (int)    : min=1 max=5000 step=1 default=157 value=157 flags=inactive
+";
+        let controls = parse_control_list(output);
+        assert_eq!(controls.len(), 4);
+
+        let brightness = controls.iter().find(|c| c.name == "brightness").unwrap();
+        assert_eq!((brightness.min_value, brightness.max_value), (-64, 64));
+
+        let wb = controls.iter().find(|c| c.name == "white_balance_automatic").unwrap();
+        assert_eq!(wb.control_type, "bool");
+        assert_eq!(wb.default_value, 1);
+
+        let exposure = controls.iter().find(|c| c.name == "exposure_time_absolute").unwrap();
+        assert_eq!(exposure.max_value, 5000);
+    }
+
+    #[test]
+    fn test_parse_control_values() {
+        assert_eq!("auto".parse::<ControlValue>().unwrap(), ControlValue::Auto);
+        assert_eq!("night".parse::<ControlValue>().unwrap(), ControlValue::Manual(0));
+        assert_eq!("-12".parse::<ControlValue>().unwrap(), ControlValue::Manual(-12));
+        assert_eq!("ir".parse::<CameraControlKind>().unwrap(), CameraControlKind::IrCut);
+    }
+}
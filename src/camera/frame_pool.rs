@@ -0,0 +1,316 @@
+use chrono::{DateTime, Utc};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast;
+
+/// Frames are packed RGB, three bytes per pixel
+pub const BYTES_PER_PIXEL: usize = 3;
+
+/// Frames a subscriber may fall behind by before it starts skipping. Each
+/// one holds a pool buffer, so this also bounds the memory frames can use.
+const FEED_CAPACITY: usize = 4;
+
+/// Frame buffers handed back by finished frames, so capture doesn't allocate
+/// a full frame every tick. Cheap to clone; clones share the same buffers.
+#[derive(Clone)]
+pub struct FramePool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    idle: Mutex<Vec<Vec<u8>>>,
+    max_idle: usize,
+    allocated: AtomicU64,
+    reused: AtomicU64,
+}
+
+/// How well the pool is keeping up, for diagnostics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FramePoolStats {
+    /// Buffers allocated because none was idle
+    pub allocated: u64,
+    /// Buffers taken from the pool
+    pub reused: u64,
+    pub idle: usize,
+}
+
+impl FramePool {
+    /// Keep up to `max_idle` buffers waiting for reuse
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                idle: Mutex::new(Vec::with_capacity(max_idle)),
+                max_idle,
+                allocated: AtomicU64::new(0),
+                reused: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// A zeroed buffer for one `width` x `height` frame, reusing an idle one
+    /// when it is big enough
+    pub fn acquire(&self, width: u32, height: u32) -> FrameBuffer {
+        let len = width as usize * height as usize * BYTES_PER_PIXEL;
+        let reusable = {
+            let mut idle = self.inner.idle.lock().unwrap();
+            idle.iter().position(|buffer| buffer.capacity() >= len)
+                .map(|index| idle.swap_remove(index))
+        };
+
+        let data = match reusable {
+            Some(mut data) => {
+                self.inner.reused.fetch_add(1, Ordering::Relaxed);
+                data.resize(len, 0);
+                data
+            }
+            None => {
+                self.inner.allocated.fetch_add(1, Ordering::Relaxed);
+                vec![0; len]
+            }
+        };
+        FrameBuffer { width, height, data, pool: Arc::downgrade(&self.inner) }
+    }
+
+    pub fn stats(&self) -> FramePoolStats {
+        FramePoolStats {
+            allocated: self.inner.allocated.load(Ordering::Relaxed),
+            reused: self.inner.reused.load(Ordering::Relaxed),
+            idle: self.inner.idle.lock().unwrap().len(),
+        }
+    }
+}
+
+impl PoolInner {
+    fn release(&self, mut data: Vec<u8>) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.max_idle {
+            data.clear();
+            idle.push(data);
+        }
+    }
+}
+
+/// A frame being filled by the capture loop
+pub struct FrameBuffer {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    pool: Weak<PoolInner>,
+}
+
+impl FrameBuffer {
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Finish the frame so it can be shared. The buffer goes back to the
+    /// pool once every holder has dropped it.
+    pub fn freeze(self, sequence: u64, captured_at: DateTime<Utc>) -> SharedFrame {
+        let mut this = std::mem::ManuallyDrop::new(self);
+        SharedFrame(Arc::new(PooledFrame {
+            width: this.width,
+            height: this.height,
+            sequence,
+            captured_at,
+            data: std::mem::take(&mut this.data),
+            pool: std::mem::take(&mut this.pool),
+        }))
+    }
+}
+
+impl Drop for FrameBuffer {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.upgrade() {
+            pool.release(std::mem::take(&mut self.data));
+        }
+    }
+}
+
+/// A captured frame. The pixels are never copied between consumers; each
+/// holds the same buffer until it is done with it.
+pub struct PooledFrame {
+    pub width: u32,
+    pub height: u32,
+    /// Counts up from the start of the capture; gaps are skipped frames
+    pub sequence: u64,
+    pub captured_at: DateTime<Utc>,
+    data: Vec<u8>,
+    pool: Weak<PoolInner>,
+}
+
+impl PooledFrame {
+    /// Packed RGB pixels, row by row
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Drop for PooledFrame {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.upgrade() {
+            pool.release(std::mem::take(&mut self.data));
+        }
+    }
+}
+
+/// Reference-counted handle to a frame
+#[derive(Clone)]
+pub struct SharedFrame(Arc<PooledFrame>);
+
+impl Deref for SharedFrame {
+    type Target = PooledFrame;
+
+    fn deref(&self) -> &PooledFrame {
+        &self.0
+    }
+}
+
+/// Frames from one camera, shared by the preview and the encoder feed.
+/// Clones are cheap; the feed ends once its publisher is dropped.
+pub struct FrameFeed {
+    /// The camera the frames come from, as the recorder names it
+    pub device_path: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    rx: broadcast::Receiver<SharedFrame>,
+}
+
+/// The capture side of a feed
+pub struct FramePublisher {
+    tx: broadcast::Sender<SharedFrame>,
+}
+
+impl FrameFeed {
+    pub fn channel(device_path: &str, width: u32, height: u32, fps: u32) -> (FramePublisher, Self) {
+        let (tx, rx) = broadcast::channel(FEED_CAPACITY);
+        (FramePublisher { tx }, Self { device_path: device_path.to_string(), width, height, fps, rx })
+    }
+
+    pub fn subscribe(&self) -> FrameSubscriber {
+        FrameSubscriber { rx: self.rx.resubscribe(), skipped: 0 }
+    }
+}
+
+impl Clone for FrameFeed {
+    fn clone(&self) -> Self {
+        Self {
+            device_path: self.device_path.clone(),
+            width: self.width,
+            height: self.height,
+            fps: self.fps,
+            rx: self.rx.resubscribe(),
+        }
+    }
+}
+
+impl FramePublisher {
+    /// Hand a frame to every subscriber
+    pub fn publish(&self, frame: SharedFrame) {
+        let _ = self.tx.send(frame);
+    }
+}
+
+/// One consumer of a feed. A consumer that falls behind skips frames
+/// rather than holding up capture.
+pub struct FrameSubscriber {
+    rx: broadcast::Receiver<SharedFrame>,
+    skipped: u64,
+}
+
+impl FrameSubscriber {
+    /// The next frame, or None once capture has stopped
+    pub async fn recv(&mut self) -> Option<SharedFrame> {
+        loop {
+            match self.rx.recv().await {
+                Ok(frame) => return Some(frame),
+                Err(broadcast::error::RecvError::Lagged(count)) => self.skipped += count,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// The newest frame waiting, dropping any older ones. For consumers that
+    /// only show the latest frame, like the preview.
+    pub fn try_latest(&mut self) -> Option<SharedFrame> {
+        let mut latest = None;
+        loop {
+            match self.rx.try_recv() {
+                Ok(frame) => {
+                    if latest.replace(frame).is_some() {
+                        self.skipped += 1;
+                    }
+                }
+                Err(broadcast::error::TryRecvError::Lagged(count)) => self.skipped += count,
+                Err(_) => return latest,
+            }
+        }
+    }
+
+    /// Frames this subscriber never saw
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+/// Write each frame's pixels straight from the shared buffer into an
+/// encoder reading raw video, until capture stops or the encoder goes away
+pub async fn feed_encoder(mut frames: FrameSubscriber, mut encoder: impl AsyncWrite + Unpin) {
+    while let Some(frame) = frames.recv().await {
+        if let Err(e) = encoder.write_all(frame.data()).await {
+            tracing::debug!("Encoder stopped reading frames: {}", e);
+            break;
+        }
+    }
+    if frames.skipped() > 0 {
+        tracing::warn!("Encoder skipped {} frames it couldn't keep up with", frames.skipped());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_return_to_the_pool() {
+        let pool = FramePool::new(2);
+        let mut buffer = pool.acquire(4, 2);
+        buffer.data_mut()[0] = 7;
+        let frame = buffer.freeze(0, Utc::now());
+        let shared = frame.clone();
+        assert_eq!(shared.data().len(), 4 * 2 * BYTES_PER_PIXEL);
+        assert_eq!(pool.stats().idle, 0);
+
+        drop(frame);
+        assert_eq!(pool.stats().idle, 0);
+        drop(shared);
+        assert_eq!(pool.stats().idle, 1);
+
+        let reused = pool.acquire(2, 2);
+        assert!(reused.data.iter().all(|byte| *byte == 0));
+        assert_eq!(pool.stats(), FramePoolStats { allocated: 1, reused: 1, idle: 0 });
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_only_sees_the_latest_frame() {
+        let pool = FramePool::new(8);
+        let (publisher, feed) = FrameFeed::channel("/dev/video0", 2, 2, 30);
+        let mut preview = feed.subscribe();
+        let mut encoder = feed.clone().subscribe();
+
+        for sequence in 0..3 {
+            publisher.publish(pool.acquire(2, 2).freeze(sequence, Utc::now()));
+        }
+
+        assert_eq!(preview.try_latest().map(|frame| frame.sequence), Some(2));
+        assert_eq!(preview.skipped(), 2);
+        assert_eq!(encoder.recv().await.map(|frame| frame.sequence), Some(0));
+
+        let mut written = Vec::new();
+        drop(publisher);
+        feed_encoder(encoder, &mut written).await;
+        assert_eq!(written.len(), 2 * 2 * 2 * BYTES_PER_PIXEL);
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaDeviceKind {
+    Camera,
+    Microphone,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotplugEvent {
+    Added {
+        kind: MediaDeviceKind,
+        device: String,
+    },
+    Removed {
+        kind: MediaDeviceKind,
+        device: String,
+    },
+}
+
+impl HotplugEvent {
+    pub fn kind(&self) -> MediaDeviceKind {
+        match self {
+            HotplugEvent::Added { kind, .. } | HotplugEvent::Removed { kind, .. } => *kind,
+        }
+    }
+
+    pub fn device(&self) -> &str {
+        match self {
+            HotplugEvent::Added { device, .. } | HotplugEvent::Removed { device, .. } => device,
+        }
+    }
+}
+
+const POLL_INTERVAL_MS: u64 = 2000;
+
+/// Watches for cameras and microphones being connected or removed.
+///
+/// On Linux this follows `udevadm monitor` for the video4linux and sound
+/// subsystems and falls back to polling `/dev` when udevadm isn't available.
+/// On macOS the AVFoundation/CoreAudio device lists (backed by IOKit) are
+/// polled through nokhwa and cpal.
+pub struct HotplugMonitor;
+
+impl HotplugMonitor {
+    pub fn start() -> Result<mpsc::UnboundedReceiver<HotplugEvent>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        #[cfg(target_os = "linux")]
+        tokio::spawn(async move {
+            if let Err(e) = Self::follow_udev(tx.clone()).await {
+                tracing::warn!("udev monitoring unavailable ({}), polling /dev instead", e);
+                Self::poll_devices(tx, Self::scan_linux_devices).await;
+            }
+        });
+
+        #[cfg(not(target_os = "linux"))]
+        tokio::spawn(async move {
+            Self::poll_devices(tx, Self::scan_system_devices).await;
+        });
+
+        Ok(rx)
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn follow_udev(tx: mpsc::UnboundedSender<HotplugEvent>) -> Result<()> {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let mut child = tokio::process::Command::new("udevadm")
+            .args([
+                "monitor",
+                "--udev",
+                "--subsystem-match=video4linux",
+                "--subsystem-match=sound",
+            ])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdout = child.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("udevadm produced no stdout"))?;
+        let mut lines = BufReader::new(stdout).lines();
+
+        tracing::info!("Hot-plug monitoring started via udev");
+
+        while let Some(line) = lines.next_line().await? {
+            if let Some(event) = parse_udev_line(&line) {
+                tracing::info!("Hot-plug event: {:?}", event);
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        }
+
+        Err(anyhow::anyhow!("udevadm monitor exited"))
+    }
+
+    async fn poll_devices<F>(tx: mpsc::UnboundedSender<HotplugEvent>, scan: F)
+    where
+        F: Fn() -> HashSet<(MediaDeviceKind, String)>,
+    {
+        let mut known = scan();
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(POLL_INTERVAL_MS));
+
+        loop {
+            interval.tick().await;
+
+            let current = scan();
+            for event in diff_devices(&known, &current) {
+                tracing::info!("Hot-plug event: {:?}", event);
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+            known = current;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn scan_linux_devices() -> HashSet<(MediaDeviceKind, String)> {
+        let mut devices = HashSet::new();
+
+        if let Ok(entries) = std::fs::read_dir("/dev") {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with("video") {
+                    devices.insert((MediaDeviceKind::Camera, format!("/dev/{}", name)));
+                }
+            }
+        }
+
+        if let Ok(entries) = std::fs::read_dir("/dev/snd") {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(device) = alsa_capture_device(&name) {
+                    devices.insert((MediaDeviceKind::Microphone, device));
+                }
+            }
+        }
+
+        devices
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn scan_system_devices() -> HashSet<(MediaDeviceKind, String)> {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let mut devices = HashSet::new();
+
+        if let Ok(cameras) = nokhwa::query(nokhwa::utils::ApiBackend::Auto) {
+            for camera in cameras {
+                devices.insert((MediaDeviceKind::Camera, camera.human_name().to_string()));
+            }
+        }
+
+        if let Ok(inputs) = cpal::default_host().input_devices() {
+            for input in inputs {
+                if let Ok(name) = input.name() {
+                    devices.insert((MediaDeviceKind::Microphone, name));
+                }
+            }
+        }
+
+        devices
+    }
+}
+
+/// Map an ALSA capture node name (`pcmC1D0c`) to its `hw:1,0` device string
+fn alsa_capture_device(node: &str) -> Option<String> {
+    let rest = node.strip_prefix("pcmC")?.strip_suffix('c')?;
+    let (card, device) = rest.split_once('D')?;
+    let card: u32 = card.parse().ok()?;
+    let device: u32 = device.parse().ok()?;
+    Some(format!("hw:{},{}", card, device))
+}
+
+/// Parse a line of `udevadm monitor --udev` output, e.g.
+/// `UDEV  [1234.567890] add      /devices/.../video4linux/video0 (video4linux)`
+pub fn parse_udev_line(line: &str) -> Option<HotplugEvent> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != "UDEV" {
+        return None;
+    }
+
+    let _timestamp = parts.next()?;
+    let action = parts.next()?;
+    let devpath = parts.next()?;
+    let subsystem = parts.next()?.trim_matches(|c| c == '(' || c == ')');
+    let node = devpath.rsplit('/').next()?;
+
+    let (kind, device) = match subsystem {
+        "video4linux" if node.starts_with("video") => (MediaDeviceKind::Camera, format!("/dev/{}", node)),
+        "sound" => (MediaDeviceKind::Microphone, alsa_capture_device(node)?),
+        _ => return None,
+    };
+
+    match action {
+        "add" => Some(HotplugEvent::Added { kind, device }),
+        "remove" => Some(HotplugEvent::Removed { kind, device }),
+        _ => None,
+    }
+}
+
+pub fn diff_devices(
+    previous: &HashSet<(MediaDeviceKind, String)>,
+    current: &HashSet<(MediaDeviceKind, String)>,
+) -> Vec<HotplugEvent> {
+    let removed = previous.difference(current).map(|(kind, device)| HotplugEvent::Removed {
+        kind: *kind,
+        device: device.clone(),
+    });
+    let added = current.difference(previous).map(|(kind, device)| HotplugEvent::Added {
+        kind: *kind,
+        device: device.clone(),
+    });
+
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_camera_remove() {
+        let line = "UDEV  [8123.456789] remove   /devices/platform/usb/1-1/1-1:1.0/video4linux/video2 (video4linux)";
+        assert_eq!(
+            parse_udev_line(line),
+            Some(HotplugEvent::Removed {
+                kind: MediaDeviceKind::Camera,
+                device: "/dev/video2".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_capture_add_and_ignore_playback() {
+        let add = "UDEV  [10.0] add      /devices/usb/sound/card1/pcmC1D0c (sound)";
+        assert_eq!(
+            parse_udev_line(add),
+            Some(HotplugEvent::Added {
+                kind: MediaDeviceKind::Microphone,
+                device: "hw:1,0".to_string(),
+            })
+        );
+
+        let playback = "UDEV  [10.0] add      /devices/usb/sound/card1/pcmC1D0p (sound)";
+        assert_eq!(parse_udev_line(playback), None);
+        assert_eq!(parse_udev_line("monitor will print the received events for:"), None);
+    }
+
+    #[test]
+    fn test_diff_devices() {
+        let previous: HashSet<_> = [(MediaDeviceKind::Camera, "/dev/video0".to_string())].into();
+        let current: HashSet<_> = [(MediaDeviceKind::Camera, "/dev/video1".to_string())].into();
+        let events = diff_devices(&previous, &current);
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&HotplugEvent::Removed {
+            kind: MediaDeviceKind::Camera,
+            device: "/dev/video0".to_string(),
+        }));
+    }
+}
@@ -1,10 +1,20 @@
 use anyhow::{Result, Context};
 use nokhwa::Camera;
+use nokhwa::pixel_format::RgbFormat;
 use nokhwa::utils::{CameraIndex, RequestedFormat, RequestedFormatType, CameraFormat, FrameFormat};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
+pub mod controls;
+pub mod frame_pool;
+pub mod hotplug;
+pub mod night_mode;
+pub mod quality_ladder;
+
+use frame_pool::{FrameFeed, FramePool};
+
 #[derive(Debug, Clone)]
 pub struct CameraDevice {
     pub index: u32,
@@ -26,6 +36,14 @@ pub struct CameraManager {
     current_camera: Option<Camera>,
     current_audio: Option<cpal::Device>,
     is_recording: bool,
+    capture: Option<Capture>,
+}
+
+/// A camera being read into pooled frames on its own thread
+struct Capture {
+    feed: FrameFeed,
+    stop: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<()>,
 }
 
 impl CameraManager {
@@ -39,6 +57,7 @@ impl CameraManager {
             current_camera: None,
             current_audio: None,
             is_recording: false,
+            capture: None,
         })
     }
 
@@ -133,6 +152,88 @@ impl CameraManager {
         Ok(())
     }
 
+    /// Capture from the camera into frames taken from `pool`, shared with
+    /// the preview and the recorder without copying. The camera is opened on
+    /// the capture thread, so it is busy until `stop_capture`.
+    pub fn start_capture(&mut self, camera_index: u32, resolution: (u32, u32), fps: u32, pool: FramePool) -> Result<FrameFeed> {
+        self.stop_capture();
+
+        let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::Closest(CameraFormat::new(
+            nokhwa::utils::Resolution::new(resolution.0, resolution.1),
+            FrameFormat::MJPEG,
+            fps,
+        )));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = std::sync::mpsc::sync_channel(1);
+
+        // The camera can't move between threads, so it is opened on the one
+        // that reads it
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name(format!("camera-{}", camera_index))
+                .spawn(move || {
+                    let opened = Camera::new(CameraIndex::Index(camera_index), requested)
+                        .and_then(|mut camera| camera.open_stream().map(|_| camera));
+                    let mut camera = match opened {
+                        Ok(camera) => camera,
+                        Err(e) => {
+                            let _ = ready_tx.send(Err(anyhow::anyhow!("Failed to open camera {}: {}", camera_index, e)));
+                            return;
+                        }
+                    };
+                    let format = camera.camera_format();
+                    let (publisher, feed) = FrameFeed::channel(
+                        &format!("/dev/video{}", camera_index),
+                        format.width(),
+                        format.height(),
+                        format.frame_rate(),
+                    );
+                    let _ = ready_tx.send(Ok(feed));
+
+                    let mut sequence = 0;
+                    while !stop.load(Ordering::Relaxed) {
+                        let captured = match camera.frame() {
+                            Ok(captured) => captured,
+                            Err(e) => {
+                                tracing::warn!("Camera {} frame lost: {}", camera_index, e);
+                                continue;
+                            }
+                        };
+                        let size = captured.resolution();
+                        let mut frame = pool.acquire(size.width(), size.height());
+                        // Decoded straight into the pooled buffer
+                        if let Err(e) = captured.decode_image_to_buffer::<RgbFormat>(frame.data_mut()) {
+                            tracing::warn!("Camera {} frame undecodable: {}", camera_index, e);
+                            continue;
+                        }
+                        publisher.publish(frame.freeze(sequence, chrono::Utc::now()));
+                        sequence += 1;
+                    }
+                    let _ = camera.stop_stream();
+                })?
+        };
+        let feed = ready_rx.recv()
+            .map_err(|_| anyhow::anyhow!("Camera {} capture thread exited", camera_index))??;
+
+        tracing::info!("Capturing camera {} at {}x{}@{}fps", camera_index, feed.width, feed.height, feed.fps);
+        self.capture = Some(Capture { feed: feed.clone(), stop, thread });
+        Ok(feed)
+    }
+
+    /// The running capture's frames, if any
+    pub fn frame_feed(&self) -> Option<FrameFeed> {
+        self.capture.as_ref().map(|capture| capture.feed.clone())
+    }
+
+    /// Stop capturing and release the camera. Subscribers see the feed end.
+    pub fn stop_capture(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            capture.stop.store(true, Ordering::Relaxed);
+            let _ = capture.thread.join();
+        }
+    }
+
     pub fn stop_camera(&mut self) -> Result<()> {
         if let Some(camera) = &mut self.current_camera {
             camera.stop_stream()?;
@@ -174,4 +275,10 @@ impl CameraManager {
     pub fn is_recording(&self) -> bool {
         self.is_recording
     }
+}
+
+impl Drop for CameraManager {
+    fn drop(&mut self) {
+        self.stop_capture();
+    }
 }
\ No newline at end of file
@@ -0,0 +1,217 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use crate::config::{NightModeConfig, VideoQualityConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LightCondition {
+    Day,
+    Night,
+}
+
+/// Where a light reading came from; the two use different scales
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightReading {
+    /// Ambient light sensor, in lux
+    Lux(f64),
+    /// Mean frame luma (Y), 0-255
+    Luminance(f64),
+}
+
+/// Decides when to enter and leave night mode. Separate enter/exit
+/// thresholds plus a hold time stop the camera flapping at dusk.
+pub struct NightModeController {
+    config: NightModeConfig,
+    condition: LightCondition,
+    pending_since: Option<Instant>,
+    last_sample: Option<Instant>,
+}
+
+impl NightModeController {
+    pub fn new(config: NightModeConfig) -> Self {
+        Self {
+            config,
+            condition: LightCondition::Day,
+            pending_since: None,
+            last_sample: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn condition(&self) -> LightCondition {
+        self.condition
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.condition == LightCondition::Night
+    }
+
+    /// Feed a light reading; returns the new condition when a switch should happen
+    pub fn observe(&mut self, reading: LightReading) -> Option<LightCondition> {
+        self.observe_at(reading, Instant::now())
+    }
+
+    fn observe_at(&mut self, reading: LightReading, now: Instant) -> Option<LightCondition> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let wants = self.wanted(reading);
+        if wants == self.condition {
+            self.pending_since = None;
+            return None;
+        }
+
+        let since = *self.pending_since.get_or_insert(now);
+        if now.duration_since(since) >= Duration::from_secs(self.config.hold_seconds) {
+            self.condition = wants;
+            self.pending_since = None;
+            return Some(wants);
+        }
+
+        None
+    }
+
+    /// Take a reading at face value, skipping the hold time. For the sample
+    /// taken before a recording starts, so the first segment is already
+    /// encoded for the light it's recorded in.
+    pub fn seed(&mut self, reading: LightReading) -> Option<LightCondition> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let wants = self.wanted(reading);
+        self.pending_since = None;
+        if wants == self.condition {
+            return None;
+        }
+        self.condition = wants;
+        Some(wants)
+    }
+
+    fn wanted(&self, reading: LightReading) -> LightCondition {
+        let (value, enter, exit) = match reading {
+            LightReading::Lux(lux) => (lux, self.config.enter_lux, self.config.exit_lux),
+            LightReading::Luminance(luma) => (luma, self.config.enter_luminance, self.config.exit_luminance),
+        };
+
+        match self.condition {
+            LightCondition::Day if value < enter => LightCondition::Night,
+            LightCondition::Night if value > exit => LightCondition::Day,
+            current => current,
+        }
+    }
+
+    /// Whether it's time to grab another frame for a luminance sample
+    pub fn sample_due(&mut self) -> bool {
+        let interval = Duration::from_secs(self.config.sample_interval_seconds);
+        let due = self.config.enabled
+            && self.last_sample.map(|t| t.elapsed() >= interval).unwrap_or(true);
+        if due {
+            self.last_sample = Some(Instant::now());
+        }
+        due
+    }
+
+    /// Encoder settings to use for a quality while in night mode: fewer
+    /// frames give the sensor longer exposure, the extra bitrate absorbs noise.
+    pub fn adjust_quality(&self, quality: &VideoQualityConfig) -> VideoQualityConfig {
+        let mut adjusted = quality.clone();
+        adjusted.fps = quality.fps.min(self.config.night_fps).max(1);
+        adjusted.bitrate = (quality.bitrate as f64 * self.config.night_bitrate_multiplier) as u32;
+        adjusted
+    }
+}
+
+/// Grab one frame and return its mean luma using ffmpeg's signalstats filter.
+/// Only usable while the camera isn't held open by a recording.
+pub async fn measure_frame_luminance(device_path: &str) -> Result<f64> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-f", "v4l2", "-i", device_path, "-frames:v", "1"])
+        .args(["-vf", "signalstats,metadata=print:key=lavfi.signalstats.YAVG"])
+        .args(["-f", "null", "-"])
+        .output()
+        .await
+        .context("Failed to run ffmpeg for luminance sample")?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    parse_yavg(&stderr).ok_or_else(|| anyhow::anyhow!("No luminance value in ffmpeg output"))
+}
+
+/// Filter chain that logs a frame's mean luma every `interval_seconds`,
+/// for an encoder to measure the light while it holds the camera
+pub fn luminance_tap(interval_seconds: u64) -> String {
+    format!("fps=1/{},signalstats,metadata=print:key=lavfi.signalstats.YAVG", interval_seconds.max(1))
+}
+
+pub fn parse_yavg(output: &str) -> Option<f64> {
+    output
+        .lines()
+        .find_map(|line| line.split("lavfi.signalstats.YAVG=").nth(1))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> NightModeConfig {
+        NightModeConfig {
+            enabled: true,
+            enter_lux: 10.0,
+            exit_lux: 30.0,
+            enter_luminance: 40.0,
+            exit_luminance: 70.0,
+            hold_seconds: 5,
+            night_fps: 15,
+            night_bitrate_multiplier: 1.5,
+            sample_interval_seconds: 30,
+        }
+    }
+
+    #[test]
+    fn test_switch_requires_hold_time() {
+        let mut controller = NightModeController::new(config());
+        let start = Instant::now();
+
+        assert_eq!(controller.observe_at(LightReading::Lux(2.0), start), None);
+        assert_eq!(controller.observe_at(LightReading::Lux(2.0), start + Duration::from_secs(3)), None);
+        assert_eq!(
+            controller.observe_at(LightReading::Lux(2.0), start + Duration::from_secs(6)),
+            Some(LightCondition::Night)
+        );
+        assert!(controller.is_night());
+    }
+
+    #[test]
+    fn test_hysteresis_band_keeps_state() {
+        let mut controller = NightModeController::new(NightModeConfig { hold_seconds: 0, ..config() });
+        let now = Instant::now();
+
+        assert_eq!(controller.observe_at(LightReading::Luminance(20.0), now), Some(LightCondition::Night));
+        // Between the enter and exit thresholds nothing changes
+        assert_eq!(controller.observe_at(LightReading::Luminance(55.0), now), None);
+        assert_eq!(controller.observe_at(LightReading::Luminance(90.0), now), Some(LightCondition::Day));
+    }
+
+    #[test]
+    fn test_seed_skips_hold_time() {
+        let mut controller = NightModeController::new(config());
+
+        assert_eq!(controller.seed(LightReading::Luminance(20.0)), Some(LightCondition::Night));
+        assert_eq!(controller.seed(LightReading::Luminance(55.0)), None);
+        assert!(controller.is_night());
+    }
+
+    #[test]
+    fn test_parse_yavg() {
+        let output = "[Parsed_metadata_1 @ 0x55] frame:0    pts:0\n[Parsed_metadata_1 @ 0x55] lavfi.signalstats.YAVG=37.52\n";
+        assert_eq!(parse_yavg(output), Some(37.52));
+    }
+}
@@ -0,0 +1,483 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::capabilities::{CameraCapabilities, CameraDevice, Resolution};
+use crate::capture::VideoSource;
+use crate::config::{Config, RecordingConfig, VideoQuality, VideoQualityConfig};
+
+/// Length of the clips `qualities test` records per profile
+pub const TEST_CLIP_SECONDS: u32 = 5;
+/// A test clip that takes longer than this to record has hung
+const TEST_CLIP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Lowest first, so the ladder can be checked for order
+pub fn quality_rank(quality: &VideoQuality) -> u8 {
+    match quality {
+        VideoQuality::Low => 0,
+        VideoQuality::Medium => 1,
+        VideoQuality::High => 2,
+        VideoQuality::Ultra => 3,
+    }
+}
+
+pub fn quality_name(quality: &VideoQuality) -> &'static str {
+    match quality {
+        VideoQuality::Low => "low",
+        VideoQuality::Medium => "medium",
+        VideoQuality::High => "high",
+        VideoQuality::Ultra => "ultra",
+    }
+}
+
+pub fn parse_quality(name: &str) -> Option<VideoQuality> {
+    match name.to_ascii_lowercase().as_str() {
+        "low" => Some(VideoQuality::Low),
+        "medium" => Some(VideoQuality::Medium),
+        "high" => Some(VideoQuality::High),
+        "ultra" => Some(VideoQuality::Ultra),
+        _ => None,
+    }
+}
+
+/// `WxH`, e.g. `1920x1080`
+pub fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let (width, height) = resolution.trim().split_once(['x', 'X'])?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// Something wrong with the configured quality ladder
+#[derive(Debug, Clone, PartialEq)]
+pub enum LadderProblem {
+    Empty,
+    Duplicate { quality: VideoQuality },
+    /// `default_quality` isn't one of the configured profiles
+    MissingDefault { quality: VideoQuality },
+    BadResolution { quality: VideoQuality, resolution: String },
+    ZeroFps { quality: VideoQuality },
+    ZeroBitrate { quality: VideoQuality },
+    /// A higher quality with fewer pixels or a lower bitrate than a lower one
+    OutOfOrder { quality: VideoQuality, below: VideoQuality },
+    /// The profile's camera wasn't detected
+    UnknownCamera { quality: VideoQuality, device_path: String },
+    /// The camera can't capture at the profile's resolution or frame rate
+    Unsupported { quality: VideoQuality, resolution: String, fps: u32 },
+}
+
+impl LadderProblem {
+    /// Errors make the ladder unusable as configured; the rest are worked
+    /// around by downgrading or left for the operator to check
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::OutOfOrder { .. } | Self::UnknownCamera { .. } | Self::Unsupported { .. })
+    }
+}
+
+impl fmt::Display for LadderProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no qualities are configured"),
+            Self::Duplicate { quality } => write!(f, "{} is configured more than once", quality_name(quality)),
+            Self::MissingDefault { quality } => {
+                write!(f, "default quality {} isn't in the ladder", quality_name(quality))
+            }
+            Self::BadResolution { quality, resolution } => {
+                write!(f, "{}: resolution {:?} isn't WIDTHxHEIGHT", quality_name(quality), resolution)
+            }
+            Self::ZeroFps { quality } => write!(f, "{}: frame rate is zero", quality_name(quality)),
+            Self::ZeroBitrate { quality } => write!(f, "{}: bitrate is zero", quality_name(quality)),
+            Self::OutOfOrder { quality, below } => write!(
+                f,
+                "{} has fewer pixels or a lower bitrate than {}",
+                quality_name(quality),
+                quality_name(below)
+            ),
+            Self::UnknownCamera { quality, device_path } => {
+                write!(f, "{}: camera {} wasn't detected", quality_name(quality), device_path)
+            }
+            Self::Unsupported { quality, resolution, fps } => write!(
+                f,
+                "{}: camera can't capture {}@{}fps",
+                quality_name(quality),
+                resolution,
+                fps
+            ),
+        }
+    }
+}
+
+/// Checks the ladder on its own and, when cameras were detected, against
+/// what each profile's camera can capture
+pub fn validate_ladder(recording: &RecordingConfig, cameras: Option<&CameraCapabilities>) -> Vec<LadderProblem> {
+    let ladder = &recording.available_qualities;
+    let mut problems = Vec::new();
+    if ladder.is_empty() {
+        problems.push(LadderProblem::Empty);
+        return problems;
+    }
+
+    let mut seen = HashSet::new();
+    for profile in ladder {
+        let quality = profile.quality.clone();
+        if !seen.insert(quality.clone()) {
+            problems.push(LadderProblem::Duplicate { quality: quality.clone() });
+        }
+        if parse_resolution(&profile.resolution).is_none() {
+            problems.push(LadderProblem::BadResolution { quality: quality.clone(), resolution: profile.resolution.clone() });
+        }
+        if profile.fps == 0 {
+            problems.push(LadderProblem::ZeroFps { quality: quality.clone() });
+        }
+        if profile.bitrate == 0 {
+            problems.push(LadderProblem::ZeroBitrate { quality: quality.clone() });
+        }
+
+        if let Some(cameras) = cameras {
+            match cameras.devices.iter().find(|device| device.device_path == profile.device_path) {
+                Some(device) => {
+                    if fit_to_camera(profile, device).is_some() {
+                        problems.push(LadderProblem::Unsupported {
+                            quality,
+                            resolution: profile.resolution.clone(),
+                            fps: profile.fps,
+                        });
+                    }
+                }
+                None => problems.push(LadderProblem::UnknownCamera { quality, device_path: profile.device_path.clone() }),
+            }
+        }
+    }
+    if !seen.contains(&recording.default_quality) {
+        problems.push(LadderProblem::MissingDefault { quality: recording.default_quality.clone() });
+    }
+
+    let mut ordered: Vec<_> = ladder.iter()
+        .filter_map(|profile| parse_resolution(&profile.resolution).map(|(w, h)| (profile, w as u64 * h as u64)))
+        .collect();
+    ordered.sort_by_key(|(profile, _)| quality_rank(&profile.quality));
+    for pair in ordered.windows(2) {
+        let ((lower, lower_pixels), (higher, higher_pixels)) = (pair[0], pair[1]);
+        if lower.quality == higher.quality {
+            continue;
+        }
+        if higher_pixels < lower_pixels || higher.bitrate < lower.bitrate {
+            problems.push(LadderProblem::OutOfOrder { quality: higher.quality.clone(), below: lower.quality.clone() });
+        }
+    }
+
+    problems
+}
+
+/// The closest profile the camera can capture, or `None` when it can
+/// already capture this one. Picks the largest supported resolution no
+/// bigger than requested (same aspect ratio first) and the highest frame
+/// rate no higher than requested, and scales the bitrate to match.
+pub fn fit_to_camera(profile: &VideoQualityConfig, camera: &CameraDevice) -> Option<VideoQualityConfig> {
+    let (width, height) = parse_resolution(&profile.resolution)?;
+    let resolution_supported = camera.resolutions.is_empty()
+        || camera.resolutions.iter().any(|r| r.width == width && r.height == height);
+    let fps_supported = camera.frame_rates.is_empty() || camera.frame_rates.contains(&profile.fps);
+    if resolution_supported && fps_supported {
+        return None;
+    }
+
+    let (new_width, new_height) = if resolution_supported {
+        (width, height)
+    } else {
+        let pixels = |r: &Resolution| r.width as u64 * r.height as u64;
+        let requested = width as u64 * height as u64;
+        let fits = |r: &&Resolution| pixels(r) <= requested;
+        let same_aspect = |r: &&Resolution| r.width as u64 * height as u64 == r.height as u64 * width as u64;
+        let best = camera.resolutions.iter().filter(fits).filter(same_aspect).max_by_key(|r| pixels(r))
+            .or_else(|| camera.resolutions.iter().filter(fits).max_by_key(|r| pixels(r)))
+            .or_else(|| camera.resolutions.iter().min_by_key(|r| pixels(r)))?;
+        (best.width, best.height)
+    };
+    let new_fps = if fps_supported {
+        profile.fps
+    } else {
+        camera.frame_rates.iter().copied().filter(|fps| *fps <= profile.fps).max()
+            .or_else(|| camera.frame_rates.iter().copied().min())?
+    };
+
+    let scale = (new_width as f64 * new_height as f64 * new_fps as f64)
+        / (width as f64 * height as f64 * profile.fps.max(1) as f64);
+    Some(VideoQualityConfig {
+        resolution: format!("{}x{}", new_width, new_height),
+        fps: new_fps,
+        bitrate: ((profile.bitrate as f64 * scale).round() as u32).max(1),
+        ..profile.clone()
+    })
+}
+
+/// A profile changed to fit its camera
+#[derive(Debug, Clone)]
+pub struct Downgrade {
+    pub requested: VideoQualityConfig,
+    pub applied: VideoQualityConfig,
+}
+
+/// Downgrades each profile its camera can't capture, in place. Profiles for
+/// cameras that weren't detected are left alone.
+pub fn apply_camera_limits(recording: &mut RecordingConfig, cameras: &CameraCapabilities) -> Vec<Downgrade> {
+    let mut downgrades = Vec::new();
+    for profile in recording.available_qualities.iter_mut() {
+        let Some(device) = cameras.devices.iter().find(|device| device.device_path == profile.device_path) else {
+            continue;
+        };
+        if let Some(applied) = fit_to_camera(profile, device) {
+            downgrades.push(Downgrade { requested: profile.clone(), applied: applied.clone() });
+            *profile = applied;
+        }
+    }
+    downgrades
+}
+
+/// Formats, frame sizes and frame rates in `v4l2-ctl --list-formats-ext`
+/// output
+pub fn parse_formats_ext(output: &str) -> (Vec<String>, Vec<Resolution>, Vec<u32>) {
+    let mut formats = Vec::new();
+    let mut resolutions: Vec<Resolution> = Vec::new();
+    let mut frame_rates = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix('[') {
+            // [0]: 'MJPG' (Motion-JPEG, compressed)
+            if let Some(format) = rest.split('\'').nth(1) {
+                if !formats.iter().any(|f| f == format) {
+                    formats.push(format.to_string());
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("Size: Discrete") {
+            if let Some((width, height)) = parse_resolution(rest) {
+                if !resolutions.iter().any(|r| r.width == width && r.height == height) {
+                    resolutions.push(Resolution { width, height, aspect_ratio: aspect_ratio(width, height) });
+                }
+            }
+        } else if line.starts_with("Interval:") {
+            // Interval: Discrete 0.033s (30.000 fps)
+            let fps = line.split_once('(')
+                .and_then(|(_, rest)| rest.split_whitespace().next())
+                .and_then(|fps| fps.parse::<f64>().ok())
+                .map(|fps| fps.round() as u32);
+            if let Some(fps) = fps.filter(|fps| *fps > 0) {
+                if !frame_rates.contains(&fps) {
+                    frame_rates.push(fps);
+                }
+            }
+        }
+    }
+    frame_rates.sort_unstable();
+    (formats, resolutions, frame_rates)
+}
+
+fn aspect_ratio(width: u32, height: u32) -> String {
+    fn gcd(a: u32, b: u32) -> u32 {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    let divisor = gcd(width, height).max(1);
+    format!("{}:{}", width / divisor, height / divisor)
+}
+
+pub async fn detect_camera(device_path: &str) -> Result<CameraDevice> {
+    let output = Command::new("v4l2-ctl")
+        .args(["--device", device_path, "--list-formats-ext"])
+        .output()
+        .await
+        .context("Failed to run v4l2-ctl")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("v4l2-ctl failed for {}: {}", device_path, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let (formats, resolutions, frame_rates) = parse_formats_ext(&String::from_utf8_lossy(&output.stdout));
+
+    let node = Path::new(device_path).file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let name = tokio::fs::read_to_string(format!("/sys/class/video4linux/{}/name", node)).await
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| device_path.to_string());
+    Ok(CameraDevice {
+        name,
+        device_path: device_path.to_string(),
+        driver: String::new(),
+        resolutions,
+        frame_rates,
+        formats,
+        controls: Vec::new(),
+        is_available: true,
+    })
+}
+
+/// Detects the cameras the ladder uses. Nothing is detected in simulation,
+/// where the video source can produce any format.
+pub async fn detect_ladder_cameras(config: &Config) -> CameraCapabilities {
+    let mut devices: Vec<CameraDevice> = Vec::new();
+    if !config.simulation.enabled {
+        for profile in &config.recording.available_qualities {
+            if devices.iter().any(|device| device.device_path == profile.device_path) {
+                continue;
+            }
+            match detect_camera(&profile.device_path).await {
+                Ok(device) => devices.push(device),
+                Err(e) => tracing::warn!("Could not read capabilities of {}: {:#}", profile.device_path, e),
+            }
+        }
+    }
+    let default_device = devices.first().map(|device| device.device_path.clone());
+    CameraCapabilities { devices, default_device }
+}
+
+/// Result of recording one test clip
+#[derive(Debug, Clone)]
+pub struct TestClip {
+    pub quality: VideoQuality,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// What ffprobe found in the clip: width, height and frame rate
+    pub probed: Option<(u32, u32, f64)>,
+}
+
+impl TestClip {
+    /// Whether the clip came out at the profile's resolution
+    pub fn matches(&self, profile: &VideoQualityConfig) -> bool {
+        match (self.probed, parse_resolution(&profile.resolution)) {
+            (Some((width, height, _)), Some(requested)) => (width, height) == requested,
+            _ => false,
+        }
+    }
+}
+
+/// Records a short clip with the profile's settings
+pub async fn record_test_clip(config: &Config, profile: &VideoQualityConfig, output_dir: &Path) -> Result<TestClip> {
+    tokio::fs::create_dir_all(output_dir).await?;
+    let path = output_dir.join(format!("test_{}.mp4", quality_name(&profile.quality)));
+    let source = VideoSource::for_config(config, &profile.device_path);
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-y", "-loglevel", "error"]);
+    cmd.args(source.input_args(profile.fps, &profile.resolution));
+    cmd.args(source.output_args(profile.fps, &profile.resolution));
+    cmd.args(["-t", &TEST_CLIP_SECONDS.to_string()]);
+    cmd.args(["-b:v", &profile.bitrate.to_string()]);
+    cmd.args(["-c:v", &profile.codec]);
+    cmd.args(["-preset", "ultrafast", "-an", "-f", "mp4"]);
+    cmd.arg(&path);
+    cmd.kill_on_drop(true);
+
+    let output = tokio::time::timeout(TEST_CLIP_TIMEOUT, cmd.output()).await
+        .context("Test clip timed out")?
+        .context("Failed to run ffmpeg")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let size_bytes = tokio::fs::metadata(&path).await?.len();
+    let probed = probe_video(&path).await
+        .map_err(|e| tracing::warn!("Could not probe {}: {:#}", path.display(), e))
+        .ok()
+        .flatten();
+    Ok(TestClip { quality: profile.quality.clone(), path, size_bytes, probed })
+}
+
+async fn probe_video(path: &Path) -> Result<Option<(u32, u32, f64)>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=width,height,avg_frame_rate", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .await
+        .context("Failed to run ffprobe")?;
+    Ok(parse_probe(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `width,height,num/den` from ffprobe's csv output
+fn parse_probe(output: &str) -> Option<(u32, u32, f64)> {
+    let mut fields = output.trim().split(',');
+    let width = fields.next()?.parse().ok()?;
+    let height = fields.next()?.parse().ok()?;
+    let fps = match fields.next()?.split_once('/') {
+        Some((num, den)) => num.parse::<f64>().ok()? / den.parse::<f64>().ok().filter(|den| *den > 0.0)?,
+        None => fields.next().and_then(|fps| fps.parse().ok()).unwrap_or(0.0),
+    };
+    Some((width, height, fps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(quality: VideoQuality, resolution: &str, fps: u32, bitrate: u32) -> VideoQualityConfig {
+        VideoQualityConfig {
+            quality,
+            resolution: resolution.to_string(),
+            fps,
+            bitrate,
+            codec: "libx264".to_string(),
+            stream_index: 0,
+            device_path: "/dev/video0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_validate_and_downgrade() {
+        let output = "ioctl: VIDIOC_ENUM_FMT\n\
+            \tType: Video Capture\n\n\
+            \t[0]: 'YUYV' (YUYV 4:2:2)\n\
+            \t\tSize: Discrete 640x480\n\
+            \t\t\tInterval: Discrete 0.033s (30.000 fps)\n\
+            \t\t\tInterval: Discrete 0.067s (15.000 fps)\n\
+            \t[1]: 'MJPG' (Motion-JPEG, compressed)\n\
+            \t\tSize: Discrete 1280x720\n\
+            \t\t\tInterval: Discrete 0.033s (30.000 fps)\n\
+            \t\tSize: Discrete 640x480\n\
+            \t\t\tInterval: Discrete 0.033s (30.000 fps)\n";
+        let (formats, resolutions, frame_rates) = parse_formats_ext(output);
+        assert_eq!(formats, ["YUYV", "MJPG"]);
+        assert_eq!(resolutions.len(), 2);
+        assert_eq!(resolutions[1].aspect_ratio, "16:9");
+        assert_eq!(frame_rates, [15, 30]);
+
+        let cameras = CameraCapabilities {
+            devices: vec![CameraDevice {
+                name: "Test camera".to_string(),
+                device_path: "/dev/video0".to_string(),
+                driver: String::new(),
+                resolutions,
+                frame_rates,
+                formats,
+                controls: Vec::new(),
+                is_available: true,
+            }],
+            default_device: Some("/dev/video0".to_string()),
+        };
+        let mut recording = crate::config::Config::default().recording;
+        recording.default_quality = VideoQuality::High;
+        recording.available_qualities = vec![
+            profile(VideoQuality::Low, "640x480", 15, 500_000),
+            profile(VideoQuality::High, "1920x1080", 60, 4_000_000),
+        ];
+
+        let problems = validate_ladder(&recording, Some(&cameras));
+        assert_eq!(problems, [LadderProblem::Unsupported {
+            quality: VideoQuality::High,
+            resolution: "1920x1080".to_string(),
+            fps: 60,
+        }]);
+        assert!(!problems[0].is_error());
+
+        let downgrades = apply_camera_limits(&mut recording, &cameras);
+        assert_eq!(downgrades.len(), 1);
+        let high = &recording.available_qualities[1];
+        assert_eq!((high.resolution.as_str(), high.fps), ("1280x720", 30));
+        // Scaled down with the pixel rate
+        assert_eq!(high.bitrate, 888_889);
+        assert!(validate_ladder(&recording, Some(&cameras)).is_empty());
+
+        recording.default_quality = VideoQuality::Ultra;
+        recording.available_qualities.push(profile(VideoQuality::Medium, "320x240", 0, 300_000));
+        let problems = validate_ladder(&recording, None);
+        assert!(problems.contains(&LadderProblem::ZeroFps { quality: VideoQuality::Medium }));
+        assert!(problems.contains(&LadderProblem::MissingDefault { quality: VideoQuality::Ultra }));
+        assert!(problems.contains(&LadderProblem::OutOfOrder { quality: VideoQuality::Medium, below: VideoQuality::Low }));
+        assert_eq!(parse_probe("1280,720,30000/1001\n").map(|(w, h, fps)| (w, h, fps.round())), Some((1280, 720, 30.0)));
+    }
+}
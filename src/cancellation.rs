@@ -0,0 +1,129 @@
+//! Cancelling long operations: uploads, diagnostics and recording startup.
+//!
+//! The device hands each operation a token from its [`Canceller`]. Ctrl-C,
+//! the UI's cancel button and shutdown cancel every operation in flight,
+//! which then fails with [`DeviceError::Cancelled`].
+
+use anyhow::Result;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use crate::error_handling::DeviceError;
+
+/// Operations currently waiting in [`cancellable`], for the UI's cancel button
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Hands out tokens for long operations and cancels them. Cloned handles
+/// share state, so it can be used without holding the device lock.
+#[derive(Clone)]
+pub struct Canceller {
+    /// Cancelled at shutdown; nothing started afterwards runs
+    root: CancellationToken,
+    /// Cancelled by the user; replaced so later operations can run
+    current: Arc<Mutex<CancellationToken>>,
+}
+
+impl Canceller {
+    pub fn new() -> Self {
+        let root = CancellationToken::new();
+        let current = Arc::new(Mutex::new(root.child_token()));
+        Self { root, current }
+    }
+
+    /// A token for one operation
+    pub fn token(&self) -> CancellationToken {
+        self.current.lock().unwrap().child_token()
+    }
+
+    /// Cancel the operations in flight, e.g. from Ctrl-C or the UI
+    pub fn cancel(&self) {
+        let mut current = self.current.lock().unwrap();
+        current.cancel();
+        *current = self.root.child_token();
+    }
+
+    /// Cancel everything, now and from now on
+    pub fn shutdown(&self) {
+        self.root.cancel();
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.root.is_cancelled()
+    }
+}
+
+impl Default for Canceller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run `future` until it finishes or `token` is cancelled. Cancelling drops
+/// the future, so whatever it owns (requests, child processes) is released.
+pub async fn cancellable<T>(
+    token: &CancellationToken,
+    operation: &str,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    if token.is_cancelled() {
+        return Err(DeviceError::Cancelled { operation: operation.to_string() }.into());
+    }
+
+    RUNNING.fetch_add(1, Ordering::SeqCst);
+    let _running = RunningGuard;
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => {
+            tracing::info!("Cancelled {}", operation);
+            Err(DeviceError::Cancelled { operation: operation.to_string() }.into())
+        }
+        result = future => result,
+    }
+}
+
+/// Whether `error` came from a cancelled operation
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| matches!(cause.downcast_ref::<DeviceError>(), Some(DeviceError::Cancelled { .. })))
+}
+
+/// Number of cancellable operations running
+pub fn running() -> usize {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_interrupts_only_running_operations() {
+        let canceller = Canceller::new();
+        let token = canceller.token();
+        let pending = tokio::spawn(async move {
+            cancellable(&token, "upload", async {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                Ok(())
+            }).await
+        });
+        tokio::task::yield_now().await;
+
+        canceller.cancel();
+        let error = pending.await.unwrap().unwrap_err();
+        assert!(is_cancelled(&error.context("Segment not uploaded")));
+
+        // A later operation isn't affected, until shutdown
+        assert_eq!(cancellable(&canceller.token(), "upload", async { Ok(1) }).await.unwrap(), 1);
+        canceller.shutdown();
+        assert!(cancellable(&canceller.token(), "upload", async { Ok(1) }).await.is_err());
+    }
+}
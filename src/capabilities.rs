@@ -333,6 +333,36 @@ pub struct PowerManagementInfo {
     pub wake_sources: Vec<String>,
 }
 
+impl PowerManagementInfo {
+    /// What the kernel offers for sleeping and waking. Buttons, motion and
+    /// the charger wake the system through their interrupts; the schedule
+    /// needs an RTC with a wake alarm.
+    pub async fn detect(simulation: bool) -> Self {
+        let mut wake_sources: Vec<String> = ["button", "motion", "charger"].iter().map(|s| s.to_string()).collect();
+        if simulation {
+            wake_sources.push("rtc".to_string());
+            return Self {
+                cpu_scaling_available: false,
+                sleep_modes: vec!["freeze".to_string(), "mem".to_string()],
+                wake_sources,
+            };
+        }
+
+        let sleep_modes = tokio::fs::read_to_string("/sys/power/state").await
+            .map(|modes| modes.split_whitespace().map(|mode| mode.to_string()).collect())
+            .unwrap_or_default();
+        if std::path::Path::new("/sys/class/rtc/rtc0/wakealarm").exists() {
+            wake_sources.push("rtc".to_string());
+        }
+
+        Self {
+            cpu_scaling_available: std::path::Path::new("/sys/devices/system/cpu/cpu0/cpufreq").exists(),
+            sleep_modes,
+            wake_sources,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UICapabilities {
     pub display: Option<DisplayInfo>,
@@ -0,0 +1,189 @@
+use crate::config::Config;
+
+/// Where ffmpeg reads video from. On hardware this is the V4L2 camera; in
+/// simulation a media file looped in real time stands in for it, so the
+/// encode, encryption, integrity and upload paths see realistic data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VideoSource {
+    Camera { device_path: String },
+    File { path: String },
+    /// Generated pattern, for simulation without a media file
+    TestPattern,
+    /// Raw RGB frames written to stdin from a camera already being captured
+    /// for the preview (see `camera::frame_pool::feed_encoder`)
+    Frames { width: u32, height: u32, fps: u32 },
+}
+
+impl VideoSource {
+    pub fn for_config(config: &Config, device_path: &str) -> Self {
+        if !config.simulation.enabled {
+            return Self::Camera { device_path: device_path.to_string() };
+        }
+        match &config.simulation.video_source {
+            Some(path) => Self::File { path: path.clone() },
+            None => Self::TestPattern,
+        }
+    }
+
+    /// Simulation without a media file; recording writes placeholder files
+    /// rather than encoding a test pattern
+    pub fn is_placeholder(&self) -> bool {
+        *self == Self::TestPattern
+    }
+
+    /// Input options, ending with `-i`
+    pub fn input_args(&self, fps: u32, resolution: &str) -> Vec<String> {
+        match self {
+            Self::Camera { device_path } => vec![
+                "-f".into(), "v4l2".into(),
+                "-framerate".into(), fps.to_string(),
+                "-video_size".into(), resolution.to_string(),
+                "-i".into(), device_path.clone(),
+            ],
+            Self::File { path } => vec![
+                "-re".into(),
+                "-stream_loop".into(), "-1".into(),
+                "-i".into(), path.clone(),
+            ],
+            Self::TestPattern => vec![
+                "-f".into(), "lavfi".into(),
+                "-i".into(), format!("testsrc2=size={}:rate={}", resolution, fps),
+            ],
+            // Stamped on arrival, so frames skipped under load don't speed
+            // the recording up
+            Self::Frames { width, height, fps } => vec![
+                "-f".into(), "rawvideo".into(),
+                "-pix_fmt".into(), "rgb24".into(),
+                "-video_size".into(), format!("{}x{}", width, height),
+                "-framerate".into(), fps.to_string(),
+                "-use_wallclock_as_timestamps".into(), "1".into(),
+                "-i".into(), "pipe:0".into(),
+            ],
+        }
+    }
+
+    /// Output options that bring the source to the requested format; a
+    /// camera opened by ffmpeg is already capturing at it
+    pub fn output_args(&self, fps: u32, resolution: &str) -> Vec<String> {
+        match self {
+            Self::File { .. } | Self::Frames { .. } => vec![
+                "-s".into(), resolution.to_string(),
+                "-r".into(), fps.to_string(),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Where ffmpeg reads audio from; the microphone on hardware, a looped file
+/// or a tone in simulation
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioSource {
+    Microphone { device: String },
+    File { path: String },
+    Tone,
+}
+
+impl AudioSource {
+    /// `device` is the ALSA device to use on hardware
+    pub fn for_config(config: &Config, device: &str) -> Self {
+        if !config.simulation.enabled {
+            return Self::Microphone { device: device.to_string() };
+        }
+        match &config.simulation.audio_source {
+            Some(path) => Self::File { path: path.clone() },
+            None => Self::Tone,
+        }
+    }
+
+    /// Input options, ending with `-i`
+    pub fn input_args(&self) -> Vec<String> {
+        match self {
+            Self::Microphone { device } => vec![
+                "-f".into(), "alsa".into(),
+                "-i".into(), device.clone(),
+            ],
+            Self::File { path } => vec![
+                "-re".into(),
+                "-stream_loop".into(), "-1".into(),
+                "-i".into(), path.clone(),
+            ],
+            Self::Tone => vec![
+                "-f".into(), "lavfi".into(),
+                "-i".into(), "sine=frequency=1000:sample_rate=48000".into(),
+            ],
+        }
+    }
+}
+
+/// Resolution and frame rate to capture at so every rendition can be scaled
+/// down from it: the largest resolution and the highest frame rate
+pub fn capture_format(renditions: &[(&str, u32)]) -> Option<(String, u32)> {
+    let resolution = renditions.iter()
+        .max_by_key(|(resolution, _)| {
+            crate::camera::quality_ladder::parse_resolution(resolution)
+                .map(|(width, height)| width as u64 * height as u64)
+                .unwrap_or(0)
+        })?
+        .0;
+    let fps = renditions.iter().map(|(_, fps)| *fps).max()?;
+    Some((resolution.to_string(), fps))
+}
+
+/// `-filter_complex` graph that splits the first input's video into one
+/// copy per rendition, scaled to its resolution and frame rate and labelled
+/// `[v0]`, `[v1]`... in order
+pub fn split_filter(renditions: &[(&str, u32)]) -> String {
+    let labels: String = (0..renditions.len()).map(|index| format!("[s{}]", index)).collect();
+    let mut graph = format!("[0:v]split={}{}", renditions.len(), labels);
+    for (index, (resolution, fps)) in renditions.iter().enumerate() {
+        graph.push_str(&format!(";[s{}]scale={},fps={}[v{}]", index, resolution.replace(['x', 'X'], ":"), fps, index));
+    }
+    graph
+}
+
+/// Pick video from the first input and audio from the second, so a media
+/// file's own soundtrack doesn't win over the configured audio source
+pub fn map_video_and_audio_args() -> Vec<String> {
+    vec!["-map".into(), "0:v:0".into(), "-map".into(), "1:a:0".into()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_follow_simulation_config() {
+        let mut config = Config::default();
+        assert_eq!(VideoSource::for_config(&config, "/dev/video0"),
+            VideoSource::Camera { device_path: "/dev/video0".to_string() });
+        assert_eq!(AudioSource::for_config(&config, "default"),
+            AudioSource::Microphone { device: "default".to_string() });
+
+        config.simulation.enabled = true;
+        assert!(VideoSource::for_config(&config, "/dev/video0").is_placeholder());
+        assert_eq!(AudioSource::for_config(&config, "default"), AudioSource::Tone);
+
+        config.simulation.video_source = Some("fixtures/patrol.mp4".to_string());
+        config.simulation.audio_source = Some("fixtures/patrol.wav".to_string());
+        let video = VideoSource::for_config(&config, "/dev/video0");
+        assert_eq!(video.input_args(30, "1280x720"),
+            ["-re", "-stream_loop", "-1", "-i", "fixtures/patrol.mp4"]);
+        assert_eq!(video.output_args(30, "1280x720"), ["-s", "1280x720", "-r", "30"]);
+        assert_eq!(AudioSource::for_config(&config, "default").input_args(),
+            ["-re", "-stream_loop", "-1", "-i", "fixtures/patrol.wav"]);
+
+        let frames = VideoSource::Frames { width: 1280, height: 720, fps: 30 };
+        assert_eq!(frames.input_args(30, "1280x720").last().map(String::as_str), Some("pipe:0"));
+        assert!(!frames.is_placeholder());
+    }
+
+    #[test]
+    fn test_split_one_capture_into_renditions() {
+        let renditions = [("640x480", 15), ("1920x1080", 30)];
+        assert_eq!(capture_format(&renditions), Some(("1920x1080".to_string(), 30)));
+        assert_eq!(capture_format(&[]), None);
+        assert_eq!(split_filter(&renditions),
+            "[0:v]split=2[s0][s1];[s0]scale=640:480,fps=15[v0];[s1]scale=1920:1080,fps=30[v1]");
+    }
+}
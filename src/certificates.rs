@@ -0,0 +1,315 @@
+//! The device's client certificate. Enrollment works like EST: the device
+//! generates its own key, sends the platform a signing request, and stores
+//! the certificate that comes back. It asks again well before the
+//! certificate expires, and raises alerts as the expiry gets closer.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::CertificateStatus;
+
+const CERTIFICATE_FILE: &str = "device.crt";
+const KEY_FILE: &str = "device.key";
+const CHAIN_FILE: &str = "chain.pem";
+const STATE_FILE: &str = "state.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateConfig {
+    pub enabled: bool,
+    /// Ask for a new certificate this many days before the current one expires
+    pub renew_before_days: u32,
+    /// Days before expiry at which to raise an alert, e.g. `[14, 7, 1]`
+    pub alert_days: Vec<u32>,
+    /// How often expiry is checked and a due renewal attempted
+    pub check_interval_hours: u64,
+}
+
+impl Default for CertificateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            renew_before_days: 30,
+            alert_days: vec![14, 7, 1],
+            check_interval_hours: 6,
+        }
+    }
+}
+
+/// What the platform is asked to sign
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateRequest {
+    pub csr_pem: String,
+    /// Serial of the certificate being replaced; None on first enrollment
+    pub renewing: Option<String>,
+}
+
+/// What the platform signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedCertificate {
+    pub certificate_pem: String,
+    /// Intermediate certificates up to the platform's root
+    #[serde(default)]
+    pub chain_pem: Option<String>,
+}
+
+/// The parts of the installed certificate the device acts on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// SHA-256 of the DER encoding, hex
+    pub fingerprint: String,
+}
+
+impl CertificateInfo {
+    pub fn parse(pem: &str) -> Result<Self> {
+        let (_, pem) = x509_parser::pem::parse_x509_pem(pem.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Not a PEM certificate: {}", e))?;
+        let certificate = pem.parse_x509()
+            .map_err(|e| anyhow::anyhow!("Unreadable certificate: {}", e))?;
+        let validity = certificate.validity();
+        Ok(Self {
+            subject: certificate.subject().to_string(),
+            issuer: certificate.issuer().to_string(),
+            serial: certificate.raw_serial_as_string(),
+            not_before: DateTime::from_timestamp(validity.not_before.timestamp(), 0)
+                .context("Certificate start date out of range")?,
+            not_after: DateTime::from_timestamp(validity.not_after.timestamp(), 0)
+                .context("Certificate expiry out of range")?,
+            fingerprint: hex::encode(Sha256::digest(&pem.contents)),
+        })
+    }
+
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.not_before <= now && now < self.not_after
+    }
+
+    /// Whole days until expiry; negative once expired
+    pub fn days_left(&self, now: DateTime<Utc>) -> i64 {
+        (self.not_after - now).num_days()
+    }
+}
+
+/// The subject public key of a PEM certificate, DER
+fn public_key(pem: &str) -> Result<Vec<u8>> {
+    let (_, pem) = x509_parser::pem::parse_x509_pem(pem.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Not a PEM certificate: {}", e))?;
+    let certificate = pem.parse_x509()
+        .map_err(|e| anyhow::anyhow!("Unreadable certificate: {}", e))?;
+    Ok(certificate.public_key().raw.to_vec())
+}
+
+/// A fresh key and a signing request for it
+pub fn generate_request(device_id: &str) -> Result<(String, zeroize::Zeroizing<String>)> {
+    let key_pair = rcgen::KeyPair::generate().context("Failed to generate certificate key")?;
+    let mut params = rcgen::CertificateParams::new(Vec::<String>::new())?;
+    params.distinguished_name.push(rcgen::DnType::CommonName, device_id);
+    let csr = params.serialize_request(&key_pair).context("Failed to create signing request")?;
+    Ok((csr.pem()?, zeroize::Zeroizing::new(key_pair.serialize_pem())))
+}
+
+/// Renewal bookkeeping kept next to the certificate
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RenewalState {
+    checked_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    /// Serial of the certificate alerts were raised for, and the smallest
+    /// threshold alerted
+    alerted: Option<(String, u32)>,
+}
+
+/// The installed certificate and its renewal state, under `data/certs`
+#[derive(Debug)]
+pub struct CertificateStore {
+    config: CertificateConfig,
+    dir: PathBuf,
+    certificate: Option<CertificateInfo>,
+    state: RenewalState,
+}
+
+impl CertificateStore {
+    pub fn default_dir() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("certs")
+    }
+
+    pub async fn load(config: CertificateConfig) -> Self {
+        Self::load_from(config, Self::default_dir()).await
+    }
+
+    async fn load_from(config: CertificateConfig, dir: PathBuf) -> Self {
+        let certificate = match tokio::fs::read_to_string(dir.join(CERTIFICATE_FILE)).await {
+            Ok(pem) => CertificateInfo::parse(&pem)
+                .map_err(|e| tracing::warn!("Ignoring stored device certificate: {:#}", e))
+                .ok(),
+            Err(_) => None,
+        };
+        let state = tokio::fs::read_to_string(dir.join(STATE_FILE)).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { config, dir, certificate, state }
+    }
+
+    async fn save_state(&self) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.dir.join(STATE_FILE), serde_json::to_string_pretty(&self.state)?).await?;
+        Ok(())
+    }
+
+    pub fn certificate(&self) -> Option<&CertificateInfo> {
+        self.certificate.as_ref()
+    }
+
+    /// Whether a check is due; with no certificate yet, this is enrollment
+    pub fn check_due(&self, now: DateTime<Utc>) -> bool {
+        self.config.enabled && self.state.checked_at
+            .map_or(true, |checked_at| now - checked_at >= Duration::hours(self.config.check_interval_hours as i64))
+    }
+
+    /// When the certificate should be replaced
+    pub fn next_renewal(&self) -> Option<DateTime<Utc>> {
+        self.certificate.as_ref()
+            .map(|certificate| certificate.not_after - Duration::days(self.config.renew_before_days as i64))
+    }
+
+    pub fn renewal_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_renewal().map_or(true, |renew_at| now >= renew_at)
+    }
+
+    /// The request to send, with the key to keep until the answer comes
+    pub fn request(&self, device_id: &str) -> Result<(CertificateRequest, zeroize::Zeroizing<String>)> {
+        let (csr_pem, key_pem) = generate_request(device_id)?;
+        let renewing = self.certificate.as_ref().map(|certificate| certificate.serial.clone());
+        Ok((CertificateRequest { csr_pem, renewing }, key_pem))
+    }
+
+    /// Store a signed certificate with the key its request was made for.
+    /// The previous certificate stays in place if anything doesn't match.
+    pub async fn install(&mut self, issued: &IssuedCertificate, key_pem: &str, now: DateTime<Utc>) -> Result<&CertificateInfo> {
+        let info = CertificateInfo::parse(&issued.certificate_pem)?;
+        let key_pair = rcgen::KeyPair::from_pem(key_pem).context("Unreadable certificate key")?;
+        if public_key(&issued.certificate_pem)? != key_pair.public_key_der() {
+            return Err(anyhow::anyhow!("Issued certificate is not for the requested key"));
+        }
+        if !info.is_valid(now) {
+            return Err(anyhow::anyhow!("Issued certificate is only valid from {} to {}", info.not_before, info.not_after));
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        write_private(&self.dir.join(KEY_FILE), key_pem).await?;
+        write_replacing(&self.dir.join(CERTIFICATE_FILE), &issued.certificate_pem).await?;
+        match &issued.chain_pem {
+            Some(chain) => write_replacing(&self.dir.join(CHAIN_FILE), chain).await?,
+            None => { let _ = tokio::fs::remove_file(self.dir.join(CHAIN_FILE)).await; }
+        }
+
+        self.state.last_error = None;
+        self.state.alerted = None;
+        self.save_state().await?;
+        Ok(self.certificate.insert(info))
+    }
+
+    /// Note a finished check, and why renewal failed if it did
+    pub async fn checked(&mut self, now: DateTime<Utc>, error: Option<String>) -> Result<()> {
+        self.state.checked_at = Some(now);
+        self.state.last_error = error;
+        self.save_state().await
+    }
+
+    /// The alert threshold the certificate has just reached, if it hasn't
+    /// been alerted already
+    pub async fn new_alert(&mut self, now: DateTime<Utc>) -> Option<u32> {
+        let certificate = self.certificate.as_ref()?;
+        let days_left = certificate.days_left(now);
+        let reached = self.config.alert_days.iter().copied()
+            .filter(|days| days_left < *days as i64)
+            .min()?;
+        if let Some((serial, alerted)) = &self.state.alerted {
+            if *serial == certificate.serial && *alerted <= reached {
+                return None;
+            }
+        }
+        self.state.alerted = Some((certificate.serial.clone(), reached));
+        if let Err(e) = self.save_state().await {
+            tracing::warn!("Failed to save certificate alert state: {}", e);
+        }
+        Some(reached)
+    }
+
+    /// Real dates for the diagnostics report
+    pub fn status(&self, now: DateTime<Utc>) -> CertificateStatus {
+        CertificateStatus {
+            certificates_valid: self.certificate.as_ref().is_some_and(|certificate| certificate.is_valid(now)),
+            expiry_dates: self.certificate.iter().map(|certificate| certificate.not_after).collect(),
+            next_renewal: self.next_renewal(),
+            subject: self.certificate.as_ref().map(|certificate| certificate.subject.clone()),
+            issuer: self.certificate.as_ref().map(|certificate| certificate.issuer.clone()),
+            last_checked: self.state.checked_at,
+            last_error: self.state.last_error.clone(),
+        }
+    }
+}
+
+/// Write through a temporary file so a crash never leaves half a certificate
+async fn write_replacing(path: &Path, content: &str) -> Result<()> {
+    let temp = path.with_extension("tmp");
+    tokio::fs::write(&temp, content).await?;
+    tokio::fs::rename(&temp, path).await
+        .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+async fn write_private(path: &Path, content: &str) -> Result<()> {
+    let temp = path.with_extension("tmp");
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&temp).await?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, content.as_bytes()).await?;
+    file.sync_all().await?;
+    tokio::fs::rename(&temp, path).await
+        .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_install_checks_key_and_tracks_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CertificateConfig { alert_days: vec![14, 7, 1], renew_before_days: 30, ..CertificateConfig::default() };
+        let mut store = CertificateStore::load_from(config.clone(), dir.path().to_path_buf()).await;
+        let now = Utc::now();
+        assert!(store.check_due(now) && store.renewal_due(now));
+
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(vec!["dev-1".to_string()]).unwrap();
+        params.distinguished_name.push(rcgen::DnType::CommonName, "dev-1");
+        let issued = IssuedCertificate { certificate_pem: params.self_signed(&key_pair).unwrap().pem(), chain_pem: None };
+
+        let other_key = rcgen::KeyPair::generate().unwrap();
+        assert!(store.install(&issued, &other_key.serialize_pem(), now).await.is_err());
+        assert!(store.certificate().is_none());
+
+        let not_after = store.install(&issued, &key_pair.serialize_pem(), now).await.unwrap().not_after;
+        let reloaded = CertificateStore::load_from(config, dir.path().to_path_buf()).await;
+        assert_eq!(reloaded.certificate().unwrap().not_after, not_after);
+        assert!(reloaded.status(now).certificates_valid);
+        assert!(!store.renewal_due(now));
+        assert!(store.renewal_due(not_after - Duration::days(29)));
+
+        assert_eq!(store.new_alert(not_after - Duration::days(20)).await, None);
+        assert_eq!(store.new_alert(not_after - Duration::days(10)).await, Some(14));
+        assert_eq!(store.new_alert(not_after - Duration::days(9)).await, None);
+        assert_eq!(store.new_alert(not_after - Duration::hours(12)).await, Some(1));
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// State of one endpoint's breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Too many failures; requests fail fast until the cool-down ends
+    Open,
+    /// Cool-down over; one trial request decides whether to close again
+    HalfOpen,
+}
+
+/// When breakers open and how long they stay open
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakerSettings {
+    /// Consecutive failures that open the breaker
+    pub failure_threshold: u32,
+    pub open_for: Duration,
+}
+
+/// A breaker's state as reported in network status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndpointCircuit {
+    pub endpoint: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until an open breaker lets a trial request through
+    pub retry_in_seconds: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    open_for: Duration,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            open_for: Duration::ZERO,
+        }
+    }
+}
+
+/// Per-endpoint circuit breakers. While the server is down they turn every
+/// status tick into one fast local failure instead of a full retry storm.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    breakers: Mutex<HashMap<String, Breaker>>,
+}
+
+impl CircuitBreakers {
+    /// Shared by every `ApiClient`, which are created per call in places
+    pub fn global() -> &'static CircuitBreakers {
+        static GLOBAL: OnceLock<CircuitBreakers> = OnceLock::new();
+        GLOBAL.get_or_init(CircuitBreakers::default)
+    }
+
+    /// A permit for a request to `endpoint`, or None while its breaker is
+    /// open. Moves an open breaker whose cool-down has ended to half-open
+    /// and admits one trial request.
+    pub fn try_acquire(&self, endpoint: &str) -> Option<CircuitPermit<'_>> {
+        self.try_acquire_at(endpoint, Instant::now())
+    }
+
+    fn try_acquire_at(&self, endpoint: &str, now: Instant) -> Option<CircuitPermit<'_>> {
+        let mut breakers = self.breakers.lock().unwrap();
+        let trial = match breakers.get_mut(endpoint) {
+            None => false,
+            Some(breaker) => match breaker.state {
+                CircuitState::Closed => false,
+                // The trial request is already in flight
+                CircuitState::HalfOpen => return None,
+                CircuitState::Open => {
+                    let cooled = breaker.opened_at.map_or(true, |at| now.duration_since(at) >= breaker.open_for);
+                    if !cooled {
+                        return None;
+                    }
+                    breaker.state = CircuitState::HalfOpen;
+                    true
+                }
+            },
+        };
+        Some(CircuitPermit { breakers: self, endpoint: endpoint.to_string(), trial, settled: false })
+    }
+
+    /// A half-open trial ended without an outcome: open again for another
+    /// cool-down rather than refuse every request while waiting for it
+    fn abandon_trial_at(&self, endpoint: &str, now: Instant) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get_mut(endpoint) {
+            if breaker.state == CircuitState::HalfOpen {
+                breaker.state = CircuitState::Open;
+                breaker.opened_at = Some(now);
+            }
+        }
+    }
+
+    pub fn record_success(&self, endpoint: &str) {
+        if let Some(breaker) = self.breakers.lock().unwrap().get_mut(endpoint) {
+            if breaker.state != CircuitState::Closed {
+                tracing::info!("Circuit for {} closed", endpoint);
+            }
+            *breaker = Breaker::default();
+        }
+    }
+
+    pub fn record_failure(&self, endpoint: &str, settings: &BreakerSettings) {
+        self.record_failure_at(endpoint, settings, Instant::now());
+    }
+
+    fn record_failure_at(&self, endpoint: &str, settings: &BreakerSettings, now: Instant) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let breaker = breakers.entry(endpoint.to_string()).or_default();
+        breaker.consecutive_failures += 1;
+
+        let trip = breaker.state == CircuitState::HalfOpen
+            || breaker.consecutive_failures >= settings.failure_threshold.max(1);
+        if trip {
+            if breaker.state != CircuitState::Open {
+                tracing::warn!("Circuit for {} opened after {} failures, pausing for {:?}",
+                    endpoint, breaker.consecutive_failures, settings.open_for);
+            }
+            breaker.state = CircuitState::Open;
+            breaker.opened_at = Some(now);
+            breaker.open_for = settings.open_for;
+        }
+    }
+
+    /// Breakers that aren't closed
+    pub fn snapshot(&self) -> Vec<EndpointCircuit> {
+        let now = Instant::now();
+        let mut circuits: Vec<EndpointCircuit> = self.breakers.lock().unwrap()
+            .iter()
+            .filter(|(_, breaker)| breaker.state != CircuitState::Closed)
+            .map(|(endpoint, breaker)| EndpointCircuit {
+                endpoint: endpoint.clone(),
+                state: breaker.state,
+                consecutive_failures: breaker.consecutive_failures,
+                retry_in_seconds: breaker.opened_at
+                    .filter(|_| breaker.state == CircuitState::Open)
+                    .map(|at| breaker.open_for.saturating_sub(now.duration_since(at)).as_secs()),
+            })
+            .collect();
+        circuits.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        circuits
+    }
+}
+
+/// One admitted request. Report how it went with `succeeded` or `failed`.
+/// Dropping the permit without either, e.g. when the request is cancelled,
+/// counts as a failed trial if the breaker was half-open, so the breaker
+/// can't be left half-open with no trial in flight. A cancelled request on a
+/// closed breaker says nothing about the server and isn't counted.
+#[must_use]
+pub struct CircuitPermit<'a> {
+    breakers: &'a CircuitBreakers,
+    endpoint: String,
+    trial: bool,
+    settled: bool,
+}
+
+impl CircuitPermit<'_> {
+    pub fn succeeded(mut self) {
+        self.settled = true;
+        self.breakers.record_success(&self.endpoint);
+    }
+
+    pub fn failed(mut self, settings: &BreakerSettings) {
+        self.settled = true;
+        self.breakers.record_failure(&self.endpoint, settings);
+    }
+}
+
+impl Drop for CircuitPermit<'_> {
+    fn drop(&mut self) {
+        if self.trial && !self.settled {
+            tracing::warn!("Trial request to {} was abandoned, keeping the circuit open", self.endpoint);
+            self.breakers.abandon_trial_at(&self.endpoint, Instant::now());
+        }
+    }
+}
+
+/// The endpoint a URL belongs to: host and path with ids replaced by `*`, so
+/// `/api/devices/dev-42/status` and `/api/devices/dev-7/status` share a breaker
+pub fn endpoint_key(url: &str) -> String {
+    let path = url::Url::parse(url)
+        .map(|parsed| format!("{}{}", parsed.host_str().unwrap_or_default(), parsed.path()))
+        .unwrap_or_else(|_| url.to_string());
+
+    path.split('/')
+        .map(|segment| if segment.chars().any(|c| c.is_ascii_digit()) { "*" } else { segment })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: BreakerSettings = BreakerSettings {
+        failure_threshold: 3,
+        open_for: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn test_opens_after_threshold_and_half_opens_after_cooldown() {
+        let breakers = CircuitBreakers::default();
+        let start = Instant::now();
+
+        for _ in 0..2 {
+            breakers.record_failure_at("/api/status", &SETTINGS, start);
+        }
+        assert!(breakers.try_acquire_at("/api/status", start).is_some());
+
+        breakers.record_failure_at("/api/status", &SETTINGS, start);
+        assert!(breakers.try_acquire_at("/api/status", start + Duration::from_secs(30)).is_none());
+        assert!(breakers.try_acquire_at("/api/other", start).is_some());
+
+        // One trial request after the cool-down, then fail fast again
+        let later = start + Duration::from_secs(61);
+        let trial = breakers.try_acquire_at("/api/status", later).unwrap();
+        assert!(breakers.try_acquire_at("/api/status", later).is_none());
+        assert_eq!(breakers.snapshot()[0].state, CircuitState::HalfOpen);
+
+        trial.succeeded();
+        assert!(breakers.try_acquire_at("/api/status", later).is_some());
+        assert!(breakers.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_failed_trial_reopens() {
+        let breakers = CircuitBreakers::default();
+        let start = Instant::now();
+        for _ in 0..3 {
+            breakers.record_failure_at("/api/status", &SETTINGS, start);
+        }
+
+        let later = start + Duration::from_secs(61);
+        let _trial = breakers.try_acquire_at("/api/status", later).unwrap();
+        breakers.record_failure_at("/api/status", &SETTINGS, later);
+        assert!(breakers.try_acquire_at("/api/status", later + Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn test_dropped_trial_reopens() {
+        let breakers = CircuitBreakers::default();
+        let start = Instant::now();
+        for _ in 0..3 {
+            breakers.record_failure_at("/api/status", &SETTINGS, start);
+        }
+
+        let trial = breakers.try_acquire_at("/api/status", start + Duration::from_secs(61));
+        assert!(trial.is_some());
+        drop(trial);
+        assert_eq!(breakers.snapshot()[0].state, CircuitState::Open);
+        assert!(breakers.try_acquire_at("/api/status", Instant::now() + Duration::from_secs(61)).is_some());
+    }
+
+    #[test]
+    fn test_endpoint_key_groups_ids() {
+        assert_eq!(
+            endpoint_key("https://api.example.com/api/devices/dev-42/status"),
+            endpoint_key("https://api.example.com/api/devices/dev-7/status"),
+        );
+        assert_eq!(endpoint_key("https://api.example.com/api/incidents"), "api.example.com/api/incidents");
+    }
+}
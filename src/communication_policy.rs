@@ -0,0 +1,415 @@
+//! The platform's number allowlist and usage limits, enforced on the device
+//! as well as the server. A leaked backend token can then only make the
+//! device text or call numbers the tenant approved, and only so often.
+//! Usage is counted locally, and quotas close to their limit are reported.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::api::{DeviceCommunicationCapabilities, NumberWhitelistEntry, PlivoNumber};
+use crate::communications::{normalize_number, CommunicationKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommunicationPolicyConfig {
+    pub enforce: bool,
+    /// How often the allowlist and limits are downloaded again
+    pub refresh_minutes: u64,
+    /// Daily limits used until the platform's have been downloaded
+    pub default_daily_sms_limit: u32,
+    pub default_daily_call_limit: u32,
+    /// What a message and a call are assumed to cost, for the monthly
+    /// limit between downloads of the platform's figure
+    pub estimated_sms_cost: f64,
+    pub estimated_call_cost: f64,
+    /// Quotas this far used are reported
+    pub warn_at_percent: u8,
+}
+
+impl Default for CommunicationPolicyConfig {
+    fn default() -> Self {
+        Self {
+            enforce: true,
+            refresh_minutes: 60,
+            default_daily_sms_limit: 50,
+            default_daily_call_limit: 20,
+            estimated_sms_cost: 0.01,
+            estimated_call_cost: 0.05,
+            warn_at_percent: 80,
+        }
+    }
+}
+
+/// The platform's rules for this device's number, as last downloaded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicySnapshot {
+    pub fetched_at: DateTime<Utc>,
+    pub sms_enabled: bool,
+    pub voice_enabled: bool,
+    /// Only numbers on the allowlist may be reached
+    pub allowlist_enforced: bool,
+    pub allowlist: Vec<NumberWhitelistEntry>,
+    pub daily_sms_limit: Option<u32>,
+    pub daily_call_limit: Option<u32>,
+    pub emergency_bypass_limits: bool,
+    pub emergency_contacts_only: bool,
+    #[serde(default)]
+    pub monthly_cost_limit: Option<f64>,
+    /// The platform's counts for this month when downloaded
+    #[serde(default)]
+    pub month_sms: u32,
+    #[serde(default)]
+    pub month_calls: u32,
+    #[serde(default)]
+    pub month_cost: f64,
+}
+
+impl PolicySnapshot {
+    pub fn new(number: &PlivoNumber, capabilities: &DeviceCommunicationCapabilities, allowlist: Vec<NumberWhitelistEntry>) -> Self {
+        Self {
+            fetched_at: Utc::now(),
+            sms_enabled: number.sms_enabled && capabilities.sms_enabled,
+            voice_enabled: number.voice_enabled && capabilities.voice_enabled,
+            allowlist_enforced: number.whitelist_mode == "enabled",
+            allowlist: allowlist.into_iter().filter(|entry| entry.is_active).collect(),
+            daily_sms_limit: capabilities.daily_sms_limit,
+            daily_call_limit: capabilities.daily_call_limit,
+            emergency_bypass_limits: capabilities.emergency_bypass_limits,
+            emergency_contacts_only: capabilities.emergency_contacts_only,
+            monthly_cost_limit: capabilities.monthly_usage_limit,
+            month_sms: capabilities.current_month_sms,
+            month_calls: capabilities.current_month_calls,
+            month_cost: capabilities.current_month_cost,
+        }
+    }
+}
+
+/// Messages and calls placed on one local day
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DailyUsage {
+    date: Option<NaiveDate>,
+    sms: u32,
+    calls: u32,
+}
+
+impl DailyUsage {
+    fn count(&mut self, kind: CommunicationKind, today: NaiveDate) -> &mut u32 {
+        if self.date != Some(today) {
+            *self = DailyUsage { date: Some(today), ..DailyUsage::default() };
+        }
+        match kind {
+            CommunicationKind::Sms => &mut self.sms,
+            CommunicationKind::Call => &mut self.calls,
+        }
+    }
+}
+
+/// Messages, calls and their cost in one calendar month
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MonthlyUsage {
+    /// e.g. "2026-05"
+    month: Option<String>,
+    sms: u32,
+    calls: u32,
+    cost: f64,
+}
+
+impl MonthlyUsage {
+    fn current(&mut self, today: NaiveDate) -> &mut Self {
+        let month = today.format("%Y-%m").to_string();
+        if self.month.as_deref() != Some(month.as_str()) {
+            *self = MonthlyUsage { month: Some(month), ..MonthlyUsage::default() };
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quota {
+    DailySms,
+    DailyCalls,
+    MonthlyCost,
+}
+
+impl Quota {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Quota::DailySms => "daily_sms",
+            Quota::DailyCalls => "daily_calls",
+            Quota::MonthlyCost => "monthly_cost",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct QuotaUsage {
+    pub quota: Quota,
+    pub used: f64,
+    pub limit: f64,
+}
+
+impl QuotaUsage {
+    pub fn percent(&self) -> f64 {
+        if self.limit <= 0.0 { 100.0 } else { self.used / self.limit * 100.0 }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PolicyState {
+    policy: Option<PolicySnapshot>,
+    usage: DailyUsage,
+    #[serde(default)]
+    monthly: MonthlyUsage,
+}
+
+/// Checks each message and call against the cached rules and counts it
+#[derive(Debug)]
+pub struct CommunicationGuard {
+    config: CommunicationPolicyConfig,
+    path: PathBuf,
+    state: PolicyState,
+    /// Quotas already reported as nearly used up
+    warned: Vec<Quota>,
+}
+
+impl CommunicationGuard {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("communication_policy.json")
+    }
+
+    pub async fn load(config: CommunicationPolicyConfig) -> Self {
+        let path = Self::default_path();
+        let state = tokio::fs::read_to_string(&path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { config, path, state, warned: Vec::new() }
+    }
+
+    async fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_string_pretty(&self.state)?).await?;
+        Ok(())
+    }
+
+    pub fn refresh_due(&self, now: DateTime<Utc>) -> bool {
+        self.state.policy.as_ref()
+            .map_or(true, |policy| now - policy.fetched_at >= Duration::minutes(self.config.refresh_minutes as i64))
+    }
+
+    /// Cache freshly downloaded rules. The platform's monthly counts catch
+    /// up on anything placed from elsewhere, or before this device counted.
+    pub async fn replace(&mut self, policy: PolicySnapshot, today: NaiveDate) -> Result<()> {
+        let monthly = self.state.monthly.current(today);
+        monthly.sms = monthly.sms.max(policy.month_sms);
+        monthly.calls = monthly.calls.max(policy.month_calls);
+        monthly.cost = monthly.cost.max(policy.month_cost);
+        self.state.policy = Some(policy);
+        self.save().await
+    }
+
+    /// Why a message or call to `to` isn't allowed, if it isn't
+    pub fn check(&mut self, kind: CommunicationKind, to: &str, emergency: bool, today: NaiveDate) -> Result<(), String> {
+        if !self.config.enforce {
+            return Ok(());
+        }
+        let (default_sms, default_calls) = (self.config.default_daily_sms_limit, self.config.default_daily_call_limit);
+        let used = *self.state.usage.count(kind, today);
+        let Some(policy) = &self.state.policy else {
+            let limit = match kind {
+                CommunicationKind::Sms => default_sms,
+                CommunicationKind::Call => default_calls,
+            };
+            return check_limit(kind, used, Some(limit));
+        };
+
+        let (enabled, limit) = match kind {
+            CommunicationKind::Sms => (policy.sms_enabled, policy.daily_sms_limit),
+            CommunicationKind::Call => (policy.voice_enabled, policy.daily_call_limit),
+        };
+        if !enabled {
+            return Err(format!("{} disabled for this device", kind_name(kind)));
+        }
+
+        let number = normalize_number(to);
+        let entry = policy.allowlist.iter().find(|entry| normalize_number(&entry.allowed_number) == number);
+        if policy.allowlist_enforced || policy.emergency_contacts_only {
+            let allowed = entry.is_some_and(|entry| match kind {
+                CommunicationKind::Sms => entry.sms_allowed,
+                CommunicationKind::Call => entry.voice_allowed,
+            });
+            if !allowed {
+                return Err(format!("{} not on the allowlist", to));
+            }
+        }
+        if policy.emergency_contacts_only && entry.map_or(true, |entry| entry.number_type != "emergency") {
+            return Err(format!("{} is not an emergency contact", to));
+        }
+
+        if emergency && policy.emergency_bypass_limits {
+            return Ok(());
+        }
+        check_limit(kind, used, limit)?;
+        match policy.monthly_cost_limit {
+            Some(limit) if self.state.monthly.current(today).cost >= limit => {
+                Err(format!("monthly cost limit of {:.2} reached", limit))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a message or call that was placed
+    pub async fn record(&mut self, kind: CommunicationKind, today: NaiveDate) -> Result<()> {
+        *self.state.usage.count(kind, today) += 1;
+        let cost = match kind {
+            CommunicationKind::Sms => self.config.estimated_sms_cost,
+            CommunicationKind::Call => self.config.estimated_call_cost,
+        };
+        let monthly = self.state.monthly.current(today);
+        match kind {
+            CommunicationKind::Sms => monthly.sms += 1,
+            CommunicationKind::Call => monthly.calls += 1,
+        }
+        monthly.cost += cost;
+        self.save().await
+    }
+
+    /// How much of each quota that has a limit is used
+    pub fn quotas(&mut self, today: NaiveDate) -> Vec<QuotaUsage> {
+        let sms = *self.state.usage.count(CommunicationKind::Sms, today);
+        let calls = *self.state.usage.count(CommunicationKind::Call, today);
+        let cost = self.state.monthly.current(today).cost;
+        let (sms_limit, call_limit, cost_limit) = match &self.state.policy {
+            Some(policy) => (policy.daily_sms_limit, policy.daily_call_limit, policy.monthly_cost_limit),
+            None => (Some(self.config.default_daily_sms_limit), Some(self.config.default_daily_call_limit), None),
+        };
+        [
+            (Quota::DailySms, sms as f64, sms_limit.map(f64::from)),
+            (Quota::DailyCalls, calls as f64, call_limit.map(f64::from)),
+            (Quota::MonthlyCost, cost, cost_limit),
+        ]
+        .into_iter()
+        .filter_map(|(quota, used, limit)| limit.map(|limit| QuotaUsage { quota, used, limit }))
+        .collect()
+    }
+
+    /// Quotas past the warning threshold
+    pub fn warnings(&mut self, today: NaiveDate) -> Vec<QuotaUsage> {
+        let threshold = f64::from(self.config.warn_at_percent);
+        self.quotas(today).into_iter().filter(|usage| usage.percent() >= threshold).collect()
+    }
+
+    /// Quotas that crossed the warning threshold since last asked. One that
+    /// drops back below it, e.g. on a new day, is reported again next time.
+    pub fn new_warnings(&mut self, today: NaiveDate) -> Vec<QuotaUsage> {
+        let warnings = self.warnings(today);
+        self.warned.retain(|quota| warnings.iter().any(|usage| usage.quota == *quota));
+        let new: Vec<QuotaUsage> = warnings.into_iter().filter(|usage| !self.warned.contains(&usage.quota)).collect();
+        self.warned.extend(new.iter().map(|usage| usage.quota));
+        new
+    }
+}
+
+fn check_limit(kind: CommunicationKind, used: u32, limit: Option<u32>) -> Result<(), String> {
+    match limit {
+        Some(limit) if used >= limit => Err(format!("daily {} limit of {} reached", kind_name(kind), limit)),
+        _ => Ok(()),
+    }
+}
+
+fn kind_name(kind: CommunicationKind) -> &'static str {
+    match kind {
+        CommunicationKind::Sms => "SMS",
+        CommunicationKind::Call => "call",
+    }
+}
+
+/// The local date usage is counted against
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(number: &str, number_type: &str, voice_allowed: bool) -> NumberWhitelistEntry {
+        NumberWhitelistEntry {
+            id: number.to_string(),
+            allowed_number: number.to_string(),
+            number_type: number_type.to_string(),
+            sms_allowed: true,
+            voice_allowed,
+            description: None,
+            is_active: true,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_allowlist_and_daily_limits() {
+        let mut guard = CommunicationGuard {
+            config: CommunicationPolicyConfig::default(),
+            path: PathBuf::new(),
+            state: PolicyState::default(),
+            warned: Vec::new(),
+        };
+        guard.state.policy = Some(PolicySnapshot {
+            fetched_at: Utc::now(),
+            sms_enabled: true,
+            voice_enabled: true,
+            allowlist_enforced: true,
+            allowlist: vec![entry("+15550100", "emergency", true), entry("+15550111", "contact", false)],
+            daily_sms_limit: Some(1),
+            daily_call_limit: None,
+            emergency_bypass_limits: true,
+            emergency_contacts_only: false,
+            monthly_cost_limit: Some(10.0),
+            month_sms: 0,
+            month_calls: 0,
+            month_cost: 0.0,
+        });
+        let day = NaiveDate::from_ymd_opt(2026, 5, 1).unwrap();
+        let sms = CommunicationKind::Sms;
+
+        assert!(guard.check(sms, "+1 555-0100", false, day).is_ok());
+        assert!(guard.check(sms, "+15550199", false, day).is_err());
+        assert!(guard.check(CommunicationKind::Call, "+15550111", false, day).is_err());
+
+        *guard.state.usage.count(sms, day) += 1;
+        assert_eq!(guard.check(sms, "+15550111", false, day), Err("daily SMS limit of 1 reached".to_string()));
+        assert!(guard.check(sms, "+15550111", true, day).is_ok());
+        assert!(guard.check(sms, "+15550111", false, day.succ_opt().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_quota_warnings_are_reported_once() {
+        let mut guard = CommunicationGuard {
+            config: CommunicationPolicyConfig { default_daily_sms_limit: 10, ..CommunicationPolicyConfig::default() },
+            path: PathBuf::new(),
+            state: PolicyState::default(),
+            warned: Vec::new(),
+        };
+        let day = NaiveDate::from_ymd_opt(2026, 5, 1).unwrap();
+        *guard.state.usage.count(CommunicationKind::Sms, day) = 7;
+        assert!(guard.new_warnings(day).is_empty());
+
+        *guard.state.usage.count(CommunicationKind::Sms, day) = 8;
+        let warnings = guard.new_warnings(day);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].quota, Quota::DailySms);
+        assert!(guard.new_warnings(day).is_empty());
+
+        // A new day starts the count over, so the warning can come back
+        let next = day.succ_opt().unwrap();
+        assert!(guard.new_warnings(next).is_empty());
+        *guard.state.usage.count(CommunicationKind::Sms, next) = 9;
+        assert_eq!(guard.new_warnings(next).len(), 1);
+    }
+}
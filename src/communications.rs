@@ -0,0 +1,177 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::api::{SmsMessage, VoiceCall};
+
+/// How long a message or call's status is followed after it was placed
+const TRACK_FOR_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommunicationKind {
+    Sms,
+    Call,
+}
+
+impl CommunicationKind {
+    /// Statuses after which a message or call won't change again
+    pub fn is_final(self, status: &str) -> bool {
+        match self {
+            CommunicationKind::Sms => matches!(status, "delivered" | "failed" | "undelivered" | "rejected"),
+            CommunicationKind::Call => matches!(
+                status,
+                "completed" | "failed" | "busy" | "no_answer" | "canceled" | "rejected"
+            ),
+        }
+    }
+}
+
+/// An entry on an incident's timeline: a message or call being placed, or
+/// a later change in its status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommunicationEvent {
+    pub incident_id: String,
+    pub kind: CommunicationKind,
+    /// The server's id for the message or call
+    pub communication_id: String,
+    pub to: String,
+    pub status: String,
+    pub at: DateTime<Utc>,
+    /// The message text, or how long a call lasted
+    pub detail: Option<String>,
+}
+
+/// A message or call whose delivery or answer is still being followed
+#[derive(Debug, Clone)]
+pub struct PendingCommunication {
+    pub incident_id: String,
+    pub kind: CommunicationKind,
+    pub communication_id: String,
+    pub to: String,
+    pub status: String,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl PendingCommunication {
+    pub fn new(event: &CommunicationEvent) -> Self {
+        Self {
+            incident_id: event.incident_id.clone(),
+            kind: event.kind,
+            communication_id: event.communication_id.clone(),
+            to: event.to.clone(),
+            status: event.status.clone(),
+            placed_at: event.at,
+        }
+    }
+
+    /// Still worth asking the server about
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        !self.kind.is_final(&self.status) && now - self.placed_at < Duration::minutes(TRACK_FOR_MINUTES)
+    }
+
+    /// A timeline entry if the server's status differs from the last one
+    /// seen, updating it
+    fn update(&mut self, status: &str, at: Option<DateTime<Utc>>, detail: Option<String>) -> Option<CommunicationEvent> {
+        if status == self.status {
+            return None;
+        }
+        self.status = status.to_string();
+        Some(CommunicationEvent {
+            incident_id: self.incident_id.clone(),
+            kind: self.kind,
+            communication_id: self.communication_id.clone(),
+            to: self.to.clone(),
+            status: status.to_string(),
+            at: at.unwrap_or_else(Utc::now),
+            detail,
+        })
+    }
+}
+
+fn from_millis(millis: Option<i64>) -> Option<DateTime<Utc>> {
+    millis.and_then(DateTime::from_timestamp_millis)
+}
+
+/// Compare the server's history for an incident with what's being followed,
+/// returning a timeline entry for each status that changed
+pub fn status_changes(
+    pending: &mut [PendingCommunication],
+    messages: &[SmsMessage],
+    calls: &[VoiceCall],
+) -> Vec<CommunicationEvent> {
+    let mut changes = Vec::new();
+    for tracked in pending.iter_mut() {
+        let change = match tracked.kind {
+            CommunicationKind::Sms => messages.iter()
+                .find(|message| message.id == tracked.communication_id)
+                .and_then(|message| {
+                    let at = from_millis(message.delivered_at).or(from_millis(message.sent_at));
+                    tracked.update(&message.status, at, None)
+                }),
+            CommunicationKind::Call => calls.iter()
+                .find(|call| call.id == tracked.communication_id)
+                .and_then(|call| {
+                    let at = from_millis(call.ended_at).or(from_millis(call.answered_at));
+                    let detail = call.duration.map(|seconds| format!("{} s", seconds));
+                    tracked.update(&call.status, at, detail)
+                }),
+        };
+        changes.extend(change);
+    }
+    changes
+}
+
+/// Digits and a leading `+`, so `+1 555-0100` matches `+15550100`
+pub fn normalize_number(number: &str) -> String {
+    number.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect()
+}
+
+/// Where an export's communications are written, next to the exported file
+pub fn sidecar_path(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".communications.json");
+    output.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, status: &str) -> SmsMessage {
+        SmsMessage {
+            id: id.to_string(),
+            to: "+15550100".to_string(),
+            from: "+15550199".to_string(),
+            text: "Officer needs assistance".to_string(),
+            status: status.to_string(),
+            direction: "outbound".to_string(),
+            sent_at: Some(1_700_000_000_000),
+            delivered_at: None,
+            incident_id: Some("incident-1".to_string()),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_only_changed_statuses_reach_the_timeline() {
+        let placed = CommunicationEvent {
+            incident_id: "incident-1".to_string(),
+            kind: CommunicationKind::Sms,
+            communication_id: "sms-1".to_string(),
+            to: "+15550100".to_string(),
+            status: "queued".to_string(),
+            at: Utc::now(),
+            detail: None,
+        };
+        let mut pending = vec![PendingCommunication::new(&placed)];
+
+        assert!(status_changes(&mut pending, &[message("sms-1", "queued")], &[]).is_empty());
+
+        let changes = status_changes(&mut pending, &[message("sms-2", "failed"), message("sms-1", "delivered")], &[]);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].status, "delivered");
+        assert_eq!(changes[0].at.timestamp(), 1_700_000_000);
+        assert!(!pending[0].is_open(Utc::now()));
+    }
+}
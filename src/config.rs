@@ -1,11 +1,56 @@
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use std::collections::HashMap;
 use std::path::Path;
 
+use crate::backend::BackendKind;
+use crate::http::RequestCompression;
+use crate::audio_encoding::{AudioCodec, AudioEncodingConfig};
+use crate::audio_processing::AudioProcessingConfig;
+use crate::audio_mixer::AudioMixerConfig;
+use crate::speaker_loudness::SpeakerLoudnessConfig;
+use crate::gop_buffer::PreIncidentBufferConfig;
+use crate::redaction::RedactionConfig;
+use crate::detection::DetectionConfig;
+use crate::incident_rules::IncidentRule;
+use crate::vehicle::VehicleProfileConfig;
+use crate::webhooks::WebhookConfig;
+use crate::remote_support::SupportConfig;
+use crate::access::AccessConfig;
+use crate::i18n::I18nConfig;
+use crate::theme::ThemeConfig;
+use crate::kiosk::KioskConfig;
+use crate::gps::GpsFilterConfig;
+use crate::hardware::charging::ChargingSafetyConfig;
+use crate::hardware::brightness::DimmingConfig;
+use crate::deep_sleep::DeepSleepConfig;
+use crate::preflight::PreflightConfig;
+use crate::recording_time::RemainingTimeConfig;
+use crate::location::LocationFallbackConfig;
+use crate::metrics_history::MetricsHistoryConfig;
+use crate::data_usage::DataUsageConfig;
+use crate::diagnostics_archive::DiagnosticsArchiveConfig;
+use crate::self_test::SelfTestConfig;
+use crate::sms_commands::SmsCommandConfig;
+use crate::contact_directory::ContactDirectoryConfig;
+use crate::communication_policy::CommunicationPolicyConfig;
+use crate::hardware_identity::HardwareBindingConfig;
+use crate::certificates::CertificateConfig;
+use crate::loop_recording::LoopRecordingConfig;
+use crate::audio_presets::AudioPresetConfig;
+use crate::button_actions::ButtonActionsConfig;
+use crate::sites::SiteProfile;
+use crate::streaming::outputs::{StreamDestinationConfig, StreamDestinationKind};
+use crate::streaming::srt::{SrtConfig, StreamProtocolPreference};
+use crate::streaming::local_copy::LocalStreamCopyConfig;
+use crate::streaming::pip::PipConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server_url: String,
     pub convex_url: Option<String>, // New: Convex backend URL
+    /// Platform backend to use; `auto` picks Convex when `convex_url` is set
+    pub backend: BackendKind,
     pub device_id: Option<String>,
     pub device_key: Option<String>,
     pub device_serial: Option<String>, // New: Hardware serial number
@@ -26,8 +71,55 @@ pub struct Config {
     pub monitoring: MonitoringConfig,
     pub remote_config: RemoteConfig,
     pub security: SecurityConfig,
+    /// Local roles required for privileged CLI and UI operations
+    pub access: AccessConfig,
+    /// Remote support access; off unless consented to on the device
+    pub support: SupportConfig,
+    /// UI, prompt and alert language
+    pub i18n: I18nConfig,
+    /// Touchscreen colour scheme and touch target size
+    pub theme: ThemeConfig,
+    /// Fullscreen UI that needs a supervisor PIN to leave
+    pub kiosk: KioskConfig,
+    /// Which GPS fixes are trusted and when one counts as stale
+    pub gps_filter: GpsFilterConfig,
+    /// Where the position comes from when there's no GPS fix
+    pub location_fallback: LocationFallbackConfig,
+    /// Metrics kept on the device for trend queries
+    pub metrics_history: MetricsHistoryConfig,
+    /// Data used per link type, and the monthly cellular cap
+    pub data_usage: DataUsageConfig,
+    /// How long comprehensive diagnostics reports are kept on the device
+    pub diagnostics_archive: DiagnosticsArchiveConfig,
+    /// Nightly and dock-time self-test, and which checks gate duty readiness
+    pub self_test: SelfTestConfig,
+    /// Commands supervisors text to the device's platform number
+    pub sms_commands: SmsCommandConfig,
+    /// Cached contact directory used for emergency SMS and calls offline
+    pub contact_directory: ContactDirectoryConfig,
+    /// Number allowlist and daily SMS and call limits enforced on the device
+    pub communication_policy: CommunicationPolicyConfig,
+    /// Ties the credentials to the hardware they were issued to
+    pub hardware_binding: HardwareBindingConfig,
+    /// Renewal and expiry alerts for the device certificate
+    pub certificates: CertificateConfig,
+    /// Nonstop recording into a bounded loop, for fixed deployments
+    pub loop_recording: LoopRecordingConfig,
+    /// Alert sounds synced from the platform
+    pub audio_presets: AudioPresetConfig,
+    /// What each press of each button does, synced from the platform
+    pub buttons: ButtonActionsConfig,
     pub storage: StorageConfig,
     pub streaming: StreamingConfig,
+    pub night_mode: NightModeConfig,
+    pub redaction: RedactionConfig,
+    pub detection: DetectionConfig,
+    pub incident_rules: Vec<IncidentRule>,
+    pub vehicle: VehicleProfileConfig,
+    /// Local endpoints sent signed event notifications; none by default
+    pub webhooks: Vec<WebhookConfig>,
+    /// Credentials for other sites the device can switch to
+    pub sites: Vec<SiteProfile>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +131,10 @@ pub struct SimulationConfig {
     pub battery_drain_rate: f64,
     pub simulate_storage: bool,
     pub storage_usage_rate: f64,
+    /// Media file (e.g. an MP4) looped in place of the camera
+    pub video_source: Option<String>,
+    /// Media file (e.g. a WAV) looped in place of the microphone
+    pub audio_source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,16 +156,48 @@ pub struct RecordingConfig {
     pub segment_duration: u64,
     pub encryption: bool,
     pub pre_incident_buffer_seconds: u64,
+    pub pre_incident_buffer: PreIncidentBufferConfig,
     pub default_quality: VideoQuality,
     pub available_qualities: Vec<VideoQualityConfig>,
+    /// Record microphone only instead of video (interviews, statements)
+    pub audio_only: bool,
+    /// How often the GPS is sampled into a recording's location track
+    pub location_sample_seconds: u64,
+    /// Checks made before a recording starts
+    pub preflight: PreflightConfig,
+    /// Estimating the recording time left
+    pub remaining: RemainingTimeConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
+    /// Cap on the combined rate of all uploads, in bytes per second
     pub upload_bandwidth: u32,
+    pub max_concurrent_uploads: usize,
+    /// Chunk size proposed for chunked uploads; the server has the final say
+    pub upload_chunk_size: u64,
+    /// Incident uploads started in a row before a routine upload gets a turn
+    pub incident_upload_weight: u32,
     pub retry_attempts: u32,
+    /// First retry waits up to this long; later ones double, with jitter
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    /// Consecutive failures before an endpoint's circuit breaker opens
+    pub circuit_failure_threshold: u32,
+    /// How long an open breaker fails requests before letting a trial through
+    pub circuit_open_seconds: u64,
     pub timeout: u64,
+    /// Per-endpoint timeouts in seconds, keyed by a path suffix such as
+    /// `/api/media/*/upload` (ids appear as `*`); longest match wins
+    pub endpoint_timeouts: HashMap<String, u64>,
+    /// Compress status and metrics request bodies
     pub compression: bool,
+    pub compression_algorithm: RequestCompression,
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout_seconds: u64,
+    pub tcp_keepalive_seconds: u64,
+    /// Offer HTTP/2 via ALPN; servers without it fall back to HTTP/1.1
+    pub http2: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,6 +209,10 @@ pub struct CameraConfig {
     pub format: String,
     pub exposure: String,
     pub white_balance: String,
+    pub fallback_device_path: Option<String>,
+    pub ir_cut_path: Option<String>,
+    /// Frame buffers kept for reuse while the preview captures the camera
+    pub frame_pool_size: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,7 +244,17 @@ pub struct AudioConfig {
     pub sample_rate: u32,
     pub channels: u8,
     pub bitrate: u32,
-    pub format: String,
+    /// Codec used for recordings
+    pub codec: AudioCodec,
+    pub processing: AudioProcessingConfig,
+    /// Peak level below which the mic counts as silent
+    pub silence_threshold_db: f32,
+    /// Warn when the mic stays silent this long while recording
+    pub dead_mic_minutes: u64,
+    /// How overlapping sounds on the speaker are prioritised
+    pub mixer: AudioMixerConfig,
+    /// Speaker calibration and ambient-adaptive alert volume
+    pub loudness: SpeakerLoudnessConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,11 +274,28 @@ pub struct PowerManagementConfig {
     pub idle_timeout_seconds: u64,
     pub max_cpu_usage_percent: f64,
     pub background_task_delay_ms: u64,
+    /// Battery heat, charge current and capacity checks while charging
+    pub charging_safety: ChargingSafetyConfig,
+    /// Display and LED brightness before any dimming, in percent
+    pub brightness_level: u32,
+    /// When the display and LEDs dim to save battery
+    pub dimming: DimmingConfig,
+    /// Wake sources and schedule for deep sleep
+    pub deep_sleep: DeepSleepConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringConfig {
+    /// Status report interval while idle on external power
     pub checkin_interval_seconds: u64,
+    /// Status report interval while recording or during an incident
+    pub active_checkin_interval_seconds: u64,
+    /// Status report interval while idle on battery
+    pub idle_checkin_interval_seconds: u64,
+    /// Send only the status fields changed since the last acknowledged report
+    pub status_deltas: bool,
+    /// Delta reports between full ones
+    pub full_status_every: u32,
     pub enable_real_time_updates: bool,
     pub enable_server_polling: bool,
     pub update_on_demand: bool,
@@ -151,6 +310,9 @@ pub struct RemoteConfig {
     pub config_endpoint: String,
     pub last_update: Option<chrono::DateTime<chrono::Utc>>,
     pub config_version: String,
+    /// Settings keys (e.g. `recording.encryption`) the site policy doesn't
+    /// let officers change on the device; set by config sync
+    pub locked_settings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -168,6 +330,12 @@ pub struct SecurityConfig {
     pub sos_enabled: bool,
     pub emergency_contacts: Vec<String>,
     pub auto_call_timeout: u32,
+    /// Overwrite passes per file when the device is decommissioned
+    pub wipe_passes: u32,
+    /// Discard freed blocks with `fstrim` after a decommission wipe
+    pub wipe_trim: bool,
+    /// Base64 Ed25519 key the backend signs tamper policies with
+    pub policy_public_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -178,6 +346,11 @@ pub struct StorageConfig {
     pub upload_on_charging_only: bool,
     pub max_file_size_mb: u32,
     pub compression_level: u8,
+    /// Never delete recordings after upload; set per site
+    pub keep_local_copy: bool,
+    /// Hours an upload must have been confirmed by the server before its
+    /// local file may be deleted
+    pub deletion_grace_hours: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -188,6 +361,31 @@ pub struct StreamingConfig {
     pub reconnect_attempts: u32,
     pub buffer_size_seconds: u32,
     pub adaptive_bitrate: bool,
+    /// Audio encoding for live streams, independent of recording. Opus over
+    /// RTMP needs an ffmpeg build and ingest server with enhanced FLV support.
+    pub audio: AudioEncodingConfig,
+    /// Extra outputs streamed alongside the platform ingest
+    pub destinations: Vec<StreamDestinationConfig>,
+    /// Protocol for the platform ingest; SRT copes far better with lossy LTE
+    pub protocol: StreamProtocolPreference,
+    pub srt: SrtConfig,
+    /// Local segments written while streaming, so network loss never loses evidence
+    pub local_copy: LocalStreamCopyConfig,
+    /// Second camera composed into the live stream
+    pub pip: PipConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NightModeConfig {
+    pub enabled: bool,
+    pub enter_lux: f64,
+    pub exit_lux: f64,
+    pub enter_luminance: f64,
+    pub exit_luminance: f64,
+    pub hold_seconds: u64,
+    pub night_fps: u32,
+    pub night_bitrate_multiplier: f64,
+    pub sample_interval_seconds: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -205,6 +403,7 @@ impl Default for Config {
         Self {
             server_url: "http://localhost:3000".to_string(),
             convex_url: None, // Set via environment or config file
+            backend: BackendKind::Auto,
             device_id: None,
             device_key: None,
             device_serial: None,
@@ -221,6 +420,8 @@ impl Default for Config {
                 battery_drain_rate: 0.5,
                 simulate_storage: true,
                 storage_usage_rate: 0.1,
+                video_source: None,
+                audio_source: None,
             },
             hardware: HardwareConfig {
                 camera_index: Some(0),
@@ -238,6 +439,7 @@ impl Default for Config {
                 segment_duration: 300,
                 encryption: true,
                 pre_incident_buffer_seconds: 30,
+                pre_incident_buffer: PreIncidentBufferConfig::default(),
                 default_quality: VideoQuality::Low,
                 available_qualities: vec![
                     VideoQualityConfig {
@@ -259,12 +461,32 @@ impl Default for Config {
                         device_path: "/dev/video1".to_string(),
                     },
                 ],
+                audio_only: false,
+                location_sample_seconds: 5,
+                preflight: PreflightConfig::default(),
+                remaining: RemainingTimeConfig::default(),
             },
             network: NetworkConfig {
                 upload_bandwidth: 1_000_000,
+                max_concurrent_uploads: 2,
+                upload_chunk_size: 1024 * 1024,
+                incident_upload_weight: 3,
                 retry_attempts: 3,
+                retry_base_delay_ms: 500,
+                retry_max_delay_ms: 30_000,
+                circuit_failure_threshold: 5,
+                circuit_open_seconds: 60,
                 timeout: 30,
+                endpoint_timeouts: HashMap::from([
+                    ("/gap-upload".to_string(), 300),
+                    ("/diagnostics".to_string(), 60),
+                ]),
                 compression: true,
+                compression_algorithm: RequestCompression::Gzip,
+                pool_max_idle_per_host: 4,
+                pool_idle_timeout_seconds: 90,
+                tcp_keepalive_seconds: 60,
+                http2: true,
             },
             camera: CameraConfig {
                 device_index: 0,
@@ -274,6 +496,9 @@ impl Default for Config {
                 format: "MJPEG".to_string(),
                 exposure: "auto".to_string(),
                 white_balance: "auto".to_string(),
+                fallback_device_path: None,
+                ir_cut_path: None,
+                frame_pool_size: 8,
             },
             audio: AudioConfig {
                 enabled: true,
@@ -283,7 +508,12 @@ impl Default for Config {
                 sample_rate: 44100,
                 channels: 2,
                 bitrate: 128000,
-                format: "AAC".to_string(),
+                codec: AudioCodec::Aac,
+                processing: AudioProcessingConfig::default(),
+                silence_threshold_db: -60.0,
+                dead_mic_minutes: 5,
+                mixer: AudioMixerConfig::default(),
+                loudness: SpeakerLoudnessConfig::default(),
             },
             encryption: EncryptionConfig {
                 enabled: false,
@@ -299,10 +529,18 @@ impl Default for Config {
                 idle_timeout_seconds: 300,  // 5 minutes
                 max_cpu_usage_percent: 15.0,  // Keep CPU usage low
                 background_task_delay_ms: 100,
+                charging_safety: ChargingSafetyConfig::default(),
+                brightness_level: 80,
+                dimming: DimmingConfig::default(),
+                deep_sleep: DeepSleepConfig::default(),
             },
             sentry: None, // Sentry configuration is optional
             monitoring: MonitoringConfig {
                 checkin_interval_seconds: 30, // Default 30 seconds
+                active_checkin_interval_seconds: 10,
+                idle_checkin_interval_seconds: 120,
+                status_deltas: true,
+                full_status_every: 10,
                 enable_real_time_updates: true, // Enable Convex real-time
                 enable_server_polling: true, // Enable polling fallback
                 update_on_demand: true, // Enable server-requested updates
@@ -315,6 +553,7 @@ impl Default for Config {
                 config_endpoint: "/api/devices/config".to_string(),
                 last_update: None,
                 config_version: "1.0.0".to_string(),
+                locked_settings: Vec::new(),
             },
             security: SecurityConfig {
                 enable_tamper_detection: true,
@@ -330,7 +569,29 @@ impl Default for Config {
                 sos_enabled: true,
                 emergency_contacts: vec![],
                 auto_call_timeout: 30,
+                wipe_passes: 3,
+                wipe_trim: true,
+                policy_public_key: None,
             },
+            access: AccessConfig::default(),
+            support: SupportConfig::default(),
+            i18n: I18nConfig::default(),
+            theme: ThemeConfig::default(),
+            kiosk: KioskConfig::default(),
+            gps_filter: GpsFilterConfig::default(),
+            location_fallback: LocationFallbackConfig::default(),
+            metrics_history: MetricsHistoryConfig::default(),
+            data_usage: DataUsageConfig::default(),
+            diagnostics_archive: DiagnosticsArchiveConfig::default(),
+            self_test: SelfTestConfig::default(),
+            sms_commands: SmsCommandConfig::default(),
+            contact_directory: ContactDirectoryConfig::default(),
+            communication_policy: CommunicationPolicyConfig::default(),
+            hardware_binding: HardwareBindingConfig::default(),
+            certificates: CertificateConfig::default(),
+            loop_recording: LoopRecordingConfig::default(),
+            audio_presets: AudioPresetConfig::default(),
+            buttons: ButtonActionsConfig::default(),
             storage: StorageConfig {
                 max_local_storage_gb: 32,
                 auto_cleanup_days: 7,
@@ -338,6 +599,8 @@ impl Default for Config {
                 upload_on_charging_only: false,
                 max_file_size_mb: 1024, // 1GB
                 compression_level: 6,
+                keep_local_copy: false,
+                deletion_grace_hours: 24,
             },
             streaming: StreamingConfig {
                 enable_live_streaming: true,
@@ -346,7 +609,43 @@ impl Default for Config {
                 reconnect_attempts: 3,
                 buffer_size_seconds: 5,
                 adaptive_bitrate: true,
+                audio: AudioEncodingConfig {
+                    codec: AudioCodec::Opus,
+                    sample_rate: 48000,
+                    bitrate: 32_000,
+                    channels: 1,
+                },
+                destinations: vec![
+                    StreamDestinationConfig {
+                        name: "local_rtsp".to_string(),
+                        kind: StreamDestinationKind::Rtsp,
+                        url: "rtsp://127.0.0.1:8554/bodycam".to_string(),
+                        quality: Some("low".to_string()),
+                        enabled: false,
+                    },
+                ],
+                protocol: StreamProtocolPreference::Auto,
+                srt: SrtConfig::default(),
+                local_copy: LocalStreamCopyConfig::default(),
+                pip: PipConfig::default(),
+            },
+            night_mode: NightModeConfig {
+                enabled: true,
+                enter_lux: 10.0,
+                exit_lux: 30.0,
+                enter_luminance: 40.0,  // Mean luma on a 0-255 scale
+                exit_luminance: 70.0,
+                hold_seconds: 10,
+                night_fps: 15,
+                night_bitrate_multiplier: 1.5,
+                sample_interval_seconds: 60,
             },
+            redaction: RedactionConfig::default(),
+            detection: DetectionConfig::default(),
+            incident_rules: IncidentRule::defaults(),
+            vehicle: VehicleProfileConfig::default(),
+            webhooks: Vec::new(),
+            sites: Vec::new(),
         }
     }
 }
@@ -366,6 +665,18 @@ impl Config {
         Ok(config)
     }
 
+    /// Pre-incident buffer length in effect, raised to the vehicle
+    /// profile's pre-roll while that profile is on. The configured value is
+    /// what gets saved.
+    pub fn pre_incident_buffer_seconds(&self) -> u64 {
+        let configured = self.recording.pre_incident_buffer_seconds;
+        if self.vehicle.enabled {
+            configured.max(self.vehicle.pre_roll_seconds)
+        } else {
+            configured
+        }
+    }
+
     pub async fn save(&self, path: &Path) -> Result<()> {
         let content = toml::to_string_pretty(self)?;
         tokio::fs::write(path, content).await?;
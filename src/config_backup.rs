@@ -0,0 +1,257 @@
+//! Encrypted backups of a device's setup, so replacement hardware can be
+//! re-imaged as the same logical device. The passphrase key wraps two
+//! random keys: one for the configuration and data files, and one for the
+//! credentials. A restore can then leave the credentials out.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key};
+type Nonce = aes_gcm::Nonce<aes_gcm::aes::cipher::typenum::U12>;
+use anyhow::{Context, Result};
+use argon2::{password_hash::rand_core::RngCore, Argon2};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use crate::config::Config;
+use crate::sites::SiteProfile;
+
+const FORMAT_VERSION: u32 = 1;
+const MIN_PASSPHRASE_LENGTH: usize = 12;
+
+/// Files in `data/` that describe this hardware's setup rather than what it
+/// recorded: battery calibration, the tamper policy and cached directories
+pub const DATA_FILES: &[&str] = &[
+    "battery_capacity.json",
+    "tamper_policy.json",
+    "recording_policy.json",
+    "contacts.json",
+    "communication_policy.json",
+    "sms_commands.json",
+];
+
+/// Everything in the configuration that grants access to the platform or
+/// to encrypted recordings
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Credentials {
+    pub device_key: Option<String>,
+    pub auth_token: Option<String>,
+    pub api_key: Option<String>,
+    pub encryption_key: Option<String>,
+    pub sms_command_secret: Option<String>,
+    pub sites: Vec<SiteProfile>,
+}
+
+impl Credentials {
+    /// Move the credentials out of `config`
+    pub fn take(config: &mut Config) -> Self {
+        Self {
+            device_key: config.device_key.take(),
+            auth_token: config.auth_token.take(),
+            api_key: config.api_key.take(),
+            encryption_key: config.encryption.key.take(),
+            sms_command_secret: config.sms_commands.secret.take(),
+            sites: std::mem::take(&mut config.sites),
+        }
+    }
+
+    pub fn apply(self, config: &mut Config) {
+        config.device_key = self.device_key;
+        config.auth_token = self.auth_token;
+        config.api_key = self.api_key;
+        config.encryption.key = self.encryption_key;
+        config.sms_commands.secret = self.sms_command_secret;
+        config.sites = self.sites;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Contents {
+    /// The configuration without credentials, as TOML
+    config: String,
+    /// Data files by name, base64
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// A backup as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupArchive {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub device_id: Option<String>,
+    /// Salt for deriving the passphrase key
+    salt: String,
+    /// Key for the contents, sealed with the passphrase key
+    data_key: Sealed,
+    /// Key for the credentials, sealed with the passphrase key
+    credential_key: Sealed,
+    contents: Sealed,
+    credentials: Sealed,
+}
+
+/// What a backup holds once opened
+#[derive(Debug)]
+pub struct RestoredBackup {
+    /// The backed-up configuration, without credentials
+    pub config: Config,
+    pub credentials: Credentials,
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key[..])
+        .map_err(|e| anyhow::anyhow!("Failed to derive backup key: {}", e))?;
+    Ok(key)
+}
+
+fn seal(key: &[u8], plaintext: &[u8]) -> Result<Sealed> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, plaintext)
+        .map_err(|e| anyhow::anyhow!("Backup encryption failed: {}", e))?;
+    Ok(Sealed {
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext),
+    })
+}
+
+fn unseal(key: &[u8], sealed: &Sealed) -> Result<Zeroizing<Vec<u8>>> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = general_purpose::STANDARD.decode(&sealed.nonce).context("Damaged backup")?;
+    let ciphertext = general_purpose::STANDARD.decode(&sealed.ciphertext).context("Damaged backup")?;
+    if nonce.len() != 12 {
+        return Err(anyhow::anyhow!("Damaged backup"));
+    }
+    cipher.decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow::anyhow!("Wrong passphrase or damaged backup"))
+}
+
+impl BackupArchive {
+    /// Encrypt `config` and `files` under `passphrase`
+    pub fn create(config: &Config, files: BTreeMap<String, Vec<u8>>, passphrase: &str) -> Result<Self> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+            return Err(anyhow::anyhow!("Backup passphrase must be at least {} characters", MIN_PASSPHRASE_LENGTH));
+        }
+        let mut config = config.clone();
+        let credentials = Credentials::take(&mut config);
+        let contents = Contents {
+            config: toml::to_string(&config)?,
+            files: files.into_iter()
+                .map(|(name, bytes)| (name, general_purpose::STANDARD.encode(bytes)))
+                .collect(),
+        };
+
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let wrapping_key = passphrase_key(passphrase, &salt)?;
+        let data_key = Zeroizing::new(Aes256Gcm::generate_key(&mut OsRng).to_vec());
+        let credential_key = Zeroizing::new(Aes256Gcm::generate_key(&mut OsRng).to_vec());
+
+        Ok(Self {
+            version: FORMAT_VERSION,
+            created_at: Utc::now(),
+            device_id: config.device_id.clone(),
+            salt: general_purpose::STANDARD.encode(salt),
+            data_key: seal(&wrapping_key[..], &data_key)?,
+            credential_key: seal(&wrapping_key[..], &credential_key)?,
+            contents: seal(&data_key, &Zeroizing::new(serde_json::to_vec(&contents)?))?,
+            credentials: seal(&credential_key, &Zeroizing::new(serde_json::to_vec(&credentials)?))?,
+        })
+    }
+
+    /// Decrypt the backup
+    pub fn open(&self, passphrase: &str) -> Result<RestoredBackup> {
+        if self.version != FORMAT_VERSION {
+            return Err(anyhow::anyhow!("Unsupported backup version {}", self.version));
+        }
+        let salt = general_purpose::STANDARD.decode(&self.salt).context("Damaged backup")?;
+        let wrapping_key = passphrase_key(passphrase, &salt)?;
+        let data_key = unseal(&wrapping_key[..], &self.data_key)?;
+        let credential_key = unseal(&wrapping_key[..], &self.credential_key)?;
+
+        let contents: Contents = serde_json::from_slice(&unseal(&data_key, &self.contents)?)?;
+        let credentials: Credentials = serde_json::from_slice(&unseal(&credential_key, &self.credentials)?)?;
+        let config: Config = toml::from_str(&contents.config).context("Backup holds an unreadable configuration")?;
+        let files = contents.files.into_iter()
+            .map(|(name, encoded)| Ok((name, general_purpose::STANDARD.decode(encoded)?)))
+            .collect::<Result<_>>()
+            .context("Damaged backup")?;
+        Ok(RestoredBackup { config, credentials, files })
+    }
+
+    pub async fn write(&self, path: &Path) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await
+            .with_context(|| format!("Failed to write backup to {}", path.display()))
+    }
+
+    pub async fn read(path: &Path) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await
+            .with_context(|| format!("Failed to read backup {}", path.display()))?;
+        serde_json::from_str(&content).context("Not a configuration backup")
+    }
+}
+
+/// The `DATA_FILES` present in `dir`
+pub async fn read_data_files(dir: &Path) -> BTreeMap<String, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    for name in DATA_FILES {
+        if let Ok(bytes) = tokio::fs::read(dir.join(name)).await {
+            files.insert(name.to_string(), bytes);
+        }
+    }
+    files
+}
+
+/// Put restored data files back. Names outside `DATA_FILES` are ignored.
+pub async fn write_data_files(dir: &Path, files: &BTreeMap<String, Vec<u8>>) -> Result<usize> {
+    tokio::fs::create_dir_all(dir).await?;
+    let mut written = 0;
+    for (name, bytes) in files {
+        if !DATA_FILES.contains(&name.as_str()) {
+            tracing::warn!("Ignoring unexpected file {:?} in backup", name);
+            continue;
+        }
+        tokio::fs::write(dir.join(name), bytes).await?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_round_trip_keeps_credentials_sealed() {
+        let mut config = Config::default();
+        config.device_id = Some("dev-1".to_string());
+        config.device_key = Some("secret-device-key".to_string());
+        config.encryption.key = Some("recording-key".to_string());
+        let files = BTreeMap::from([("battery_capacity.json".to_string(), b"{\"full\":5000}".to_vec())]);
+
+        let archive = BackupArchive::create(&config, files, "correct horse battery").unwrap();
+        let written = serde_json::to_string(&archive).unwrap();
+        assert!(!written.contains("secret-device-key"));
+        assert!(!written.contains("recording-key"));
+        assert!(archive.open("wrong horse battery").is_err());
+
+        let restored = archive.open("correct horse battery").unwrap();
+        assert_eq!(restored.config.device_id.as_deref(), Some("dev-1"));
+        assert!(restored.config.device_key.is_none());
+        assert_eq!(restored.credentials.device_key.as_deref(), Some("secret-device-key"));
+        assert_eq!(restored.files["battery_capacity.json"], b"{\"full\":5000}");
+
+        assert!(BackupArchive::create(&config, BTreeMap::new(), "short").is_err());
+    }
+}
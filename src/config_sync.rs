@@ -94,7 +94,8 @@ impl ConfigSyncManager {
         config.recording.bitrate = server_settings.video_bitrate;
         config.audio.enabled = server_settings.audio_enabled;
         
-        // Update button actions
+        // Update button actions; the device applies them from `buttons.actions`
+        config.buttons.actions = server_settings.button_actions.clone().into_iter().collect();
         for (button_type, action) in server_settings.button_actions {
             match button_type.as_str() {
                 "single_press" => {
@@ -109,7 +110,8 @@ impl ConfigSyncManager {
                 "triple_press" => {
                     config.security.triple_press_action = Some(action);
                 },
-                _ => warn!("Unknown button action type: {}", button_type),
+                // Per-button keys such as `menu.double_press`
+                _ => {}
             }
         }
 
@@ -126,6 +128,14 @@ impl ConfigSyncManager {
         config.power_management.auto_shutdown_timeout = server_settings.power_management.auto_shutdown_timeout;
         config.power_management.brightness_level = server_settings.power_management.brightness_level;
 
+        // Update site retention policy
+        if let Some(retention) = &server_settings.retention {
+            config.storage.keep_local_copy = retention.keep_local_copy;
+            config.storage.deletion_grace_hours = retention.deletion_grace_hours;
+        }
+
+        config.remote_config.locked_settings = server_settings.locked_settings.unwrap_or_default();
+
         // Save updated configuration
         config.remote_config.last_update = Some(chrono::Utc::now());
         config.remote_config.config_version = chrono::Utc::now().timestamp().to_string();
@@ -259,6 +269,22 @@ impl ConfigSyncManager {
             changed = true;
         }
 
+        if let Some(retention) = &server_settings.retention {
+            if config.storage.keep_local_copy != retention.keep_local_copy
+                || config.storage.deletion_grace_hours != retention.deletion_grace_hours
+            {
+                config.storage.keep_local_copy = retention.keep_local_copy;
+                config.storage.deletion_grace_hours = retention.deletion_grace_hours;
+                changed = true;
+            }
+        }
+
+        let button_actions: std::collections::BTreeMap<_, _> = server_settings.button_actions.clone().into_iter().collect();
+        if config.buttons.actions != button_actions {
+            config.buttons.actions = button_actions;
+            changed = true;
+        }
+
         if changed {
             config.remote_config.last_update = Some(chrono::Utc::now());
             config.remote_config.config_version = chrono::Utc::now().timestamp().to_string();
@@ -327,6 +353,11 @@ impl ConfigSyncManager {
         self.real_time_config.update_receiver.clone()
     }
 
+    /// The button mapping from the last sync
+    pub async fn button_actions(&self) -> std::collections::BTreeMap<String, String> {
+        self.config.read().await.buttons.actions.clone()
+    }
+
     pub async fn is_subscription_active(&self) -> bool {
         *self.real_time_config.subscription_active.read().await
     }
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::api::CommunicationContact;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactDirectoryConfig {
+    /// How often the directory is downloaded again
+    pub refresh_minutes: u64,
+    /// The UI marks the cached directory out of date after this long
+    pub stale_after_hours: u64,
+}
+
+impl Default for ContactDirectoryConfig {
+    fn default() -> Self {
+        Self {
+            refresh_minutes: 60,
+            stale_after_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedContacts {
+    fetched_at: Option<DateTime<Utc>>,
+    contacts: Vec<CommunicationContact>,
+}
+
+/// The cached directory and how old it is, for the contact picker
+#[derive(Debug, Clone)]
+pub struct ContactList {
+    pub contacts: Vec<CommunicationContact>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    pub stale: bool,
+}
+
+/// The platform's contact directory, kept on the device so emergency
+/// messages and calls can be placed when the directory can't be reached
+#[derive(Debug)]
+pub struct ContactDirectory {
+    config: ContactDirectoryConfig,
+    path: PathBuf,
+    cache: CachedContacts,
+}
+
+impl ContactDirectory {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("contacts.json")
+    }
+
+    pub async fn load(config: ContactDirectoryConfig) -> Self {
+        let path = Self::default_path();
+        let cache = tokio::fs::read_to_string(&path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { config, path, cache }
+    }
+
+    async fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_string_pretty(&self.cache)?).await?;
+        Ok(())
+    }
+
+    /// Replace the cache with a fresh download
+    pub async fn replace(&mut self, contacts: Vec<CommunicationContact>, now: DateTime<Utc>) -> Result<()> {
+        self.cache = CachedContacts { fetched_at: Some(now), contacts };
+        self.save().await
+    }
+
+    pub fn refresh_due(&self, now: DateTime<Utc>) -> bool {
+        self.cache.fetched_at
+            .map_or(true, |fetched_at| now - fetched_at >= Duration::minutes(self.config.refresh_minutes as i64))
+    }
+
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        self.cache.fetched_at
+            .map_or(true, |fetched_at| now - fetched_at >= Duration::hours(self.config.stale_after_hours as i64))
+    }
+
+    pub fn list(&self, now: DateTime<Utc>) -> ContactList {
+        ContactList {
+            contacts: self.cache.contacts.clone(),
+            fetched_at: self.cache.fetched_at,
+            stale: self.is_stale(now),
+        }
+    }
+
+    pub fn find(&self, id: &str) -> Option<&CommunicationContact> {
+        self.cache.contacts.iter().find(|contact| contact.id == id)
+    }
+}
+
+/// A short age for the UI, e.g. "5 min", "3 h" or "2 d"
+pub fn format_age(age: Duration) -> String {
+    if age < Duration::hours(1) {
+        format!("{} min", age.num_minutes().max(1))
+    } else if age < Duration::days(2) {
+        format!("{} h", age.num_hours())
+    } else {
+        format!("{} d", age.num_days())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cache_survives_reload_and_goes_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contacts.json");
+        let config = ContactDirectoryConfig { refresh_minutes: 60, stale_after_hours: 24 };
+        let mut directory = ContactDirectory { config: config.clone(), path: path.clone(), cache: CachedContacts::default() };
+        let now = Utc::now();
+        assert!(directory.refresh_due(now) && directory.is_stale(now));
+
+        let dispatch = CommunicationContact {
+            id: "c1".to_string(),
+            name: "Dispatch".to_string(),
+            phone_number: "+15550100".to_string(),
+            email: None,
+            contact_type: "dispatch".to_string(),
+            can_receive_sms: true,
+            can_receive_calls: true,
+            site_id: None,
+            notes: None,
+        };
+        directory.replace(vec![dispatch], now).await.unwrap();
+
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        let reloaded = ContactDirectory { config, path, cache: serde_json::from_str(&content).unwrap() };
+        assert_eq!(reloaded.find("c1").unwrap().phone_number, "+15550100");
+        assert!(!reloaded.refresh_due(now + Duration::minutes(30)));
+        assert!(reloaded.refresh_due(now + Duration::minutes(61)));
+        assert!(!reloaded.is_stale(now + Duration::hours(2)));
+        assert!(reloaded.is_stale(now + Duration::hours(25)));
+        assert_eq!(format_age(Duration::hours(25)), "25 h");
+    }
+}
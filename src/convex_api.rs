@@ -6,7 +6,10 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use base64::{Engine as _, engine::general_purpose};
 
+use crate::api::IdempotencyKey;
 use crate::config::Config;
+use crate::incident::IncidentType;
+use crate::integrity::{ChecksumConfirmation, UploadChecksums};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConvexDeviceStatus {
@@ -18,6 +21,7 @@ pub struct ConvexDeviceStatus {
     pub longitude: Option<f64>,
     pub location_accuracy: Option<f64>,
     pub location_timestamp: Option<u64>,
+    pub location_source: Option<String>, // "gps" | "last_known" | "wifi" | "ble_gateway" | "site"
     
     // Power management (enhanced)
     pub battery_level: Option<f64>,
@@ -68,6 +72,14 @@ pub struct VideoCreateRequest {
     pub metadata: VideoMetadata,
 }
 
+/// The server's answer to `start_video_upload`
+#[derive(Debug, Clone)]
+pub struct VideoUploadTicket {
+    pub video_id: String,
+    /// Chunk size the server chose, if it overrides the proposed one
+    pub chunk_size: Option<u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VideoMetadata {
     pub codec: String,
@@ -80,11 +92,12 @@ pub struct VideoMetadata {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IncidentCreateRequest {
     pub device_id: String,
-    pub incident_type: String,
+    pub incident_type: IncidentType,
     pub button_type: String, // "single" | "double" | "long" | "triple"
     pub gps_latitude: Option<f64>,
     pub gps_longitude: Option<f64>,
     pub gps_accuracy: Option<f64>,
+    pub location_source: Option<String>,
     pub metadata: Value,
 }
 
@@ -97,6 +110,16 @@ pub struct DeviceSettings {
     pub sos_settings: SOSSettings,
     pub wifi_networks: Vec<WiFiNetwork>,
     pub power_management: PowerManagementSettings,
+    /// Site retention policy for uploaded recordings
+    pub retention: Option<RetentionSettings>,
+    /// Settings officers may not change on the device
+    pub locked_settings: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetentionSettings {
+    pub keep_local_copy: bool,
+    pub deletion_grace_hours: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -149,8 +172,10 @@ impl ConvexApiClient {
         device_serial: &str,
         factory_secret: &str,
         client_info: &HashMap<String, String>,
+        idempotency_key: &IdempotencyKey,
     ) -> Result<DeviceCredentials> {
         let args = json!({
+            "idempotencyKey": idempotency_key.as_str(),
             "appType": app_type,
             "currentVersion": current_version,
             "deviceSerial": device_serial,
@@ -201,6 +226,7 @@ impl ConvexApiClient {
             "longitude": status.longitude,
             "locationAccuracy": status.location_accuracy,
             "locationTimestamp": status.location_timestamp,
+            "locationSource": status.location_source,
             "batteryLevel": status.battery_level,
             "isCharging": status.is_charging,
             "powerSource": status.power_source,
@@ -255,11 +281,52 @@ impl ConvexApiClient {
         Ok(video_id)
     }
 
+    /// Create a video record for a chunked upload, proposing a chunk size.
+    /// The server may answer with a different one, which then applies.
+    pub async fn start_video_upload(
+        &self,
+        video_request: &VideoCreateRequest,
+        preferred_chunk_size: u64,
+    ) -> Result<VideoUploadTicket> {
+        let args = json!({
+            "deviceId": video_request.device_id,
+            "filename": video_request.filename,
+            "duration": video_request.duration,
+            "quality": video_request.quality,
+            "incidentId": video_request.incident_id,
+            "preferredChunkSize": preferred_chunk_size,
+            "metadata": {
+                "codec": video_request.metadata.codec,
+                "bitrate": video_request.metadata.bitrate,
+                "resolution": video_request.metadata.resolution,
+                "isEncrypted": video_request.metadata.is_encrypted,
+                "encryptionAlgorithm": video_request.metadata.encryption_algorithm
+            }
+        });
+
+        let result = self.convex_client
+            .mutation("createVideo", args)
+            .await
+            .context("Failed to create video record")?;
+
+        let video_id = result["videoId"].as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing videoId in response"))?
+            .to_string();
+
+        Ok(VideoUploadTicket {
+            video_id,
+            chunk_size: result["chunkSize"].as_u64(),
+        })
+    }
+
+    /// Upload one chunk. The server confirms chunks strictly in order and
+    /// ignores a repeat of a chunk it already has.
     pub async fn upload_video_chunk(
         &self,
         video_id: &str,
-        chunk_index: usize,
+        chunk_index: u32,
         chunk_data: &[u8],
+        sha256_hash: &str,
         is_last_chunk: bool,
     ) -> Result<()> {
         let chunk_base64 = general_purpose::STANDARD.encode(chunk_data);
@@ -268,6 +335,7 @@ impl ConvexApiClient {
             "videoId": video_id,
             "chunkIndex": chunk_index,
             "chunkData": chunk_base64,
+            "sha256Hash": sha256_hash,
             "isLastChunk": is_last_chunk
         });
 
@@ -279,27 +347,67 @@ impl ConvexApiClient {
         Ok(())
     }
 
-    pub async fn complete_video_upload(&self, video_id: &str) -> Result<()> {
+    /// How many chunks of `video_id` the server has confirmed, i.e. the
+    /// index an interrupted upload resumes from
+    pub async fn get_video_upload_progress(&self, video_id: &str) -> Result<u32> {
         let args = json!({
             "videoId": video_id
         });
 
-        self.convex_client
+        let result = self.convex_client
+            .query("getVideoUploadProgress", args)
+            .await
+            .context("Failed to get video upload progress")?;
+
+        result["confirmedChunks"].as_u64()
+            .map(|chunks| chunks as u32)
+            .ok_or_else(|| anyhow::anyhow!("Missing confirmedChunks in response"))
+    }
+
+    /// Finish a video upload, handing the server the checksums to verify the
+    /// assembled file against
+    pub async fn complete_video_upload(
+        &self,
+        video_id: &str,
+        checksums: &UploadChecksums,
+    ) -> Result<ChecksumConfirmation> {
+        let args = json!({
+            "idempotencyKey": IdempotencyKey::derived("complete_video", video_id).as_str(),
+            "videoId": video_id,
+            "checksumAlgorithm": "sha256",
+            "sha256Hash": checksums.sha256,
+            "fileSize": checksums.file_size,
+            "chunkSha256Hashes": checksums.chunk_sha256
+        });
+
+        let result = self.convex_client
             .mutation("completeVideoUpload", args)
             .await
             .context("Failed to complete video upload")?;
 
-        Ok(())
+        Ok(ChecksumConfirmation {
+            verified: result["verified"].as_bool().unwrap_or(false),
+            server_sha256: result["sha256Hash"].as_str().map(|s| s.to_string()),
+            mismatched_chunks: result["mismatchedChunks"].as_array()
+                .map(|chunks| chunks.iter().filter_map(|c| c.as_u64()).map(|c| c as u32).collect())
+                .unwrap_or_default(),
+        })
     }
 
-    pub async fn create_incident(&self, incident_request: &IncidentCreateRequest) -> Result<String> {
+    pub async fn create_incident(
+        &self,
+        incident_request: &IncidentCreateRequest,
+        idempotency_key: &IdempotencyKey,
+    ) -> Result<String> {
         let args = json!({
+            "idempotencyKey": idempotency_key.as_str(),
             "deviceId": incident_request.device_id,
             "incidentType": incident_request.incident_type,
             "buttonType": incident_request.button_type,
             "gpsLatitude": incident_request.gps_latitude,
             "gpsLongitude": incident_request.gps_longitude,
             "gpsAccuracy": incident_request.gps_accuracy,
+            "locationSource": incident_request.location_source,
             "metadata": incident_request.metadata
         });
 
@@ -397,6 +505,17 @@ impl ConvexApiClient {
             }
         };
 
+        // Parse site retention policy
+        let retention = result["retention"].as_object().map(|retention| RetentionSettings {
+            keep_local_copy: retention["keepLocalCopy"].as_bool().unwrap_or(false),
+            deletion_grace_hours: retention["deletionGraceHours"].as_u64().unwrap_or(24) as u32,
+        });
+
+        // Settings the site policy takes out of the officer's hands
+        let locked_settings = result["lockedSettings"].as_array().map(|keys| {
+            keys.iter().filter_map(|key| key.as_str().map(str::to_string)).collect()
+        });
+
         Ok(DeviceSettings {
             video_quality,
             video_bitrate,
@@ -405,35 +524,18 @@ impl ConvexApiClient {
             sos_settings,
             wifi_networks,
             power_management,
+            retention,
+            locked_settings,
         })
     }
 
-    // Chunked video upload helper method
+    /// Upload a video file in chunks, resuming a previous attempt at the same
+    /// file if one was interrupted. Returns the video id.
     pub async fn upload_video_file(&self, file_path: &str, video_request: &VideoCreateRequest) -> Result<String> {
-        // 1. Create video record
-        let video_id = self.create_video(video_request).await?;
-
-        // 2. Read file and upload in chunks
-        let file_data = tokio::fs::read(file_path).await
-            .context("Failed to read video file")?;
-
-        const CHUNK_SIZE: usize = 1024 * 1024; // 1MB chunks
-        let total_chunks = (file_data.len() + CHUNK_SIZE - 1) / CHUNK_SIZE;
-
-        for (index, chunk) in file_data.chunks(CHUNK_SIZE).enumerate() {
-            let is_last_chunk = index == total_chunks - 1;
-            
-            self.upload_video_chunk(&video_id, index, chunk, is_last_chunk).await
-                .with_context(|| format!("Failed to upload chunk {}/{}", index + 1, total_chunks))?;
-            
-            tracing::info!("Uploaded chunk {}/{} for video {}", index + 1, total_chunks, video_id);
-        }
-
-        // 3. Complete upload
-        self.complete_video_upload(&video_id).await?;
-
-        tracing::info!("Successfully uploaded video file {} as {}", file_path, video_id);
-        Ok(video_id)
+        let upload = crate::video_upload::VideoUploader::new(self, &self.config)
+            .upload(file_path, video_request)
+            .await?;
+        Ok(upload.video_id)
     }
 
     // New chunked upload methods for the upload manager
@@ -470,7 +572,7 @@ impl ConvexApiClient {
         upload_session_id: &str,
         chunk_index: u32,
         chunk_data: &[u8],
-        md5_hash: &str,
+        sha256_hash: &str,
     ) -> Result<()> {
         let chunk_base64 = general_purpose::STANDARD.encode(chunk_data);
         
@@ -478,7 +580,7 @@ impl ConvexApiClient {
             "uploadSessionId": upload_session_id,
             "chunkIndex": chunk_index,
             "chunkData": chunk_base64,
-            "md5Hash": md5_hash
+            "sha256Hash": sha256_hash
         });
 
         self.convex_client
@@ -489,9 +591,21 @@ impl ConvexApiClient {
         Ok(())
     }
 
-    pub async fn complete_chunked_upload(&self, upload_session_id: &str) -> Result<String> {
+    /// Finish a chunked upload, handing the server the file and chunk
+    /// checksums to verify against what it assembled. The confirmation says
+    /// whether they matched; the video id is only returned when they did.
+    pub async fn complete_chunked_upload(
+        &self,
+        upload_session_id: &str,
+        checksums: &UploadChecksums,
+    ) -> Result<(Option<String>, ChecksumConfirmation)> {
         let args = json!({
-            "uploadSessionId": upload_session_id
+            "idempotencyKey": IdempotencyKey::derived("complete_upload", upload_session_id).as_str(),
+            "uploadSessionId": upload_session_id,
+            "checksumAlgorithm": "sha256",
+            "sha256Hash": checksums.sha256,
+            "fileSize": checksums.file_size,
+            "chunkSha256Hashes": checksums.chunk_sha256
         });
 
         let result = self.convex_client
@@ -499,11 +613,22 @@ impl ConvexApiClient {
             .await
             .context("Failed to complete chunked upload")?;
 
-        let video_id = result["videoId"].as_str()
-            .ok_or_else(|| anyhow::anyhow!("Missing videoId in response"))?
-            .to_string();
+        let confirmation = ChecksumConfirmation {
+            verified: result["verified"].as_bool().unwrap_or(false),
+            server_sha256: result["sha256Hash"].as_str().map(|s| s.to_string()),
+            mismatched_chunks: result["mismatchedChunks"].as_array()
+                .map(|chunks| chunks.iter().filter_map(|c| c.as_u64()).map(|c| c as u32).collect())
+                .unwrap_or_default(),
+        };
 
-        Ok(video_id)
+        let video_id = match confirmation.ensure_matches(checksums) {
+            Ok(()) => Some(result["videoId"].as_str()
+                .ok_or_else(|| anyhow::anyhow!("Missing videoId in response"))?
+                .to_string()),
+            Err(_) => None,
+        };
+
+        Ok((video_id, confirmation))
     }
 
     pub async fn get_upload_session_status(&self, upload_session_id: &str) -> Result<serde_json::Value> {
@@ -519,6 +644,239 @@ impl ConvexApiClient {
         Ok(result)
     }
 
+    pub async fn record_device_metrics(&self, metrics: &crate::api::DeviceMetrics) -> Result<()> {
+        let args = json!({
+            "deviceId": metrics.device_id,
+            "timestamp": metrics.timestamp.timestamp_millis(),
+            "cpuUsage": metrics.cpu_usage,
+            "memoryUsage": metrics.memory_usage,
+            "storageUsage": metrics.storage_usage,
+            "batteryLevel": metrics.battery_level,
+            "temperature": metrics.temperature,
+            "networkQuality": metrics.network_quality,
+            "activeIncidents": metrics.active_incidents
+        });
+
+        self.convex_client
+            .mutation("recordDeviceMetrics", args)
+            .await
+            .context("Failed to record device metrics")?;
+
+        Ok(())
+    }
+
+    pub async fn record_diagnostics(&self, diagnostics: &crate::diagnostics::ComprehensiveDiagnostics) -> Result<()> {
+        let args = json!({
+            "deviceId": diagnostics.device_info.device_id,
+            "generatedAt": diagnostics.timestamp.timestamp_millis(),
+            "diagnosticVersion": diagnostics.diagnostic_version,
+            "report": diagnostics
+        });
+
+        self.convex_client
+            .mutation("recordDiagnostics", args)
+            .await
+            .context("Failed to record diagnostics")?;
+
+        Ok(())
+    }
+
+    /// The operator a platform-issued token belongs to
+    pub async fn verify_operator_token(&self, device_id: &str, token: &str) -> Result<crate::access::Operator> {
+        let args = json!({
+            "deviceId": device_id,
+            "token": token
+        });
+
+        let result = self.convex_client
+            .query("verifyOperatorToken", args)
+            .await
+            .context("Failed to verify operator token")?;
+
+        serde_json::from_value(result).context("Invalid operator in response")
+    }
+
+    /// Have the platform's CA sign this device's certificate request
+    pub async fn enroll_certificate(
+        &self,
+        device_id: &str,
+        request: &crate::certificates::CertificateRequest,
+    ) -> Result<crate::certificates::IssuedCertificate> {
+        let args = json!({
+            "deviceId": device_id,
+            "csr": request.csr_pem,
+            "renewing": request.renewing
+        });
+
+        let result = self.convex_client
+            .mutation("enrollDeviceCertificate", args)
+            .await
+            .context("Failed to enroll device certificate")?;
+
+        Ok(crate::certificates::IssuedCertificate {
+            certificate_pem: result["certificate"].as_str().context("Enrollment returned no certificate")?.to_string(),
+            chain_pem: result["chain"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Open a pairing request for an administrator to approve
+    pub async fn request_pairing(&self, device_name: &str) -> Result<crate::pairing::PairingRequest> {
+        let args = json!({
+            "deviceName": device_name
+        });
+
+        let result = self.convex_client
+            .mutation("requestDevicePairing", args)
+            .await
+            .context("Failed to request pairing")?;
+
+        let expires_at = result["expiresAt"].as_i64()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .ok_or_else(|| anyhow::anyhow!("Pairing request has no expiry"))?;
+        Ok(crate::pairing::PairingRequest {
+            code: result["code"].as_str().context("Pairing request has no code")?.to_string(),
+            approval_url: result["approvalUrl"].as_str().context("Pairing request has no approval URL")?.to_string(),
+            expires_at,
+        })
+    }
+
+    pub async fn pairing_status(&self, code: &str) -> Result<crate::pairing::PairingStatus> {
+        let args = json!({
+            "code": code
+        });
+
+        let result = self.convex_client
+            .query("getDevicePairing", args)
+            .await
+            .context("Failed to check pairing")?;
+
+        use crate::pairing::PairingStatus;
+        Ok(match result["status"].as_str() {
+            Some("approved") => {
+                let credentials = &result["credentials"];
+                let field = |name: &str| -> Result<String> {
+                    credentials[name].as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| anyhow::anyhow!("Approved pairing is missing {}", name))
+                };
+                PairingStatus::Approved {
+                    credentials: DeviceCredentials {
+                        device_id: field("deviceId")?,
+                        device_key: field("deviceKey")?,
+                        site_id: field("siteId")?,
+                        tenant_id: field("tenantId")?,
+                        auth_token: field("authToken")?,
+                    },
+                }
+            }
+            Some("rejected") => PairingStatus::Rejected { reason: result["reason"].as_str().map(str::to_string) },
+            Some("expired") => PairingStatus::Expired,
+            _ => PairingStatus::Pending,
+        })
+    }
+
+    /// Position of the device from the access points around it, if the
+    /// backend knows them
+    pub async fn locate_wifi(&self, access_points: &[crate::location::AccessPoint]) -> Result<Option<crate::location::WifiPosition>> {
+        let args = json!({
+            "accessPoints": access_points.iter()
+                .map(|ap| json!({ "bssid": ap.bssid, "signal": ap.signal }))
+                .collect::<Vec<_>>()
+        });
+
+        let result = self.convex_client
+            .query("locateByWifi", args)
+            .await
+            .context("Failed to look up Wi-Fi location")?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(crate::location::WifiPosition {
+            latitude: result["latitude"].as_f64().context("Wi-Fi location has no latitude")?,
+            longitude: result["longitude"].as_f64().context("Wi-Fi location has no longitude")?,
+            accuracy: result["accuracy"].as_f64(),
+        }))
+    }
+
+    pub async fn revoke_device(&self, device_id: &str, reason: &str) -> Result<()> {
+        let args = json!({
+            "deviceId": device_id,
+            "reason": reason
+        });
+
+        self.convex_client
+            .mutation("revokeDevice", args)
+            .await
+            .context("Failed to revoke device")?;
+
+        Ok(())
+    }
+
+    pub async fn record_recording_access(&self, access: &crate::custody::RecordingAccess) -> Result<()> {
+        let args = json!({
+            "accessId": access.id,
+            "deviceId": access.device_id,
+            "segmentId": access.segment_id,
+            "incidentId": access.incident_id,
+            "kind": access.kind,
+            "operator": access.operator.as_ref().map(|operator| operator.name.clone()),
+            "operatorRole": access.operator.as_ref().map(|operator| operator.role),
+            "reason": access.reason,
+            "source": access.source,
+            "outputPath": access.output_path,
+            "accessedAt": access.accessed_at.timestamp_millis()
+        });
+
+        self.convex_client
+            .mutation("recordRecordingAccess", args)
+            .await
+            .context("Failed to record recording access")?;
+
+        Ok(())
+    }
+
+    pub async fn record_deletions(&self, records: &[crate::storage_manager::DeletedFileRecord]) -> Result<()> {
+        let deletions: Vec<Value> = records.iter().map(|record| json!({
+            "filePath": record.file_path,
+            "incidentId": record.incident_id,
+            "quality": record.quality,
+            "sizeBytes": record.size_bytes,
+            "deletedAt": record.deleted_at.timestamp_millis(),
+            "reason": record.deletion_reason
+        })).collect();
+
+        let args = json!({
+            "deviceId": records.first().map(|r| r.device_id.clone()),
+            "deletions": deletions
+        });
+
+        self.convex_client
+            .mutation("recordDeletions", args)
+            .await
+            .context("Failed to record deletions")?;
+
+        Ok(())
+    }
+
+    pub async fn acknowledge_command(&self, response: &crate::realtime::CommandResponse) -> Result<()> {
+        let args = json!({
+            "requestId": response.request_id,
+            "deviceId": response.device_id,
+            "status": response.status,
+            "result": response.result,
+            "error": response.error,
+            "timestamp": response.timestamp.timestamp_millis()
+        });
+
+        self.convex_client
+            .mutation("acknowledgeDeviceCommand", args)
+            .await
+            .context("Failed to acknowledge command")?;
+
+        Ok(())
+    }
+
     pub fn set_auth_token(&mut self, token: String) {
         self.auth_token = Some(token.clone());
         // Auth token will be used in future API calls
@@ -544,6 +902,7 @@ impl From<crate::device::DeviceStatus> for ConvexDeviceStatus {
             longitude: status.location.as_ref().map(|loc| loc.longitude),
             location_accuracy: status.location.as_ref().and_then(|loc| loc.accuracy),
             location_timestamp: Some(status.last_seen.timestamp() as u64),
+            location_source: status.location.as_ref().map(|loc| loc.source.as_str().to_string()),
             battery_level: Some(status.battery_level as f64),
             is_charging: Some(status.is_charging),
             power_source: Some(if status.is_charging { "charging".to_string() } else { "battery".to_string() }),
@@ -557,8 +916,22 @@ impl From<crate::device::DeviceStatus> for ConvexDeviceStatus {
             temperature: Some(status.temperature as f64),
             uptime: None, // Not tracked in legacy status
             memory_usage: None, // Not tracked in legacy status
-            errors: None,
-            warnings: None,
+            errors: (!status.unhealthy_services.is_empty()).then(|| status.unhealthy_services.iter()
+                .map(|task| format!("Service {} unhealthy: {}", task.name, task.last_error.as_deref().unwrap_or("stopped")))
+                .collect()),
+            warnings: {
+                let mut warnings = Vec::new();
+                if !status.not_ready_for_duty.is_empty() {
+                    warnings.push(format!(
+                        "Not ready for duty: self-test failed {}",
+                        status.not_ready_for_duty.iter().map(|check| check.as_str()).collect::<Vec<_>>().join(", "),
+                    ));
+                }
+                if status.hardware_mismatch {
+                    warnings.push("Credentials in use were issued to other hardware".to_string());
+                }
+                (!warnings.is_empty()).then_some(warnings)
+            },
             timestamp: status.last_seen.timestamp() as u64,
         }
     }
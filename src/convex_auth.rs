@@ -97,6 +97,9 @@ impl ConvexAuthenticator {
         client_info.insert("deviceName".to_string(), device_name.to_string());
         client_info.insert("siteId".to_string(), site_id.to_string());
         client_info.insert("publicKey".to_string(), public_key.clone());
+        if let Some(fingerprint) = crate::hardware_identity::HardwareIdentity::read().await.fingerprint() {
+            client_info.insert("hardwareFingerprint".to_string(), fingerprint);
+        }
 
         // Use checkVersion for factory provisioning (device-specific, NOT better-auth)
         let credentials = {
@@ -107,6 +110,7 @@ impl ConvexAuthenticator {
                 &factory_data.device_serial,
                 &factory_data.factory_secret,
                 &client_info,
+                &crate::api::IdempotencyKey::derived("register", &factory_data.device_serial),
             ).await?
         };
 
@@ -178,7 +182,7 @@ impl ConvexAuthenticator {
         }
 
         let session = self.auth_session.as_ref().unwrap();
-        let current_time = chrono::Utc::now().timestamp() as u64;
+        let current_time = crate::simulation::faults::now().timestamp() as u64;
         
         if current_time >= session.expires_at.parse::<u64>().unwrap_or(0) {
             return Ok(false);
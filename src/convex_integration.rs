@@ -8,6 +8,8 @@ use crate::config::Config;
 use crate::convex_api::ConvexApiClient;
 use crate::convex_auth::ConvexAuthenticator;
 use crate::convex_tenant::TenantManager;
+use crate::event_bus::EventBus;
+use crate::incident::IncidentType;
 use crate::config_sync::ConfigSyncManager;
 use crate::convex_subscriptions::ConvexSubscriptionManager;
 use crate::upload_manager::{UploadManager, UploadPriority};
@@ -28,6 +30,12 @@ pub struct ConvexIntegration {
 
 impl ConvexIntegration {
     pub async fn new(config: Arc<RwLock<Config>>) -> Result<Self> {
+        Self::with_event_bus(config, EventBus::default()).await
+    }
+
+    /// Build the integration publishing upload and connectivity events on
+    /// `events`, usually the device's bus
+    pub async fn with_event_bus(config: Arc<RwLock<Config>>, events: EventBus) -> Result<Self> {
         // Extract device and tenant info from config
         let (device_id, tenant_id) = {
             let config = config.read().await;
@@ -62,15 +70,19 @@ impl ConvexIntegration {
             config.clone(),
             "./data/offline_queue",
             upload_command_sender,
-        ));
+        ).with_event_bus(events.clone()));
 
         // Initialize upload manager
+        let network = config.read().await.network.clone();
         let upload_manager = Arc::new(UploadManager::new(
             api_client.clone(),
-            3, // max_concurrent_uploads
+            network.max_concurrent_uploads,
             5, // max_retries
-            1024 * 1024, // 1MB chunk_size
-        ));
+            network.upload_chunk_size,
+        )
+        .with_bandwidth_limit(network.upload_bandwidth as u64)
+        .with_incident_weight(network.incident_upload_weight)
+        .with_event_bus(events));
 
         // Initialize tenant manager
         let tenant_manager = Arc::new(RwLock::new(TenantManager::new(
@@ -208,18 +220,19 @@ impl ConvexIntegration {
     }
 
     pub async fn create_incident(&self,
-        incident_type: &str,
+        incident_type: IncidentType,
         button_type: &str,
         gps_location: Option<(f64, f64)>,
         metadata: serde_json::Value,
     ) -> Result<String> {
         let request = crate::convex_api::IncidentCreateRequest {
             device_id: self.device_id.clone(),
-            incident_type: incident_type.to_string(),
+            incident_type,
             button_type: button_type.to_string(),
             gps_latitude: gps_location.map(|loc| loc.0),
             gps_longitude: gps_location.map(|loc| loc.1),
             gps_accuracy: Some(10.0), // Default accuracy
+            location_source: gps_location.map(|_| "gps".to_string()),
             metadata,
         };
 
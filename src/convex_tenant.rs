@@ -6,6 +6,7 @@ use tokio::sync::RwLock;
 
 use crate::config::Config;
 use crate::convex_api::{ConvexApiClient, ConvexDeviceStatus};
+use crate::incident::IncidentType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TenantContext {
@@ -169,7 +170,7 @@ impl TenantManager {
 
     pub async fn create_tenant_scoped_incident(
         &self,
-        incident_type: &str,
+        incident_type: IncidentType,
         button_type: &str,
         metadata: serde_json::Value,
     ) -> Result<String> {
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::access::Operator;
+use crate::config::Config;
+use crate::encryption::MediaEncryptor;
+use crate::media::RecordingSegment;
+use crate::recording_index::RecordingIndex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingAccessKind {
+    /// Decrypted to plaintext on the device
+    Decrypted,
+    /// Copied off the device, as recorded or redacted
+    Exported,
+    /// Viewed on the device's own screen
+    Played,
+}
+
+/// One look at a recording, kept for chain-of-custody questions about who
+/// saw footage on the device, when and why. Written to the audit log and
+/// synced to the backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingAccess {
+    pub id: String,
+    pub device_id: String,
+    pub segment_id: String,
+    pub incident_id: String,
+    pub kind: RecordingAccessKind,
+    /// None when local access control is disabled
+    pub operator: Option<Operator>,
+    pub reason: String,
+    pub source: String,
+    /// Where an export was written
+    pub output_path: Option<String>,
+    pub accessed_at: DateTime<Utc>,
+}
+
+impl RecordingAccess {
+    /// Fails without a reason, so every access can be accounted for later
+    pub fn new(
+        segment: &RecordingSegment,
+        kind: RecordingAccessKind,
+        operator: Option<Operator>,
+        reason: &str,
+        source: &str,
+    ) -> Result<Self> {
+        if reason.trim().is_empty() {
+            return Err(anyhow::anyhow!("A reason is required to access a recording"));
+        }
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            device_id: segment.device_id.clone(),
+            segment_id: segment.id.clone(),
+            incident_id: segment.incident_id.clone(),
+            kind,
+            operator,
+            reason: reason.trim().to_string(),
+            source: source.to_string(),
+            output_path: None,
+            accessed_at: Utc::now(),
+        })
+    }
+
+    pub fn with_output(mut self, output: &Path) -> Self {
+        self.output_path = Some(output.to_string_lossy().to_string());
+        self
+    }
+}
+
+/// A recorded segment from the recording index
+pub async fn load_segment(segment_id: &str) -> Result<RecordingSegment> {
+    RecordingIndex::open()?.segment(segment_id)?
+        .ok_or_else(|| anyhow::anyhow!("Unknown segment {}", segment_id))
+}
+
+/// Write a plaintext copy of `segment` to `output`, decrypting it with the
+/// configured key if it was recorded encrypted
+pub async fn plaintext_copy(config: &Config, segment: &RecordingSegment, output: &Path) -> Result<()> {
+    let source = Path::new(&segment.file_path);
+    if segment.metadata.encryption_key.is_none() {
+        tokio::fs::copy(source, output).await
+            .with_context(|| format!("Failed to copy segment {}", segment.id))?;
+        return Ok(());
+    }
+
+    let key = config.encryption.key.as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Segment {} is encrypted but no key is configured", segment.id))?;
+    let mut encryptor = MediaEncryptor::new(segment.device_id.clone());
+    if let Some(password) = key.strip_prefix("password:") {
+        encryptor.initialize_with_password(password).await?;
+    } else {
+        encryptor.initialize_with_device_key(key).await?;
+    }
+    encryptor.decrypt_video_file(source, output).await
+        .with_context(|| format!("Failed to decrypt segment {}", segment.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(dir: &Path) -> RecordingSegment {
+        serde_json::from_value(serde_json::json!({
+            "id": "seg-1",
+            "incident_id": "inc-1",
+            "device_id": "dev-1",
+            "start_time": Utc::now(),
+            "end_time": null,
+            "duration": 30,
+            "file_path": dir.join("seg-1.mp4"),
+            "file_size": null,
+            "metadata": {
+                "resolution": "1920x1080",
+                "fps": 30,
+                "bitrate": 4000,
+                "codec": "h264",
+                "audio_enabled": true,
+                "audio_codec": "aac",
+                "encryption_key": null,
+                "location": null,
+                "ir_periods": []
+            },
+            "uploaded": false,
+            "quality": "high",
+            "pre_incident_segments": [],
+            "integrity": null,
+            "previous_parts": [],
+            "audio_only": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_access_requires_reason() {
+        let dir = tempfile::tempdir().unwrap();
+        let segment = segment(dir.path());
+        assert!(RecordingAccess::new(&segment, RecordingAccessKind::Played, None, "  ", "ui").is_err());
+
+        let access = RecordingAccess::new(&segment, RecordingAccessKind::Exported, None, " court request ", "cli")
+            .unwrap()
+            .with_output(&dir.path().join("export.mp4"));
+        assert_eq!(access.reason, "court request");
+        assert_eq!(access.incident_id, "inc-1");
+        assert!(access.output_path.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unencrypted_segment_is_copied() {
+        let dir = tempfile::tempdir().unwrap();
+        let segment = segment(dir.path());
+        tokio::fs::write(&segment.file_path, b"footage").await.unwrap();
+
+        let output = dir.path().join("export.mp4");
+        plaintext_copy(&Config::default(), &segment, &output).await.unwrap();
+        assert_eq!(tokio::fs::read(&output).await.unwrap(), b"footage");
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::network_status::LinkKind;
+
+/// Samples between saves. Traffic since the last save isn't lost on a
+/// restart, since the saved counters still cover it; only a reboot loses it.
+const SAMPLES_PER_SAVE: u32 = 10;
+
+/// Set while the cellular cap is used up and cellular carries the default route
+static CELLULAR_CAPPED: AtomicBool = AtomicBool::new(false);
+
+/// Hold off uploads and non-emergency streams; set by the usage sampler
+pub fn set_cellular_capped(capped: bool) {
+    CELLULAR_CAPPED.store(capped, Ordering::Relaxed);
+}
+
+/// True if sending now would run over the monthly cellular cap
+pub fn cellular_capped() -> bool {
+    CELLULAR_CAPPED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataUsageConfig {
+    pub enabled: bool,
+    /// Cellular data allowed per billing cycle, sent and received; no cap if None
+    pub cellular_cap_mb: Option<u64>,
+    /// Day of the month the carrier's billing cycle starts, 1-28
+    pub billing_day: u32,
+    /// Daily totals older than this are dropped
+    pub retention_days: u32,
+}
+
+impl Default for DataUsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cellular_cap_mb: None,
+            billing_day: 1,
+            retention_days: 400,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkUsage {
+    pub sent_bytes: u64,
+    pub received_bytes: u64,
+}
+
+impl LinkUsage {
+    pub fn total(&self) -> u64 {
+        self.sent_bytes + self.received_bytes
+    }
+
+    fn add(&mut self, other: LinkUsage) {
+        self.sent_bytes += other.sent_bytes;
+        self.received_bytes += other.received_bytes;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageByLink {
+    pub wifi: LinkUsage,
+    pub cellular: LinkUsage,
+    pub ethernet: LinkUsage,
+}
+
+impl UsageByLink {
+    fn link_mut(&mut self, link: LinkKind) -> Option<&mut LinkUsage> {
+        match link {
+            LinkKind::Wifi => Some(&mut self.wifi),
+            LinkKind::Cellular => Some(&mut self.cellular),
+            LinkKind::Ethernet => Some(&mut self.ethernet),
+            LinkKind::None => None,
+        }
+    }
+
+    fn add(&mut self, other: &UsageByLink) {
+        self.wifi.add(other.wifi);
+        self.cellular.add(other.cellular);
+        self.ethernet.add(other.ethernet);
+    }
+}
+
+/// Data used so far, reported with the device metrics so customers can
+/// reconcile carrier bills
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataUsageReport {
+    /// First day of the current billing cycle
+    pub cycle_start: NaiveDate,
+    pub today: UsageByLink,
+    pub cycle: UsageByLink,
+    pub cellular_cap_bytes: Option<u64>,
+    pub cellular_cap_reached: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UsageFile {
+    days: BTreeMap<NaiveDate, UsageByLink>,
+    /// Interface counters at the last sample, so a restart doesn't count
+    /// traffic twice
+    counters: HashMap<String, LinkUsage>,
+}
+
+/// Bytes sent and received per link type per day, kept across restarts
+#[derive(Debug)]
+pub struct DataUsageLedger {
+    config: DataUsageConfig,
+    path: PathBuf,
+    usage: UsageFile,
+    unsaved_samples: u32,
+}
+
+impl DataUsageLedger {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("data_usage.json")
+    }
+
+    pub async fn load(config: DataUsageConfig) -> Self {
+        Self::load_from(Self::default_path(), config).await
+    }
+
+    pub async fn load_from(path: PathBuf, config: DataUsageConfig) -> Self {
+        let usage = tokio::fs::read_to_string(&path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { config, path, usage, unsaved_samples: 0 }
+    }
+
+    pub async fn save(&mut self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_string_pretty(&self.usage)?).await?;
+        self.unsaved_samples = 0;
+        Ok(())
+    }
+
+    /// Add the traffic since the last sample to `today`. Counters that went
+    /// backwards were reset by a reboot or the interface coming back up.
+    /// True once enough samples have built up to be worth saving.
+    pub fn record_counters(&mut self, counters: &HashMap<String, LinkUsage>, today: NaiveDate) -> bool {
+        let day = self.usage.days.entry(today).or_default();
+        for (interface, current) in counters {
+            let previous = self.usage.counters.get(interface).copied().unwrap_or_default();
+            let delta = |now: u64, before: u64| if now >= before { now - before } else { now };
+            let used = LinkUsage {
+                sent_bytes: delta(current.sent_bytes, previous.sent_bytes),
+                received_bytes: delta(current.received_bytes, previous.received_bytes),
+            };
+            if let Some(usage) = day.link_mut(LinkKind::from_interface(interface)) {
+                usage.add(used);
+            }
+        }
+        self.usage.counters = counters.clone();
+
+        let oldest = today - Duration::days(self.config.retention_days as i64);
+        self.usage.days.retain(|date, _| *date >= oldest);
+
+        self.unsaved_samples += 1;
+        self.unsaved_samples >= SAMPLES_PER_SAVE
+    }
+
+    /// The billing cycle `today` falls in starts on the last billing day
+    /// on or before it
+    pub fn cycle_start(&self, today: NaiveDate) -> NaiveDate {
+        let day = self.config.billing_day.clamp(1, 28);
+        if today.day() >= day {
+            today.with_day(day).unwrap_or(today)
+        } else {
+            let last_month = today.with_day(1).unwrap_or(today) - Duration::days(1);
+            last_month.with_day(day).unwrap_or(last_month)
+        }
+    }
+
+    pub fn report(&self, today: NaiveDate) -> DataUsageReport {
+        let cycle_start = self.cycle_start(today);
+        let mut cycle = UsageByLink::default();
+        for usage in self.usage.days.range(cycle_start..=today).map(|(_, usage)| usage) {
+            cycle.add(usage);
+        }
+        let cellular_cap_bytes = self.config.cellular_cap_mb.map(|mb| mb * 1024 * 1024);
+        DataUsageReport {
+            cycle_start,
+            today: self.usage.days.get(&today).copied().unwrap_or_default(),
+            cycle,
+            cellular_cap_bytes,
+            cellular_cap_reached: cellular_cap_bytes.is_some_and(|cap| cycle.cellular.total() >= cap),
+        }
+    }
+}
+
+/// Bytes received and sent per interface in `/proc/net/dev` contents,
+/// leaving out loopback
+pub fn parse_net_dev(contents: &str) -> HashMap<String, LinkUsage> {
+    contents.lines().skip(2).filter_map(|line| {
+        let (name, rest) = line.split_once(':')?;
+        let name = name.trim();
+        if name == "lo" {
+            return None;
+        }
+        let fields: Vec<u64> = rest.split_whitespace().filter_map(|field| field.parse().ok()).collect();
+        // Eight receive columns, then the transmit columns
+        let usage = LinkUsage { received_bytes: *fields.first()?, sent_bytes: *fields.get(8)? };
+        Some((name.to_string(), usage))
+    }).collect()
+}
+
+pub async fn read_counters() -> HashMap<String, LinkUsage> {
+    tokio::fs::read_to_string("/proc/net/dev").await
+        .map(|contents| parse_net_dev(&contents))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NET_DEV: &str = "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:    5000      50    0    0    0     0          0         0     5000      50    0    0    0     0       0          0
+ wlan0: 1000000    900    0    0    0     0          0         0   400000     300    0    0    0     0       0          0
+ wwan0:   20000     20    0    0    0     0          0         0    80000      60    0    0    0     0       0          0
+";
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_counters_are_split_by_link_and_survive_resets() {
+        let counters = parse_net_dev(NET_DEV);
+        assert_eq!(counters.len(), 2);
+        assert_eq!(counters["wlan0"], LinkUsage { sent_bytes: 400_000, received_bytes: 1_000_000 });
+
+        let config = DataUsageConfig { cellular_cap_mb: Some(1), ..DataUsageConfig::default() };
+        let mut ledger = DataUsageLedger { config, path: PathBuf::new(), usage: UsageFile::default(), unsaved_samples: 0 };
+        ledger.record_counters(&counters, date(10));
+
+        let mut later = counters.clone();
+        later.insert("wwan0".to_string(), LinkUsage { sent_bytes: 1_000_000, received_bytes: 100_000 });
+        later.insert("wlan0".to_string(), LinkUsage { sent_bytes: 500, received_bytes: 0 });
+        ledger.record_counters(&later, date(10));
+
+        let report = ledger.report(date(10));
+        assert_eq!(report.today.wifi, LinkUsage { sent_bytes: 400_500, received_bytes: 1_000_000 });
+        assert_eq!(report.cycle.cellular, LinkUsage { sent_bytes: 1_000_000, received_bytes: 100_000 });
+        assert!(report.cellular_cap_reached);
+    }
+
+    #[test]
+    fn test_billing_cycle_starts_on_the_billing_day() {
+        let config = DataUsageConfig { billing_day: 15, ..DataUsageConfig::default() };
+        let ledger = DataUsageLedger { config, path: PathBuf::new(), usage: UsageFile::default(), unsaved_samples: 0 };
+        assert_eq!(ledger.cycle_start(date(20)), date(15));
+        assert_eq!(ledger.cycle_start(date(3)), NaiveDate::from_ymd_opt(2026, 2, 15).unwrap());
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+use crate::config::Config;
+
+/// Directories under the working directory holding recordings, logs and
+/// local state; all of them are erased on decommission
+pub const WIPE_DIRS: &[&str] = &["recordings", "logs", "data"];
+
+const OVERWRITE_CHUNK: usize = 1024 * 1024;
+
+/// The token that confirms a decommission, e.g. `WIPE-3F9A2C1B`. It is tied
+/// to the device and the UTC day, so one read off another device's screen or
+/// an old ticket won't wipe this one.
+pub fn confirmation_token(device_id: &str, date: NaiveDate) -> String {
+    let digest = Sha256::digest(format!("decommission:{}:{}", device_id, date).as_bytes());
+    format!("WIPE-{}", hex::encode_upper(&digest[..4]))
+}
+
+pub fn verify_token(device_id: &str, token: &str) -> Result<()> {
+    let expected = confirmation_token(device_id, Utc::now().date_naive());
+    if !token.trim().eq_ignore_ascii_case(&expected) {
+        return Err(anyhow::anyhow!("Confirmation token does not match; request a new one"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WipeReport {
+    pub files_erased: usize,
+    pub bytes_erased: u64,
+    /// Whether freed blocks were discarded with `fstrim`
+    pub trimmed: bool,
+    /// Files that couldn't be erased, with the reason
+    pub failures: Vec<String>,
+    /// Recordings the server hadn't confirmed and uploads still queued,
+    /// erased anyway because the decommission was forced
+    pub unsynced: Vec<String>,
+}
+
+/// Recordings under `root` the server hasn't confirmed, plus files with an
+/// upload still in progress. Erasing these loses the only copy.
+pub async fn unsynced_files(root: &Path) -> Result<Vec<String>> {
+    let confirmed = crate::storage_manager::confirmed_upload_paths().await?;
+    let mut unsynced = std::collections::BTreeSet::new();
+
+    let mut pending = vec![root.join("recordings")];
+    while let Some(dir) = pending.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                pending.push(path);
+            } else if path.extension().and_then(|e| e.to_str()) != Some("json")
+                && !crate::storage_manager::is_confirmed(&confirmed, &path)
+            {
+                unsynced.insert(path.display().to_string());
+            }
+        }
+    }
+
+    // Interrupted or queued uploads keep their state here until the server verifies them
+    if let Ok(mut entries) = tokio::fs::read_dir(root.join("data").join("video_uploads")).await {
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(bytes) = tokio::fs::read(entry.path()).await else { continue };
+            let state: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+            if let Some(file_path) = state.get("file_path").and_then(|v| v.as_str()) {
+                unsynced.insert(file_path.to_string());
+            }
+        }
+    }
+
+    Ok(unsynced.into_iter().collect())
+}
+
+/// Overwrites files before unlinking them. Flash storage remaps writes, so
+/// overwriting alone isn't enough there: freed blocks are also TRIMmed, and
+/// recordings encrypted with the device key are unreadable once the key is
+/// shredded regardless.
+pub struct SecureEraser {
+    passes: u32,
+    trim: bool,
+}
+
+impl SecureEraser {
+    pub fn new(passes: u32, trim: bool) -> Self {
+        Self { passes: passes.max(1), trim }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.security.wipe_passes, config.security.wipe_trim)
+    }
+
+    /// Overwrite `path` with random data on every pass but the last, which
+    /// writes zeros, then remove it. Returns the bytes overwritten.
+    pub async fn erase_file(&self, path: &Path) -> Result<u64> {
+        let size = tokio::fs::metadata(path).await?.len();
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .await
+            .with_context(|| format!("Failed to open {} for erasure", path.display()))?;
+
+        let mut buffer = vec![0u8; OVERWRITE_CHUNK];
+        for pass in 0..self.passes {
+            let random = pass + 1 < self.passes;
+            file.seek(std::io::SeekFrom::Start(0)).await?;
+            let mut remaining = size;
+            while remaining > 0 {
+                let len = remaining.min(OVERWRITE_CHUNK as u64) as usize;
+                if random {
+                    rand::thread_rng().fill_bytes(&mut buffer[..len]);
+                } else {
+                    buffer[..len].fill(0);
+                }
+                file.write_all(&buffer[..len]).await?;
+                remaining -= len as u64;
+            }
+            file.sync_data().await?;
+        }
+
+        file.set_len(0).await?;
+        drop(file);
+        tokio::fs::remove_file(path).await?;
+        Ok(size)
+    }
+
+    /// Erase every file under `dir`, then the directories themselves
+    pub async fn erase_dir(&self, dir: &Path, report: &mut WipeReport) {
+        let mut pending = vec![dir.to_path_buf()];
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        while let Some(current) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&current).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    report.failures.push(format!("{}: {}", current.display(), e));
+                    continue;
+                }
+            };
+            dirs.push(current);
+
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                match entry.file_type().await {
+                    Ok(kind) if kind.is_dir() => pending.push(path),
+                    Ok(_) => match self.erase_file(&path).await {
+                        Ok(bytes) => {
+                            report.files_erased += 1;
+                            report.bytes_erased += bytes;
+                        }
+                        Err(e) => report.failures.push(format!("{}: {:#}", path.display(), e)),
+                    },
+                    Err(e) => report.failures.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+        }
+
+        // Deepest first, so each directory is empty by the time it's removed
+        for dir in dirs.iter().rev() {
+            let _ = tokio::fs::remove_dir(dir).await;
+        }
+    }
+
+    /// Erase `WIPE_DIRS` under `root`, then discard the freed blocks
+    pub async fn erase_all(&self, root: &Path) -> WipeReport {
+        let mut report = WipeReport::default();
+        for dir in WIPE_DIRS {
+            self.erase_dir(&root.join(dir), &mut report).await;
+        }
+
+        if self.trim {
+            report.trimmed = match tokio::process::Command::new("fstrim").arg(root).output().await {
+                Ok(output) => output.status.success(),
+                Err(e) => {
+                    tracing::debug!("fstrim unavailable: {}", e);
+                    false
+                }
+            };
+        }
+        report
+    }
+}
+
+/// Drop every key and credential from `config`. Recordings encrypted with
+/// them can't be read afterwards, and the device can't reach the backend
+/// until it is provisioned again.
+pub fn shred_keys(config: &mut Config) {
+    shred_credentials(config);
+    // Recordings after this are encrypted with a new key, never in the clear
+    config.encryption.enabled |= config.encryption.key.is_some();
+    config.encryption.key = None;
+}
+
+/// Drop the platform credentials, keeping the recording encryption key
+pub fn shred_credentials(config: &mut Config) {
+    config.device_key = None;
+    config.auth_token = None;
+    config.api_key = None;
+    config.sites.clear();
+}
+
+/// `config` with every credential and key removed, so the device comes back
+/// up waiting for factory provisioning. Hardware settings and the factory
+/// secret are kept.
+pub fn factory_state(config: &Config) -> Config {
+    let mut config = config.clone();
+    shred_keys(&mut config);
+    config.device_id = None;
+    config.site_id = None;
+    config.tenant_id = None;
+    config.security.pin_code = None;
+    config.webhooks.clear();
+    config.hardware_binding.bound_fingerprint = None;
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_is_per_device_and_day() {
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let token = confirmation_token("dev-1", day);
+        assert!(token.starts_with("WIPE-"));
+        assert_eq!(token.len(), "WIPE-".len() + 8);
+        assert_ne!(token, confirmation_token("dev-2", day));
+        assert_ne!(token, confirmation_token("dev-1", day.succ_opt().unwrap()));
+
+        let today = confirmation_token("dev-1", Utc::now().date_naive());
+        assert!(verify_token("dev-1", &today.to_lowercase()).is_ok());
+        assert!(verify_token("dev-2", &today).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_erase_removes_files_and_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let recordings = root.path().join("recordings").join("inc-1");
+        tokio::fs::create_dir_all(&recordings).await.unwrap();
+        tokio::fs::write(recordings.join("segment.mp4"), vec![7u8; 3000]).await.unwrap();
+        tokio::fs::create_dir_all(root.path().join("logs")).await.unwrap();
+        tokio::fs::write(root.path().join("logs").join("audit.jsonl"), b"{}").await.unwrap();
+
+        let report = SecureEraser::new(2, false).erase_all(root.path()).await;
+        assert_eq!(report.files_erased, 2);
+        assert_eq!(report.bytes_erased, 3002);
+        assert!(report.failures.is_empty());
+        assert!(!root.path().join("recordings").exists());
+        assert!(!root.path().join("logs").exists());
+    }
+
+    #[test]
+    fn test_factory_state_drops_credentials() {
+        let mut config = Config::default();
+        config.device_id = Some("dev-1".to_string());
+        config.device_key = Some("secret".to_string());
+        config.encryption.key = Some("key".to_string());
+        config.factory_secret = Some("factory".to_string());
+
+        let reset = factory_state(&config);
+        assert!(!reset.is_provisioned());
+        assert!(reset.device_key.is_none());
+        assert!(reset.encryption.key.is_none());
+        assert_eq!(reset.factory_secret.as_deref(), Some("factory"));
+    }
+}
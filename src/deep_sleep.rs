@@ -0,0 +1,182 @@
+//! Deep sleep for multi-day deployments. Recording, streaming, the
+//! pre-incident buffer, the display and the LEDs all stop. The device wakes
+//! on the configured sources: a button, motion, the charger being
+//! connected, or a scheduled time. With `system_suspend` the whole system
+//! also suspends to RAM, and the RTC alarm wakes it for the schedule.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities::PowerManagementInfo;
+use crate::hardware::HardwareEvent;
+
+const RTC_WAKEALARM: &str = "/sys/class/rtc/rtc0/wakealarm";
+const POWER_STATE: &str = "/sys/power/state";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeSource {
+    Button,
+    Motion,
+    Charger,
+    /// The `wake_times` schedule
+    Rtc,
+}
+
+impl WakeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WakeSource::Button => "button",
+            WakeSource::Motion => "motion",
+            WakeSource::Charger => "charger",
+            WakeSource::Rtc => "rtc",
+        }
+    }
+
+    /// The source a hardware event would wake the device through
+    pub fn for_event(event: &HardwareEvent) -> Option<Self> {
+        match event {
+            HardwareEvent::ButtonPressed { .. } => Some(WakeSource::Button),
+            HardwareEvent::MotionDetected { .. } | HardwareEvent::MovementDetected { .. } => Some(WakeSource::Motion),
+            HardwareEvent::ChargingConnected => Some(WakeSource::Charger),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeReason {
+    Source(WakeSource),
+    /// Woken by a remote command or the CLI
+    Command,
+    /// The system resumed from suspend for a reason the kernel didn't say
+    Resume,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeepSleepConfig {
+    pub wake_sources: Vec<WakeSource>,
+    /// Local times to wake at, as `HH:MM`
+    pub wake_times: Vec<String>,
+    /// Stay awake this long after a scheduled wake, to check in and upload,
+    /// before sleeping again
+    pub scheduled_awake_minutes: u64,
+    /// Sleep after this long idle on battery; None to sleep only on request
+    pub idle_minutes: Option<u64>,
+    /// Suspend the system to RAM as well
+    pub system_suspend: bool,
+}
+
+impl Default for DeepSleepConfig {
+    fn default() -> Self {
+        Self {
+            wake_sources: vec![WakeSource::Button, WakeSource::Charger, WakeSource::Rtc],
+            wake_times: Vec::new(),
+            scheduled_awake_minutes: 10,
+            idle_minutes: None,
+            system_suspend: false,
+        }
+    }
+}
+
+impl DeepSleepConfig {
+    pub fn wakes_on(&self, source: WakeSource) -> bool {
+        self.wake_sources.contains(&source)
+    }
+
+    pub fn parse_wake_times(&self) -> Result<Vec<NaiveTime>> {
+        self.wake_times.iter()
+            .map(|time| NaiveTime::parse_from_str(time, "%H:%M")
+                .with_context(|| format!("Invalid wake time {:?}, expected HH:MM", time)))
+            .collect()
+    }
+
+    /// Configured sources this device can't wake from. Under system suspend
+    /// the schedule needs an RTC alarm; while the process keeps running,
+    /// every source is handled by the device itself.
+    pub fn unsupported_sources(&self, power: &PowerManagementInfo) -> Vec<WakeSource> {
+        if !self.system_suspend {
+            return Vec::new();
+        }
+        self.wake_sources.iter()
+            .copied()
+            .filter(|source| !power.wake_sources.iter().any(|available| available == source.as_str()))
+            .collect()
+    }
+}
+
+/// The next of `times` strictly after `now`, today or tomorrow
+pub fn next_wake<Tz: TimeZone>(now: DateTime<Tz>, times: &[NaiveTime]) -> Option<DateTime<Tz>> {
+    let today = now.date_naive();
+    [today, today + Duration::days(1)].into_iter()
+        .flat_map(|day| times.iter().map(move |time| day.and_time(*time)))
+        .filter_map(|naive| now.timezone().from_local_datetime(&naive).earliest())
+        .filter(|at| *at > now)
+        .min()
+}
+
+/// A deep sleep in progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SleepSession {
+    pub since: DateTime<Utc>,
+    pub entered_by: String,
+    pub next_scheduled_wake: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SleepStatus {
+    pub sleeping: Option<SleepSession>,
+    pub wake_sources: Vec<WakeSource>,
+    pub last_wake: Option<(WakeReason, DateTime<Utc>)>,
+    pub power_management: PowerManagementInfo,
+}
+
+pub fn next_scheduled_wake(config: &DeepSleepConfig) -> Result<Option<DateTime<Utc>>> {
+    if !config.wakes_on(WakeSource::Rtc) {
+        return Ok(None);
+    }
+    let times = config.parse_wake_times()?;
+    Ok(next_wake(Local::now(), &times).map(|at| at.with_timezone(&Utc)))
+}
+
+/// Set the RTC to wake the system at `at`, clearing any earlier alarm first
+pub async fn arm_rtc_alarm(at: DateTime<Utc>) -> Result<()> {
+    tokio::fs::write(RTC_WAKEALARM, "0").await.context("No RTC wake alarm")?;
+    tokio::fs::write(RTC_WAKEALARM, at.timestamp().to_string()).await
+        .context("Failed to arm the RTC wake alarm")
+}
+
+/// Suspend to RAM. Returns once the system has resumed.
+pub async fn suspend_system() -> Result<()> {
+    tokio::fs::write(POWER_STATE, "mem").await.context("Failed to suspend")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn test_next_wake_and_supported_sources() {
+        let zone = FixedOffset::east_opt(3600).unwrap();
+        let config = DeepSleepConfig { wake_times: vec!["07:00".to_string(), "19:30".to_string()], ..DeepSleepConfig::default() };
+        let times = config.parse_wake_times().unwrap();
+
+        let morning = zone.with_ymd_and_hms(2024, 3, 1, 6, 0, 0).unwrap();
+        assert_eq!(next_wake(morning, &times), Some(zone.with_ymd_and_hms(2024, 3, 1, 7, 0, 0).unwrap()));
+        let night = zone.with_ymd_and_hms(2024, 3, 1, 22, 0, 0).unwrap();
+        assert_eq!(next_wake(night, &times), Some(zone.with_ymd_and_hms(2024, 3, 2, 7, 0, 0).unwrap()));
+        assert!(DeepSleepConfig { wake_times: vec!["7am".to_string()], ..config.clone() }.parse_wake_times().is_err());
+
+        let power = PowerManagementInfo {
+            cpu_scaling_available: false,
+            sleep_modes: vec!["mem".to_string()],
+            wake_sources: vec!["button".to_string(), "charger".to_string()],
+        };
+        assert!(config.unsupported_sources(&power).is_empty());
+        let suspending = DeepSleepConfig { system_suspend: true, ..config };
+        assert_eq!(suspending.unsupported_sources(&power), vec![WakeSource::Rtc]);
+    }
+}
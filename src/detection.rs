@@ -0,0 +1,303 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::buffer::CircularBuffer;
+use crate::resource_manager::{InferenceBudget, ResourceThrottle};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionConfig {
+    pub enabled: bool,
+    /// ONNX object detection model with NMS applied, producing one
+    /// `[x1, y1, x2, y2, score, class]` row per detection
+    pub model_path: String,
+    /// Class names, indexed by the model's class ids
+    pub labels: Vec<String>,
+    /// Square input size the model expects
+    pub input_size: u32,
+    pub sample_interval_ms: u64,
+    pub min_confidence: f32,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model_path: "models/detector.onnx".to_string(),
+            labels: ["person", "bicycle", "car", "motorcycle", "bus", "truck", "knife", "gun"]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            input_size: 320,
+            sample_interval_ms: 500,
+            min_confidence: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionClass {
+    Person,
+    Vehicle,
+    /// Weapon-shaped object; the model can't tell a real weapon from a replica
+    Weapon,
+}
+
+impl DetectionClass {
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.to_lowercase().as_str() {
+            "person" | "pedestrian" => Some(Self::Person),
+            "car" | "truck" | "bus" | "motorcycle" | "bicycle" | "vehicle" => Some(Self::Vehicle),
+            "knife" | "gun" | "pistol" | "rifle" | "weapon" => Some(Self::Weapon),
+            _ => None,
+        }
+    }
+
+    /// Signal name used by the incident rules
+    pub fn signal(&self) -> &'static str {
+        match self {
+            Self::Person => "person_detected",
+            Self::Vehicle => "vehicle_detected",
+            Self::Weapon => "weapon_detected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionEvent {
+    pub class: DetectionClass,
+    pub label: String,
+    pub confidence: f32,
+    /// Normalised `[x1, y1, x2, y2]`
+    pub bbox: [f32; 4],
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Turn raw model rows into events, keeping the most confident per class
+pub fn parse_detections(rows: &[[f32; 6]], config: &DetectionConfig) -> Vec<DetectionEvent> {
+    let size = config.input_size.max(1) as f32;
+    let timestamp = chrono::Utc::now();
+    let mut best: Vec<DetectionEvent> = Vec::new();
+
+    for row in rows {
+        let [x1, y1, x2, y2, score, class_id] = *row;
+        if score < config.min_confidence || class_id < 0.0 {
+            continue;
+        }
+        let Some(label) = config.labels.get(class_id as usize) else {
+            continue;
+        };
+        let Some(class) = DetectionClass::from_label(label) else {
+            continue;
+        };
+
+        let event = DetectionEvent {
+            class,
+            label: label.clone(),
+            confidence: score,
+            bbox: [x1 / size, y1 / size, x2 / size, y2 / size],
+            timestamp,
+        };
+        match best.iter_mut().find(|e| e.class == class) {
+            Some(existing) if existing.confidence < score => *existing = event,
+            Some(_) => {}
+            None => best.push(event),
+        }
+    }
+
+    best
+}
+
+#[cfg(feature = "inference")]
+mod model {
+    use anyhow::{Result, Context};
+    use ort::session::Session;
+    use ort::value::Tensor;
+
+    pub struct Model {
+        session: Session,
+        input_size: u32,
+    }
+
+    impl Model {
+        pub fn load(path: &str, input_size: u32) -> Result<Self> {
+            let session = Session::builder()?
+                .with_intra_threads(1)?
+                .commit_from_file(path)
+                .context("Failed to load detection model")?;
+            Ok(Self { session, input_size })
+        }
+
+        /// Run on a packed RGB frame of `input_size` square
+        pub fn infer(&mut self, rgb: &[u8]) -> Result<Vec<[f32; 6]>> {
+            let size = self.input_size as usize;
+            let plane = size * size;
+            // HWC bytes to normalised CHW floats
+            let mut input = vec![0f32; 3 * plane];
+            for (i, pixel) in rgb.chunks_exact(3).enumerate() {
+                for c in 0..3 {
+                    input[c * plane + i] = pixel[c] as f32 / 255.0;
+                }
+            }
+
+            let tensor = Tensor::from_array(([1usize, 3, size, size], input.into_boxed_slice()))?;
+            let outputs = self.session.run(ort::inputs![tensor]?)?;
+            let (_, data) = outputs[0].try_extract_raw_tensor::<f32>()?;
+
+            Ok(data.chunks_exact(6)
+                .map(|row| [row[0], row[1], row[2], row[3], row[4], row[5]])
+                .collect())
+        }
+    }
+}
+
+#[cfg(not(feature = "inference"))]
+mod model {
+    use anyhow::Result;
+
+    pub struct Model;
+
+    impl Model {
+        pub fn load(_path: &str, _input_size: u32) -> Result<Self> {
+            Err(anyhow::anyhow!("This build has no inference support (enable the `inference` feature)"))
+        }
+
+        pub fn infer(&mut self, _rgb: &[u8]) -> Result<Vec<[f32; 6]>> {
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Where sampled frames come from. The RAM pre-incident buffer is decoded
+/// when running; otherwise the camera is read directly while it is free.
+#[derive(Clone)]
+pub struct FrameSource {
+    pub buffer: CircularBuffer,
+    pub camera_path: String,
+    pub camera_busy: Arc<AtomicBool>,
+}
+
+impl FrameSource {
+    /// One RGB frame scaled to `size` square, or None when no frame can be
+    /// had without disturbing a recording
+    async fn grab(&self, size: u32) -> Result<Option<Vec<u8>>> {
+        let scale = format!("scale={}:{}", size, size);
+
+        let output = if let Some(gop) = self.buffer.latest_gop().await {
+            let mut child = Command::new("ffmpeg")
+                .args(["-hide_banner", "-loglevel", "error", "-f", "mpegts", "-i", "pipe:0"])
+                .args(["-frames:v", "1", "-vf", &scale, "-f", "rawvideo", "-pix_fmt", "rgb24", "pipe:1"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .context("Failed to start frame decoder")?;
+            if let Some(mut stdin) = child.stdin.take() {
+                // ffmpeg may stop reading after one frame
+                let _ = stdin.write_all(&gop).await;
+            }
+            child.wait_with_output().await?
+        } else if self.buffer.is_memory_backed() || self.camera_busy.load(Ordering::Relaxed) {
+            return Ok(None);
+        } else {
+            Command::new("ffmpeg")
+                .args(["-hide_banner", "-loglevel", "error", "-f", "v4l2", "-i", &self.camera_path])
+                .args(["-frames:v", "1", "-vf", &scale, "-f", "rawvideo", "-pix_fmt", "rgb24", "pipe:1"])
+                .output()
+                .await
+                .context("Failed to grab camera frame")?
+        };
+
+        let expected = (size * size * 3) as usize;
+        if output.stdout.len() < expected {
+            return Ok(None);
+        }
+        Ok(Some(output.stdout[..expected].to_vec()))
+    }
+}
+
+/// Run the detector on sampled frames, sending an event for each object
+/// class found. Sampling slows down or pauses as the resource manager
+/// reports load.
+pub fn spawn_detector(
+    config: DetectionConfig,
+    source: FrameSource,
+    throttle: ResourceThrottle,
+) -> Result<(mpsc::UnboundedReceiver<DetectionEvent>, JoinHandle<()>)> {
+    let mut model = model::Model::load(&config.model_path, config.input_size)?;
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+    let handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(config.sample_interval_ms));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut skipped = 0u32;
+
+        loop {
+            interval.tick().await;
+
+            match throttle.inference_budget().await {
+                InferenceBudget::Paused => continue,
+                InferenceBudget::Run { skip } if skipped < skip => {
+                    skipped += 1;
+                    continue;
+                }
+                InferenceBudget::Run { .. } => skipped = 0,
+            }
+
+            let frame = match source.grab(config.input_size).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::debug!("Detection frame grab failed: {}", e);
+                    continue;
+                }
+            };
+
+            // Inference is CPU bound; keep it off the async workers
+            let result = tokio::task::block_in_place(|| model.infer(&frame));
+            match result {
+                Ok(rows) => {
+                    for event in parse_detections(&rows, &config) {
+                        if event_tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Object detection failed: {}", e),
+            }
+        }
+    });
+
+    Ok((event_rx, handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keeps_best_per_class() {
+        let config = DetectionConfig::default();
+        let rows = [
+            [0.0, 0.0, 160.0, 320.0, 0.9, 0.0],  // person
+            [10.0, 0.0, 100.0, 100.0, 0.6, 0.0], // weaker person
+            [0.0, 0.0, 32.0, 32.0, 0.4, 7.0],    // gun below threshold
+            [0.0, 0.0, 64.0, 64.0, 0.8, 5.0],    // truck
+            [0.0, 0.0, 64.0, 64.0, 0.8, 42.0],   // unknown class id
+        ];
+
+        let events = parse_detections(&rows, &config);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].class, DetectionClass::Person);
+        assert_eq!(events[0].confidence, 0.9);
+        assert_eq!(events[0].bbox, [0.0, 0.0, 0.5, 1.0]);
+        assert_eq!(events[1].class, DetectionClass::Vehicle);
+    }
+}
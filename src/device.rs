@@ -1,27 +1,94 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock, RwLockReadGuard, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::Mutex;
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Timelike, Utc};
 
-use crate::auth::Authenticator;
-use crate::convex_auth::ConvexAuthenticator;
+use crate::backend::{BackendKind, PlatformBackend};
+use crate::convex_api::DeviceCredentials;
 use crate::config::Config;
-use crate::hardware::{HardwareInterface, HardwareEvent, LedState};
-use crate::media::MediaRecorder;
-use crate::status::StatusReporter;
-use crate::incident::IncidentManager;
+use crate::error_handling::{DeviceError, HardwareError};
+use crate::hardware::{ButtonType, HardwareInterface, HardwareEvent};
+use crate::hardware::charging::ChargingSafetyMonitor;
+use crate::hardware::led::{LedController, LedIndicator};
+use crate::hardware::buzzer::{BuzzerController, Tone, ToneEvent};
+use crate::hardware::display::{DisplayManager, DisplayStatus};
+use crate::hardware::brightness::{BrightnessLevel, BrightnessStatus, DimmingInputs};
+use crate::hardware::haptics::{HapticController, HapticPattern};
+use crate::media::{MediaRecorder, RecordingMode};
+use crate::incident::{ActiveIncident, ActiveIncidentHandle, IncidentCreateRequest, IncidentSeverity, IncidentType};
+use crate::journal::{JournalOp, OfflineJournal, ReplayReport};
+use crate::sites::SiteProfile;
+use crate::decommission::{SecureEraser, WipeReport};
+use crate::access::{AccessControl, Credential, Operator, PrivilegedOperation};
+use crate::custody::{RecordingAccess, RecordingAccessKind};
+use crate::i18n::Localizer;
+use crate::theme::{Theme, ThemeController, ThemeHandle, ThemeMode};
+use crate::network_status::{LinkKind, NetworkMonitor, NetworkStatusHandle};
+use crate::pairing::{PairingRequest, PairingStatus};
+use crate::playback::Player;
+use crate::recording_index::{IncidentRecord, RecordingIndex, SavedBookmark, SegmentQuery};
+use crate::tamper::{SignedTamperPolicy, TamperPolicy, TamperResponder};
+use crate::remote_support::{RemoteSupport, SupportArtifact, SupportRequest};
+use crate::status_report::{StatusDeltaEncoder, StatusReport};
+use crate::supervisor::{RestartPolicy, TaskHealth, TaskSupervisor};
+use crate::startup::{LazyStart, Subsystem, Subsystems};
+use crate::data_usage::{DataUsageLedger, DataUsageReport};
 use crate::buffer::CircularBuffer;
+use crate::cancellation::Canceller;
 use crate::audio::AudioManager;
-use crate::gps::GpsManager;
+use crate::audio_meter::{AudioLevelHandle, AudioLevelMeter};
+use crate::gps::{FixQuality, GpsManager};
+use crate::location::{LocationResolver, LocationSource, ResolvedLocation};
+use crate::metrics_history::{MetricsHistory, StorageSeries};
 use crate::validation::InputValidator;
-use crate::streaming::StreamingManager;
+use crate::streaming::{StreamQuality, StreamingManager};
+use crate::streaming::viewers::{ViewerPresence, ViewerPresenceHandle};
+use crate::streaming::pip::PipLayout;
 use crate::resource_manager::{ResourceManager, ResourceLimits};
-use crate::diagnostics::{DiagnosticsRunner, ComprehensiveDiagnostics};
+use crate::diagnostics::{DiagnosticsRunner, ComprehensiveDiagnostics, CrashReport, RecordingPerformance};
+use crate::diagnostics_archive::{DiagnosticsArchive, DiagnosticsUpload};
+use crate::communications::{self, CommunicationEvent, CommunicationKind, PendingCommunication};
+use crate::contact_directory::{ContactDirectory, ContactList};
+use crate::audio_presets::{AudioPreset, AudioPresetLibrary, PresetSyncReport};
+use crate::speaker_loudness::{AmbientTracker, SpeakerCalibration, ToneResponse, CALIBRATION_FREQUENCIES_HZ};
+use crate::preflight::PreflightFailure;
+use crate::recording_time::{self, DrawModel, LowRemainingAlarm, RemainingRecording};
+use crate::deep_sleep::{self, SleepSession, SleepStatus, WakeReason, WakeSource};
+use crate::button_actions::{ButtonAction, ButtonActionMap, PressKind, PressTracker};
+use crate::capabilities::PowerManagementInfo;
+use crate::communication_policy::{CommunicationGuard, PolicySnapshot, QuotaUsage};
+use crate::certificates::CertificateStore;
+use crate::loop_recording::{self, LockWindow, LOOP_INCIDENT_ID};
+use crate::recording_policy::{PolicyDecision, RecordingPolicy, RecordingPolicyStore, SignedRecordingPolicy};
+use crate::hardware_identity::{BindingCheck, HardwareIdentity};
+use crate::sms_commands::{SmsCommand, SmsCommandState};
+use crate::self_test::{CheckResult, CheckStatus, SelfTestCheck, SelfTestReport, SelfTestTrigger};
 use crate::storage_manager::{StorageManager, DeletedFileRecord};
+use crate::audit::AuditLog;
+use crate::event_bus::{BusEvent, EventBus, IncidentEvent, RecordingEvent, Topic};
+use crate::legal_hold::{LegalHold, LegalHolds};
+use crate::redaction::{RedactionReport, Redactor};
+use crate::detection::{self, DetectionEvent, FrameSource};
+use crate::incident_rules::RuleEngine;
+use crate::vehicle::{self, VehicleEvent};
+use crate::camera::frame_pool::FrameFeed;
+use crate::camera::hotplug::{HotplugEvent, HotplugMonitor, MediaDeviceKind};
+use crate::camera::controls::{CameraControls, CameraControlKind, ControlValue};
+use crate::camera::night_mode::{self, LightCondition, LightReading, NightModeController};
 use crate::sentry_integration;
 
+const MAX_CRASH_REPORTS: usize = 20;
+/// Restart a background loop that panicked, giving up if it keeps doing so
+const MONITOR_RESTART: RestartPolicy = RestartPolicy::OnFailure { max_restarts: 5, backoff: std::time::Duration::from_secs(1) };
+/// Tone the self-test plays and listens for on the microphone
+const SELF_TEST_TONE_HZ: u32 = 1000;
+const SELF_TEST_TONE_MS: u64 = 800;
+/// How far above the background the test tone must peak to count as heard
+const SELF_TEST_LOOPBACK_DB: f32 = 10.0;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceStatus {
     pub device_id: String,
@@ -34,6 +101,27 @@ pub struct DeviceStatus {
     pub last_seen: DateTime<Utc>,
     pub location: Option<Location>,
     pub incident_active: bool,
+    pub stealth_mode: bool,
+    pub night_mode: bool,
+    /// Measured encoder performance while recording
+    pub recording_performance: Option<RecordingPerformance>,
+    /// Background tasks that failed and are restarting or were given up on
+    pub unhealthy_services: Vec<TaskHealth>,
+    /// Critical checks that failed in the last self-test; empty when ready for duty
+    #[serde(default)]
+    pub not_ready_for_duty: Vec<SelfTestCheck>,
+    /// The credentials in use were issued to other hardware
+    #[serde(default)]
+    pub hardware_mismatch: bool,
+    #[serde(default)]
+    pub deep_sleep: bool,
+    /// Estimated recording time left on storage and battery
+    #[serde(default)]
+    pub recording_remaining: Option<RemainingRecording>,
+    #[serde(default)]
+    pub recording_paused: bool,
+    #[serde(default)]
+    pub muted: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +138,27 @@ pub struct Location {
     pub longitude: f64,
     pub altitude: Option<f64>,
     pub accuracy: Option<f64>,
+    pub hdop: Option<f64>,
+    /// GPS sources only
+    pub fix_quality: Option<FixQuality>,
+    pub source: LocationSource,
+    /// When the position was measured
+    pub fix_time: DateTime<Utc>,
+}
+
+impl From<ResolvedLocation> for Location {
+    fn from(location: ResolvedLocation) -> Self {
+        Self {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            altitude: location.altitude,
+            accuracy: location.accuracy,
+            hdop: location.hdop,
+            fix_quality: location.fix_quality,
+            source: location.source,
+            fix_time: location.timestamp,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,378 +200,1341 @@ pub struct AudioStatus {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkStatus {
     pub connected: bool,
+    /// Wi-Fi signal level in dBm
     pub signal_strength: Option<i32>,
-    pub ip_address: Option<String>,
+    /// Interface carrying the default route
+    pub interface: Option<String>,
+    /// Measured upload throughput in kbps
     pub upload_speed: Option<u32>,
+    pub upload_queue_depth: usize,
+    /// Backend endpoints whose circuit breaker is open or half-open
+    pub backend_circuits: Vec<crate::circuit_breaker::EndpointCircuit>,
 }
 
+/// A handle to the device. Clones share the same device, so the background
+/// loops, services, UI and CLI each hold their own handle instead of queueing
+/// on one lock around the whole device. Each subsystem has its own lock, held
+/// only while that subsystem is in use.
+#[derive(Clone)]
 pub struct BodycamDevice {
-    config: Config,
-    auth: Authenticator,
-    convex_auth: Option<ConvexAuthenticator>, // New: Convex authentication
-    hardware: Box<dyn HardwareInterface>,
-    recorder: Option<MediaRecorder>,
+    inner: Arc<DeviceInner>,
+}
+
+struct DeviceInner {
+    config: RwLock<Config>,
+    /// REST or Convex, chosen by `config.backend`
+    backend: RwLock<Arc<dyn PlatformBackend>>,
+    reporting: Mutex<Reporting>,
+    hardware: Arc<dyn HardwareInterface>,
+    leds: Mutex<LedController>,
+    /// Manual brightness, replacing the dimming schedule until cleared
+    brightness_override: std::sync::Mutex<Option<u8>>,
+    brightness: std::sync::Mutex<Option<BrightnessLevel>>,
+    buzzer: BuzzerController,
+    display: Mutex<DisplayManager>,
+    haptics: HapticController,
+    camera_controls: CameraControls,
+    night_mode: std::sync::Mutex<NightModeController>,
+    /// Held while a recording starts, stops or is supervised
+    recorder: Mutex<Option<MediaRecorder>>,
+    /// Full-size recording of the second camera, alongside `recorder`
+    secondary_recorder: Mutex<Option<MediaRecorder>>,
+    is_recording: AtomicBool,
+    /// Set while the recording in progress is paused
+    recording_paused: AtomicBool,
+    /// Encoder performance as of the last supervision pass, so status
+    /// doesn't wait on a recording that is starting or stopping
+    recording_performance: std::sync::Mutex<Option<RecordingPerformance>>,
     buffer: CircularBuffer,
-    status_reporter: StatusReporter,
-    incident_manager: IncidentManager,
     audio_manager: AudioManager,
+    /// Opened the first time the level is needed
+    audio_meter: std::sync::OnceLock<Option<AudioLevelMeter>>,
+    mic_warning_raised: AtomicBool,
+    /// Recent encoder crashes, reported in diagnostics
+    crash_reports: std::sync::Mutex<Vec<CrashReport>>,
     gps_manager: GpsManager,
-    streaming_manager: StreamingManager,
+    location_resolver: LocationResolver,
+    streaming_manager: Mutex<StreamingManager>,
+    /// Dispatcher viewers and talk-back, readable without the streaming lock
+    viewers: ViewerPresenceHandle,
+    /// Presence last reflected on the LEDs and display
+    stream_presence: std::sync::Mutex<ViewerPresence>,
     resource_manager: ResourceManager,
-    storage_manager: StorageManager,
-    device_id: Option<String>,
-    device_key: Option<String>,
-    is_recording: bool,
-    current_incident_id: Option<String>,
+    storage_manager: Mutex<StorageManager>,
+    audit_log: RwLock<AuditLog>,
+    /// Local roles for privileged operations, and the UI login
+    access: Mutex<AccessControl>,
+    incident_rules: std::sync::Mutex<RuleEngine>,
+    /// Signed policy deciding how to respond to tampering
+    tamper: Mutex<TamperResponder>,
+    /// Signed no-record and must-record zones and hours
+    recording_policy: Mutex<RecordingPolicyStore>,
+    /// What the recording policy last decided, for the display and UI
+    recording_restriction: std::sync::Mutex<PolicyDecision>,
+    charging_safety: Mutex<ChargingSafetyMonitor>,
+    /// Bytes sent and received per link type, for carrier bills and the cap
+    data_usage: Mutex<DataUsageLedger>,
+    /// Metrics and errors kept locally for trends; None if it couldn't be opened
+    metrics_history: Option<MetricsHistory>,
+    /// Last self-test, loaded at startup so a failure still flags the device
+    self_test: std::sync::Mutex<Option<SelfTestReport>>,
+    /// Incident messages and calls whose delivery or answer is still followed
+    pending_communications: std::sync::Mutex<Vec<PendingCommunication>>,
+    /// The platform's contact directory, cached for when it can't be reached
+    contacts: Mutex<ContactDirectory>,
+    /// Cached number allowlist and daily limits, checked before every
+    /// message and call
+    communication_guard: Mutex<CommunicationGuard>,
+    /// Fingerprint of this hardware; None if no identifier is readable
+    hardware_fingerprint: Option<String>,
+    /// Hash of each identifier of this hardware that could be read
+    hardware_components: std::collections::BTreeMap<String, String>,
+    /// The configured credentials were issued to other hardware
+    hardware_mismatch: AtomicBool,
+    /// Client certificate from the platform, renewed before it expires
+    certificates: Mutex<CertificateStore>,
+    /// Alert sounds downloaded from the platform
+    audio_presets: Mutex<AudioPresetLibrary>,
+    /// This unit's measured speaker loudness
+    speaker_calibration: Mutex<SpeakerCalibration>,
+    /// Set while a self-test runs, so the Record button answers the test
+    /// instead of starting a recording
+    self_test_running: AtomicBool,
+    /// Set by the tamper policy; buttons other than emergency are ignored
+    controls_locked: AtomicBool,
+    /// Strings for prompts and alerts in the configured language
+    i18n: RwLock<Arc<Localizer>>,
+    /// UI theme, switched by the ambient light sensor in auto mode
+    theme: std::sync::Mutex<ThemeController>,
+    /// Link, signal and upload progress for the network panel
+    network: NetworkMonitor,
+    /// Hardware, incident, upload and network events shared with every subsystem
+    events: EventBus,
+    /// Set while a recording holds the camera, so samplers leave it alone
+    camera_busy: Arc<AtomicBool>,
+    /// Frames from a camera the UI preview is capturing, recorded from
+    /// instead of opening the camera a second time
+    frame_feed: std::sync::Mutex<Option<FrameFeed>>,
+    /// Cancels uploads, diagnostics and recording startup in progress
+    canceller: Canceller,
+    current_incident_id: std::sync::Mutex<Option<String>>,
+    /// Type, severity and start of the incident in progress, for the UI banner
+    active_incident: ActiveIncidentHandle,
+    stealth_mode: AtomicBool,
+    /// Tones, voice prompts and non-critical playback are silenced
+    muted: AtomicBool,
+    /// The synced button mapping, replaced when settings sync
+    button_actions: RwLock<ButtonActionMap>,
+    button_presses: std::sync::Mutex<PressTracker>,
+    /// Why the last recording couldn't start, shown on the display until
+    /// one starts
+    preflight_failure: std::sync::Mutex<Option<PreflightFailure>>,
+    /// Battery draw while recording, for the recording time left
+    draw_model: std::sync::Mutex<DrawModel>,
+    recording_remaining: std::sync::Mutex<Option<RemainingRecording>>,
+    low_remaining: std::sync::Mutex<LowRemainingAlarm>,
+    /// Set while in deep sleep
+    deep_sleep: std::sync::Mutex<Option<SleepSession>>,
+    last_wake: std::sync::Mutex<Option<(WakeReason, DateTime<Utc>)>>,
+    /// When to sleep again after a scheduled wake
+    scheduled_sleep_at: std::sync::Mutex<Option<DateTime<Utc>>>,
+    /// Last button press or duty, for the idle timeout
+    last_activity: std::sync::Mutex<DateTime<Utc>>,
+    /// Subsystems that have been started, now or on demand
+    started: LazyStart,
+}
+
+/// Backend calls waiting for connectivity, and the last status the backend
+/// acknowledged. Locked together since deltas are only sent once the journal
+/// has drained.
+struct Reporting {
+    /// Backend calls waiting for connectivity, replayed in order
+    journal: OfflineJournal,
+    /// Tracks the last acknowledged status so reports can be sent as deltas
+    status_encoder: StatusDeltaEncoder,
+}
+
+impl Reporting {
+    /// Journal a backend call, then deliver everything pending oldest first.
+    /// While offline the call just waits in the journal for the next attempt.
+    async fn submit(&mut self, backend: &dyn PlatformBackend, op: JournalOp) -> Result<()> {
+        self.journal.append(op).await?;
+        if let Err(e) = self.journal.replay(backend).await {
+            tracing::debug!("{} backend call(s) journaled while offline: {:#}", self.journal.len(), e);
+        }
+        Ok(())
+    }
+}
+
+/// Lower each quality in the ladder to what its camera can capture
+async fn limit_to_cameras(config: &mut Config) {
+    if config.simulation.enabled {
+        return;
+    }
+    let cameras = crate::camera::quality_ladder::detect_ladder_cameras(config).await;
+    for downgrade in crate::camera::quality_ladder::apply_camera_limits(&mut config.recording, &cameras) {
+        tracing::warn!(
+            "Camera can't capture {} at {}@{}fps, recording {}@{}fps instead",
+            crate::camera::quality_ladder::quality_name(&downgrade.requested.quality),
+            downgrade.requested.resolution, downgrade.requested.fps,
+            downgrade.applied.resolution, downgrade.applied.fps,
+        );
+    }
 }
 
 impl BodycamDevice {
-    pub async fn new(mut config: Config) -> Result<Self> {
+    pub async fn new(config: Config) -> Result<Self> {
+        Self::with_subsystems(config, Subsystems::all()).await
+    }
+
+    /// Build a device that only starts `subsystems` now, leaving the rest
+    /// to start the first time they're needed
+    pub async fn with_subsystems(config: Config, subsystems: Subsystems) -> Result<Self> {
+        let hardware = crate::hardware::create_hardware_interface(config.simulation.enabled);
+        Self::build(config, hardware, subsystems).await
+    }
+
+    /// Build a device on the given hardware, e.g. `MockHardware` in tests
+    pub async fn with_hardware(config: Config, hardware: Box<dyn HardwareInterface>) -> Result<Self> {
+        Self::build(config, hardware, Subsystems::all()).await
+    }
+
+    async fn build(mut config: Config, mut hardware: Box<dyn HardwareInterface>, subsystems: Subsystems) -> Result<Self> {
         let simulation = config.simulation.enabled;
-        let mut hardware = crate::hardware::create_hardware_interface(simulation);
-        
+
         // Skip hardware initialization in simulation mode for now
         // The hardware interface will use simulation defaults
+        let hardware_config = crate::hardware::HardwareConfig::load(&crate::hardware::HardwareConfig::default_path()).await?;
         if !simulation {
-            let hardware_config = crate::hardware::HardwareConfig::default();
-            hardware.init(&hardware_config).await?;
+            hardware.init(&hardware_config).await.map_err(|e| HardwareError::Init {
+                component: "hardware".to_string(),
+                message: format!("{:#}", e),
+            })?;
         }
-        
-        let auth = Authenticator::new(config.clone());
-        
-        // Initialize Convex auth if URL is configured
-        let convex_auth = if config.convex_url.is_some() {
-            Some(ConvexAuthenticator::new(config.clone())?)
-        } else {
-            None
-        };
-        
-        let status_reporter = StatusReporter::new(config.clone());
-        let incident_manager = IncidentManager::new(config.clone());
+        let started = LazyStart::default();
+        if subsystems.contains(Subsystem::CameraLimits) {
+            // Before the buffer and recorder take a copy of the ladder
+            limit_to_cameras(&mut config).await;
+            started.mark_started(Subsystem::CameraLimits);
+        }
+        for problem in crate::camera::quality_ladder::validate_ladder(&config.recording, None) {
+            if problem.is_error() {
+                tracing::error!("Quality ladder: {}", problem);
+            } else {
+                tracing::warn!("Quality ladder: {}", problem);
+            }
+        }
+        let led_controller = LedController::new(&hardware_config.leds);
+        let buzzer = BuzzerController::new(hardware_config.buzzer.clone());
+        let display = DisplayManager::new(hardware_config.display.clone());
+        let haptics = HapticController::new(hardware_config.haptics.clone());
+
+        // Before the backend takes a copy of the credentials
+        let identity = HardwareIdentity::read().await;
+        let hardware_fingerprint = identity.fingerprint();
+        let hardware_components = identity.components();
+        let binding = crate::hardware_identity::check_binding(&config.hardware_binding, &identity);
+        let blocked = matches!(binding, BindingCheck::Mismatch { .. }) && config.hardware_binding.block_on_mismatch;
+        if blocked {
+            // Only the platform credentials; recordings stay encrypted
+            crate::decommission::shred_credentials(&mut config);
+        } else if binding == BindingCheck::Matches {
+            // Follow identifiers that changed or were recorded before they were kept
+            config.hardware_binding.bound_components.extend(hardware_components.clone());
+        }
+
+        let backend = crate::backend::create_backend(&config)?;
+        let journal = OfflineJournal::open().await?;
+        let status_encoder = StatusDeltaEncoder::new(config.monitoring.full_status_every);
+
+        let camera_controls = CameraControls::new(
+            format!("/dev/video{}", config.camera.device_index),
+            config.camera.ir_cut_path.clone(),
+            simulation,
+        );
+        let night_mode = NightModeController::new(config.night_mode.clone());
+
         let audio_manager = AudioManager::new(config.clone());
-        let gps_manager = GpsManager::new(config.hardware.gps);
-        let streaming_manager = StreamingManager::new(config.clone());
-        
-        // Check if device is provisioned
-        let device_id = config.device_id.clone();
-        let device_key = config.device_key.clone();
-        
+        let gps_manager = GpsManager::new(config.hardware.gps).with_filter(config.gps_filter.clone());
+        let canceller = Canceller::new();
+        let streaming_manager = StreamingManager::new(config.clone()).with_canceller(canceller.clone());
+        let viewers = streaming_manager.presence_handle();
+
+        let device_id = config.device_id.clone().unwrap_or_default();
+
         // Initialize resource manager
         let resource_manager = ResourceManager::new(
-            device_id.clone().unwrap_or_default(),
+            device_id.clone(),
             Some(ResourceLimits::default())
         );
-        
-        let mut device = Self {
-            config,
-            auth,
-            convex_auth,
-            hardware,
-            recorder: None,
-            buffer: CircularBuffer::new(config.clone(), device_id.clone().unwrap_or_default()),
-            status_reporter,
-            incident_manager,
-            audio_manager,
-            gps_manager,
-            streaming_manager,
-            resource_manager,
-            device_id,
-            device_key,
-            is_recording: false,
-            current_incident_id: None,
+
+        let storage_manager = StorageManager::new(device_id.clone(), config.clone());
+        let audit_log = AuditLog::new(device_id.clone())?;
+        let access = AccessControl::new(config.access.clone());
+        let tamper = TamperResponder::load(config.security.policy_public_key.as_deref()).await;
+        let recording_policy = RecordingPolicyStore::load(config.security.policy_public_key.as_deref()).await;
+        let charging_safety = ChargingSafetyMonitor::load(config.power_management.charging_safety.clone()).await;
+        let data_usage = DataUsageLedger::load(config.data_usage.clone()).await;
+        let last_self_test = SelfTestReport::load().await;
+        let contacts = ContactDirectory::load(config.contact_directory.clone()).await;
+        let communication_guard = CommunicationGuard::load(config.communication_policy.clone()).await;
+        let certificates = CertificateStore::load(config.certificates.clone()).await;
+        let audio_presets = AudioPresetLibrary::load(config.audio_presets.clone()).await;
+        let speaker_calibration = SpeakerCalibration::load().await;
+        let draw_model = DrawModel::new(&config.recording.remaining);
+        let button_actions = ButtonActionMap::from_settings(&config.buttons.actions);
+        let press_window = std::time::Duration::from_millis(config.buttons.multi_press_ms);
+        let i18n = Localizer::load(&config.i18n).await;
+        let metrics_history = if config.metrics_history.enabled {
+            match MetricsHistory::open(&config.metrics_history) {
+                Ok(history) => Some(history),
+                Err(e) => {
+                    tracing::warn!("Metrics history unavailable: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
         };
 
-        // Start hardware monitoring
-        device.start_monitoring().await?;
-        
-        // Start resource manager monitoring
-        device.resource_manager.start_monitoring().await?;
-        
-        // Start status reporting
-        device.start_status_reporting().await?;
-        
-        // Start GPS monitoring
-        device.gps_manager.start_monitoring().await?;
-        
-        // Start pre-incident buffer if enabled
-        if device.config.recording.pre_incident_buffer_seconds > 0 {
-            device.buffer.start_buffering().await?;
+        let device = Self {
+            inner: Arc::new(DeviceInner {
+                buffer: CircularBuffer::new(config.clone(), device_id),
+                location_resolver: LocationResolver::new(config.location_fallback.clone()),
+                incident_rules: std::sync::Mutex::new(RuleEngine::new(config.incident_rules.clone())),
+                theme: std::sync::Mutex::new(ThemeController::new(config.theme.clone())),
+                config: RwLock::new(config),
+                backend: RwLock::new(Arc::from(backend)),
+                reporting: Mutex::new(Reporting { journal, status_encoder }),
+                hardware: Arc::from(hardware),
+                leds: Mutex::new(led_controller),
+                brightness_override: std::sync::Mutex::new(None),
+                brightness: std::sync::Mutex::new(None),
+                buzzer,
+                display: Mutex::new(display),
+                haptics,
+                camera_controls,
+                night_mode: std::sync::Mutex::new(night_mode),
+                recorder: Mutex::new(None),
+                secondary_recorder: Mutex::new(None),
+                is_recording: AtomicBool::new(false),
+                recording_paused: AtomicBool::new(false),
+                recording_performance: std::sync::Mutex::new(None),
+                audio_manager,
+                audio_meter: std::sync::OnceLock::new(),
+                mic_warning_raised: AtomicBool::new(false),
+                crash_reports: std::sync::Mutex::new(Vec::new()),
+                gps_manager,
+                streaming_manager: Mutex::new(streaming_manager),
+                viewers,
+                stream_presence: std::sync::Mutex::new(ViewerPresence::default()),
+                resource_manager,
+                storage_manager: Mutex::new(storage_manager),
+                audit_log: RwLock::new(audit_log),
+                access: Mutex::new(access),
+                tamper: Mutex::new(tamper),
+                recording_policy: Mutex::new(recording_policy),
+                recording_restriction: std::sync::Mutex::new(PolicyDecision::Allow),
+                charging_safety: Mutex::new(charging_safety),
+                data_usage: Mutex::new(data_usage),
+                metrics_history,
+                self_test: std::sync::Mutex::new(last_self_test),
+                self_test_running: AtomicBool::new(false),
+                pending_communications: std::sync::Mutex::new(Vec::new()),
+                contacts: Mutex::new(contacts),
+                communication_guard: Mutex::new(communication_guard),
+                hardware_fingerprint,
+                hardware_components,
+                hardware_mismatch: AtomicBool::new(false),
+                certificates: Mutex::new(certificates),
+                audio_presets: Mutex::new(audio_presets),
+                speaker_calibration: Mutex::new(speaker_calibration),
+                controls_locked: AtomicBool::new(false),
+                i18n: RwLock::new(Arc::new(i18n)),
+                network: NetworkMonitor::new(),
+                events: EventBus::default(),
+                camera_busy: Arc::new(AtomicBool::new(false)),
+                frame_feed: std::sync::Mutex::new(None),
+                canceller,
+                current_incident_id: std::sync::Mutex::new(None),
+                active_incident: ActiveIncidentHandle::default(),
+                stealth_mode: AtomicBool::new(false),
+                muted: AtomicBool::new(false),
+                button_actions: RwLock::new(button_actions),
+                button_presses: std::sync::Mutex::new(PressTracker::new(press_window)),
+                preflight_failure: std::sync::Mutex::new(None),
+                draw_model: std::sync::Mutex::new(draw_model),
+                recording_remaining: std::sync::Mutex::new(None),
+                low_remaining: std::sync::Mutex::new(LowRemainingAlarm::default()),
+                deep_sleep: std::sync::Mutex::new(None),
+                last_wake: std::sync::Mutex::new(None),
+                scheduled_sleep_at: std::sync::Mutex::new(None),
+                last_activity: std::sync::Mutex::new(Utc::now()),
+                started,
+            }),
+        };
+
+        if let BindingCheck::Mismatch { bound, current } = binding {
+            device.report_hardware_mismatch(&bound, &current, blocked).await;
         }
-        
+
+        for subsystem in Subsystem::ALL {
+            if subsystems.contains(subsystem) {
+                device.ensure_started(subsystem).await?;
+            }
+        }
+
         Ok(device)
     }
 
-    pub async fn register(&mut self, device_name: &str, site_id: &str) -> Result<()> {
+    /// Start a subsystem unless it's already running. One-shot commands
+    /// build the device without them and start what they touch here.
+    pub async fn ensure_started(&self, subsystem: Subsystem) -> Result<()> {
+        self.inner.started.ensure(subsystem, || async {
+            tracing::debug!("Starting {}", subsystem.name());
+            match subsystem {
+                Subsystem::Monitoring => {
+                    self.start_monitoring().await?;
+                    // Following the link and uploads
+                    self.inner.network.start(&self.inner.events);
+                    self.start_status_reporting();
+                    self.start_self_test_schedule();
+                    self.start_sms_commands();
+                    self.start_loop_recording();
+                    self.start_adaptive_volume();
+                    self.start_deep_sleep_monitor();
+                    if !self.not_ready_for_duty().is_empty() {
+                        self.set_led_indicator(LedIndicator::NotReady, true).await?;
+                    }
+                    self.audio_meter();
+                }
+                Subsystem::Gps => self.inner.gps_manager.start_monitoring().await?,
+                Subsystem::Resources => self.inner.resource_manager.start_monitoring().await?,
+                Subsystem::PreIncidentBuffer => {
+                    if self.read_config().pre_incident_buffer_seconds() > 0 {
+                        if let Err(e) = self.inner.buffer.restore().await {
+                            tracing::warn!("Failed to restore pre-incident buffer: {}", e);
+                        }
+                        self.inner.buffer.start_buffering().await?;
+                    }
+                }
+                Subsystem::CameraLimits => self.apply_camera_limits().await,
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Lower any quality a camera can't capture to what it can. Only the
+    /// running config changes; the file keeps what was asked for.
+    async fn apply_camera_limits(&self) {
+        let mut config = self.config();
+        limit_to_cameras(&mut config).await;
+        self.inner.config.write().unwrap().recording = config.recording;
+    }
+
+    /// The microphone level meter, opened on first use. None if audio is
+    /// disabled or the microphone couldn't be opened.
+    fn audio_meter(&self) -> Option<&AudioLevelMeter> {
+        self.inner.audio_meter.get_or_init(|| {
+            let config = self.read_config();
+            if !config.audio.enabled {
+                return None;
+            }
+            AudioLevelMeter::start(config.audio.device_path.clone(), config.audio.silence_threshold_db, config.simulation.enabled)
+                .map_err(|e| tracing::warn!("Audio level metering unavailable: {}", e))
+                .ok()
+        }).as_ref()
+    }
+
+    /// The device behind a background loop's handle, unless it has been dropped
+    fn upgrade(inner: &Weak<DeviceInner>) -> Option<Self> {
+        inner.upgrade().map(|inner| Self { inner })
+    }
+
+    /// Never held across an await; clone what's needed first
+    fn read_config(&self) -> RwLockReadGuard<'_, Config> {
+        self.inner.config.read().unwrap()
+    }
+
+    /// A snapshot of the current settings
+    pub fn config(&self) -> Config {
+        self.read_config().clone()
+    }
+
+    /// Change the settings and keep them across restarts
+    async fn update_config(&self, change: impl FnOnce(&mut Config)) -> Result<Config> {
+        let config = {
+            let mut config = self.inner.config.write().unwrap();
+            change(&mut config);
+            config.clone()
+        };
+        config.save(std::path::Path::new("config.toml")).await?;
+        Ok(config)
+    }
+
+    /// None until the device is provisioned
+    pub fn device_id(&self) -> Option<String> {
+        self.read_config().device_id.clone()
+    }
+
+    fn audit_log(&self) -> AuditLog {
+        self.inner.audit_log.read().unwrap().clone()
+    }
+
+    pub async fn register(&self, device_name: &str, site_id: &str) -> Result<()> {
         let _transaction = sentry_integration::start_transaction("device.register", "device");
-        
+
         // Validate inputs
         InputValidator::validate_device_name(device_name)?;
         InputValidator::validate_site_id(site_id)?;
-        
+
         println!("Registering device '{}' with site '{}'", device_name, site_id);
         sentry_integration::add_device_breadcrumb("register_start", Some(&format!("name: {}, site_id: {}", device_name, site_id)));
-        
-        // Use Convex auth if available, otherwise fall back to legacy auth
-        let credentials = if let Some(ref mut convex_auth) = self.convex_auth {
-            // Use Convex authentication system
-            tracing::info!("Using Convex authentication for device registration");
-            convex_auth.factory_provision(device_name, site_id).await?
-        } else {
-            // Use legacy authentication system
-            tracing::info!("Using legacy authentication for device registration");
-            let legacy_credentials = self.auth.provision_device(device_name, site_id).await?;
-            crate::convex_api::DeviceCredentials {
-                device_id: legacy_credentials.device_id,
-                device_key: legacy_credentials.device_key,
-                site_id: legacy_credentials.site_id,
-                tenant_id: legacy_credentials.tenant_id,
-                auth_token: "legacy".to_string(), // Placeholder
-            }
-        };
-        
-        self.device_id = Some(credentials.device_id.clone());
-        self.device_key = Some(credentials.device_key.clone());
-        
-        self.config.device_id = Some(credentials.device_id);
-        self.config.device_key = Some(credentials.device_key);
-        self.config.site_id = Some(credentials.site_id);
-        self.config.tenant_id = Some(credentials.tenant_id);
-        self.config.auth_token = Some(credentials.auth_token);
-        
-        self.config.save(std::path::Path::new("config.toml")).await?;
-        
+
+        let backend = self.backend();
+        tracing::info!("Registering through the {:?} backend", backend.kind());
+        let credentials = backend.register(device_name, site_id).await?;
+        self.store_credentials(credentials).await?;
+
+        sentry_integration::add_device_breadcrumb("register_complete", Some("success"));
+        println!("Device successfully registered!");
+        Ok(())
+    }
+
+    async fn store_credentials(&self, credentials: DeviceCredentials) -> Result<()> {
+        self.update_config(|config| {
+            // Keep the previous site's credentials so the device can switch back
+            crate::sites::remember_active(config);
+
+            config.device_id = Some(credentials.device_id.clone());
+            config.device_key = Some(credentials.device_key.clone());
+            config.site_id = Some(credentials.site_id.clone());
+            config.tenant_id = Some(credentials.tenant_id.clone());
+            config.auth_token = Some(credentials.auth_token.clone());
+            // The credentials now belong to this hardware
+            self.bind_to_this_hardware(config);
+        }).await?;
+        self.inner.hardware_mismatch.store(false, Ordering::Relaxed);
+        self.inner.audit_log.write().unwrap().set_device_id(credentials.device_id.clone());
+
         // Update Sentry context with new device information
         sentry_integration::set_device_context(
             Some(&credentials.device_id),
             Some(&credentials.site_id),
             Some(&credentials.tenant_id),
         );
-        
-        sentry_integration::add_device_breadcrumb("register_complete", Some("success"));
-        println!("Device successfully registered!");
         Ok(())
     }
 
-    pub async fn start_recording(
-        &mut self,
-        duration: Option<u64>,
-        incident_id: Option<String>
-    ) -> Result<()> {
-        let _transaction = sentry_integration::start_transaction("device.start_recording", "recording");
-        
-        if self.is_recording {
-            return Err(anyhow::anyhow!("Already recording"));
-        }
-        
-        sentry_integration::add_device_breadcrumb("start_recording", 
-            Some(&format!("duration: {:?}, incident_id: {:?}", duration, incident_id)));
-        
-        // Validate inputs
-        if let Some(duration) = duration {
-            InputValidator::validate_recording_duration(duration)?;
-        }
-        
-        if let Some(ref incident_id) = incident_id {
-            InputValidator::validate_uuid(incident_id)?;
-        }
+    fn bind_to_this_hardware(&self, config: &mut Config) {
+        config.hardware_binding.bound_fingerprint = self.inner.hardware_fingerprint.clone();
+        config.hardware_binding.bound_components = self.inner.hardware_components.clone();
+    }
 
-        if !self.config.is_provisioned() {
-            return Err(anyhow::anyhow!("Device not provisioned"));
+    /// The configuration was copied from, or restored onto, other hardware.
+    /// Reported locally, in status reports and to Sentry; with
+    /// `block_on_mismatch` the platform credentials have already been dropped.
+    async fn report_hardware_mismatch(&self, bound: &str, current: &str, blocked: bool) {
+        self.inner.hardware_mismatch.store(true, Ordering::Relaxed);
+        let error = format!(
+            "Credentials were issued to other hardware ({} here, bound to {}){}",
+            &current[..12.min(current.len())],
+            &bound[..12.min(bound.len())],
+            if blocked { "; not using them" } else { "" },
+        );
+        tracing::error!("{}", error);
+        crate::sentry_capture_message!(&error, sentry::Level::Error, "security" => "hardware_mismatch");
+        let _ = self.audit_log().record("hardware_mismatch", "hardware_binding", serde_json::json!({
+            "bound_fingerprint": bound,
+            "current_fingerprint": current,
+            "credentials_blocked": blocked,
+        })).await;
+    }
+
+    /// Enroll for a client certificate, or renew it before it expires, then
+    /// alert as expiry nears. Checks at most every
+    /// `certificates.check_interval_hours`; a failed renewal is retried at
+    /// the next check.
+    async fn maintain_certificate(&self) {
+        let now = Utc::now();
+        let mut store = self.inner.certificates.lock().await;
+        if !store.check_due(now) {
+            return;
         }
+        let Some(device_id) = self.device_id() else { return };
 
-        let incident_id = incident_id.or_else(|| {
-            if self.current_incident_id.is_none() {
-                Some(Uuid::new_v4().to_string())
-            } else {
-                self.current_incident_id.clone()
+        let mut error = None;
+        if store.renewal_due(now) {
+            if let Err(e) = self.renew_certificate(&mut store, &device_id, now).await {
+                tracing::warn!("Device certificate renewal failed: {:#}", e);
+                error = Some(format!("{:#}", e));
             }
-        });
+        }
+        if let Err(e) = store.checked(now, error).await {
+            tracing::warn!("Failed to save certificate state: {}", e);
+        }
 
-        let device_id = self.device_id.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Device not properly initialized - missing device_id"))?;
-            
-        let mut recorder = MediaRecorder::new(
-            self.config.clone(),
-            device_id.clone(),
-            incident_id.clone(),
-            duration,
-        );
+        let Some(threshold) = store.new_alert(now).await else { return };
+        let Some(certificate) = store.certificate().cloned() else { return };
+        let days_left = certificate.days_left(now);
+        let (message, level) = if certificate.not_after <= now {
+            (format!("Device certificate expired {}", certificate.not_after), sentry::Level::Error)
+        } else {
+            (format!("Device certificate expires in {} day(s), on {}", days_left, certificate.not_after), sentry::Level::Warning)
+        };
+        tracing::warn!("{}", message);
+        crate::sentry_capture_message!(&message, level, "security" => "certificate_expiry");
+        let _ = self.audit_log().record("certificate_expiring", "certificates", serde_json::json!({
+            "serial": certificate.serial,
+            "not_after": certificate.not_after,
+            "days_left": days_left,
+            "threshold_days": threshold,
+        })).await;
+    }
 
-        // Initialize encryption if enabled in config
-        if let Some(ref encryption_key) = self.config.encryption.key {
-            recorder.initialize_encryption(Some(encryption_key.clone())).await
-                .context("Failed to initialize encryption")?;
-        }
+    async fn renew_certificate(&self, store: &mut CertificateStore, device_id: &str, now: DateTime<Utc>) -> Result<()> {
+        let (request, key_pem) = store.request(device_id)?;
+        let issued = self.backend().enroll_certificate(&request).await?;
+        let certificate = store.install(&issued, &key_pem, now).await?.clone();
+        tracing::info!("Device certificate {} installed, valid until {}", certificate.serial, certificate.not_after);
+        let _ = self.audit_log().record("certificate_renewed", "certificates", serde_json::json!({
+            "serial": certificate.serial,
+            "replaced": request.renewing,
+            "not_after": certificate.not_after,
+            "fingerprint": certificate.fingerprint,
+        })).await;
+        Ok(())
+    }
 
-        recorder.start().await?;
-        self.recorder = Some(recorder);
-        self.is_recording = true;
-        self.current_incident_id = incident_id;
+    /// Rebuild the backend client after the URL or credentials changed
+    fn replace_backend(&self) -> Result<()> {
+        let backend = crate::backend::create_backend(&self.read_config())?;
+        *self.inner.backend.write().unwrap() = Arc::from(backend);
+        Ok(())
+    }
 
-        self.hardware.set_led("recording", LedState::On).await?;
-        
-        // Register temp files with resource manager if any are created during recording
-        let temp_dir = std::env::current_dir()?.join("temp");
-        if temp_dir.exists() {
-            self.resource_manager.register_temp_file(temp_dir).await?;
-        }
-        
-        sentry_integration::add_device_breadcrumb("start_recording_complete", Some("success"));
+    /// Point the device at another platform, e.g. from the setup screen.
+    /// Convex URLs go to `convex_url`; anything else is the REST server.
+    pub async fn set_backend_url(&self, url: &str, source: &str) -> Result<()> {
+        InputValidator::validate_url(url)?;
+        let mut previous = None;
+        self.update_config(|config| {
+            previous = match config.backend.resolve(config) {
+                BackendKind::Convex => config.convex_url.replace(url.to_string()),
+                _ => Some(std::mem::replace(&mut config.server_url, url.to_string())),
+            };
+        }).await?;
+        self.replace_backend()?;
+
+        self.audit_log().record("backend_changed", source, serde_json::json!({
+            "from": previous,
+            "to": url,
+        })).await?;
         Ok(())
     }
 
-    pub async fn stop_recording(&mut self
-    ) -> Result<()> {
-        if !self.is_recording {
-            return Err(anyhow::anyhow!("Not currently recording"));
-        }
+    /// Start pairing; show the returned code and QR until `check_pairing` succeeds
+    pub async fn request_pairing(&self, device_name: &str) -> Result<PairingRequest> {
+        InputValidator::validate_device_name(device_name)?;
+        self.backend().request_pairing(device_name).await
+    }
 
-        if let Some(recorder) = &mut self.recorder {
-            recorder.stop().await?;
+    /// Poll a pairing request. Once it's approved the credentials are stored
+    /// and `true` is returned.
+    pub async fn check_pairing(&self, request: &PairingRequest, source: &str) -> Result<bool> {
+        if request.is_expired(Utc::now()) {
+            return Err(anyhow::anyhow!("Pairing code {} has expired", request.code));
         }
 
-        self.recorder = None;
-        self.is_recording = false;
-
-        self.hardware.set_led("recording", LedState::Off).await?;
-        
-        // Check storage after recording stops
-        let deleted_files = self.storage_manager.check_storage_and_cleanup().await?;
-        if !deleted_files.is_empty() {
-            tracing::info!("Storage cleanup completed, deleted {} files", deleted_files.len());
-            
-            // Save deletion log for server communication
-            if let Err(e) = self.storage_manager.save_deletion_log().await {
-                tracing::error!("Failed to save deletion log: {}", e);
+        let status = self.backend().pairing_status(&request.code).await?;
+        match status {
+            PairingStatus::Pending => Ok(false),
+            PairingStatus::Approved { credentials } => {
+                let site_id = credentials.site_id.clone();
+                self.store_credentials(credentials).await?;
+                self.audit_log().record("device_paired", source, serde_json::json!({
+                    "code": request.code,
+                    "site_id": site_id,
+                })).await?;
+                Ok(true)
             }
+            PairingStatus::Rejected { reason } => Err(anyhow::anyhow!(
+                "Pairing was rejected{}",
+                reason.map(|reason| format!(": {}", reason)).unwrap_or_default()
+            )),
+            PairingStatus::Expired => Err(anyhow::anyhow!("Pairing code {} has expired", request.code)),
         }
-        
-        Ok(())
     }
 
-    pub async fn get_status(&self) -> Result<DeviceStatus> {
-        let battery_level = self.hardware.get_battery_level().await?;
-        let storage_info = self.hardware.get_storage_info().await?;
-        let temperature = self.hardware.get_temperature().await?;
-        let is_charging = self.hardware.is_charging().await?;
-
-        let location = self.gps_manager.get_location().await.map(|gps| Location {
-            latitude: gps.latitude,
-            longitude: gps.longitude,
-            altitude: gps.altitude,
-            accuracy: gps.accuracy,
-        });
+    pub fn is_recording(&self) -> bool {
+        self.inner.is_recording.load(Ordering::Relaxed)
+    }
 
-        Ok(DeviceStatus {
-            device_id: self.device_id.clone().unwrap_or_else(|| "unknown".to_string()),
-            online: true,
-            recording: self.is_recording,
-            battery_level,
-            storage_info,
-            temperature,
-            is_charging,
-            last_seen: Utc::now(),
-            location,
-            incident_active: self.current_incident_id.is_some(),
-        })
+    pub fn is_recording_paused(&self) -> bool {
+        self.inner.recording_paused.load(Ordering::Relaxed)
     }
 
-    pub async fn trigger_incident(
-        &mut self,
-        incident_type: &str,
-        severity: &str
-    ) -> Result<String> {
-        let _transaction = sentry_integration::start_transaction("device.trigger_incident", "incident");
-        
-        // Validate inputs
-        InputValidator::validate_incident_type(incident_type)?;
-        InputValidator::validate_incident_severity(severity)?;
-        
-        sentry_integration::add_device_breadcrumb("trigger_incident", 
-            Some(&format!("type: {}, severity: {}", incident_type, severity)));
-        
-        if !self.config.is_provisioned() {
-            return Err(anyhow::anyhow!("Device not provisioned"));
+    /// Pause the recording in progress, e.g. for a privacy-sensitive
+    /// conversation. The files carry on after `resume_recording`, with the
+    /// pause marked as a gap. Evidence of an open incident is never paused.
+    pub async fn pause_recording(&self, reason: Option<String>, source: &str) -> Result<()> {
+        if let Some(incident) = self.inner.active_incident.get() {
+            return Err(anyhow::anyhow!("Can't pause while incident {} is active", incident.incident_id));
         }
+        let mut active = self.inner.recorder.lock().await;
+        let Some(recorder) = active.as_mut() else {
+            return Err(anyhow::anyhow!("Not currently recording"));
+        };
+        recorder.pause(reason.clone()).await?;
+        drop(active);
+        if let Some(secondary) = self.inner.secondary_recorder.lock().await.as_mut() {
+            if let Err(e) = secondary.pause(reason.clone()).await {
+                tracing::error!("Failed to pause second camera recording: {:#}", e);
+            }
+        }
+        self.inner.recording_paused.store(true, Ordering::Relaxed);
+        *self.inner.recording_performance.lock().unwrap() = None;
+        self.inner.events.publish(BusEvent::Recording(RecordingEvent::Paused));
 
-        let incident_id = Uuid::new_v4().to_string();
-        self.current_incident_id = Some(incident_id.clone());
-
-        // Get current GPS location
-        let location = self.gps_manager.get_location().await.map(|gps| crate::incident::LocationData {
-            latitude: gps.latitude,
-            longitude: gps.longitude,
-            altitude: gps.altitude,
-            accuracy: gps.accuracy,
-            timestamp: Utc::now(),
-        });
-
-        self.incident_manager
-            .create_incident_with_location(
-                &incident_id,
-                incident_type,
-                severity,
-                self.device_id.as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("Device not initialized - missing device_id"))?,
-                location,
-            )
-            .await?;
+        self.audit_log().record("recording_paused", source, serde_json::json!({
+            "incident_id": self.current_incident_id(),
+            "reason": reason,
+        })).await?;
+        self.set_led_indicator(LedIndicator::Recording, false).await?;
+        self.play_tone(ToneEvent::RecordStop).await;
+        self.refresh_display().await;
+        Ok(())
+    }
 
-        // Start recording automatically if not already
-        if !self.is_recording {
-            self.start_recording(None, Some(incident_id.clone())).await?;
+    pub async fn resume_recording(&self, source: &str) -> Result<()> {
+        let mut active = self.inner.recorder.lock().await;
+        let Some(recorder) = active.as_mut() else {
+            return Err(anyhow::anyhow!("Not currently recording"));
+        };
+        let paused_at = recorder.paused_at();
+        recorder.resume().await?;
+        drop(active);
+        if let Some(secondary) = self.inner.secondary_recorder.lock().await.as_mut() {
+            if let Err(e) = secondary.resume().await {
+                tracing::error!("Failed to resume second camera recording: {:#}", e);
+            }
         }
+        self.inner.recording_paused.store(false, Ordering::Relaxed);
+        self.inner.events.publish(BusEvent::Recording(RecordingEvent::Resumed));
 
-        // Flash emergency LED
-        self.hardware.set_led("recording", LedState::Blink {
-            on_duration: 200,
-            off_duration: 200,
-            repeat: None,
-        }).await?;
+        self.audit_log().record("recording_resumed", source, serde_json::json!({
+            "incident_id": self.current_incident_id(),
+            "paused_seconds": paused_at.map(|at| (Utc::now() - at).num_seconds()),
+        })).await?;
+        self.set_led_indicator(LedIndicator::Recording, true).await?;
+        self.play_tone(ToneEvent::RecordStart).await;
+        self.refresh_display().await;
+        Ok(())
+    }
 
-        sentry_integration::add_device_breadcrumb("trigger_incident_complete", Some("success"));
-        
-        // Report incident to Sentry as a message
-        crate::sentry_capture_message!(
-            &format!("Incident triggered: {} ({})", incident_type, severity),
-            sentry::Level::Warning,
-            "incident_id" => incident_id.clone(),
-            "incident_type" => incident_type,
-            "severity" => severity
-        );
+    fn current_incident_id(&self) -> Option<String> {
+        self.inner.current_incident_id.lock().unwrap().clone()
+    }
 
-        Ok(incident_id)
+    pub async fn start_recording(
+        &self,
+        duration: Option<u64>,
+        incident_id: Option<String>
+    ) -> Result<()> {
+        let mode = if self.read_config().recording.audio_only {
+            RecordingMode::AudioOnly
+        } else {
+            RecordingMode::Video
+        };
+        self.start_recording_with_mode(duration, incident_id, mode, None).await
+    }
+
+    /// Record audio only, e.g. for taking a statement
+    pub async fn start_audio_recording(
+        &self,
+        duration: Option<u64>,
+        incident_id: Option<String>
+    ) -> Result<()> {
+        self.start_recording_with_mode(duration, incident_id, RecordingMode::AudioOnly, None).await
     }
 
-    pub async fn start_streaming(&mut self, quality: Option<&str>, include_audio: Option<bool>) -> Result<String> {
-        if !self.config.is_provisioned() {
-            return Err(anyhow::anyhow!("Device not provisioned"));
+    pub fn set_audio_only_recording(&self, audio_only: bool) {
+        self.inner.config.write().unwrap().recording.audio_only = audio_only;
+    }
+
+    /// Apply edits from the settings screen. Settings locked by site policy
+    /// are refused, and nothing changes unless every edit is valid.
+    pub async fn apply_settings(
+        &self,
+        changes: &std::collections::BTreeMap<String, serde_json::Value>,
+        source: &str,
+    ) -> Result<Config> {
+        if changes.is_empty() {
+            return Ok(self.config());
         }
 
-        let quality = quality.unwrap_or("medium");
-        let include_audio = include_audio.unwrap_or(true);
-        
-        let stream_info = self.streaming_manager
-            .start_streaming(self.current_incident_id.clone(), quality, include_audio)
-            .await?;
+        let (updated, previous) = {
+            let mut config = self.inner.config.write().unwrap();
+            let updated = crate::settings::apply(&config, changes)?;
+            let previous: serde_json::Map<String, serde_json::Value> = changes.keys()
+                .filter_map(|key| crate::settings::get(&config, key).ok().map(|value| (key.clone(), value)))
+                .collect();
+            *config = updated.clone();
+            (updated, previous)
+        };
+        updated.save(std::path::Path::new("config.toml")).await?;
 
-        println!("Live streaming started: {}", stream_info.stream_id);
-        Ok(stream_info.stream_id)
+        self.audit_log().record("settings_changed", source, serde_json::json!({
+            "from": previous,
+            "to": changes,
+        })).await?;
+        Ok(updated)
     }
 
-    pub async fn stop_streaming(&mut self) -> Result<()> {
-        self.streaming_manager.stop_streaming().await?;
-        println!("Live streaming stopped");
+    /// Replace the button mapping, e.g. after a settings sync. Does nothing
+    /// if the mapping hasn't changed.
+    pub async fn apply_button_actions(&self, actions: std::collections::BTreeMap<String, String>, source: &str) -> Result<()> {
+        let previous = self.read_config().buttons.actions.clone();
+        if previous == actions {
+            return Ok(());
+        }
+        *self.inner.button_actions.write().unwrap() = ButtonActionMap::from_settings(&actions);
+        self.update_config(|config| config.buttons.actions = actions.clone()).await?;
+
+        self.audit_log().record("button_actions_changed", source, serde_json::json!({
+            "from": previous,
+            "to": actions,
+        })).await?;
         Ok(())
     }
 
-    pub fn is_streaming(&self) -> bool {
-        self.streaming_manager.is_streaming()
-    }
+    /// Check storage, camera, battery and clock before starting. A manual
+    /// start without a camera fails now with a reason rather than minutes
+    /// in; anything else, and every failure for an incident recording, is
+    /// only warned about so evidence is never refused.
+    async fn preflight(&self, config: &Config, duration: Option<u64>, mode: RecordingMode, incident_id: Option<&str>) -> Result<()> {
+        let preflight = &config.recording.preflight;
+        if !preflight.enabled {
+            *self.inner.preflight_failure.lock().unwrap() = None;
+            return Ok(());
+        }
+        let bitrate = recording_time::recording_bitrate(config, mode);
+        let seconds = duration
+            .or(config.recording.duration_limit)
+            .unwrap_or(preflight.expected_minutes * 60);
 
-    pub async fn get_streaming_stats(&self) -> Result<crate::streaming::StreamStats> {
-        self.streaming_manager.get_stream_stats().await
-    }
+        let camera_error = if mode == RecordingMode::AudioOnly || config.simulation.enabled {
+            None
+        } else {
+            let device = self.inner.camera_controls.device_path();
+            crate::preflight::probe_camera(std::path::Path::new(device)).err()
+                .map(|e| (device.to_string(), e.to_string()))
+        };
+        let latest_recording = RecordingIndex::open().ok()
+            .and_then(|index| index.segments(&SegmentQuery { limit: Some(1), ..SegmentQuery::default() }).ok())
+            .and_then(|segments| segments.first().map(|segment| segment.start_time));
+
+        let inputs = crate::preflight::PreflightInputs {
+            // Space that can't be read doesn't hold up a recording
+            recording_space: self.inner.hardware.get_storage_info().await.map_or(u64::MAX, |storage| storage.recording_space),
+            required_bytes: crate::preflight::expected_bytes(preflight, bitrate, seconds),
+            battery_percent: self.inner.hardware.get_battery_level().await.ok(),
+            charging: self.inner.hardware.is_charging().await.unwrap_or(false),
+            camera_error,
+            now: Utc::now(),
+            latest_recording,
+        };
+        let failures = crate::preflight::run_checks(preflight, &inputs);
+        let incident = incident_id.is_some() || self.inner.active_incident.get().is_some();
+        let blocking = failures.iter().find(|failure| failure.blocks(incident)).cloned();
+        *self.inner.preflight_failure.lock().unwrap() = blocking.clone();
+        if failures.is_empty() {
+            return Ok(());
+        }
+
+        for failure in &failures {
+            tracing::warn!("Recording pre-flight failed: {}", failure);
+        }
+        let action = if blocking.is_some() { "recording_preflight_failed" } else { "recording_preflight_warning" };
+        let _ = self.audit_log().record(action, "recording", serde_json::json!({
+            "incident_id": incident_id,
+            "failures": failures.iter()
+                .map(|failure| serde_json::json!({ "check": failure.check(), "reason": failure.to_string() }))
+                .collect::<Vec<_>>(),
+        })).await;
+        let Some(failure) = blocking else { return Ok(()) };
+        self.refresh_display().await;
+        Err(DeviceError::Preflight(failure).into())
+    }
+
+    /// The key recordings are encrypted with. If encryption is on but the
+    /// key is gone, e.g. shredded after tampering, a new one is made rather
+    /// than recording in the clear.
+    async fn recording_key(&self, config: &Config) -> Result<Option<String>> {
+        if let Some(key) = &config.encryption.key {
+            return Ok(Some(key.clone()));
+        }
+        if !config.encryption.enabled {
+            return Ok(None);
+        }
+        let mut bytes = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut bytes);
+        let key = hex::encode(bytes);
+        self.update_config(|config| config.encryption.key = Some(key.clone())).await?;
+        self.audit_log().record("encryption_key_generated", "recording", serde_json::json!({})).await?;
+        tracing::warn!("No recording encryption key; generated a new one");
+        Ok(Some(key))
+    }
+
+    async fn start_recording_with_mode(
+        &self,
+        duration: Option<u64>,
+        incident_id: Option<String>,
+        mode: RecordingMode,
+        pre_roll_seconds: Option<u64>,
+    ) -> Result<()> {
+        let _transaction = sentry_integration::start_transaction("device.start_recording", "recording");
+
+        // Before the recorder lock, which switching night mode takes too
+        if mode == RecordingMode::Video && !self.is_recording() {
+            self.seed_light_condition().await;
+        }
+
+        // Held until the recorder is running, so two starts can't race
+        let mut active = self.inner.recorder.lock().await;
+        if active.is_some() {
+            return Err(anyhow::anyhow!("Already recording"));
+        }
+
+        sentry_integration::add_device_breadcrumb("start_recording",
+            Some(&format!("duration: {:?}, incident_id: {:?}, mode: {:?}", duration, incident_id, mode)));
+
+        // Validate inputs
+        if let Some(duration) = duration {
+            InputValidator::validate_recording_duration(duration)?;
+        }
+
+        if let Some(ref incident_id) = incident_id {
+            InputValidator::validate_uuid(incident_id)?;
+        }
+
+        if !self.read_config().is_provisioned() {
+            return Err(DeviceError::NotProvisioned.into());
+        }
+        // Camera limits first, so the recorder gets the fitted ladder
+        self.ensure_started(Subsystem::CameraLimits).await?;
+        self.ensure_started(Subsystem::Resources).await?;
+        if let Err(e) = self.ensure_started(Subsystem::Gps).await {
+            tracing::warn!("Recording without GPS: {:#}", e);
+        }
+        if let PolicyDecision::Block(rule) = self.decide_recording().await {
+            tracing::warn!("Recording blocked by policy rule {:?}", rule);
+            *self.inner.recording_restriction.lock().unwrap() = PolicyDecision::Block(rule.clone());
+            let _ = self.audit_log().record("recording_blocked", "recording_policy", serde_json::json!({
+                "rule": rule,
+                "incident_id": incident_id,
+            })).await;
+            if let Err(e) = self.vibrate_pattern(HapticPattern::Double).await {
+                tracing::debug!("Failed to signal blocked recording: {}", e);
+            }
+            return Err(anyhow::anyhow!("Recording is not allowed here: {}", rule));
+        }
+        let config = self.config();
+        self.preflight(&config, duration, mode, incident_id.as_deref()).await?;
+
+        let incident_id = incident_id
+            .or_else(|| self.current_incident_id())
+            .or_else(|| Some(Uuid::new_v4().to_string()));
+
+        let device_id = config.device_id.clone()
+            .ok_or_else(|| anyhow::anyhow!("Device not properly initialized - missing device_id"))?;
+
+        let mut recorder = MediaRecorder::new(
+            config.clone(),
+            device_id,
+            incident_id.clone(),
+            duration,
+        ).with_mode(mode)
+            .with_cancellation(self.inner.canceller.token())
+            .with_event_bus(self.event_bus())
+            .with_buffer(self.inner.buffer.clone())
+            .with_location(self.inner.gps_manager.shared_location());
+        if let Some(seconds) = pre_roll_seconds {
+            recorder = recorder.with_pre_roll(seconds);
+        }
+        if let Some(feed) = self.inner.frame_feed.lock().unwrap().clone() {
+            recorder = recorder.with_frame_feed(feed);
+        }
+
+        if let Some(encryption_key) = self.recording_key(&config).await? {
+            recorder.initialize_encryption(Some(encryption_key)).await
+                .context("Failed to initialize encryption")?;
+        }
+
+        recorder.set_night_mode(self.night_overrides()).await?;
+
+        self.inner.camera_busy.store(true, Ordering::Relaxed);
+        if let Err(e) = recorder.start().await {
+            self.inner.camera_busy.store(false, Ordering::Relaxed);
+            return Err(e);
+        }
+        *active = Some(recorder);
+        drop(active);
+        if mode == RecordingMode::Video && config.streaming.pip.record_secondary {
+            if let Some(camera) = crate::streaming::pip::secondary_camera(&config) {
+                self.start_secondary_recording(&config, &camera, incident_id.clone().unwrap_or_default(), duration).await;
+            }
+        }
+        self.inner.is_recording.store(true, Ordering::Relaxed);
+        // Loop footage isn't an incident
+        let incident_id = incident_id.filter(|id| id != LOOP_INCIDENT_ID);
+        self.inner.events.publish(BusEvent::Recording(RecordingEvent::Started { incident_id: incident_id.clone() }));
+        *self.inner.current_incident_id.lock().unwrap() = incident_id;
+
+        self.set_led_indicator(LedIndicator::Recording, true).await?;
+        self.play_tone(ToneEvent::RecordStart).await;
+        let _ = self.vibrate_pattern(HapticPattern::Single).await;
+        self.refresh_display().await;
+
+        // Register temp files with resource manager if any are created during recording
+        let temp_dir = std::env::current_dir()?.join("temp");
+        if temp_dir.exists() {
+            self.inner.resource_manager.register_temp_file(temp_dir).await?;
+        }
+
+        sentry_integration::add_device_breadcrumb("start_recording_complete", Some("success"));
+        Ok(())
+    }
+
+    /// Record the second camera on its own. Recording goes on without it
+    /// if it fails to start.
+    async fn start_secondary_recording(&self, config: &Config, camera: &str, incident_id: String, duration: Option<u64>) {
+        let (Some(secondary_config), Some(device_id)) = (
+            crate::streaming::pip::secondary_recording_config(config, camera),
+            config.device_id.clone(),
+        ) else {
+            return;
+        };
+        let mut recorder = MediaRecorder::new(secondary_config, device_id, incident_id, duration)
+            .with_pre_roll(0)
+            .with_cancellation(self.inner.canceller.token())
+            .with_event_bus(self.event_bus())
+            .with_location(self.inner.gps_manager.shared_location());
+        if let Some(encryption_key) = self.read_config().encryption.key.clone() {
+            if let Err(e) = recorder.initialize_encryption(Some(encryption_key)).await {
+                tracing::warn!("Not recording second camera {}: {:#}", camera, e);
+                return;
+            }
+        }
+        match recorder.start().await {
+            Ok(()) => {
+                tracing::info!("Recording second camera {}", camera);
+                *self.inner.secondary_recorder.lock().await = Some(recorder);
+            }
+            Err(e) => tracing::warn!("Recording without second camera {}: {:#}", camera, e),
+        }
+    }
+
+    pub async fn stop_recording(&self
+    ) -> Result<()> {
+        let mut active = self.inner.recorder.lock().await;
+        let Some(recorder) = active.as_mut() else {
+            return Err(anyhow::anyhow!("Not currently recording"));
+        };
+        recorder.stop().await?;
+
+        *active = None;
+        drop(active);
+        let secondary = self.inner.secondary_recorder.lock().await.take();
+        if let Some(mut secondary) = secondary {
+            if let Err(e) = secondary.stop().await {
+                tracing::error!("Failed to stop second camera recording: {:#}", e);
+            }
+        }
+        self.inner.is_recording.store(false, Ordering::Relaxed);
+        self.inner.recording_paused.store(false, Ordering::Relaxed);
+        *self.inner.recording_performance.lock().unwrap() = None;
+        // Only an open incident carries over to the next recording
+        if self.inner.active_incident.get().is_none() {
+            *self.inner.current_incident_id.lock().unwrap() = None;
+        }
+        // The emergency flash lasts as long as the incident's recording
+        let _ = self.set_led_indicator(LedIndicator::Error, false).await;
+        self.inner.camera_busy.store(false, Ordering::Relaxed);
+        self.inner.events.publish(BusEvent::Recording(RecordingEvent::Stopped));
+
+        // A RAM buffer hands the camera to the recorder; take it back
+        if self.inner.started.is_started(Subsystem::PreIncidentBuffer)
+            && self.read_config().pre_incident_buffer_seconds() > 0
+            && self.inner.buffer.is_memory_backed()
+        {
+            if let Err(e) = self.inner.buffer.start_buffering().await {
+                tracing::error!("Failed to restart pre-incident buffer: {}", e);
+            }
+        }
+
+        self.set_led_indicator(LedIndicator::Recording, false).await?;
+        self.play_tone(ToneEvent::RecordStop).await;
+        self.refresh_display().await;
+
+        // Check storage after recording stops
+        let mut storage = self.inner.storage_manager.lock().await;
+        let deleted_files = storage.check_storage_and_cleanup().await?;
+        if storage.take_full_alert() {
+            self.inner.events.publish(BusEvent::Hardware(HardwareEvent::StorageFull));
+        }
+        if !deleted_files.is_empty() {
+            tracing::info!("Storage cleanup completed, deleted {} files", deleted_files.len());
+
+            // Save deletion log for server communication
+            if let Err(e) = storage.save_deletion_log().await {
+                tracing::error!("Failed to save deletion log: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inject a GPS fix, overriding the receiver (simulation only)
+    pub async fn simulate_location(&self, location: crate::gps::GpsLocation) {
+        self.inner.gps_manager.set_simulated_location(location).await;
+    }
+
+    /// The device's position from the best source available: GPS, the
+    /// last good fix, Wi-Fi, a BLE gateway or the site's location
+    pub async fn resolve_location(&self) -> Option<ResolvedLocation> {
+        if let Err(e) = self.ensure_started(Subsystem::Gps).await {
+            tracing::debug!("GPS unavailable, using fallbacks: {:#}", e);
+        }
+        let backend = self.backend();
+        self.inner.location_resolver.resolve(&self.inner.gps_manager, backend.as_ref()).await
+    }
+
+    pub async fn get_status(&self) -> Result<DeviceStatus> {
+        let hardware = &self.inner.hardware;
+        let battery_level = hardware.get_battery_level().await?;
+        let storage_info = hardware.get_storage_info().await?;
+        let temperature = hardware.get_temperature().await?;
+        let is_charging = hardware.is_charging().await?;
+
+        let location = self.resolve_location().await.map(Location::from);
+        // One-shot commands don't run housekeeping, so estimate here
+        let estimated = self.inner.recording_remaining.lock().unwrap().is_some();
+        if !estimated {
+            self.update_recording_remaining().await;
+        }
+
+        Ok(DeviceStatus {
+            device_id: self.device_id().unwrap_or_else(|| "unknown".to_string()),
+            online: !crate::simulation::network_down(),
+            recording: self.is_recording(),
+            battery_level,
+            storage_info,
+            temperature,
+            is_charging,
+            last_seen: crate::simulation::faults::now(),
+            location,
+            incident_active: self.inner.active_incident.get().is_some(),
+            stealth_mode: self.is_stealth_mode(),
+            night_mode: self.inner.night_mode.lock().unwrap().is_night(),
+            recording_performance: self.recording_performance(),
+            unhealthy_services: TaskSupervisor::global().unhealthy(),
+            not_ready_for_duty: self.not_ready_for_duty(),
+            hardware_mismatch: self.inner.hardware_mismatch.load(Ordering::Relaxed),
+            deep_sleep: self.is_deep_sleeping(),
+            recording_remaining: self.recording_remaining(),
+            recording_paused: self.is_recording_paused(),
+            muted: self.is_muted(),
+        })
+    }
+
+    pub async fn trigger_incident(
+        &self,
+        incident_type: IncidentType,
+        severity: IncidentSeverity,
+    ) -> Result<String> {
+        self.trigger_incident_with_pre_roll(incident_type, severity, None).await
+    }
+
+    /// Start an incident whose recording includes `pre_roll_seconds` of
+    /// buffered footage instead of the configured default
+    pub async fn trigger_incident_with_pre_roll(
+        &self,
+        incident_type: IncidentType,
+        severity: IncidentSeverity,
+        pre_roll_seconds: Option<u64>,
+    ) -> Result<String> {
+        let _transaction = sentry_integration::start_transaction("device.trigger_incident", "incident");
+
+        sentry_integration::add_device_breadcrumb("trigger_incident",
+            Some(&format!("type: {}, severity: {}", incident_type, severity)));
+
+        if !self.read_config().is_provisioned() {
+            return Err(DeviceError::NotProvisioned.into());
+        }
+
+        let incident_id = Uuid::new_v4().to_string();
+        let started_at = Utc::now();
+        *self.inner.current_incident_id.lock().unwrap() = Some(incident_id.clone());
+        self.inner.active_incident.set(Some(ActiveIncident {
+            incident_id: incident_id.clone(),
+            incident_type,
+            severity,
+            started_at,
+        }));
+        let record = IncidentRecord {
+            id: incident_id.clone(),
+            incident_type,
+            severity,
+            started_at,
+            ended_at: None,
+        };
+        if let Err(e) = RecordingIndex::open().and_then(|mut index| index.record_incident(&record)) {
+            tracing::warn!("Failed to index incident {}: {:#}", incident_id, e);
+        }
+
+        let device_id = self.device_id()
+            .ok_or_else(|| anyhow::anyhow!("Device not initialized - missing device_id"))?;
+
+        // Start recording automatically if not already. This comes before
+        // telling the backend, which can take a while on a poor connection.
+        let recording = if self.is_recording() {
+            Ok(())
+        } else {
+            let mode = if self.read_config().recording.audio_only {
+                RecordingMode::AudioOnly
+            } else {
+                RecordingMode::Video
+            };
+            self.start_recording_with_mode(None, Some(incident_id.clone()), mode, pre_roll_seconds).await
+        };
+
+        // The journal keeps the incident if the backend can't be reached
+        let device = self.clone();
+        let reported_id = incident_id.clone();
+        tokio::spawn(async move {
+            let location = device.resolve_location().await.map(|location| crate::incident::LocationData {
+                latitude: location.latitude,
+                longitude: location.longitude,
+                altitude: location.altitude,
+                accuracy: location.accuracy,
+                timestamp: location.timestamp,
+                source: location.source,
+            });
+            let request = IncidentCreateRequest::automatic(&device_id, incident_type, severity, location);
+            if let Err(e) = device.submit(JournalOp::Incident { incident_id: reported_id.clone(), request }).await {
+                tracing::error!("Failed to journal incident {}: {:#}", reported_id, e);
+            }
+        });
+        recording?;
+
+        // Flash emergency LED
+        self.set_led_indicator(LedIndicator::Error, true).await?;
+        self.inner.display.lock().await.wake();
+        self.refresh_display().await;
+
+        sentry_integration::add_device_breadcrumb("trigger_incident_complete", Some("success"));
+        self.inner.events.publish(BusEvent::Incident(IncidentEvent::Triggered {
+            incident_id: incident_id.clone(),
+            incident_type,
+            severity,
+        }));
+
+        // Report incident to Sentry as a message
+        crate::sentry_capture_message!(
+            &format!("Incident triggered: {} ({})", incident_type, severity),
+            sentry::Level::Warning,
+            "incident_id" => incident_id.clone(),
+            "incident_type" => incident_type.as_str(),
+            "severity" => severity.as_str()
+        );
+
+        Ok(incident_id)
+    }
+
+    pub async fn start_streaming(&self, quality: Option<StreamQuality>, include_audio: Option<bool>) -> Result<String> {
+        if !self.read_config().is_provisioned() {
+            return Err(DeviceError::NotProvisioned.into());
+        }
+
+        let incident_id = self.current_incident_id();
+        // Over the cap only an incident is worth the cellular data
+        if crate::data_usage::cellular_capped() && self.inner.active_incident.get().is_none() {
+            return Err(anyhow::anyhow!("Cellular data cap reached, streaming only during an incident"));
+        }
+
+        let quality = quality.unwrap_or_default();
+        let include_audio = include_audio.unwrap_or(true);
+
+        let stream_info = self.inner.streaming_manager.lock().await
+            .start_streaming(incident_id, quality, include_audio)
+            .await?;
+
+        self.set_led_indicator(LedIndicator::Streaming, true).await?;
+
+        println!("Live streaming started: {}", stream_info.stream_id);
+        Ok(stream_info.stream_id)
+    }
+
+    pub async fn stop_streaming(&self) -> Result<()> {
+        self.inner.streaming_manager.lock().await.stop_streaming().await?;
+        self.set_led_indicator(LedIndicator::Streaming, false).await?;
+        self.update_stream_presence().await;
+        println!("Live streaming stopped");
+        Ok(())
+    }
+
+    pub async fn is_streaming(&self) -> bool {
+        self.inner.streaming_manager.lock().await.is_streaming()
+    }
+
+    /// Change how the second camera is composed into the live stream
+    pub async fn set_pip_layout(&self, layout: PipLayout, source: &str) -> Result<()> {
+        self.inner.streaming_manager.lock().await.set_pip_layout(layout).await?;
+        self.audit_log().record("pip_layout_changed", source, serde_json::json!({
+            "layout": layout,
+        })).await?;
+        Ok(())
+    }
+
+    pub fn stream_presence_handle(&self) -> ViewerPresenceHandle {
+        self.inner.viewers.clone()
+    }
+
+    /// Reconnect dropped stream outputs
+    async fn supervise_streaming(&self) {
+        let mut streaming = self.inner.streaming_manager.lock().await;
+        if streaming.is_streaming() {
+            streaming.check_outputs().await;
+        }
+    }
+
+    /// Reflect dispatcher viewers and talk-back on the LEDs and display so
+    /// the officer knows someone is watching
+    async fn update_stream_presence(&self) {
+        let presence = self.inner.streaming_manager.lock().await.presence();
+        let previous = self.inner.stream_presence.lock().unwrap().clone();
+        if presence == previous {
+            return;
+        }
+
+        let watched = presence.viewers > 0;
+        let _ = self.set_led_indicator(LedIndicator::Watched, watched).await;
+        let _ = self.set_led_indicator(LedIndicator::Talkback, presence.talkback.is_some()).await;
+
+        if presence.talkback.is_some() && previous.talkback.is_none() {
+            tracing::info!("Talk-back opened by {}", presence.talkback.as_deref().unwrap_or_default());
+            self.inner.display.lock().await.wake();
+            let _ = self.vibrate_pattern(HapticPattern::Double).await;
+        }
+
+        *self.inner.stream_presence.lock().unwrap() = presence;
+        self.refresh_display().await;
+    }
+
+    pub async fn get_streaming_stats(&self) -> Result<crate::streaming::StreamStats> {
+        self.inner.streaming_manager.lock().await.get_stream_stats().await
+    }
 
     pub async fn play_audio(
         &self,
@@ -471,343 +1543,3410 @@ impl BodycamDevice {
         loop_playback: Option<bool>,
         priority: crate::audio::AudioPriority,
     ) -> Result<String> {
+        if self.is_stealth_mode() {
+            return Err(anyhow::anyhow!("Audio playback suppressed in stealth mode"));
+        }
+        if self.is_muted() && priority < crate::audio::AudioPriority::Critical {
+            return Err(anyhow::anyhow!("Audio playback suppressed while muted"));
+        }
+
+        // A synced preset in the device's language wins over the built-in sounds
+        let source = match source {
+            crate::audio::AudioSource::PresetFile { file_id } => {
+                let language = self.read_config().i18n.language.clone();
+                match self.inner.audio_presets.lock().await.resolve(&file_id, &language) {
+                    Some(path) => crate::audio::AudioSource::CustomFile { file_path: path.to_string_lossy().to_string() },
+                    None => crate::audio::AudioSource::PresetFile { file_id },
+                }
+            }
+            source => source,
+        };
+
         let request = crate::audio::AudioPlaybackRequest {
             source,
             volume,
             loop_playback,
             priority,
         };
-        
-        self.audio_manager.play_audio(request).await
+
+        self.inner.audio_manager.play_audio(request).await
     }
 
     pub async fn stop_audio(&self) -> Result<()> {
-        self.audio_manager.stop_audio().await
+        self.inner.audio_manager.stop_audio().await
+    }
+
+    /// Stop one sound, letting any it ducked or paused carry on
+    pub fn stop_playback(&self, playback_id: &str) -> Result<()> {
+        self.inner.audio_manager.stop_playback(playback_id)
+    }
+
+    pub async fn wait_for_audio(&self, playback_id: &str) {
+        self.inner.audio_manager.wait(playback_id).await
+    }
+
+    /// Play test tones and measure them at the microphone, so this unit's
+    /// alerts reach the same loudness as the rest of the fleet. The speaker
+    /// must be otherwise quiet.
+    pub async fn calibrate_speaker(&self, source: &str) -> Result<SpeakerCalibration> {
+        if self.is_stealth_mode() {
+            return Err(anyhow::anyhow!("Speaker calibration is unavailable in stealth mode"));
+        }
+        let meter = self.audio_meter().map(|meter| meter.handle())
+            .ok_or_else(|| anyhow::anyhow!("No microphone available for calibration"))?;
+        if !self.inner.audio_manager.is_idle() {
+            return Err(anyhow::anyhow!("Audio is playing; calibrate when the speaker is quiet"));
+        }
+        let audio = self.read_config().audio.clone();
+
+        let ambient_db = crate::speaker_loudness::sample_level(&meter, std::time::Duration::from_secs(2)).await
+            .ok_or_else(|| anyhow::anyhow!("Microphone gave no readings"))?;
+        let mut response = Vec::new();
+        for frequency_hz in CALIBRATION_FREQUENCIES_HZ {
+            let tone = crate::speaker_loudness::render_tone(frequency_hz, std::time::Duration::from_secs(2)).await?;
+            let gain_db = self.inner.audio_manager.output_trim_db()
+                + audio.mixer.gain_db(crate::audio::AudioPriority::Normal, crate::audio_mixer::TrackState::Playing);
+            let playback_id = self.inner.audio_manager.play_audio(crate::audio::AudioPlaybackRequest {
+                source: crate::audio::AudioSource::CustomFile { file_path: tone.to_string_lossy().to_string() },
+                volume: None,
+                loop_playback: None,
+                priority: crate::audio::AudioPriority::Normal,
+            }).await;
+
+            // Measure the middle of the tone, clear of its start and end
+            let level_db = match playback_id {
+                Ok(playback_id) => {
+                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                    let level_db = crate::speaker_loudness::sample_level(&meter, std::time::Duration::from_secs(1)).await;
+                    self.inner.audio_manager.wait(&playback_id).await;
+                    level_db
+                }
+                Err(e) => {
+                    let _ = tokio::fs::remove_file(&tone).await;
+                    return Err(e);
+                }
+            };
+            let _ = tokio::fs::remove_file(&tone).await;
+            if let Some(level_db) = level_db {
+                response.push(ToneResponse { frequency_hz, level_db, gain_db });
+            }
+        }
+
+        let calibration = SpeakerCalibration::from_response(response, ambient_db, audio.loudness.calibration_target_db, Utc::now())?;
+        calibration.save().await?;
+        *self.inner.speaker_calibration.lock().await = calibration.clone();
+
+        self.audit_log().record(
+            "speaker_calibrated",
+            source,
+            serde_json::json!({
+                "offset_db": calibration.offset_db,
+                "ambient_db": ambient_db,
+                "response": calibration.response,
+            }),
+        ).await?;
+        tracing::info!("Speaker calibrated: {:+.1} dB", calibration.offset_db);
+
+        Ok(calibration)
+    }
+
+    pub async fn speaker_calibration(&self) -> SpeakerCalibration {
+        self.inner.speaker_calibration.lock().await.clone()
     }
 
     pub async fn get_audio_status(&self) -> Result<crate::audio::AudioStatus> {
-        self.audio_manager.get_status().await
+        let mut status = self.inner.audio_manager.get_status().await?;
+        if let Some(meter) = self.audio_meter() {
+            let level = meter.level();
+            status.input_level_db = Some(level.rms_db);
+            status.input_peak_db = Some(level.peak_db);
+            status.input_silent_seconds = meter.silent_for().map(|d| d.as_secs());
+        }
+        Ok(status)
     }
 
-    pub async fn set_volume(&self, volume: f32) -> Result<()> {
-        InputValidator::validate_volume(volume)?;
-        self.audio_manager.set_volume(volume).await
+    pub fn audio_level_handle(&self) -> Option<AudioLevelHandle> {
+        self.audio_meter().map(|meter| meter.handle())
     }
 
-    pub async fn run_comprehensive_diagnostics(&self) -> Result<ComprehensiveDiagnostics> {
-        let device_id = self.device_id.clone()
-            .unwrap_or_else(|| "unknown".to_string());
-            
-        let diagnostics_runner = DiagnosticsRunner::new(
-            device_id,
-            self.config.clone()
-        );
-        
-        diagnostics_runner.run_comprehensive_diagnostics(
-            self.hardware.as_ref(),
-            &self.resource_manager
-        ).await
+    /// Raise a sensor error if the microphone has been silent for too long
+    /// while recording, which usually means it's dead, covered or unplugged
+    async fn check_microphone(&self) {
+        let silent_for = match self.audio_meter() {
+            Some(meter) if self.is_recording() => meter.silent_for(),
+            _ => None,
+        };
+
+        let limit = std::time::Duration::from_secs(self.read_config().audio.dead_mic_minutes * 60);
+        match silent_for {
+            Some(silent) if silent >= limit => {
+                if !self.inner.mic_warning_raised.swap(true, Ordering::Relaxed) {
+                    let error = format!("No audio input for {} minutes while recording", silent.as_secs() / 60);
+                    crate::sentry_capture_message!(&error, sentry::Level::Warning, "sensor" => "microphone");
+                    Self::handle_hardware_event(self, HardwareEvent::SensorError {
+                        sensor: "microphone".to_string(),
+                        error,
+                    }).await;
+                }
+            }
+            _ => self.inner.mic_warning_raised.store(false, Ordering::Relaxed),
+        }
+    }
+
+    /// Check the battery while it charges: refuse fast charging when it's
+    /// hot and raise heat, charge current and capacity problems
+    async fn check_charging_safety(&self) {
+        if !self.read_config().power_management.charging_safety.enabled {
+            return;
+        }
+        let Ok(info) = self.inner.hardware.get_battery_info().await else {
+            return;
+        };
+
+        let mut charging_safety = self.inner.charging_safety.lock().await;
+        let check = charging_safety.observe(&info, Utc::now());
+        if let Some(allowed) = check.fast_charge {
+            tracing::info!("{} fast charging at {:?}°C", if allowed { "Allowing" } else { "Refusing" }, info.temperature_c);
+            if let Err(e) = self.inner.hardware.set_fast_charge(allowed).await {
+                tracing::debug!("Couldn't change the charge type: {}", e);
+            }
+        }
+        if check.history_changed {
+            if let Err(e) = charging_safety.save().await {
+                tracing::warn!("Failed to save battery capacity history: {}", e);
+            }
+        }
+        drop(charging_safety);
+        // Through the bus so webhooks and plugins see them too
+        for event in check.events {
+            self.inner.events.publish(BusEvent::Hardware(event));
+        }
+    }
+
+    /// Add traffic since the last sample to today's usage, and hold off
+    /// cellular uploads once the cap for the billing cycle is used up
+    async fn sample_data_usage(&self) {
+        if !self.read_config().data_usage.enabled {
+            return;
+        }
+        let counters = crate::data_usage::read_counters().await;
+        let today = Utc::now().date_naive();
+
+        let mut ledger = self.inner.data_usage.lock().await;
+        if ledger.record_counters(&counters, today) {
+            if let Err(e) = ledger.save().await {
+                tracing::warn!("Failed to save data usage: {}", e);
+            }
+        }
+        let cap_reached = ledger.report(today).cellular_cap_reached;
+        drop(ledger);
+
+        let capped = cap_reached && self.inner.network.handle().get().link == LinkKind::Cellular;
+        if capped != crate::data_usage::cellular_capped() {
+            if capped {
+                tracing::warn!("Cellular data cap reached; uploads wait for Wi-Fi until the next billing cycle");
+            }
+            crate::data_usage::set_cellular_capped(capped);
+        }
+    }
+
+    /// Data used today and this billing cycle; None if tracking is off
+    pub async fn data_usage(&self) -> Option<DataUsageReport> {
+        if !self.read_config().data_usage.enabled {
+            return None;
+        }
+        Some(self.inner.data_usage.lock().await.report(Utc::now().date_naive()))
+    }
+
+    /// Frame rate and dropped frames reported by the running encoders
+    pub fn recording_performance(&self) -> Option<RecordingPerformance> {
+        self.inner.recording_performance.lock().unwrap().clone()
+    }
+
+    /// Feed the temperature and encoder performance to the resource
+    /// throttle, so background work backs off before recording suffers
+    async fn report_resource_sensors(&self) {
+        let temperature = self.inner.hardware.get_temperature().await.ok();
+        self.inner.resource_manager.report_sensors(temperature, self.recording_performance()).await;
+    }
+
+    /// Restart crashed recording encoders and report each crash with the
+    /// encoder's last stderr output
+    async fn supervise_recording(&self) {
+        let crashes = match self.inner.recorder.try_lock() {
+            Ok(mut active) => match active.as_mut() {
+                Some(recorder) => {
+                    let crashes = recorder.supervise().await;
+                    *self.inner.recording_performance.lock().unwrap() = recorder.performance();
+                    crashes
+                }
+                None => return,
+            },
+            // Starting or stopping; there's nothing to supervise yet
+            Err(_) => return,
+        };
+
+        for crash in crashes {
+            let error = format!("{} crashed{}", crash.label,
+                if crash.restarted { ", resumed into a new segment part" } else { ", recording stopped" });
+            crate::sentry_capture_message!(&error, sentry::Level::Error,
+                "component" => "recording",
+                "stderr" => crash.stderr_tail.join("\n"));
+
+            {
+                let mut crash_reports = self.inner.crash_reports.lock().unwrap();
+                crash_reports.push(CrashReport {
+                    timestamp: Utc::now(),
+                    component: crash.label.clone(),
+                    exit_code: crash.exit_code,
+                    signal: crash.signal.clone(),
+                    stack_trace: None,
+                    memory_usage_at_crash: None,
+                    logs_before_crash: crash.stderr_tail.clone(),
+                });
+                if crash_reports.len() > MAX_CRASH_REPORTS {
+                    crash_reports.remove(0);
+                }
+            }
+
+            Self::handle_hardware_event(self, HardwareEvent::SensorError {
+                sensor: "recording".to_string(),
+                error,
+            }).await;
+        }
+    }
+
+    pub async fn set_volume(&self, volume: f32) -> Result<()> {
+        InputValidator::validate_volume(volume)?;
+        self.inner.audio_manager.set_volume(volume).await
+    }
+
+    pub async fn set_led_indicator(&self, indicator: LedIndicator, active: bool) -> Result<()> {
+        let mut leds = self.inner.leds.lock().await;
+        if active {
+            leds.activate(self.inner.hardware.as_ref(), indicator).await
+        } else {
+            leds.deactivate(self.inner.hardware.as_ref(), indicator).await
+        }
+    }
+
+    pub async fn active_led_indicators(&self) -> Vec<LedIndicator> {
+        self.inner.leds.lock().await.active_indicators()
+    }
+
+    pub fn is_stealth_mode(&self) -> bool {
+        self.inner.stealth_mode.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable covert operation. While active all LEDs, speaker output
+    /// and vibration are suppressed; recording and status reporting continue.
+    pub async fn set_stealth_mode(&self, enabled: bool, source: &str) -> Result<()> {
+        if self.inner.stealth_mode.swap(enabled, Ordering::Relaxed) == enabled {
+            return Ok(());
+        }
+
+        let _transaction = sentry_integration::start_transaction("device.set_stealth_mode", "security");
+
+        if enabled {
+            let _ = self.inner.audio_manager.stop_audio().await;
+        }
+
+        self.set_led_indicator(LedIndicator::Stealth, enabled).await?;
+        self.inner.display.lock().await.set_suppressed(enabled).await?;
+
+        self.audit_log().record(
+            if enabled { "stealth_mode_enabled" } else { "stealth_mode_disabled" },
+            source,
+            serde_json::json!({
+                "recording": self.is_recording(),
+                "incident_id": self.current_incident_id(),
+            }),
+        ).await?;
+
+        Ok(())
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.inner.muted.load(Ordering::Relaxed)
+    }
+
+    /// Silence the speaker without the rest of stealth mode: tones, voice
+    /// prompts and playback below critical priority are suppressed
+    pub async fn set_muted(&self, muted: bool, source: &str) -> Result<()> {
+        if self.inner.muted.swap(muted, Ordering::Relaxed) == muted {
+            return Ok(());
+        }
+        if muted {
+            let _ = self.inner.audio_manager.stop_audio().await;
+        }
+        self.audit_log().record(
+            if muted { "speaker_muted" } else { "speaker_unmuted" },
+            source,
+            serde_json::json!({ "incident_id": self.current_incident_id() }),
+        ).await?;
+        Ok(())
+    }
+
+    /// Audible feedback is best-effort and never fails the calling operation
+    pub async fn play_tone(&self, event: ToneEvent) {
+        if self.is_stealth_mode() || self.is_muted() {
+            return;
+        }
+        if let Err(e) = self.inner.buzzer.play_event(self.inner.hardware.as_ref(), event).await {
+            tracing::warn!("Failed to play {:?} tone: {}", event, e);
+        }
+        if self.read_config().i18n.voice_prompts && !matches!(event, ToneEvent::Countdown | ToneEvent::CountdownFinal) {
+            let priority = match event {
+                ToneEvent::Error | ToneEvent::LowBattery => crate::audio::AudioPriority::High,
+                _ => crate::audio::AudioPriority::Normal,
+            };
+            self.speak(&format!("prompt.{}", event.pattern_name()), priority).await;
+        }
+    }
+
+    /// Speak the localized string for `key` in the pack's voice
+    pub async fn speak(&self, key: &str, priority: crate::audio::AudioPriority) {
+        let i18n = self.localizer();
+        let source = crate::audio::AudioSource::TtsLocal {
+            text: i18n.get(key),
+            voice: Some(i18n.tts_voice().to_string()),
+            rate: None,
+        };
+        if let Err(e) = self.play_audio(source, None, None, priority).await {
+            tracing::warn!("Failed to speak {}: {}", key, e);
+        }
+    }
+
+    pub fn localizer(&self) -> Arc<Localizer> {
+        self.inner.i18n.read().unwrap().clone()
+    }
+
+    /// Switch the language of prompts and alerts, and remember it across restarts
+    pub async fn set_language(&self, language: &str, source: &str) -> Result<()> {
+        let packs_dir = Localizer::packs_dir(&self.read_config().i18n);
+        let localizer = Localizer::load_language(&packs_dir, language).await?;
+        let previous = std::mem::replace(&mut *self.inner.i18n.write().unwrap(), Arc::new(localizer));
+        self.update_config(|config| config.i18n.language = language.to_string()).await?;
+
+        self.audit_log().record("language_changed", source, serde_json::json!({
+            "from": previous.language(),
+            "to": language,
+        })).await?;
+        Ok(())
+    }
+
+    pub async fn beep_countdown(&self, seconds: u32) {
+        if self.is_stealth_mode() {
+            return;
+        }
+        if let Err(e) = self.inner.buzzer.countdown(self.inner.hardware.as_ref(), seconds).await {
+            tracing::warn!("Failed to play countdown: {}", e);
+        }
+    }
+
+    /// Redraw the status display; failures are logged rather than propagated
+    pub async fn refresh_display(&self) {
+        if !self.inner.display.lock().await.is_enabled() {
+            return;
+        }
+
+        let status = match self.get_status().await {
+            Ok(status) => status,
+            Err(e) => {
+                tracing::warn!("Failed to collect status for display: {}", e);
+                return;
+            }
+        };
+
+        let presence = self.inner.stream_presence.lock().unwrap().clone();
+        let display_status = DisplayStatus {
+            battery_level: status.battery_level,
+            is_charging: status.is_charging,
+            recording: status.recording,
+            paused: status.recording_paused,
+            streaming: self.is_streaming().await,
+            viewers: presence.viewers,
+            talkback: presence.talkback.is_some(),
+            network_connected: status.online,
+            time: chrono::Local::now(),
+            incident_banner: if status.incident_active {
+                Some("INCIDENT ACTIVE".to_string())
+            } else if !status.not_ready_for_duty.is_empty() {
+                Some("NOT READY FOR DUTY".to_string())
+            } else if let Some(failure) = self.inner.preflight_failure.lock().unwrap().as_ref() {
+                Some(format!("CAN'T RECORD: {}", failure.check().to_uppercase()))
+            } else {
+                match self.recording_restriction() {
+                    PolicyDecision::Allow => None,
+                    PolicyDecision::Block(_) => Some("NO RECORDING HERE".to_string()),
+                    PolicyDecision::Force(_) => Some("RECORDING REQUIRED".to_string()),
+                }
+            },
+        };
+
+        if let Err(e) = self.inner.display.lock().await.update(&display_status).await {
+            tracing::warn!("Failed to update status display: {}", e);
+        }
+    }
+
+    pub async fn vibrate(&self, duration_ms: u64) -> Result<()> {
+        if self.is_stealth_mode() {
+            return Ok(());
+        }
+        self.inner.hardware.vibrate(duration_ms).await
+    }
+
+    pub async fn vibrate_pattern(&self, pattern: HapticPattern) -> Result<()> {
+        if self.is_stealth_mode() {
+            return Ok(());
+        }
+        self.inner.haptics.play(self.inner.hardware.as_ref(), pattern).await
+    }
+
+    /// The bus this device publishes to and acts on. Publishing a
+    /// `BusEvent::Hardware` here is handled exactly as if the hardware had
+    /// raised it.
+    pub fn event_bus(&self) -> EventBus {
+        self.inner.events.clone()
+    }
+
+    /// Cancels long operations in progress, e.g. from a Ctrl-C handler or
+    /// the UI's cancel button
+    pub fn canceller(&self) -> Canceller {
+        self.inner.canceller.clone()
+    }
+
+    /// Called when dispatch confirms it has seen an incident raised by this device
+    pub fn active_incident_handle(&self) -> ActiveIncidentHandle {
+        self.inner.active_incident.clone()
+    }
+
+    /// Close the incident in progress. Recording carries on until stopped,
+    /// so closing an incident never cuts footage short.
+    pub async fn end_incident(&self, source: &str) -> Result<()> {
+        let incident = self.inner.active_incident.get()
+            .ok_or_else(|| anyhow::anyhow!("No incident is active"))?;
+
+        self.inner.active_incident.set(None);
+        if !self.is_recording() {
+            *self.inner.current_incident_id.lock().unwrap() = None;
+        }
+        if let Err(e) = RecordingIndex::open().and_then(|mut index| index.end_incident(&incident.incident_id, Utc::now())) {
+            tracing::warn!("Failed to index end of incident {}: {:#}", incident.incident_id, e);
+        }
+        let _ = self.set_led_indicator(LedIndicator::Error, false).await;
+        self.refresh_display().await;
+
+        sentry_integration::add_device_breadcrumb("end_incident", Some(&incident.incident_id));
+        self.inner.events.publish(BusEvent::Incident(IncidentEvent::Ended {
+            incident_id: incident.incident_id.clone(),
+        }));
+        self.audit_log().record("incident_ended", source, serde_json::json!({
+            "incident_id": incident.incident_id,
+            "incident_type": incident.incident_type,
+            "severity": incident.severity,
+            "duration_seconds": incident.elapsed(Utc::now()).num_seconds(),
+        })).await?;
+        Ok(())
+    }
+
+    pub async fn acknowledge_incident(&self, incident_id: &str) -> Result<()> {
+        InputValidator::validate_uuid(incident_id)?;
+
+        tracing::info!("Incident {} acknowledged by dispatch", incident_id);
+        sentry_integration::add_device_breadcrumb("incident_acknowledged", Some(incident_id));
+        self.inner.events.publish(BusEvent::Incident(IncidentEvent::Acknowledged {
+            incident_id: incident_id.to_string(),
+        }));
+
+        if self.current_incident_id().as_deref() == Some(incident_id) {
+            self.vibrate_pattern(HapticPattern::Double).await?;
+        }
+        Ok(())
+    }
+
+    /// Record from frames the preview is already capturing rather than
+    /// opening the camera again; takes effect from the next recording
+    pub fn attach_frame_feed(&self, feed: Option<FrameFeed>) {
+        *self.inner.frame_feed.lock().unwrap() = feed;
+    }
+
+    pub async fn list_camera_controls(&self) -> Result<Vec<crate::capabilities::CameraControl>> {
+        self.inner.camera_controls.list().await
+    }
+
+    pub async fn get_camera_control(&self, control: &str) -> Result<i32> {
+        let kind: CameraControlKind = control.parse()?;
+        self.inner.camera_controls.get(kind).await
+    }
+
+    pub async fn set_camera_control(&self, control: &str, value: &str) -> Result<()> {
+        let kind: CameraControlKind = control.parse()?;
+        let value: ControlValue = value.parse()?;
+
+        sentry_integration::add_device_breadcrumb("set_camera_control",
+            Some(&format!("{}={:?}", kind.name(), value)));
+
+        self.inner.camera_controls.set(kind, value).await
+    }
+
+    pub fn light_condition(&self) -> LightCondition {
+        self.inner.night_mode.lock().unwrap().condition()
+    }
+
+    /// Dim the display and LEDs for the time of day, the light and the
+    /// battery, unless brightness has been set by hand
+    async fn apply_brightness(&self) {
+        let (full, dimming) = {
+            let config = self.read_config();
+            (config.power_management.brightness_level.min(100) as u8, config.power_management.dimming.clone())
+        };
+        let inputs = DimmingInputs {
+            local_hour: chrono::Local::now().hour(),
+            dark: self.light_condition() == LightCondition::Night,
+            battery_percent: self.inner.hardware.get_battery_level().await.ok(),
+            charging: self.inner.hardware.is_charging().await.unwrap_or(false),
+        };
+        let manual = *self.inner.brightness_override.lock().unwrap();
+        let target = dimming.target(full, inputs, manual);
+
+        let previous = self.inner.brightness.lock().unwrap().replace(target);
+        if previous != Some(target) {
+            tracing::info!("Brightness {}% ({:?})", target.percent, target.reason);
+        }
+        if let Err(e) = self.inner.display.lock().await.set_brightness(target.percent).await {
+            tracing::debug!("Couldn't set display brightness: {}", e);
+        }
+        if let Err(e) = self.inner.leds.lock().await.set_brightness(self.inner.hardware.as_ref(), target.percent).await {
+            tracing::debug!("Couldn't set LED brightness: {}", e);
+        }
+    }
+
+    /// Set the brightness by hand, or None to go back to the dimming schedule
+    pub async fn set_brightness_override(&self, percent: Option<u8>, source: &str) -> Result<BrightnessStatus> {
+        if percent.is_some_and(|percent| percent > 100) {
+            return Err(anyhow::anyhow!("Brightness must be between 0 and 100"));
+        }
+        *self.inner.brightness_override.lock().unwrap() = percent;
+        self.apply_brightness().await;
+        self.audit_log().record("brightness_override", source, serde_json::json!({
+            "percent": percent,
+        })).await?;
+        Ok(self.brightness_status().await)
+    }
+
+    pub async fn brightness_status(&self) -> BrightnessStatus {
+        let target = match *self.inner.brightness.lock().unwrap() {
+            Some(target) => target,
+            None => BrightnessLevel {
+                percent: self.read_config().power_management.brightness_level.min(100) as u8,
+                reason: crate::hardware::brightness::DimReason::Full,
+            },
+        };
+        BrightnessStatus {
+            display_percent: self.inner.display.lock().await.brightness(),
+            led_percent: self.inner.leds.lock().await.brightness(),
+            target,
+        }
+    }
+
+    pub fn is_deep_sleeping(&self) -> bool {
+        self.inner.deep_sleep.lock().unwrap().is_some()
+    }
+
+    /// Stop recording, streaming, the pre-incident buffer, the display and
+    /// the LEDs until a configured wake source fires
+    pub async fn enter_deep_sleep(&self, source: &str) -> Result<SleepSession> {
+        let (config, simulation) = {
+            let config = self.read_config();
+            (config.power_management.deep_sleep.clone(), config.simulation.enabled)
+        };
+        if self.is_deep_sleeping() {
+            return Err(anyhow::anyhow!("Already in deep sleep"));
+        }
+        if self.inner.active_incident.get().is_some() {
+            return Err(anyhow::anyhow!("Can't sleep during an incident"));
+        }
+        if config.wake_sources.is_empty() {
+            return Err(anyhow::anyhow!("No wake sources are configured, the device would never wake"));
+        }
+        let power = PowerManagementInfo::detect(simulation).await;
+        for unsupported in config.unsupported_sources(&power) {
+            tracing::warn!("This device can't wake from suspend by {}", unsupported.as_str());
+        }
+        let next_scheduled_wake = deep_sleep::next_scheduled_wake(&config)?;
+
+        if self.is_recording() {
+            self.stop_recording().await?;
+        }
+        if self.is_streaming().await {
+            self.stop_streaming().await?;
+        }
+        if let Err(e) = self.inner.audio_manager.stop_all().await {
+            tracing::debug!("Failed to stop audio before sleeping: {}", e);
+        }
+        if let Err(e) = self.inner.buffer.suspend().await {
+            tracing::warn!("Failed to suspend pre-incident buffer: {}", e);
+        }
+        self.inner.display.lock().await.set_suppressed(true).await?;
+        if let Err(e) = self.inner.leds.lock().await.set_brightness(self.inner.hardware.as_ref(), 0).await {
+            tracing::debug!("Couldn't turn off the LEDs: {}", e);
+        }
+
+        let session = SleepSession {
+            since: Utc::now(),
+            entered_by: source.to_string(),
+            next_scheduled_wake,
+        };
+        *self.inner.deep_sleep.lock().unwrap() = Some(session.clone());
+        *self.inner.scheduled_sleep_at.lock().unwrap() = None;
+        tracing::info!("Entering deep sleep, waking on {:?}", config.wake_sources);
+        self.audit_log().record("deep_sleep_entered", source, serde_json::json!({
+            "wake_sources": config.wake_sources,
+            "next_scheduled_wake": next_scheduled_wake,
+            "system_suspend": config.system_suspend,
+        })).await?;
+
+        if config.system_suspend && !simulation {
+            self.suspend_until_wake(next_scheduled_wake);
+        }
+        Ok(session)
+    }
+
+    /// Suspend the system to RAM, arming the RTC for the next scheduled
+    /// wake. The kernel resumes on the hardware wake sources too.
+    fn suspend_until_wake(&self, next_scheduled_wake: Option<DateTime<Utc>>) {
+        let device = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            // Let the audit entry and the LEDs settle first
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            if let Some(at) = next_scheduled_wake {
+                if let Err(e) = deep_sleep::arm_rtc_alarm(at).await {
+                    tracing::warn!("Scheduled wake unavailable: {:#}", e);
+                }
+            }
+            if let Err(e) = deep_sleep::suspend_system().await {
+                tracing::error!("{:#}", e);
+                return;
+            }
+
+            let Some(device) = Self::upgrade(&device) else { return };
+            let reason = match next_scheduled_wake {
+                Some(at) if Utc::now() >= at => WakeReason::Source(WakeSource::Rtc),
+                _ => WakeReason::Resume,
+            };
+            if let Err(e) = device.wake_from_deep_sleep(reason, "resume").await {
+                tracing::error!("Failed to wake from deep sleep: {:#}", e);
+            }
+        });
+    }
+
+    /// Bring everything back up. Returns false if the device wasn't asleep.
+    pub async fn wake_from_deep_sleep(&self, reason: WakeReason, source: &str) -> Result<bool> {
+        let Some(session) = self.inner.deep_sleep.lock().unwrap().take() else {
+            return Ok(false);
+        };
+        let now = Utc::now();
+        *self.inner.last_wake.lock().unwrap() = Some((reason, now));
+        *self.inner.last_activity.lock().unwrap() = now;
+        let (awake_minutes, buffer_seconds) = {
+            let config = self.read_config();
+            (config.power_management.deep_sleep.scheduled_awake_minutes, config.pre_incident_buffer_seconds())
+        };
+        if reason == WakeReason::Source(WakeSource::Rtc) {
+            // A scheduled check-in, so go back to sleep afterwards
+            *self.inner.scheduled_sleep_at.lock().unwrap() = Some(now + chrono::Duration::minutes(awake_minutes as i64));
+        }
+
+        if !self.is_stealth_mode() {
+            self.inner.display.lock().await.set_suppressed(false).await?;
+        }
+        self.apply_brightness().await;
+        if self.inner.started.is_started(Subsystem::PreIncidentBuffer) && buffer_seconds > 0 {
+            if let Err(e) = self.inner.buffer.start_buffering().await {
+                tracing::error!("Failed to restart pre-incident buffer: {}", e);
+            }
+        }
+        self.refresh_display().await;
+
+        let slept_seconds = (now - session.since).num_seconds();
+        tracing::info!("Woke from deep sleep after {}s ({:?})", slept_seconds, reason);
+        self.audit_log().record("deep_sleep_exited", source, serde_json::json!({
+            "reason": reason,
+            "slept_seconds": slept_seconds,
+        })).await?;
+        Ok(true)
+    }
+
+    pub async fn sleep_status(&self) -> SleepStatus {
+        let (wake_sources, simulation) = {
+            let config = self.read_config();
+            (config.power_management.deep_sleep.wake_sources.clone(), config.simulation.enabled)
+        };
+        SleepStatus {
+            sleeping: self.inner.deep_sleep.lock().unwrap().clone(),
+            wake_sources,
+            last_wake: *self.inner.last_wake.lock().unwrap(),
+            power_management: PowerManagementInfo::detect(simulation).await,
+        }
+    }
+
+    /// Recording time left, as of the last housekeeping pass
+    pub fn recording_remaining(&self) -> Option<RemainingRecording> {
+        self.inner.recording_remaining.lock().unwrap().clone()
+    }
+
+    /// Estimate the recording time left from free storage, the ladder's
+    /// bitrates and the battery draw, and warn once it runs low
+    async fn update_recording_remaining(&self) {
+        let (bitrate, capacity_mah, remaining_config) = {
+            let config = self.read_config();
+            let mode = if config.recording.audio_only { RecordingMode::AudioOnly } else { RecordingMode::Video };
+            (recording_time::recording_bitrate(&config, mode), config.hardware.battery_capacity as f32, config.recording.remaining.clone())
+        };
+        let Ok(storage) = self.inner.hardware.get_storage_info().await else { return };
+        let battery = self.inner.hardware.get_battery_info().await.unwrap_or_default();
+        let level = self.inner.hardware.get_battery_level().await.ok();
+        let charging = self.inner.hardware.is_charging().await.unwrap_or(false);
+
+        let draw_ma = {
+            let mut model = self.inner.draw_model.lock().unwrap();
+            if self.is_recording() && !charging {
+                if let Some(current_ma) = battery.current_ma {
+                    model.observe(current_ma);
+                }
+            }
+            model.draw_ma()
+        };
+        let battery_mah = level
+            .filter(|_| !charging)
+            .map(|level| battery.full_capacity_mah.unwrap_or(capacity_mah) * level / 100.0);
+        let remaining = recording_time::estimate(storage.recording_space, bitrate, battery_mah, draw_ma);
+
+        let warn = self.inner.low_remaining.lock().unwrap().update(remaining.minutes, remaining_config.warn_minutes);
+        if warn {
+            tracing::warn!("About {} minutes of recording left ({:?})", remaining.minutes, remaining.limited_by);
+            self.inner.events.publish(BusEvent::Recording(RecordingEvent::RemainingLow {
+                minutes: remaining.minutes,
+                limited_by: remaining.limited_by,
+            }));
+        }
+        *self.inner.recording_remaining.lock().unwrap() = Some(remaining);
+    }
+
+    /// Feed a light reading to the night mode controller and switch the
+    /// camera and encoder over when the condition changes
+    pub async fn observe_light(&self, reading: LightReading) -> Result<()> {
+        if let LightReading::Lux(lux) = reading {
+            let theme = self.inner.theme.lock().unwrap().observe_lux(lux);
+            if let Some(theme) = theme {
+                tracing::info!("Switching UI to {:?} theme", theme);
+            }
+        }
+
+        let condition = self.inner.night_mode.lock().unwrap().observe(reading);
+        match condition {
+            Some(condition) => self.apply_light_condition(condition).await,
+            None => Ok(()),
+        }
+    }
+
+    pub fn theme_handle(&self) -> ThemeHandle {
+        self.inner.theme.lock().unwrap().handle()
+    }
+
+    pub fn network_status_handle(&self) -> NetworkStatusHandle {
+        self.inner.network.handle()
+    }
+
+    /// Pick a fixed theme, or `Auto` to follow ambient light, and keep it across restarts
+    pub async fn set_theme_mode(&self, mode: ThemeMode, source: &str) -> Result<Theme> {
+        let theme = self.inner.theme.lock().unwrap().set_mode(mode);
+        self.update_config(|config| config.theme.mode = mode).await?;
+        self.audit_log().record("theme_changed", source, serde_json::json!({
+            "mode": mode,
+            "theme": theme,
+        })).await?;
+        Ok(theme)
+    }
+
+    async fn apply_light_condition(&self, condition: LightCondition) -> Result<()> {
+        let night = condition == LightCondition::Night;
+        tracing::info!("Switching camera to {} mode", if night { "night" } else { "day" });
+        sentry_integration::add_device_breadcrumb("night_mode", Some(if night { "night" } else { "day" }));
+
+        // Not every module has an IR-cut filter or gain control, so these are best effort
+        let camera_controls = &self.inner.camera_controls;
+        let ir_cut = if night { ControlValue::Manual(0) } else { ControlValue::Manual(1) };
+        if let Err(e) = camera_controls.set(CameraControlKind::IrCut, ir_cut).await {
+            tracing::warn!("Failed to switch IR-cut filter: {}", e);
+        }
+
+        if let Ok(controls) = camera_controls.list().await {
+            if let Some(gain) = controls.iter().find(|c| c.name == "gain") {
+                let value = if night { gain.max_value } else { gain.default_value };
+                if let Err(e) = camera_controls.set(CameraControlKind::Gain, ControlValue::Manual(value)).await {
+                    tracing::warn!("Failed to set camera gain: {}", e);
+                }
+            }
+        }
+
+        if let Err(e) = camera_controls.set(CameraControlKind::Exposure, ControlValue::Auto).await {
+            tracing::warn!("Failed to set auto exposure: {}", e);
+        }
+
+        let overrides = self.night_overrides();
+        if let Some(recorder) = self.inner.recorder.lock().await.as_mut() {
+            recorder.set_night_mode(overrides).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Encoder settings for the current light, None in daylight
+    fn night_overrides(&self) -> Option<Vec<crate::config::VideoQualityConfig>> {
+        let night_mode = self.inner.night_mode.lock().unwrap();
+        night_mode.is_night().then(|| {
+            self.read_config().recording.available_qualities
+                .iter()
+                .map(|q| night_mode.adjust_quality(q))
+                .collect()
+        })
+    }
+
+    /// Sample the light before a recording opens the camera, so the first
+    /// segment isn't encoded with day settings at night. Without a sample
+    /// the last known condition stands.
+    async fn seed_light_condition(&self) {
+        if self.read_config().simulation.enabled || !self.inner.night_mode.lock().unwrap().is_enabled() {
+            return;
+        }
+
+        let luma = match night_mode::measure_frame_luminance(self.inner.camera_controls.device_path()).await {
+            Ok(luma) => luma,
+            Err(e) => {
+                tracing::debug!("Luminance sample before recording failed: {}", e);
+                return;
+            }
+        };
+        let condition = self.inner.night_mode.lock().unwrap().seed(LightReading::Luminance(luma));
+        if let Some(condition) = condition {
+            if let Err(e) = self.apply_light_condition(condition).await {
+                tracing::error!("Failed to apply night mode: {}", e);
+            }
+        }
+    }
+
+    /// Sample frame luminance when there's no light sensor reading to go on.
+    /// While recording the camera is busy, so the sample comes from the
+    /// encoder's luminance tap instead.
+    async fn sample_frame_luminance(&self) {
+        if self.read_config().simulation.enabled {
+            return;
+        }
+
+        let luma = if self.is_recording() {
+            // The tap already runs at the sample interval
+            let recorder = self.inner.recorder.lock().await;
+            match recorder.as_ref().and_then(|recorder| recorder.take_frame_luminance()) {
+                Some(luma) => Ok(luma),
+                None => return,
+            }
+        } else {
+            if !self.inner.night_mode.lock().unwrap().sample_due() {
+                return;
+            }
+            night_mode::measure_frame_luminance(self.inner.camera_controls.device_path()).await
+        };
+
+        match luma {
+            Ok(luma) => {
+                if let Err(e) = self.observe_light(LightReading::Luminance(luma)).await {
+                    tracing::error!("Failed to apply night mode: {}", e);
+                }
+            }
+            Err(e) => tracing::debug!("Luminance sample failed: {}", e),
+        }
+    }
+
+    pub async fn run_comprehensive_diagnostics(&self) -> Result<ComprehensiveDiagnostics> {
+        let device_id = self.device_id()
+            .unwrap_or_else(|| "unknown".to_string());
+        let crash_reports = self.inner.crash_reports.lock().unwrap().clone();
+        let stream_latency = self.inner.streaming_manager.lock().await.latency_ms();
+        let certificate_status = self.inner.certificates.lock().await.status(Utc::now());
+        let brightness = self.brightness_status().await;
+
+        let diagnostics_runner = DiagnosticsRunner::new(
+            device_id,
+            self.config()
+        )
+        .with_cancellation(self.inner.canceller.token())
+        .with_crash_reports(crash_reports)
+        .with_recording_performance(self.recording_performance())
+        .with_stream_latency(stream_latency)
+        .with_certificate_status(certificate_status)
+        .with_brightness(brightness)
+        .with_metrics_history(self.inner.metrics_history.as_ref());
+
+        diagnostics_runner.run_comprehensive_diagnostics(
+            self.inner.hardware.as_ref(),
+            &self.inner.resource_manager
+        ).await
+    }
+
+    /// Run comprehensive diagnostics, archive the report and send it to the
+    /// backend. Offline, the report waits in the journal like other calls.
+    pub async fn upload_comprehensive_diagnostics(&self, source: &str) -> Result<DiagnosticsUpload> {
+        let diagnostics = self.run_comprehensive_diagnostics().await?;
+        let archive = DiagnosticsArchive::new(self.read_config().diagnostics_archive.clone());
+        let archived_path = archive.store(&diagnostics).await?;
+
+        let generated_at = diagnostics.timestamp;
+        self.submit(JournalOp::Diagnostics { diagnostics: Box::new(diagnostics) }).await?;
+        let delivered = self.pending_journal_entries().await == 0;
+
+        self.audit_log().record("diagnostics_uploaded", source, serde_json::json!({
+            "generated_at": generated_at,
+            "delivered": delivered,
+        })).await?;
+        Ok(DiagnosticsUpload { generated_at, archived_path, delivered })
+    }
+
+    /// Critical checks that failed in the last self-test
+    pub fn not_ready_for_duty(&self) -> Vec<SelfTestCheck> {
+        self.inner.self_test.lock().unwrap().as_ref()
+            .map(|report| report.critical_failures.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn last_self_test(&self) -> Option<SelfTestReport> {
+        self.inner.self_test.lock().unwrap().clone()
+    }
+
+    /// Check the camera, microphone, speaker, LEDs, buttons, GPS and backend.
+    /// A failed critical check flags the device not ready for duty until a
+    /// later self-test passes.
+    pub async fn run_self_test(&self, trigger: SelfTestTrigger, source: &str) -> Result<SelfTestReport> {
+        if self.is_recording() {
+            anyhow::bail!("Self-test can't run while recording");
+        }
+        self.ensure_started(Subsystem::Monitoring).await?;
+        if self.inner.self_test_running.swap(true, Ordering::SeqCst) {
+            anyhow::bail!("Self-test already running");
+        }
+
+        let config = self.read_config().self_test.clone();
+        let started_at = Utc::now();
+        let mut results = vec![self.check_camera().await];
+        results.extend(self.check_speaker_and_microphone().await);
+        results.extend(self.check_leds_and_buttons(std::time::Duration::from_secs(config.operator_timeout_seconds)).await);
+        results.push(self.check_gps_fix(std::time::Duration::from_secs(config.gps_timeout_seconds)).await);
+        results.push(self.check_api().await);
+        self.inner.self_test_running.store(false, Ordering::SeqCst);
+
+        let report = SelfTestReport::new(trigger, started_at, results, &config.critical);
+        if let Err(e) = report.save().await {
+            tracing::warn!("Failed to save self-test report: {}", e);
+        }
+        *self.inner.self_test.lock().unwrap() = Some(report.clone());
+
+        let ready = report.ready_for_duty();
+        self.set_led_indicator(LedIndicator::NotReady, !ready).await?;
+        if ready {
+            tracing::info!("Self-test finished, {}", if report.passed() { "all checks passed" } else { "ready for duty" });
+        } else {
+            let failed: Vec<_> = report.critical_failures.iter().map(|check| check.as_str()).collect();
+            tracing::error!("Self-test failed ({}): not ready for duty", failed.join(", "));
+            if !self.is_stealth_mode() {
+                self.speak("prompt.not_ready", crate::audio::AudioPriority::High).await;
+            }
+        }
+        self.refresh_display().await;
+
+        self.audit_log().record("self_test", source, serde_json::json!({
+            "trigger": report.trigger,
+            "results": report.results,
+            "ready_for_duty": ready,
+        })).await?;
+        Ok(report)
+    }
+
+    async fn check_camera(&self) -> CheckResult {
+        if self.read_config().simulation.enabled {
+            return CheckResult::skipped(SelfTestCheck::Camera, "simulation");
+        }
+        match night_mode::measure_frame_luminance(self.inner.camera_controls.device_path()).await {
+            Ok(luma) => CheckResult::passed(SelfTestCheck::Camera, Some(format!("frame luminance {:.0}", luma))),
+            Err(e) => CheckResult::failed(SelfTestCheck::Camera, format!("{:#}", e)),
+        }
+    }
+
+    /// Play a tone and listen for it on the microphone
+    async fn check_speaker_and_microphone(&self) -> [CheckResult; 2] {
+        if self.is_stealth_mode() {
+            return [
+                CheckResult::skipped(SelfTestCheck::Speaker, "stealth mode"),
+                CheckResult::skipped(SelfTestCheck::Microphone, "stealth mode"),
+            ];
+        }
+        let audio_enabled = self.read_config().audio.enabled;
+        let meter = self.audio_meter();
+        let background = meter.map(|meter| meter.level().rms_db);
+
+        let tone = [Tone::new(SELF_TEST_TONE_HZ, SELF_TEST_TONE_MS, 0)];
+        let playing = self.inner.buzzer.play_sequence(self.inner.hardware.as_ref(), &tone);
+        let listening = async {
+            let mut loudest = f32::MIN;
+            if let Some(meter) = meter {
+                let until = tokio::time::Instant::now() + std::time::Duration::from_millis(SELF_TEST_TONE_MS + 300);
+                while tokio::time::Instant::now() < until {
+                    loudest = loudest.max(meter.level().peak_db);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                }
+            }
+            loudest
+        };
+        let (played, loudest) = tokio::join!(playing, listening);
+
+        let speaker = match played {
+            Ok(()) => CheckResult::passed(SelfTestCheck::Speaker, None),
+            Err(e) => CheckResult::failed(SelfTestCheck::Speaker, format!("{:#}", e)),
+        };
+        let microphone = match background {
+            None if !audio_enabled => CheckResult::skipped(SelfTestCheck::Microphone, "audio disabled"),
+            None => CheckResult::failed(SelfTestCheck::Microphone, "microphone unavailable"),
+            Some(_) if speaker.status == CheckStatus::Failed => {
+                CheckResult::skipped(SelfTestCheck::Microphone, "no test tone to listen for")
+            }
+            Some(background) if loudest >= background + SELF_TEST_LOOPBACK_DB => {
+                CheckResult::passed(SelfTestCheck::Microphone, Some(format!("tone heard at {:.0} dB", loudest)))
+            }
+            Some(background) => CheckResult::failed(
+                SelfTestCheck::Microphone,
+                format!("tone not heard: loudest {:.0} dB against {:.0} dB background", loudest, background),
+            ),
+        };
+        [speaker, microphone]
+    }
+
+    /// Light every LED and ask the operator to press Record. Nobody being
+    /// there to answer isn't a failure, so both are skipped on a timeout.
+    async fn check_leds_and_buttons(&self, timeout: std::time::Duration) -> [CheckResult; 2] {
+        if self.is_stealth_mode() {
+            return [
+                CheckResult::skipped(SelfTestCheck::Leds, "stealth mode"),
+                CheckResult::skipped(SelfTestCheck::Buttons, "stealth mode"),
+            ];
+        }
+        let mut events = self.inner.events.subscribe_to(&[Topic::Hardware]);
+        let lit = self.inner.leds.lock().await.lamp_test(self.inner.hardware.as_ref(), true).await;
+        self.speak("prompt.self_test_press_record", crate::audio::AudioPriority::Normal).await;
+
+        let pressed = tokio::time::timeout(timeout, async {
+            while let Some(event) = events.recv().await {
+                if matches!(event, BusEvent::Hardware(HardwareEvent::ButtonPressed { button: crate::hardware::ButtonType::Record, .. })) {
+                    return true;
+                }
+            }
+            false
+        }).await.unwrap_or(false);
+
+        if let Err(e) = self.inner.leds.lock().await.lamp_test(self.inner.hardware.as_ref(), false).await {
+            tracing::warn!("Failed to restore LEDs after self-test: {}", e);
+        }
+
+        let leds = match lit {
+            Err(e) => CheckResult::failed(SelfTestCheck::Leds, format!("{:#}", e)),
+            Ok(()) if pressed => CheckResult::passed(SelfTestCheck::Leds, None),
+            Ok(()) => CheckResult::skipped(SelfTestCheck::Leds, "no operator response"),
+        };
+        let buttons = if pressed {
+            CheckResult::passed(SelfTestCheck::Buttons, None)
+        } else {
+            CheckResult::skipped(SelfTestCheck::Buttons, "no operator response")
+        };
+        [leds, buttons]
+    }
+
+    async fn check_gps_fix(&self, timeout: std::time::Duration) -> CheckResult {
+        if !self.read_config().hardware.gps {
+            return CheckResult::skipped(SelfTestCheck::Gps, "GPS disabled");
+        }
+        if let Err(e) = self.ensure_started(Subsystem::Gps).await {
+            return CheckResult::failed(SelfTestCheck::Gps, format!("{:#}", e));
+        }
+        let until = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(fix) = self.inner.gps_manager.get_location().await {
+                if self.inner.gps_manager.fix_quality(&fix) != FixQuality::Stale {
+                    return CheckResult::passed(SelfTestCheck::Gps, Some(format!("{:.5}, {:.5}", fix.latitude, fix.longitude)));
+                }
+            }
+            if tokio::time::Instant::now() >= until {
+                return CheckResult::failed(SelfTestCheck::Gps, format!("no fix within {}s", timeout.as_secs()));
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    }
+
+    async fn check_api(&self) -> CheckResult {
+        let Some(device_id) = self.device_id() else {
+            return CheckResult::skipped(SelfTestCheck::Api, "not provisioned");
+        };
+        let started = std::time::Instant::now();
+        match self.backend().get_settings(&device_id).await {
+            Ok(_) => CheckResult::passed(SelfTestCheck::Api, Some(format!("{} ms", started.elapsed().as_millis()))),
+            Err(e) => CheckResult::failed(SelfTestCheck::Api, format!("{:#}", e)),
+        }
+    }
+
+    pub async fn diagnose(&self) -> Result<DiagnosticsReport> {
+        let battery_level = self.inner.hardware.get_battery_level().await?;
+        let storage_info = self.inner.hardware.get_storage_info().await?;
+        let temperature = self.inner.hardware.get_temperature().await?;
+        let battery_info = self.inner.hardware.get_battery_info().await.unwrap_or_default();
+        
+        // Get resource stats from resource manager
+        let resource_stats = self.inner.resource_manager.get_resource_stats().await;
+        let charging_safety = self.inner.charging_safety.lock().await;
+        let config = self.config();
+
+        let mut sensors = vec![
+            SensorStatus {
+                sensor_type: "battery".to_string(),
+                status: "ok".to_string(),
+                value: Some(battery_level as f64),
+            },
+            SensorStatus {
+                sensor_type: "battery_current_ma".to_string(),
+                status: battery_info.health.clone().unwrap_or_else(|| "unknown".to_string()),
+                value: battery_info.current_ma.map(|c| c as f64),
+            },
+            SensorStatus {
+                sensor_type: "battery_cycle_count".to_string(),
+                status: if battery_info.cycle_count.is_some() { "ok".to_string() } else { "unavailable".to_string() },
+                value: battery_info.cycle_count.map(|c| c as f64),
+            },
+            SensorStatus {
+                sensor_type: "battery_temperature".to_string(),
+                status: if charging_safety.is_overheating() {
+                    "warning".to_string()
+                } else if !charging_safety.fast_charge_allowed() {
+                    "fast_charge_refused".to_string()
+                } else {
+                    "ok".to_string()
+                },
+                value: battery_info.temperature_c.map(|t| t as f64),
+            },
+            SensorStatus {
+                sensor_type: "charge_current_ma".to_string(),
+                status: if charging_safety.has_current_anomaly() { "warning".to_string() } else { "ok".to_string() },
+                value: battery_info.current_ma.filter(|_| battery_info.charging).map(|c| c as f64),
+            },
+            SensorStatus {
+                sensor_type: "battery_full_capacity_mah".to_string(),
+                status: if charging_safety.capacity_drop_percent().is_some() { "warning".to_string() } else { "ok".to_string() },
+                value: battery_info.full_capacity_mah.map(|c| c as f64),
+            },
+            SensorStatus {
+                sensor_type: "temperature".to_string(),
+                status: "ok".to_string(),
+                value: Some(temperature as f64),
+            },
+            SensorStatus {
+                sensor_type: "memory_usage_mb".to_string(),
+                status: "ok".to_string(),
+                value: Some(resource_stats.memory_usage.process_memory_kb as f64 / 1024.0),
+            },
+            SensorStatus {
+                sensor_type: "cpu_usage_percent".to_string(),
+                status: "ok".to_string(),
+                value: Some(resource_stats.process_stats.cpu_usage_percent),
+            },
+            SensorStatus {
+                sensor_type: "disk_usage_percent".to_string(),
+                status: if resource_stats.disk_usage.used_gb / resource_stats.disk_usage.total_gb > 0.85 { "warning".to_string() } else { "ok".to_string() },
+                value: Some((resource_stats.disk_usage.used_gb / resource_stats.disk_usage.total_gb) * 100.0),
+            },
+        ];
+
+        if let Some(performance) = self.recording_performance() {
+            let status = match performance.recording_status {
+                crate::diagnostics::HealthStatus::Healthy => "ok",
+                crate::diagnostics::HealthStatus::Unknown => "unknown",
+                _ => "warning",
+            };
+            sensors.push(SensorStatus {
+                sensor_type: "recording_fps".to_string(),
+                status: status.to_string(),
+                value: performance.current_fps,
+            });
+            sensors.push(SensorStatus {
+                sensor_type: "dropped_frames".to_string(),
+                status: status.to_string(),
+                value: Some(performance.dropped_frames as f64),
+            });
+        }
+
+        Ok(DiagnosticsReport {
+            device_id: self.device_id().unwrap_or_else(|| "unknown".to_string()),
+            timestamp: Utc::now(),
+            battery_level,
+            storage_info,
+            temperature,
+            sensors,
+            camera_status: CameraStatus {
+                enabled: true,
+                resolution: config.recording.resolution.clone(),
+                fps: config.recording.fps,
+                bitrate: config.recording.bitrate,
+            },
+            audio_status: AudioStatus {
+                enabled: true,
+                sample_rate: config.audio.sample_rate,
+                channels: config.audio.channels,
+                bitrate: config.audio.bitrate,
+            },
+            network_status: self.get_network_status(),
+        })
+    }
+
+
+    async fn start_monitoring(&self
+    ) -> Result<()> {
+        // Hardware events go through the bus so the simulator and plugins see
+        // the same stream the device acts on
+        let supervisor = TaskSupervisor::global();
+        // Subscribed before the forwarder starts, so its first events aren't missed
+        let hardware_events = self.inner.events.subscribe_to(&[Topic::Hardware]);
+        let hardware_rx = Arc::new(Mutex::new(self.inner.hardware.start_monitoring().await?));
+        let bus = self.inner.events.clone();
+        supervisor.spawn("hardware_events", MONITOR_RESTART, move || {
+            let hardware_rx = hardware_rx.clone();
+            let bus = bus.clone();
+            async move {
+                // Held for the life of the run, so a restart picks it back up
+                let mut hardware_rx = hardware_rx.lock().await;
+                while let Some(event) = hardware_rx.recv().await {
+                    bus.publish(BusEvent::Hardware(event));
+                }
+                anyhow::Ok(())
+            }
+        });
+        let hotplug_events = HotplugMonitor::start()?;
+        let detection_events = self.start_detection();
+        let vehicle_events = {
+            let config = self.read_config();
+            if config.vehicle.enabled && !config.simulation.enabled {
+                vehicle::spawn_monitor(config.vehicle.clone(), self.inner.gps_manager.shared_location()).0
+            } else {
+                tokio::sync::mpsc::unbounded_channel().1
+            }
+        };
+        // Loops hold the device weakly, so they end once it is dropped
+        let device = Arc::downgrade(&self.inner);
+        let sources = Arc::new(Mutex::new((hardware_events, hotplug_events, detection_events, vehicle_events)));
+
+        supervisor.spawn("device_events", MONITOR_RESTART, move || {
+            let device = device.clone();
+            let sources = sources.clone();
+            async move {
+                let mut sources = sources.lock().await;
+                let (event_rx, hotplug_rx, detection_rx, vehicle_rx) = &mut *sources;
+
+                loop {
+                    tokio::select! {
+                        Some(BusEvent::Hardware(event)) = event_rx.recv() => {
+                            let Some(device) = Self::upgrade(&device) else { break };
+                            Self::handle_hardware_event(&device, event).await;
+                        }
+                        Some(event) = hotplug_rx.recv() => {
+                            let Some(device) = Self::upgrade(&device) else { break };
+                            Self::handle_hotplug_event(&device, event).await;
+                        }
+                        Some(event) = detection_rx.recv() => {
+                            let Some(device) = Self::upgrade(&device) else { break };
+                            device.handle_detection(event).await;
+                        }
+                        Some(event) = vehicle_rx.recv() => {
+                            let Some(device) = Self::upgrade(&device) else { break };
+                            device.handle_vehicle_event(event).await;
+                        }
+                        else => break,
+                    }
+                }
+                anyhow::Ok(())
+            }
+        });
+
+        // Stream outputs and encoders are polled often so a dropped
+        // connection or crashed ffmpeg recovers within seconds rather than at
+        // the next status report. Separate from the event loop so a slow
+        // button action doesn't hold up recovery.
+        let device = Arc::downgrade(&self.inner);
+        supervisor.spawn("media_supervision", MONITOR_RESTART, move || {
+            let device = device.clone();
+            async move {
+                let mut process_check = tokio::time::interval(tokio::time::Duration::from_secs(1));
+                loop {
+                    process_check.tick().await;
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    device.supervise_streaming().await;
+                    device.update_stream_presence().await;
+                    device.supervise_recording().await;
+                }
+                anyhow::Ok(())
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Start the object detector if enabled. The returned channel simply
+    /// stays empty when detection is off or unavailable.
+    fn start_detection(&self) -> tokio::sync::mpsc::UnboundedReceiver<DetectionEvent> {
+        let config = self.read_config();
+        if config.detection.enabled && !config.simulation.enabled {
+            let source = FrameSource {
+                buffer: self.inner.buffer.clone(),
+                camera_path: self.inner.camera_controls.device_path().to_string(),
+                camera_busy: self.inner.camera_busy.clone(),
+            };
+            match detection::spawn_detector(config.detection.clone(), source, self.inner.resource_manager.throttle()) {
+                Ok((events, _handle)) => return events,
+                Err(e) => tracing::warn!("Object detection unavailable: {}", e),
+            }
+        }
+
+        tokio::sync::mpsc::unbounded_channel().1
+    }
+
+    /// Feed a detection to the incident rules, starting an incident when one fires
+    async fn handle_detection(&self, event: DetectionEvent) {
+        tracing::debug!("Detected {} ({:.2})", event.label, event.confidence);
+
+        let matched = self.inner.incident_rules.lock().unwrap().observe(event.class.signal(), event.confidence, event.timestamp);
+        let Some(matched) = matched else {
+            return;
+        };
+
+        let _ = self.audit_log().record(
+            "incident_rule_fired",
+            "detection",
+            serde_json::json!({
+                "signal": matched.signal,
+                "incident_type": matched.incident_type,
+                "severity": matched.severity,
+                "confidence": matched.confidence,
+                "label": event.label,
+            }),
+        ).await;
+
+        if self.inner.active_incident.get().is_some() {
+            return;
+        }
+        if let Err(e) = self.trigger_incident_with_pre_roll(matched.incident_type, matched.severity, matched.pre_roll_seconds).await {
+            tracing::warn!("Failed to start {} incident: {}", matched.incident_type, e);
+        }
+    }
+
+    /// Feed a detection to the incident rules as if the detector had made
+    /// it (simulation only)
+    pub async fn simulate_detection(&self, event: DetectionEvent) {
+        self.handle_detection(event).await;
+    }
+
+    /// Feed a vehicle event to the incident rules
+    async fn handle_vehicle_event(&self, event: VehicleEvent) {
+        let _ = self.audit_log().record("vehicle_event", "vehicle", serde_json::to_value(&event).unwrap_or_default()).await;
+
+        let matched = self.inner.incident_rules.lock().unwrap().observe(event.signal(), 1.0, Utc::now());
+        let Some(matched) = matched else {
+            return;
+        };
+        if self.inner.active_incident.get().is_some() {
+            return;
+        }
+        if let Err(e) = self.trigger_incident_with_pre_roll(matched.incident_type, matched.severity, matched.pre_roll_seconds).await {
+            tracing::warn!("Failed to start {} incident: {}", matched.incident_type, e);
+        }
+    }
+
+    /// The platform backend this device registered with. Replaced when the
+    /// device switches sites, so hold on to it only for the call at hand.
+    pub fn backend(&self) -> Arc<dyn PlatformBackend> {
+        self.inner.backend.read().unwrap().clone()
+    }
+
+    /// Send the current status to the backend
+    /// Report status, as a delta against the last acknowledged report where
+    /// the backend supports it. Returns the status that was reported.
+    pub async fn report_status(&self) -> Result<DeviceStatus> {
+        let status = self.get_status().await?;
+        let backend = self.backend();
+
+        // Deltas only make sense with nothing older still waiting in the journal
+        let deltas = self.read_config().monitoring.status_deltas && backend.supports_status_deltas();
+        let mut reporting = self.inner.reporting.lock().await;
+        if deltas && reporting.journal.is_empty() {
+            let report = reporting.status_encoder.encode(&status)?;
+            if let StatusReport::Delta(delta) = &report {
+                match backend.report_status_delta(delta).await {
+                    Ok(()) => {
+                        reporting.status_encoder.acknowledge(&report)?;
+                        return Ok(status);
+                    }
+                    Err(e) => {
+                        // The server may have lost the base report; resync in full
+                        tracing::debug!("Status delta not delivered, falling back to a full report: {:#}", e);
+                        reporting.status_encoder.reset();
+                    }
+                }
+            }
+        }
+
+        reporting.submit(backend.as_ref(), JournalOp::Status { status: status.clone() }).await?;
+        if reporting.journal.is_empty() {
+            reporting.status_encoder.acknowledge(&StatusReport::Full(status.clone()))?;
+        }
+        Ok(status)
+    }
+
+    pub async fn send_metrics(&self, metrics: crate::api::DeviceMetrics) -> Result<()> {
+        self.record_metrics(&metrics);
+        self.submit(JournalOp::Metrics { metrics }).await
+    }
+
+    /// Current CPU, memory, storage, battery and network readings
+    pub async fn collect_metrics(&self) -> crate::api::DeviceMetrics {
+        let stats = self.inner.resource_manager.get_resource_stats().await;
+        let network = self.inner.network.handle().get();
+        let percent = |used: f64, total: f64| if total > 0.0 { (used / total * 100.0) as f32 } else { 0.0 };
+        crate::api::DeviceMetrics {
+            device_id: self.device_id().unwrap_or_default(),
+            timestamp: Utc::now(),
+            cpu_usage: stats.process_stats.cpu_usage_percent as f32,
+            memory_usage: percent(stats.memory_usage.used_kb as f64, stats.memory_usage.total_kb as f64),
+            storage_usage: percent(stats.disk_usage.used_gb, stats.disk_usage.total_gb),
+            battery_level: self.inner.hardware.get_battery_level().await.unwrap_or(0.0),
+            temperature: self.inner.hardware.get_temperature().await.unwrap_or(0.0),
+            network_quality: if network.online { network.link.as_str().to_string() } else { "offline".to_string() },
+            active_incidents: self.inner.active_incident.get().is_some() as u32,
+            data_usage: self.data_usage().await,
+        }
+    }
+
+    fn record_metrics(&self, metrics: &crate::api::DeviceMetrics) {
+        if let Some(history) = &self.inner.metrics_history {
+            if let Err(e) = history.record_metrics(metrics) {
+                tracing::warn!("Failed to record metrics history: {:#}", e);
+            }
+        }
+    }
+
+    /// Keep a metrics sample and the size of each storage category locally
+    async fn sample_metrics_history(&self) {
+        let Some(history) = &self.inner.metrics_history else {
+            return;
+        };
+        let metrics = self.collect_metrics().await;
+        self.record_metrics(&metrics);
+
+        let disk = self.inner.resource_manager.get_resource_stats().await.disk_usage;
+        let system_gb = (disk.used_gb - disk.recordings_gb - disk.logs_gb - disk.temp_files_gb).max(0.0);
+        for (series, used_gb) in [
+            (StorageSeries::Recordings, disk.recordings_gb),
+            (StorageSeries::Logs, disk.logs_gb),
+            (StorageSeries::Temp, disk.temp_files_gb),
+            (StorageSeries::System, system_gb),
+        ] {
+            if let Err(e) = history.record_storage(series, used_gb, metrics.timestamp) {
+                tracing::warn!("Failed to record storage history: {:#}", e);
+                return;
+            }
+        }
+    }
+
+    /// Metrics kept on the device over the last `window`, oldest first
+    pub fn metrics_history(&self, window: chrono::Duration) -> Result<Vec<crate::api::DeviceMetrics>> {
+        let history = self.inner.metrics_history.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Metrics history is disabled"))?;
+        history.metrics_since(Utc::now() - window)
+    }
+
+    /// Journal a backend call, then deliver everything pending oldest first
+    async fn submit(&self, op: JournalOp) -> Result<()> {
+        let backend = self.backend();
+        self.inner.reporting.lock().await.submit(backend.as_ref(), op).await
+    }
+
+    /// Replay journaled backend calls, e.g. when connectivity returns
+    pub async fn flush_journal(&self) -> Result<ReplayReport> {
+        let backend = self.backend();
+        self.inner.reporting.lock().await.journal.replay(backend.as_ref()).await
+    }
+
+    pub async fn pending_journal_entries(&self) -> usize {
+        self.inner.reporting.lock().await.journal.len()
+    }
+
+    /// Periodic housekeeping; status reports are sent by the service runner
+    fn start_status_reporting(&self) {
+        let device = Arc::downgrade(&self.inner);
+
+        TaskSupervisor::global().spawn("housekeeping", MONITOR_RESTART, move || {
+            let device = device.clone();
+            async move {
+                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+                // Ends once the device shuts down or is dropped
+                loop {
+                    interval.tick().await;
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    if device.inner.canceller.is_shut_down() {
+                        break;
+                    }
+                    if device.is_deep_sleeping() {
+                        continue;
+                    }
+
+                    device.refresh_display().await;
+                    device.sample_frame_luminance().await;
+                    device.check_microphone().await;
+                    device.check_charging_safety().await;
+                    device.apply_brightness().await;
+                    device.update_recording_remaining().await;
+                    device.report_resource_sensors().await;
+                    device.sample_metrics_history().await;
+                    device.sample_data_usage().await;
+                    device.follow_communications().await;
+                    device.refresh_contacts(false).await;
+                    if let Err(e) = device.sync_audio_presets(false).await {
+                        tracing::debug!("Audio preset library unreachable, keeping cached presets: {:#}", e);
+                    }
+                    device.refresh_communication_policy().await;
+                    device.maintain_certificate().await;
+                    device.enforce_recording_policy().await;
+
+                    // Check storage and perform automatic cleanup
+                    let (cleanup, full) = {
+                        let mut storage = device.inner.storage_manager.lock().await;
+                        let cleanup = storage.check_storage_and_cleanup().await;
+                        (cleanup, storage.take_full_alert())
+                    };
+                    // Unconfirmed recordings are kept, so the device has to stop filling up instead
+                    if full {
+                        device.inner.events.publish(BusEvent::Hardware(HardwareEvent::StorageFull));
+                    }
+                    if let Ok(deleted_files) = cleanup {
+                        if !deleted_files.is_empty() {
+                            tracing::info!("Automatic storage cleanup completed, deleted {} files", deleted_files.len());
+
+                            // Save deletion log
+                            let saved = device.inner.storage_manager.lock().await.save_deletion_log().await;
+                            if let Err(e) = saved {
+                                tracing::error!("Failed to save deletion log: {}", e);
+                            }
+
+                            // Sync deletions to server
+                            let _ = device.sync_deletions_to_server().await;
+                        }
+                    }
+                }
+                anyhow::Ok(())
+            }
+        });
+    }
+
+    /// Run the self-test at the nightly hour and when the device is docked
+    fn start_self_test_schedule(&self) {
+        let config = self.read_config().self_test.clone();
+        if !config.enabled || (config.nightly_hour.is_none() && !config.on_dock) {
+            return;
+        }
+        let device = Arc::downgrade(&self.inner);
+        let bus = self.inner.events.clone();
+
+        TaskSupervisor::global().spawn("self_test", MONITOR_RESTART, move || {
+            let device = device.clone();
+            let config = config.clone();
+            let mut events = bus.subscribe_to(&[Topic::Hardware]);
+            async move {
+                loop {
+                    let nightly = async {
+                        match config.nightly_hour {
+                            Some(hour) => tokio::time::sleep(crate::self_test::until_nightly_run(hour)).await,
+                            None => std::future::pending().await,
+                        }
+                    };
+                    let trigger = tokio::select! {
+                        _ = nightly => SelfTestTrigger::Nightly,
+                        event = events.recv() => match event {
+                            Some(BusEvent::Hardware(HardwareEvent::ChargingConnected)) if config.on_dock => SelfTestTrigger::Docked,
+                            Some(_) => continue,
+                            None => break,
+                        },
+                    };
+
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    if device.inner.canceller.is_shut_down() {
+                        break;
+                    }
+                    if let Err(e) = device.run_self_test(trigger, "schedule").await {
+                        tracing::info!("Skipped {:?} self-test: {}", trigger, e);
+                    }
+                }
+                anyhow::Ok(())
+            }
+        });
+    }
+
+    /// Record nonstop in continuous mode, starting a new segment every
+    /// `segment_duration` and overwriting the oldest unlocked footage once
+    /// the loop is full
+    fn start_loop_recording(&self) {
+        if !self.read_config().loop_recording.enabled {
+            return;
+        }
+        let device = Arc::downgrade(&self.inner);
+
+        TaskSupervisor::global().spawn("loop_recording", MONITOR_RESTART, move || {
+            let device = device.clone();
+            async move {
+                loop {
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    if device.inner.canceller.is_shut_down() {
+                        break;
+                    }
+                    device.rotate_loop_segment().await;
+                    let segment_duration = device.read_config().recording.segment_duration.max(10);
+                    drop(device);
+                    tokio::time::sleep(std::time::Duration::from_secs(segment_duration)).await;
+                }
+                anyhow::Ok(())
+            }
+        });
+    }
+
+    /// Follow the ambient noise and keep the speaker trim up to date
+    fn start_adaptive_volume(&self) {
+        let window = std::time::Duration::from_secs(self.read_config().audio.loudness.ambient_window_seconds);
+        let device = Arc::downgrade(&self.inner);
+
+        TaskSupervisor::global().spawn("adaptive_volume", MONITOR_RESTART, move || {
+            let device = device.clone();
+            async move {
+                let mut ambient = AmbientTracker::new(window);
+                loop {
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    if device.inner.canceller.is_shut_down() {
+                        break;
+                    }
+                    device.update_output_trim(&mut ambient).await;
+                    drop(device);
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                }
+                anyhow::Ok(())
+            }
+        });
+    }
+
+    /// Wake at the scheduled times, and sleep again after a scheduled
+    /// check-in or once idle on battery for `idle_minutes`
+    fn start_deep_sleep_monitor(&self) {
+        let device = Arc::downgrade(&self.inner);
+
+        TaskSupervisor::global().spawn("deep_sleep", MONITOR_RESTART, move || {
+            let device = device.clone();
+            async move {
+                loop {
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    if device.inner.canceller.is_shut_down() {
+                        break;
+                    }
+                    device.follow_sleep_schedule().await;
+                    drop(device);
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+                anyhow::Ok(())
+            }
+        });
+    }
+
+    async fn follow_sleep_schedule(&self) {
+        let now = Utc::now();
+        let scheduled_wake = self.inner.deep_sleep.lock().unwrap().as_ref()
+            .map(|session| session.next_scheduled_wake);
+        if let Some(next_scheduled_wake) = scheduled_wake {
+            if next_scheduled_wake.is_some_and(|at| now >= at) {
+                if let Err(e) = self.wake_from_deep_sleep(WakeReason::Source(WakeSource::Rtc), "schedule").await {
+                    tracing::error!("Failed to wake from deep sleep: {:#}", e);
+                }
+            }
+            return;
+        }
+
+        // Anything on duty keeps the device awake
+        let charging = self.inner.hardware.is_charging().await.unwrap_or(false);
+        if self.is_recording() || self.inner.active_incident.get().is_some() || self.is_streaming().await || charging {
+            *self.inner.last_activity.lock().unwrap() = now;
+            return;
+        }
+
+        let idle_minutes = self.read_config().power_management.deep_sleep.idle_minutes;
+        let check_in_over = self.inner.scheduled_sleep_at.lock().unwrap().is_some_and(|at| now >= at);
+        let idle = idle_minutes.is_some_and(|minutes| {
+            now - *self.inner.last_activity.lock().unwrap() >= chrono::Duration::minutes(minutes as i64)
+        });
+        if check_in_over || idle {
+            let source = if check_in_over { "schedule" } else { "idle" };
+            if let Err(e) = self.enter_deep_sleep(source).await {
+                tracing::warn!("Couldn't enter deep sleep: {:#}", e);
+                *self.inner.last_activity.lock().unwrap() = now;
+                *self.inner.scheduled_sleep_at.lock().unwrap() = None;
+            }
+        }
+    }
+
+    /// Sample the ambient level while the speaker is quiet, so alerts don't
+    /// raise their own volume, and apply it with the calibration
+    async fn update_output_trim(&self, ambient: &mut AmbientTracker) {
+        let loudness = self.read_config().audio.loudness.clone();
+        if loudness.adaptive && self.inner.audio_manager.is_idle() {
+            if let Some(meter) = self.audio_meter() {
+                ambient.push(std::time::Instant::now(), meter.level().rms_db);
+            }
+        }
+
+        let ambient_db = ambient.ambient_db().filter(|_| loudness.adaptive);
+        let adaptive_db = ambient_db.map_or(0.0, |db| loudness.adaptive_gain_db(db));
+        let offset_db = self.inner.speaker_calibration.lock().await.offset_db;
+        self.inner.audio_manager.set_output_trim(offset_db + adaptive_db, ambient_db);
+    }
+
+    /// Close the current segment and carry on into the next, then lock and
+    /// overwrite footage. Footage during an incident is recorded under the
+    /// incident, so it's kept and uploaded like any other incident.
+    async fn rotate_loop_segment(&self) {
+        // A paused recording stays paused until the operator resumes it
+        if !self.read_config().is_provisioned() || self.is_deep_sleeping() || self.is_recording_paused() {
+            return;
+        }
+        let incident_id = self.inner.active_incident.get()
+            .map(|incident| incident.incident_id)
+            .unwrap_or_else(|| LOOP_INCIDENT_ID.to_string());
+        if self.is_recording() {
+            self.rotate_recording(incident_id).await;
+        } else if let Err(e) = self.start_recording(None, Some(incident_id)).await {
+            tracing::warn!("Continuous recording failed to start: {:#}", e);
+        }
+
+        if let Err(e) = self.maintain_loop().await {
+            tracing::error!("Failed to maintain the recording loop: {:#}", e);
+        }
+    }
+
+    /// Move the running recording on to new segments without the feedback
+    /// of a stop and start. A recording that can't carry on is stopped, so
+    /// the next rotation starts it afresh.
+    async fn rotate_recording(&self, incident_id: String) {
+        let rotated = match self.inner.recorder.lock().await.as_mut() {
+            Some(recorder) => recorder.rotate(incident_id.clone()).await,
+            None => return,
+        };
+        if let Err(e) = rotated {
+            tracing::warn!("Failed to rotate loop segment: {:#}", e);
+            if let Err(e) = self.stop_recording().await {
+                tracing::error!("Failed to stop recording after a failed rotation: {:#}", e);
+            }
+            return;
+        }
+
+        let mut secondary = self.inner.secondary_recorder.lock().await;
+        if let Some(recorder) = secondary.as_mut() {
+            if let Err(e) = recorder.rotate(incident_id).await {
+                tracing::warn!("Recording without second camera: {:#}", e);
+                *secondary = None;
+            }
+        }
+    }
+
+    /// Lock loop footage around incidents and bookmarks, then overwrite the
+    /// oldest unlocked footage past the loop size
+    async fn maintain_loop(&self) -> Result<()> {
+        let config = self.read_config().loop_recording.clone();
+        let mut index = RecordingIndex::open()?;
+        let segments = index.segments(&SegmentQuery {
+            incident_id: Some(LOOP_INCIDENT_ID.to_string()),
+            ..SegmentQuery::default()
+        })?;
+        let Some(oldest) = segments.last() else { return Ok(()) };
+
+        let since = oldest.start_time - chrono::Duration::seconds(config.lock_before_seconds as i64);
+        let windows: Vec<LockWindow> = index.incidents(since)?.iter()
+            .map(|incident| LockWindow::for_incident(incident, &config))
+            .chain(index.incident_bookmarks(LOOP_INCIDENT_ID)?.into_iter()
+                .map(|(bookmark, at)| LockWindow::for_bookmark(bookmark.id, at, &config)))
+            .collect();
+
+        let mut locked = index.locked_segments()?;
+        let to_lock = loop_recording::segments_to_lock(&segments, &windows, &locked);
+        for (segment, reason) in &to_lock {
+            index.lock_segment(&segment.id, reason)?;
+            locked.insert(segment.id.clone());
+        }
+        if !to_lock.is_empty() {
+            tracing::info!("Locked {} loop segments", to_lock.len());
+            self.audit_log().record("loop_segments_locked", "loop_recording", serde_json::json!({
+                "segments": to_lock.iter()
+                    .map(|(segment, reason)| serde_json::json!({ "segment_id": segment.id, "reason": reason }))
+                    .collect::<Vec<_>>(),
+            })).await?;
+        }
+
+        let overwrite = loop_recording::segments_to_overwrite(&segments, &locked, config.loop_bytes());
+        if overwrite.is_empty() {
+            return Ok(());
+        }
+        let deleted = self.inner.storage_manager.lock().await
+            .overwrite_loop_segments(&overwrite, &mut index).await?;
+        drop(index);
+        if !deleted.is_empty() {
+            tracing::info!("Overwrote {} loop segments", deleted.len());
+            if let Err(e) = self.sync_deletions_to_server().await {
+                tracing::warn!("Failed to report loop overwrites: {:#}", e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_hardware_event(
+        device: &BodycamDevice,
+        event: HardwareEvent
+    ) {
+        if device.is_deep_sleeping() {
+            let config = device.read_config().power_management.deep_sleep.clone();
+            let emergency = matches!(event, HardwareEvent::ButtonPressed { button: crate::hardware::ButtonType::Emergency, .. });
+            let source = WakeSource::for_event(&event).filter(|source| emergency || config.wakes_on(*source));
+            let Some(source) = source else { return };
+            if let Err(e) = device.wake_from_deep_sleep(WakeReason::Source(source), source.as_str()).await {
+                tracing::error!("Failed to wake from deep sleep: {:#}", e);
+            }
+            // The press that woke the device does nothing else, except an emergency
+            if !emergency {
+                return;
+            }
+        }
+        match event {
+            HardwareEvent::ButtonPressed { button, duration } => {
+                *device.inner.last_activity.lock().unwrap() = Utc::now();
+                device.inner.display.lock().await.wake();
+                if device.inner.controls_locked.load(Ordering::Relaxed) && !matches!(button, ButtonType::Emergency) {
+                    tracing::warn!("Ignoring {:?} button: controls locked after tampering", button);
+                    return;
+                }
+                if matches!(button, ButtonType::Record) && device.inner.self_test_running.load(Ordering::SeqCst) {
+                    // The self-test is waiting on this press
+                    return;
+                }
+                // The emergency button always raises its incident
+                if !matches!(button, ButtonType::Emergency) && device.dispatch_button(button, duration).await {
+                    return;
+                }
+                Self::handle_button_press(device, button, duration).await;
+            }
+            HardwareEvent::BatteryLow { level } => {
+                let _ = device.set_led_indicator(LedIndicator::LowBattery, true).await;
+                if level < 10.0 {
+                    device.beep_countdown(3).await;
+                    let _ = device.inner.hardware.shutdown().await;
+                } else {
+                    device.play_tone(ToneEvent::LowBattery).await;
+                    let _ = device.vibrate_pattern(HapticPattern::Heartbeat).await;
+                }
+            }
+            HardwareEvent::BatteryCritical { level } => {
+                tracing::error!("Battery critical at {}%", level);
+            }
+            HardwareEvent::TemperatureHigh { temp } => {
+                tracing::warn!("Device temperature high: {}°C", temp);
+            }
+            HardwareEvent::BatteryOverheating { temp } => {
+                let error = format!("Battery at {}°C while charging", temp);
+                crate::sentry_capture_message!(&error, sentry::Level::Warning, "sensor" => "battery");
+                tracing::warn!("{}", error);
+                device.play_tone(ToneEvent::Error).await;
+            }
+            HardwareEvent::ChargeCurrentAnomaly { current_ma } => {
+                let error = format!("Unexpected charge current of {} mA", current_ma);
+                crate::sentry_capture_message!(&error, sentry::Level::Warning, "sensor" => "battery");
+                tracing::warn!("{}", error);
+            }
+            HardwareEvent::BatteryCapacityDrop { drop_percent } => {
+                let error = format!("Battery lost {:.0}% of its capacity recently; check it for swelling", drop_percent);
+                crate::sentry_capture_message!(&error, sentry::Level::Warning, "sensor" => "battery");
+                tracing::warn!("{}", error);
+            }
+            HardwareEvent::StorageFull => {
+                let _ = device.stop_recording().await;
+
+                // Perform immediate storage cleanup
+                let cleanup = device.inner.storage_manager.lock().await.check_storage_and_cleanup().await;
+                if let Ok(deleted_files) = cleanup {
+                    if !deleted_files.is_empty() {
+                        tracing::info!("Storage full cleanup completed, deleted {} files", deleted_files.len());
+
+                        // Save deletion log and notify server
+                        let _ = device.inner.storage_manager.lock().await.save_deletion_log().await;
+                        let _ = device.sync_deletions_to_server().await;
+                    } else {
+                        tracing::warn!("Storage full but no files could be deleted");
+                    }
+                }
+            }
+            HardwareEvent::ChargingConnected => {
+                let _ = device.set_led_indicator(LedIndicator::LowBattery, false).await;
+            }
+            HardwareEvent::SensorError { sensor, error } => {
+                tracing::error!("Sensor {} reported error: {}", sensor, error);
+                if let Some(history) = &device.inner.metrics_history {
+                    if let Err(e) = history.record_error(&sensor, Utc::now()) {
+                        tracing::warn!("Failed to record error history: {:#}", e);
+                    }
+                }
+                device.play_tone(ToneEvent::Error).await;
+            }
+            HardwareEvent::TamperDetected => {
+                device.respond_to_tamper().await;
+            }
+            HardwareEvent::LightDetected { level, threshold } => {
+                if let Err(e) = device.observe_light(LightReading::Lux(level)).await {
+                    tracing::error!("Failed to apply night mode: {}", e);
+                }
+                let _ = device.trigger_incident(IncidentType::LightDetection, IncidentSeverity::Medium).await;
+            }
+            HardwareEvent::SoundDetected { level, frequency } => {
+                let _ = device.trigger_incident(IncidentType::SoundDetection, IncidentSeverity::Low).await;
+            }
+            HardwareEvent::MovementDetected { acceleration, threshold } => {
+                let _ = device.trigger_incident(IncidentType::MovementDetection, IncidentSeverity::Medium).await;
+            }
+            HardwareEvent::SpeechDetected { confidence, phrase, duration } => {
+                let _ = device.trigger_incident(IncidentType::SpeechDetection, IncidentSeverity::High).await;
+            }
+            _ => {}
+        }
+    }
+
+    /// The button's built-in behaviour, for presses the synced mapping
+    /// leaves alone
+    async fn handle_button_press(device: &BodycamDevice, button: ButtonType, duration: Option<u64>) {
+        match button {
+            ButtonType::Record => {
+                let result = if duration.is_some() {
+                    device.stop_recording().await
+                } else {
+                    device.start_recording(None, None).await
+                };
+                if let Err(e) = result {
+                    tracing::warn!("Record button action failed: {}", e);
+                    device.play_tone(ToneEvent::Error).await;
+                }
+            }
+            ButtonType::Emergency => {
+                let _ = device.trigger_incident(IncidentType::Emergency, IncidentSeverity::High).await;
+            }
+            ButtonType::Power => {
+                if duration.map(|d| d >= 3000).unwrap_or(false) {
+                    let _ = device.inner.hardware.shutdown().await;
+                }
+            }
+            ButtonType::Menu => {
+                // Holding the menu button toggles stealth mode
+                if duration.map(|d| d >= 3000).unwrap_or(false) {
+                    let enabled = !device.is_stealth_mode();
+                    let _ = device.set_stealth_mode(enabled, "button").await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Run a press through the synced button mapping. Returns false when the
+    /// press isn't mapped and the built-in behaviour applies. While a double
+    /// or triple press is mapped for the button, a short press waits out the
+    /// multi-press window before anything happens.
+    async fn dispatch_button(&self, button: ButtonType, duration: Option<u64>) -> bool {
+        let actions = self.inner.button_actions.read().unwrap().clone();
+        if duration.is_some() || !actions.has_multi_press(button) {
+            let press = if duration.is_some() { PressKind::Long } else { PressKind::Single };
+            let Some(action) = actions.get(button, press) else { return false };
+            self.run_button_action(action, button, press).await;
+            return true;
+        }
+
+        let (seq, window) = {
+            let mut presses = self.inner.button_presses.lock().unwrap();
+            (presses.press(button, std::time::Instant::now()), presses.window())
+        };
+        let device = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            let press = device.inner.button_presses.lock().unwrap().settle(button, seq);
+            match (press, press.and_then(|press| actions.get(button, press))) {
+                (Some(press), Some(action)) => device.run_button_action(action, button, press).await,
+                (Some(PressKind::Single), None) => Self::handle_button_press(&device, button, None).await,
+                // An unmapped double or triple press does nothing
+                _ => {}
+            }
+        });
+        true
+    }
+
+    async fn run_button_action(&self, action: ButtonAction, button: ButtonType, press: PressKind) {
+        tracing::info!("{:?} button {} press: {}", button, press.as_str(), action.as_str());
+        let result = match action {
+            ButtonAction::RecordToggle if self.is_recording() => self.stop_recording().await,
+            ButtonAction::RecordToggle => self.start_recording(None, None).await,
+            ButtonAction::PauseToggle if self.is_recording_paused() => self.resume_recording("button").await,
+            ButtonAction::PauseToggle => self.pause_recording(None, "button").await,
+            ButtonAction::Bookmark => self.bookmark_recording("Button bookmark", "button").await.map(|_| ()),
+            ButtonAction::Sos => self.trigger_incident(IncidentType::Emergency, IncidentSeverity::High).await.map(|_| ()),
+            ButtonAction::Mute => self.set_muted(!self.is_muted(), "button").await,
+            ButtonAction::Stealth => self.set_stealth_mode(!self.is_stealth_mode(), "button").await,
+            ButtonAction::None => Ok(()),
+        };
+        match result {
+            // Confirm a bookmark, which gives no other feedback
+            Ok(()) if action == ButtonAction::Bookmark => {
+                let _ = self.vibrate_pattern(HapticPattern::Single).await;
+            }
+            Ok(()) => {}
+            Err(e) => {
+                tracing::warn!("Button action {} failed: {:#}", action.as_str(), e);
+                self.play_tone(ToneEvent::Error).await;
+            }
+        }
+    }
+
+    async fn handle_hotplug_event(
+        device: &BodycamDevice,
+        event: HotplugEvent
+    ) {
+        sentry_integration::add_device_breadcrumb("hotplug", Some(&format!("{:?}", event)));
+
+        match event {
+            HotplugEvent::Added { kind: MediaDeviceKind::Camera, device: path } => {
+                tracing::info!("Camera connected: {}", path);
+                if let Some(recorder) = device.inner.recorder.lock().await.as_mut() {
+                    if let Err(e) = recorder.handle_device_added(&path).await {
+                        tracing::error!("Failed to resume recording on {}: {}", path, e);
+                    }
+                }
+            }
+            HotplugEvent::Removed { kind: MediaDeviceKind::Camera, device: path } => {
+                tracing::warn!("Camera disconnected: {}", path);
+
+                let fallback = device.read_config().camera.fallback_device_path.clone();
+                let affected = match device.inner.recorder.lock().await.as_mut() {
+                    Some(recorder) => recorder.handle_device_removed(&path, fallback.as_deref()).await
+                        .unwrap_or_else(|e| {
+                            tracing::error!("Failed to handle camera removal: {}", e);
+                            true
+                        }),
+                    None => false,
+                };
+
+                if affected {
+                    // Losing the evidence camera mid-recording is treated as an incident
+                    crate::sentry_capture_message!(
+                        &format!("Evidence camera disconnected during recording: {}", path),
+                        sentry::Level::Error,
+                        "device_path" => path.clone()
+                    );
+                    device.play_tone(ToneEvent::Error).await;
+                    if let Err(e) = device.trigger_incident(IncidentType::CameraDisconnected, IncidentSeverity::Critical).await {
+                        tracing::error!("Failed to raise camera disconnect incident: {}", e);
+                    }
+                }
+            }
+            HotplugEvent::Added { kind: MediaDeviceKind::Microphone, device: name } => {
+                tracing::info!("Microphone connected: {}", name);
+            }
+            HotplugEvent::Removed { kind: MediaDeviceKind::Microphone, device: name } => {
+                let in_use = device.read_config().audio.device_path.as_deref() == Some(name.as_str());
+                if in_use && device.is_recording() {
+                    tracing::error!("Recording microphone {} disconnected", name);
+                    device.play_tone(ToneEvent::Error).await;
+                } else {
+                    tracing::info!("Microphone disconnected: {}", name);
+                }
+            }
+        }
+    }
+
+    pub async fn get_resource_stats(&self) -> Result<crate::resource_manager::ResourceStats> {
+        Ok(self.inner.resource_manager.get_resource_stats().await)
+    }
+
+    pub async fn force_cleanup(&self) -> Result<()> {
+        self.inner.resource_manager.force_cleanup().await
+    }
+
+    /// Delete every recording. Needs a supervisor: `credential`, or the
+    /// operator logged in on the device.
+    pub async fn clear_storage(&self, credential: Option<&Credential>, source: &str) -> Result<()> {
+        self.authorize(PrivilegedOperation::ClearStorage, credential, source).await?;
+        let _transaction = sentry_integration::start_transaction("device.clear_storage", "storage");
+
+        tracing::info!("Clearing all storage");
+        sentry_integration::add_device_breadcrumb("clear_storage", Some("user_requested"));
+
+        // Stop recording if active
+        if self.is_recording() {
+            self.stop_recording().await?;
+        }
+
+        // Clear media files, leaving anything under legal hold
+        let media_dir = std::env::current_dir()?.join("media");
+        if media_dir.exists() {
+            let holds = LegalHolds::load().await;
+            let mut kept = 0;
+            let mut entries = tokio::fs::read_dir(&media_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if holds.is_held(&path) {
+                    kept += 1;
+                } else if entry.file_type().await?.is_dir() {
+                    tokio::fs::remove_dir_all(&path).await?;
+                } else {
+                    tokio::fs::remove_file(&path).await?;
+                }
+            }
+            if kept > 0 {
+                tracing::warn!("Kept {} media files under legal hold", kept);
+            }
+        }
+
+        // Clear temp files
+        let temp_dir = std::env::current_dir()?.join("temp");
+        if temp_dir.exists() {
+            tokio::fs::remove_dir_all(&temp_dir).await?;
+            tokio::fs::create_dir_all(&temp_dir).await?;
+        }
+
+        // Clear buffer
+        self.inner.buffer.clear_buffer().await?;
+
+        // Force resource cleanup
+        self.force_cleanup().await?;
+
+        sentry_integration::add_device_breadcrumb("clear_storage", Some("complete"));
+        Ok(())
+    }
+
+    pub async fn scan_wifi(&self) -> Result<Vec<crate::hardware::WifiNetwork>> {
+        let _transaction = sentry_integration::start_transaction("device.scan_wifi", "network");
+
+        tracing::info!("Scanning for WiFi networks");
+        let networks = self.inner.hardware.scan_wifi().await?;
+
+        sentry_integration::add_device_breadcrumb("scan_wifi",
+            Some(&format!("found {} networks", networks.len())));
+
+        Ok(networks)
+    }
+
+    pub async fn get_storage_breakdown(&self) -> Result<Vec<crate::media::StorageBreakdown>> {
+        let _transaction = sentry_integration::start_transaction("device.get_storage_breakdown", "storage");
+
+        let media_dir = std::env::current_dir()?.join("media");
+        let breakdown = crate::media::analyze_storage_usage(&media_dir).await?;
+
+        Ok(breakdown)
+    }
+
+    pub fn get_network_status(&self) -> NetworkStatus {
+        let network = self.inner.network.handle().get();
+        NetworkStatus {
+            connected: network.online,
+            signal_strength: network.signal_dbm,
+            interface: network.interface,
+            upload_speed: (network.upload_bytes_per_second > 0.0)
+                .then(|| (network.upload_bytes_per_second * 8.0 / 1000.0) as u32),
+            upload_queue_depth: network.queue_depth,
+            backend_circuits: crate::circuit_breaker::CircuitBreakers::global().snapshot(),
+        }
+    }
+
+    /// Local recordings with their legal hold status, for the recordings browser
+    pub async fn list_recordings(&self) -> Result<Vec<crate::media::RecordingFile>> {
+        let holds = LegalHolds::load().await;
+        match RecordingIndex::open().and_then(|index| index.segments(&SegmentQuery::default())) {
+            Ok(segments) => Ok(crate::media::indexed_recordings(&segments, &holds)),
+            Err(e) => {
+                tracing::warn!("Recording index unavailable, scanning recordings instead: {:#}", e);
+                let recordings_dir = std::env::current_dir()?.join("recordings");
+                crate::media::list_recordings(&recordings_dir, &holds).await
+            }
+        }
+    }
+
+    /// Mark a point in a recording, e.g. from the playback screen
+    pub async fn add_bookmark(&self, segment_id: &str, offset_seconds: f64, note: &str, source: &str) -> Result<SavedBookmark> {
+        let bookmark = RecordingIndex::open()?.add_bookmark(segment_id, offset_seconds, note)?;
+        self.audit_log().record("recording_bookmarked", source, serde_json::json!({
+            "segment_id": segment_id,
+            "offset_seconds": offset_seconds,
+        })).await?;
+        Ok(bookmark)
+    }
+
+    /// Bookmark the point being recorded in every quality of the recording
+    /// in progress
+    pub async fn bookmark_recording(&self, note: &str, source: &str) -> Result<Vec<SavedBookmark>> {
+        let positions = match self.inner.recorder.lock().await.as_ref() {
+            Some(recorder) => recorder.positions(Utc::now()),
+            None => return Err(anyhow::anyhow!("Not currently recording")),
+        };
+        let mut index = RecordingIndex::open()?;
+        let bookmarks = positions.iter()
+            .map(|(segment_id, offset_seconds)| index.add_bookmark(segment_id, *offset_seconds, note))
+            .collect::<Result<Vec<_>>>()?;
+        self.audit_log().record("recording_bookmarked", source, serde_json::json!({
+            "segments": positions.iter().map(|(segment_id, _)| segment_id).collect::<Vec<_>>(),
+            "offset_seconds": positions.first().map(|(_, offset)| offset),
+        })).await?;
+        Ok(bookmarks)
+    }
+
+    pub fn bookmarks(&self, segment_id: &str) -> Result<Vec<SavedBookmark>> {
+        RecordingIndex::open()?.bookmarks(segment_id)
+    }
+
+    pub async fn legal_holds(&self) -> Vec<LegalHold> {
+        LegalHolds::load().await.holds().to_vec()
+    }
+
+    pub async fn place_legal_hold(&self, hold: LegalHold, source: &str) -> Result<()> {
+        LegalHolds::load().await.place(hold.clone()).await?;
+
+        self.audit_log().record(
+            "legal_hold_placed",
+            source,
+            serde_json::json!({
+                "hold_id": hold.id,
+                "incident_id": hold.incident_id,
+                "segment_id": hold.segment_id,
+                "reason": hold.reason,
+            }),
+        ).await?;
+        Ok(())
+    }
+
+    pub async fn release_legal_hold(&self, hold_id: &str, source: &str) -> Result<bool> {
+        let released = LegalHolds::load().await.release(hold_id).await?;
+
+        if released {
+            self.audit_log().record("legal_hold_released", source, serde_json::json!({"hold_id": hold_id})).await?;
+        }
+        Ok(released)
+    }
+
+    /// Replace local holds with the full list from the backend
+    pub async fn sync_legal_holds(&self, holds: Vec<LegalHold>, source: &str) -> Result<()> {
+        let count = holds.len();
+        LegalHolds::load().await.replace_all(holds).await?;
+
+        self.audit_log().record("legal_holds_synced", source, serde_json::json!({"count": count})).await?;
+        Ok(())
+    }
+
+    /// Serve a fleet support request if the device has consented to remote
+    /// support. Every request is audited, including refused ones.
+    pub async fn handle_support_request(&self, request: SupportRequest, requested_by: &str, source: &str) -> Result<SupportArtifact> {
+        let config = self.config();
+        let result = match RemoteSupport::new(config.support.clone()) {
+            Ok(support) => support.fetch(&request, &config).await,
+            Err(e) => Err(e),
+        };
+
+        self.audit_log().record(
+            if result.is_ok() { "support_access_granted" } else { "support_access_denied" },
+            source,
+            serde_json::json!({
+                "request": request,
+                "requested_by": requested_by,
+                "bytes": result.as_ref().ok().map(|artifact| artifact.content.len()),
+                "sha256": result.as_ref().ok().map(|artifact| artifact.sha256.clone()),
+                "error": result.as_ref().err().map(|e| e.to_string()),
+            }),
+        ).await?;
+        result
+    }
+
+    /// Sites this device holds credentials for, the active one first
+    pub fn known_sites(&self) -> Vec<SiteProfile> {
+        crate::sites::known_sites(&self.read_config())
+    }
+
+    /// Scope the device to another site it holds credentials for. The site is
+    /// revalidated with the backend first, and everything journaled for the
+    /// current site must be delivered before uploads and incidents move over.
+    pub async fn switch_site(&self, site_id: &str, source: &str) -> Result<SiteProfile> {
+        InputValidator::validate_site_id(site_id)?;
+        let (target, previous) = {
+            let config = self.read_config();
+            if config.site_id.as_deref() == Some(site_id) {
+                return Err(anyhow::anyhow!("Already on site {}", site_id));
+            }
+            (crate::sites::find(&config, site_id)?.clone(), config.site_id.clone())
+        };
+        if self.is_recording() {
+            return Err(anyhow::anyhow!("Stop recording before switching sites"));
+        }
+
+        if let Err(e) = self.flush_journal().await {
+            tracing::debug!("Journal not flushed before site switch: {:#}", e);
+        }
+        let pending = self.pending_journal_entries().await;
+        if pending > 0 {
+            return Err(anyhow::anyhow!(
+                "{} backend call(s) for the current site are still pending; connect before switching",
+                pending
+            ));
+        }
+
+        let target = self.backend().validate_site(&target).await?;
+
+        self.update_config(|config| {
+            crate::sites::remember_active(config);
+            target.apply(config);
+            crate::sites::remember_active(config);
+        }).await?;
+        self.replace_backend()?;
+        self.inner.reporting.lock().await.status_encoder.reset();
+        self.inner.audit_log.write().unwrap().set_device_id(target.device_id.clone());
+
+        sentry_integration::set_device_context(
+            Some(&target.device_id),
+            Some(&target.site_id),
+            Some(&target.tenant_id),
+        );
+        self.audit_log().record(
+            "site_switched",
+            source,
+            serde_json::json!({
+                "from_site_id": previous,
+                "to_site_id": target.site_id,
+                "tenant_id": target.tenant_id,
+            }),
+        ).await?;
+        tracing::info!("Switched to site {} (tenant {})", target.site_id, target.tenant_id);
+        Ok(target)
+    }
+
+    /// Apply the tamper policy: snapshots while the camera is free, then the
+    /// incident and its recording, then the rest of the configured responses
+    async fn respond_to_tamper(&self) {
+        let response = self.inner.tamper.lock().await.record_event(Utc::now());
+        let policy = response.policy.clone();
+        tracing::error!("Tamper detected ({} in window), policy v{}", response.events_in_window, policy.version);
+
+        if policy.lock_controls {
+            self.inner.controls_locked.store(true, Ordering::Relaxed);
+        }
+
+        let mut snapshots = Vec::new();
+        if policy.snapshot_burst > 0 && !self.is_recording() {
+            let dir = match std::env::current_dir() {
+                Ok(dir) => dir.join("recordings").join("tamper"),
+                Err(_) => std::path::PathBuf::from("recordings/tamper"),
+            };
+            match crate::tamper::capture_burst(self.inner.camera_controls.device_path(), policy.snapshot_burst, &dir).await {
+                Ok(paths) => snapshots = paths,
+                Err(e) => tracing::warn!("Tamper snapshot burst failed: {:#}", e),
+            }
+        }
+
+        let incident_id = match self.trigger_incident(IncidentType::Tamper, IncidentSeverity::Critical).await {
+            Ok(incident_id) => Some(incident_id),
+            Err(e) => {
+                tracing::error!("Failed to raise tamper incident: {:#}", e);
+                None
+            }
+        };
+        if policy.start_recording && !self.is_recording() {
+            if let Err(e) = self.start_recording(None, incident_id.clone()).await {
+                tracing::error!("Failed to start recording on tamper: {:#}", e);
+            }
+        }
+        if policy.start_streaming {
+            if let Err(e) = self.start_streaming(None, Some(true)).await {
+                tracing::error!("Failed to start streaming on tamper: {:#}", e);
+            }
+        }
+        if policy.alert_contacts {
+            self.alert_emergency_contacts(incident_id.as_deref()).await;
+        }
+
+        let _ = self.audit_log().record("tamper_detected", "hardware", serde_json::json!({
+            "incident_id": incident_id,
+            "policy_version": policy.version,
+            "events_in_window": response.events_in_window,
+            "controls_locked": self.inner.controls_locked.load(Ordering::Relaxed),
+            "snapshots": snapshots,
+            "keys_shredded": response.wipe_keys,
+        })).await;
+
+        if response.wipe_keys {
+            if let Err(e) = self.shred_keys("tamper_policy").await {
+                tracing::error!("Failed to shred keys after repeated tampering: {:#}", e);
+            }
+        }
+    }
+
+    async fn alert_emergency_contacts(&self, incident_id: Option<&str>) {
+        let config = self.config();
+        let message = self.localizer().format(
+            "alert.tamper",
+            &[("device", config.device_id.as_deref().unwrap_or("unknown"))],
+        );
+        for contact in &config.security.emergency_contacts {
+            if let Err(e) = self.send_sms(contact, &message, incident_id, true, "tamper_policy").await {
+                tracing::warn!("Tamper alert to {} failed: {:#}", contact, e);
+            }
+        }
+    }
+
+    /// Send an SMS through the platform. One sent for an incident goes on
+    /// the incident's timeline, and its delivery is followed from there.
+    pub async fn send_sms(
+        &self,
+        to: &str,
+        text: &str,
+        incident_id: Option<&str>,
+        emergency: bool,
+        source: &str,
+    ) -> Result<crate::api::SendSmsResponse> {
+        self.allow_communication(CommunicationKind::Sms, to, emergency, source).await?;
+        let api = crate::api::ApiClient::new(self.config());
+        let device_id = self.device_id();
+        let response = if emergency {
+            api.send_emergency_sms(to, text, device_id.as_deref(), incident_id).await?
+        } else {
+            api.send_sms(to, text, device_id.as_deref(), incident_id, None, None).await?
+        };
+        self.count_communication(CommunicationKind::Sms).await;
+
+        if let Some(incident_id) = incident_id {
+            self.record_communication(CommunicationEvent {
+                incident_id: incident_id.to_string(),
+                kind: CommunicationKind::Sms,
+                communication_id: response.sms_id.clone(),
+                to: to.to_string(),
+                status: "sent".to_string(),
+                at: Utc::now(),
+                detail: Some(text.to_string()),
+            }, source).await;
+        }
+        Ok(response)
+    }
+
+    /// Call a number through the platform, on the incident's timeline like
+    /// an SMS. Calls placed for an incident are recorded by the platform.
+    pub async fn place_call(
+        &self,
+        to: &str,
+        incident_id: Option<&str>,
+        emergency: bool,
+        source: &str,
+    ) -> Result<crate::api::MakeCallResponse> {
+        self.allow_communication(CommunicationKind::Call, to, emergency, source).await?;
+        let api = crate::api::ApiClient::new(self.config());
+        let device_id = self.device_id();
+        let response = if emergency {
+            api.make_emergency_call(to, device_id.as_deref(), incident_id).await?
+        } else {
+            api.make_call(to, device_id.as_deref(), incident_id, None, None, Some(incident_id.is_some())).await?
+        };
+        self.count_communication(CommunicationKind::Call).await;
+
+        if let Some(incident_id) = incident_id {
+            self.record_communication(CommunicationEvent {
+                incident_id: incident_id.to_string(),
+                kind: CommunicationKind::Call,
+                communication_id: response.call_id.clone(),
+                to: to.to_string(),
+                status: "initiated".to_string(),
+                at: Utc::now(),
+                detail: None,
+            }, source).await;
+        }
+        Ok(response)
+    }
+
+    /// Download the contact directory when the cache is due, or now when
+    /// forced. A failed download keeps the cached numbers.
+    pub async fn refresh_contacts(&self, force: bool) -> bool {
+        if !force && !self.inner.contacts.lock().await.refresh_due(Utc::now()) {
+            return false;
+        }
+        let config = self.config();
+        let api = crate::api::ApiClient::new(config.clone());
+        match api.get_contacts(None, config.site_id.as_deref()).await {
+            Ok(contacts) => {
+                tracing::debug!("Contact directory refreshed: {} contacts", contacts.len());
+                if let Err(e) = self.inner.contacts.lock().await.replace(contacts, Utc::now()).await {
+                    tracing::warn!("Failed to cache contact directory: {}", e);
+                }
+                true
+            }
+            Err(e) => {
+                tracing::debug!("Contact directory unreachable, keeping cached contacts: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Bring the alert sound library in line with the platform's. Returns
+    /// None when it isn't due and `force` isn't set.
+    pub async fn sync_audio_presets(&self, force: bool) -> Result<Option<PresetSyncReport>> {
+        if !force && !self.inner.audio_presets.lock().await.sync_due(Utc::now()) {
+            return Ok(None);
+        }
+        let config = self.config();
+        let device_id = config.device_id.clone().ok_or(DeviceError::NotProvisioned)?;
+        let api = crate::api::ApiClient::new(config);
+        let remote = api.get_audio_presets(&device_id).await?;
+
+        // Not held over downloads, so alerts keep playing from the cache
+        let plan = self.inner.audio_presets.lock().await.plan(&remote).await;
+        let mut report = PresetSyncReport::default();
+        for preset in &plan.download {
+            let name = format!("{}/{}", preset.id, preset.language);
+            let installed = match api.download_audio_preset(preset).await {
+                Ok(bytes) => self.inner.audio_presets.lock().await.install(preset, &bytes).await,
+                Err(e) => Err(e),
+            };
+            match installed {
+                Ok(()) => report.downloaded.push(name),
+                Err(e) => {
+                    tracing::warn!("Audio preset {} not updated: {:#}", name, e);
+                    report.failed.push((name, format!("{:#}", e)));
+                }
+            }
+        }
+
+        let mut library = self.inner.audio_presets.lock().await;
+        for preset in &plan.remove {
+            library.remove(preset).await?;
+            report.removed.push(format!("{}/{}", preset.id, preset.language));
+        }
+        // Failed downloads are retried at the next sync
+        library.synced(Utc::now()).await?;
+        report.presets = library.list().len();
+        drop(library);
+
+        if !report.downloaded.is_empty() || !report.removed.is_empty() {
+            tracing::info!("Audio presets synced: {} updated, {} removed", report.downloaded.len(), report.removed.len());
+        }
+        Ok(Some(report))
+    }
+
+    /// The alert sounds cached on the device
+    pub async fn audio_presets(&self) -> Vec<AudioPreset> {
+        self.inner.audio_presets.lock().await.list().to_vec()
+    }
+
+    /// The cached contact directory and whether it's out of date
+    pub async fn contacts(&self) -> ContactList {
+        self.inner.contacts.lock().await.list(Utc::now())
+    }
+
+    /// Text or call a contact from the cached directory, so it works while
+    /// the directory itself can't be reached. Goes on the incident in
+    /// progress, if any.
+    pub async fn contact_emergency(&self, contact_id: &str, call: bool, source: &str) -> Result<()> {
+        let contact = self.inner.contacts.lock().await.find(contact_id).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown contact: {}", contact_id))?;
+        let incident_id = self.current_incident_id();
+        if call {
+            if !contact.can_receive_calls {
+                return Err(anyhow::anyhow!("{} can't receive calls", contact.name));
+            }
+            self.place_call(&contact.phone_number, incident_id.as_deref(), true, source).await?;
+        } else {
+            if !contact.can_receive_sms {
+                return Err(anyhow::anyhow!("{} can't receive SMS", contact.name));
+            }
+            let config = self.config();
+            let message = self.localizer().format(
+                "alert.emergency",
+                &[("device", config.device_id.as_deref().unwrap_or("unknown"))],
+            );
+            self.send_sms(&contact.phone_number, &message, incident_id.as_deref(), true, source).await?;
+        }
+        let _ = self.audit_log().record(
+            if call { "emergency_call_placed" } else { "emergency_sms_sent" },
+            source,
+            serde_json::json!({
+                "contact_id": contact.id,
+                "contact": contact.name,
+                "to": contact.phone_number,
+                "incident_id": incident_id,
+            }),
+        ).await;
+        Ok(())
+    }
+
+    /// Refuse a message or call the cached allowlist or daily limits don't
+    /// permit, whatever asked for it
+    async fn allow_communication(&self, kind: CommunicationKind, to: &str, emergency: bool, source: &str) -> Result<()> {
+        let today = crate::communication_policy::today();
+        let Err(reason) = self.inner.communication_guard.lock().await.check(kind, to, emergency, today) else {
+            return Ok(());
+        };
+        tracing::warn!("Blocked {:?} to {}: {}", kind, to, reason);
+        let _ = self.audit_log().record("communication_blocked", source, serde_json::json!({
+            "kind": kind,
+            "to": to,
+            "emergency": emergency,
+            "reason": reason,
+        })).await;
+        Err(anyhow::anyhow!("Blocked by communication policy: {}", reason))
     }
 
-    pub async fn diagnose(&self) -> Result<DiagnosticsReport> {
-        let battery_level = self.hardware.get_battery_level().await?;
-        let storage_info = self.hardware.get_storage_info().await?;
-        let temperature = self.hardware.get_temperature().await?;
-        
-        // Get resource stats from resource manager
-        let resource_stats = self.resource_manager.get_resource_stats().await;
+    async fn count_communication(&self, kind: CommunicationKind) {
+        let today = crate::communication_policy::today();
+        if let Err(e) = self.inner.communication_guard.lock().await.record(kind, today).await {
+            tracing::warn!("Failed to save communication usage: {}", e);
+        }
+        self.check_communication_quotas().await;
+    }
 
-        let sensors = vec![
-            SensorStatus {
-                sensor_type: "battery".to_string(),
-                status: "ok".to_string(),
-                value: Some(battery_level as f64),
-            },
-            SensorStatus {
-                sensor_type: "temperature".to_string(),
-                status: "ok".to_string(),
-                value: Some(temperature as f64),
-            },
-            SensorStatus {
-                sensor_type: "memory_usage_mb".to_string(),
-                status: "ok".to_string(),
-                value: Some(resource_stats.memory_usage.process_memory_kb as f64 / 1024.0),
-            },
-            SensorStatus {
-                sensor_type: "cpu_usage_percent".to_string(),
-                status: "ok".to_string(),
-                value: Some(resource_stats.process_stats.cpu_usage_percent),
-            },
-            SensorStatus {
-                sensor_type: "disk_usage_percent".to_string(),
-                status: if resource_stats.disk_usage.used_gb / resource_stats.disk_usage.total_gb > 0.85 { "warning".to_string() } else { "ok".to_string() },
-                value: Some((resource_stats.disk_usage.used_gb / resource_stats.disk_usage.total_gb) * 100.0),
-            },
-        ];
+    /// Log quotas that just passed the warning threshold, and keep the
+    /// quota LED on while any is past it
+    async fn check_communication_quotas(&self) {
+        let today = crate::communication_policy::today();
+        let (new_warnings, warning) = {
+            let mut guard = self.inner.communication_guard.lock().await;
+            (guard.new_warnings(today), !guard.warnings(today).is_empty())
+        };
+        for usage in &new_warnings {
+            tracing::warn!(
+                "Communication quota {} at {:.0}% ({} of {})",
+                usage.quota.as_str(), usage.percent(), usage.used, usage.limit,
+            );
+            let _ = self.audit_log().record("communication_quota_warning", "communication_policy", serde_json::json!(usage)).await;
+        }
+        if let Err(e) = self.set_led_indicator(LedIndicator::QuotaWarning, warning).await {
+            tracing::debug!("Failed to set quota LED: {}", e);
+        }
+    }
 
-        Ok(DiagnosticsReport {
-            device_id: self.device_id.clone().unwrap_or_else(|| "unknown".to_string()),
-            timestamp: Utc::now(),
-            battery_level,
-            storage_info,
-            temperature,
-            sensors,
-            camera_status: CameraStatus {
-                enabled: true,
-                resolution: self.config.recording.resolution.clone(),
-                fps: self.config.recording.fps,
-                bitrate: self.config.recording.bitrate,
-            },
-            audio_status: AudioStatus {
-                enabled: true,
-                sample_rate: self.config.audio.sample_rate,
-                channels: self.config.audio.channels,
-                bitrate: self.config.audio.bitrate,
-            },
-            network_status: NetworkStatus {
-                connected: true,
-                signal_strength: Some(-45),
-                ip_address: Some("192.168.1.100".to_string()),
-                upload_speed: Some(5000),
-            },
-        })
+    /// How much of each SMS, call and cost quota is used
+    pub async fn communication_quotas(&self) -> Vec<QuotaUsage> {
+        self.inner.communication_guard.lock().await.quotas(crate::communication_policy::today())
     }
 
-    async fn start_monitoring(&mut self
-    ) -> Result<()> {
-        let hardware_events = self.hardware.start_monitoring().await?;
-        let device = Arc::new(Mutex::new(self));
-        
-        tokio::spawn(async move {
-            let mut event_rx = hardware_events;
-            
-            while let Some(event) = event_rx.recv().await {
-                let mut device = device.lock().await;
-                Self::handle_hardware_event(&mut device, event).await;
+    /// Download the allowlist and limits for this device's number when the
+    /// cached copy is due. A failed download keeps the cached rules.
+    async fn refresh_communication_policy(&self) {
+        if !self.inner.communication_guard.lock().await.refresh_due(Utc::now()) {
+            return;
+        }
+        let Some(device_id) = self.device_id() else { return };
+        let api = crate::api::ApiClient::new(self.config());
+        let (number, capabilities) = match api.get_device_allocated_number(&device_id).await {
+            Ok(Some(allocation)) => allocation,
+            Ok(None) => {
+                tracing::debug!("No number allocated to this device; keeping default communication limits");
+                return;
             }
-        });
+            Err(e) => {
+                tracing::debug!("Can't refresh communication policy: {}", e);
+                return;
+            }
+        };
+        let allowlist = match api.get_number_whitelist(&number.id).await {
+            Ok(allowlist) => allowlist,
+            Err(e) => {
+                tracing::debug!("Can't refresh number allowlist: {}", e);
+                return;
+            }
+        };
+        let policy = PolicySnapshot::new(&number, &capabilities, allowlist);
+        let today = crate::communication_policy::today();
+        if let Err(e) = self.inner.communication_guard.lock().await.replace(policy, today).await {
+            tracing::warn!("Failed to cache communication policy: {}", e);
+        }
+        self.check_communication_quotas().await;
+    }
 
-        Ok(())
+    async fn record_communication(&self, event: CommunicationEvent, source: &str) {
+        if let Err(e) = RecordingIndex::open().and_then(|mut index| index.record_communication(&event)) {
+            tracing::warn!("Failed to add {:?} to incident {} timeline: {:#}", event.kind, event.incident_id, e);
+        }
+        self.inner.pending_communications.lock().unwrap().push(PendingCommunication::new(&event));
+        let _ = self.audit_log().record(
+            match event.kind {
+                CommunicationKind::Sms => "incident_sms_sent",
+                CommunicationKind::Call => "incident_call_placed",
+            },
+            source,
+            serde_json::json!({
+                "incident_id": event.incident_id,
+                "communication_id": event.communication_id,
+                "to": event.to,
+            }),
+        ).await;
     }
 
-    async fn start_status_reporting(&self
-    ) -> Result<()> {
-        let status_reporter = self.status_reporter.clone();
-        let device = Arc::new(Mutex::new(self));
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
-            
-            loop {
-                interval.tick().await;
-                
-                let mut device_guard = device.lock().await;
-                
-                // Report current status
-                if let Ok(status) = device_guard.get_status().await {
-                    let _ = status_reporter.report_status(status).await;
-                }
-                
-                // Check storage and perform automatic cleanup
-                if let Ok(deleted_files) = device_guard.storage_manager.check_storage_and_cleanup().await {
-                    if !deleted_files.is_empty() {
-                        tracing::info!("Automatic storage cleanup completed, deleted {} files", deleted_files.len());
-                        
-                        // Save deletion log
-                        if let Err(e) = device_guard.storage_manager.save_deletion_log().await {
-                            tracing::error!("Failed to save deletion log: {}", e);
-                        }
-                        
-                        // Sync deletions to server
-                        let _ = device_guard.sync_deletions_to_server().await;
-                    }
+    /// Put delivery and answer status changes of incident messages and calls
+    /// on their incidents' timelines
+    async fn follow_communications(&self) {
+        let now = Utc::now();
+        let incidents: Vec<String> = {
+            let mut pending = self.inner.pending_communications.lock().unwrap();
+            pending.retain(|tracked| tracked.is_open(now));
+            let mut incidents: Vec<String> = pending.iter().map(|tracked| tracked.incident_id.clone()).collect();
+            incidents.sort();
+            incidents.dedup();
+            incidents
+        };
+        if incidents.is_empty() {
+            return;
+        }
+
+        let api = crate::api::ApiClient::new(self.config());
+        let device_id = self.device_id();
+        for incident_id in incidents {
+            let messages = api.get_sms_history(device_id.as_deref(), Some(&incident_id), None).await;
+            let calls = api.get_call_history(device_id.as_deref(), Some(&incident_id), None).await;
+            let (messages, calls) = match (messages, calls) {
+                (Ok(messages), Ok(calls)) => (messages, calls),
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::debug!("Can't follow communications for incident {}: {}", incident_id, e);
+                    continue;
                 }
-            }
-        });
+            };
 
-        Ok(())
+            // The history only holds this incident's messages and calls
+            let changes = communications::status_changes(
+                &mut self.inner.pending_communications.lock().unwrap(),
+                &messages,
+                &calls,
+            );
+            if changes.is_empty() {
+                continue;
+            }
+            let recorded = RecordingIndex::open().and_then(|mut index| {
+                changes.iter().try_for_each(|event| index.record_communication(event))
+            });
+            if let Err(e) = recorded {
+                tracing::warn!("Failed to update incident {} timeline: {:#}", incident_id, e);
+            }
+        }
     }
 
-    async fn handle_hardware_event(
-        device: &mut BodycamDevice,
-        event: HardwareEvent
-    ) {
-        match event {
-            HardwareEvent::ButtonPressed { button, duration } => {
-                match button {
-                    crate::hardware::ButtonType::Record => {
-                        if duration.is_some() {
-                            let _ = device.stop_recording().await;
-                        } else {
-                            let _ = device.start_recording(None, None).await;
-                        }
-                    }
-                    crate::hardware::ButtonType::Emergency => {
-                        let _ = device.trigger_incident("emergency", "high").await;
+    /// Poll the platform for commands texted to the device's number
+    fn start_sms_commands(&self) {
+        let config = self.read_config().sms_commands.clone();
+        if !config.enabled {
+            return;
+        }
+        if config.allowed_senders.is_empty() {
+            tracing::warn!("SMS commands enabled with no allowed senders; ignoring them");
+            return;
+        }
+        let device = Arc::downgrade(&self.inner);
+
+        TaskSupervisor::global().spawn("sms_commands", MONITOR_RESTART, move || {
+            let device = device.clone();
+            let poll = std::time::Duration::from_secs(config.poll_interval_seconds.max(5));
+            async move {
+                let mut state = SmsCommandState::load().await;
+                // Commands sent before this device read any aren't run late
+                if state.since.is_none() {
+                    state.since = Some(Utc::now());
+                    if let Err(e) = state.save().await {
+                        tracing::warn!("Failed to save SMS command state: {}", e);
                     }
-                    crate::hardware::ButtonType::Power => {
-                        if duration.map(|d| d >= 3000).unwrap_or(false) {
-                            let _ = device.hardware.shutdown().await;
-                        }
+                }
+                let mut interval = tokio::time::interval(poll);
+                loop {
+                    interval.tick().await;
+                    let Some(device) = Self::upgrade(&device) else { break };
+                    if device.inner.canceller.is_shut_down() {
+                        break;
                     }
-                    _ => {}
+                    device.check_sms_commands(&mut state).await;
                 }
+                anyhow::Ok(())
             }
-            HardwareEvent::BatteryLow { level } => {
-                if level < 10.0 {
-                    let _ = device.hardware.shutdown().await;
-                }
+        });
+    }
+
+    async fn check_sms_commands(&self, state: &mut SmsCommandState) {
+        let config = self.read_config().sms_commands.clone();
+        let api = crate::api::ApiClient::new(self.config());
+        let messages = match api.get_sms_history(self.device_id().as_deref(), None, Some(20)).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                tracing::debug!("Can't check for SMS commands: {}", e);
+                return;
             }
-            HardwareEvent::StorageFull => {
-                let _ = device.stop_recording().await;
-                
-                // Perform immediate storage cleanup
-                if let Ok(deleted_files) = device.storage_manager.check_storage_and_cleanup().await {
-                    if !deleted_files.is_empty() {
-                        tracing::info!("Storage full cleanup completed, deleted {} files", deleted_files.len());
-                        
-                        // Save deletion log and notify server
-                        let _ = device.storage_manager.save_deletion_log().await;
-                        let _ = device.sync_deletions_to_server().await;
-                    } else {
-                        tracing::warn!("Storage full but no files could be deleted");
-                    }
+        };
+
+        let since = state.since.unwrap_or_default();
+        let mut inbound: Vec<_> = messages.into_iter()
+            .filter(|message| message.direction == "inbound" && !state.is_processed(&message.id))
+            .filter(|message| message.sent_at.and_then(DateTime::from_timestamp_millis).is_some_and(|at| at >= since))
+            .collect();
+        if inbound.is_empty() {
+            return;
+        }
+        inbound.sort_by_key(|message| message.sent_at);
+
+        for message in inbound {
+            state.mark_processed(&message.id);
+            let reply = match crate::sms_commands::verify(&config, state, &message.from, &message.text, Utc::now()) {
+                Ok(command) => {
+                    let reply = match self.run_sms_command(command).await {
+                        Ok(reply) => reply,
+                        Err(e) => format!("{} failed: {}", command.text(), e),
+                    };
+                    let _ = self.audit_log().record("sms_command", "sms", serde_json::json!({
+                        "from": message.from,
+                        "command": command,
+                        "result": reply,
+                    })).await;
+                    Some(reply)
+                }
+                Err(reason) => {
+                    tracing::warn!("Ignoring SMS from {}: {}", message.from, reason);
+                    let _ = self.audit_log().record("sms_command_rejected", "sms", serde_json::json!({
+                        "from": message.from,
+                        "reason": reason,
+                    })).await;
+                    // Only allowlisted senders learn that a command was refused
+                    (reason != "sender not allowed").then(|| format!("Command refused: {}", reason))
+                }
+            };
+            if let Some(reply) = reply.filter(|_| config.reply) {
+                if let Err(e) = self.send_sms(&message.from, &reply, None, false, "sms_command").await {
+                    tracing::warn!("Failed to reply to SMS command from {}: {}", message.from, e);
                 }
             }
-            HardwareEvent::TamperDetected => {
-                let _ = device.trigger_incident("tamper", "critical").await;
+        }
+        if let Err(e) = state.save().await {
+            tracing::warn!("Failed to save SMS command state: {}", e);
+        }
+    }
+
+    /// Run a texted command, returning the reply
+    async fn run_sms_command(&self, command: SmsCommand) -> Result<String> {
+        match command {
+            SmsCommand::Status => {
+                let status = self.get_status().await?;
+                Ok(format!(
+                    "Battery {:.0}%{}, {}, {}{}",
+                    status.battery_level,
+                    if status.is_charging { " charging" } else { "" },
+                    if status.recording { "recording" } else { "not recording" },
+                    if self.is_streaming().await { "streaming" } else { "not streaming" },
+                    if status.incident_active { ", incident active" } else { "" },
+                ))
             }
-            HardwareEvent::LightDetected { level, threshold } => {
-                let _ = device.trigger_incident("light_detection", "medium").await;
+            SmsCommand::Locate => match self.resolve_location().await {
+                Some(location) => Ok(format!(
+                    "{:.5},{:.5}{} https://maps.google.com/?q={:.5},{:.5}",
+                    location.latitude,
+                    location.longitude,
+                    location.accuracy.map(|accuracy| format!(" ±{:.0}m", accuracy)).unwrap_or_default(),
+                    location.latitude,
+                    location.longitude,
+                )),
+                None => Ok("Location unknown".to_string()),
+            },
+            SmsCommand::StreamOn => {
+                let stream_id = self.start_streaming(None, None).await?;
+                Ok(format!("Streaming, stream {}", stream_id))
             }
-            HardwareEvent::SoundDetected { level, frequency } => {
-                let _ = device.trigger_incident("sound_detection", "low").await;
+            SmsCommand::StreamOff => {
+                self.stop_streaming().await?;
+                Ok("Streaming stopped".to_string())
             }
-            HardwareEvent::MovementDetected { acceleration, threshold } => {
-                let _ = device.trigger_incident("movement_detection", "medium").await;
+            SmsCommand::RecordOn => {
+                self.start_recording(None, None).await?;
+                Ok("Recording started".to_string())
             }
-            HardwareEvent::SpeechDetected { confidence, phrase, duration } => {
-                let _ = device.trigger_incident("speech_detection", "high").await;
+            SmsCommand::RecordOff => {
+                self.stop_recording().await?;
+                Ok("Recording stopped".to_string())
             }
-            _ => {}
         }
     }
 
-    pub async fn get_resource_stats(&self) -> Result<crate::resource_manager::ResourceStats> {
-        Ok(self.resource_manager.get_resource_stats().await)
+    /// Messages and calls placed for an incident, with their status changes
+    pub fn incident_communications(&self, incident_id: &str) -> Result<Vec<CommunicationEvent>> {
+        RecordingIndex::open()?.communications(incident_id)
     }
 
-    pub async fn force_cleanup(&self) -> Result<()> {
-        self.resource_manager.force_cleanup().await
+    /// Install a tamper policy signed by the backend
+    pub async fn install_tamper_policy(&self, signed: SignedTamperPolicy, source: &str) -> Result<TamperPolicy> {
+        let policy = self.inner.tamper.lock().await.install(signed).await?.clone();
+        self.audit_log().record("tamper_policy_installed", source, serde_json::to_value(&policy)?).await?;
+        Ok(policy)
     }
 
-    pub async fn clear_storage(&mut self) -> Result<()> {
-        let _transaction = sentry_integration::start_transaction("device.clear_storage", "storage");
-        
-        tracing::info!("Clearing all storage");
-        sentry_integration::add_device_breadcrumb("clear_storage", Some("user_requested"));
-        
-        // Stop recording if active
-        if self.is_recording {
-            self.stop_recording().await?;
+    pub async fn tamper_policy(&self) -> TamperPolicy {
+        self.inner.tamper.lock().await.policy().clone()
+    }
+
+    /// Install no-record and must-record rules signed by the backend
+    pub async fn install_recording_policy(&self, signed: SignedRecordingPolicy, source: &str) -> Result<RecordingPolicy> {
+        let policy = self.inner.recording_policy.lock().await.install(signed).await?.clone();
+        self.audit_log().record("recording_policy_installed", source, serde_json::to_value(&policy)?).await?;
+        self.enforce_recording_policy().await;
+        Ok(policy)
+    }
+
+    pub async fn recording_policy(&self) -> RecordingPolicy {
+        self.inner.recording_policy.lock().await.policy().clone()
+    }
+
+    /// What the recording policy last decided for where the device is now
+    pub fn recording_restriction(&self) -> PolicyDecision {
+        self.inner.recording_restriction.lock().unwrap().clone()
+    }
+
+    /// The recording policy's decision for the current position and local
+    /// time. An emergency incident in progress lifts blocks that allow it.
+    async fn decide_recording(&self) -> PolicyDecision {
+        if self.inner.recording_policy.lock().await.policy().rules.is_empty() {
+            return PolicyDecision::Allow;
         }
-        
-        // Clear media files
-        let media_dir = std::env::current_dir()?.join("media");
-        if media_dir.exists() {
-            tokio::fs::remove_dir_all(&media_dir).await?;
-            tokio::fs::create_dir_all(&media_dir).await?;
+        let emergency = self.inner.active_incident.get()
+            .is_some_and(|incident| incident.incident_type == IncidentType::Emergency);
+        let position = self.resolve_location().await.map(|location| (location.latitude, location.longitude));
+        let time = chrono::Local::now().time();
+        self.inner.recording_policy.lock().await.policy().decide(position, time, emergency)
+    }
+
+    /// Follow the recording policy as the device moves: log each change of
+    /// decision, and start recording where it's required
+    async fn enforce_recording_policy(&self) {
+        let decision = self.decide_recording().await;
+        let previous = std::mem::replace(&mut *self.inner.recording_restriction.lock().unwrap(), decision.clone());
+        if previous != decision {
+            tracing::info!("Recording policy decision: {:?} (was {:?})", decision, previous);
+            let _ = self.audit_log().record("recording_policy_decision", "recording_policy", serde_json::json!({
+                "decision": decision,
+                "previous": previous,
+            })).await;
+            self.refresh_display().await;
         }
-        
-        // Clear temp files
-        let temp_dir = std::env::current_dir()?.join("temp");
-        if temp_dir.exists() {
-            tokio::fs::remove_dir_all(&temp_dir).await?;
-            tokio::fs::create_dir_all(&temp_dir).await?;
+
+        let PolicyDecision::Force(rule) = decision else { return };
+        if self.is_recording() || !self.read_config().is_provisioned() {
+            return;
         }
-        
-        // Clear buffer
-        self.buffer.clear().await?;
-        
-        // Force resource cleanup
-        self.force_cleanup().await?;
-        
-        sentry_integration::add_device_breadcrumb("clear_storage", Some("complete"));
+        match self.start_recording(None, None).await {
+            Ok(()) => {
+                tracing::info!("Recording started by policy rule {:?}", rule);
+                let _ = self.audit_log().record("recording_forced", "recording_policy", serde_json::json!({
+                    "rule": rule,
+                })).await;
+            }
+            Err(e) => tracing::warn!("Policy rule {:?} requires recording, but it failed to start: {:#}", rule, e),
+        }
+    }
+
+    /// Re-enable the buttons after a tamper lock
+    pub async fn unlock_controls(&self, source: &str) -> Result<()> {
+        self.inner.controls_locked.store(false, Ordering::Relaxed);
+        self.audit_log().record("controls_unlocked", source, serde_json::json!({})).await?;
         Ok(())
     }
 
-    pub async fn scan_wifi(&self) -> Result<Vec<crate::hardware::WifiNetwork>> {
-        let _transaction = sentry_integration::start_transaction("device.scan_wifi", "network");
-        
-        tracing::info!("Scanning for WiFi networks");
-        let networks = self.hardware.scan_wifi().await?;
-        
-        sentry_integration::add_device_breadcrumb("scan_wifi", 
-            Some(&format!("found {} networks", networks.len())));
-        
-        Ok(networks)
+    /// Destroy local keys and credentials. The device keeps recording, but
+    /// can't decrypt or upload until it is provisioned again.
+    pub async fn shred_keys(&self, source: &str) -> Result<()> {
+        let config_path = std::path::Path::new("config.toml");
+        if config_path.exists() {
+            SecureEraser::from_config(&self.config()).erase_file(config_path).await?;
+        }
+        self.update_config(crate::decommission::shred_keys).await?;
+
+        self.replace_backend()?;
+        self.audit_log().record("keys_shredded", source, serde_json::json!({})).await?;
+        tracing::warn!("Local keys shredded ({})", source);
+        Ok(())
     }
 
-    pub async fn get_storage_breakdown(&self) -> Result<Vec<crate::media::StorageBreakdown>> {
-        let _transaction = sentry_integration::start_transaction("device.get_storage_breakdown", "storage");
-        
-        let media_dir = std::env::current_dir()?.join("media");
-        let breakdown = crate::media::analyze_storage_usage(&media_dir).await?;
-        
-        Ok(breakdown)
+    /// Check that whoever is at the device may run `operation`, using
+    /// `credential` or else the UI session. Every attempt is audited. Returns
+    /// the operator, or None when access control is disabled.
+    pub async fn authorize(
+        &self,
+        operation: PrivilegedOperation,
+        credential: Option<&Credential>,
+        source: &str,
+    ) -> Result<Option<Operator>> {
+        let access = self.inner.access.lock().await;
+        if !access.enabled() {
+            self.audit_log().record("privileged_action", source, serde_json::json!({
+                "operation": operation,
+                "operator": null,
+            })).await?;
+            return Ok(None);
+        }
+
+        let result = match credential {
+            Some(credential) => access.authenticate(credential, self.backend().as_ref()).await,
+            None => access.current().cloned()
+                .ok_or_else(|| anyhow::anyhow!("{:?} requires a PIN, badge or operator token", operation)),
+        }
+        .and_then(|operator| crate::access::check(&operator, operation).map(|_| operator));
+        drop(access);
+
+        self.audit_log().record(
+            if result.is_ok() { "privileged_action" } else { "privileged_action_denied" },
+            source,
+            serde_json::json!({
+                "operation": operation,
+                "operator": result.as_ref().ok(),
+                "error": result.as_ref().err().map(|e| e.to_string()),
+            }),
+        ).await?;
+        result.map(Some)
     }
 
-    pub async fn get_network_status(&self) -> Result<crate::hardware::NetworkStatus> {
-        let _transaction = sentry_integration::start_transaction("device.get_network_status", "network");
-        
-        let status = self.hardware.get_network_status().await?;
-        
-        Ok(status)
+    /// Start a UI session for the holder of `credential`
+    pub async fn login(&self, credential: &Credential, source: &str) -> Result<Operator> {
+        let mut access = self.inner.access.lock().await;
+        let result = access.authenticate(credential, self.backend().as_ref()).await;
+        self.audit_log().record(
+            if result.is_ok() { "operator_login" } else { "operator_login_failed" },
+            source,
+            serde_json::json!({
+                "operator": result.as_ref().ok(),
+                "error": result.as_ref().err().map(|e| e.to_string()),
+            }),
+        ).await?;
+        let operator = result?;
+        access.login(operator.clone());
+        Ok(operator)
     }
 
-    pub fn get_recent_deletions(&self, limit: usize) -> Vec<crate::storage_manager::DeletedFileRecord> {
-        self.storage_manager.get_recent_deletions(limit)
+    pub async fn logout(&self) {
+        self.inner.access.lock().await.logout();
     }
 
-    pub async fn sync_deletions_to_server(&mut self) -> Result<()> {
-        let deleted_files = self.storage_manager.get_recent_deletions(100);
+    /// Check a supervisor PIN for leaving the kiosk-mode UI. Unlike other
+    /// privileged operations this is refused when access control is off, so
+    /// kiosk mode can't be left by anyone holding the device.
+    pub async fn authorize_kiosk_exit(&self, pin: &str, source: &str) -> Result<Operator> {
+        if !self.inner.access.lock().await.enabled() {
+            return Err(anyhow::anyhow!("Leaving kiosk mode needs access control with a supervisor configured"));
+        }
+        self.authorize(PrivilegedOperation::ExitKiosk, Some(&Credential::Pin(pin.to_string())), source).await?
+            .ok_or_else(|| anyhow::anyhow!("No operator authorized"))
+    }
+
+    /// The token an operator must pass back to `decommission`, valid today
+    pub fn decommission_token(&self) -> Result<String> {
+        let device_id = self.device_id()
+            .ok_or(DeviceError::NotProvisioned)?;
+        Ok(crate::decommission::confirmation_token(&device_id, chrono::Utc::now().date_naive()))
+    }
+
+    /// Retire the device: revoke its credentials with the backend, shred
+    /// local keys, securely erase recordings and logs, and leave it waiting
+    /// for factory provisioning. Refused while recordings haven't reached
+    /// the server, unless `force` accepts losing them.
+    pub async fn decommission(&self, token: &str, force: bool, source: &str) -> Result<WipeReport> {
+        let device_id = self.device_id()
+            .ok_or(DeviceError::NotProvisioned)?;
+        crate::decommission::verify_token(&device_id, token)?;
+        if self.is_recording() {
+            return Err(anyhow::anyhow!("Stop recording before decommissioning"));
+        }
+        let holds = LegalHolds::load().await;
+        if !holds.holds().is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} legal hold(s) in force; release them before decommissioning",
+                holds.holds().len()
+            ));
+        }
+        let root = std::env::current_dir()?;
+        let unsynced = crate::decommission::unsynced_files(&root).await?;
+        if !unsynced.is_empty() && !force {
+            return Err(anyhow::anyhow!(
+                "{} recording(s) haven't been confirmed by the server and would be lost: {}; \
+                 upload them first, or force the decommission",
+                unsynced.len(), unsynced.join(", ")
+            ));
+        }
+
+        let config = self.config();
+        self.audit_log().record("decommission_started", source, serde_json::json!({
+            "site_id": config.site_id,
+            "tenant_id": config.tenant_id,
+            "forced": force,
+            "unsynced": unsynced,
+        })).await?;
+
+        // Revoke while the credentials still exist; a wiped device whose
+        // credentials still work upstream is worse than one not wiped yet
+        self.backend().revoke_credentials(&device_id).await
+            .context("Could not revoke credentials; decommission needs a connection")?;
+
+        // Shred keys first, so anything the erase misses stays encrypted
+        let eraser = SecureEraser::from_config(&config);
+        let config_path = std::path::Path::new("config.toml");
+        if config_path.exists() {
+            eraser.erase_file(config_path).await?;
+        }
+        self.update_config(|config| *config = crate::decommission::factory_state(config)).await?;
+
+        let mut report = eraser.erase_all(&root).await;
+        report.unsynced = unsynced;
+
+        self.replace_backend()?;
+        let mut reporting = self.inner.reporting.lock().await;
+        reporting.journal = OfflineJournal::open().await?;
+        reporting.status_encoder.reset();
+        drop(reporting);
+
+        tracing::warn!(
+            "Device {} decommissioned: {} files ({} bytes) erased, {} failures",
+            device_id, report.files_erased, report.bytes_erased, report.failures.len()
+        );
+        Ok(report)
+    }
+
+    /// Write an encrypted backup of the configuration, credentials and
+    /// calibration data, for re-imaging replacement hardware
+    pub async fn backup_config(&self, output: &std::path::Path, passphrase: &str, source: &str) -> Result<()> {
+        let data_dir = std::env::current_dir()?.join("data");
+        let files = crate::config_backup::read_data_files(&data_dir).await;
+        let file_names: Vec<String> = files.keys().cloned().collect();
+        let archive = crate::config_backup::BackupArchive::create(&self.config(), files, passphrase)?;
+        archive.write(output).await?;
+
+        self.audit_log().record("config_backup", source, serde_json::json!({
+            "output": output,
+            "files": file_names,
+        })).await?;
+        tracing::info!("Configuration backed up to {}", output.display());
+        Ok(())
+    }
+
+    /// Replace the configuration and calibration data from a backup. Without
+    /// `with_credentials` the device keeps the credentials it has. Takes
+    /// effect on the next start.
+    pub async fn restore_config(
+        &self,
+        input: &std::path::Path,
+        passphrase: &str,
+        with_credentials: bool,
+        source: &str,
+    ) -> Result<Config> {
+        if self.is_recording() {
+            return Err(anyhow::anyhow!("Stop recording before restoring a backup"));
+        }
+        let archive = crate::config_backup::BackupArchive::read(input).await?;
+        let restored = archive.open(passphrase)?;
+
+        let mut config = restored.config;
+        if with_credentials {
+            restored.credentials.apply(&mut config);
+        } else {
+            crate::config_backup::Credentials::take(&mut self.config()).apply(&mut config);
+        }
+        let data_dir = std::env::current_dir()?.join("data");
+        let files = crate::config_backup::write_data_files(&data_dir, &restored.files).await?;
+        // A backup restored onto replacement hardware belongs to it now
+        let config = self.update_config(|current| {
+            *current = config;
+            self.bind_to_this_hardware(current);
+        }).await?;
+
+        self.audit_log().record("config_restored", source, serde_json::json!({
+            "input": input,
+            "backup_device_id": archive.device_id,
+            "backup_created_at": archive.created_at,
+            "with_credentials": with_credentials,
+            "files": files,
+        })).await?;
+        tracing::warn!(
+            "Configuration restored from backup of {} made {}; restart to apply",
+            archive.device_id.as_deref().unwrap_or("an unprovisioned device"),
+            archive.created_at,
+        );
+        Ok(config)
+    }
+
+    /// Write a redacted copy of a segment for export; the original is left as is
+    pub async fn redact_segment(
+        &self,
+        segment_id: &str,
+        output: Option<std::path::PathBuf>,
+        operator: Option<Operator>,
+        reason: &str,
+        source: &str,
+    ) -> Result<RedactionReport> {
+        let segment = crate::custody::load_segment(segment_id).await?;
+        let access = RecordingAccess::new(&segment, RecordingAccessKind::Exported, operator, reason, source)?;
+        let report = Redactor::new(self.config()).redact_segment(segment_id, output).await?;
+
+        self.audit_log().record(
+            "recording_redacted",
+            source,
+            serde_json::json!({
+                "segment_id": report.segment_id,
+                "incident_id": report.incident_id,
+                "output_path": report.output_path,
+                "output_sha256": report.output_sha256,
+                "frames_redacted": report.frames_redacted,
+            }),
+        ).await?;
+        self.record_recording_access(access.with_output(std::path::Path::new(&report.output_path))).await?;
+        Ok(report)
+    }
+
+    /// Export a plaintext copy of an original recording, decrypting it if
+    /// it was recorded encrypted
+    pub async fn export_recording(
+        &self,
+        segment_id: &str,
+        output: &std::path::Path,
+        operator: Option<Operator>,
+        reason: &str,
+        source: &str,
+    ) -> Result<RecordingAccess> {
+        let segment = crate::custody::load_segment(segment_id).await?;
+        let kind = if segment.metadata.encryption_key.is_some() {
+            RecordingAccessKind::Decrypted
+        } else {
+            RecordingAccessKind::Exported
+        };
+        let access = RecordingAccess::new(&segment, kind, operator, reason, source)?.with_output(output);
+
+        crate::custody::plaintext_copy(&self.config(), &segment, output).await?;
+        self.export_communications(&segment.incident_id, output).await?;
+        self.record_recording_access(access.clone()).await?;
+        Ok(access)
+    }
+
+    /// Write the incident's messages and calls next to an exported recording
+    async fn export_communications(&self, incident_id: &str, output: &std::path::Path) -> Result<()> {
+        let events = self.incident_communications(incident_id)?;
+        if events.is_empty() {
+            return Ok(());
+        }
+        let path = communications::sidecar_path(output);
+        tokio::fs::write(&path, serde_json::to_string_pretty(&events)?).await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Open a local recording for review on the device's screen. Encrypted
+    /// recordings need the same role as decrypting them, and are decrypted
+    /// to a temporary copy the player removes when closed.
+    pub async fn open_playback(
+        &self,
+        segment_id: &str,
+        credential: Option<&Credential>,
+        reason: &str,
+        source: &str,
+    ) -> Result<Player> {
+        let segment = crate::custody::load_segment(segment_id).await?;
+        // Checked before authorizing so a missing reason isn't logged as a use
+        RecordingAccess::new(&segment, RecordingAccessKind::Played, None, reason, source)?;
+
+        let encrypted = segment.metadata.encryption_key.is_some();
+        let operation = if encrypted { PrivilegedOperation::ExportOriginal } else { PrivilegedOperation::PlayRecording };
+        let operator = self.authorize(operation, credential, source).await?;
+
+        let player = if encrypted {
+            let temp_dir = std::env::current_dir()?.join("temp");
+            tokio::fs::create_dir_all(&temp_dir).await?;
+            let copy = temp_dir.join(format!("{}_playback.mp4", segment.id));
+            crate::custody::plaintext_copy(&self.config(), &segment, &copy).await?;
+            Player::new(&segment, copy, true)
+        } else {
+            Player::new(&segment, std::path::PathBuf::from(&segment.file_path), false)
+        };
+
+        let access = RecordingAccess::new(&segment, RecordingAccessKind::Played, operator, reason, source)?;
+        self.record_recording_access(access).await?;
+        Ok(player)
+    }
+
+    /// Audit a look at a recording and sync it to the backend, journaled
+    /// like any other backend call so it survives being offline
+    pub async fn record_recording_access(&self, access: RecordingAccess) -> Result<()> {
+        self.audit_log().record("recording_accessed", &access.source, serde_json::to_value(&access)?).await?;
+        self.submit(JournalOp::RecordingAccess { access }).await
+    }
+
+    pub async fn get_recent_deletions(&self, limit: usize) -> Vec<crate::storage_manager::DeletedFileRecord> {
+        self.inner.storage_manager.lock().await.get_recent_deletions(limit)
+    }
+
+    pub async fn sync_deletions_to_server(&self) -> Result<()> {
+        let mut storage_manager = self.inner.storage_manager.lock().await;
+        let deleted_files = storage_manager.get_deleted_files().to_vec();
         if !deleted_files.is_empty() {
-            // Here you would integrate with your server API to notify about deleted files
             tracing::info!("Syncing {} deleted files to server", deleted_files.len());
-            
-            // After successful sync, clear the local deletion log
-            self.storage_manager.clear_deletion_log().await?;
+
+            // Once journaled the deletions are safe to drop from the local log
+            self.submit(JournalOp::Deletions { records: deleted_files }).await?;
+            storage_manager.clear_deletion_log().await?;
         }
         Ok(())
     }
 
-    pub async fn shutdown(&mut self) -> Result<()> {
+    pub async fn shutdown(&self) -> Result<()> {
         tracing::info!("Shutting down bodycam device");
 
+        // Don't wait on uploads; finished segments stay for the next start
+        self.inner.canceller.shutdown();
+
         // Stop recording if active
-        if self.is_recording {
+        if self.is_recording() {
             if let Err(e) = self.stop_recording().await {
                 tracing::error!("Failed to stop recording during shutdown: {}", e);
             }
         }
 
+        // Keep buffered pre-incident footage for the next start
+        if let Err(e) = self.inner.buffer.suspend().await {
+            tracing::error!("Failed to persist pre-incident buffer: {}", e);
+        }
+
         // Stop streaming if active
-        if let Err(e) = self.streaming_manager.stop_streaming().await {
+        if let Err(e) = self.inner.streaming_manager.lock().await.stop_streaming().await {
             tracing::error!("Failed to stop streaming during shutdown: {}", e);
         }
 
         // Stop audio if active
-        if let Err(e) = self.audio_manager.stop_all().await {
+        if let Err(e) = self.inner.audio_manager.stop_all().await {
             tracing::error!("Failed to stop audio during shutdown: {}", e);
         }
 
         // Shutdown resource manager (this will cleanup processes and temp files)
-        if let Err(e) = self.resource_manager.shutdown().await {
+        if let Err(e) = self.inner.resource_manager.shutdown().await {
             tracing::error!("Failed to shutdown resource manager: {}", e);
         }
 
         // Shutdown hardware interface
-        if let Err(e) = self.hardware.shutdown().await {
+        if let Err(e) = self.inner.hardware.shutdown().await {
             tracing::error!("Failed to shutdown hardware interface: {}", e);
         }
 
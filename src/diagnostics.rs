@@ -2,6 +2,15 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
+use tokio_util::sync::CancellationToken;
+
+use crate::cancellation::cancellable;
+use crate::supervisor::{TaskHealth, TaskSupervisor};
+use crate::metrics_history::{MetricsHistory, StorageSeries};
+use crate::hardware::brightness::BrightnessStatus;
+
+/// How far back storage growth and error trends look
+const TREND_WINDOW_DAYS: i64 = 7;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComprehensiveDiagnostics {
@@ -39,6 +48,8 @@ pub struct SystemHealth {
     pub temperature: TemperatureMetrics,
     pub power_status: PowerMetrics,
     pub disk_health: DiskHealthMetrics,
+    /// Supervised background tasks and their restarts
+    pub services: Vec<TaskHealth>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,6 +98,9 @@ pub struct PowerMetrics {
     pub power_consumption_w: Option<f32>,
     pub estimated_runtime_hours: Option<f32>,
     pub power_status: HealthStatus,
+    /// Display and LED levels after dimming
+    #[serde(default)]
+    pub brightness: Option<BrightnessStatus>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -185,6 +199,8 @@ pub struct ConnectivityTests {
     pub gps_connectivity: ConnectivityTest,
     pub cellular_connectivity: Option<ConnectivityTest>,
     pub wifi_connectivity: Option<ConnectivityTest>,
+    /// Retry counters for API requests since startup
+    pub api_retries: crate::retry::RetryStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -248,11 +264,21 @@ pub struct AuthenticationStatus {
     pub authentication_method: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CertificateStatus {
     pub certificates_valid: bool,
     pub expiry_dates: Vec<DateTime<Utc>>,
     pub next_renewal: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// Last expiry check or renewal attempt
+    #[serde(default)]
+    pub last_checked: Option<DateTime<Utc>>,
+    /// Why the last renewal failed
+    #[serde(default)]
+    pub last_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -317,17 +343,106 @@ pub struct CrashReport {
 pub struct DiagnosticsRunner {
     device_id: String,
     config: crate::config::Config,
+    crash_reports: Vec<CrashReport>,
+    recording_performance: Option<RecordingPerformance>,
+    /// Measured glass-to-glass latency of the live stream
+    stream_latency_ms: Option<u64>,
+    /// GB a day, from the metrics history
+    storage_growth: HashMap<StorageSeries, f64>,
+    error_trends: Vec<ErrorTrend>,
+    /// The device certificate as stored; none is reported without it
+    certificate_status: Option<CertificateStatus>,
+    brightness: Option<BrightnessStatus>,
+    cancel: CancellationToken,
 }
 
 impl DiagnosticsRunner {
     pub fn new(device_id: String, config: crate::config::Config) -> Self {
-        Self { device_id, config }
+        Self {
+            device_id,
+            config,
+            crash_reports: Vec::new(),
+            recording_performance: None,
+            stream_latency_ms: None,
+            storage_growth: HashMap::new(),
+            error_trends: Vec::new(),
+            certificate_status: None,
+            brightness: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Stop the diagnostics run when `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Encoder metrics of the running recording, if any
+    pub fn with_recording_performance(mut self, performance: Option<RecordingPerformance>) -> Self {
+        self.recording_performance = performance;
+        self
+    }
+
+    /// Glass-to-glass latency of the live stream, if one is running
+    pub fn with_stream_latency(mut self, latency_ms: Option<u64>) -> Self {
+        self.stream_latency_ms = latency_ms;
+        self
+    }
+
+    /// Expiry and renewal of the device certificate
+    pub fn with_certificate_status(mut self, status: CertificateStatus) -> Self {
+        self.certificate_status = Some(status);
+        self
+    }
+
+    /// Display and LED brightness, reported with the power metrics
+    pub fn with_brightness(mut self, brightness: BrightnessStatus) -> Self {
+        self.brightness = Some(brightness);
+        self
+    }
+
+    /// Include crashes of supervised child processes in the error logs
+    pub fn with_crash_reports(mut self, crash_reports: Vec<CrashReport>) -> Self {
+        self.crash_reports = crash_reports;
+        self
+    }
+
+    /// Work out storage growth and error trends from the metrics kept on
+    /// the device. Without history they're left out of the report.
+    pub fn with_metrics_history(mut self, history: Option<&MetricsHistory>) -> Self {
+        let Some(history) = history else {
+            return self;
+        };
+        let since = Utc::now() - chrono::Duration::days(TREND_WINDOW_DAYS);
+        for series in [StorageSeries::Recordings, StorageSeries::Logs, StorageSeries::Temp, StorageSeries::System] {
+            match history.storage_growth_per_day(series, since) {
+                Ok(Some(growth)) => {
+                    self.storage_growth.insert(series, growth);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read storage history: {:#}", e),
+            }
+        }
+        match history.error_trends(since) {
+            Ok(trends) => self.error_trends = trends,
+            Err(e) => tracing::warn!("Failed to read error history: {:#}", e),
+        }
+        self
     }
 
     pub async fn run_comprehensive_diagnostics(
         &self,
         hardware: &dyn crate::hardware::HardwareInterface,
         resource_manager: &crate::resource_manager::ResourceManager,
+    ) -> Result<ComprehensiveDiagnostics> {
+        cancellable(&self.cancel, "diagnostics", self.collect_diagnostics(hardware, resource_manager)).await
+    }
+
+    async fn collect_diagnostics(
+        &self,
+        hardware: &dyn crate::hardware::HardwareInterface,
+        resource_manager: &crate::resource_manager::ResourceManager,
     ) -> Result<ComprehensiveDiagnostics> {
         tracing::info!("Starting comprehensive diagnostics");
 
@@ -394,9 +509,13 @@ impl DiagnosticsRunner {
             HealthStatus::Healthy
         };
 
+        let services = TaskSupervisor::global().health();
+        let services_down = services.iter().any(|task| !task.is_healthy());
+
         let overall_status = match (&memory_pressure, &disk_health) {
             (HealthStatus::Critical, _) | (_, HealthStatus::Critical) => HealthStatus::Critical,
             (HealthStatus::Warning, _) | (_, HealthStatus::Warning) => HealthStatus::Warning,
+            _ if services_down => HealthStatus::Warning,
             _ => HealthStatus::Healthy,
         };
 
@@ -433,6 +552,7 @@ impl DiagnosticsRunner {
                 power_consumption_w: Some(2.5),
                 estimated_runtime_hours: Some(8.5),
                 power_status: HealthStatus::Healthy,
+                brightness: self.brightness.clone(),
             },
             disk_health: DiskHealthMetrics {
                 total_space_gb: resource_stats.disk_usage.total_gb,
@@ -443,6 +563,7 @@ impl DiagnosticsRunner {
                 write_speed_mbps: Some(30.0),
                 disk_health,
             },
+            services,
         })
     }
 
@@ -492,17 +613,38 @@ impl DiagnosticsRunner {
     }
 
     async fn test_microphone(&self) -> ComponentHealth {
+        let audio = &self.config.audio;
+        let mut status = HealthStatus::Healthy;
+        let mut error_count = 0;
+
+        let mut details = HashMap::new();
+        details.insert("sample_rate".to_string(), serde_json::json!(audio.sample_rate));
+        details.insert("channels".to_string(), serde_json::json!(audio.channels));
+        details.insert("noise_suppression".to_string(), serde_json::json!(audio.processing.noise_suppression));
+        details.insert("automatic_gain_control".to_string(), serde_json::json!(audio.processing.automatic_gain_control));
+        details.insert("high_pass_hz".to_string(), serde_json::json!(audio.processing.high_pass_hz));
+
+        if !self.config.simulation.enabled {
+            let device = audio.device_path.as_deref().unwrap_or("default");
+            match crate::audio_processing::measure_input_levels(device, &audio.processing, 3).await {
+                Ok(levels) => {
+                    details.insert("input_rms_before_db".to_string(), serde_json::json!(levels.rms_before_db));
+                    details.insert("input_rms_after_db".to_string(), serde_json::json!(levels.rms_after_db));
+                }
+                Err(e) => {
+                    tracing::warn!("Microphone level measurement failed: {}", e);
+                    details.insert("error".to_string(), serde_json::json!(e.to_string()));
+                    status = HealthStatus::Warning;
+                    error_count = 1;
+                }
+            }
+        }
+
         ComponentHealth {
-            status: HealthStatus::Healthy,
+            status,
             last_test: Some(Utc::now()),
-            error_count: 0,
-            details: {
-                let mut details = HashMap::new();
-                details.insert("sample_rate".to_string(), serde_json::Value::Number(serde_json::Number::from(44100)));
-                details.insert("channels".to_string(), serde_json::Value::Number(serde_json::Number::from(2)));
-                details.insert("noise_reduction".to_string(), serde_json::Value::Bool(true));
-                details
-            },
+            error_count,
+            details,
         }
     }
 
@@ -624,19 +766,19 @@ impl DiagnosticsRunner {
 
     async fn measure_performance(&self) -> Result<PerformanceMetrics> {
         Ok(PerformanceMetrics {
-            recording_performance: RecordingPerformance {
-                current_fps: Some(29.8),
-                target_fps: 30,
-                dropped_frames: 12,
-                encoding_latency_ms: Some(16.7),
-                disk_write_speed_mbps: Some(25.0),
-                recording_status: HealthStatus::Healthy,
-            },
+            recording_performance: self.recording_performance.clone().unwrap_or(RecordingPerformance {
+                current_fps: None,
+                target_fps: self.config.recording.fps,
+                dropped_frames: 0,
+                encoding_latency_ms: None,
+                disk_write_speed_mbps: None,
+                recording_status: HealthStatus::Unknown,
+            }),
             streaming_performance: StreamingPerformance {
                 bitrate_kbps: Some(2500),
                 target_bitrate_kbps: 2500,
                 packet_loss_percent: Some(0.1),
-                latency_ms: Some(150.0),
+                latency_ms: self.stream_latency_ms.map(|ms| ms as f64),
                 streaming_status: HealthStatus::Healthy,
             },
             audio_performance: AudioPerformance {
@@ -701,6 +843,7 @@ impl DiagnosticsRunner {
                 error_message: None,
                 test_details: HashMap::new(),
             }),
+            api_retries: crate::retry::stats(),
         })
     }
 
@@ -711,28 +854,28 @@ impl DiagnosticsRunner {
                 file_count: 156,
                 oldest_file: Some(Utc::now() - chrono::Duration::days(25)),
                 newest_file: Some(Utc::now() - chrono::Duration::hours(2)),
-                growth_rate_gb_per_day: Some(0.8),
+                growth_rate_gb_per_day: self.storage_growth.get(&StorageSeries::Recordings).copied(),
             },
             logs_storage: StorageCategory {
                 used_gb: 0.25,
                 file_count: 45,
                 oldest_file: Some(Utc::now() - chrono::Duration::days(15)),
                 newest_file: Some(Utc::now()),
-                growth_rate_gb_per_day: Some(0.05),
+                growth_rate_gb_per_day: self.storage_growth.get(&StorageSeries::Logs).copied(),
             },
             temp_storage: StorageCategory {
                 used_gb: 0.1,
                 file_count: 12,
                 oldest_file: Some(Utc::now() - chrono::Duration::hours(6)),
                 newest_file: Some(Utc::now() - chrono::Duration::minutes(30)),
-                growth_rate_gb_per_day: Some(0.2),
+                growth_rate_gb_per_day: self.storage_growth.get(&StorageSeries::Temp).copied(),
             },
             system_storage: StorageCategory {
                 used_gb: 2.8,
                 file_count: 1245,
                 oldest_file: Some(Utc::now() - chrono::Duration::days(90)),
                 newest_file: Some(Utc::now()),
-                growth_rate_gb_per_day: Some(0.01),
+                growth_rate_gb_per_day: self.storage_growth.get(&StorageSeries::System).copied(),
             },
             cleanup_recommendations: vec![
                 CleanupRecommendation {
@@ -767,11 +910,7 @@ impl DiagnosticsRunner {
                 last_authentication: Some(Utc::now() - chrono::Duration::hours(2)),
                 authentication_method: "Ed25519".to_string(),
             },
-            certificate_status: CertificateStatus {
-                certificates_valid: true,
-                expiry_dates: vec![Utc::now() + chrono::Duration::days(365)],
-                next_renewal: Some(Utc::now() + chrono::Duration::days(300)),
-            },
+            certificate_status: self.certificate_status.clone().unwrap_or_default(),
             access_control: AccessControlStatus {
                 permissions_valid: true,
                 role_assignments: vec!["bodycam_operator".to_string()],
@@ -808,9 +947,9 @@ impl DiagnosticsRunner {
                     map.insert("WARN".to_string(), 12);
                     map
                 },
-                error_trends: vec![],
+                error_trends: self.error_trends.clone(),
             },
-            crash_reports: vec![],
+            crash_reports: self.crash_reports.clone(),
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::ComprehensiveDiagnostics;
+
+/// Report file names sort oldest first
+const FILE_NAME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsArchiveConfig {
+    /// Reports older than this are deleted
+    pub retention_days: u32,
+    /// Only the newest reports are kept beyond this many
+    pub max_reports: usize,
+}
+
+impl Default for DiagnosticsArchiveConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            max_reports: 20,
+        }
+    }
+}
+
+/// Where a diagnostics run requested remotely ended up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsUpload {
+    pub generated_at: DateTime<Utc>,
+    pub archived_path: PathBuf,
+    /// False if the report is waiting in the journal for connectivity
+    pub delivered: bool,
+}
+
+/// Comprehensive diagnostics kept on the device, so support can look back
+/// at earlier runs even if an upload never got through
+#[derive(Debug, Clone)]
+pub struct DiagnosticsArchive {
+    dir: PathBuf,
+    config: DiagnosticsArchiveConfig,
+}
+
+impl DiagnosticsArchive {
+    pub fn default_dir() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("diagnostics")
+    }
+
+    pub fn new(config: DiagnosticsArchiveConfig) -> Self {
+        Self::with_dir(Self::default_dir(), config)
+    }
+
+    pub fn with_dir(dir: PathBuf, config: DiagnosticsArchiveConfig) -> Self {
+        Self { dir, config }
+    }
+
+    /// Save `report`, then drop reports past the retention policy
+    pub async fn store(&self, report: &ComprehensiveDiagnostics) -> Result<PathBuf> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!("{}.json", report.timestamp.format(FILE_NAME_FORMAT)));
+        tokio::fs::write(&path, serde_json::to_string_pretty(report)?).await
+            .with_context(|| format!("Failed to archive diagnostics to {}", path.display()))?;
+
+        let removed = self.prune(Utc::now()).await?;
+        if removed > 0 {
+            tracing::debug!("Removed {} archived diagnostics report(s)", removed);
+        }
+        Ok(path)
+    }
+
+    /// Archived reports, oldest first
+    pub async fn reports(&self) -> Result<Vec<PathBuf>> {
+        let mut reports = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(reports),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if report_time(&path).is_some() {
+                reports.push(path);
+            }
+        }
+        reports.sort();
+        Ok(reports)
+    }
+
+    pub async fn load(path: &Path) -> Result<ComprehensiveDiagnostics> {
+        let content = tokio::fs::read_to_string(path).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Delete reports older than the retention period and all but the
+    /// newest `max_reports`, returning how many were deleted
+    pub async fn prune(&self, now: DateTime<Utc>) -> Result<usize> {
+        let reports = self.reports().await?;
+        let oldest = now - Duration::days(self.config.retention_days as i64);
+        let excess = reports.len().saturating_sub(self.config.max_reports);
+
+        let mut removed = 0;
+        for (index, path) in reports.iter().enumerate() {
+            let expired = report_time(path).is_some_and(|time| time < oldest);
+            if index < excess || expired {
+                tokio::fs::remove_file(path).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// When a report was generated, from its file name
+fn report_time(path: &Path) -> Option<DateTime<Utc>> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    NaiveDateTime::parse_from_str(stem, FILE_NAME_FORMAT).ok().map(|time| time.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prune_keeps_recent_reports_within_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = DiagnosticsArchive::with_dir(
+            dir.path().to_path_buf(),
+            DiagnosticsArchiveConfig { retention_days: 7, max_reports: 2 },
+        );
+        let now = Utc::now();
+        for days_ago in [30, 3, 2, 1] {
+            let name = format!("{}.json", (now - Duration::days(days_ago)).format(FILE_NAME_FORMAT));
+            std::fs::write(dir.path().join(name), "{}").unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        assert_eq!(archive.prune(now).await.unwrap(), 2);
+        let kept = archive.reports().await.unwrap();
+        assert_eq!(kept.len(), 2);
+        assert!(report_time(&kept[0]).unwrap() > now - Duration::days(3));
+        assert!(dir.path().join("notes.txt").exists());
+    }
+}
@@ -3,22 +3,248 @@ use sentry::Level;
 use std::collections::BTreeMap;
 use tracing::{warn, error};
 
+use crate::preflight::PreflightFailure;
 use crate::sentry_integration;
 
+/// Exit codes the CLI uses for each kind of failure, so scripts and the
+/// supervisor can tell them apart
+pub mod exit_codes {
+    /// Anything without a structured error
+    pub const GENERAL: u8 = 1;
+    pub const NOT_PROVISIONED: u8 = 10;
+    pub const AUTHENTICATION: u8 = 11;
+    pub const CONFIGURATION: u8 = 12;
+    pub const NETWORK: u8 = 20;
+    pub const SERVER: u8 = 21;
+    pub const MEDIA: u8 = 30;
+    pub const STORAGE: u8 = 31;
+    /// A recording's prerequisites weren't met
+    pub const PREFLIGHT: u8 = 32;
+    pub const HARDWARE: u8 = 40;
+    pub const BATTERY_CRITICAL: u8 = 41;
+    pub const TIMEOUT: u8 = 50;
+    pub const RESOURCE_EXHAUSTED: u8 = 51;
+    /// As for a process stopped by Ctrl-C
+    pub const CANCELLED: u8 = 130;
+}
+
+/// What every structured error reports, for exit codes, breadcrumbs and
+/// grouping in Sentry
+pub trait StructuredError: std::error::Error {
+    /// Short area name, e.g. "api" or "hardware"
+    fn category(&self) -> &'static str;
+
+    /// The variant, without any message text
+    fn kind(&self) -> &'static str;
+
+    fn exit_code(&self) -> u8;
+
+    fn sentry_level(&self) -> Level {
+        Level::Error
+    }
+
+    /// Events with the same fingerprint are grouped into one Sentry issue,
+    /// whatever their messages say
+    fn fingerprint(&self) -> Vec<String> {
+        vec![self.category().to_string(), self.kind().to_string()]
+    }
+}
+
+/// Failures talking to the platform API
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// The request never got a response: no route, DNS, timeout
+    #[error("Network error calling {endpoint}: {message}")]
+    Network { endpoint: String, message: String },
+
+    #[error("Circuit open for {endpoint}, not sending request")]
+    CircuitOpen { endpoint: String },
+
+    #[error("{endpoint} rejected the device's credentials (HTTP {status})")]
+    Unauthorized { endpoint: String, status: u16 },
+
+    /// The server kept failing after retries
+    #[error("{endpoint} failed with HTTP {status}: {body}")]
+    Server { endpoint: String, status: u16, body: String },
+
+    /// The server refused the request itself; sending it again won't help
+    #[error("{endpoint} rejected the request with HTTP {status}: {body}")]
+    Rejected { endpoint: String, status: u16, body: String },
+}
+
+impl ApiError {
+    /// Turns a response the retries gave up on into an error; responses
+    /// the caller can act on, including other 4xx, are passed through
+    pub async fn check(endpoint: &str, result: Result<reqwest::Response>) -> Result<reqwest::Response> {
+        let endpoint = endpoint.to_string();
+        match result {
+            Ok(response) if matches!(response.status().as_u16(), 401 | 403) => {
+                Err(ApiError::Unauthorized { endpoint, status: response.status().as_u16() }.into())
+            }
+            Ok(response) if crate::retry::is_retryable_status(response.status()) => {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                Err(ApiError::Server { endpoint, status, body }.into())
+            }
+            Ok(response) => Ok(response),
+            Err(e) => Err(ApiError::Network { endpoint, message: format!("{:#}", e) }.into()),
+        }
+    }
+
+    /// The error for a response that wasn't a success
+    pub async fn from_response(endpoint: &str, response: reqwest::Response) -> anyhow::Error {
+        let endpoint = endpoint.to_string();
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        match status.as_u16() {
+            401 | 403 => ApiError::Unauthorized { endpoint, status: status.as_u16() },
+            _ if status.is_client_error() && !crate::retry::is_retryable_status(status) => {
+                ApiError::Rejected { endpoint, status: status.as_u16(), body }
+            }
+            _ => ApiError::Server { endpoint, status: status.as_u16(), body },
+        }.into()
+    }
+
+    /// Whether `error` is a request the server will never accept as sent
+    pub fn is_permanent(error: &anyhow::Error) -> bool {
+        matches!(error.downcast_ref::<ApiError>(), Some(ApiError::Rejected { .. }))
+    }
+}
+
+impl StructuredError for ApiError {
+    fn category(&self) -> &'static str {
+        "api"
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            ApiError::Network { .. } => "network",
+            ApiError::CircuitOpen { .. } => "circuit_open",
+            ApiError::Unauthorized { .. } => "unauthorized",
+            ApiError::Server { .. } => "server",
+            ApiError::Rejected { .. } => "rejected",
+        }
+    }
+
+    fn exit_code(&self) -> u8 {
+        match self {
+            ApiError::Network { .. } | ApiError::CircuitOpen { .. } => exit_codes::NETWORK,
+            ApiError::Unauthorized { .. } => exit_codes::AUTHENTICATION,
+            ApiError::Server { .. } | ApiError::Rejected { .. } => exit_codes::SERVER,
+        }
+    }
+
+    fn sentry_level(&self) -> Level {
+        match self {
+            ApiError::Server { .. } => Level::Error,
+            _ => Level::Warning,
+        }
+    }
+
+    fn fingerprint(&self) -> Vec<String> {
+        let endpoint = match self {
+            ApiError::Network { endpoint, .. }
+            | ApiError::CircuitOpen { endpoint }
+            | ApiError::Unauthorized { endpoint, .. }
+            | ApiError::Server { endpoint, .. }
+            | ApiError::Rejected { endpoint, .. } => endpoint.clone(),
+        };
+        vec![self.category().to_string(), self.kind().to_string(), endpoint]
+    }
+}
+
+/// Failures recording or encoding media
+#[derive(Debug, thiserror::Error)]
+pub enum MediaError {
+    #[error("Failed to start {label}: {message}")]
+    EncoderStart { label: String, message: String },
+
+    #[error("No space left on device")]
+    DiskFull,
+
+    #[error("No active segment for quality {quality}")]
+    NoActiveSegment { quality: String },
+
+    #[error("Invalid audio recording settings: {message}")]
+    InvalidAudioSettings { message: String },
+}
+
+impl StructuredError for MediaError {
+    fn category(&self) -> &'static str {
+        "media"
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            MediaError::EncoderStart { .. } => "encoder_start",
+            MediaError::DiskFull => "disk_full",
+            MediaError::NoActiveSegment { .. } => "no_active_segment",
+            MediaError::InvalidAudioSettings { .. } => "invalid_audio_settings",
+        }
+    }
+
+    fn exit_code(&self) -> u8 {
+        match self {
+            MediaError::DiskFull => exit_codes::STORAGE,
+            MediaError::InvalidAudioSettings { .. } => exit_codes::CONFIGURATION,
+            _ => exit_codes::MEDIA,
+        }
+    }
+}
+
+/// Failures of the device's own hardware
+#[derive(Debug, thiserror::Error)]
+pub enum HardwareError {
+    #[error("Failed to initialize {component}: {message}")]
+    Init { component: String, message: String },
+
+    #[error("{device} is not connected")]
+    Missing { device: String },
+
+    #[error("{component} failed: {message}")]
+    Fault { component: String, message: String },
+}
+
+impl StructuredError for HardwareError {
+    fn category(&self) -> &'static str {
+        "hardware"
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            HardwareError::Init { .. } => "init",
+            HardwareError::Missing { .. } => "missing",
+            HardwareError::Fault { .. } => "fault",
+        }
+    }
+
+    fn exit_code(&self) -> u8 {
+        exit_codes::HARDWARE
+    }
+
+    fn fingerprint(&self) -> Vec<String> {
+        let component = match self {
+            HardwareError::Init { component, .. } | HardwareError::Fault { component, .. } => component.clone(),
+            HardwareError::Missing { device } => device.clone(),
+        };
+        vec![self.category().to_string(), self.kind().to_string(), component]
+    }
+}
+
 /// Custom error types for device operations
 #[derive(Debug, thiserror::Error)]
 pub enum DeviceError {
-    #[error("Hardware error: {message}")]
-    Hardware { message: String },
+    #[error(transparent)]
+    Hardware(#[from] HardwareError),
     
-    #[error("Authentication error: {message}")]
-    Authentication { message: String },
+    #[error(transparent)]
+    Api(#[from] ApiError),
     
-    #[error("Recording error: {message}")]
-    Recording { message: String },
+    #[error(transparent)]
+    Media(#[from] MediaError),
     
-    #[error("Network error: {message}")]
-    Network { message: String },
+    #[error("Authentication error: {message}")]
+    Authentication { message: String },
     
     #[error("Configuration error: {message}")]
     Configuration { message: String },
@@ -37,22 +263,30 @@ pub enum DeviceError {
     
     #[error("Resource exhausted: {resource}")]
     ResourceExhausted { resource: String },
+    
+    #[error("Cancelled: {operation}")]
+    Cancelled { operation: String },
+
+    #[error(transparent)]
+    Preflight(#[from] PreflightFailure),
 }
 
 impl DeviceError {
     /// Get the error category for Sentry breadcrumbs
     pub fn category(&self) -> &'static str {
         match self {
-            DeviceError::Hardware { .. } => "hardware",
+            DeviceError::Hardware(e) => e.category(),
+            DeviceError::Api(e) => e.category(),
+            DeviceError::Media(e) => e.category(),
             DeviceError::Authentication { .. } => "auth",
-            DeviceError::Recording { .. } => "recording",
-            DeviceError::Network { .. } => "network",
             DeviceError::Configuration { .. } => "config",
             DeviceError::Storage { .. } => "storage",
             DeviceError::BatteryCritical { .. } => "power",
             DeviceError::NotProvisioned => "provisioning",
             DeviceError::Timeout { .. } => "timeout",
             DeviceError::ResourceExhausted { .. } => "resources",
+            DeviceError::Cancelled { .. } => "cancelled",
+            DeviceError::Preflight(_) => "preflight",
         }
     }
     
@@ -60,15 +294,17 @@ impl DeviceError {
     pub fn sentry_level(&self) -> Level {
         match self {
             DeviceError::BatteryCritical { .. } => Level::Fatal,
-            DeviceError::Hardware { .. } => Level::Error,
-            DeviceError::Recording { .. } => Level::Error,
+            DeviceError::Hardware(e) => e.sentry_level(),
+            DeviceError::Api(e) => e.sentry_level(),
+            DeviceError::Media(e) => e.sentry_level(),
             DeviceError::Storage { .. } => Level::Error,
             DeviceError::ResourceExhausted { .. } => Level::Error,
             DeviceError::Authentication { .. } => Level::Warning,
-            DeviceError::Network { .. } => Level::Warning,
             DeviceError::Configuration { .. } => Level::Warning,
             DeviceError::NotProvisioned => Level::Info,
             DeviceError::Timeout { .. } => Level::Warning,
+            DeviceError::Cancelled { .. } => Level::Info,
+            DeviceError::Preflight(_) => Level::Warning,
         }
     }
     
@@ -78,17 +314,17 @@ impl DeviceError {
         context.insert("error_category".to_string(), self.category().into());
         
         match self {
-            DeviceError::Hardware { message } => {
-                context.insert("hardware_message".to_string(), message.clone().into());
+            DeviceError::Hardware(e) => {
+                context.insert("hardware_message".to_string(), e.to_string().into());
             }
-            DeviceError::Authentication { message } => {
-                context.insert("auth_message".to_string(), message.clone().into());
+            DeviceError::Api(e) => {
+                context.insert("api_message".to_string(), e.to_string().into());
             }
-            DeviceError::Recording { message } => {
-                context.insert("recording_message".to_string(), message.clone().into());
+            DeviceError::Media(e) => {
+                context.insert("media_message".to_string(), e.to_string().into());
             }
-            DeviceError::Network { message } => {
-                context.insert("network_message".to_string(), message.clone().into());
+            DeviceError::Authentication { message } => {
+                context.insert("auth_message".to_string(), message.clone().into());
             }
             DeviceError::Configuration { message } => {
                 context.insert("config_message".to_string(), message.clone().into());
@@ -108,12 +344,94 @@ impl DeviceError {
             DeviceError::NotProvisioned => {
                 context.insert("provisioning_status".to_string(), "not_provisioned".into());
             }
+            DeviceError::Cancelled { operation } => {
+                context.insert("cancelled_operation".to_string(), operation.clone().into());
+            }
+            DeviceError::Preflight(failure) => {
+                context.insert("preflight_check".to_string(), failure.check().into());
+                context.insert("preflight_message".to_string(), failure.to_string().into());
+            }
         }
         
         context
     }
 }
 
+impl StructuredError for DeviceError {
+    fn category(&self) -> &'static str {
+        DeviceError::category(self)
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            DeviceError::Hardware(e) => e.kind(),
+            DeviceError::Api(e) => e.kind(),
+            DeviceError::Media(e) => e.kind(),
+            DeviceError::Authentication { .. } => "authentication",
+            DeviceError::Configuration { .. } => "configuration",
+            DeviceError::Storage { .. } => "storage",
+            DeviceError::BatteryCritical { .. } => "battery_critical",
+            DeviceError::NotProvisioned => "not_provisioned",
+            DeviceError::Timeout { .. } => "timeout",
+            DeviceError::ResourceExhausted { .. } => "resource_exhausted",
+            DeviceError::Cancelled { .. } => "cancelled",
+            DeviceError::Preflight(failure) => failure.check(),
+        }
+    }
+
+    fn exit_code(&self) -> u8 {
+        match self {
+            DeviceError::Hardware(e) => e.exit_code(),
+            DeviceError::Api(e) => e.exit_code(),
+            DeviceError::Media(e) => e.exit_code(),
+            DeviceError::Authentication { .. } => exit_codes::AUTHENTICATION,
+            DeviceError::Configuration { .. } => exit_codes::CONFIGURATION,
+            DeviceError::Storage { .. } => exit_codes::STORAGE,
+            DeviceError::BatteryCritical { .. } => exit_codes::BATTERY_CRITICAL,
+            DeviceError::NotProvisioned => exit_codes::NOT_PROVISIONED,
+            DeviceError::Timeout { .. } => exit_codes::TIMEOUT,
+            DeviceError::ResourceExhausted { .. } => exit_codes::RESOURCE_EXHAUSTED,
+            DeviceError::Cancelled { .. } => exit_codes::CANCELLED,
+            DeviceError::Preflight(_) => exit_codes::PREFLIGHT,
+        }
+    }
+
+    fn sentry_level(&self) -> Level {
+        DeviceError::sentry_level(self)
+    }
+
+    fn fingerprint(&self) -> Vec<String> {
+        match self {
+            DeviceError::Hardware(e) => e.fingerprint(),
+            DeviceError::Api(e) => e.fingerprint(),
+            DeviceError::Media(e) => e.fingerprint(),
+            _ => vec![self.category().to_string(), self.kind().to_string()],
+        }
+    }
+}
+
+/// The first structured error in `error`'s chain, under any context added
+/// on the way up
+pub fn structured(error: &anyhow::Error) -> Option<&(dyn StructuredError + 'static)> {
+    error.chain().find_map(|cause| -> Option<&(dyn StructuredError + 'static)> {
+        if let Some(e) = cause.downcast_ref::<DeviceError>() {
+            return Some(e);
+        }
+        if let Some(e) = cause.downcast_ref::<ApiError>() {
+            return Some(e);
+        }
+        if let Some(e) = cause.downcast_ref::<MediaError>() {
+            return Some(e);
+        }
+        cause.downcast_ref::<HardwareError>().map(|e| e as &(dyn StructuredError + 'static))
+    })
+}
+
+/// Process exit code for a failed command
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    structured(error).map(|e| e.exit_code()).unwrap_or(exit_codes::GENERAL)
+}
+
 /// Result type for device operations
 pub type DeviceResult<T> = Result<T, DeviceError>;
 
@@ -349,4 +667,29 @@ pub mod network {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structured_errors_survive_context() {
+        let error = anyhow::Error::from(DeviceError::NotProvisioned).context("Failed to start streaming");
+        assert_eq!(exit_code(&error), exit_codes::NOT_PROVISIONED);
+
+        let error = anyhow::Error::from(ApiError::Server {
+            endpoint: "/api/incidents".to_string(),
+            status: 503,
+            body: "maintenance".to_string(),
+        }).context("Incident creation failed");
+        let found = structured(&error).unwrap();
+        assert_eq!(found.exit_code(), exit_codes::SERVER);
+        assert_eq!(found.fingerprint(), ["api", "server", "/api/incidents"]);
+
+        let nested = DeviceError::from(HardwareError::Missing { device: "/dev/video0".to_string() });
+        assert_eq!(nested.category(), "hardware");
+        assert_eq!(exit_code(&nested.into()), exit_codes::HARDWARE);
+        assert_eq!(exit_code(&anyhow::anyhow!("something else")), exit_codes::GENERAL);
+    }
+}
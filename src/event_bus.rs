@@ -0,0 +1,171 @@
+use tokio::sync::broadcast;
+
+use crate::hardware::HardwareEvent;
+use crate::incident::{IncidentSeverity, IncidentType};
+use crate::recording_time::RecordingLimit;
+
+/// Events buffered per subscriber before the slowest one starts missing them
+const DEFAULT_CAPACITY: usize = 256;
+
+/// Groups of events a subscriber can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Hardware,
+    Incidents,
+    Recording,
+    Uploads,
+    Network,
+}
+
+#[derive(Debug, Clone)]
+pub enum IncidentEvent {
+    Triggered { incident_id: String, incident_type: IncidentType, severity: IncidentSeverity },
+    Acknowledged { incident_id: String },
+    /// Closed on the device by the operator
+    Ended { incident_id: String },
+}
+
+#[derive(Debug, Clone)]
+pub enum RecordingEvent {
+    Started { incident_id: Option<String> },
+    Stopped,
+    /// The recording in progress was paused; the encoders are suspended
+    Paused,
+    Resumed,
+    /// The estimated recording time left fell below the warning threshold
+    RemainingLow { minutes: u64, limited_by: RecordingLimit },
+}
+
+#[derive(Debug, Clone)]
+pub enum UploadEvent {
+    Queued { file_id: String, path: String },
+    /// Confirmed by the server so far
+    Progress { file_id: String, bytes_uploaded: u64, bytes_total: u64 },
+    Completed { file_id: String },
+    /// Gave up after the last retry
+    Failed { file_id: String, error: String },
+}
+
+#[derive(Debug, Clone)]
+pub enum NetworkEvent {
+    Online,
+    Offline,
+}
+
+#[derive(Debug, Clone)]
+pub enum BusEvent {
+    Hardware(HardwareEvent),
+    Incident(IncidentEvent),
+    Recording(RecordingEvent),
+    Upload(UploadEvent),
+    Network(NetworkEvent),
+}
+
+impl BusEvent {
+    pub fn topic(&self) -> Topic {
+        match self {
+            BusEvent::Hardware(_) => Topic::Hardware,
+            BusEvent::Incident(_) => Topic::Incidents,
+            BusEvent::Recording(_) => Topic::Recording,
+            BusEvent::Upload(_) => Topic::Uploads,
+            BusEvent::Network(_) => Topic::Network,
+        }
+    }
+}
+
+/// Process-wide publish/subscribe channel. Subsystems publish what happened
+/// and whoever cares subscribes, so the device, the simulator and plugins
+/// such as MQTT or webhooks all see the same stream. Clones share the channel.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Returns the number of subscribers that will see the event
+    pub fn publish(&self, event: BusEvent) -> usize {
+        // No subscribers is not an error; nobody was interested
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Every event on every topic
+    pub fn subscribe(&self) -> Subscription {
+        Subscription { receiver: self.sender.subscribe(), topics: None }
+    }
+
+    pub fn subscribe_to(&self, topics: &[Topic]) -> Subscription {
+        Subscription { receiver: self.sender.subscribe(), topics: Some(topics.to_vec()) }
+    }
+}
+
+pub struct Subscription {
+    receiver: broadcast::Receiver<BusEvent>,
+    topics: Option<Vec<Topic>>,
+}
+
+impl Subscription {
+    /// The next event on a subscribed topic, or None once every publisher is
+    /// gone. A subscriber that falls behind skips what it missed rather than
+    /// stalling publishers.
+    pub async fn recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => {
+                    if self.topics.as_ref().map_or(true, |topics| topics.contains(&event.topic())) {
+                        return Some(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Event bus subscriber lagged, skipped {} events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_only_see_their_topics() {
+        let bus = EventBus::default();
+        let mut everything = bus.subscribe();
+        let mut network = bus.subscribe_to(&[Topic::Network]);
+
+        assert_eq!(bus.publish(BusEvent::Hardware(HardwareEvent::TamperDetected)), 2);
+        bus.publish(BusEvent::Network(NetworkEvent::Offline));
+
+        assert!(matches!(everything.recv().await, Some(BusEvent::Hardware(HardwareEvent::TamperDetected))));
+        assert!(matches!(everything.recv().await, Some(BusEvent::Network(NetworkEvent::Offline))));
+        assert!(matches!(network.recv().await, Some(BusEvent::Network(NetworkEvent::Offline))));
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_skips_ahead() {
+        let bus = EventBus::new(2);
+        let mut subscription = bus.subscribe();
+        for _ in 0..3 {
+            bus.publish(BusEvent::Network(NetworkEvent::Offline));
+        }
+        bus.publish(BusEvent::Network(NetworkEvent::Online));
+
+        assert!(matches!(subscription.recv().await, Some(BusEvent::Network(NetworkEvent::Offline))));
+        assert!(matches!(subscription.recv().await, Some(BusEvent::Network(NetworkEvent::Online))));
+
+        drop(bus);
+        assert!(subscription.recv().await.is_none());
+    }
+}
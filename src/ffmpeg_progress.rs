@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// Encoder counters reported by ffmpeg's `-progress` output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncoderProgress {
+    pub frame: u64,
+    pub fps: f64,
+    pub drop_frames: u64,
+    pub dup_frames: u64,
+    pub bitrate_kbps: Option<f64>,
+    pub total_size: u64,
+    /// Encoding speed relative to real time (1.0 keeps up with the camera)
+    pub speed: Option<f64>,
+    pub out_time_us: u64,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// ffmpeg arguments that write progress blocks to stdout for `ProgressParser`
+pub fn progress_args() -> [&'static str; 3] {
+    ["-progress", "pipe:1", "-nostats"]
+}
+
+/// Parses the `key=value` lines ffmpeg writes with `-progress`. Each block
+/// ends with a `progress=continue` (or `end`) line.
+#[derive(Debug, Default)]
+pub struct ProgressParser {
+    current: EncoderProgress,
+}
+
+impl ProgressParser {
+    /// Feed one line, returning the completed block when it ends
+    pub fn feed_line(&mut self, line: &str) -> Option<EncoderProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key {
+            "frame" => self.current.frame = value.parse().unwrap_or(self.current.frame),
+            "fps" => self.current.fps = value.parse().unwrap_or(self.current.fps),
+            "drop_frames" => self.current.drop_frames = value.parse().unwrap_or(self.current.drop_frames),
+            "dup_frames" => self.current.dup_frames = value.parse().unwrap_or(self.current.dup_frames),
+            // e.g. "2048.3kbits/s", or "N/A" before the first packet
+            "bitrate" => self.current.bitrate_kbps = value.trim_end_matches("kbits/s").parse().ok(),
+            "total_size" => self.current.total_size = value.parse().unwrap_or(self.current.total_size),
+            "speed" => self.current.speed = value.trim_end_matches('x').parse().ok(),
+            "out_time_us" => self.current.out_time_us = value.parse().unwrap_or(self.current.out_time_us),
+            "progress" => {
+                self.current.updated_at = Some(chrono::Utc::now());
+                return Some(self.current.clone());
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// Latest progress of one encoder, shared with its reader task
+#[derive(Debug, Clone, Default)]
+pub struct ProgressHandle {
+    latest: Arc<Mutex<Option<EncoderProgress>>>,
+}
+
+impl ProgressHandle {
+    pub fn get(&self) -> Option<EncoderProgress> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, progress: EncoderProgress) {
+        *self.latest.lock().unwrap() = Some(progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_block() {
+        let output = "\
+frame=300
+fps=29.97
+stream_0_0_q=23.0
+bitrate=2048.3kbits/s
+total_size=2621440
+out_time_us=10010000
+dup_frames=1
+drop_frames=4
+speed=0.998x
+progress=continue
+";
+        let mut parser = ProgressParser::default();
+        let blocks: Vec<EncoderProgress> = output.lines().filter_map(|l| parser.feed_line(l)).collect();
+
+        assert_eq!(blocks.len(), 1);
+        let progress = &blocks[0];
+        assert_eq!(progress.frame, 300);
+        assert_eq!(progress.drop_frames, 4);
+        assert_eq!(progress.dup_frames, 1);
+        assert_eq!(progress.bitrate_kbps, Some(2048.3));
+        assert_eq!(progress.speed, Some(0.998));
+        assert!((progress.fps - 29.97).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unavailable_values() {
+        let mut parser = ProgressParser::default();
+        parser.feed_line("bitrate=N/A");
+        parser.feed_line("speed=N/A");
+        let progress = parser.feed_line("progress=continue").unwrap();
+        assert_eq!(progress.bitrate_kbps, None);
+        assert_eq!(progress.speed, None);
+    }
+}
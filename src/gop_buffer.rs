@@ -0,0 +1,615 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+
+use crate::capture::VideoSource;
+use crate::config::VideoQualityConfig;
+
+const TS_PACKET_SIZE: usize = 188;
+const TS_SYNC_BYTE: u8 = 0x47;
+const PAT_PID: u16 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BufferStorage {
+    /// Encoded GOPs held in RAM, spilling the oldest to disk if allowed.
+    /// Survives a planned restart, but not a crash or power loss.
+    Memory,
+    /// Short segment files written continuously to storage. Checkpointed,
+    /// so the pre-roll survives a crash.
+    Disk,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreIncidentBufferConfig {
+    pub storage: BufferStorage,
+    /// RAM the buffer may use before it spills or drops the oldest GOPs
+    pub max_memory_mb: u32,
+    /// Move GOPs that don't fit in RAM to disk instead of dropping them
+    pub spill_to_disk: bool,
+    /// How often the disk buffer's index is saved for recovery after a crash
+    pub checkpoint_interval_seconds: u64,
+}
+
+impl Default for PreIncidentBufferConfig {
+    fn default() -> Self {
+        Self {
+            storage: BufferStorage::Disk,
+            max_memory_mb: 32,
+            spill_to_disk: false,
+            checkpoint_interval_seconds: 10,
+        }
+    }
+}
+
+/// One group of pictures: MPEG-TS packets from a keyframe up to the next
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gop {
+    pub started_at: DateTime<Utc>,
+    pub data: Vec<u8>,
+}
+
+/// Splits an MPEG-TS byte stream into GOPs at video keyframes (packets
+/// starting a PES with the random access indicator set), and remembers the
+/// latest PAT/PMT so a flushed buffer plays on its own
+#[derive(Debug, Default)]
+pub struct TsGopSplitter {
+    pending: Vec<u8>,
+    current: Option<Gop>,
+    pmt_pid: Option<u16>,
+    pat: Option<Vec<u8>>,
+    pmt: Option<Vec<u8>>,
+}
+
+impl TsGopSplitter {
+    /// Feed bytes from the encoder, returning every GOP completed by them
+    pub fn feed(&mut self, bytes: &[u8], now: DateTime<Utc>) -> Vec<Gop> {
+        self.pending.extend_from_slice(bytes);
+        let mut completed = Vec::new();
+        let mut offset = 0;
+
+        while self.pending.len() - offset >= TS_PACKET_SIZE {
+            if self.pending[offset] != TS_SYNC_BYTE {
+                // Lost sync; skip ahead to the next sync byte
+                offset += 1;
+                continue;
+            }
+
+            let packet = self.pending[offset..offset + TS_PACKET_SIZE].to_vec();
+            offset += TS_PACKET_SIZE;
+
+            let pid = packet_pid(&packet);
+            if pid == PAT_PID {
+                self.pmt_pid = parse_pat_pmt_pid(&packet).or(self.pmt_pid);
+                self.pat = Some(packet);
+                continue;
+            }
+            if Some(pid) == self.pmt_pid {
+                self.pmt = Some(packet);
+                continue;
+            }
+
+            if is_random_access_start(&packet) {
+                if let Some(gop) = self.current.take() {
+                    completed.push(gop);
+                }
+                self.current = Some(Gop { started_at: now, data: Vec::new() });
+            }
+
+            // Anything before the first keyframe can't be decoded
+            if let Some(gop) = self.current.as_mut() {
+                gop.data.extend_from_slice(&packet);
+            }
+        }
+
+        self.pending.drain(..offset);
+        completed
+    }
+
+    /// The GOP still being received
+    pub fn current(&self) -> Option<&Gop> {
+        self.current.as_ref()
+    }
+
+    /// PAT and PMT packets to put in front of flushed data
+    pub fn headers(&self) -> Vec<u8> {
+        let mut headers = Vec::new();
+        if let (Some(pat), Some(pmt)) = (&self.pat, &self.pmt) {
+            headers.extend_from_slice(pat);
+            headers.extend_from_slice(pmt);
+        }
+        headers
+    }
+}
+
+fn packet_pid(packet: &[u8]) -> u16 {
+    (((packet[1] & 0x1f) as u16) << 8) | packet[2] as u16
+}
+
+/// Offset of the payload, or None for packets without one
+fn payload_offset(packet: &[u8]) -> Option<usize> {
+    let adaptation_field_control = (packet[3] >> 4) & 0x3;
+    match adaptation_field_control {
+        1 => Some(4),
+        3 => Some(5 + packet[4] as usize).filter(|o| *o < TS_PACKET_SIZE),
+        _ => None,
+    }
+}
+
+fn is_random_access_start(packet: &[u8]) -> bool {
+    let payload_unit_start = packet[1] & 0x40 != 0;
+    let has_adaptation = (packet[3] >> 4) & 0x2 != 0;
+    payload_unit_start && has_adaptation && packet[4] > 0 && packet[5] & 0x40 != 0
+}
+
+/// PID of the first program's PMT, from a PAT packet
+fn parse_pat_pmt_pid(packet: &[u8]) -> Option<u16> {
+    let payload = &packet[payload_offset(packet)?..];
+    let section = payload.get(1 + *payload.first()? as usize..)?;
+    let section_length = ((((section.get(1)? & 0x0f) as usize) << 8) | *section.get(2)? as usize).min(section.len().saturating_sub(3));
+    // Program entries follow the 8 byte header and precede the 4 byte CRC
+    let entries = section.get(8..(3 + section_length).saturating_sub(4))?;
+
+    entries.chunks_exact(4).find_map(|entry| {
+        let program_number = ((entry[0] as u16) << 8) | entry[1] as u16;
+        (program_number != 0).then(|| (((entry[2] & 0x1f) as u16) << 8) | entry[3] as u16)
+    })
+}
+
+/// Newest GOPs within the buffer window, bounded in bytes
+#[derive(Debug)]
+pub struct GopRing {
+    gops: VecDeque<Gop>,
+    bytes: usize,
+    max_bytes: usize,
+    window: chrono::Duration,
+}
+
+impl GopRing {
+    pub fn new(max_bytes: usize, window: chrono::Duration) -> Self {
+        Self {
+            gops: VecDeque::new(),
+            bytes: 0,
+            max_bytes,
+            window,
+        }
+    }
+
+    /// Add a GOP, returning older GOPs pushed out by the memory bound that
+    /// are still inside the window (candidates for spilling). GOPs that
+    /// aged out of the window are dropped.
+    pub fn push(&mut self, gop: Gop, now: DateTime<Utc>) -> Vec<Gop> {
+        self.bytes += gop.data.len();
+        self.gops.push_back(gop);
+        self.expire(now);
+
+        let mut evicted = Vec::new();
+        // Always keep the newest GOP, even if it alone is over the bound
+        while self.bytes > self.max_bytes && self.gops.len() > 1 {
+            if let Some(oldest) = self.gops.pop_front() {
+                self.bytes -= oldest.data.len();
+                evicted.push(oldest);
+            }
+        }
+        evicted
+    }
+
+    pub fn expire(&mut self, now: DateTime<Utc>) {
+        while self.gops.front().map(|g| now - g.started_at > self.window).unwrap_or(false) {
+            if let Some(oldest) = self.gops.pop_front() {
+                self.bytes -= oldest.data.len();
+            }
+        }
+    }
+
+    pub fn gops(&self) -> impl Iterator<Item = &Gop> {
+        self.gops.iter()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.gops.clear();
+        self.bytes = 0;
+    }
+}
+
+struct BufferState {
+    splitter: TsGopSplitter,
+    ring: GopRing,
+    /// GOPs moved to disk, oldest first
+    spilled: VecDeque<(DateTime<Utc>, PathBuf)>,
+}
+
+/// What a flush wrote into the incident recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreIncidentFlush {
+    pub path: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub gop_count: usize,
+    pub size_bytes: u64,
+}
+
+/// Pre-incident buffer kept in memory. One ffmpeg encodes the camera to
+/// MPEG-TS on stdout; complete GOPs are kept in a size-bounded ring and,
+/// when allowed, the oldest spill to disk rather than being lost. Like most
+/// body cameras, the buffer holds video only.
+pub struct MemoryBuffer {
+    config: PreIncidentBufferConfig,
+    spill_dir: PathBuf,
+    state: Arc<Mutex<BufferState>>,
+    capture: Option<Child>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl MemoryBuffer {
+    pub fn new(config: PreIncidentBufferConfig, window_seconds: u64) -> Result<Self> {
+        let spill_dir = std::env::current_dir()?.join("buffer").join("spill");
+        let ring = GopRing::new(
+            config.max_memory_mb as usize * 1024 * 1024,
+            chrono::Duration::seconds(window_seconds as i64),
+        );
+
+        Ok(Self {
+            config,
+            spill_dir,
+            state: Arc::new(Mutex::new(BufferState {
+                splitter: TsGopSplitter::default(),
+                ring,
+                spilled: VecDeque::new(),
+            })),
+            capture: None,
+            reader: None,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Bytes currently held in RAM
+    pub fn memory_used(&self) -> usize {
+        self.state.lock().unwrap().ring.bytes()
+    }
+
+    /// The newest complete GOP with stream headers, decodable on its own
+    pub fn latest_gop(&self) -> Option<Vec<u8>> {
+        if self.capture.is_none() {
+            return None;
+        }
+
+        let state = self.state.lock().unwrap();
+        let gop = state.ring.gops().last()?;
+        let mut data = state.splitter.headers();
+        data.extend_from_slice(&gop.data);
+        Some(data)
+    }
+
+    pub async fn start(&mut self, quality: &VideoQualityConfig, source: &VideoSource) -> Result<()> {
+        if self.capture.is_some() {
+            return Ok(());
+        }
+
+        if self.config.spill_to_disk {
+            fs::create_dir_all(&self.spill_dir).await
+                .context("Failed to create buffer spill directory")?;
+        }
+        self.restore_spilled().await;
+
+        // One-second GOPs keep the buffer's granularity fine
+        let mut child = Command::new("ffmpeg")
+            .args(source.input_args(quality.fps, &quality.resolution))
+            .args(source.output_args(quality.fps, &quality.resolution))
+            .arg("-c:v").arg("libx264")
+            .arg("-preset").arg("ultrafast")
+            .arg("-b:v").arg(quality.bitrate.to_string())
+            .arg("-g").arg(quality.fps.to_string())
+            .arg("-an")
+            .arg("-f").arg("mpegts")
+            .arg("pipe:1")
+            .arg("-loglevel").arg("error")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start pre-incident buffer capture")?;
+
+        let mut stdout = child.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("Buffer capture has no stdout"))?;
+        let state = self.state.clone();
+        let spill_dir = self.config.spill_to_disk.then(|| self.spill_dir.clone());
+        let window = state.lock().unwrap().ring.window;
+
+        self.reader = Some(tokio::spawn(async move {
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                let read = match stdout.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => read,
+                };
+
+                let now = Utc::now();
+                let evicted = {
+                    let mut state = state.lock().unwrap();
+                    let completed = state.splitter.feed(&buf[..read], now);
+                    let mut evicted = Vec::new();
+                    for gop in completed {
+                        evicted.extend(state.ring.push(gop, now));
+                    }
+                    evicted
+                };
+
+                if let Some(spill_dir) = &spill_dir {
+                    Self::spill(&state, spill_dir, evicted, window, now).await;
+                }
+            }
+            tracing::warn!("Pre-incident buffer capture ended");
+        }));
+
+        self.capture = Some(child);
+        tracing::info!("Pre-incident buffer capturing to memory (up to {} MB)", self.config.max_memory_mb);
+        Ok(())
+    }
+
+    /// Write GOPs evicted from RAM to disk and drop spilled GOPs that have
+    /// left the window
+    async fn spill(
+        state: &Arc<Mutex<BufferState>>,
+        spill_dir: &Path,
+        evicted: Vec<Gop>,
+        window: chrono::Duration,
+        now: DateTime<Utc>,
+    ) {
+        for gop in evicted {
+            let path = spill_dir.join(format!("{}.ts", gop.started_at.timestamp_millis()));
+            match fs::write(&path, &gop.data).await {
+                Ok(()) => state.lock().unwrap().spilled.push_back((gop.started_at, path)),
+                Err(e) => tracing::warn!("Failed to spill buffer GOP to disk: {}", e),
+            }
+        }
+
+        let expired: Vec<PathBuf> = {
+            let mut state = state.lock().unwrap();
+            let mut expired = Vec::new();
+            while state.spilled.front().map(|(t, _)| now - *t > window).unwrap_or(false) {
+                if let Some((_, path)) = state.spilled.pop_front() {
+                    expired.push(path);
+                }
+            }
+            expired
+        };
+        for path in expired {
+            let _ = fs::remove_file(path).await;
+        }
+    }
+
+    /// Pick up GOPs left on disk by `persist` or by spilling before a
+    /// restart, dropping any that have aged out of the window
+    async fn restore_spilled(&self) {
+        let Ok(mut entries) = fs::read_dir(&self.spill_dir).await else {
+            return;
+        };
+
+        let window = self.state.lock().unwrap().ring.window;
+        let now = Utc::now();
+        let mut restored = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let started_at = path.file_stem()
+                .and_then(|stem| stem.to_str()?.parse::<i64>().ok())
+                .and_then(|ms| DateTime::<Utc>::from_timestamp_millis(ms));
+
+            match started_at {
+                Some(started_at) if now - started_at <= window => restored.push((started_at, path)),
+                _ => {
+                    let _ = fs::remove_file(&path).await;
+                }
+            }
+        }
+
+        if restored.is_empty() {
+            return;
+        }
+        restored.sort();
+
+        let mut state = self.state.lock().unwrap();
+        let known: Vec<PathBuf> = state.spilled.iter().map(|(_, p)| p.clone()).collect();
+        restored.retain(|(_, path)| !known.contains(path));
+        tracing::info!("Restored {} buffered GOPs from disk", restored.len());
+        for spilled in restored.into_iter().rev() {
+            state.spilled.push_front(spilled);
+        }
+    }
+
+    /// Stop capturing and write the GOPs held in RAM to disk so a planned
+    /// restart keeps them. RAM contents are lost on a crash; only GOPs
+    /// already spilled survive that.
+    pub async fn persist(&mut self) -> Result<()> {
+        self.stop().await;
+
+        let gops: Vec<Gop> = {
+            let mut state = self.state.lock().unwrap();
+            let mut gops: Vec<Gop> = state.ring.gops().cloned().collect();
+            gops.extend(state.splitter.current().cloned());
+            state.ring.clear();
+            state.splitter = TsGopSplitter::default();
+            gops
+        };
+
+        if gops.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.spill_dir).await
+            .context("Failed to create buffer spill directory")?;
+        for gop in &gops {
+            let path = self.spill_dir.join(format!("{}.ts", gop.started_at.timestamp_millis()));
+            fs::write(&path, &gop.data).await?;
+            self.state.lock().unwrap().spilled.push_back((gop.started_at, path));
+        }
+
+        tracing::info!("Persisted {} buffered GOPs for restart", gops.len());
+        Ok(())
+    }
+
+    pub async fn stop(&mut self) {
+        if let Some(mut capture) = self.capture.take() {
+            let _ = capture.kill().await;
+            let _ = capture.wait().await;
+        }
+        if let Some(reader) = self.reader.take() {
+            reader.abort();
+        }
+    }
+
+    /// Stop capturing (so the recorder can open the camera) and write the
+    /// buffered video from the last `pre_roll`, spilled GOPs first, to
+    /// `path`. Returns None when the buffer is empty.
+    pub async fn flush_to(&mut self, path: &Path, pre_roll: chrono::Duration) -> Result<Option<PreIncidentFlush>> {
+        self.stop().await;
+
+        let (headers, gops, spilled) = {
+            let mut state = self.state.lock().unwrap();
+            state.ring.expire(Utc::now());
+            let mut gops: Vec<Gop> = state.ring.gops().cloned().collect();
+            // The GOP in progress is complete up to the moment of the trigger
+            gops.extend(state.splitter.current().cloned());
+            let spilled: Vec<(DateTime<Utc>, PathBuf)> = state.spilled.drain(..).collect();
+            let headers = state.splitter.headers();
+            state.ring.clear();
+            state.splitter = TsGopSplitter::default();
+            (headers, gops, spilled)
+        };
+
+        let cutoff = Utc::now() - pre_roll;
+        let gops: Vec<Gop> = gops.into_iter().filter(|g| g.started_at >= cutoff).collect();
+        let (spilled, too_old): (Vec<_>, Vec<_>) = spilled.into_iter().partition(|(t, _)| *t >= cutoff);
+        for (_, spill_path) in too_old {
+            let _ = fs::remove_file(spill_path).await;
+        }
+
+        if gops.is_empty() && spilled.is_empty() {
+            return Ok(None);
+        }
+
+        let started_at = spilled.first().map(|(t, _)| *t)
+            .or_else(|| gops.first().map(|g| g.started_at))
+            .unwrap_or_else(Utc::now);
+
+        let mut file = fs::File::create(path).await
+            .context("Failed to create pre-incident file")?;
+        file.write_all(&headers).await?;
+        for (_, spill_path) in &spilled {
+            match fs::read(spill_path).await {
+                Ok(data) => file.write_all(&data).await?,
+                Err(e) => tracing::warn!("Lost spilled buffer GOP {}: {}", spill_path.display(), e),
+            }
+            let _ = fs::remove_file(spill_path).await;
+        }
+        for gop in &gops {
+            file.write_all(&gop.data).await?;
+        }
+        file.flush().await?;
+
+        let size_bytes = file.metadata().await?.len();
+        tracing::info!("Flushed {} buffered GOPs ({} spilled) to {}", gops.len() + spilled.len(), spilled.len(), path.display());
+
+        Ok(Some(PreIncidentFlush {
+            path: path.to_path_buf(),
+            started_at,
+            gop_count: gops.len() + spilled.len(),
+            size_bytes,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(pid: u16, keyframe: bool, fill: u8) -> Vec<u8> {
+        let mut packet = vec![fill; TS_PACKET_SIZE];
+        packet[0] = TS_SYNC_BYTE;
+        packet[1] = ((pid >> 8) as u8 & 0x1f) | if keyframe { 0x40 } else { 0 };
+        packet[2] = pid as u8;
+        if keyframe {
+            packet[3] = 0x30; // adaptation field and payload
+            packet[4] = 7;
+            packet[5] = 0x40; // random access indicator
+        } else {
+            packet[3] = 0x10;
+        }
+        packet
+    }
+
+    fn pat(pmt_pid: u16) -> Vec<u8> {
+        let mut packet = vec![0xff; TS_PACKET_SIZE];
+        packet[..4].copy_from_slice(&[TS_SYNC_BYTE, 0x40, 0x00, 0x10]);
+        let section = [
+            0x00, // pointer field
+            0x00, 0xb0, 0x0d, // table id, section length 13
+            0x00, 0x01, 0xc1, 0x00, 0x00, // transport stream id, version, section numbers
+            0x00, 0x01, 0xe0 | (pmt_pid >> 8) as u8, pmt_pid as u8, // program 1
+            0x00, 0x00, 0x00, 0x00, // CRC
+        ];
+        packet[4..4 + section.len()].copy_from_slice(&section);
+        packet
+    }
+
+    #[test]
+    fn test_pat_gives_pmt_pid() {
+        assert_eq!(parse_pat_pmt_pid(&pat(0x1000)), Some(0x1000));
+    }
+
+    #[test]
+    fn test_splitter_cuts_at_keyframes() {
+        let now = Utc::now();
+        let mut splitter = TsGopSplitter::default();
+        let mut stream = Vec::new();
+        stream.extend(pat(0x1000));
+        stream.extend(packet(0x1000, false, 1));
+        stream.extend(packet(0x100, false, 2)); // before any keyframe, dropped
+        stream.extend(packet(0x100, true, 3));
+        stream.extend(packet(0x100, false, 4));
+        stream.extend(packet(0x100, true, 5));
+
+        // Split mid-packet to exercise buffering
+        let mut gops = splitter.feed(&stream[..500], now);
+        gops.extend(splitter.feed(&stream[500..], now));
+
+        assert_eq!(gops.len(), 1);
+        assert_eq!(gops[0].data.len(), 2 * TS_PACKET_SIZE);
+        assert_eq!(gops[0].data[TS_PACKET_SIZE + 4], 4);
+        assert_eq!(splitter.current().unwrap().data.len(), TS_PACKET_SIZE);
+        assert_eq!(splitter.headers().len(), 2 * TS_PACKET_SIZE);
+    }
+
+    #[test]
+    fn test_ring_bounds_memory_and_window() {
+        let start = Utc::now();
+        let gop = |secs: i64| Gop { started_at: start + chrono::Duration::seconds(secs), data: vec![0; 100] };
+        let mut ring = GopRing::new(250, chrono::Duration::seconds(30));
+
+        assert!(ring.push(gop(0), start).is_empty());
+        assert!(ring.push(gop(1), start).is_empty());
+        // Over the byte bound: the oldest is handed back for spilling
+        let evicted = ring.push(gop(2), start + chrono::Duration::seconds(2));
+        assert_eq!(evicted, vec![gop(0)]);
+        assert_eq!(ring.bytes(), 200);
+
+        // Outside the window: dropped, not spilled
+        let evicted = ring.push(gop(40), start + chrono::Duration::seconds(40));
+        assert!(evicted.is_empty());
+        assert_eq!(ring.gops().count(), 1);
+    }
+}
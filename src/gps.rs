@@ -1,5 +1,7 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use chrono::{DateTime, Utc};
@@ -11,6 +13,8 @@ pub struct GpsLocation {
     pub longitude: f64,
     pub altitude: Option<f64>,
     pub accuracy: Option<f64>,
+    /// Horizontal dilution of precision, when the receiver reports it
+    pub hdop: Option<f64>,
     pub speed: Option<f64>,
     pub heading: Option<f64>,
     pub timestamp: DateTime<Utc>,
@@ -23,10 +27,178 @@ impl GpsLocation {
     }
 }
 
+/// Great-circle distance in metres
+pub fn distance_m(a: &GpsLocation, b: &GpsLocation) -> f64 {
+    const EARTH_RADIUS_M: f64 = 6_371_000.0;
+    let (lat1, lat2) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.longitude - a.longitude).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+/// Limits a fix has to meet before it replaces the current one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpsFilterConfig {
+    /// Fixes with a higher horizontal dilution of precision are dropped
+    pub max_hdop: f64,
+    /// Fixes reporting a worse accuracy than this, in metres, are dropped
+    pub max_accuracy_m: f64,
+    /// A fix implying faster travel than this since the last one is a jump
+    pub max_speed_mps: f64,
+    /// Jumps in a row after which the device is taken to have really moved,
+    /// e.g. on leaving a tunnel
+    pub max_rejected_jumps: u32,
+    /// Fixes the position is the median of
+    pub median_window: usize,
+    /// A fix older than this is flagged stale
+    pub stale_after_seconds: u64,
+}
+
+impl Default for GpsFilterConfig {
+    fn default() -> Self {
+        Self {
+            max_hdop: 5.0,
+            max_accuracy_m: 50.0,
+            max_speed_mps: 70.0,
+            max_rejected_jumps: 3,
+            median_window: 3,
+            stale_after_seconds: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixQuality {
+    Good,
+    /// Accepted, but using more than half the allowed HDOP or accuracy
+    Degraded,
+    /// No new fix for longer than `stale_after_seconds`
+    Stale,
+}
+
+impl FixQuality {
+    pub fn assess(fix: &GpsLocation, config: &GpsFilterConfig, now: DateTime<Utc>) -> Self {
+        if (now - fix.timestamp).num_seconds() > config.stale_after_seconds as i64 {
+            FixQuality::Stale
+        } else if fix.hdop.is_some_and(|hdop| hdop > config.max_hdop / 2.0)
+            || fix.accuracy.is_some_and(|accuracy| accuracy > config.max_accuracy_m / 2.0)
+        {
+            FixQuality::Degraded
+        } else {
+            FixQuality::Good
+        }
+    }
+}
+
+/// Drops imprecise fixes and jumps, and smooths what's left with a median
+/// over the last few fixes
+#[derive(Debug)]
+pub struct FixFilter {
+    config: GpsFilterConfig,
+    recent: VecDeque<GpsLocation>,
+    rejected_jumps: u32,
+}
+
+impl FixFilter {
+    pub fn new(config: GpsFilterConfig) -> Self {
+        Self { config, recent: VecDeque::new(), rejected_jumps: 0 }
+    }
+
+    /// The smoothed fix to use, or None if `fix` was rejected
+    pub fn accept(&mut self, fix: GpsLocation) -> Option<GpsLocation> {
+        if !fix.is_valid() {
+            return None;
+        }
+        if fix.hdop.is_some_and(|hdop| hdop > self.config.max_hdop)
+            || fix.accuracy.is_some_and(|accuracy| accuracy > self.config.max_accuracy_m)
+        {
+            tracing::debug!("Dropping imprecise GPS fix (hdop {:?}, accuracy {:?})", fix.hdop, fix.accuracy);
+            return None;
+        }
+
+        if let Some(last) = self.recent.back() {
+            let seconds = (fix.timestamp - last.timestamp).num_milliseconds() as f64 / 1000.0;
+            if distance_m(last, &fix) / seconds.max(1.0) > self.config.max_speed_mps {
+                self.rejected_jumps += 1;
+                if self.rejected_jumps <= self.config.max_rejected_jumps {
+                    tracing::debug!("Dropping GPS fix that jumped {:.0} m", distance_m(last, &fix));
+                    return None;
+                }
+                // Smoothing across the jump would place the device in between
+                self.recent.clear();
+            }
+        }
+        self.rejected_jumps = 0;
+
+        self.recent.push_back(fix.clone());
+        while self.recent.len() > self.config.median_window.max(1) {
+            self.recent.pop_front();
+        }
+        Some(GpsLocation {
+            latitude: median(self.recent.iter().map(|fix| fix.latitude)),
+            longitude: median(self.recent.iter().map(|fix| fix.longitude)),
+            ..fix
+        })
+    }
+}
+
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// The position in `gpspipe -w` output, with the HDOP of the latest SKY
+/// report if there was one
+pub fn parse_gpsd_reports(data: &str) -> Option<GpsLocation> {
+    let mut hdop = None;
+    let mut location = None;
+    for line in data.lines() {
+        let Ok(report) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        match report.get("class").and_then(|class| class.as_str()) {
+            Some("SKY") => hdop = report.get("hdop").and_then(|v| v.as_f64()).or(hdop),
+            Some("TPV") => {
+                // Modes 2 and 3 are 2D and 3D fixes
+                if report.get("mode").and_then(|v| v.as_u64()).unwrap_or(0) < 2 {
+                    continue;
+                }
+                let (Some(lat), Some(lon)) = (report.get("lat").and_then(|v| v.as_f64()), report.get("lon").and_then(|v| v.as_f64())) else {
+                    continue;
+                };
+                location = Some(GpsLocation {
+                    latitude: lat,
+                    longitude: lon,
+                    altitude: report.get("alt").and_then(|v| v.as_f64()),
+                    accuracy: report.get("eph").or_else(|| report.get("epx")).and_then(|v| v.as_f64()),
+                    hdop: None,
+                    speed: report.get("speed").and_then(|v| v.as_f64()),
+                    heading: report.get("track").and_then(|v| v.as_f64()),
+                    timestamp: Utc::now(),
+                    satellites: None,
+                });
+            }
+            _ => {}
+        }
+    }
+    location.map(|location| GpsLocation { hdop, ..location })
+}
+
 pub struct GpsManager {
     enabled: bool,
     last_location: Arc<Mutex<Option<GpsLocation>>>,
     update_interval: std::time::Duration,
+    filter: GpsFilterConfig,
+    /// Set once a simulated fix has been injected; real fixes are ignored
+    simulated: Arc<AtomicBool>,
 }
 
 impl GpsManager {
@@ -35,9 +207,16 @@ impl GpsManager {
             enabled,
             last_location: Arc::new(Mutex::new(None)),
             update_interval: std::time::Duration::from_secs(5),
+            filter: GpsFilterConfig::default(),
+            simulated: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    pub fn with_filter(mut self, filter: GpsFilterConfig) -> Self {
+        self.filter = filter;
+        self
+    }
+
     pub async fn start_monitoring(&self) -> Result<()> {
         if !self.enabled {
             return Ok(());
@@ -45,16 +224,28 @@ impl GpsManager {
 
         let last_location = self.last_location.clone();
         let update_interval = self.update_interval;
+        let simulated = self.simulated.clone();
+        let mut filter = FixFilter::new(self.filter.clone());
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(update_interval);
             
             loop {
                 interval.tick().await;
+
+                if crate::simulation::faults::is_active(crate::simulation::faults::Fault::GpsLoss) {
+                    *last_location.lock().await = None;
+                    continue;
+                }
+                if simulated.load(Ordering::Relaxed) {
+                    continue;
+                }
                 
                 match Self::get_current_location().await {
                     Ok(location) => {
-                        *last_location.lock().await = Some(location);
+                        if let Some(location) = filter.accept(location) {
+                            *last_location.lock().await = Some(location);
+                        }
                     }
                     Err(e) => {
                         tracing::warn!("Failed to get GPS location: {}", e);
@@ -66,10 +257,31 @@ impl GpsManager {
         Ok(())
     }
 
+    /// Latest fix, shared with background monitors
+    pub fn shared_location(&self) -> Arc<Mutex<Option<GpsLocation>>> {
+        self.last_location.clone()
+    }
+
+    /// Replace the current fix with a scripted one, e.g. from a simulation
+    /// scenario. Real fixes stay suppressed from then on.
+    pub async fn set_simulated_location(&self, location: GpsLocation) {
+        self.simulated.store(true, Ordering::Relaxed);
+        if !crate::simulation::faults::is_active(crate::simulation::faults::Fault::GpsLoss) {
+            *self.last_location.lock().await = Some(location);
+        }
+    }
+
     pub async fn get_location(&self) -> Option<GpsLocation> {
+        if crate::simulation::faults::is_active(crate::simulation::faults::Fault::GpsLoss) {
+            return None;
+        }
         self.last_location.lock().await.clone()
     }
 
+    pub fn fix_quality(&self, fix: &GpsLocation) -> FixQuality {
+        FixQuality::assess(fix, &self.filter, crate::simulation::faults::now())
+    }
+
     async fn get_current_location() -> Result<GpsLocation> {
         // Try multiple methods to get GPS location
         
@@ -93,10 +305,11 @@ impl GpsManager {
     }
 
     async fn get_location_from_gpsd() -> Result<GpsLocation> {
+        // A few reports, so a SKY report with the HDOP comes with the TPV
         let output = Command::new("gpspipe")
             .arg("-w")
             .arg("-n")
-            .arg("1")
+            .arg("8")
             .output()
             .await
             .context("GPSD not available")?;
@@ -105,26 +318,8 @@ impl GpsManager {
             return Err(anyhow::anyhow!("GPSD command failed"));
         }
 
-        let data = String::from_utf8_lossy(&output.stdout);
-        
-        // Parse GPSD JSON response
-        for line in data.lines() {
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-                if let Some(tpv) = json.get("TPV") {
-                    if let (Some(lat), Some(lon)) = (tpv.get("lat"), tpv.get("lon")) {
-                        return Ok(GpsLocation {
-                            latitude: lat.as_f64().unwrap_or(0.0),
-                            longitude: lon.as_f64().unwrap_or(0.0),
-                            altitude: tpv.get("alt").and_then(|v| v.as_f64()),
-                            accuracy: tpv.get("epx").and_then(|v| v.as_f64()),
-                            speed: tpv.get("speed").and_then(|v| v.as_f64()),
-                            heading: tpv.get("track").and_then(|v| v.as_f64()),
-                            timestamp: Utc::now(),
-                            satellites: tpv.get("satellites").and_then(|v| v.as_u64().map(|v| v as u32)),
-                        });
-                    }
-                }
-            }
+        if let Some(location) = parse_gpsd_reports(&String::from_utf8_lossy(&output.stdout)) {
+            return Ok(location);
         }
 
         Err(anyhow::anyhow!("No GPS data from GPSD"))
@@ -173,6 +368,7 @@ impl GpsManager {
                     longitude: lon.as_f64().unwrap_or(0.0),
                     altitude: json.get("altitude").and_then(|v| v.as_f64()),
                     accuracy: json.get("accuracy").and_then(|v| v.as_f64()),
+                    hdop: None,
                     speed: json.get("speed").and_then(|v| v.as_f64()),
                     heading: json.get("heading").and_then(|v| v.as_f64()),
                     timestamp: Utc::now(),
@@ -203,6 +399,7 @@ impl GpsManager {
                     longitude: lon,
                     altitude: None,
                     accuracy: response.get("accuracy").and_then(|v| v.as_f64()),
+                    hdop: None,
                     speed: None,
                     heading: None,
                     timestamp: Utc::now(),
@@ -232,6 +429,7 @@ mod tests {
             longitude: -122.4194,
             altitude: Some(50.0),
             accuracy: Some(5.0),
+            hdop: Some(1.2),
             speed: Some(10.0),
             heading: Some(45.0),
             timestamp: Utc::now(),
@@ -248,6 +446,7 @@ mod tests {
             longitude: -122.4194,
             altitude: None,
             accuracy: None,
+            hdop: None,
             speed: None,
             heading: None,
             timestamp: Utc::now(),
@@ -256,4 +455,60 @@ mod tests {
 
         assert!(!location.is_valid());
     }
+
+    fn fix_at(latitude: f64, longitude: f64, seconds: i64) -> GpsLocation {
+        GpsLocation {
+            latitude,
+            longitude,
+            altitude: None,
+            accuracy: Some(5.0),
+            hdop: Some(1.0),
+            speed: None,
+            heading: None,
+            timestamp: DateTime::<Utc>::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            satellites: Some(8),
+        }
+    }
+
+    #[test]
+    fn test_filter_drops_imprecise_fixes_and_jumps() {
+        let mut filter = FixFilter::new(GpsFilterConfig::default());
+        assert!(filter.accept(fix_at(22.2800, 114.1600, 0)).is_some());
+
+        let mut imprecise = fix_at(22.2801, 114.1600, 5);
+        imprecise.hdop = Some(8.0);
+        assert!(filter.accept(imprecise).is_none());
+
+        // About 11 km in 5 s
+        assert!(filter.accept(fix_at(22.3800, 114.1600, 10)).is_none());
+
+        filter.accept(fix_at(22.2802, 114.1600, 15)).unwrap();
+        let smoothed = filter.accept(fix_at(22.2806, 114.1600, 20)).unwrap();
+        assert_eq!(smoothed.latitude, 22.2802);
+
+        // Jumps that keep coming are a real move
+        for seconds in [25, 30, 35] {
+            assert!(filter.accept(fix_at(23.0000, 114.1600, seconds)).is_none());
+        }
+        assert_eq!(filter.accept(fix_at(23.0000, 114.1600, 40)).unwrap().latitude, 23.0);
+    }
+
+    #[test]
+    fn test_fix_quality_and_gpsd_reports() {
+        let config = GpsFilterConfig::default();
+        let fix = fix_at(22.28, 114.16, 0);
+        assert_eq!(FixQuality::assess(&fix, &config, fix.timestamp), FixQuality::Good);
+        assert_eq!(FixQuality::assess(&fix, &config, fix.timestamp + chrono::Duration::seconds(60)), FixQuality::Stale);
+        let degraded = GpsLocation { hdop: Some(3.0), ..fix };
+        assert_eq!(FixQuality::assess(&degraded, &config, degraded.timestamp), FixQuality::Degraded);
+
+        let reports = r#"{"class":"VERSION","release":"3.22"}
+{"class":"TPV","mode":1}
+{"class":"SKY","hdop":1.4}
+{"class":"TPV","mode":3,"lat":22.28,"lon":114.16,"alt":40.0,"eph":6.5}"#;
+        let location = parse_gpsd_reports(reports).unwrap();
+        assert_eq!(location.hdop, Some(1.4));
+        assert_eq!(location.accuracy, Some(6.5));
+        assert!(parse_gpsd_reports(r#"{"class":"TPV","mode":1}"#).is_none());
+    }
 }
\ No newline at end of file
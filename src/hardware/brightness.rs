@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Dims the display and LEDs to save battery: at night, in the dark, and
+/// when the battery runs low. The lowest level that applies wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimmingConfig {
+    pub enabled: bool,
+    /// Local hours of the night schedule; it may wrap past midnight
+    pub night_start_hour: u32,
+    pub night_end_hour: u32,
+    pub night_percent: u8,
+    /// Used while the camera's light sensing reports night
+    pub dark_percent: u8,
+    /// Battery saver starts at this charge, unless charging
+    pub battery_saver_threshold_percent: f32,
+    pub battery_saver_percent: u8,
+    /// Never dim below this, so the device can still be read
+    pub min_percent: u8,
+}
+
+impl Default for DimmingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            night_start_hour: 22,
+            night_end_hour: 6,
+            night_percent: 50,
+            dark_percent: 35,
+            battery_saver_threshold_percent: 20.0,
+            battery_saver_percent: 25,
+            min_percent: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DimReason {
+    Full,
+    Schedule,
+    AmbientDark,
+    BatterySaver,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BrightnessLevel {
+    pub percent: u8,
+    pub reason: DimReason,
+}
+
+/// Levels in effect, as reported in diagnostics. None where the display
+/// or LEDs are disabled or haven't been set yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrightnessStatus {
+    pub display_percent: Option<u8>,
+    pub led_percent: Option<u8>,
+    pub target: BrightnessLevel,
+}
+
+/// What the dimming decision is based on
+#[derive(Debug, Clone, Copy)]
+pub struct DimmingInputs {
+    pub local_hour: u32,
+    pub dark: bool,
+    pub battery_percent: Option<f32>,
+    pub charging: bool,
+}
+
+impl DimmingConfig {
+    fn is_night(&self, hour: u32) -> bool {
+        if self.night_start_hour <= self.night_end_hour {
+            (self.night_start_hour..self.night_end_hour).contains(&hour)
+        } else {
+            hour >= self.night_start_hour || hour < self.night_end_hour
+        }
+    }
+
+    /// Brightness for the display and LEDs. `full` is the configured
+    /// brightness, and a manual override replaces the schedule entirely.
+    pub fn target(&self, full: u8, inputs: DimmingInputs, manual: Option<u8>) -> BrightnessLevel {
+        if let Some(percent) = manual {
+            return BrightnessLevel { percent: percent.min(100), reason: DimReason::Manual };
+        }
+
+        let full = full.min(100);
+        let mut level = BrightnessLevel { percent: full, reason: DimReason::Full };
+        if !self.enabled {
+            return level;
+        }
+
+        let battery_saver = !inputs.charging
+            && inputs.battery_percent.is_some_and(|battery| battery <= self.battery_saver_threshold_percent);
+        let caps = [
+            (self.is_night(inputs.local_hour), self.night_percent, DimReason::Schedule),
+            (inputs.dark, self.dark_percent, DimReason::AmbientDark),
+            (battery_saver, self.battery_saver_percent, DimReason::BatterySaver),
+        ];
+        for (applies, percent, reason) in caps {
+            if applies && percent < level.percent {
+                level = BrightnessLevel { percent, reason };
+            }
+        }
+        level.percent = level.percent.max(self.min_percent.min(full));
+        level
+    }
+}
+
+/// Write `percent` to a sysfs brightness directory, e.g. a backlight or an
+/// LED class device, scaled to its `max_brightness`
+pub async fn write_sysfs_brightness(dir: &Path, percent: u8) -> Result<()> {
+    let max: u32 = tokio::fs::read_to_string(dir.join("max_brightness")).await
+        .with_context(|| format!("No brightness control at {}", dir.display()))?
+        .trim()
+        .parse()
+        .context("Invalid max_brightness")?;
+    let value = (max as u64 * percent.min(100) as u64 + 50) / 100;
+    tokio::fs::write(dir.join("brightness"), value.to_string()).await
+        .with_context(|| format!("Failed to set brightness at {}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lowest_applicable_level_wins() {
+        let config = DimmingConfig::default();
+        let day = DimmingInputs { local_hour: 12, dark: false, battery_percent: Some(80.0), charging: false };
+        assert_eq!(config.target(80, day, None), BrightnessLevel { percent: 80, reason: DimReason::Full });
+
+        let night = DimmingInputs { local_hour: 23, ..day };
+        assert_eq!(config.target(80, night, None), BrightnessLevel { percent: 50, reason: DimReason::Schedule });
+        assert_eq!(config.target(80, DimmingInputs { dark: true, ..night }, None).reason, DimReason::AmbientDark);
+
+        let low = DimmingInputs { battery_percent: Some(15.0), ..night };
+        assert_eq!(config.target(80, low, None), BrightnessLevel { percent: 25, reason: DimReason::BatterySaver });
+        assert_eq!(config.target(80, DimmingInputs { charging: true, ..low }, None).reason, DimReason::Schedule);
+
+        assert_eq!(config.target(80, low, Some(100)), BrightnessLevel { percent: 100, reason: DimReason::Manual });
+        assert!(!config.is_night(6) && config.is_night(0));
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Duration};
+
+use super::HardwareInterface;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuzzerConfig {
+    pub enabled: bool,
+    pub gpio_pin: Option<u32>,
+    pub pwm_path: Option<String>,
+    pub patterns: Vec<TonePattern>,
+}
+
+impl Default for BuzzerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gpio_pin: None,
+            pwm_path: None,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Sequence of tones played for a single event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TonePattern {
+    pub name: String,
+    pub tones: Vec<Tone>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tone {
+    /// Ignored by plain GPIO buzzers that can only switch on and off
+    pub frequency_hz: Option<u32>,
+    pub duration_ms: u64,
+    pub pause_ms: u64,
+}
+
+impl Tone {
+    pub fn new(frequency_hz: u32, duration_ms: u64, pause_ms: u64) -> Self {
+        Self {
+            frequency_hz: Some(frequency_hz),
+            duration_ms,
+            pause_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneEvent {
+    RecordStart,
+    RecordStop,
+    LowBattery,
+    Countdown,
+    CountdownFinal,
+    Error,
+}
+
+impl ToneEvent {
+    pub fn pattern_name(&self) -> &'static str {
+        match self {
+            ToneEvent::RecordStart => "record_start",
+            ToneEvent::RecordStop => "record_stop",
+            ToneEvent::LowBattery => "low_battery",
+            ToneEvent::Countdown => "countdown",
+            ToneEvent::CountdownFinal => "countdown_final",
+            ToneEvent::Error => "error",
+        }
+    }
+
+    fn default_tones(&self) -> Vec<Tone> {
+        match self {
+            ToneEvent::RecordStart => vec![Tone::new(2000, 80, 60), Tone::new(2500, 80, 0)],
+            ToneEvent::RecordStop => vec![Tone::new(2500, 80, 60), Tone::new(2000, 80, 0)],
+            ToneEvent::LowBattery => vec![Tone::new(1500, 200, 200); 3],
+            ToneEvent::Countdown => vec![Tone::new(1800, 100, 0)],
+            ToneEvent::CountdownFinal => vec![Tone::new(2400, 500, 0)],
+            ToneEvent::Error => vec![Tone::new(800, 400, 100), Tone::new(600, 600, 0)],
+        }
+    }
+}
+
+/// Plays configurable tone sequences on the device buzzer
+pub struct BuzzerController {
+    config: BuzzerConfig,
+}
+
+impl BuzzerController {
+    pub fn new(config: BuzzerConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn tones_for(&self, event: ToneEvent) -> Vec<Tone> {
+        self.config
+            .patterns
+            .iter()
+            .find(|pattern| pattern.name == event.pattern_name())
+            .map(|pattern| pattern.tones.clone())
+            .unwrap_or_else(|| event.default_tones())
+    }
+
+    pub async fn play_event(&self, hardware: &dyn HardwareInterface, event: ToneEvent) -> Result<()> {
+        let tones = self.tones_for(event);
+        self.play_sequence(hardware, &tones).await
+    }
+
+    pub async fn play_sequence(&self, hardware: &dyn HardwareInterface, tones: &[Tone]) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        for tone in tones {
+            hardware.start_tone(tone.frequency_hz).await?;
+            sleep(Duration::from_millis(tone.duration_ms)).await;
+            hardware.stop_tone().await?;
+
+            if tone.pause_ms > 0 {
+                sleep(Duration::from_millis(tone.pause_ms)).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn beep(&self, hardware: &dyn HardwareInterface, frequency_hz: Option<u32>, duration_ms: u64) -> Result<()> {
+        let tone = Tone {
+            frequency_hz,
+            duration_ms,
+            pause_ms: 0,
+        };
+        self.play_sequence(hardware, &[tone]).await
+    }
+
+    /// One short beep per remaining second followed by a long final tone
+    pub async fn countdown(&self, hardware: &dyn HardwareInterface, seconds: u32) -> Result<()> {
+        let tick = self.tones_for(ToneEvent::Countdown);
+        let tick_ms: u64 = tick.iter().map(|t| t.duration_ms + t.pause_ms).sum();
+
+        for _ in 0..seconds {
+            self.play_sequence(hardware, &tick).await?;
+            sleep(Duration::from_millis(1000u64.saturating_sub(tick_ms))).await;
+        }
+
+        self.play_event(hardware, ToneEvent::CountdownFinal).await
+    }
+}
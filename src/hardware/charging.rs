@@ -0,0 +1,277 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::power_supply::BatteryInfo;
+use super::HardwareEvent;
+
+/// Readings in a row with almost no current while charging before it's an anomaly
+const STALLED_READINGS: u32 = 3;
+/// Daily capacity samples kept for the trend
+const MAX_CAPACITY_SAMPLES: usize = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChargingSafetyConfig {
+    pub enabled: bool,
+    /// Fast charging is refused above this battery temperature
+    pub fast_charge_max_temp_c: f32,
+    /// and allowed again once the battery has cooled below this
+    pub fast_charge_resume_temp_c: f32,
+    /// Charging above this temperature is reported as overheating
+    pub max_charge_temp_c: f32,
+    /// Charge current above this is an anomaly
+    pub max_charge_current_ma: f32,
+    /// Charging with less current than this, short of full, is an anomaly
+    pub min_charge_current_ma: f32,
+    /// Full capacity lost within `capacity_trend_days` that suggests a
+    /// swelling battery
+    pub capacity_drop_percent: f32,
+    pub capacity_trend_days: u32,
+}
+
+impl Default for ChargingSafetyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fast_charge_max_temp_c: 40.0,
+            fast_charge_resume_temp_c: 37.0,
+            max_charge_temp_c: 45.0,
+            max_charge_current_ma: 3000.0,
+            min_charge_current_ma: 20.0,
+            capacity_drop_percent: 10.0,
+            capacity_trend_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacitySample {
+    pub date: DateTime<Utc>,
+    pub full_capacity_mah: f32,
+}
+
+/// What one battery reading means for charging
+#[derive(Debug, Default)]
+pub struct ChargingCheck {
+    /// Newly raised problems
+    pub events: Vec<HardwareEvent>,
+    /// Fast charging should be switched to this
+    pub fast_charge: Option<bool>,
+    /// A capacity sample was taken and the history should be saved
+    pub history_changed: bool,
+}
+
+/// Watches battery readings for unsafe charging: heat, odd charge current
+/// and full capacity falling faster than normal wear
+#[derive(Debug)]
+pub struct ChargingSafetyMonitor {
+    config: ChargingSafetyConfig,
+    path: PathBuf,
+    history: Vec<CapacitySample>,
+    fast_charge_allowed: bool,
+    overheating: bool,
+    current_anomaly: bool,
+    stalled_readings: u32,
+    capacity_drop: Option<f32>,
+}
+
+impl ChargingSafetyMonitor {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("battery_capacity.json")
+    }
+
+    pub async fn load(config: ChargingSafetyConfig) -> Self {
+        Self::load_from(Self::default_path(), config).await
+    }
+
+    pub async fn load_from(path: PathBuf, config: ChargingSafetyConfig) -> Self {
+        let history = tokio::fs::read_to_string(&path).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            config,
+            path,
+            history,
+            fast_charge_allowed: true,
+            overheating: false,
+            current_anomaly: false,
+            stalled_readings: 0,
+            capacity_drop: None,
+        }
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_string_pretty(&self.history)?).await?;
+        Ok(())
+    }
+
+    pub fn observe(&mut self, info: &BatteryInfo, now: DateTime<Utc>) -> ChargingCheck {
+        let mut check = ChargingCheck::default();
+        if !info.present {
+            return check;
+        }
+
+        if let Some(temp) = info.temperature_c {
+            let allowed = if self.fast_charge_allowed {
+                temp <= self.config.fast_charge_max_temp_c
+            } else {
+                temp < self.config.fast_charge_resume_temp_c
+            };
+            if allowed != self.fast_charge_allowed {
+                self.fast_charge_allowed = allowed;
+                check.fast_charge = Some(allowed);
+            }
+
+            let overheating = info.charging && temp > self.config.max_charge_temp_c;
+            if overheating && !self.overheating {
+                check.events.push(HardwareEvent::BatteryOverheating { temp });
+            }
+            self.overheating = overheating;
+        }
+
+        let anomaly = match info.current_ma.filter(|_| info.charging) {
+            // Drivers disagree on the sign of charge current
+            Some(current) if current.abs() > self.config.max_charge_current_ma => Some(current),
+            Some(current) if current.abs() < self.config.min_charge_current_ma
+                && info.capacity_percent.is_some_and(|capacity| capacity < 95.0) =>
+            {
+                self.stalled_readings += 1;
+                (self.stalled_readings >= STALLED_READINGS).then_some(current)
+            }
+            _ => {
+                self.stalled_readings = 0;
+                None
+            }
+        };
+        if let Some(current_ma) = anomaly {
+            if !self.current_anomaly {
+                check.events.push(HardwareEvent::ChargeCurrentAnomaly { current_ma });
+            }
+        }
+        self.current_anomaly = anomaly.is_some();
+
+        if let Some(full) = info.full_capacity_mah.filter(|full| *full > 0.0) {
+            let sampled_today = self.history.last()
+                .is_some_and(|sample| sample.date.date_naive() == now.date_naive());
+            if !sampled_today {
+                self.history.push(CapacitySample { date: now, full_capacity_mah: full });
+                if self.history.len() > MAX_CAPACITY_SAMPLES {
+                    self.history.remove(0);
+                }
+                check.history_changed = true;
+
+                let drop = self.capacity_drop(now);
+                if let (Some(drop_percent), None) = (drop, self.capacity_drop) {
+                    check.events.push(HardwareEvent::BatteryCapacityDrop { drop_percent });
+                }
+                self.capacity_drop = drop;
+            }
+        }
+
+        check
+    }
+
+    /// How much full capacity fell within the trend window, if by more than
+    /// normal wear allows
+    fn capacity_drop(&self, now: DateTime<Utc>) -> Option<f32> {
+        let since = now - Duration::days(self.config.capacity_trend_days as i64);
+        let latest = self.history.last()?.full_capacity_mah;
+        let peak = self.history.iter()
+            .filter(|sample| sample.date >= since)
+            .map(|sample| sample.full_capacity_mah)
+            .fold(latest, f32::max);
+        let drop = (peak - latest) / peak * 100.0;
+        (drop > self.config.capacity_drop_percent).then_some(drop)
+    }
+
+    pub fn fast_charge_allowed(&self) -> bool {
+        self.fast_charge_allowed
+    }
+
+    pub fn is_overheating(&self) -> bool {
+        self.overheating
+    }
+
+    pub fn has_current_anomaly(&self) -> bool {
+        self.current_anomaly
+    }
+
+    /// Percent of full capacity lost within the trend window, when it
+    /// suggests a swelling battery
+    pub fn capacity_drop_percent(&self) -> Option<f32> {
+        self.capacity_drop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charging(temp: f32, current_ma: f32) -> BatteryInfo {
+        BatteryInfo {
+            present: true,
+            capacity_percent: Some(60.0),
+            temperature_c: Some(temp),
+            current_ma: Some(current_ma),
+            charging: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heat_and_current_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut monitor = ChargingSafetyMonitor::load_from(dir.path().join("capacity.json"), ChargingSafetyConfig::default()).await;
+        let now = Utc::now();
+
+        assert!(monitor.observe(&charging(30.0, 1500.0), now).fast_charge.is_none());
+        assert_eq!(monitor.observe(&charging(41.0, 1500.0), now).fast_charge, Some(false));
+        // Not yet below the resume temperature
+        assert!(monitor.observe(&charging(38.0, 1500.0), now).fast_charge.is_none());
+
+        let check = monitor.observe(&charging(46.0, 1500.0), now);
+        assert!(matches!(check.events[..], [HardwareEvent::BatteryOverheating { .. }]));
+        assert!(monitor.observe(&charging(46.0, 1500.0), now).events.is_empty());
+        assert_eq!(monitor.observe(&charging(36.0, 1500.0), now).fast_charge, Some(true));
+        assert!(!monitor.is_overheating());
+
+        let check = monitor.observe(&charging(30.0, -4000.0), now);
+        assert!(matches!(check.events[..], [HardwareEvent::ChargeCurrentAnomaly { .. }]));
+        monitor.observe(&charging(30.0, 1500.0), now);
+        for _ in 0..STALLED_READINGS - 1 {
+            assert!(monitor.observe(&charging(30.0, 5.0), now).events.is_empty());
+        }
+        let check = monitor.observe(&charging(30.0, 5.0), now);
+        assert!(matches!(check.events[..], [HardwareEvent::ChargeCurrentAnomaly { .. }]));
+        assert!(monitor.has_current_anomaly());
+    }
+
+    #[tokio::test]
+    async fn test_capacity_drop_trend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capacity.json");
+        let mut monitor = ChargingSafetyMonitor::load_from(path.clone(), ChargingSafetyConfig::default()).await;
+        let start = Utc::now() - Duration::days(20);
+        let full = |mah: f32| BatteryInfo { present: true, full_capacity_mah: Some(mah), ..Default::default() };
+
+        assert!(monitor.observe(&full(3000.0), start).history_changed);
+        // Only one sample a day
+        assert!(!monitor.observe(&full(2990.0), start).history_changed);
+        assert!(monitor.observe(&full(2950.0), start + Duration::days(10)).events.is_empty());
+
+        let check = monitor.observe(&full(2600.0), start + Duration::days(20));
+        assert!(matches!(check.events[..], [HardwareEvent::BatteryCapacityDrop { .. }]));
+        assert!(monitor.capacity_drop_percent().unwrap() > 13.0);
+
+        monitor.save().await.unwrap();
+        let reloaded = ChargingSafetyMonitor::load_from(path, ChargingSafetyConfig::default()).await;
+        assert_eq!(reloaded.history.len(), 3);
+    }
+}
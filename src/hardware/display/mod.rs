@@ -0,0 +1,378 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::DisplayConfig;
+
+pub mod font;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayPanel {
+    /// SSD1306/SH1106 style OLED, driven through the kernel fbdev driver
+    Oled,
+    /// E-ink panel; only refreshed when the content actually changes
+    Eink,
+}
+
+/// Snapshot of the values shown on the status display
+#[derive(Debug, Clone)]
+pub struct DisplayStatus {
+    pub battery_level: f32,
+    pub is_charging: bool,
+    pub recording: bool,
+    /// The recording is paused
+    pub paused: bool,
+    pub streaming: bool,
+    /// Dispatchers watching the live stream
+    pub viewers: u32,
+    pub talkback: bool,
+    pub network_connected: bool,
+    pub time: chrono::DateTime<chrono::Local>,
+    pub incident_banner: Option<String>,
+}
+
+/// Monochrome off-screen frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    width: u32,
+    height: u32,
+    pixels: Vec<bool>,
+}
+
+impl Frame {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, on: bool) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return;
+        }
+        self.pixels[(y as u32 * self.width + x as u32) as usize] = on;
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, on: bool) {
+        for dy in 0..height as i32 {
+            for dx in 0..width as i32 {
+                self.set(x + dx, y + dy, on);
+            }
+        }
+    }
+
+    /// Draw text with the 5x7 font, returning the x position after the last glyph
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, on: bool) -> i32 {
+        let mut cursor = x;
+        for c in text.chars() {
+            let columns = font::glyph(c);
+            for (col, bits) in columns.iter().enumerate() {
+                for row in 0..font::GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        self.set(cursor + col as i32, y + row as i32, on);
+                    }
+                }
+            }
+            cursor += font::GLYPH_WIDTH as i32 + 1;
+        }
+        cursor
+    }
+
+    /// Pack the frame into the byte layout expected by the framebuffer device
+    pub fn to_bytes(&self, color_depth: u8) -> Vec<u8> {
+        match color_depth {
+            1 => {
+                // ssd1307fb and most mono panels use 1bpp, LSB first
+                let line_length = ((self.width + 7) / 8) as usize;
+                let mut bytes = vec![0u8; line_length * self.height as usize];
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        if self.get(x, y) {
+                            bytes[y as usize * line_length + (x / 8) as usize] |= 1 << (x % 8);
+                        }
+                    }
+                }
+                bytes
+            }
+            _ => {
+                let bytes_per_pixel = ((color_depth as usize) + 7) / 8;
+                let mut bytes = Vec::with_capacity(self.pixels.len() * bytes_per_pixel);
+                for on in &self.pixels {
+                    let value = if *on { 0xFF } else { 0x00 };
+                    bytes.extend(std::iter::repeat(value).take(bytes_per_pixel));
+                }
+                bytes
+            }
+        }
+    }
+}
+
+/// Renders device status to a small OLED or e-ink panel exposed as a Linux
+/// framebuffer (e.g. `/dev/fb1` from the ssd1307fb or repaper drivers), with
+/// a screen timeout and pixel shifting to avoid OLED burn-in.
+pub struct DisplayManager {
+    config: DisplayConfig,
+    last_frame: Option<Frame>,
+    last_activity: Instant,
+    last_shift: Instant,
+    shift_step: usize,
+    blanked: bool,
+    suppressed: bool,
+    brightness_percent: Option<u8>,
+}
+
+/// Offsets cycled through for burn-in protection
+const SHIFT_PATTERN: [(i32, i32); 4] = [(0, 0), (1, 0), (1, 1), (0, 1)];
+
+impl DisplayManager {
+    pub fn new(config: DisplayConfig) -> Self {
+        Self {
+            config,
+            last_frame: None,
+            last_activity: Instant::now(),
+            last_shift: Instant::now(),
+            shift_step: 0,
+            blanked: false,
+            suppressed: false,
+            brightness_percent: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn is_blanked(&self) -> bool {
+        self.blanked
+    }
+
+    /// Reset the screen timeout, e.g. after a button press
+    pub fn wake(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    /// Brightness last applied to the backlight
+    pub fn brightness(&self) -> Option<u8> {
+        self.brightness_percent
+    }
+
+    /// Set the backlight, or the OLED contrast where the driver exposes it
+    /// as a backlight. Panels without either stay at their fixed level.
+    pub async fn set_brightness(&mut self, percent: u8) -> Result<()> {
+        if !self.config.enabled || self.brightness_percent == Some(percent) {
+            return Ok(());
+        }
+
+        let backlight = match &self.config.backlight_path {
+            Some(path) => Some(std::path::PathBuf::from(path)),
+            None => first_backlight().await,
+        };
+        match backlight {
+            Some(dir) => super::brightness::write_sysfs_brightness(&dir, percent).await?,
+            None => tracing::debug!("No backlight for the status display, brightness stays fixed"),
+        }
+        self.brightness_percent = Some(percent);
+        Ok(())
+    }
+
+    /// Keep the panel dark regardless of activity (used by stealth mode)
+    pub async fn set_suppressed(&mut self, suppressed: bool) -> Result<()> {
+        self.suppressed = suppressed;
+        if suppressed {
+            self.blank().await?;
+        } else {
+            self.wake();
+        }
+        Ok(())
+    }
+
+    pub async fn update(&mut self, status: &DisplayStatus) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let timeout = Duration::from_secs(self.config.screen_timeout_seconds);
+        let timed_out = self.config.screen_timeout_seconds > 0
+            && self.last_activity.elapsed() >= timeout
+            && status.incident_banner.is_none();
+
+        if self.suppressed || timed_out {
+            return self.blank().await;
+        }
+
+        if self.config.panel == DisplayPanel::Oled
+            && self.last_shift.elapsed() >= Duration::from_secs(self.config.burn_in_shift_interval_seconds)
+        {
+            self.shift_step = (self.shift_step + 1) % SHIFT_PATTERN.len();
+            self.last_shift = Instant::now();
+        }
+
+        let frame = self.render(status);
+        if !self.blanked && self.last_frame.as_ref() == Some(&frame) {
+            return Ok(());
+        }
+
+        self.write_frame(&frame).await?;
+        self.last_frame = Some(frame);
+        self.blanked = false;
+        Ok(())
+    }
+
+    pub async fn blank(&mut self) -> Result<()> {
+        if !self.config.enabled || self.blanked {
+            return Ok(());
+        }
+
+        let frame = Frame::new(self.config.width, self.config.height);
+        self.write_frame(&frame).await?;
+        self.blanked = true;
+        tracing::debug!("Status display blanked");
+        Ok(())
+    }
+
+    pub fn render(&self, status: &DisplayStatus) -> Frame {
+        let mut frame = Frame::new(self.config.width, self.config.height);
+        let (dx, dy) = if self.config.panel == DisplayPanel::Oled {
+            SHIFT_PATTERN[self.shift_step]
+        } else {
+            (0, 0)
+        };
+
+        let line_height = font::GLYPH_HEIGHT as i32 + 3;
+        let mut y = 1 + dy;
+
+        let battery = if status.is_charging {
+            format!("BAT {:.0}%+", status.battery_level)
+        } else {
+            format!("BAT {:.0}%", status.battery_level)
+        };
+        frame.draw_text(1 + dx, y, &battery, true);
+
+        let time = status.time.format("%H:%M").to_string();
+        let time_x = self.config.width as i32 - (time.len() as i32 * (font::GLYPH_WIDTH as i32 + 1)) - 1 + dx;
+        frame.draw_text(time_x, y, &time, true);
+        y += line_height;
+
+        let state = match (status.recording, status.streaming) {
+            (true, true) if status.paused => "REC PAUSED + LIVE",
+            (true, false) if status.paused => "REC PAUSED",
+            (true, true) => "REC + LIVE",
+            (true, false) => "REC",
+            (false, true) => "LIVE",
+            (false, false) => "STANDBY",
+        };
+        let state = if status.streaming && status.viewers > 0 {
+            format!("{} ({})", state, status.viewers)
+        } else {
+            state.to_string()
+        };
+        frame.draw_text(1 + dx, y, &state, true);
+        y += line_height;
+
+        if status.talkback {
+            frame.draw_text(1 + dx, y, "DISPATCH TALKING", true);
+            y += line_height;
+        }
+
+        let network = if status.network_connected { "NET OK" } else { "NET OFFLINE" };
+        frame.draw_text(1 + dx, y, network, true);
+        y += line_height;
+
+        if let Some(banner) = &status.incident_banner {
+            // Inverted bar so the incident stands out
+            frame.fill_rect(0, y - 1, self.config.width, line_height as u32, true);
+            frame.draw_text(1 + dx, y, banner, false);
+        }
+
+        frame
+    }
+
+    async fn write_frame(&self, frame: &Frame) -> Result<()> {
+        let bytes = frame.to_bytes(self.config.color_depth);
+        tokio::fs::write(&self.config.device_path, bytes).await
+            .context(format!("Failed to write to display {}", self.config.device_path))?;
+        Ok(())
+    }
+}
+
+async fn first_backlight() -> Option<std::path::PathBuf> {
+    let mut entries = tokio::fs::read_dir("/sys/class/backlight").await.ok()?;
+    entries.next_entry().await.ok().flatten().map(|entry| entry.path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DisplayConfig {
+        DisplayConfig {
+            enabled: true,
+            device_path: "/dev/null".to_string(),
+            width: 128,
+            height: 64,
+            color_depth: 1,
+            panel: DisplayPanel::Oled,
+            screen_timeout_seconds: 30,
+            burn_in_shift_interval_seconds: 60,
+            backlight_path: None,
+        }
+    }
+
+    fn status() -> DisplayStatus {
+        DisplayStatus {
+            battery_level: 80.0,
+            is_charging: false,
+            recording: true,
+            paused: false,
+            streaming: false,
+            viewers: 0,
+            talkback: false,
+            network_connected: true,
+            time: chrono::Local::now(),
+            incident_banner: None,
+        }
+    }
+
+    #[test]
+    fn test_mono_packing() {
+        let mut frame = Frame::new(16, 2);
+        frame.set(0, 0, true);
+        frame.set(9, 1, true);
+        let bytes = frame.to_bytes(1);
+        assert_eq!(bytes, vec![0x01, 0x00, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let manager = DisplayManager::new(config());
+        let status = status();
+        assert_eq!(manager.render(&status), manager.render(&status));
+    }
+
+    #[test]
+    fn test_incident_banner_inverts_row() {
+        let manager = DisplayManager::new(config());
+        let mut status = status();
+        status.incident_banner = Some("INCIDENT".to_string());
+        let frame = manager.render(&status);
+        // Right edge of the banner row is lit, the same spot is dark without a banner
+        assert!(frame.get(127, 32));
+        status.incident_banner = None;
+        assert!(!manager.render(&status).get(127, 32));
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Duration};
+
+use super::HardwareInterface;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HapticsConfig {
+    pub enabled: bool,
+    pub patterns: Vec<HapticPatternConfig>,
+}
+
+impl Default for HapticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Overrides the built-in pulse sequence for a named pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HapticPatternConfig {
+    pub name: String,
+    pub pulses: Vec<HapticPulse>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HapticPulse {
+    pub duration_ms: u64,
+    pub pause_ms: u64,
+}
+
+impl HapticPulse {
+    const fn new(duration_ms: u64, pause_ms: u64) -> Self {
+        Self { duration_ms, pause_ms }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HapticPattern {
+    Single,
+    Double,
+    Sos,
+    Heartbeat,
+}
+
+impl HapticPattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HapticPattern::Single => "single",
+            HapticPattern::Double => "double",
+            HapticPattern::Sos => "sos",
+            HapticPattern::Heartbeat => "heartbeat",
+        }
+    }
+
+    pub fn all() -> [HapticPattern; 4] {
+        [
+            HapticPattern::Single,
+            HapticPattern::Double,
+            HapticPattern::Sos,
+            HapticPattern::Heartbeat,
+        ]
+    }
+
+    fn default_pulses(&self) -> Vec<HapticPulse> {
+        match self {
+            HapticPattern::Single => vec![HapticPulse::new(200, 0)],
+            HapticPattern::Double => vec![HapticPulse::new(150, 100), HapticPulse::new(150, 0)],
+            HapticPattern::Sos => {
+                let short = HapticPulse::new(100, 100);
+                let long = HapticPulse::new(300, 100);
+                let mut pulses = vec![short; 3];
+                pulses.extend([long; 3]);
+                pulses.extend([short; 3]);
+                pulses
+            }
+            HapticPattern::Heartbeat => vec![
+                HapticPulse::new(80, 120),
+                HapticPulse::new(120, 600),
+                HapticPulse::new(80, 120),
+                HapticPulse::new(120, 0),
+            ],
+        }
+    }
+}
+
+impl std::str::FromStr for HapticPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        HapticPattern::all()
+            .into_iter()
+            .find(|pattern| pattern.name() == s.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Unknown haptic pattern: {}", s))
+    }
+}
+
+/// Plays named vibration patterns using the hardware vibration motor
+pub struct HapticController {
+    config: HapticsConfig,
+}
+
+impl HapticController {
+    pub fn new(config: HapticsConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn pulses_for(&self, pattern: HapticPattern) -> Vec<HapticPulse> {
+        self.config
+            .patterns
+            .iter()
+            .find(|p| p.name == pattern.name())
+            .map(|p| p.pulses.clone())
+            .unwrap_or_else(|| pattern.default_pulses())
+    }
+
+    pub async fn play(&self, hardware: &dyn HardwareInterface, pattern: HapticPattern) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        for pulse in self.pulses_for(pattern) {
+            hardware.vibrate(pulse.duration_ms).await?;
+            sleep(Duration::from_millis(pulse.duration_ms + pulse.pause_ms)).await;
+        }
+
+        Ok(())
+    }
+}
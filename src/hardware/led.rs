@@ -0,0 +1,406 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::{BlinkPattern, HardwareInterface, LedConfig, LedState};
+
+/// Semantic device states that can be signalled on the status LEDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedIndicator {
+    Idle,
+    Recording,
+    Streaming,
+    /// Live with at least one dispatcher watching
+    Watched,
+    /// A dispatcher is talking back
+    Talkback,
+    LowBattery,
+    Error,
+    /// A critical self-test check failed
+    NotReady,
+    UpdateInProgress,
+    /// An SMS, call or cost quota is nearly used up
+    QuotaWarning,
+    Stealth,
+}
+
+impl LedIndicator {
+    /// Higher values win when several indicators are active at once
+    pub fn priority(&self) -> u8 {
+        match self {
+            LedIndicator::Stealth => 100,
+            LedIndicator::Error => 90,
+            LedIndicator::NotReady => 85,
+            LedIndicator::LowBattery => 80,
+            LedIndicator::Talkback => 75,
+            LedIndicator::Recording => 70,
+            LedIndicator::Watched => 65,
+            LedIndicator::Streaming => 60,
+            LedIndicator::UpdateInProgress => 50,
+            LedIndicator::QuotaWarning => 40,
+            LedIndicator::Idle => 0,
+        }
+    }
+
+    /// Name of the blink pattern looked up in `LedConfig`
+    pub fn pattern_name(&self) -> &'static str {
+        match self {
+            LedIndicator::Idle => "idle",
+            LedIndicator::Recording => "recording",
+            LedIndicator::Streaming => "streaming",
+            LedIndicator::Watched => "watched",
+            LedIndicator::Talkback => "talkback",
+            LedIndicator::LowBattery => "low_battery",
+            LedIndicator::Error => "error",
+            LedIndicator::NotReady => "not_ready",
+            LedIndicator::UpdateInProgress => "update_in_progress",
+            LedIndicator::QuotaWarning => "quota_warning",
+            LedIndicator::Stealth => "stealth",
+        }
+    }
+
+    /// LED used when no configured pattern names one explicitly
+    fn default_led(&self) -> &'static str {
+        match self {
+            LedIndicator::Idle => "power",
+            LedIndicator::Recording => "recording",
+            LedIndicator::Streaming => "wifi",
+            LedIndicator::Watched => "wifi",
+            LedIndicator::Talkback => "wifi",
+            LedIndicator::LowBattery => "power",
+            LedIndicator::Error => "recording",
+            LedIndicator::NotReady => "power",
+            LedIndicator::UpdateInProgress => "wifi",
+            LedIndicator::QuotaWarning => "wifi",
+            LedIndicator::Stealth => "power",
+        }
+    }
+
+    /// Pattern used when the hardware config doesn't define one
+    fn default_state(&self) -> LedState {
+        match self {
+            LedIndicator::Idle => LedState::Pulse { duration: 2000 },
+            LedIndicator::Recording => LedState::On,
+            LedIndicator::Streaming => LedState::Blink {
+                on_duration: 1000,
+                off_duration: 1000,
+                repeat: None,
+            },
+            LedIndicator::Watched => LedState::On,
+            LedIndicator::Talkback => LedState::Blink {
+                on_duration: 250,
+                off_duration: 250,
+                repeat: None,
+            },
+            LedIndicator::LowBattery => LedState::Blink {
+                on_duration: 200,
+                off_duration: 200,
+                repeat: Some(10),
+            },
+            LedIndicator::Error => LedState::Blink {
+                on_duration: 100,
+                off_duration: 100,
+                repeat: None,
+            },
+            LedIndicator::NotReady => LedState::Blink {
+                on_duration: 100,
+                off_duration: 900,
+                repeat: None,
+            },
+            LedIndicator::UpdateInProgress => LedState::Blink {
+                on_duration: 500,
+                off_duration: 500,
+                repeat: None,
+            },
+            LedIndicator::QuotaWarning => LedState::Blink {
+                on_duration: 100,
+                off_duration: 1900,
+                repeat: None,
+            },
+            LedIndicator::Stealth => LedState::Off,
+        }
+    }
+
+    pub fn all() -> [LedIndicator; 11] {
+        [
+            LedIndicator::Idle,
+            LedIndicator::Recording,
+            LedIndicator::Streaming,
+            LedIndicator::Watched,
+            LedIndicator::Talkback,
+            LedIndicator::LowBattery,
+            LedIndicator::Error,
+            LedIndicator::NotReady,
+            LedIndicator::UpdateInProgress,
+            LedIndicator::QuotaWarning,
+            LedIndicator::Stealth,
+        ]
+    }
+}
+
+impl std::str::FromStr for LedIndicator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        LedIndicator::all()
+            .into_iter()
+            .find(|indicator| indicator.pattern_name() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown LED indicator: {}", s))
+    }
+}
+
+/// Resolved output for a single indicator
+#[derive(Debug, Clone)]
+struct LedOutput {
+    led: String,
+    state: LedState,
+}
+
+/// Maps semantic device states onto physical LEDs, arbitrating by priority
+/// so that only the most important active state is shown at any time.
+pub struct LedController {
+    enabled: bool,
+    outputs: HashMap<LedIndicator, LedOutput>,
+    led_names: Vec<String>,
+    active: HashSet<LedIndicator>,
+    current: Option<LedIndicator>,
+    brightness_percent: Option<u8>,
+}
+
+impl LedController {
+    pub fn new(config: &LedConfig) -> Self {
+        let mut outputs = HashMap::new();
+
+        for indicator in LedIndicator::all() {
+            let configured = config.leds.iter().find_map(|led| {
+                led.blink_patterns
+                    .iter()
+                    .find(|pattern| pattern.name == indicator.pattern_name())
+                    .map(|pattern| (led.name.clone(), pattern))
+            });
+
+            let output = match configured {
+                Some((led, pattern)) => LedOutput {
+                    led,
+                    state: Self::pattern_to_state(pattern),
+                },
+                None => LedOutput {
+                    led: indicator.default_led().to_string(),
+                    state: indicator.default_state(),
+                },
+            };
+
+            outputs.insert(indicator, output);
+        }
+
+        let mut led_names: Vec<String> = config.leds.iter().map(|led| led.name.clone()).collect();
+        for output in outputs.values() {
+            if !led_names.contains(&output.led) {
+                led_names.push(output.led.clone());
+            }
+        }
+
+        let mut active = HashSet::new();
+        active.insert(LedIndicator::Idle);
+
+        Self {
+            enabled: config.enabled,
+            outputs,
+            led_names,
+            active,
+            current: None,
+            brightness_percent: None,
+        }
+    }
+
+    fn pattern_to_state(pattern: &BlinkPattern) -> LedState {
+        match (pattern.on_duration, pattern.off_duration) {
+            (0, _) => LedState::Off,
+            (_, 0) => LedState::On,
+            (on, off) => LedState::Blink {
+                on_duration: on,
+                off_duration: off,
+                repeat: pattern.repeat_count,
+            },
+        }
+    }
+
+    /// Highest-priority indicator among the currently active ones
+    pub fn resolve(&self) -> LedIndicator {
+        self.active
+            .iter()
+            .copied()
+            .max_by_key(|indicator| indicator.priority())
+            .unwrap_or(LedIndicator::Idle)
+    }
+
+    pub fn is_active(&self, indicator: LedIndicator) -> bool {
+        self.active.contains(&indicator)
+    }
+
+    pub fn active_indicators(&self) -> Vec<LedIndicator> {
+        let mut indicators: Vec<LedIndicator> = self.active.iter().copied().collect();
+        indicators.sort_by_key(|indicator| std::cmp::Reverse(indicator.priority()));
+        indicators
+    }
+
+    pub async fn activate(
+        &mut self,
+        hardware: &dyn HardwareInterface,
+        indicator: LedIndicator,
+    ) -> Result<()> {
+        self.active.insert(indicator);
+        self.apply(hardware).await
+    }
+
+    pub async fn deactivate(
+        &mut self,
+        hardware: &dyn HardwareInterface,
+        indicator: LedIndicator,
+    ) -> Result<()> {
+        if indicator != LedIndicator::Idle {
+            self.active.remove(&indicator);
+        }
+        self.apply(hardware).await
+    }
+
+    pub fn brightness(&self) -> Option<u8> {
+        self.brightness_percent
+    }
+
+    /// Dim every LED to `percent`
+    pub async fn set_brightness(&mut self, hardware: &dyn HardwareInterface, percent: u8) -> Result<()> {
+        if !self.enabled || self.brightness_percent == Some(percent) {
+            return Ok(());
+        }
+        for led in &self.led_names {
+            hardware.set_led_brightness(led, percent).await?;
+        }
+        self.brightness_percent = Some(percent);
+        Ok(())
+    }
+
+    /// Light every LED so the operator can check none is dead, or put the
+    /// indicators back afterwards. Does nothing in stealth mode.
+    pub async fn lamp_test(&mut self, hardware: &dyn HardwareInterface, on: bool) -> Result<()> {
+        if !self.enabled || self.active.contains(&LedIndicator::Stealth) {
+            return Ok(());
+        }
+        let state = if on { LedState::On } else { LedState::Off };
+        for led in &self.led_names {
+            hardware.set_led(led, state.clone()).await?;
+        }
+        self.current = None;
+        if on {
+            Ok(())
+        } else {
+            self.apply(hardware).await
+        }
+    }
+
+    /// Push the winning indicator to the hardware, turning off the LED used
+    /// by the previous winner when it differs.
+    async fn apply(&mut self, hardware: &dyn HardwareInterface) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let winner = self.resolve();
+        if self.current == Some(winner) {
+            return Ok(());
+        }
+
+        if winner == LedIndicator::Stealth {
+            for led in &self.led_names {
+                hardware.set_led(led, LedState::Off).await?;
+            }
+        } else {
+            if let Some(previous) = self.current.and_then(|p| self.outputs.get(&p)) {
+                let next_led = self.outputs.get(&winner).map(|o| o.led.as_str());
+                if next_led != Some(previous.led.as_str()) {
+                    hardware.set_led(&previous.led, LedState::Off).await?;
+                }
+            }
+
+            if let Some(output) = self.outputs.get(&winner) {
+                hardware.set_led(&output.led, output.state.clone()).await?;
+            }
+        }
+
+        tracing::debug!("LED indicator changed: {:?} -> {:?}", self.current, winner);
+        self.current = Some(winner);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::Led;
+
+    fn controller() -> LedController {
+        LedController::new(&LedConfig {
+            enabled: true,
+            leds: vec![Led {
+                name: "recording".to_string(),
+                gpio_pin: 17,
+                color: "red".to_string(),
+                blink_patterns: vec![BlinkPattern {
+                    name: "recording".to_string(),
+                    on_duration: 1000,
+                    off_duration: 1000,
+                    repeat_count: None,
+                }],
+            }],
+        })
+    }
+
+    #[test]
+    fn test_idle_when_nothing_active() {
+        let controller = controller();
+        assert_eq!(controller.resolve(), LedIndicator::Idle);
+    }
+
+    #[test]
+    fn test_priority_arbitration() {
+        let mut controller = controller();
+        controller.active.insert(LedIndicator::Streaming);
+        controller.active.insert(LedIndicator::Recording);
+        assert_eq!(controller.resolve(), LedIndicator::Recording);
+
+        controller.active.insert(LedIndicator::LowBattery);
+        assert_eq!(controller.resolve(), LedIndicator::LowBattery);
+
+        controller.active.insert(LedIndicator::Stealth);
+        assert_eq!(controller.resolve(), LedIndicator::Stealth);
+    }
+
+    #[test]
+    fn test_configured_pattern_is_used() {
+        let controller = controller();
+        let output = controller.outputs.get(&LedIndicator::Recording).unwrap();
+        assert_eq!(output.led, "recording");
+        assert!(matches!(output.state, LedState::Blink { on_duration: 1000, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_lamp_test_stays_dark_in_stealth() {
+        let hardware = crate::hardware::mock::MockHardware::new();
+        let mut controller = controller();
+        controller.active.insert(LedIndicator::Stealth);
+
+        controller.lamp_test(&hardware, true).await.unwrap();
+        assert!(hardware.led("recording").is_none());
+
+        controller.active.remove(&LedIndicator::Stealth);
+        controller.lamp_test(&hardware, true).await.unwrap();
+        assert!(matches!(hardware.led("recording"), Some(LedState::On)));
+    }
+
+    #[test]
+    fn test_indicator_from_str() {
+        assert_eq!("low_battery".parse::<LedIndicator>().unwrap(), LedIndicator::LowBattery);
+        assert!("disco".parse::<LedIndicator>().is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use super::power_supply::{BatteryInfo, PowerSupplyReader};
 use anyhow::{Result, Context};
 use tokio::fs;
 use tokio::sync::mpsc;
@@ -12,6 +13,9 @@ pub struct LinuxHardware {
     leds: HashMap<String, LedInfo>,
     buttons: HashMap<String, ButtonInfo>,
     sensors: HashMap<String, SensorInfo>,
+    buzzer_pin: Option<u32>,
+    buzzer_pwm_path: Option<String>,
+    power_supply: PowerSupplyReader,
     battery_level: Arc<Mutex<f32>>,
     storage_used: Arc<Mutex<u64>>,
     temperature: Arc<Mutex<f32>>,
@@ -55,6 +59,9 @@ impl LinuxHardware {
             leds: HashMap::new(),
             buttons: HashMap::new(),
             sensors: HashMap::new(),
+            buzzer_pin: None,
+            buzzer_pwm_path: None,
+            power_supply: PowerSupplyReader::new(None, None),
             battery_level: Arc::new(Mutex::new(100.0)),
             storage_used: Arc::new(Mutex::new(0)),
             temperature: Arc::new(Mutex::new(25.0)),
@@ -98,12 +105,15 @@ impl LinuxHardware {
                 PinFunction::Button(button_type) => {
                     let button_info = ButtonInfo {
                         gpio_pin: pin_config.number,
-                        button_type: button_type.clone(),
+                        button_type: *button_type,
                         debounce_ms: 50,
                         long_press_ms: 1000,
                     };
                     self.buttons.insert(format!("{:?}", button_type), button_info);
                 }
+                PinFunction::Buzzer => {
+                    self.buzzer_pin = Some(pin_config.number);
+                }
                 _ => {}
             }
         }
@@ -204,7 +214,8 @@ impl LinuxHardware {
                 {
                     let mut storage = storage_used.lock().await;
                     *storage += 10_000_000; // 10MB per interval
-                    if *storage > 50_000_000_000 { // 50GB
+                    let disk_full = crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull);
+                    if *storage > 50_000_000_000 || disk_full { // 50GB
                         let _ = tx.send(HardwareEvent::StorageFull);
                     }
                 }
@@ -230,7 +241,7 @@ impl LinuxHardware {
         // Simulate button presses
         for (_, button_info) in &self.buttons {
             let tx_clone = tx.clone();
-            let button_type = button_info.button_type.clone();
+            let button_type = button_info.button_type;
             
             tokio::spawn(async move {
                 let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
@@ -243,7 +254,7 @@ impl LinuxHardware {
                         let duration = if is_long_press { Some(2000) } else { None };
                         
                         let _ = tx_clone.send(HardwareEvent::ButtonPressed {
-                            button: button_type.clone(),
+                            button: button_type,
                             duration,
                         });
                     }
@@ -262,7 +273,7 @@ impl LinuxHardware {
         for (_, button_info) in &self.buttons {
             let tx_clone = tx.clone();
             let pin = button_info.gpio_pin;
-            let button_type = button_info.button_type.clone();
+            let button_type = button_info.button_type;
             let debounce_ms = button_info.debounce_ms;
             let long_press_ms = button_info.long_press_ms;
 
@@ -286,7 +297,7 @@ impl LinuxHardware {
                                         let is_long_press = duration >= long_press_ms;
                                         
                                         let _ = tx_clone.send(HardwareEvent::ButtonPressed {
-                                            button: button_type.clone(),
+                                            button: button_type,
                                             duration: if is_long_press { Some(duration) } else { None },
                                         });
                                     }
@@ -315,6 +326,19 @@ impl HardwareInterface for LinuxHardware {
     async fn init(&mut self, config: &super::HardwareConfig
     ) -> Result<()> {
         self.init_gpio_pins(config).await?;
+
+        if config.buzzer.gpio_pin.is_some() {
+            self.buzzer_pin = config.buzzer.gpio_pin;
+        }
+        self.buzzer_pwm_path = config.buzzer.pwm_path.clone();
+
+        self.power_supply = PowerSupplyReader::new(
+            config.sensors.battery.as_ref(),
+            config.sensors.temperature.as_ref(),
+        );
+        if !self.simulation {
+            self.power_supply.detect().await?;
+        }
         
         if !self.simulation {
             tracing::info!("Initializing Linux hardware interface");
@@ -352,6 +376,18 @@ impl HardwareInterface for LinuxHardware {
         Ok(())
     }
 
+    async fn set_led_brightness(&self, led_name: &str, percent: u8) -> Result<()> {
+        if self.simulation {
+            return Ok(());
+        }
+        // Only LEDs behind a kernel LED driver can dim; plain GPIO is on or off
+        let dir = std::path::Path::new("/sys/class/leds").join(led_name);
+        if dir.exists() {
+            super::brightness::write_sysfs_brightness(&dir, percent).await?;
+        }
+        Ok(())
+    }
+
     async fn get_battery_level(&self
     ) -> Result<f32> {
         if self.simulation {
@@ -359,15 +395,47 @@ impl HardwareInterface for LinuxHardware {
             return Ok(level);
         }
 
-        // Real battery reading would go here
-        Ok(75.0)
+        match self.power_supply.read_battery().await.capacity_percent {
+            Some(level) => Ok(level),
+            None => {
+                // Boards without a fuel gauge run from external power
+                tracing::debug!("No battery capacity available, assuming external power");
+                Ok(100.0)
+            }
+        }
+    }
+
+    async fn get_battery_info(&self) -> Result<BatteryInfo> {
+        if self.simulation {
+            let charging = *self.is_charging.lock().await;
+            return Ok(BatteryInfo {
+                present: true,
+                capacity_percent: Some(*self.battery_level.lock().await),
+                voltage_v: Some(3.8),
+                current_ma: Some(if charging { 500.0 } else { -350.0 }),
+                temperature_c: Some(*self.temperature.lock().await),
+                status: Some(if charging { "Charging" } else { "Discharging" }.to_string()),
+                health: Some("Good".to_string()),
+                cycle_count: Some(0),
+                full_capacity_mah: None,
+                design_capacity_mah: None,
+                charging,
+                external_power: charging,
+            });
+        }
+
+        Ok(self.power_supply.read_battery().await)
     }
 
     async fn get_storage_info(&self
     ) -> Result<StorageInfo> {
         if self.simulation {
-            let used = *self.storage_used.lock().await;
             let total = 64_000_000_000; // 64GB
+            let used = if crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull) {
+                total
+            } else {
+                *self.storage_used.lock().await
+            };
             let available = total.saturating_sub(used);
             
             return Ok(StorageInfo {
@@ -394,8 +462,12 @@ impl HardwareInterface for LinuxHardware {
             return Ok(temp);
         }
 
-        // Real temperature reading would go here
-        Ok(28.5)
+        if let Some(temp) = self.power_supply.read_temperature().await {
+            return Ok(temp);
+        }
+
+        self.power_supply.read_battery().await.temperature_c
+            .ok_or_else(|| anyhow::anyhow!("No thermal zone or battery temperature available"))
     }
 
     async fn is_charging(&self
@@ -405,8 +477,7 @@ impl HardwareInterface for LinuxHardware {
             return Ok(charging);
         }
 
-        // Real charging detection would go here
-        Ok(false)
+        Ok(self.power_supply.read_battery().await.charging)
     }
 
     async fn vibrate(&self, duration_ms: u64) -> Result<()> {
@@ -419,6 +490,47 @@ impl HardwareInterface for LinuxHardware {
         Ok(())
     }
 
+    async fn start_tone(&self, frequency_hz: Option<u32>) -> Result<()> {
+        tracing::debug!("Buzzer on ({:?} Hz)", frequency_hz);
+
+        if self.simulation {
+            return Ok(());
+        }
+
+        // PWM buzzers can play a specific pitch, plain GPIO buzzers only switch on/off
+        if let (Some(pwm_path), Some(frequency)) = (&self.buzzer_pwm_path, frequency_hz) {
+            let period_ns = 1_000_000_000u64 / frequency.max(1) as u64;
+            fs::write(format!("{}/period", pwm_path), period_ns.to_string()).await
+                .context("Failed to set buzzer PWM period")?;
+            fs::write(format!("{}/duty_cycle", pwm_path), (period_ns / 2).to_string()).await
+                .context("Failed to set buzzer PWM duty cycle")?;
+            fs::write(format!("{}/enable", pwm_path), "1").await
+                .context("Failed to enable buzzer PWM")?;
+        } else if let Some(pin) = self.buzzer_pin {
+            self.set_gpio_value(pin, true).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn stop_tone(&self) -> Result<()> {
+        tracing::debug!("Buzzer off");
+
+        if self.simulation {
+            return Ok(());
+        }
+
+        if let Some(pwm_path) = &self.buzzer_pwm_path {
+            fs::write(format!("{}/enable", pwm_path), "0").await
+                .context("Failed to disable buzzer PWM")?;
+        }
+        if let Some(pin) = self.buzzer_pin {
+            self.set_gpio_value(pin, false).await?;
+        }
+
+        Ok(())
+    }
+
     async fn shutdown(&self) -> Result<()> {
         tracing::info!("Shutting down device");
         
@@ -429,4 +541,11 @@ impl HardwareInterface for LinuxHardware {
         
         Ok(())
     }
+
+    async fn set_fast_charge(&self, allowed: bool) -> Result<()> {
+        if self.simulation {
+            return Ok(());
+        }
+        self.power_supply.set_fast_charge(allowed).await
+    }
 }
\ No newline at end of file
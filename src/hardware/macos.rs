@@ -126,7 +126,8 @@ impl MacHardware {
                 {
                     let mut storage = storage_used.lock().await;
                     *storage += 5_000_000; // 5MB per interval
-                    if *storage > 60_000_000_000 { // 60GB
+                    let disk_full = crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull);
+                    if *storage > 60_000_000_000 || disk_full { // 60GB
                         let _ = tx.send(HardwareEvent::StorageFull);
                     }
                 }
@@ -164,7 +165,7 @@ impl MacHardware {
         // Simulate button presses based on keyboard input
         for (_, button_info) in &self.buttons {
             let tx_clone = tx.clone();
-            let button_type = button_info.button_type.clone();
+            let button_type = button_info.button_type;
             let debounce_ms = button_info.debounce_ms;
             let long_press_ms = button_info.long_press_ms;
 
@@ -182,7 +183,7 @@ impl MacHardware {
                         } else { None };
                         
                         let _ = tx_clone.send(HardwareEvent::ButtonPressed {
-                            button: button_type.clone(),
+                            button: button_type,
                             duration,
                         });
                     }
@@ -233,6 +234,29 @@ impl HardwareInterface for MacHardware {
         Ok(())
     }
 
+    async fn set_led_brightness(&self, led_name: &str, percent: u8) -> Result<()> {
+        tracing::debug!("Setting LED {} brightness to {}%", led_name, percent);
+        Ok(())
+    }
+
+    async fn get_battery_info(&self) -> Result<super::power_supply::BatteryInfo> {
+        let charging = *self.is_charging.lock().await;
+        Ok(super::power_supply::BatteryInfo {
+            present: true,
+            capacity_percent: Some(*self.battery_level.lock().await),
+            voltage_v: None,
+            current_ma: None,
+            temperature_c: None,
+            status: Some(if charging { "Charging" } else { "Discharging" }.to_string()),
+            health: None,
+            cycle_count: None,
+            full_capacity_mah: None,
+            design_capacity_mah: None,
+            charging,
+            external_power: charging,
+        })
+    }
+
     async fn get_battery_level(&self) -> Result<f32> {
         if self.simulation {
             let level = *self.battery_level.lock().await;
@@ -246,8 +270,12 @@ impl HardwareInterface for MacHardware {
 
     async fn get_storage_info(&self) -> Result<StorageInfo> {
         if self.simulation {
-            let used = *self.storage_used.lock().await;
             let total = 64_000_000_000; // 64GB
+            let used = if crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull) {
+                total
+            } else {
+                *self.storage_used.lock().await
+            };
             let available = total.saturating_sub(used);
             
             return Ok(StorageInfo {
@@ -295,6 +323,16 @@ impl HardwareInterface for MacHardware {
         Ok(())
     }
 
+    async fn start_tone(&self, frequency_hz: Option<u32>) -> Result<()> {
+        tracing::info!("Simulating buzzer tone ({:?} Hz)", frequency_hz);
+        Ok(())
+    }
+
+    async fn stop_tone(&self) -> Result<()> {
+        tracing::debug!("Simulating buzzer off");
+        Ok(())
+    }
+
     async fn shutdown(&self) -> Result<()> {
         tracing::info!("Simulating shutdown");
         
@@ -302,4 +340,10 @@ impl HardwareInterface for MacHardware {
         // For now, just log the action
         Ok(())
     }
+
+    async fn set_fast_charge(&self, allowed: bool) -> Result<()> {
+        // macOS manages charging itself
+        tracing::debug!("Fast charging {}", if allowed { "allowed" } else { "refused" });
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,341 @@
+use super::*;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+const STORAGE_TOTAL: u64 = 64_000_000_000; // 64GB
+
+/// Calls that can be made to fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockOperation {
+    Init,
+    SetLed,
+    Battery,
+    Storage,
+    Temperature,
+    Vibrate,
+    Tone,
+    Shutdown,
+}
+
+#[derive(Debug)]
+struct MockState {
+    battery_level: f32,
+    /// Readings returned by successive battery reads before `battery_level`
+    battery_script: VecDeque<f32>,
+    charging: bool,
+    temperature: f32,
+    storage_used: u64,
+    leds: HashMap<String, LedState>,
+    led_brightness: HashMap<String, u8>,
+    vibrations: Vec<u64>,
+    tone: Option<Option<u32>>,
+    shut_down: bool,
+    fast_charge: bool,
+    failures: HashSet<MockOperation>,
+    event_tx: Option<mpsc::UnboundedSender<HardwareEvent>>,
+}
+
+/// Platform-independent hardware with scriptable readings and injectable
+/// events. Clones share state, so a test can keep a handle after giving the
+/// device its own.
+#[derive(Clone)]
+pub struct MockHardware {
+    state: Arc<Mutex<MockState>>,
+    /// Slowly drain the battery and fill storage, as simulation mode expects
+    drift: bool,
+}
+
+impl Default for MockHardware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockHardware {
+    /// Static readings that only change when scripted
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                battery_level: 100.0,
+                battery_script: VecDeque::new(),
+                charging: false,
+                temperature: 35.0,
+                storage_used: 0,
+                leds: HashMap::new(),
+                led_brightness: HashMap::new(),
+                vibrations: Vec::new(),
+                tone: None,
+                shut_down: false,
+                fast_charge: true,
+                failures: HashSet::new(),
+                event_tx: None,
+            })),
+            drift: false,
+        }
+    }
+
+    /// Hardware for simulation mode, with battery and storage drifting over time
+    pub fn simulated() -> Self {
+        Self { drift: true, ..Self::new() }
+    }
+
+    pub fn set_battery_level(&self, level: f32) {
+        let mut state = self.state.lock().unwrap();
+        state.battery_script.clear();
+        state.battery_level = level;
+    }
+
+    /// Return these levels from successive reads, then stay on the last one
+    pub fn script_battery_levels(&self, levels: impl IntoIterator<Item = f32>) {
+        self.state.lock().unwrap().battery_script = levels.into_iter().collect();
+    }
+
+    pub fn set_charging(&self, charging: bool) {
+        self.state.lock().unwrap().charging = charging;
+    }
+
+    pub fn set_temperature(&self, temperature: f32) {
+        self.state.lock().unwrap().temperature = temperature;
+    }
+
+    pub fn set_storage_used(&self, used: u64) {
+        self.state.lock().unwrap().storage_used = used.min(STORAGE_TOTAL);
+    }
+
+    /// Make every call of `operation` fail until cleared
+    pub fn fail(&self, operation: MockOperation) {
+        self.state.lock().unwrap().failures.insert(operation);
+    }
+
+    pub fn clear_failure(&self, operation: MockOperation) {
+        self.state.lock().unwrap().failures.remove(&operation);
+    }
+
+    /// Deliver an event to the monitoring channel
+    pub fn inject(&self, event: HardwareEvent) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        let tx = state.event_tx.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Hardware monitoring has not started"))?;
+        tx.send(event).map_err(|_| anyhow::anyhow!("Hardware event receiver dropped"))
+    }
+
+    pub fn led(&self, name: &str) -> Option<LedState> {
+        self.state.lock().unwrap().leds.get(name).cloned()
+    }
+
+    pub fn led_brightness(&self, name: &str) -> Option<u8> {
+        self.state.lock().unwrap().led_brightness.get(name).copied()
+    }
+
+    /// Durations of every vibration so far
+    pub fn vibrations(&self) -> Vec<u64> {
+        self.state.lock().unwrap().vibrations.clone()
+    }
+
+    /// Frequency of the tone playing, if any
+    pub fn tone(&self) -> Option<Option<u32>> {
+        self.state.lock().unwrap().tone
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.state.lock().unwrap().shut_down
+    }
+
+    pub fn fast_charge_allowed(&self) -> bool {
+        self.state.lock().unwrap().fast_charge
+    }
+
+    fn check(&self, operation: MockOperation) -> Result<()> {
+        if self.state.lock().unwrap().failures.contains(&operation) {
+            return Err(anyhow::anyhow!("Mock hardware: {:?} failed", operation));
+        }
+        Ok(())
+    }
+
+    fn spawn_drift(&self, tx: mpsc::UnboundedSender<HardwareEvent>) {
+        let state = Arc::clone(&self.state);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+
+            loop {
+                interval.tick().await;
+
+                let events = {
+                    let mut state = state.lock().unwrap();
+                    let mut events = Vec::new();
+
+                    if !state.charging {
+                        state.battery_level = (state.battery_level - 0.1).max(0.0);
+                        if state.battery_level < 5.0 {
+                            events.push(HardwareEvent::BatteryCritical { level: state.battery_level });
+                        } else if state.battery_level < 20.0 {
+                            events.push(HardwareEvent::BatteryLow { level: state.battery_level });
+                        }
+                    }
+
+                    state.storage_used = (state.storage_used + 10_000_000).min(STORAGE_TOTAL); // 10MB per interval
+                    let disk_full = crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull);
+                    if state.storage_used > 50_000_000_000 || disk_full { // 50GB
+                        events.push(HardwareEvent::StorageFull);
+                    }
+                    events
+                };
+
+                for event in events {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl HardwareInterface for MockHardware {
+    async fn init(&mut self, _config: &HardwareConfig) -> Result<()> {
+        self.check(MockOperation::Init)?;
+        tracing::info!("Using mock hardware");
+        Ok(())
+    }
+
+    async fn start_monitoring(&self) -> Result<mpsc::UnboundedReceiver<HardwareEvent>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        if self.drift {
+            self.spawn_drift(tx.clone());
+        }
+        self.state.lock().unwrap().event_tx = Some(tx);
+        Ok(rx)
+    }
+
+    async fn set_led(&self, led: &str, state: LedState) -> Result<()> {
+        self.check(MockOperation::SetLed)?;
+        self.state.lock().unwrap().leds.insert(led.to_string(), state);
+        Ok(())
+    }
+
+    async fn set_led_brightness(&self, led: &str, percent: u8) -> Result<()> {
+        self.check(MockOperation::SetLed)?;
+        self.state.lock().unwrap().led_brightness.insert(led.to_string(), percent);
+        Ok(())
+    }
+
+    async fn get_battery_level(&self) -> Result<f32> {
+        self.check(MockOperation::Battery)?;
+        let mut state = self.state.lock().unwrap();
+        if let Some(level) = state.battery_script.pop_front() {
+            state.battery_level = level;
+        }
+        Ok(state.battery_level)
+    }
+
+    async fn get_battery_info(&self) -> Result<BatteryInfo> {
+        let level = self.get_battery_level().await?;
+        let charging = self.state.lock().unwrap().charging;
+        Ok(BatteryInfo {
+            present: true,
+            capacity_percent: Some(level),
+            voltage_v: None,
+            current_ma: None,
+            temperature_c: None,
+            status: Some(if charging { "Charging" } else { "Discharging" }.to_string()),
+            health: None,
+            cycle_count: None,
+            full_capacity_mah: None,
+            design_capacity_mah: None,
+            charging,
+            external_power: charging,
+        })
+    }
+
+    async fn get_storage_info(&self) -> Result<StorageInfo> {
+        self.check(MockOperation::Storage)?;
+        let used = if crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull) {
+            STORAGE_TOTAL
+        } else {
+            self.state.lock().unwrap().storage_used
+        };
+        let available = STORAGE_TOTAL.saturating_sub(used);
+
+        Ok(StorageInfo {
+            total: STORAGE_TOTAL,
+            used,
+            available,
+            recording_space: available,
+        })
+    }
+
+    async fn get_temperature(&self) -> Result<f32> {
+        self.check(MockOperation::Temperature)?;
+        Ok(self.state.lock().unwrap().temperature)
+    }
+
+    async fn is_charging(&self) -> Result<bool> {
+        self.check(MockOperation::Battery)?;
+        Ok(self.state.lock().unwrap().charging)
+    }
+
+    async fn vibrate(&self, duration_ms: u64) -> Result<()> {
+        self.check(MockOperation::Vibrate)?;
+        self.state.lock().unwrap().vibrations.push(duration_ms);
+        Ok(())
+    }
+
+    async fn start_tone(&self, frequency_hz: Option<u32>) -> Result<()> {
+        self.check(MockOperation::Tone)?;
+        self.state.lock().unwrap().tone = Some(frequency_hz);
+        Ok(())
+    }
+
+    async fn stop_tone(&self) -> Result<()> {
+        self.check(MockOperation::Tone)?;
+        self.state.lock().unwrap().tone = None;
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        self.check(MockOperation::Shutdown)?;
+        self.state.lock().unwrap().shut_down = true;
+        Ok(())
+    }
+
+    async fn set_fast_charge(&self, allowed: bool) -> Result<()> {
+        self.check(MockOperation::Battery)?;
+        self.state.lock().unwrap().fast_charge = allowed;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scripted_battery_then_steady() {
+        let hardware = MockHardware::new();
+        hardware.script_battery_levels([50.0, 40.0]);
+
+        assert_eq!(hardware.get_battery_level().await.unwrap(), 50.0);
+        assert_eq!(hardware.get_battery_level().await.unwrap(), 40.0);
+        assert_eq!(hardware.get_battery_level().await.unwrap(), 40.0);
+    }
+
+    #[tokio::test]
+    async fn test_injected_events_and_failures() {
+        let hardware = MockHardware::new();
+        assert!(hardware.inject(HardwareEvent::TamperDetected).is_err());
+
+        let mut events = hardware.start_monitoring().await.unwrap();
+        let handle = hardware.clone();
+        handle.inject(HardwareEvent::ChargingConnected).unwrap();
+        assert!(matches!(events.recv().await, Some(HardwareEvent::ChargingConnected)));
+
+        handle.fail(MockOperation::Vibrate);
+        assert!(hardware.vibrate(100).await.is_err());
+        handle.clear_failure(MockOperation::Vibrate);
+        hardware.vibrate(200).await.unwrap();
+        assert_eq!(handle.vibrations(), vec![200]);
+    }
+}
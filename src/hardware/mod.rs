@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
@@ -7,8 +8,24 @@ use tokio::sync::mpsc;
 pub mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
+pub mod mock;
+pub mod led;
+pub mod buzzer;
+pub mod display;
+pub mod haptics;
+pub mod power_supply;
+pub mod charging;
+pub mod brightness;
 
+use buzzer::BuzzerConfig;
+use haptics::HapticsConfig;
+use power_supply::BatteryInfo;
+use display::DisplayPanel;
+
+/// Pins, LEDs and peripherals of the board, from `hardware.toml`. Sections
+/// left out keep their defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HardwareConfig {
     pub gpio: GpioConfig,
     pub camera: CameraConfig,
@@ -17,6 +34,8 @@ pub struct HardwareConfig {
     pub leds: LedConfig,
     pub buttons: ButtonConfig,
     pub display: DisplayConfig,
+    pub buzzer: BuzzerConfig,
+    pub haptics: HapticsConfig,
 }
 
 impl Default for HardwareConfig {
@@ -29,11 +48,32 @@ impl Default for HardwareConfig {
             leds: LedConfig::default(),
             buttons: ButtonConfig::default(),
             display: DisplayConfig::default(),
+            buzzer: BuzzerConfig::default(),
+            haptics: HapticsConfig::default(),
+        }
+    }
+}
+
+impl HardwareConfig {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("hardware.toml")
+    }
+
+    /// The board's configuration, or the defaults when there's no file
+    pub async fn load(path: &Path) -> Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Invalid hardware configuration in {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GpioConfig {
     pub enabled: bool,
     pub pins: Vec<GpioPin>,
@@ -56,7 +96,9 @@ impl Default for GpioConfig {
 pub struct GpioPin {
     pub number: u32,
     pub direction: GpioDirection,
+    #[serde(default)]
     pub active_low: bool,
+    #[serde(default)]
     pub description: String,
     pub function: PinFunction,
 }
@@ -75,7 +117,7 @@ pub enum PinFunction {
     Buzzer,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ButtonType {
     Record,
     Emergency,
@@ -171,6 +213,15 @@ pub struct LedConfig {
     pub leds: Vec<Led>,
 }
 
+impl Default for LedConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            leds: Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Led {
     pub name: String,
@@ -209,6 +260,27 @@ pub struct DisplayConfig {
     pub width: u32,
     pub height: u32,
     pub color_depth: u8,
+    pub panel: DisplayPanel,
+    pub screen_timeout_seconds: u64,
+    pub burn_in_shift_interval_seconds: u64,
+    /// sysfs backlight directory; the first under /sys/class/backlight if unset
+    pub backlight_path: Option<String>,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device_path: "/dev/fb1".to_string(),
+            width: 128,
+            height: 64,
+            color_depth: 1,
+            panel: DisplayPanel::Oled,
+            screen_timeout_seconds: 30,
+            burn_in_shift_interval_seconds: 60,
+            backlight_path: None,
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -216,12 +288,19 @@ pub trait HardwareInterface: Send + Sync {
     async fn init(&mut self, config: &HardwareConfig) -> Result<()>;
     async fn start_monitoring(&self) -> Result<mpsc::UnboundedReceiver<HardwareEvent>>;
     async fn set_led(&self, led: &str, state: LedState) -> Result<()>;
+    /// Dim an LED, where the hardware allows it
+    async fn set_led_brightness(&self, led: &str, percent: u8) -> Result<()>;
     async fn get_battery_level(&self) -> Result<f32>;
+    async fn get_battery_info(&self) -> Result<BatteryInfo>;
     async fn get_storage_info(&self) -> Result<StorageInfo>;
     async fn get_temperature(&self) -> Result<f32>;
     async fn is_charging(&self) -> Result<bool>;
     async fn vibrate(&self, duration_ms: u64) -> Result<()>;
+    async fn start_tone(&self, frequency_hz: Option<u32>) -> Result<()>;
+    async fn stop_tone(&self) -> Result<()>;
     async fn shutdown(&self) -> Result<()>;
+    /// Allow or refuse fast charging, e.g. while the battery is hot
+    async fn set_fast_charge(&self, allowed: bool) -> Result<()>;
 }
 
 #[derive(Debug, Clone)]
@@ -261,6 +340,18 @@ pub enum HardwareEvent {
     TemperatureHigh {
         temp: f32,
     },
+    /// Battery too hot while charging
+    BatteryOverheating {
+        temp: f32,
+    },
+    /// Charge current far too high, or next to none while charging
+    ChargeCurrentAnomaly {
+        current_ma: f32,
+    },
+    /// Full capacity falling fast enough to suggest a swelling battery
+    BatteryCapacityDrop {
+        drop_percent: f32,
+    },
     StorageFull,
     TamperDetected,
     SensorError {
@@ -293,18 +384,16 @@ pub struct StorageInfo {
 
 pub fn create_hardware_interface(simulation: bool) -> Box<dyn HardwareInterface> {
     if simulation {
-        #[cfg(target_os = "linux")]
-        return Box::new(linux::LinuxHardware::new(true));
-        #[cfg(target_os = "macos")]
-        return Box::new(macos::MacHardware::new(true));
-        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-        return Box::new(macos::MacHardware::new(true));
-    } else {
-        #[cfg(target_os = "linux")]
-        return Box::new(linux::LinuxHardware::new(false));
-        #[cfg(target_os = "macos")]
-        return Box::new(macos::MacHardware::new(false));
-        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-        return Box::new(macos::MacHardware::new(false));
+        return Box::new(mock::MockHardware::simulated());
+    }
+
+    #[cfg(target_os = "linux")]
+    return Box::new(linux::LinuxHardware::new(false));
+    #[cfg(target_os = "macos")]
+    return Box::new(macos::MacHardware::new(false));
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        tracing::warn!("No hardware support on this platform, using mock hardware");
+        Box::new(mock::MockHardware::new())
     }
 }
\ No newline at end of file
@@ -0,0 +1,265 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use super::{BatteryConfig, TemperatureConfig};
+
+const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+const THERMAL_ROOT: &str = "/sys/class/thermal";
+
+/// Battery details read from the kernel power supply class. Every field is
+/// optional because drivers expose wildly different subsets of attributes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    pub present: bool,
+    pub capacity_percent: Option<f32>,
+    pub voltage_v: Option<f32>,
+    pub current_ma: Option<f32>,
+    pub temperature_c: Option<f32>,
+    pub status: Option<String>,
+    pub health: Option<String>,
+    pub cycle_count: Option<u32>,
+    /// What the battery holds when full now, and what it held when new
+    pub full_capacity_mah: Option<f32>,
+    pub design_capacity_mah: Option<f32>,
+    pub charging: bool,
+    pub external_power: bool,
+}
+
+/// Reads battery and thermal values from sysfs, falling back to autodetected
+/// nodes when the configured paths are missing.
+#[derive(Debug, Clone)]
+pub struct PowerSupplyReader {
+    battery_dir: Option<PathBuf>,
+    capacity_path: Option<PathBuf>,
+    voltage_path: Option<PathBuf>,
+    current_path: Option<PathBuf>,
+    battery_temp_path: Option<PathBuf>,
+    thermal_path: Option<PathBuf>,
+    supply_root: PathBuf,
+}
+
+impl PowerSupplyReader {
+    pub fn new(battery: Option<&BatteryConfig>, temperature: Option<&TemperatureConfig>) -> Self {
+        let configured = |enabled: bool, path: &str| {
+            if enabled && !path.is_empty() {
+                Some(PathBuf::from(path))
+            } else {
+                None
+            }
+        };
+
+        let battery_enabled = battery.map(|b| b.enabled).unwrap_or(false);
+
+        Self {
+            battery_dir: None,
+            capacity_path: battery.and_then(|b| configured(battery_enabled, &b.capacity_path)),
+            voltage_path: battery.and_then(|b| configured(battery_enabled, &b.voltage_path)),
+            current_path: battery.and_then(|b| configured(battery_enabled, &b.current_path)),
+            battery_temp_path: battery.and_then(|b| configured(battery_enabled, &b.temperature_path)),
+            thermal_path: temperature.and_then(|t| configured(t.enabled, &t.device_path)),
+            supply_root: PathBuf::from(POWER_SUPPLY_ROOT),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_root(mut self, supply_root: PathBuf) -> Self {
+        self.supply_root = supply_root;
+        self
+    }
+
+    /// Locate the first supply of type "Battery" under the power supply root
+    pub async fn detect(&mut self) -> Result<()> {
+        self.battery_dir = None;
+
+        if !self.supply_root.exists() {
+            tracing::warn!("{} not available, battery readings disabled", self.supply_root.display());
+            return Ok(());
+        }
+
+        let mut entries = fs::read_dir(&self.supply_root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if read_string(&path.join("type")).await.as_deref() == Some("Battery") {
+                tracing::info!("Using battery at {}", path.display());
+                self.battery_dir = Some(path);
+                break;
+            }
+        }
+
+        if self.thermal_path.is_none() {
+            let zone = Path::new(THERMAL_ROOT).join("thermal_zone0/temp");
+            if zone.exists() {
+                self.thermal_path = Some(zone);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn battery_node(&self, configured: &Option<PathBuf>, name: &str) -> Option<PathBuf> {
+        configured
+            .clone()
+            .filter(|p| p.exists())
+            .or_else(|| self.battery_dir.as_ref().map(|dir| dir.join(name)))
+    }
+
+    pub async fn read_battery(&self) -> BatteryInfo {
+        let mut info = BatteryInfo::default();
+
+        let dir = self.battery_dir.clone();
+        let attr = |name: &str| dir.as_ref().map(|d| d.join(name));
+
+        info.present = match attr("present") {
+            Some(path) => read_string(&path).await.map(|v| v == "1").unwrap_or(true),
+            None => self.capacity_path.as_ref().map(|p| p.exists()).unwrap_or(false),
+        };
+
+        info.capacity_percent = match self.battery_node(&self.capacity_path, "capacity") {
+            Some(path) => read_number(&path).await.map(|v| v.clamp(0.0, 100.0) as f32),
+            None => None,
+        };
+
+        // Some fuel gauges only report energy or charge counters
+        if info.capacity_percent.is_none() {
+            if let Some(dir) = &dir {
+                for (now, full) in [("energy_now", "energy_full"), ("charge_now", "charge_full")] {
+                    if let (Some(now), Some(full)) = (read_number(&dir.join(now)).await, read_number(&dir.join(full)).await) {
+                        if full > 0.0 {
+                            info.capacity_percent = Some(((now / full) * 100.0).clamp(0.0, 100.0) as f32);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Kernel reports microvolts, microamps and tenths of a degree
+        if let Some(path) = self.battery_node(&self.voltage_path, "voltage_now") {
+            info.voltage_v = read_number(&path).await.map(|uv| (uv / 1_000_000.0) as f32);
+        }
+        if let Some(path) = self.battery_node(&self.current_path, "current_now") {
+            info.current_ma = read_number(&path).await.map(|ua| (ua / 1_000.0) as f32);
+        }
+        if let Some(path) = self.battery_node(&self.battery_temp_path, "temp") {
+            info.temperature_c = read_number(&path).await.map(|t| (t / 10.0) as f32);
+        }
+
+        if let Some(dir) = &dir {
+            info.status = read_string(&dir.join("status")).await;
+            info.health = read_string(&dir.join("health")).await;
+            info.cycle_count = read_number(&dir.join("cycle_count")).await.map(|c| c as u32);
+            // Microamp-hours
+            info.full_capacity_mah = read_number(&dir.join("charge_full")).await.map(|uah| (uah / 1_000.0) as f32);
+            info.design_capacity_mah = read_number(&dir.join("charge_full_design")).await.map(|uah| (uah / 1_000.0) as f32);
+        }
+
+        info.external_power = self.external_power_online().await;
+        info.charging = match info.status.as_deref() {
+            Some("Charging") => true,
+            Some(_) => false,
+            None => info.external_power,
+        };
+
+        info
+    }
+
+    async fn external_power_online(&self) -> bool {
+        let Ok(mut entries) = fs::read_dir(&self.supply_root).await else {
+            return false;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let supply_type = read_string(&path.join("type")).await;
+            if matches!(supply_type.as_deref(), Some("Mains") | Some("USB") | Some("USB_PD")) {
+                if read_string(&path.join("online")).await.as_deref() == Some("1") {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Switch the charger between fast and standard charging, on drivers
+    /// that expose `charge_type`
+    pub async fn set_fast_charge(&self, allowed: bool) -> Result<()> {
+        let path = self.battery_dir.as_ref()
+            .map(|dir| dir.join("charge_type"))
+            .filter(|path| path.exists())
+            .ok_or_else(|| anyhow::anyhow!("Charger doesn't support selecting the charge type"))?;
+        fs::write(&path, if allowed { "Fast" } else { "Standard" }).await?;
+        Ok(())
+    }
+
+    /// SoC temperature in Celsius from the thermal zone (reported in millidegrees)
+    pub async fn read_temperature(&self) -> Option<f32> {
+        let path = self.thermal_path.as_ref()?;
+        read_number(path).await.map(|t| (t / 1000.0) as f32)
+    }
+}
+
+async fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).await.ok().map(|s| s.trim().to_string())
+}
+
+async fn read_number(path: &Path) -> Option<f64> {
+    read_string(path).await.and_then(|s| s.parse::<f64>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn write(dir: &Path, name: &str, value: &str) {
+        fs::write(dir.join(name), value).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reads_battery_attributes() {
+        let root = tempfile::tempdir().unwrap();
+        let bat = root.path().join("BAT0");
+        fs::create_dir_all(&bat).await.unwrap();
+        write(&bat, "type", "Battery\n").await;
+        write(&bat, "capacity", "87\n").await;
+        write(&bat, "voltage_now", "3950000\n").await;
+        write(&bat, "current_now", "-450000\n").await;
+        write(&bat, "status", "Discharging\n").await;
+        write(&bat, "health", "Good\n").await;
+        write(&bat, "cycle_count", "112\n").await;
+        write(&bat, "charge_full", "2850000\n").await;
+
+        let mut reader = PowerSupplyReader::new(None, None).with_root(root.path().to_path_buf());
+        reader.detect().await.unwrap();
+        let info = reader.read_battery().await;
+
+        assert!(info.present);
+        assert_eq!(info.capacity_percent, Some(87.0));
+        assert_eq!(info.voltage_v, Some(3.95));
+        assert_eq!(info.current_ma, Some(-450.0));
+        assert_eq!(info.health.as_deref(), Some("Good"));
+        assert_eq!(info.cycle_count, Some(112));
+        assert_eq!(info.full_capacity_mah, Some(2850.0));
+        assert!(!info.charging);
+    }
+
+    #[tokio::test]
+    async fn test_missing_nodes_degrade_gracefully() {
+        let root = tempfile::tempdir().unwrap();
+        let bat = root.path().join("battery");
+        fs::create_dir_all(&bat).await.unwrap();
+        write(&bat, "type", "Battery").await;
+        write(&bat, "charge_now", "1500000").await;
+        write(&bat, "charge_full", "3000000").await;
+
+        let mut reader = PowerSupplyReader::new(None, None).with_root(root.path().to_path_buf());
+        reader.detect().await.unwrap();
+        let info = reader.read_battery().await;
+
+        assert_eq!(info.capacity_percent, Some(50.0));
+        assert!(info.voltage_v.is_none());
+        assert!(info.cycle_count.is_none());
+    }
+}
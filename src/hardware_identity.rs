@@ -0,0 +1,235 @@
+//! Binds a device's credentials to the hardware they were issued to. The
+//! fingerprint is taken from the OS machine id, the CPU serial and the TPM
+//! endorsement key. It is recorded when the device is provisioned and sent
+//! with a signed claim whenever the device authenticates. A `config.toml`
+//! copied to other hardware then shows up as a mismatch, both here and on
+//! the platform.
+//!
+//! On the device the identifiers are compared one by one, so one that
+//! can't be read this time, e.g. after a failed `tpm2_readpublic`, or one
+//! that changed, e.g. a machine id regenerated by a re-image, isn't taken
+//! for other hardware while another still matches.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Persistent handle where the TPM endorsement key is conventionally kept
+const TPM_EK_HANDLE: &str = "0x81010001";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareBindingConfig {
+    pub enabled: bool,
+    /// Refuse to use the platform credentials on mismatched hardware,
+    /// instead of only reporting it
+    pub block_on_mismatch: bool,
+    /// Fingerprint of the hardware the credentials were issued to
+    pub bound_fingerprint: Option<String>,
+    /// Hash of each identifier of that hardware, by name
+    #[serde(default)]
+    pub bound_components: BTreeMap<String, String>,
+}
+
+impl Default for HardwareBindingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            block_on_mismatch: false,
+            bound_fingerprint: None,
+            bound_components: BTreeMap::new(),
+        }
+    }
+}
+
+/// Identifiers of the hardware the client runs on; each is None where the
+/// platform doesn't have it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HardwareIdentity {
+    pub machine_id: Option<String>,
+    pub cpu_serial: Option<String>,
+    /// Hash of the TPM endorsement key's public area
+    pub tpm_ek: Option<String>,
+}
+
+impl HardwareIdentity {
+    pub async fn read() -> Self {
+        Self {
+            machine_id: read_first(&["/etc/machine-id", "/var/lib/dbus/machine-id"]).await,
+            cpu_serial: cpu_serial().await,
+            tpm_ek: tpm_endorsement_key().await,
+        }
+    }
+
+    /// None when no identifier could be read, e.g. in a container
+    pub fn fingerprint(&self) -> Option<String> {
+        if self.machine_id.is_none() && self.cpu_serial.is_none() && self.tpm_ek.is_none() {
+            return None;
+        }
+        let mut hasher = Sha256::new();
+        for (name, value) in self.named() {
+            hasher.update(format!("{}={}\n", name, value.as_deref().unwrap_or("")));
+        }
+        Some(hex::encode(hasher.finalize()))
+    }
+
+    /// A hash of each identifier that could be read
+    pub fn components(&self) -> BTreeMap<String, String> {
+        self.named().into_iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| {
+                (name.to_string(), hex::encode(Sha256::digest(format!("{}={}", name, value))))
+            }))
+            .collect()
+    }
+
+    fn named(&self) -> [(&'static str, &Option<String>); 3] {
+        [("machine_id", &self.machine_id), ("cpu_serial", &self.cpu_serial), ("tpm_ek", &self.tpm_ek)]
+    }
+
+    /// The CPU serial where there is one, for the provisioning request
+    pub fn serial_number(&self) -> Option<&str> {
+        self.cpu_serial.as_deref()
+    }
+}
+
+async fn read_first(paths: &[&str]) -> Option<String> {
+    for path in paths {
+        if let Ok(content) = tokio::fs::read_to_string(path).await {
+            let value = content.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+            if !value.is_empty() {
+                return Some(value.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// `Serial` from `/proc/cpuinfo` on ARM boards, or the device tree serial
+async fn cpu_serial() -> Option<String> {
+    let cpuinfo = tokio::fs::read_to_string("/proc/cpuinfo").await.unwrap_or_default();
+    parse_cpu_serial(&cpuinfo)
+        .or(read_first(&["/sys/firmware/devicetree/base/serial-number"]).await)
+}
+
+fn parse_cpu_serial(cpuinfo: &str) -> Option<String> {
+    cpuinfo.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "Serial")
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|serial| !serial.is_empty() && serial.chars().any(|c| c != '0'))
+}
+
+/// The TPM's name for its endorsement key, read with tpm2-tools
+async fn tpm_endorsement_key() -> Option<String> {
+    if !std::path::Path::new("/dev/tpmrm0").exists() && !std::path::Path::new("/dev/tpm0").exists() {
+        return None;
+    }
+    let output = tokio::process::Command::new("tpm2_readpublic")
+        .args(["-c", TPM_EK_HANDLE])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        tracing::debug!("No TPM endorsement key at {}", TPM_EK_HANDLE);
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).lines()
+        .find_map(|line| line.strip_prefix("name:"))
+        .map(|name| name.trim().to_string())
+}
+
+/// Claim sent with authentication: the fingerprint, signed with the device
+/// key over the same timestamp and nonce as the request
+pub fn attestation(device_key: &str, device_id: &str, timestamp: i64, nonce: &str, fingerprint: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(device_key.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(format!("{}:{}:{}:{}", device_id, timestamp, nonce, fingerprint).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether the credentials in use were issued to this hardware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingCheck {
+    /// Not provisioned yet, binding is off, or no identifier is readable
+    Unbound,
+    Matches,
+    Mismatch { bound: String, current: String },
+}
+
+/// Compared identifier by identifier: any identifier readable both then and
+/// now that still matches means the same hardware, and identifiers missing
+/// on either side are left out. Only when every one that can be compared
+/// differs is it other hardware.
+pub fn check_binding(config: &HardwareBindingConfig, current: &HardwareIdentity) -> BindingCheck {
+    let (Some(bound), Some(fingerprint)) = (config.bound_fingerprint.as_deref(), current.fingerprint()) else {
+        return BindingCheck::Unbound;
+    };
+    if !config.enabled {
+        return BindingCheck::Unbound;
+    }
+    let mismatch = || BindingCheck::Mismatch { bound: bound.to_string(), current: fingerprint.clone() };
+
+    if config.bound_components.is_empty() {
+        // Bound before identifiers were kept separately: only the whole
+        // fingerprint, which can't be trusted unless every identifier was read
+        let complete = current.named().iter().all(|(_, value)| value.is_some());
+        return if bound == fingerprint {
+            BindingCheck::Matches
+        } else if complete {
+            mismatch()
+        } else {
+            BindingCheck::Unbound
+        };
+    }
+    let components = current.components();
+    let compared: Vec<bool> = config.bound_components.iter()
+        .filter_map(|(name, hash)| components.get(name).map(|current| current == hash))
+        .collect();
+    match compared {
+        compared if compared.is_empty() => BindingCheck::Unbound,
+        compared if compared.contains(&true) => BindingCheck::Matches,
+        _ => mismatch(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_and_binding() {
+        let identity = HardwareIdentity {
+            machine_id: Some("4c4c4544".to_string()),
+            cpu_serial: parse_cpu_serial("processor\t: 0\nSerial\t\t: 10000000abcdef12\n"),
+            tpm_ek: None,
+        };
+        assert_eq!(identity.serial_number(), Some("10000000abcdef12"));
+        assert_eq!(parse_cpu_serial("Serial\t: 0000000000000000\n"), None);
+        assert_eq!(HardwareIdentity::default().fingerprint(), None);
+
+        let fingerprint = identity.fingerprint().unwrap();
+        let copied = HardwareIdentity { machine_id: Some("other".to_string()), ..identity.clone() };
+        assert_ne!(copied.fingerprint().unwrap(), fingerprint);
+
+        let config = HardwareBindingConfig {
+            bound_fingerprint: Some(fingerprint.clone()),
+            bound_components: identity.components(),
+            ..HardwareBindingConfig::default()
+        };
+        assert_eq!(check_binding(&config, &identity), BindingCheck::Matches);
+        // A re-imaged machine id, or an identifier that couldn't be read, is
+        // still the same hardware
+        assert_eq!(check_binding(&config, &copied), BindingCheck::Matches);
+        let unreadable = HardwareIdentity { cpu_serial: None, ..identity.clone() };
+        assert_eq!(check_binding(&config, &unreadable), BindingCheck::Matches);
+        let other = HardwareIdentity {
+            machine_id: Some("other".to_string()),
+            cpu_serial: Some("20000000fedcba98".to_string()),
+            tpm_ek: Some("000b1234".to_string()),
+        };
+        assert!(matches!(check_binding(&config, &other), BindingCheck::Mismatch { .. }));
+        assert_eq!(check_binding(&config, &HardwareIdentity::default()), BindingCheck::Unbound);
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::config::NetworkConfig;
+
+/// Encoding applied to status and metrics bodies when `network.compression`
+/// is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestCompression {
+    #[default]
+    Gzip,
+    Zstd,
+}
+
+impl RequestCompression {
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            RequestCompression::Gzip => "gzip",
+            RequestCompression::Zstd => "zstd",
+        }
+    }
+
+    pub fn encode(&self, body: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            RequestCompression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body)?;
+                Ok(encoder.finish()?)
+            }
+            RequestCompression::Zstd => zstd::encode_all(body, 0).context("zstd compression failed"),
+        }
+    }
+}
+
+/// The settings that shape a connection pool; clients with the same
+/// settings share one pool
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PoolSettings {
+    max_idle_per_host: usize,
+    idle_timeout_seconds: u64,
+    tcp_keepalive_seconds: u64,
+    http2: bool,
+    timeout_seconds: u64,
+}
+
+impl PoolSettings {
+    fn from_config(network: &NetworkConfig) -> Self {
+        // The client-wide timeout has to allow the slowest override; each
+        // request is then held to its own endpoint's limit
+        let longest_override = network.endpoint_timeouts.values().copied().max().unwrap_or(0);
+        Self {
+            max_idle_per_host: network.pool_max_idle_per_host,
+            idle_timeout_seconds: network.pool_idle_timeout_seconds,
+            tcp_keepalive_seconds: network.tcp_keepalive_seconds,
+            http2: network.http2,
+            timeout_seconds: network.timeout.max(longest_override),
+        }
+    }
+
+    fn build(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(self.timeout_seconds))
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(self.idle_timeout_seconds))
+            .tcp_keepalive(Duration::from_secs(self.tcp_keepalive_seconds))
+            .https_only(true)
+            .danger_accept_invalid_certs(false);
+        if self.http2 {
+            // Negotiated over ALPN (reqwest's `native-tls-alpn` feature), so
+            // HTTP/1.1-only servers still work
+            builder = builder.http2_adaptive_window(true);
+        } else {
+            builder = builder.http1_only();
+        }
+        builder.build().context("Failed to create HTTP client")
+    }
+}
+
+/// A client for `network`, reused across `ApiClient`s so connections stay
+/// pooled and kept alive between calls
+pub fn shared_client(network: &NetworkConfig) -> Result<Client> {
+    static CLIENTS: OnceLock<Mutex<HashMap<PoolSettings, Client>>> = OnceLock::new();
+
+    let settings = PoolSettings::from_config(network);
+    let mut clients = CLIENTS.get_or_init(Default::default).lock().unwrap();
+    if let Some(client) = clients.get(&settings) {
+        return Ok(client.clone());
+    }
+    let client = settings.build()?;
+    clients.insert(settings, client.clone());
+    Ok(client)
+}
+
+/// Timeout for a request to `endpoint` (see `circuit_breaker::endpoint_key`).
+/// Overrides are keyed by path pattern, e.g. `/api/media/*/upload`.
+pub fn endpoint_timeout(network: &NetworkConfig, endpoint: &str) -> Duration {
+    let seconds = network.endpoint_timeouts.iter()
+        .filter(|(pattern, _)| endpoint.ends_with(pattern.as_str()))
+        .max_by_key(|(pattern, _)| pattern.len())
+        .map(|(_, seconds)| *seconds)
+        .unwrap_or(network.timeout);
+    Duration::from_secs(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::io::Read;
+
+    #[test]
+    fn test_compression_round_trips() {
+        let body = serde_json::to_vec(&serde_json::json!({"battery_level": 87.5, "recording": true})).unwrap();
+        let encoded = RequestCompression::Gzip.encode(&body).unwrap();
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(encoded.as_slice()).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, body);
+
+        let encoded = RequestCompression::Zstd.encode(&body).unwrap();
+        assert_eq!(zstd::decode_all(encoded.as_slice()).unwrap(), body);
+    }
+
+    #[test]
+    fn test_most_specific_timeout_override_wins() {
+        let mut network = Config::default().network;
+        network.timeout = 30;
+        network.endpoint_timeouts.insert("/upload".to_string(), 300);
+        network.endpoint_timeouts.insert("/api/media/*/upload".to_string(), 600);
+
+        assert_eq!(endpoint_timeout(&network, "api.example.com/api/media/*/upload"), Duration::from_secs(600));
+        assert_eq!(endpoint_timeout(&network, "api.example.com/api/streams/*/upload"), Duration::from_secs(300));
+        assert_eq!(endpoint_timeout(&network, "api.example.com/api/incidents"), Duration::from_secs(30));
+    }
+}
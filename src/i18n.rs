@@ -0,0 +1,314 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Language used when no pack is configured, and for any key a pack is missing
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Built-in English strings. Packs translate any subset of these keys.
+const ENGLISH: &[(&str, &str)] = &[
+    ("ui.title", "PatrolSight Bodycam"),
+    ("ui.recording", "RECORDING"),
+    ("ui.recording_audio", "RECORDING AUDIO"),
+    ("ui.camera_off", "CAMERA OFF"),
+    ("ui.start_recording", "Start Recording"),
+    ("ui.stop_recording", "Stop Recording"),
+    ("ui.pause_recording", "Pause"),
+    ("ui.resume_recording", "Resume"),
+    ("ui.emergency", "Emergency"),
+    ("ui.cancel_emergency", "Cancel Emergency"),
+    ("ui.live_watching", "LIVE — {count} watching"),
+    ("ui.live_no_viewers", "LIVE — no viewers"),
+    ("ui.talking", "{name} is talking"),
+    ("ui.status", "Status:"),
+    ("ui.battery", "Battery:"),
+    ("ui.storage", "Storage:"),
+    ("ui.time", "Time:"),
+    ("ui.mic", "Mic:"),
+    ("ui.camera_settings", "Camera Settings"),
+    ("ui.camera_device", "Camera Device:"),
+    ("ui.audio_device", "Audio Device:"),
+    ("ui.resolution", "Resolution:"),
+    ("ui.fps", "FPS:"),
+    ("ui.brightness", "Brightness:"),
+    ("ui.zoom", "Zoom:"),
+    ("ui.auto_exposure", "Auto Exposure"),
+    ("ui.auto_focus", "Auto Focus"),
+    ("ui.focus", "Focus:"),
+    ("ui.ir_mode", "IR Mode:"),
+    ("ui.recording_settings", "Recording Settings"),
+    ("ui.simulation_mode", "Simulation Mode"),
+    ("ui.audio_only", "Audio Only"),
+    ("ui.recordings", "Recordings"),
+    ("ui.hold", "HOLD"),
+    ("ui.refresh", "Refresh"),
+    ("ui.network_status", "Network Status"),
+    ("ui.network_offline", "Offline"),
+    ("ui.network_link", "{link} ({interface})"),
+    ("ui.link_wifi", "Wi-Fi"),
+    ("ui.link_cellular", "Cellular"),
+    ("ui.link_ethernet", "Ethernet"),
+    ("ui.signal", "Signal: {dbm} dBm"),
+    ("ui.upload_rate", "Upload: {rate} Mbps"),
+    ("ui.upload_queue_empty", "Upload queue empty"),
+    ("ui.upload_queue", "Queue: {count} files"),
+    ("ui.upload_queue_eta", "Queue: {count} files, {eta} left"),
+    ("ui.stream_health", "Stream: {bitrate} kbps, {drops} drops"),
+    ("ui.stream_degraded", "Stream degraded: {bitrate} kbps, {drops} drops"),
+    ("ui.language", "Language:"),
+    ("ui.theme", "Theme:"),
+    ("ui.footer", "PatrolSight Security Systems"),
+    ("ui.settings", "Settings"),
+    ("ui.apply", "Apply"),
+    ("ui.revert", "Revert"),
+    ("ui.locked", "Locked by site policy"),
+    ("ui.restart_required", "Restart the device for some changes to take effect"),
+    ("ui.incident", "Incident"),
+    ("ui.incident_type", "Type"),
+    ("ui.severity", "Severity"),
+    ("ui.raise_incident", "Raise Incident"),
+    ("ui.end_incident", "End Incident"),
+    ("ui.incident_banner", "INCIDENT: {type} ({severity})"),
+    ("ui.confirm_critical", "Raise a CRITICAL incident? Dispatch is alerted immediately."),
+    ("ui.confirm", "Confirm"),
+    ("ui.cancel", "Cancel"),
+    ("ui.kiosk_exit", "Supervisor PIN to exit"),
+    ("ui.kiosk_denied", "PIN not accepted"),
+    ("ui.play", "Play"),
+    ("ui.pause", "Pause"),
+    ("ui.close", "Close"),
+    ("ui.pin", "PIN"),
+    ("ui.contacts", "Contacts"),
+    ("ui.call", "Call"),
+    ("ui.sms", "SMS"),
+    ("ui.contacts_age", "Updated {age} ago"),
+    ("ui.contacts_stale", "Out of date: last updated {age} ago"),
+    ("ui.contacts_never", "Not downloaded yet"),
+    ("ui.contact_sms_sent", "Emergency SMS sent to {name}"),
+    ("ui.contact_calling", "Calling {name}"),
+    ("ui.contact_failed", "Couldn't reach {name}: {error}"),
+    ("ui.quota_daily_sms", "SMS today {used}/{limit}"),
+    ("ui.quota_daily_calls", "Calls today {used}/{limit}"),
+    ("ui.quota_monthly_cost", "Cost this month {used}/{limit}"),
+    ("ui.recording_blocked", "Recording not allowed here: {rule}"),
+    ("ui.recording_required", "Recording required here: {rule}"),
+    ("ui.recording_remaining", "{time} recording left"),
+    ("playback.title", "Review recording"),
+    ("playback.reason", "Reason for viewing"),
+    ("playback.failed", "Can't play this recording: {error}"),
+    ("playback.night_mode_on", "Night mode on"),
+    ("playback.night_mode_off", "Night mode off"),
+    ("setup.title", "Device Setup"),
+    ("setup.wifi", "Connect to Wi-Fi"),
+    ("setup.scan", "Scan"),
+    ("setup.password", "Password"),
+    ("setup.connect", "Connect"),
+    ("setup.skip", "Skip"),
+    ("setup.backend", "Platform address"),
+    ("setup.next", "Next"),
+    ("setup.device_name", "Device name"),
+    ("setup.pair", "Request Pairing"),
+    ("setup.pair_hint", "Scan the code with the console app, or enter the code at the console, to approve this device."),
+    ("setup.done", "This device is ready to use."),
+    ("setup.finish", "Finish"),
+    ("setup.scanning", "Scanning for networks…"),
+    ("setup.scan_failed", "Wi-Fi scan failed: {error}"),
+    ("setup.connecting", "Joining {ssid}…"),
+    ("setup.connect_failed", "Couldn't join {ssid}: {error}"),
+    ("setup.invalid_url", "Check the address: {error}"),
+    ("setup.waiting", "Waiting for approval…"),
+    ("setup.pair_failed", "Pairing failed: {error}"),
+    ("incident.emergency", "Emergency"),
+    ("incident.panic", "Panic"),
+    ("incident.manual", "Manual"),
+    ("incident.vehicle_collision", "Vehicle collision"),
+    ("incident.weapon_detection", "Weapon"),
+    ("severity.low", "Low"),
+    ("severity.medium", "Medium"),
+    ("severity.high", "High"),
+    ("severity.critical", "Critical"),
+    ("settings.resolution", "Resolution"),
+    ("settings.fps", "Frame rate"),
+    ("settings.segment_duration", "Segment length (seconds)"),
+    ("settings.pre_incident_buffer", "Pre-incident buffer (seconds)"),
+    ("settings.encryption", "Encrypt recordings"),
+    ("settings.audio_enabled", "Record audio"),
+    ("settings.upload_wifi_only", "Upload on Wi-Fi only"),
+    ("settings.upload_charging_only", "Upload while charging only"),
+    ("settings.keep_local_copy", "Keep local copy after upload"),
+    ("settings.auto_cleanup_days", "Delete uploaded recordings after (days)"),
+    ("settings.low_power_mode", "Low power mode"),
+    ("settings.voice_prompts", "Voice prompts"),
+    ("settings.large_touch_targets", "Large controls for gloves"),
+    ("prompt.record_start", "Recording started"),
+    ("prompt.record_stop", "Recording stopped"),
+    ("prompt.low_battery", "Battery low"),
+    ("prompt.error", "Device error"),
+    ("prompt.self_test_press_record", "Self-test. If every light is on, press Record"),
+    ("prompt.not_ready", "Self-test failed. Device not ready for duty"),
+    ("alert.tamper", "PatrolSight: device {device} reported tampering"),
+    ("alert.emergency", "PatrolSight: officer with device {device} needs assistance"),
+    ("cli.decommission_warning", "This permanently erases all recordings and logs on this device."),
+    ("cli.decommission_confirm", "To continue, run: decommission --confirm {token}"),
+    ("cli.rollback_confirm", "Are you sure you want to rollback? (y/N): "),
+    ("cli.rollback_cancelled", "Rollback cancelled."),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct I18nConfig {
+    /// Language code of the active pack, e.g. `es`; `en` uses the built-in strings
+    pub language: String,
+    /// Directory holding `<language>.toml` packs, relative to the working directory
+    pub packs_dir: String,
+    /// Speak a short prompt alongside the buzzer tones
+    pub voice_prompts: bool,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            language: DEFAULT_LANGUAGE.to_string(),
+            packs_dir: "lang".to_string(),
+            voice_prompts: false,
+        }
+    }
+}
+
+/// A language pack as stored on disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguagePack {
+    /// Name of the language in that language, shown in the UI picker
+    pub name: String,
+    /// espeak voice for spoken prompts; defaults to the language code
+    pub tts_voice: Option<String>,
+    pub strings: HashMap<String, String>,
+}
+
+/// Looks up strings in the active pack, falling back to English
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    language: String,
+    pack: LanguagePack,
+}
+
+impl Default for Localizer {
+    fn default() -> Self {
+        Self {
+            language: DEFAULT_LANGUAGE.to_string(),
+            pack: LanguagePack::default(),
+        }
+    }
+}
+
+impl Localizer {
+    pub fn packs_dir(config: &I18nConfig) -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(&config.packs_dir)
+    }
+
+    /// The configured language, or English if its pack can't be loaded
+    pub async fn load(config: &I18nConfig) -> Self {
+        match Self::load_language(&Self::packs_dir(config), &config.language).await {
+            Ok(localizer) => localizer,
+            Err(e) => {
+                tracing::warn!("Using English strings: {:#}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub async fn load_language(dir: &Path, language: &str) -> Result<Self> {
+        if language == DEFAULT_LANGUAGE {
+            return Ok(Self::default());
+        }
+        if language.is_empty() || !language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(anyhow::anyhow!("Invalid language code {:?}", language));
+        }
+
+        let path = dir.join(format!("{}.toml", language));
+        let content = tokio::fs::read_to_string(&path).await
+            .with_context(|| format!("No language pack for {} at {}", language, path.display()))?;
+        let pack: LanguagePack = toml::from_str(&content)
+            .with_context(|| format!("Invalid language pack {}", path.display()))?;
+        Ok(Self { language: language.to_string(), pack })
+    }
+
+    /// Language codes with a pack in `dir`, English included
+    pub async fn available(dir: &Path) -> Vec<String> {
+        let mut languages = vec![DEFAULT_LANGUAGE.to_string()];
+        if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) == Some("toml") {
+                    if let Some(language) = path.file_stem().and_then(|stem| stem.to_str()) {
+                        if language != DEFAULT_LANGUAGE {
+                            languages.push(language.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        languages[1..].sort();
+        languages
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn tts_voice(&self) -> &str {
+        self.pack.tts_voice.as_deref().unwrap_or(&self.language)
+    }
+
+    /// The string for `key`; the key itself if no English string exists either
+    pub fn get(&self, key: &str) -> String {
+        self.pack.strings.get(key)
+            .map(String::as_str)
+            .or_else(|| ENGLISH.iter().find(|(k, _)| *k == key).map(|(_, v)| *v))
+            .unwrap_or(key)
+            .to_string()
+    }
+
+    /// `get` with `{name}` placeholders filled in from `args`
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.get(key), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pack_falls_back_to_english() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(
+            dir.path().join("es.toml"),
+            r#"
+name = "Español"
+
+[strings]
+"ui.start_recording" = "Iniciar grabación"
+"alert.tamper" = "PatrolSight: el dispositivo {device} fue manipulado"
+"#,
+        ).await.unwrap();
+
+        let localizer = Localizer::load_language(dir.path(), "es").await.unwrap();
+        assert_eq!(localizer.get("ui.start_recording"), "Iniciar grabación");
+        assert_eq!(localizer.get("ui.stop_recording"), "Stop Recording");
+        assert_eq!(localizer.get("ui.unknown"), "ui.unknown");
+        assert_eq!(
+            localizer.format("alert.tamper", &[("device", "cam-7")]),
+            "PatrolSight: el dispositivo cam-7 fue manipulado"
+        );
+        assert_eq!(localizer.tts_voice(), "es");
+
+        assert_eq!(Localizer::available(dir.path()).await, vec!["en", "es"]);
+        assert!(Localizer::load_language(dir.path(), "fr").await.is_err());
+        assert!(Localizer::load_language(dir.path(), "../es").await.is_err());
+    }
+}
@@ -4,13 +4,16 @@ use uuid::Uuid;
 use chrono::Utc;
 use reqwest::Client;
 
+use crate::api::{with_idempotency_key, IdempotencyKey};
 use crate::config::Config;
+use crate::error_handling::{ApiError, DeviceError};
+use crate::retry::{send_with_retry, RetryPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Incident {
     pub id: String,
     pub device_id: String,
-    pub incident_type: String,
+    pub incident_type: IncidentType,
     pub severity: IncidentSeverity,
     pub status: IncidentStatus,
     pub timestamp: chrono::DateTime<chrono::Utc>,
@@ -27,9 +30,12 @@ pub struct LocationData {
     pub altitude: Option<f64>,
     pub accuracy: Option<f64>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Journal entries written before sources were reported are GPS fixes
+    #[serde(default)]
+    pub source: crate::location::LocationSource,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum IncidentSeverity {
     Low,
@@ -38,6 +44,139 @@ pub enum IncidentSeverity {
     Critical,
 }
 
+impl IncidentSeverity {
+    pub const ALL: &'static [IncidentSeverity] = &[
+        IncidentSeverity::Low,
+        IncidentSeverity::Medium,
+        IncidentSeverity::High,
+        IncidentSeverity::Critical,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IncidentSeverity::Low => "low",
+            IncidentSeverity::Medium => "medium",
+            IncidentSeverity::High => "high",
+            IncidentSeverity::Critical => "critical",
+        }
+    }
+}
+
+impl std::fmt::Display for IncidentSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for IncidentSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL.iter()
+            .copied()
+            .find(|severity| severity.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Invalid incident severity: {:?}", s))
+    }
+}
+
+/// What an incident was raised for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentType {
+    Emergency,
+    Manual,
+    Motion,
+    Sound,
+    Tamper,
+    BatteryLow,
+    StorageFull,
+    ButtonPress,
+    Panic,
+    CameraDisconnected,
+    PersonDetection,
+    VehicleDetection,
+    WeaponDetection,
+    VehicleCollision,
+    HarshBraking,
+    Speeding,
+    LightDetection,
+    SoundDetection,
+    MovementDetection,
+    SpeechDetection,
+    /// A type this client doesn't know, e.g. one added to the platform
+    /// later, so listing incidents or replaying the journal doesn't fail
+    #[serde(other)]
+    Other,
+}
+
+impl IncidentType {
+    pub const ALL: &'static [IncidentType] = &[
+        IncidentType::Emergency,
+        IncidentType::Manual,
+        IncidentType::Motion,
+        IncidentType::Sound,
+        IncidentType::Tamper,
+        IncidentType::BatteryLow,
+        IncidentType::StorageFull,
+        IncidentType::ButtonPress,
+        IncidentType::Panic,
+        IncidentType::CameraDisconnected,
+        IncidentType::PersonDetection,
+        IncidentType::VehicleDetection,
+        IncidentType::WeaponDetection,
+        IncidentType::VehicleCollision,
+        IncidentType::HarshBraking,
+        IncidentType::Speeding,
+        IncidentType::LightDetection,
+        IncidentType::SoundDetection,
+        IncidentType::MovementDetection,
+        IncidentType::SpeechDetection,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IncidentType::Emergency => "emergency",
+            IncidentType::Manual => "manual",
+            IncidentType::Motion => "motion",
+            IncidentType::Sound => "sound",
+            IncidentType::Tamper => "tamper",
+            IncidentType::BatteryLow => "battery_low",
+            IncidentType::StorageFull => "storage_full",
+            IncidentType::ButtonPress => "button_press",
+            IncidentType::Panic => "panic",
+            IncidentType::CameraDisconnected => "camera_disconnected",
+            IncidentType::PersonDetection => "person_detection",
+            IncidentType::VehicleDetection => "vehicle_detection",
+            IncidentType::WeaponDetection => "weapon_detection",
+            IncidentType::VehicleCollision => "vehicle_collision",
+            IncidentType::HarshBraking => "harsh_braking",
+            IncidentType::Speeding => "speeding",
+            IncidentType::LightDetection => "light_detection",
+            IncidentType::SoundDetection => "sound_detection",
+            IncidentType::MovementDetection => "movement_detection",
+            IncidentType::SpeechDetection => "speech_detection",
+            IncidentType::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for IncidentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for IncidentType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL.iter()
+            .copied()
+            .find(|incident_type| incident_type.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Invalid incident type: {:?}", s))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum IncidentStatus {
@@ -47,16 +186,73 @@ pub enum IncidentStatus {
     FalseAlarm,
 }
 
+/// Incident types an operator can raise by hand from the UI
+pub const OPERATOR_INCIDENT_TYPES: &[IncidentType] = &[
+    IncidentType::Emergency,
+    IncidentType::Panic,
+    IncidentType::Manual,
+    IncidentType::VehicleCollision,
+    IncidentType::WeaponDetection,
+];
+
+/// The incident the device is currently recording for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveIncident {
+    pub incident_id: String,
+    pub incident_type: IncidentType,
+    pub severity: IncidentSeverity,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ActiveIncident {
+    pub fn elapsed(&self, now: chrono::DateTime<chrono::Utc>) -> chrono::Duration {
+        (now - self.started_at).max(chrono::Duration::zero())
+    }
+}
+
+/// Shared view of the active incident, read by the UI
+#[derive(Debug, Clone, Default)]
+pub struct ActiveIncidentHandle {
+    state: std::sync::Arc<std::sync::Mutex<Option<ActiveIncident>>>,
+}
+
+impl ActiveIncidentHandle {
+    pub fn get(&self) -> Option<ActiveIncident> {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, incident: Option<ActiveIncident>) {
+        *self.state.lock().unwrap() = incident;
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IncidentCreateRequest {
     pub device_id: String,
-    pub incident_type: String,
-    pub severity: String,
+    pub incident_type: IncidentType,
+    pub severity: IncidentSeverity,
     pub description: String,
     pub location: Option<LocationData>,
     pub metadata: serde_json::Value,
 }
 
+impl IncidentCreateRequest {
+    /// An incident raised by the device itself rather than an operator
+    pub fn automatic(device_id: &str, incident_type: IncidentType, severity: IncidentSeverity, location: Option<LocationData>) -> Self {
+        Self {
+            device_id: device_id.to_string(),
+            incident_type,
+            severity,
+            description: "Automatic incident triggered by bodycam".to_string(),
+            location,
+            metadata: serde_json::json!({
+                "trigger_type": "automatic",
+                "device_model": "PatrolSight BodyCam Pro",
+            }),
+        }
+    }
+}
+
 pub struct IncidentManager {
     config: Config,
     client: Client,
@@ -77,35 +273,17 @@ impl IncidentManager {
         }
     }
 
-    async fn make_request_with_retry<F, Fut, T>(
+    async fn make_request_with_retry<F, Fut>(
         &self,
         make_request: F,
         max_retries: u32,
-    ) -> Result<T>
+    ) -> Result<reqwest::Response>
     where
         F: Fn() -> Fut,
-        Fut: std::future::Future<Output = Result<T>>,
+        Fut: std::future::Future<Output = Result<reqwest::Response>>,
     {
-        let mut retries = 0;
-        let mut last_error = None;
-
-        while retries <= max_retries {
-            match make_request().await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    last_error = Some(e);
-                    retries += 1;
-                    
-                    if retries <= max_retries {
-                        let delay = std::time::Duration::from_millis(500 * retries as u64);
-                        tokio::time::sleep(delay).await;
-                        tracing::warn!("Request failed, retrying {}/{}: {}", retries, max_retries, last_error.as_ref().unwrap());
-                    }
-                }
-            }
-        }
-
-        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Request failed after retries")))
+        let policy = RetryPolicy::from_config(&self.config.network).with_max_retries(max_retries);
+        send_with_retry(&policy, make_request).await
     }
 
     fn get_auth_headers(&self) -> Result<reqwest::header::HeaderMap> {
@@ -138,8 +316,8 @@ impl IncidentManager {
     pub async fn create_incident(
         &self,
         incident_id: &str,
-        incident_type: &str,
-        severity: &str,
+        incident_type: IncidentType,
+        severity: IncidentSeverity,
         device_id: &str,
     ) -> Result<()> {
         self.create_incident_with_location(incident_id, incident_type, severity, device_id, None).await
@@ -148,46 +326,41 @@ impl IncidentManager {
     pub async fn create_incident_with_location(
         &self,
         incident_id: &str,
-        incident_type: &str,
-        severity: &str,
+        incident_type: IncidentType,
+        severity: IncidentSeverity,
         device_id: &str,
         location: Option<LocationData>,
     ) -> Result<()> {
         if !self.config.is_provisioned() {
-            return Err(anyhow::anyhow!("Device not provisioned"));
+            return Err(DeviceError::NotProvisioned.into());
         }
 
-        let incident = IncidentCreateRequest {
-            device_id: device_id.to_string(),
-            incident_type: incident_type.to_string(),
-            severity: severity.to_string(),
-            description: format!("Automatic incident triggered by bodycam"),
-            location,
-            metadata: serde_json::json!({
-                "trigger_type": "automatic",
-                "device_model": "PatrolSight BodyCam Pro",
-            }),
-        };
+        let incident = IncidentCreateRequest::automatic(device_id, incident_type, severity, location);
+        self.submit(&incident, &IdempotencyKey::derived("create_incident", incident_id)).await?;
+
+        println!("Incident {} created successfully", incident_id);
+        Ok(())
+    }
 
+    /// Post an incident to the REST API; retries carry the same key
+    pub async fn submit(&self, incident: &IncidentCreateRequest, idempotency_key: &IdempotencyKey) -> Result<()> {
         let url = format!("{}/api/incidents", self.config.server_url);
         
-        let headers = self.get_auth_headers()?;
+        let headers = with_idempotency_key(self.get_auth_headers()?, idempotency_key)?;
         let response = self.make_request_with_retry(|| async {
             self.client
                 .post(&url)
                 .headers(headers.clone())
-                .json(&incident)
+                .json(incident)
                 .send()
                 .await
                 .context("Failed to create incident")
         }, self.config.network.retry_attempts).await?;
 
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!("Incident creation failed: {}", error_text));
+            return Err(ApiError::from_response("Incident creation", response).await);
         }
 
-        println!("Incident {} created successfully", incident_id);
         Ok(())
     }
 
@@ -333,4 +506,23 @@ impl IncidentManager {
         let incidents = response.json().await?;
         Ok(incidents)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incident_enums_parse_and_serialize_as_strings() {
+        for incident_type in IncidentType::ALL {
+            assert_eq!(incident_type.as_str().parse::<IncidentType>().unwrap(), *incident_type);
+            assert_eq!(serde_json::to_value(incident_type).unwrap(), incident_type.as_str());
+        }
+        assert_eq!("camera_disconnected".parse::<IncidentType>().unwrap(), IncidentType::CameraDisconnected);
+        assert!("sos".parse::<IncidentType>().is_err());
+        assert_eq!(serde_json::from_value::<IncidentType>("drone_sighting".into()).unwrap(), IncidentType::Other);
+
+        assert_eq!("critical".parse::<IncidentSeverity>().unwrap(), IncidentSeverity::Critical);
+        assert!("severe".parse::<IncidentSeverity>().is_err());
+        assert!(IncidentSeverity::Critical > IncidentSeverity::High);
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::incident::{IncidentSeverity, IncidentType};
+
+/// Turns a stream of automatic signals (detections, sensor readings) into an
+/// incident once it is seen often enough
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentRule {
+    /// Signal name, e.g. "person_detected"
+    pub signal: String,
+    pub min_confidence: f32,
+    /// Signals needed within `window_seconds` before the rule fires
+    pub min_occurrences: u32,
+    pub window_seconds: u64,
+    pub incident_type: IncidentType,
+    pub severity: IncidentSeverity,
+    /// Quiet period after firing
+    pub cooldown_seconds: u64,
+    /// Buffered footage to include from before the signal, when it should
+    /// differ from the recording default
+    pub pre_roll_seconds: Option<u64>,
+}
+
+impl IncidentRule {
+    fn new(signal: &str, min_confidence: f32, min_occurrences: u32, incident_type: IncidentType, severity: IncidentSeverity) -> Self {
+        Self {
+            signal: signal.to_string(),
+            min_confidence,
+            min_occurrences,
+            window_seconds: 10,
+            incident_type,
+            severity,
+            cooldown_seconds: 300,
+            pre_roll_seconds: None,
+        }
+    }
+
+    fn with_pre_roll(mut self, seconds: u64) -> Self {
+        self.pre_roll_seconds = Some(seconds);
+        self
+    }
+
+    pub fn defaults() -> Vec<IncidentRule> {
+        vec![
+            IncidentRule::new("weapon_detected", 0.6, 2, IncidentType::WeaponDetection, IncidentSeverity::Critical),
+            IncidentRule::new("person_detected", 0.7, 5, IncidentType::PersonDetection, IncidentSeverity::Low),
+            IncidentRule::new("collision", 0.0, 1, IncidentType::VehicleCollision, IncidentSeverity::Critical).with_pre_roll(60),
+            IncidentRule::new("harsh_braking", 0.0, 1, IncidentType::HarshBraking, IncidentSeverity::Medium).with_pre_roll(30),
+            IncidentRule::new("speeding", 0.0, 1, IncidentType::Speeding, IncidentSeverity::Low).with_pre_roll(15),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub signal: String,
+    pub incident_type: IncidentType,
+    pub severity: IncidentSeverity,
+    pub confidence: f32,
+    pub pre_roll_seconds: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct RuleEngine {
+    rules: Vec<IncidentRule>,
+    recent: HashMap<usize, VecDeque<DateTime<Utc>>>,
+    last_fired: HashMap<usize, DateTime<Utc>>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<IncidentRule>) -> Self {
+        Self { rules, ..Default::default() }
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<IncidentRule>) {
+        *self = Self::new(rules);
+    }
+
+    /// Record a signal, returning the most severe rule it completes
+    pub fn observe(&mut self, signal: &str, confidence: f32, now: DateTime<Utc>) -> Option<RuleMatch> {
+        let mut matched: Option<RuleMatch> = None;
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.signal != signal || confidence < rule.min_confidence {
+                continue;
+            }
+
+            let cooling_down = self.last_fired.get(&index)
+                .map(|fired| now - *fired < Duration::seconds(rule.cooldown_seconds as i64))
+                .unwrap_or(false);
+            if cooling_down {
+                continue;
+            }
+
+            let window = Duration::seconds(rule.window_seconds as i64);
+            let recent = self.recent.entry(index).or_default();
+            recent.push_back(now);
+            while recent.front().map(|t| now - *t > window).unwrap_or(false) {
+                recent.pop_front();
+            }
+
+            if recent.len() as u32 >= rule.min_occurrences.max(1) {
+                recent.clear();
+                self.last_fired.insert(index, now);
+
+                let candidate = RuleMatch {
+                    signal: signal.to_string(),
+                    incident_type: rule.incident_type,
+                    severity: rule.severity,
+                    confidence,
+                    pre_roll_seconds: rule.pre_roll_seconds,
+                };
+                if matched.as_ref().map(|m| candidate.severity > m.severity).unwrap_or(true) {
+                    matched = Some(candidate);
+                }
+            }
+        }
+
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_needs_repeated_signals_then_cools_down() {
+        let start = Utc::now();
+        let at = |secs: i64| start + Duration::seconds(secs);
+        let mut engine = RuleEngine::new(vec![IncidentRule::new("weapon_detected", 0.6, 2, IncidentType::WeaponDetection, IncidentSeverity::Critical)]);
+
+        assert!(engine.observe("weapon_detected", 0.9, at(0)).is_none());
+        // Too weak to count
+        assert!(engine.observe("weapon_detected", 0.3, at(1)).is_none());
+        let fired = engine.observe("weapon_detected", 0.8, at(2)).unwrap();
+        assert_eq!(fired.incident_type, IncidentType::WeaponDetection);
+
+        assert!(engine.observe("weapon_detected", 0.9, at(3)).is_none());
+        assert!(engine.observe("weapon_detected", 0.9, at(4)).is_none());
+        assert!(engine.observe("weapon_detected", 0.9, at(400)).is_none());
+        assert!(engine.observe("weapon_detected", 0.9, at(401)).is_some());
+    }
+
+    #[test]
+    fn test_signals_outside_window_do_not_add_up() {
+        let start = Utc::now();
+        let mut engine = RuleEngine::new(vec![IncidentRule::new("person_detected", 0.5, 2, IncidentType::PersonDetection, IncidentSeverity::Low)]);
+
+        assert!(engine.observe("person_detected", 0.9, start).is_none());
+        assert!(engine.observe("person_detected", 0.9, start + Duration::seconds(30)).is_none());
+        assert!(engine.observe("vehicle_detected", 0.9, start + Duration::seconds(31)).is_none());
+    }
+}
@@ -23,6 +23,47 @@ pub struct IntegrityVerification {
     pub errors: Vec<String>,
 }
 
+/// Checksums sent with an upload so the server can verify what it stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadChecksums {
+    pub sha256: String,
+    pub file_size: u64,
+    pub chunk_size: u64,
+    /// SHA-256 of each `chunk_size` piece of the file, in order
+    pub chunk_sha256: Vec<String>,
+}
+
+/// The server's answer to an upload checksum handshake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecksumConfirmation {
+    pub verified: bool,
+    /// SHA-256 of the object as the server stored it
+    pub server_sha256: Option<String>,
+    /// Chunks whose checksum didn't match and must be sent again
+    pub mismatched_chunks: Vec<u32>,
+}
+
+impl ChecksumConfirmation {
+    /// Fails unless the server confirmed it holds exactly what was sent
+    pub fn ensure_matches(&self, expected: &UploadChecksums) -> Result<()> {
+        let server_matches = self.server_sha256
+            .as_ref()
+            .map(|hash| hash.eq_ignore_ascii_case(&expected.sha256))
+            .unwrap_or(false);
+
+        if !self.verified || !server_matches {
+            return Err(anyhow::anyhow!(
+                "Server checksum mismatch: expected {}, server has {} ({} chunks mismatched)",
+                expected.sha256,
+                self.server_sha256.as_deref().unwrap_or("none"),
+                self.mismatched_chunks.len()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 pub struct IntegrityManager;
 
 impl IntegrityManager {
@@ -48,6 +89,49 @@ impl IntegrityManager {
         Ok(format!("{:x}", result))
     }
 
+    /// Whole-file and per-chunk SHA-256 for a chunked upload
+    pub async fn calculate_upload_checksums(file_path: &Path, chunk_size: u64) -> Result<UploadChecksums> {
+        let mut file = File::open(file_path).await
+            .context(format!("Failed to open file: {:?}", file_path))?;
+
+        let mut file_hasher = Sha256::new();
+        let mut chunk_sha256 = Vec::new();
+        let mut buffer = vec![0; chunk_size.max(1) as usize];
+        let mut file_size = 0;
+
+        loop {
+            // Fill a whole chunk; a short read only ends the file at EOF
+            let mut filled = 0;
+            while filled < buffer.len() {
+                let bytes_read = file.read(&mut buffer[filled..]).await
+                    .context("Failed to read file")?;
+                if bytes_read == 0 {
+                    break;
+                }
+                filled += bytes_read;
+            }
+
+            if filled == 0 {
+                break;
+            }
+
+            file_hasher.update(&buffer[..filled]);
+            chunk_sha256.push(format!("{:x}", Sha256::digest(&buffer[..filled])));
+            file_size += filled as u64;
+
+            if filled < buffer.len() {
+                break;
+            }
+        }
+
+        Ok(UploadChecksums {
+            sha256: format!("{:x}", file_hasher.finalize()),
+            file_size,
+            chunk_size,
+            chunk_sha256,
+        })
+    }
+
     pub async fn verify_file_integrity(
         file_path: &Path,
         expected_hash: &str,
@@ -150,6 +234,42 @@ mod tests {
         assert!(verification.errors.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_upload_checksums() {
+        let temp_file = NamedTempFile::new().unwrap();
+        tokio::fs::write(temp_file.path(), b"0123456789").await.unwrap();
+
+        let checksums = IntegrityManager::calculate_upload_checksums(temp_file.path(), 4).await.unwrap();
+        assert_eq!(checksums.file_size, 10);
+        assert_eq!(checksums.chunk_sha256, vec![
+            format!("{:x}", Sha256::digest(b"0123")),
+            format!("{:x}", Sha256::digest(b"4567")),
+            format!("{:x}", Sha256::digest(b"89")),
+        ]);
+        assert_eq!(checksums.sha256, IntegrityManager::calculate_file_hash(temp_file.path()).await.unwrap());
+    }
+
+    #[test]
+    fn test_checksum_confirmation() {
+        let expected = UploadChecksums {
+            sha256: "abc123".to_string(),
+            file_size: 10,
+            chunk_size: 4,
+            chunk_sha256: Vec::new(),
+        };
+        let confirmation = |verified: bool, server: Option<&str>| ChecksumConfirmation {
+            verified,
+            server_sha256: server.map(str::to_string),
+            mismatched_chunks: Vec::new(),
+        };
+
+        assert!(confirmation(true, Some("ABC123")).ensure_matches(&expected).is_ok());
+        assert!(confirmation(true, Some("def456")).ensure_matches(&expected).is_err());
+        assert!(confirmation(false, Some("abc123")).ensure_matches(&expected).is_err());
+        // A server that doesn't echo the hash hasn't verified anything
+        assert!(confirmation(true, None).ensure_matches(&expected).is_err());
+    }
+
     #[tokio::test]
     async fn test_create_integrity_record() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -0,0 +1,331 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::api::{DeviceMetrics, IdempotencyKey};
+use crate::backend::PlatformBackend;
+use crate::custody::RecordingAccess;
+use crate::device::DeviceStatus;
+use crate::diagnostics::ComprehensiveDiagnostics;
+use crate::error_handling::ApiError;
+use crate::incident::IncidentCreateRequest;
+use crate::storage_manager::DeletedFileRecord;
+
+/// A backend call that couldn't be made while offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalOp {
+    Status { status: DeviceStatus },
+    Incident { incident_id: String, request: IncidentCreateRequest },
+    Deletions { records: Vec<DeletedFileRecord> },
+    Metrics { metrics: DeviceMetrics },
+    RecordingAccess { access: RecordingAccess },
+    Diagnostics { diagnostics: Box<ComprehensiveDiagnostics> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    /// Stays the same across replays, so the server can drop duplicates
+    pub idempotency_key: IdempotencyKey,
+    pub recorded_at: DateTime<Utc>,
+    pub op: JournalOp,
+}
+
+/// Statuses kept while offline; older ones are dropped first, since the
+/// newest say the most about the device
+const MAX_QUEUED_STATUSES: usize = 500;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub delivered: usize,
+    /// Entries the server rejected outright, moved to the dead-letter file
+    pub dead_lettered: usize,
+    pub remaining: usize,
+}
+
+/// Append-only, on-disk log of backend calls made while offline. Entries are
+/// replayed strictly in the order they were recorded; replay stops at the
+/// first failure so nothing is delivered out of order. An entry the server
+/// rejects outright (a 4xx that retrying won't fix) would block the rest
+/// forever, so it is set aside in a dead-letter file instead.
+#[derive(Debug)]
+pub struct OfflineJournal {
+    path: PathBuf,
+    entries: Vec<JournalEntry>,
+    next_seq: u64,
+}
+
+impl OfflineJournal {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("offline_journal.jsonl")
+    }
+
+    /// Where rejected entries go, next to the journal
+    pub fn dead_letter_path(&self) -> PathBuf {
+        self.path.with_extension("dead.jsonl")
+    }
+
+    pub async fn open() -> Result<Self> {
+        Self::open_at(Self::default_path()).await
+    }
+
+    /// Load the journal at `path`. Lines that can't be parsed (e.g. a write
+    /// cut short by power loss) are dropped with a warning.
+    pub async fn open_at(path: PathBuf) -> Result<Self> {
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        let mut torn = false;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    tracing::warn!("Dropping unreadable journal entry in {}: {}", path.display(), e);
+                    torn = true;
+                }
+            }
+        }
+
+        let next_seq = entries.last().map(|entry| entry.seq + 1).unwrap_or(0);
+        let journal = Self { path, entries, next_seq };
+        if torn {
+            // Appending after a partial line would corrupt the next entry too
+            journal.rewrite().await?;
+        }
+        Ok(journal)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Record `op` durably, returning its idempotency key
+    pub async fn append(&mut self, op: JournalOp) -> Result<IdempotencyKey> {
+        if matches!(op, JournalOp::Status { .. }) {
+            self.drop_oldest_statuses(MAX_QUEUED_STATUSES - 1).await?;
+        }
+
+        let entry = JournalEntry {
+            seq: self.next_seq,
+            idempotency_key: IdempotencyKey::new(),
+            recorded_at: Utc::now(),
+            op,
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.write_all(line.as_bytes()).await?;
+        file.sync_data().await?;
+
+        self.next_seq += 1;
+        let key = entry.idempotency_key.clone();
+        self.entries.push(entry);
+        Ok(key)
+    }
+
+    /// Keep at most `keep` queued statuses, dropping the oldest
+    async fn drop_oldest_statuses(&mut self, keep: usize) -> Result<()> {
+        let queued = self.entries.iter().filter(|entry| matches!(entry.op, JournalOp::Status { .. })).count();
+        if queued <= keep {
+            return Ok(());
+        }
+
+        let mut excess = queued - keep;
+        self.entries.retain(|entry| {
+            if excess > 0 && matches!(entry.op, JournalOp::Status { .. }) {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+        tracing::debug!("Dropped {} old status report(s) from the offline journal", queued - keep);
+        self.rewrite().await
+    }
+
+    /// Send journaled calls to `backend` in order, dropping each one once it
+    /// has been delivered. Consecutive status reports are batched.
+    pub async fn replay(&mut self, backend: &dyn PlatformBackend) -> Result<ReplayReport> {
+        let mut processed = 0;
+        let mut delivered = 0;
+        let mut dead = Vec::new();
+        let mut failure = None;
+
+        while processed < self.entries.len() {
+            let entry = &self.entries[processed];
+            // A run of statuses from one offline stretch goes out as one batch
+            let statuses: Vec<DeviceStatus> = self.entries[processed..].iter()
+                .map_while(|entry| match &entry.op {
+                    JournalOp::Status { status } => Some(status.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            let (result, count) = match &entry.op {
+                JournalOp::Status { status } if statuses.len() == 1 => (backend.report_status(status).await, 1),
+                JournalOp::Status { .. } => (backend.report_status_batch(&statuses).await, statuses.len()),
+                JournalOp::Incident { incident_id, request } => {
+                    (backend.create_incident(incident_id, request, &entry.idempotency_key).await.map(|_| ()), 1)
+                }
+                JournalOp::Deletions { records } => (backend.report_deletions(records).await, 1),
+                JournalOp::Metrics { metrics } => (backend.send_metrics(metrics).await, 1),
+                JournalOp::RecordingAccess { access } => (backend.report_recording_access(access).await, 1),
+                JournalOp::Diagnostics { diagnostics } => (backend.report_diagnostics(diagnostics).await, 1),
+            };
+
+            match result {
+                Ok(()) => delivered += count,
+                Err(e) if ApiError::is_permanent(&e) => {
+                    tracing::warn!("Journal entry {} rejected by the server, moving it to the dead-letter file: {:#}",
+                        entry.seq, e);
+                    dead.extend(self.entries[processed..processed + count].iter().cloned());
+                }
+                Err(e) => {
+                    failure = Some(e.context(format!("Journal entry {} not delivered", entry.seq)));
+                    break;
+                }
+            }
+            processed += count;
+        }
+
+        if !dead.is_empty() {
+            self.dead_letter(&dead).await?;
+        }
+        if processed > 0 {
+            self.entries.drain(..processed);
+            self.rewrite().await?;
+        }
+
+        let report = ReplayReport { delivered, dead_lettered: dead.len(), remaining: self.entries.len() };
+        match failure {
+            Some(e) if processed == 0 => Err(e),
+            Some(e) => {
+                tracing::warn!("Journal replay stopped early: {:#}", e);
+                Ok(report)
+            }
+            None => Ok(report),
+        }
+    }
+
+    /// Append rejected entries to the dead-letter file, kept for support to inspect
+    async fn dead_letter(&self, entries: &[JournalEntry]) -> Result<()> {
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+
+        let path = self.dead_letter_path();
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.write_all(content.as_bytes()).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    async fn rewrite(&self) -> Result<()> {
+        if self.entries.is_empty() {
+            return match fs::remove_file(&self.path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let mut content = String::new();
+        for entry in &self.entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+
+        // Replace atomically so a crash never leaves a half-written journal
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, content).await?;
+        fs::rename(&tmp, &self.path).await
+            .with_context(|| format!("Failed to replace {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deletion(path: &str) -> JournalOp {
+        JournalOp::Deletions {
+            records: vec![DeletedFileRecord {
+                file_path: path.to_string(),
+                incident_id: None,
+                quality: "high".to_string(),
+                size_bytes: 1024,
+                deleted_at: Utc::now(),
+                deletion_reason: "upload_complete_cleanup".to_string(),
+                device_id: "dev-1".to_string(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entries_survive_reopen_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let mut journal = OfflineJournal::open_at(path.clone()).await.unwrap();
+        let first = journal.append(deletion("a.mp4")).await.unwrap();
+        let second = journal.append(deletion("b.mp4")).await.unwrap();
+        assert_ne!(first, second);
+
+        let reopened = OfflineJournal::open_at(path.clone()).await.unwrap();
+        let keys: Vec<_> = reopened.entries().iter().map(|e| e.idempotency_key.clone()).collect();
+        assert_eq!(keys, vec![first, second]);
+        assert_eq!(reopened.entries()[1].seq, 1);
+    }
+
+    #[tokio::test]
+    async fn test_torn_trailing_line_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let mut journal = OfflineJournal::open_at(path.clone()).await.unwrap();
+        journal.append(deletion("a.mp4")).await.unwrap();
+        let mut content = fs::read_to_string(&path).await.unwrap();
+        content.push_str("{\"seq\":1,\"idem");
+        fs::write(&path, content).await.unwrap();
+
+        let mut reopened = OfflineJournal::open_at(path.clone()).await.unwrap();
+        assert_eq!(reopened.len(), 1);
+        reopened.append(deletion("b.mp4")).await.unwrap();
+        assert_eq!(reopened.entries()[1].seq, 1);
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Lockdown for devices handed to staff who shouldn't leave the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KioskConfig {
+    /// Run the UI fullscreen and ignore requests to close it. Leaving needs
+    /// a supervisor PIN, so `access` must be enabled with a supervisor.
+    pub enabled: bool,
+    /// How long the footer must be held to bring up the exit prompt
+    pub exit_hold_seconds: u64,
+}
+
+impl Default for KioskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            exit_hold_seconds: 3,
+        }
+    }
+}
+
+/// Without a compositor, draw straight to the display through KMS so there
+/// is no desktop shell whose gestures or panels could take the screen away.
+/// Leaves an explicitly chosen Slint backend alone.
+pub fn prefer_direct_display() {
+    let has_compositor = std::env::var_os("WAYLAND_DISPLAY").is_some() || std::env::var_os("DISPLAY").is_some();
+    if cfg!(target_os = "linux") && !has_compositor && std::env::var_os("SLINT_BACKEND").is_none() {
+        std::env::set_var("SLINT_BACKEND", "linuxkms");
+    }
+}
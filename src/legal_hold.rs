@@ -0,0 +1,155 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::recording_index::RecordingIndex;
+
+/// A do-not-delete flag from the backend, covering a whole incident or a
+/// single recording segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub id: String,
+    pub incident_id: Option<String>,
+    pub segment_id: Option<String>,
+    pub reason: Option<String>,
+    pub placed_by: Option<String>,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl LegalHold {
+    /// Recording files are named `<device>_<incident>_<segment>_<quality>.mp4`,
+    /// so a hold covers any file (or directory) named with one of its ids
+    fn covers(&self, path: &Path) -> bool {
+        let ids: Vec<&str> = [self.incident_id.as_deref(), self.segment_id.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        path.iter()
+            .filter_map(|component| Path::new(component).file_stem()?.to_str())
+            .flat_map(|name| name.split('_'))
+            .any(|token| ids.contains(&token))
+    }
+}
+
+/// Holds currently in force, kept in the recording index so they survive
+/// restarts. Every cleanup path loads them before deleting recordings.
+#[derive(Debug, Clone)]
+pub struct LegalHolds {
+    path: PathBuf,
+    holds: Vec<LegalHold>,
+    /// The index couldn't be read; nothing may be deleted
+    unreadable: bool,
+}
+
+impl LegalHolds {
+    pub async fn load() -> Self {
+        Self::read(RecordingIndex::default_path(), RecordingIndex::open())
+    }
+
+    pub async fn load_from(path: PathBuf) -> Self {
+        let index = RecordingIndex::open_at(&path);
+        Self::read(path, index)
+    }
+
+    fn read(path: PathBuf, index: Result<RecordingIndex>) -> Self {
+        match index.and_then(|index| index.legal_holds()) {
+            Ok(holds) => Self { path, holds, unreadable: false },
+            Err(e) => {
+                tracing::error!("Failed to read legal holds from {}, treating all recordings as held: {:#}", path.display(), e);
+                Self { path, holds: Vec::new(), unreadable: true }
+            }
+        }
+    }
+
+    pub fn holds(&self) -> &[LegalHold] {
+        &self.holds
+    }
+
+    pub fn hold_for(&self, path: &Path) -> Option<&LegalHold> {
+        self.holds.iter().find(|hold| hold.covers(path))
+    }
+
+    pub fn is_held(&self, path: &Path) -> bool {
+        self.unreadable || self.hold_for(path).is_some()
+    }
+
+    pub fn is_incident_held(&self, incident_id: &str) -> bool {
+        self.unreadable || self.holds.iter().any(|hold| hold.incident_id.as_deref() == Some(incident_id))
+    }
+
+    /// Add a hold, replacing any earlier hold with the same id
+    pub async fn place(&mut self, hold: LegalHold) -> Result<()> {
+        RecordingIndex::open_at(&self.path)?.place_hold(&hold)
+            .context("Failed to save legal hold")?;
+        tracing::info!("Legal hold {} placed (incident {:?}, segment {:?})", hold.id, hold.incident_id, hold.segment_id);
+        self.holds.retain(|h| h.id != hold.id);
+        self.holds.push(hold);
+        Ok(())
+    }
+
+    pub async fn release(&mut self, hold_id: &str) -> Result<bool> {
+        if !RecordingIndex::open_at(&self.path)?.release_hold(hold_id)? {
+            return Ok(false);
+        }
+
+        tracing::info!("Legal hold {} released", hold_id);
+        self.holds.retain(|h| h.id != hold_id);
+        Ok(true)
+    }
+
+    /// Replace every hold with the backend's current list
+    pub async fn replace_all(&mut self, holds: Vec<LegalHold>) -> Result<()> {
+        RecordingIndex::open_at(&self.path)?.replace_holds(&holds)
+            .context("Failed to save legal holds")?;
+        self.holds = holds;
+        self.unreadable = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hold(incident_id: Option<&str>, segment_id: Option<&str>) -> LegalHold {
+        LegalHold {
+            id: "hold-1".to_string(),
+            incident_id: incident_id.map(str::to_string),
+            segment_id: segment_id.map(str::to_string),
+            reason: Some("litigation".to_string()),
+            placed_by: None,
+            placed_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_hold_matches_recording_names() {
+        let file = Path::new("recordings/dev-1_inc-42_seg-7_high.mp4");
+
+        assert!(hold(Some("inc-42"), None).covers(file));
+        assert!(hold(None, Some("seg-7")).covers(file));
+        assert!(!hold(Some("inc-4"), None).covers(file));
+    }
+
+    #[tokio::test]
+    async fn test_holds_persist_and_fail_safe() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recordings.db");
+        let file = Path::new("dev-1_inc-42_seg-7_high.mp4");
+
+        let mut holds = LegalHolds::load_from(path.clone()).await;
+        assert!(!holds.is_held(file));
+        holds.place(hold(Some("inc-42"), None)).await.unwrap();
+
+        let mut reloaded = LegalHolds::load_from(path.clone()).await;
+        assert!(reloaded.is_held(file));
+        assert!(reloaded.is_incident_held("inc-42"));
+        assert!(reloaded.release("hold-1").await.unwrap());
+        assert!(!LegalHolds::load_from(path.clone()).await.is_held(file));
+
+        tokio::fs::write(&path, "not a database").await.unwrap();
+        assert!(LegalHolds::load_from(path).await.is_held(file));
+    }
+}
@@ -1,9 +1,16 @@
+//! Core body camera client library.
+//!
+//! Everything needed to drive a device lives here so it can be embedded in
+//! other programs (e.g. a vendor's custom UI). The `patrolsight-client` CLI
+//! and `patrolsight-ui` binaries are thin wrappers over this crate.
+
 pub mod auth;
 pub mod config;
 pub mod device;
 pub mod media;
 pub mod hardware;
 pub mod status;
+pub mod status_report;
 pub mod incident;
 pub mod buffer;
 pub mod audio;
@@ -13,20 +20,94 @@ pub mod ui;
 pub mod gps;
 pub mod integrity;
 pub mod api;
+pub mod circuit_breaker;
+pub mod http;
+pub mod backend;
 pub mod validation;
 pub mod streaming;
 pub mod recovery;
 pub mod encryption;
+pub mod decommission;
+pub mod tamper;
 pub mod resource_manager;
 pub mod diagnostics;
+pub mod diagnostics_archive;
 pub mod sentry_integration;
 pub mod error_handling;
 pub mod capabilities;
 pub mod realtime;
+pub mod remote_support;
+pub mod sites;
+pub mod audit;
+pub mod access;
+pub mod custody;
+pub mod i18n;
+pub mod theme;
+pub mod settings;
+pub mod network_status;
+pub mod pairing;
+pub mod kiosk;
+pub mod playback;
+pub mod recording_index;
+pub mod location_track;
+pub mod location;
+pub mod metrics_history;
+pub mod data_usage;
+pub mod self_test;
+pub mod communications;
+pub mod contact_directory;
+pub mod communication_policy;
+pub mod config_backup;
+pub mod hardware_identity;
+pub mod certificates;
+pub mod recording_policy;
+pub mod loop_recording;
+pub mod audio_presets;
+pub mod audio_mixer;
+pub mod speaker_loudness;
+pub mod deep_sleep;
+pub mod preflight;
+pub mod recording_time;
+pub mod recording_pause;
+pub mod button_actions;
+pub mod sms_commands;
+pub mod audio_encoding;
+pub mod audio_processing;
+pub mod audio_meter;
+pub mod process_monitor;
+pub mod cancellation;
+pub mod ffmpeg_progress;
+pub mod legal_hold;
+pub mod gop_buffer;
+pub mod redaction;
+pub mod incident_rules;
+pub mod detection;
+pub mod vehicle;
+pub mod video_upload;
+pub mod webhooks;
+pub mod capture;
+pub mod plugins;
+pub mod services;
+pub mod supervisor;
+pub mod startup;
+pub mod release_manager;
 // Convex integration modules
 pub mod convex_api;
 pub mod convex_auth;
 pub mod config_sync;
 pub mod convex_subscriptions;
 pub mod upload_manager;
-pub mod offline_queue;
\ No newline at end of file
+pub mod offline_queue;
+pub mod convex_tenant;
+pub mod convex_integration;
+pub mod event_bus;
+pub mod journal;
+pub mod retry;
+
+pub use api::ApiClient;
+pub use backend::PlatformBackend;
+pub use config::Config;
+pub use device::BodycamDevice;
+pub use event_bus::EventBus;
+pub use media::MediaRecorder;
+pub use plugins::{Plugin, PluginRegistry};
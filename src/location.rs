@@ -0,0 +1,316 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::backend::PlatformBackend;
+use crate::gps::{FixQuality, GpsManager};
+
+/// How long a Wi-Fi or BLE position is reused before looking it up again
+const LOOKUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where the device's position came from, best first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationSource {
+    #[default]
+    Gps,
+    /// The last good GPS fix; the device may have moved since
+    LastKnown,
+    /// Looked up by the backend from nearby access points
+    Wifi,
+    /// Position of a site BLE gateway the device can hear
+    BleGateway,
+    /// Position entered for the site
+    Site,
+}
+
+impl LocationSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LocationSource::Gps => "gps",
+            LocationSource::LastKnown => "last_known",
+            LocationSource::Wifi => "wifi",
+            LocationSource::BleGateway => "ble_gateway",
+            LocationSource::Site => "site",
+        }
+    }
+}
+
+/// A BLE gateway installed at a known position, e.g. in a stairwell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BleGateway {
+    /// Bluetooth address, e.g. `AA:BB:CC:DD:EE:FF`
+    pub address: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Roughly how far away it can be heard, in metres
+    pub range_m: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Radius of the site, in metres
+    pub radius_m: f64,
+}
+
+/// Where to look for a position when there's no current GPS fix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationFallbackConfig {
+    /// A GPS fix older than this isn't reported as the last known location
+    pub last_known_max_age_seconds: u64,
+    /// Ask the backend to locate the device from nearby Wi-Fi access points
+    pub wifi_lookup: bool,
+    pub ble_gateways: Vec<BleGateway>,
+    /// Reported when nothing better is available
+    pub site_location: Option<SiteLocation>,
+}
+
+impl Default for LocationFallbackConfig {
+    fn default() -> Self {
+        Self {
+            last_known_max_age_seconds: 900,
+            wifi_lookup: true,
+            ble_gateways: Vec::new(),
+            site_location: None,
+        }
+    }
+}
+
+/// The device's position and which source produced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+    /// Metres
+    pub accuracy: Option<f64>,
+    pub hdop: Option<f64>,
+    /// GPS sources only
+    pub fix_quality: Option<FixQuality>,
+    pub source: LocationSource,
+    /// When the position was measured
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ResolvedLocation {
+    pub fn age_seconds(&self, now: DateTime<Utc>) -> u64 {
+        (now - self.timestamp).num_seconds().max(0) as u64
+    }
+}
+
+/// An access point seen in a Wi-Fi scan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessPoint {
+    pub bssid: String,
+    /// 0-100
+    pub signal: u8,
+}
+
+/// A position the backend worked out from access points
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WifiPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: Option<f64>,
+}
+
+/// Access points in `nmcli -t -f BSSID,SIGNAL` output
+pub fn parse_access_points(output: &str) -> Vec<AccessPoint> {
+    output.lines()
+        .filter_map(|line| {
+            // The BSSID's colons are escaped
+            let (bssid, signal) = line.rsplit_once(':')?;
+            let bssid = bssid.replace("\\:", ":");
+            (!bssid.is_empty()).then(|| AccessPoint { bssid, signal: signal.parse().unwrap_or(0) })
+        })
+        .collect()
+}
+
+/// Devices and their RSSI in `btmgmt find` output
+pub fn parse_ble_sightings(output: &str) -> Vec<(String, i32)> {
+    output.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once("dev_found:")?;
+            let mut fields = rest.split_whitespace();
+            let address = fields.next()?.to_uppercase();
+            let rssi = fields.skip_while(|field| *field != "rssi").nth(1)?.parse().ok()?;
+            Some((address, rssi))
+        })
+        .collect()
+}
+
+/// The configured gateway heard loudest
+pub fn nearest_gateway<'a>(gateways: &'a [BleGateway], sightings: &[(String, i32)]) -> Option<&'a BleGateway> {
+    sightings.iter()
+        .filter_map(|(address, rssi)| {
+            gateways.iter()
+                .find(|gateway| gateway.address.eq_ignore_ascii_case(address))
+                .map(|gateway| (gateway, *rssi))
+        })
+        .max_by_key(|(_, rssi)| *rssi)
+        .map(|(gateway, _)| gateway)
+}
+
+pub async fn scan_access_points() -> Result<Vec<AccessPoint>> {
+    let output = tokio::process::Command::new("nmcli")
+        .args(["-t", "-f", "BSSID,SIGNAL", "device", "wifi", "list"])
+        .output()
+        .await
+        .context("Failed to run nmcli")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Wi-Fi scan failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_access_points(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub async fn scan_ble() -> Result<Vec<(String, i32)>> {
+    let output = tokio::process::Command::new("btmgmt")
+        .args(["find", "-l"])
+        .output()
+        .await
+        .context("Failed to run btmgmt")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("BLE scan failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_ble_sightings(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Works down the fallback chain until a source has a position: a current
+/// GPS fix, the last good one, Wi-Fi, a BLE gateway, then the site
+pub struct LocationResolver {
+    config: LocationFallbackConfig,
+    /// Last Wi-Fi or BLE position, and when it was looked up
+    lookup: Mutex<Option<(std::time::Instant, ResolvedLocation)>>,
+}
+
+impl LocationResolver {
+    pub fn new(config: LocationFallbackConfig) -> Self {
+        Self { config, lookup: Mutex::new(None) }
+    }
+
+    pub async fn resolve(&self, gps: &GpsManager, backend: &dyn PlatformBackend) -> Option<ResolvedLocation> {
+        let now = crate::simulation::faults::now();
+        if let Some(fix) = gps.get_location().await {
+            let quality = gps.fix_quality(&fix);
+            let age = (now - fix.timestamp).num_seconds();
+            if quality != FixQuality::Stale || age <= self.config.last_known_max_age_seconds as i64 {
+                return Some(ResolvedLocation {
+                    latitude: fix.latitude,
+                    longitude: fix.longitude,
+                    altitude: fix.altitude,
+                    accuracy: fix.accuracy,
+                    hdop: fix.hdop,
+                    fix_quality: Some(quality),
+                    source: if quality == FixQuality::Stale { LocationSource::LastKnown } else { LocationSource::Gps },
+                    timestamp: fix.timestamp,
+                });
+            }
+        }
+
+        if let Some((looked_up, location)) = self.lookup.lock().unwrap().clone() {
+            if looked_up.elapsed() < LOOKUP_INTERVAL {
+                return Some(location);
+            }
+        }
+        if let Some(location) = self.look_up(backend).await {
+            *self.lookup.lock().unwrap() = Some((std::time::Instant::now(), location.clone()));
+            return Some(location);
+        }
+
+        self.config.site_location.as_ref().map(|site| ResolvedLocation {
+            latitude: site.latitude,
+            longitude: site.longitude,
+            altitude: None,
+            accuracy: Some(site.radius_m),
+            hdop: None,
+            fix_quality: None,
+            source: LocationSource::Site,
+            timestamp: now,
+        })
+    }
+
+    /// Wi-Fi through the backend, then BLE gateways
+    async fn look_up(&self, backend: &dyn PlatformBackend) -> Option<ResolvedLocation> {
+        let now = crate::simulation::faults::now();
+        if self.config.wifi_lookup && !crate::simulation::network_down() {
+            let position = match scan_access_points().await {
+                Ok(access_points) if !access_points.is_empty() => backend.locate_wifi(&access_points).await,
+                Ok(_) => Ok(None),
+                Err(e) => Err(e),
+            };
+            match position {
+                Ok(Some(position)) => {
+                    return Some(ResolvedLocation {
+                        latitude: position.latitude,
+                        longitude: position.longitude,
+                        altitude: None,
+                        accuracy: position.accuracy,
+                        hdop: None,
+                        fix_quality: None,
+                        source: LocationSource::Wifi,
+                        timestamp: now,
+                    });
+                }
+                Ok(None) => {}
+                Err(e) => tracing::debug!("Wi-Fi location lookup failed: {:#}", e),
+            }
+        }
+
+        if !self.config.ble_gateways.is_empty() {
+            match scan_ble().await {
+                Ok(sightings) => {
+                    if let Some(gateway) = nearest_gateway(&self.config.ble_gateways, &sightings) {
+                        return Some(ResolvedLocation {
+                            latitude: gateway.latitude,
+                            longitude: gateway.longitude,
+                            altitude: None,
+                            accuracy: Some(gateway.range_m),
+                            hdop: None,
+                            fix_quality: None,
+                            source: LocationSource::BleGateway,
+                            timestamp: now,
+                        });
+                    }
+                }
+                Err(e) => tracing::debug!("BLE gateway scan failed: {:#}", e),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scans_and_pick_gateway() {
+        let access_points = parse_access_points("AA\\:BB\\:CC\\:00\\:11\\:22:72\n:10\n");
+        assert_eq!(access_points.len(), 1);
+        assert_eq!(access_points[0], AccessPoint { bssid: "AA:BB:CC:00:11:22".to_string(), signal: 72 });
+
+        let sightings = parse_ble_sightings(
+            "Discovery started\n\
+             hci0 dev_found: aa:bb:cc:dd:ee:01 type LE Random rssi -80 flags 0x0000\n\
+             hci0 dev_found: AA:BB:CC:DD:EE:02 type LE Public rssi -55 flags 0x0000\n\
+             hci0 dev_found: 11:22:33:44:55:66 type LE Public rssi -40 flags 0x0000\n",
+        );
+        assert_eq!(sightings.len(), 3);
+        assert_eq!(sightings[0], ("AA:BB:CC:DD:EE:01".to_string(), -80));
+
+        let gateway = |address: &str, latitude: f64| BleGateway {
+            address: address.to_string(),
+            latitude,
+            longitude: 114.16,
+            range_m: 15.0,
+        };
+        let gateways = vec![gateway("AA:BB:CC:DD:EE:01", 22.1), gateway("aa:bb:cc:dd:ee:02", 22.2)];
+        // The unknown device is loudest but isn't a gateway
+        assert_eq!(nearest_gateway(&gateways, &sightings).unwrap().latitude, 22.2);
+        assert!(nearest_gateway(&gateways, &[]).is_none());
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::gps::GpsLocation;
+
+const SIDECAR_EXTENSION: &str = "gps.json";
+
+/// Fixes taken while a segment was recorded, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocationTrack {
+    pub points: Vec<GpsLocation>,
+}
+
+impl LocationTrack {
+    /// Add `fix` unless it is invalid or the one already at the end
+    pub fn push(&mut self, fix: GpsLocation) {
+        if !fix.is_valid() {
+            return;
+        }
+        if self.points.last().is_some_and(|last| last.timestamp >= fix.timestamp) {
+            return;
+        }
+        self.points.push(fix);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&GpsLocation> {
+        self.points.first()
+    }
+
+    pub fn last(&self) -> Option<&GpsLocation> {
+        self.points.last()
+    }
+}
+
+/// Where a segment's location track was written, sent with the upload so
+/// the server can match the sidecar to the recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationTrackRef {
+    pub file_name: String,
+    pub sha256: String,
+    pub samples: usize,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Sidecar path for the recording at `media_path`: `clip.mp4` -> `clip.gps.json`
+pub fn sidecar_path(media_path: &Path) -> PathBuf {
+    media_path.with_extension(SIDECAR_EXTENSION)
+}
+
+pub fn is_sidecar(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(&format!(".{}", SIDECAR_EXTENSION)))
+}
+
+/// Write `track` next to the recording at `media_path`. Returns None when
+/// there is nothing to write.
+pub async fn write_sidecar(track: &LocationTrack, media_path: &Path) -> Result<Option<LocationTrackRef>> {
+    let (Some(first), Some(last)) = (track.first(), track.last()) else {
+        return Ok(None);
+    };
+    let path = sidecar_path(media_path);
+    let json = serde_json::to_vec_pretty(track)?;
+    tokio::fs::write(&path, &json).await
+        .with_context(|| format!("Failed to write location track {}", path.display()))?;
+
+    Ok(Some(LocationTrackRef {
+        file_name: path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        sha256: format!("{:x}", Sha256::digest(&json)),
+        samples: track.points.len(),
+        start: first.timestamp,
+        end: last.timestamp,
+    }))
+}
+
+/// Coordinates as an ISO 6709 string, the form MP4 `location` metadata takes
+pub fn iso6709(latitude: f64, longitude: f64, altitude: Option<f64>) -> String {
+    let mut location = format!("{:+08.4}{:+09.4}", latitude, longitude);
+    if let Some(altitude) = altitude {
+        location.push_str(&format!("{:+.1}", altitude));
+    }
+    location.push('/');
+    location
+}
+
+/// Samples the GPS into a `LocationTrack` while a recording runs: once when
+/// started, every `interval`, and once more when finished
+pub struct LocationSampler {
+    location: Arc<Mutex<Option<GpsLocation>>>,
+    track: Arc<std::sync::Mutex<LocationTrack>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl LocationSampler {
+    pub async fn start(location: Arc<Mutex<Option<GpsLocation>>>, interval: Duration) -> Self {
+        let track = Arc::new(std::sync::Mutex::new(LocationTrack::default()));
+        if let Some(fix) = location.lock().await.clone() {
+            track.lock().unwrap().push(fix);
+        }
+
+        let task = {
+            let location = location.clone();
+            let track = track.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                // The first tick is immediate and the start was already sampled
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    if let Some(fix) = location.lock().await.clone() {
+                        track.lock().unwrap().push(fix);
+                    }
+                }
+            })
+        };
+
+        Self { location, track, task }
+    }
+
+    /// The first fix of the track, if there was one when sampling started
+    pub fn start_fix(&self) -> Option<GpsLocation> {
+        self.track.lock().unwrap().first().cloned()
+    }
+
+    pub async fn finish(self) -> LocationTrack {
+        self.task.abort();
+        let end = self.location.lock().await.clone();
+        let mut track = std::mem::take(&mut *self.track.lock().unwrap());
+        if let Some(fix) = end {
+            track.push(fix);
+        }
+        track
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(latitude: f64, longitude: f64, seconds: i64) -> GpsLocation {
+        GpsLocation {
+            latitude,
+            longitude,
+            altitude: None,
+            accuracy: Some(5.0),
+            hdop: Some(1.0),
+            speed: None,
+            heading: None,
+            timestamp: DateTime::<Utc>::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            satellites: Some(8),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_track_sidecar_and_iso6709() {
+        let mut track = LocationTrack::default();
+        track.push(fix(22.2783, 114.1747, 0));
+        // Same fix sampled again while the GPS had nothing new
+        track.push(fix(22.2783, 114.1747, 0));
+        track.push(fix(95.0, 114.1747, 5));
+        track.push(fix(22.2790, 114.1750, 10));
+        assert_eq!(track.points.len(), 2);
+
+        assert_eq!(iso6709(22.2783, 114.1747, None), "+22.2783+114.1747/");
+        assert_eq!(iso6709(-33.8688, 151.2093, Some(58.0)), "-33.8688+151.2093+58.0/");
+        assert_eq!(iso6709(5.5, -0.1, None), "+05.5000-000.1000/");
+
+        let dir = tempfile::tempdir().unwrap();
+        let media = dir.path().join("seg-1_high.mp4");
+        let reference = write_sidecar(&track, &media).await.unwrap().unwrap();
+        assert_eq!(reference.file_name, "seg-1_high.gps.json");
+        assert!(is_sidecar(&sidecar_path(&media)));
+        assert!(!is_sidecar(&media));
+        assert_eq!(reference.samples, 2);
+
+        let written: LocationTrack = serde_json::from_slice(&std::fs::read(sidecar_path(&media)).unwrap()).unwrap();
+        assert_eq!(written.last().unwrap().timestamp, reference.end);
+        assert!(write_sidecar(&LocationTrack::default(), &media).await.unwrap().is_none());
+    }
+}
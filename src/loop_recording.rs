@@ -0,0 +1,163 @@
+//! Continuous recording for fixed deployments, e.g. a device mounted over a
+//! custody suite desk. The device records nonstop into a loop of bounded
+//! size. Footage around an incident or a bookmark is locked, and the oldest
+//! unlocked segments are overwritten as the loop fills.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::media::RecordingSegment;
+use crate::recording_index::IncidentRecord;
+
+/// Loop footage is recorded under this incident id, so it's told apart
+/// from recordings made for an incident
+pub const LOOP_INCIDENT_ID: &str = "00000000-0000-4000-8000-6c6f6f700000";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoopRecordingConfig {
+    pub enabled: bool,
+    /// Space the unlocked loop footage may take before the oldest is overwritten
+    pub loop_size_gb: f64,
+    /// Footage locked before an incident starts or a bookmark is placed
+    pub lock_before_seconds: u64,
+    /// Footage locked after an incident ends or a bookmark is placed
+    pub lock_after_seconds: u64,
+}
+
+impl Default for LoopRecordingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            loop_size_gb: 16.0,
+            lock_before_seconds: 120,
+            lock_after_seconds: 120,
+        }
+    }
+}
+
+impl LoopRecordingConfig {
+    pub fn loop_bytes(&self) -> u64 {
+        (self.loop_size_gb.max(0.0) * 1024.0 * 1024.0 * 1024.0) as u64
+    }
+}
+
+/// A stretch of time whose footage must be kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockWindow {
+    pub start: DateTime<Utc>,
+    /// None while the incident is still going
+    pub end: Option<DateTime<Utc>>,
+    /// e.g. `incident:<id>` or `bookmark:<id>`
+    pub reason: String,
+}
+
+impl LockWindow {
+    pub fn for_incident(incident: &IncidentRecord, config: &LoopRecordingConfig) -> Self {
+        Self {
+            start: incident.started_at - Duration::seconds(config.lock_before_seconds as i64),
+            end: incident.ended_at.map(|end| end + Duration::seconds(config.lock_after_seconds as i64)),
+            reason: format!("incident:{}", incident.id),
+        }
+    }
+
+    pub fn for_bookmark(id: i64, at: DateTime<Utc>, config: &LoopRecordingConfig) -> Self {
+        Self {
+            start: at - Duration::seconds(config.lock_before_seconds as i64),
+            end: Some(at + Duration::seconds(config.lock_after_seconds as i64)),
+            reason: format!("bookmark:{}", id),
+        }
+    }
+
+    fn overlaps(&self, segment: &RecordingSegment) -> bool {
+        let segment_end = segment.end_time.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        segment.start_time <= self.end.unwrap_or(DateTime::<Utc>::MAX_UTC) && self.start <= segment_end
+    }
+}
+
+/// Loop segments a window covers that aren't locked yet, with the reason
+pub fn segments_to_lock<'a>(
+    segments: &'a [RecordingSegment],
+    windows: &[LockWindow],
+    locked: &HashSet<String>,
+) -> Vec<(&'a RecordingSegment, String)> {
+    segments.iter()
+        .filter(|segment| !locked.contains(&segment.id))
+        .filter_map(|segment| {
+            windows.iter()
+                .find(|window| window.overlaps(segment))
+                .map(|window| (segment, window.reason.clone()))
+        })
+        .collect()
+}
+
+/// The oldest finished, unlocked loop segments to delete so the rest fit in
+/// the loop. `segments` is newest first, as the index lists them.
+pub fn segments_to_overwrite<'a>(
+    segments: &'a [RecordingSegment],
+    locked: &HashSet<String>,
+    loop_bytes: u64,
+) -> Vec<&'a RecordingSegment> {
+    let unlocked: Vec<&RecordingSegment> = segments.iter()
+        .filter(|segment| segment.end_time.is_some() && !locked.contains(&segment.id))
+        .collect();
+    let mut total: u64 = unlocked.iter().map(|segment| segment.file_size.unwrap_or(0)).sum();
+    let mut overwrite = Vec::new();
+    for segment in unlocked.into_iter().rev() {
+        if total <= loop_bytes {
+            break;
+        }
+        total = total.saturating_sub(segment.file_size.unwrap_or(0));
+        overwrite.push(segment);
+    }
+    overwrite
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(id: &str, start_minutes: i64, size: u64) -> RecordingSegment {
+        let start = DateTime::<Utc>::from_timestamp(1_700_000_000 + start_minutes * 60, 0).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "incident_id": LOOP_INCIDENT_ID,
+            "device_id": "dev-1",
+            "start_time": start,
+            "end_time": start + Duration::minutes(5),
+            "duration": 300,
+            "file_path": format!("{}.mp4", id),
+            "file_size": size,
+            "metadata": {
+                "resolution": "1920x1080", "fps": 30, "bitrate": 4000, "codec": "h264",
+                "audio_enabled": true, "audio_codec": "aac", "encryption_key": null,
+                "location": null, "ir_periods": [], "location_track": null
+            },
+            "uploaded": false,
+            "quality": "high",
+            "pre_incident_segments": [],
+            "integrity": null,
+            "previous_parts": [],
+            "audio_only": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_locked_segments_survive_the_loop() {
+        let config = LoopRecordingConfig { lock_before_seconds: 60, lock_after_seconds: 60, ..LoopRecordingConfig::default() };
+        // Newest first, 5 minutes each
+        let segments = vec![segment("s4", 20, 100), segment("s3", 15, 100), segment("s2", 10, 100), segment("s1", 5, 100), segment("s0", 0, 100)];
+
+        // A bookmark a few seconds into s1 also locks the end of s0
+        let bookmark_at = segments[3].start_time + Duration::seconds(30);
+        let windows = vec![LockWindow::for_bookmark(7, bookmark_at, &config)];
+        let to_lock: Vec<&str> = segments_to_lock(&segments, &windows, &HashSet::new()).iter().map(|(s, _)| s.id.as_str()).collect();
+        assert_eq!(to_lock, vec!["s1", "s0"]);
+
+        let locked: HashSet<String> = to_lock.iter().map(|id| id.to_string()).collect();
+        let overwrite: Vec<&str> = segments_to_overwrite(&segments, &locked, 150).iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(overwrite, vec!["s2"]);
+        assert!(segments_to_overwrite(&segments, &locked, 300).is_empty());
+    }
+}
@@ -2,38 +2,18 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing::{info, error};
 use tracing_subscriber;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-
-mod auth;
-mod config;
-mod device;
-mod media;
-mod hardware;
-mod status;
-mod incident;
-mod buffer;
-mod audio;
-mod simulation;
-mod camera;
-mod ui;
-mod gps;
-mod integrity;
-mod api;
-mod validation;
-mod streaming;
-mod recovery;
-mod encryption;
-mod resource_manager;
-mod diagnostics;
-mod sentry_integration;
-mod error_handling;
-mod capabilities;
-mod release_manager;
-
-use config::Config;
-use device::BodycamDevice;
-use release_manager::{ReleaseManager, UpdateChannel};
+
+use bodycam_core::access::{AccessControl, Credential, PrivilegedOperation};
+use bodycam_core::config::Config;
+use bodycam_core::device::BodycamDevice;
+use bodycam_core::i18n::Localizer;
+use bodycam_core::incident::{IncidentSeverity, IncidentType};
+use bodycam_core::release_manager::{ReleaseManager, UpdateChannel};
+use bodycam_core::self_test::SelfTestTrigger;
+use bodycam_core::startup::Subsystems;
+use bodycam_core::streaming::StreamQuality;
+use bodycam_core::camera::quality_ladder;
+use bodycam_core::{audio, capabilities, error_handling, metrics_history, sentry_capture_error, sentry_integration, services, simulation};
 
 #[derive(Parser)]
 #[command(name = "bodycam-client")]
@@ -53,6 +33,31 @@ struct Cli {
     
     #[arg(long)]
     config_dir: Option<String>,
+
+    /// Ask for a PIN authorizing privileged commands. It is read from
+    /// BODYCAM_PIN, or typed at the terminal, never taken on the command line.
+    #[arg(long, global = true)]
+    pin: bool,
+
+    /// Badge ID authorizing privileged commands
+    #[arg(long, global = true)]
+    badge: Option<String>,
+
+    /// Operator token from the platform authorizing privileged commands
+    #[arg(long, global = true)]
+    operator_token: Option<String>,
+}
+
+impl Cli {
+    fn credential(&self) -> Result<Option<Credential>> {
+        if self.pin {
+            Ok(Some(Credential::Pin(read_pin()?)))
+        } else if let Some(badge) = &self.badge {
+            Ok(Some(Credential::Badge(badge.clone())))
+        } else {
+            Ok(self.operator_token.clone().map(Credential::BackendToken))
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -64,7 +69,29 @@ enum Commands {
         /// Site ID where device is deployed
         site_id: String,
     },
+
+    /// Pair with a site by administrator approval instead of a site ID
+    Pair {
+        /// Device name
+        name: String,
+    },
+
+    /// Switch to another site this device is registered at
+    SwitchSite {
+        /// Site ID to switch to; omit to list known sites
+        site_id: Option<String>,
+    },
     
+    /// Revoke credentials, erase recordings and logs, and reset to factory state
+    Decommission {
+        /// Confirmation token; run without it to get one
+        #[arg(long)]
+        confirm: Option<String>,
+        /// Wipe even if recordings haven't reached the server yet
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Start recording and streaming
     Start {
         /// Recording duration in seconds (0 for continuous)
@@ -76,28 +103,49 @@ enum Commands {
         incident_id: Option<String>,
     },
     
+    /// Record audio only (interviews, statements)
+    RecordAudio {
+        /// Recording duration in seconds (0 for continuous)
+        #[arg(short, long)]
+        duration: Option<u64>,
+        
+        /// Incident ID to associate recording with
+        #[arg(short, long)]
+        incident_id: Option<String>,
+    },
+    
     /// Stop recording
     Stop,
     
+    /// Pause the recording in progress
+    Pause {
+        /// Why recording is paused, kept in the audit log and segment metadata
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+    
+    /// Resume a paused recording
+    Resume,
+    
     /// Get device status
     Status,
     
     /// Simulate incident detection
     TriggerIncident {
-        /// Incident type
+        /// Incident type, e.g. emergency, manual or panic
         #[arg(short, long)]
-        incident_type: String,
+        incident_type: IncidentType,
         
         /// Incident severity (low, medium, high, critical)
         #[arg(short, long)]
-        severity: String,
+        severity: IncidentSeverity,
     },
     
     /// Stream live feed
     Stream {
         /// Streaming quality (low, medium, high, ultra)
         #[arg(short, long, default_value = "medium")]
-        quality: String,
+        quality: StreamQuality,
         
         /// Include audio in stream
         #[arg(short, long)]
@@ -111,7 +159,15 @@ enum Commands {
     Diagnose,
 
     /// Run comprehensive diagnostics
-    ComprehensiveDiagnose,
+    ComprehensiveDiagnose {
+        /// Also archive the report and send it to the platform
+        #[arg(long)]
+        upload: bool,
+    },
+
+    /// Test the camera, audio, LEDs, buttons, GPS and backend, and flag the
+    /// device not ready for duty if a critical check fails
+    SelfTest,
 
     /// Play audio file or TTS
     PlayAudio {
@@ -129,6 +185,10 @@ enum Commands {
         
         #[arg(short, long)]
         tts_text: Option<String>,
+
+        /// Low, Normal, High or Critical
+        #[arg(long, default_value = "Normal")]
+        priority: String,
     },
 
     /// Stop audio playback
@@ -137,8 +197,36 @@ enum Commands {
     /// Get audio status
     AudioStatus,
 
+    /// List the alert sounds synced from the platform
+    AudioPresets {
+        /// Check the platform for changes first
+        #[arg(long)]
+        sync: bool,
+    },
+
+    /// Show or set the display and LED brightness
+    Brightness {
+        /// Fixed brightness in percent, replacing the dimming schedule
+        #[arg(long, conflicts_with = "auto")]
+        set: Option<u8>,
+        /// Go back to the dimming schedule
+        #[arg(long)]
+        auto: bool,
+    },
+
+    /// Measure the speaker with test tones so alerts play at a consistent loudness
+    CalibrateSpeaker {
+        /// Print the stored calibration instead of measuring again
+        #[arg(long)]
+        show: bool,
+    },
+
     /// Start interactive simulation mode
-    Simulate,
+    Simulate {
+        /// Play a scripted scenario (YAML or TOML) instead of the REPL
+        #[arg(long)]
+        scenario: Option<String>,
+    },
     
     /// Check for updates
     CheckUpdates {
@@ -173,6 +261,63 @@ enum Commands {
         force: bool,
     },
     
+    /// Export a copy of a recording with faces and license plates blurred
+    Redact {
+        /// Segment ID of the recording
+        #[arg(long)]
+        segment: String,
+
+        /// Output file (defaults to the configured export directory)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Why the recording is being exported, kept in the access log
+        #[arg(long)]
+        reason: String,
+    },
+
+    /// Export the original recording, decrypted if it was recorded encrypted
+    Export {
+        /// Segment ID of the recording
+        #[arg(long)]
+        segment: String,
+
+        /// Output file
+        #[arg(short, long)]
+        output: String,
+
+        /// Why the recording is being exported, kept in the access log
+        #[arg(long)]
+        reason: String,
+    },
+
+    /// Change the language of prompts and alerts, or list the installed packs
+    SetLanguage {
+        language: Option<String>,
+    },
+
+    /// Hash a PIN for an `[[access.users]]` entry. The PIN is read from
+    /// BODYCAM_PIN, or typed at the terminal.
+    HashPin,
+
+    /// Query metrics kept on the device
+    Metrics {
+        #[command(subcommand)]
+        command: MetricsCommand,
+    },
+
+    /// Check the video quality ladder against the camera
+    Qualities {
+        #[command(subcommand)]
+        command: QualitiesCommand,
+    },
+
+    /// Back up or restore the configuration, credentials and calibration data
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
     /// Show version information
     Version,
     
@@ -180,8 +325,90 @@ enum Commands {
     Ui,
 }
 
+#[derive(Subcommand)]
+enum MetricsCommand {
+    /// Print recorded metrics
+    History {
+        /// How far back to look, e.g. 30m, 24h or 7d
+        #[arg(long, default_value = "24h")]
+        since: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum QualitiesCommand {
+    /// Print the configured ladder and any problems with it
+    List,
+    /// Record a short test clip with each profile
+    Test {
+        /// Only test this quality
+        quality: Option<String>,
+        /// Where to write the clips
+        #[arg(long, default_value = "quality_tests")]
+        output: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Write an encrypted backup. The passphrase is read from
+    /// BODYCAM_BACKUP_PASSPHRASE, or asked for.
+    Backup {
+        #[arg(short, long)]
+        output: String,
+    },
+    /// Replace the configuration from a backup; takes effect on restart
+    Restore {
+        input: String,
+        /// Keep this device's credentials instead of the backed-up ones
+        #[arg(long)]
+        keep_credentials: bool,
+    },
+}
+
+/// A line typed at the terminal after `prompt`
+fn ask(prompt: &str) -> Result<String> {
+    use std::io::{self, Write};
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// The backup passphrase from the environment, or typed at the terminal
+fn backup_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("BODYCAM_BACKUP_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let passphrase = ask("Backup passphrase: ")?;
+    if confirm && ask("Repeat passphrase: ")? != passphrase {
+        return Err(anyhow::anyhow!("Passphrases don't match"));
+    }
+    Ok(passphrase)
+}
+
+/// The operator's PIN from the environment, or typed at the terminal, so it
+/// never shows up in the process list or shell history
+fn read_pin() -> Result<String> {
+    if let Ok(pin) = std::env::var("BODYCAM_PIN") {
+        return Ok(pin);
+    }
+    ask("PIN: ")
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> std::process::ExitCode {
+    match run().await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::ExitCode::from(error_handling::exit_code(&e))
+        }
+    }
+}
+
+async fn run() -> Result<()> {
     let cli = Cli::parse();
     
     // Initialize logging
@@ -217,8 +444,48 @@ use std::path::PathBuf;
     
     info!("Application configuration loaded and Sentry initialized");
     
+    if run_without_device(&cli.command, &config, &config_dir).await? {
+        return Ok(());
+    }
+
+    // Services and the UI run for a long time and want everything running;
+    // a one-shot command starts what it touches
+    let long_running = matches!(cli.command, Commands::Ui | Commands::Simulate { .. });
+    let subsystems = if long_running { Subsystems::all() } else { Subsystems::on_demand() };
+
     // Initialize device
-    let mut device = BodycamDevice::new(config).await?;
+    let runner_config = config.clone();
+    let device = BodycamDevice::with_subsystems(config, subsystems).await?;
+    let credential = cli.credential()?;
+
+    // The first Ctrl-C cancels the operation in progress, a second one quits
+    let canceller = device.canceller();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("Cancelling, press Ctrl-C again to quit");
+            canceller.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(error_handling::exit_codes::CANCELLED.into());
+        }
+    });
+
+    // Privileged commands need an operator with a high enough role
+    let privileged = match &cli.command {
+        Commands::SwitchSite { site_id: Some(_) } => Some(PrivilegedOperation::SwitchSite),
+        Commands::Decommission { confirm: Some(_), .. } => Some(PrivilegedOperation::Decommission),
+        Commands::CheckUpdates { apply: true, .. } | Commands::Update { .. } => Some(PrivilegedOperation::Update),
+        Commands::Rollback { .. } => Some(PrivilegedOperation::Rollback),
+        Commands::Redact { .. } => Some(PrivilegedOperation::ExportRedacted),
+        Commands::Export { .. } => Some(PrivilegedOperation::ExportOriginal),
+        Commands::Config { command: ConfigCommand::Backup { .. } } => Some(PrivilegedOperation::BackupConfig),
+        Commands::Config { command: ConfigCommand::Restore { .. } } => Some(PrivilegedOperation::RestoreConfig),
+        _ => None,
+    };
+    let operator = match privileged {
+        Some(operation) => device.authorize(operation, credential.as_ref(), "cli").await?,
+        None => None,
+    };
     
     match cli.command {
         Commands::Register { name, site_id } => {
@@ -235,6 +502,41 @@ use std::path::PathBuf;
                 }
             }
         }
+        Commands::Pair { name } => {
+            let request = device.request_pairing(&name).await?;
+            println!("Approve this device at {}", request.approval_url);
+            println!("Pairing code: {} (expires {})", request.code, request.expires_at.format("%H:%M:%S"));
+            while !device.check_pairing(&request, "cli").await? {
+                tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+            }
+            info!("Device paired");
+        }
+        Commands::SwitchSite { site_id: None } => {
+            for site in device.known_sites() {
+                println!("{}\t{}\t{}", site.site_id, site.tenant_id, site.device_id);
+            }
+        }
+        Commands::SwitchSite { site_id: Some(site_id) } => {
+            sentry_integration::add_device_breadcrumb("switch_site", Some(&format!("site_id: {}", site_id)));
+            match device.switch_site(&site_id, "cli").await {
+                Ok(site) => info!("Switched to site {} (tenant {})", site.site_id, site.tenant_id),
+                Err(e) => {
+                    error!("Site switch failed: {}", e);
+                    sentry_capture_error!(&e, "operation" => "switch_site", "site_id" => site_id);
+                    return Err(e);
+                }
+            }
+        }
+        Commands::Decommission { confirm: None, .. } => {
+            let token = device.decommission_token()?;
+            let strings = device.localizer();
+            println!("{}", strings.get("cli.decommission_warning"));
+            println!("{}", strings.format("cli.decommission_confirm", &[("token", &token)]));
+        }
+        Commands::Decommission { confirm: Some(token), force } => {
+            let report = device.decommission(&token, force, "cli").await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
         Commands::Start { duration, incident_id } => {
             sentry_integration::add_device_breadcrumb("start_recording", 
                 Some(&format!("duration: {:?}, incident_id: {:?}", duration, incident_id)));
@@ -250,10 +552,30 @@ use std::path::PathBuf;
                 }
             }
         }
+        Commands::RecordAudio { duration, incident_id } => {
+            sentry_integration::add_device_breadcrumb("start_audio_recording", 
+                Some(&format!("duration: {:?}, incident_id: {:?}", duration, incident_id)));
+            match device.start_audio_recording(duration, incident_id).await {
+                Ok(_) => info!("Audio recording started"),
+                Err(e) => {
+                    error!("Failed to start audio recording: {}", e);
+                    sentry_capture_error!(&e, "operation" => "start_audio_recording", "duration" => duration.unwrap_or(0), "incident_id" => incident_id.unwrap_or_default());
+                    return Err(e);
+                }
+            }
+        }
         Commands::Stop => {
             device.stop_recording().await?;
             info!("Recording stopped");
         }
+        Commands::Pause { reason } => {
+            device.pause_recording(reason, "cli").await?;
+            info!("Recording paused");
+        }
+        Commands::Resume => {
+            device.resume_recording("cli").await?;
+            info!("Recording resumed");
+        }
         Commands::Status => {
             let status = device.get_status().await?;
             println!("{}", serde_json::to_string_pretty(&status)?);
@@ -261,20 +583,20 @@ use std::path::PathBuf;
         Commands::TriggerIncident { incident_type, severity } => {
             sentry_integration::add_device_breadcrumb("trigger_incident", 
                 Some(&format!("type: {}, severity: {}", incident_type, severity)));
-            match device.trigger_incident(&incident_type, &severity).await {
+            match device.trigger_incident(incident_type, severity).await {
                 Ok(incident_id) => {
                     info!("Incident triggered: {}", incident_id);
                     sentry_integration::add_device_breadcrumb("trigger_incident", Some("success"));
                 }
                 Err(e) => {
                     error!("Failed to trigger incident: {}", e);
-                    sentry_capture_error!(&e, "operation" => "trigger_incident", "incident_type" => incident_type, "severity" => severity);
+                    sentry_capture_error!(&e, "operation" => "trigger_incident", "incident_type" => incident_type.as_str(), "severity" => severity.as_str());
                     return Err(e);
                 }
             }
         }
         Commands::Stream { quality, audio } => {
-            let stream_id = device.start_streaming(Some(&quality), Some(audio)).await?;
+            let stream_id = device.start_streaming(Some(quality), Some(audio)).await?;
             info!("Streaming started: {}", stream_id);
         }
         Commands::StopStream => {
@@ -285,31 +607,55 @@ use std::path::PathBuf;
             let report = device.diagnose().await?;
             println!("{}", serde_json::to_string_pretty(&report)?);
         }
-        Commands::ComprehensiveDiagnose => {
+        Commands::ComprehensiveDiagnose { upload: false } => {
             let comprehensive_report = device.run_comprehensive_diagnostics().await?;
             println!("{}", serde_json::to_string_pretty(&comprehensive_report)?);
         }
-        Commands::PlayAudio { source, volume, loop_playback, preset, tts_text } => {
+        Commands::ComprehensiveDiagnose { upload: true } => {
+            let upload = device.upload_comprehensive_diagnostics("cli").await?;
+            println!("Diagnostics archived to {}", upload.archived_path.display());
+            if upload.delivered {
+                println!("Report sent to the platform");
+            } else {
+                println!("Offline, the report will be sent when connectivity returns");
+            }
+        }
+        Commands::SelfTest => {
+            let report = device.run_self_test(SelfTestTrigger::Manual, "cli").await?;
+            for result in &report.results {
+                println!("{:<12} {:<8} {}", result.check.as_str(), format!("{:?}", result.status).to_lowercase(),
+                    result.detail.as_deref().unwrap_or(""));
+            }
+            if report.ready_for_duty() {
+                println!("Ready for duty");
+            } else {
+                println!("NOT READY FOR DUTY");
+            }
+        }
+        Commands::PlayAudio { source, volume, loop_playback, preset, tts_text, priority } => {
+            let priority: audio::AudioPriority = serde_json::from_value(serde_json::Value::String(priority.clone()))
+                .map_err(|_| anyhow::anyhow!("Invalid audio priority: {}", priority))?;
             let audio_source = if let Some(text) = tts_text {
-                crate::audio::AudioSource::TtsLocal {
+                audio::AudioSource::TtsLocal {
                     text,
                     voice: Some("en".to_string()),
                     rate: Some(150),
                 }
             } else if let Some(preset_id) = preset {
-                crate::audio::AudioSource::PresetFile { file_id: preset_id }
+                audio::AudioSource::PresetFile { file_id: preset_id }
             } else {
-                crate::audio::AudioSource::CustomFile { file_path: source }
+                audio::AudioSource::CustomFile { file_path: source }
             };
             
             let playback_id = device.play_audio(
                 audio_source,
                 volume,
                 loop_playback,
-                crate::audio::AudioPriority::Normal,
+                priority,
             ).await?;
             
             info!("Audio playback started: {}", playback_id);
+            device.wait_for_audio(&playback_id).await;
         }
         Commands::StopAudio => {
             device.stop_audio().await?;
@@ -319,14 +665,63 @@ use std::path::PathBuf;
             let status = device.get_audio_status().await?;
             println!("{}", serde_json::to_string_pretty(&status)?);
         }
-        Commands::Simulate => {
-            if !device.config.simulation.enabled {
+        Commands::AudioPresets { sync } => {
+            if sync {
+                if let Some(report) = device.sync_audio_presets(true).await? {
+                    println!("Synced: {} updated, {} removed", report.downloaded.len(), report.removed.len());
+                    for (preset, error) in &report.failed {
+                        println!("  {} failed: {}", preset, error);
+                    }
+                }
+            }
+            let presets = device.audio_presets().await;
+            if presets.is_empty() {
+                println!("No audio presets synced");
+            }
+            for preset in presets {
+                println!("{:<20} {:<4} {:<14} {}", preset.id, preset.language, preset.category, preset.name);
+            }
+        }
+        Commands::Brightness { set, auto } => {
+            let status = if set.is_some() || auto {
+                device.set_brightness_override(set, "cli").await?
+            } else {
+                device.brightness_status().await
+            };
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        }
+        Commands::CalibrateSpeaker { show } => {
+            let calibration = if show {
+                device.speaker_calibration().await
+            } else {
+                println!("Keep the area quiet while the test tones play...");
+                device.calibrate_speaker("cli").await?
+            };
+            match calibration.calibrated_at {
+                Some(at) => {
+                    println!("Calibrated {} with offset {:+.1} dB", at.to_rfc3339(), calibration.offset_db);
+                    for tone in &calibration.response {
+                        println!("  {:>5} Hz  {:.1} dB at {:+.1} dB gain", tone.frequency_hz, tone.level_db, tone.gain_db);
+                    }
+                }
+                None => println!("Speaker not calibrated"),
+            }
+        }
+        Commands::Simulate { scenario } => {
+            if !device.config().simulation.enabled {
                 return Err(anyhow::anyhow!("Simulation mode not enabled in config"));
             }
             
-            let device_arc = Arc::new(Mutex::new(device));
-            let mut sim_repl = simulation::SimulationRepl::new(device_arc);
-            sim_repl.run().await?;
+            if let Some(path) = scenario {
+                let scenario = simulation::Scenario::load(std::path::Path::new(&path)).await?;
+                let report = simulation::ScenarioRunner::new(device).run(&scenario).await?;
+                if !report.passed() {
+                    return Err(anyhow::anyhow!("Scenario '{}' failed with {} expectation(s) unmet", report.name, report.failures.len()));
+                }
+            } else {
+                let mut sim_repl = simulation::SimulationRepl::new(device);
+                sim_repl.run().await?;
+            }
         }
         Commands::CheckUpdates { channel, download, apply } => {
             let channel = match channel.as_str() {
@@ -412,7 +807,7 @@ use std::path::PathBuf;
             )?;
 
             if !force {
-                print!("Are you sure you want to rollback? (y/N): ");
+                print!("{}", device.localizer().get("cli.rollback_confirm"));
                 use std::io::{self, Write};
                 io::stdout().flush()?;
                 
@@ -420,7 +815,7 @@ use std::path::PathBuf;
                 io::stdin().read_line(&mut input)?;
                 
                 if !input.trim().eq_ignore_ascii_case("y") {
-                    println!("Rollback cancelled.");
+                    println!("{}", device.localizer().get("cli.rollback_cancelled"));
                     return Ok(());
                 }
             }
@@ -428,25 +823,30 @@ use std::path::PathBuf;
             release_manager.rollback().await?;
             println!("Rollback completed. Restart required.");
         }
-        Commands::Version => {
-            println!("PatrolSight Client v{}", env!("CARGO_PKG_VERSION"));
-            println!("Build date: {}", env!("BUILD_DATE", "unknown"));
-            println!("Git commit: {}", env!("GIT_COMMIT", "unknown"));
-            
-            let release_manager = ReleaseManager::new(
-                &config_dir,
-                "https://updates.patrolsight.com",
-                env!("CARGO_PKG_VERSION"),
-                UpdateChannel::Stable,
-            )?;
-            
-            println!("Current channel: {}", 
-                match release_manager.get_update_channel() {
-                    UpdateChannel::Stable => "stable",
-                    UpdateChannel::Beta => "beta",
-                    UpdateChannel::Alpha => "alpha",
-                    UpdateChannel::Development => "development",
-                });
+        Commands::Redact { segment, output, reason } => {
+            let report = device.redact_segment(&segment, output.map(PathBuf::from), operator, &reason, "cli").await?;
+            println!("Redacted copy written to {}", report.output_path);
+            println!("Frames redacted: {} of {}", report.frames_redacted, report.frames);
+            println!("SHA-256: {}", report.output_sha256);
+        }
+        Commands::Export { segment, output, reason } => {
+            let access = device.export_recording(&segment, std::path::Path::new(&output), operator, &reason, "cli").await?;
+            println!("Segment {} exported to {} ({:?})", access.segment_id, output, access.kind);
+        }
+        Commands::SetLanguage { language: Some(language) } => {
+            device.set_language(&language, "cli").await?;
+            info!("Language set to {}", language);
+        }
+        Commands::Config { command: ConfigCommand::Backup { output } } => {
+            let passphrase = backup_passphrase(true)?;
+            device.backup_config(std::path::Path::new(&output), &passphrase, "cli").await?;
+            println!("Backup written to {}. Keep the passphrase; the backup can't be opened without it.", output);
+        }
+        Commands::Config { command: ConfigCommand::Restore { input, keep_credentials } } => {
+            let passphrase = backup_passphrase(false)?;
+            let config = device.restore_config(std::path::Path::new(&input), &passphrase, !keep_credentials, "cli").await?;
+            println!("Restored configuration for {}. Restart the client to apply it.",
+                config.device_id.as_deref().unwrap_or("an unprovisioned device"));
         }
         Commands::Ui | _ => {
             if cli.headless {
@@ -469,32 +869,145 @@ use std::path::PathBuf;
                     }
                 }
                 
-                // Keep device running
-                let device_arc = Arc::new(Mutex::new(device));
-                
-                // Start status reporting
-                tokio::spawn(async move {
-                    loop {
-                        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-                        let device = device_arc.lock().unwrap();
-                        if let Ok(status) = device.get_status().await {
-                            let _ = device.status_reporter.report_status(status).await;
-                        }
-                    }
-                });
-                
-                // Keep running
-                tokio::signal::ctrl_c().await?;
+                let runner = services::ServiceRunner::new(device, runner_config, config_dir)
+                    .with_convex()
+                    .await?
+                    .with_default_services();
+                runner.run_until(async {
+                    let _ = tokio::signal::ctrl_c().await;
+                }).await?;
                 info!("Shutting down headless mode");
             } else {
                 // UI mode - use new Slint UI
                 info!("Starting UI mode with comprehensive device capabilities");
                 
                 // Run the new Slint UI
-                crate::ui::run_ui().await?;
+                bodycam_core::ui::run_ui(runner_config, device, Some(config_path)).await?;
             }
         }
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Commands that only read the config or local files, run without building
+/// the device so they don't wait on hardware, GPS or camera probes. False
+/// if the command needs the device.
+async fn run_without_device(command: &Commands, config: &Config, config_dir: &std::path::Path) -> Result<bool> {
+    match command {
+        Commands::SetLanguage { language: None } => {
+            let dir = Localizer::packs_dir(&config.i18n);
+            let current = Localizer::load(&config.i18n).await;
+            for language in Localizer::available(&dir).await {
+                let marker = if language == current.language() { "*" } else { " " };
+                println!("{} {}", marker, language);
+            }
+        }
+        Commands::HashPin => {
+            println!("{}", AccessControl::hash_pin(&read_pin()?)?);
+        }
+        Commands::Metrics { command: MetricsCommand::History { since } } => {
+            let window = metrics_history::parse_window(since)?;
+            if !config.metrics_history.enabled {
+                return Err(anyhow::anyhow!("Metrics history is disabled"));
+            }
+            let history = metrics_history::MetricsHistory::open(&config.metrics_history)?
+                .metrics_since(chrono::Utc::now() - window)?;
+            println!("{:<20} {:>6} {:>6} {:>8} {:>8} {:>6} {:<9} {:>9}",
+                "time", "cpu%", "mem%", "storage%", "battery%", "temp", "network", "incidents");
+            for metrics in &history {
+                println!("{:<20} {:>6.1} {:>6.1} {:>8.1} {:>8.1} {:>6.1} {:<9} {:>9}",
+                    metrics.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    metrics.cpu_usage,
+                    metrics.memory_usage,
+                    metrics.storage_usage,
+                    metrics.battery_level,
+                    metrics.temperature,
+                    metrics.network_quality,
+                    metrics.active_incidents);
+            }
+            println!("{} samples since {}", history.len(), since);
+        }
+        Commands::Qualities { command: QualitiesCommand::List } => {
+            let cameras = quality_ladder::detect_ladder_cameras(config).await;
+            let recording = &config.recording;
+            println!("{:<8} {:<11} {:>4} {:>10} {:<8} {}", "quality", "resolution", "fps", "bitrate", "codec", "device");
+            for profile in &recording.available_qualities {
+                let default = if profile.quality == recording.default_quality { " (default)" } else { "" };
+                println!("{:<8} {:<11} {:>4} {:>10} {:<8} {}{}",
+                    quality_ladder::quality_name(&profile.quality),
+                    profile.resolution,
+                    profile.fps,
+                    profile.bitrate,
+                    profile.codec,
+                    profile.device_path,
+                    default);
+                let fitted = cameras.devices.iter()
+                    .find(|device| device.device_path == profile.device_path)
+                    .and_then(|device| quality_ladder::fit_to_camera(profile, device));
+                if let Some(fitted) = fitted {
+                    println!("         -> recorded as {}@{}fps, {} bps", fitted.resolution, fitted.fps, fitted.bitrate);
+                }
+            }
+            let checked = (!config.simulation.enabled).then_some(&cameras);
+            let problems = quality_ladder::validate_ladder(recording, checked);
+            for problem in &problems {
+                println!("{}: {}", if problem.is_error() { "error" } else { "warning" }, problem);
+            }
+            if problems.is_empty() {
+                println!("Ladder OK");
+            }
+        }
+        Commands::Qualities { command: QualitiesCommand::Test { quality, output } } => {
+            let wanted = match quality.as_deref() {
+                Some(name) => Some(quality_ladder::parse_quality(name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown quality {:?}", name))?),
+                None => None,
+            };
+            // Test what will actually be recorded, after downgrading
+            let mut test_config = config.clone();
+            let cameras = quality_ladder::detect_ladder_cameras(&test_config).await;
+            quality_ladder::apply_camera_limits(&mut test_config.recording, &cameras);
+            let output = std::path::PathBuf::from(output);
+            for profile in &test_config.recording.available_qualities {
+                if wanted.as_ref().is_some_and(|wanted| *wanted != profile.quality) {
+                    continue;
+                }
+                let name = quality_ladder::quality_name(&profile.quality);
+                match quality_ladder::record_test_clip(&test_config, profile, &output).await {
+                    Ok(clip) => {
+                        let probed = match clip.probed {
+                            Some((width, height, fps)) => format!("{}x{}@{:.1}fps", width, height, fps),
+                            None => "unknown format".to_string(),
+                        };
+                        let verdict = if clip.matches(profile) { "ok" } else { "MISMATCH" };
+                        println!("{:<8} {} {} ({} bytes) {}", name, verdict, probed, clip.size_bytes, clip.path.display());
+                    }
+                    Err(e) => println!("{:<8} FAILED {:#}", name, e),
+                }
+            }
+        }
+        Commands::Version => {
+            println!("PatrolSight Client v{}", env!("CARGO_PKG_VERSION"));
+            println!("Build date: {}", env!("BUILD_DATE", "unknown"));
+            println!("Git commit: {}", env!("GIT_COMMIT", "unknown"));
+        
+            let release_manager = ReleaseManager::new(
+                config_dir,
+                "https://updates.patrolsight.com",
+                env!("CARGO_PKG_VERSION"),
+                UpdateChannel::Stable,
+            )?;
+        
+            println!("Current channel: {}", 
+                match release_manager.get_update_channel() {
+                    UpdateChannel::Stable => "stable",
+                    UpdateChannel::Beta => "beta",
+                    UpdateChannel::Alpha => "alpha",
+                    UpdateChannel::Development => "development",
+                });
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
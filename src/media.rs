@@ -2,17 +2,35 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use tokio::process::Command;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::Utc;
+use tokio_util::sync::CancellationToken;
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
 
 use crate::config::{Config, VideoQuality};
+use crate::error_handling::MediaError;
+use crate::cancellation::cancellable;
 use crate::buffer::{BufferSegment, CircularBuffer};
 use crate::integrity::{IntegrityManager, VideoIntegrity, IntegrityVerification};
 use crate::encryption::{MediaEncryptor, EncryptionMetadata};
+use crate::audio_encoding::AudioEncodingConfig;
+use crate::process_monitor::{MonitoredProcess, ProcessExit};
+use crate::camera::frame_pool::{self, FrameFeed};
+use crate::capture::{AudioSource, VideoSource};
+use crate::ffmpeg_progress::{self, EncoderProgress};
+use crate::diagnostics::{HealthStatus, RecordingPerformance};
+use crate::gps::GpsLocation;
+use crate::location_track::{self, LocationSampler, LocationTrack, LocationTrackRef};
+use crate::recording_index::{RecordingIndex, SegmentQuery};
+use crate::recording_pause::{self, RecordingGap};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Crashes of one quality's encoder tolerated before it is left stopped
+const MAX_ENCODER_RESTARTS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingSegment {
     pub id: String,
     pub incident_id: String,
@@ -27,9 +45,12 @@ pub struct RecordingSegment {
     pub quality: VideoQuality,
     pub pre_incident_segments: Vec<BufferSegment>,
     pub integrity: Option<VideoIntegrity>,
+    /// Earlier files of this segment, written before a camera was swapped or reconnected
+    pub previous_parts: Vec<String>,
+    pub audio_only: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordingMetadata {
     pub resolution: String,
     pub fps: u32,
@@ -39,9 +60,25 @@ pub struct RecordingMetadata {
     pub audio_codec: String,
     pub encryption_key: Option<String>,
     pub location: Option<LocationData>,
+    /// Periods recorded with the IR-cut filter removed (night mode footage)
+    pub ir_periods: Vec<IrPeriod>,
+    /// GPS track written next to the recording
+    pub location_track: Option<LocationTrackRef>,
+    /// Camera the video was captured from; None for audio-only recordings
+    #[serde(default)]
+    pub camera: Option<String>,
+    /// Spans where recording was paused
+    #[serde(default)]
+    pub gaps: Vec<RecordingGap>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrPeriod {
+    pub start: chrono::DateTime<chrono::Utc>,
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocationData {
     pub latitude: f64,
     pub longitude: f64,
@@ -49,15 +86,75 @@ pub struct LocationData {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+impl From<&GpsLocation> for LocationData {
+    fn from(fix: &GpsLocation) -> Self {
+        Self {
+            latitude: fix.latitude,
+            longitude: fix.longitude,
+            altitude: fix.altitude,
+            timestamp: fix.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingMode {
+    Video,
+    /// Microphone only, for interviews and statements
+    AudioOnly,
+}
+
+/// An encoder that exited on its own during a recording
+#[derive(Debug, Clone)]
+pub struct EncoderCrash {
+    /// Every quality the encoder was writing
+    pub qualities: Vec<VideoQuality>,
+    pub label: String,
+    pub exit_code: Option<i32>,
+    pub signal: Option<String>,
+    pub stderr_tail: Vec<String>,
+    /// Whether recording resumed into a new part
+    pub restarted: bool,
+}
+
+/// One ffmpeg process. A camera can only be opened once, so a single
+/// encoder captures it and writes every quality that uses it.
+struct Encoder {
+    /// The camera, or the audio device for audio-only recordings
+    device_path: String,
+    qualities: Vec<VideoQuality>,
+    process: MonitoredProcess,
+}
+
 pub struct MediaRecorder {
     config: Config,
+    mode: RecordingMode,
     device_id: String,
     incident_id: String,
     duration: Option<u64>,
     current_segments: HashMap<VideoQuality, RecordingSegment>,
-    recording_processes: HashMap<VideoQuality, tokio::process::Child>,
+    encoders: Vec<Encoder>,
+    exit_tx: tokio::sync::mpsc::UnboundedSender<ProcessExit>,
+    exit_rx: tokio::sync::mpsc::UnboundedReceiver<ProcessExit>,
+    restart_counts: HashMap<VideoQuality, u32>,
     buffer: CircularBuffer,
+    pre_roll_seconds: u64,
     encryptor: Option<MediaEncryptor>,
+    paused_qualities: HashMap<VideoQuality, String>,
+    night_overrides: Option<HashMap<VideoQuality, crate::config::VideoQualityConfig>>,
+    location: Option<std::sync::Arc<tokio::sync::Mutex<Option<GpsLocation>>>>,
+    location_sampler: Option<LocationSampler>,
+    /// Interrupts startup and uploads
+    cancel: CancellationToken,
+    /// Frames from a camera the preview already has open
+    frame_feed: Option<FrameFeed>,
+    /// Set while the encoders are suspended by `pause`
+    paused_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Where upload progress is published
+    events: Option<crate::event_bus::EventBus>,
+    /// When each part after the first began, per quality
+    part_starts: HashMap<VideoQuality, Vec<chrono::DateTime<chrono::Utc>>>,
 }
 
 impl MediaRecorder {
@@ -68,18 +165,80 @@ impl MediaRecorder {
         duration: Option<u64>,
     ) -> Self {
         let buffer = CircularBuffer::new(config.clone(), device_id.clone());
+        let pre_roll_seconds = config.pre_incident_buffer_seconds();
+        let (exit_tx, exit_rx) = tokio::sync::mpsc::unbounded_channel();
         Self {
             config,
+            mode: RecordingMode::Video,
             device_id,
             incident_id,
             duration,
             current_segments: HashMap::new(),
-            recording_processes: HashMap::new(),
+            encoders: Vec::new(),
+            exit_tx,
+            exit_rx,
+            restart_counts: HashMap::new(),
+            pre_roll_seconds,
             buffer,
             encryptor: None,
+            paused_qualities: HashMap::new(),
+            night_overrides: None,
+            location: None,
+            location_sampler: None,
+            cancel: CancellationToken::new(),
+            frame_feed: None,
+            paused_at: None,
+            events: None,
+            part_starts: HashMap::new(),
         }
     }
 
+    pub fn with_mode(mut self, mode: RecordingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Use the device's running pre-incident buffer instead of an empty one
+    pub fn with_buffer(mut self, buffer: CircularBuffer) -> Self {
+        self.buffer = buffer;
+        self
+    }
+
+    /// Include this much buffered footage instead of the configured default
+    pub fn with_pre_roll(mut self, seconds: u64) -> Self {
+        self.pre_roll_seconds = seconds;
+        self
+    }
+
+    /// Sample the device's GPS into a location track for each segment
+    pub fn with_location(mut self, location: std::sync::Arc<tokio::sync::Mutex<Option<GpsLocation>>>) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Abort startup and segment uploads when `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Publish segment upload progress on `events`
+    pub fn with_event_bus(mut self, events: crate::event_bus::EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Encode `feed`'s frames instead of opening its camera, which the
+    /// capture feeding the preview already holds
+    pub fn with_frame_feed(mut self, feed: FrameFeed) -> Self {
+        self.frame_feed = Some(feed);
+        self
+    }
+
+    pub fn mode(&self) -> RecordingMode {
+        self.mode
+    }
+
     pub async fn initialize_encryption(&mut self, encryption_key: Option<String>) -> Result<()> {
         if let Some(key) = encryption_key {
             let mut encryptor = MediaEncryptor::new(self.device_id.clone());
@@ -94,14 +253,88 @@ impl MediaRecorder {
         Ok(())
     }
 
+    /// Start recording. If startup fails or is cancelled, any encoders
+    /// already running are stopped.
     pub async fn start(&mut self) -> Result<()> {
-        // Get pre-incident buffer segments
-        let pre_incident_segments = self.buffer.get_buffer_segments(
-            self.config.recording.pre_incident_buffer_seconds
-        ).await?;
+        let cancel = self.cancel.clone();
+        let result = cancellable(&cancel, "recording startup", self.start_segments(true)).await;
+        if result.is_err() {
+            self.abort_start().await;
+        }
+        result
+    }
+
+    /// Close the current segments and carry straight on into new ones
+    /// under `incident_id`, e.g. for loop recording. Only the encoders
+    /// restart, and the closed segments are uploaded once recording has
+    /// resumed. If the new segments fail to start, nothing is left running.
+    pub async fn rotate(&mut self, incident_id: String) -> Result<()> {
+        if self.paused_at.is_some() {
+            return Err(anyhow::anyhow!("Can't rotate a paused recording"));
+        }
+        let segments_to_upload = self.close_segments().await?;
+
+        self.incident_id = incident_id;
+        let cancel = self.cancel.clone();
+        let result = cancellable(&cancel, "segment rotation", self.start_segments(false)).await;
+        if result.is_err() {
+            self.abort_start().await;
+        }
+
+        // The recording goes on; the closed files stay for a later upload
+        if let Err(e) = self.upload_segments(segments_to_upload).await {
+            tracing::warn!("Failed to upload rotated segments: {:#}", e);
+        }
+        result
+    }
+
+    /// Open a segment for each quality and start the encoders, beginning
+    /// with the pre-incident buffer if `with_pre_roll`
+    async fn start_segments(&mut self, with_pre_roll: bool) -> Result<()> {
+        if self.config.audio.enabled || self.mode == RecordingMode::AudioOnly {
+            self.validate_audio_encoding()?;
+        }
+
+        if crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull) {
+            return Err(MediaError::DiskFull.into());
+        }
+
+        if let Some(location) = &self.location {
+            let interval = std::time::Duration::from_secs(self.config.recording.location_sample_seconds.max(1));
+            self.location_sampler = Some(LocationSampler::start(location.clone(), interval).await);
+        }
+        let start_location = self.location_sampler.as_ref()
+            .and_then(|sampler| sampler.start_fix())
+            .map(|fix| LocationData::from(&fix));
+
+        if self.mode == RecordingMode::AudioOnly {
+            return self.start_audio_only(start_location).await;
+        }
+
+        // Get pre-incident buffer segments. A RAM buffer is flushed to one
+        // file, which also releases the camera for the recorder.
+        let pre_incident_segments = if !with_pre_roll {
+            Vec::new()
+        } else if self.buffer.is_memory_backed() {
+            let path = self.get_storage_path().await?
+                .join(format!("{}_{}_preincident.ts", self.device_id, self.incident_id));
+            match self.buffer.flush_pre_incident(&path, self.pre_roll_seconds).await {
+                Ok(flushed) => flushed.into_iter().collect(),
+                Err(e) => {
+                    tracing::error!("Failed to flush pre-incident buffer: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            self.buffer.get_buffer_segments(self.pre_roll_seconds).await?
+        };
         
-        // Start recording for each configured quality
-        for quality_config in &self.config.recording.available_qualities {
+        // A segment for each configured quality
+        let quality_configs: Vec<crate::config::VideoQualityConfig> = self.config.recording.available_qualities
+            .iter()
+            .filter_map(|q| self.quality_config(&q.quality))
+            .collect();
+        for quality_config in &quality_configs {
             let segment_id = Uuid::new_v4().to_string();
             let start_time = Utc::now();
             
@@ -125,13 +358,21 @@ impl MediaRecorder {
                 bitrate: quality_config.bitrate,
                 codec: quality_config.codec.clone(),
                 audio_enabled: self.config.audio.enabled,
-                audio_codec: "aac".to_string(),
+                audio_codec: self.config.audio.codec.name().to_string(),
                 encryption_key: if self.encryptor.is_some() { 
                     Some("AES-256-GCM".to_string()) 
                 } else { 
                     None 
                 },
-                location: None,
+                location: start_location.clone(),
+                ir_periods: if self.night_overrides.is_some() {
+                    vec![IrPeriod { start: start_time, end: None }]
+                } else {
+                    Vec::new()
+                },
+                location_track: None,
+                camera: Some(quality_config.device_path.clone()),
+                gaps: Vec::new(),
             };
 
             let segment = RecordingSegment {
@@ -147,187 +388,483 @@ impl MediaRecorder {
                 uploaded: false,
                 quality: quality_config.quality.clone(),
                 pre_incident_segments: pre_incident_segments.clone(),
+                integrity: None,
+                previous_parts: Vec::new(),
+                audio_only: false,
             };
 
             self.current_segments.insert(quality_config.quality.clone(), segment);
-            
-            if !self.config.simulation.enabled {
-                self.start_real_recording(quality_config, &file_path).await?;
-            } else {
-                self.start_simulated_recording(quality_config, &file_path).await?;
-            }
         }
 
-        Ok(())
+        self.start_encoders(quality_configs).await
+    }
+
+    async fn abort_start(&mut self) {
+        for mut encoder in std::mem::take(&mut self.encoders) {
+            let _ = encoder.process.kill().await;
+            let _ = encoder.process.wait().await;
+        }
+        if let Some(sampler) = self.location_sampler.take() {
+            sampler.finish().await;
+        }
+        self.current_segments.clear();
     }
 
     pub async fn stop(&mut self) -> Result<()> {
+        let segments_to_upload = self.close_segments().await?;
+        self.upload_segments(segments_to_upload).await
+    }
+
+    /// Stop the encoders and finish every current segment, returning the
+    /// ones to upload
+    async fn close_segments(&mut self) -> Result<Vec<RecordingSegment>> {
         let mut segments_to_upload = Vec::new();
+        let track = match self.location_sampler.take() {
+            Some(sampler) => sampler.finish().await,
+            None => Default::default(),
+        };
+        // A suspended encoder can't finish its file. Every encoder is
+        // continued, in case one was stopped without a pause being recorded.
+        self.paused_at = None;
+        if let Err(e) = self.signal_encoders(Signal::SIGCONT) {
+            tracing::warn!("Failed to resume encoders before stopping: {:#}", e);
+        }
+
+        for mut encoder in std::mem::take(&mut self.encoders) {
+            // Properly terminate the process and wait for cleanup
+            if let Err(e) = encoder.process.kill().await {
+                tracing::warn!("Failed to kill recording process: {}", e);
+            }
+
+            // Wait for the process to fully terminate to prevent zombies
+            if let Err(e) = encoder.process.wait().await {
+                tracing::warn!("Failed to wait for process cleanup: {}", e);
+            }
+
+            tracing::info!("Recording process properly terminated for qualities: {:?}", encoder.qualities);
+        }
         
-        for (quality, mut segment) in self.current_segments.drain() {
-            segment.end_time = Some(Utc::now());
-            segment.duration = segment.end_time
-                .map(|end| (end - segment.start_time).num_seconds() as u64);
-            
-            if let Some(mut process) = self.recording_processes.remove(&quality) {
-                // Properly terminate the process and wait for cleanup
-                if let Err(e) = process.kill().await {
-                    tracing::warn!("Failed to kill recording process: {}", e);
+        let segments: Vec<(VideoQuality, RecordingSegment)> = self.current_segments.drain().collect();
+        for (quality, mut segment) in segments {
+            let end_time = Utc::now();
+            segment.end_time = Some(end_time);
+            for period in segment.metadata.ir_periods.iter_mut().filter(|p| p.end.is_none()) {
+                period.end = segment.end_time;
+            }
+            recording_pause::close_gaps(&mut segment.metadata.gaps, end_time);
+
+            // Each file written before a camera swap or restart is finished
+            // and uploaded like the last one
+            let part_starts = self.part_starts.remove(&quality).unwrap_or_default();
+            let parts = split_parts(segment, &part_starts);
+            let mut finished_paths = Vec::new();
+            let last = parts.len() - 1;
+            for (index, mut part) in parts.into_iter().enumerate() {
+                if index == last {
+                    part.previous_parts = std::mem::take(&mut finished_paths);
                 }
-                
-                // Wait for the process to fully terminate to prevent zombies
-                if let Err(e) = process.wait().await {
-                    tracing::warn!("Failed to wait for process cleanup: {}", e);
+                self.finish_part(&mut part, &track).await?;
+                finished_paths.push(part.file_path.clone());
+
+                // Upload based on default quality setting. Loop footage stays on
+                // the device until it's overwritten or locked.
+                if quality == self.config.recording.default_quality && self.config.network.upload_bandwidth > 0
+                    && part.incident_id != crate::loop_recording::LOOP_INCIDENT_ID {
+                    segments_to_upload.push(part);
                 }
-                
-                tracing::info!("Recording process properly terminated for quality: {:?}", quality);
             }
+        }
 
-            if let Ok(metadata) = fs::metadata(&segment.file_path).await {
-                segment.file_size = Some(metadata.len());
+        self.current_segments.clear();
+        Ok(segments_to_upload)
+    }
+
+    /// Upload selected quality segments
+    async fn upload_segments(&self, segments_to_upload: Vec<RecordingSegment>) -> Result<()> {
+        for segment in segments_to_upload {
+            if let Err(e) = self.upload_segment(&segment).await {
+                if !crate::cancellation::is_cancelled(&e) {
+                    return Err(e);
+                }
+                // Recording itself stopped fine; the files stay for a later upload
+                tracing::info!("Upload of segment {} cancelled, keeping local copy", segment.id);
+                break;
             }
+        }
+        Ok(())
+    }
+
+    /// Write the location sidecar, encrypt, hash and index one finished file
+    async fn finish_part(&mut self, segment: &mut RecordingSegment, track: &LocationTrack) -> Result<()> {
+        let end_time = segment.end_time.unwrap_or_else(Utc::now);
+        segment.duration = Some(recording_pause::recorded_seconds(segment.start_time, end_time, &segment.metadata.gaps));
+        let mut track = track.clone();
+        track.points.retain(|fix| fix.timestamp >= segment.start_time && fix.timestamp <= end_time);
+        recording_pause::drop_paused_fixes(&mut track, &segment.metadata.gaps);
 
-            // Encrypt the recording if encryption is enabled
-            if let Some(encryptor) = &self.encryptor {
-                let original_path = PathBuf::from(&segment.file_path);
-                let encrypted_path = original_path.with_extension("encrypted.mp4");
-                
-                match encryptor.encrypt_video_file(&original_path, &encrypted_path).await {
-                    Ok(encryption_metadata) => {
-                        // Verify encrypted file exists and has reasonable size before deleting original
-                        if encrypted_path.exists() && encryption_metadata.encrypted_size > 0 {
-                            // Safely remove original unencrypted file
-                            if let Err(e) = fs::remove_file(&original_path).await {
-                                tracing::error!("Failed to remove original file after encryption: {}", e);
-                                // Keep both files rather than risk data loss
-                            } else {
-                                tracing::info!("Original unencrypted file safely removed after encryption");
-                            }
-                            
-                            // Update segment to point to encrypted file
-                            segment.file_path = encrypted_path.to_string_lossy().to_string();
-                            segment.file_size = Some(encryption_metadata.encrypted_size);
-                            
-                            tracing::info!("Successfully encrypted recording segment: {}", segment.id);
+        if let Ok(metadata) = fs::metadata(&segment.file_path).await {
+            segment.file_size = Some(metadata.len());
+        }
+
+        // Written before encryption so it keeps the recording's name
+        match location_track::write_sidecar(&track, Path::new(&segment.file_path)).await {
+            Ok(reference) => segment.metadata.location_track = reference,
+            Err(e) => tracing::warn!("Segment {} has no location track: {}", segment.id, e),
+        }
+        if segment.metadata.location.is_none() {
+            segment.metadata.location = track.first().map(LocationData::from);
+        }
+
+        // Encrypt the recording if encryption is enabled
+        if let Some(encryptor) = &self.encryptor {
+            let original_path = PathBuf::from(&segment.file_path);
+            let extension = original_path.extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_else(|| "mp4".to_string());
+            let encrypted_path = original_path.with_extension(format!("encrypted.{}", extension));
+            
+            match encryptor.encrypt_video_file(&original_path, &encrypted_path).await {
+                Ok(encryption_metadata) => {
+                    // Verify encrypted file exists and has reasonable size before deleting original
+                    if encrypted_path.exists() && encryption_metadata.encrypted_size > 0 {
+                        // Safely remove original unencrypted file
+                        if let Err(e) = fs::remove_file(&original_path).await {
+                            tracing::error!("Failed to remove original file after encryption: {}", e);
+                            // Keep both files rather than risk data loss
                         } else {
-                            tracing::error!("Encrypted file verification failed - keeping original file");
-                            // Don't update segment path, keep original
+                            tracing::info!("Original unencrypted file safely removed after encryption");
                         }
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to encrypt recording segment {}: {}", segment.id, e);
-                        // Continue without encryption rather than failing the entire operation
+                        
+                        // Update segment to point to encrypted file
+                        segment.file_path = encrypted_path.to_string_lossy().to_string();
+                        segment.file_size = Some(encryption_metadata.encrypted_size);
+                        
+                        tracing::info!("Successfully encrypted recording segment: {}", segment.id);
+                    } else {
+                        tracing::error!("Encrypted file verification failed - keeping original file");
+                        // Don't update segment path, keep original
                     }
                 }
-            }
-
-            // Create integrity record for the segment
-            self.create_integrity_record(&mut segment).await?;
-            
-            // Verify integrity before saving
-            let verification = self.verify_segment_integrity(&segment).await;
-            if let Ok(verification) = verification {
-                if !verification.is_valid {
-                    tracing::error!("Integrity verification failed for segment {}", segment.id);
+                Err(e) => {
+                    tracing::error!("Failed to encrypt recording segment {}: {}", segment.id, e);
+                    // Continue without encryption rather than failing the entire operation
                 }
             }
+        }
 
-            // Save segment metadata
-            self.save_segment_metadata(&segment).await?;
-            
-            // Upload based on default quality setting
-            if quality == self.config.recording.default_quality && self.config.network.upload_bandwidth > 0 {
-                segments_to_upload.push(segment);
+        // Create integrity record for the segment
+        self.create_integrity_record(segment).await?;
+        
+        // Verify integrity before saving
+        let verification = self.verify_segment_integrity(segment).await;
+        if let Ok(verification) = verification {
+            if !verification.is_valid {
+                tracing::error!("Integrity verification failed for segment {}", segment.id);
             }
         }
 
-        // Upload selected quality segments
-        for segment in segments_to_upload {
-            self.upload_segment(&segment).await?;
+        // Save segment metadata
+        self.save_segment_metadata(segment).await?;
+        Ok(())
+    }
+
+    /// The night override for `quality` while IR is on, otherwise its
+    /// configured profile
+    fn quality_config(&self, quality: &VideoQuality) -> Option<crate::config::VideoQualityConfig> {
+        let base = self.config.recording.available_qualities.iter().find(|q| &q.quality == quality)?;
+        Some(self.night_overrides.as_ref()
+            .and_then(|o| o.get(quality))
+            .unwrap_or(base)
+            .clone())
+    }
+
+    /// One encoder per camera, writing every quality that uses it into its
+    /// segment's current file
+    async fn start_encoders(&mut self, quality_configs: Vec<crate::config::VideoQualityConfig>) -> Result<()> {
+        let mut cameras: Vec<(String, Vec<crate::config::VideoQualityConfig>)> = Vec::new();
+        for quality_config in quality_configs {
+            match cameras.iter_mut().find(|(device_path, _)| *device_path == quality_config.device_path) {
+                Some((_, group)) => group.push(quality_config),
+                None => cameras.push((quality_config.device_path.clone(), vec![quality_config])),
+            }
         }
 
-        self.current_segments.clear();
-        self.recording_processes.clear();
+        for (device_path, group) in cameras {
+            if !VideoSource::for_config(&self.config, &device_path).is_placeholder() {
+                self.start_real_recording(&device_path, &group).await?;
+            } else {
+                self.start_simulated_recording(&device_path, &group).await?;
+            }
+        }
         Ok(())
     }
 
-async fn start_real_recording(
-        &mut self, 
-        quality_config: &crate::config::VideoQualityConfig, 
-        file_path: &PathBuf
+    /// Stops the encoders writing any of `qualities` or capturing
+    /// `device_path`, returning every quality they were writing
+    async fn stop_encoders(&mut self, qualities: &[VideoQuality], device_path: Option<&str>) -> Vec<VideoQuality> {
+        let (stopping, running): (Vec<Encoder>, Vec<Encoder>) = std::mem::take(&mut self.encoders)
+            .into_iter()
+            .partition(|encoder| {
+                encoder.qualities.iter().any(|q| qualities.contains(q))
+                    || device_path.is_some_and(|path| encoder.device_path == path)
+            });
+        self.encoders = running;
+
+        let mut stopped = Vec::new();
+        for mut encoder in stopping {
+            let _ = encoder.process.kill().await;
+            let _ = encoder.process.wait().await;
+            stopped.extend(encoder.qualities);
+        }
+        stopped
+    }
+
+    fn is_encoding(&self, quality: &VideoQuality) -> bool {
+        self.encoders.iter().any(|encoder| encoder.qualities.contains(quality))
+    }
+
+    /// Moves `quality_configs` into new parts. Other qualities captured from
+    /// the same cameras are restarted with them, so each camera is still
+    /// opened by a single encoder.
+    async fn restart_into_next_parts(&mut self, quality_configs: Vec<crate::config::VideoQualityConfig>) -> Result<()> {
+        let qualities: Vec<VideoQuality> = quality_configs.iter().map(|q| q.quality.clone()).collect();
+        let mut stopped = self.stop_encoders(&qualities, None).await;
+        for quality_config in &quality_configs {
+            stopped.extend(self.stop_encoders(&[], Some(&quality_config.device_path)).await);
+        }
+
+        let mut restarting = quality_configs;
+        for quality in stopped {
+            if !qualities.contains(&quality) {
+                if let Some(quality_config) = self.quality_config(&quality) {
+                    restarting.push(quality_config);
+                }
+            }
+        }
+        self.start_next_parts(restarting).await
+    }
+
+    async fn start_real_recording(
+        &mut self,
+        device_path: &str,
+        quality_configs: &[crate::config::VideoQualityConfig],
     ) -> Result<()> {
-        let duration_arg = self.duration
-            .map(|d| format!("-t {}", d))
-            .unwrap_or_default();
+        let renditions: Vec<(&str, u32)> = quality_configs.iter()
+            .map(|q| (q.resolution.as_str(), q.fps))
+            .collect();
+        let (capture_resolution, capture_fps) = crate::capture::capture_format(&renditions)
+            .ok_or_else(|| anyhow::anyhow!("No qualities to record from {}", device_path))?;
+
+        let feed = self.frame_feed.as_ref().filter(|feed| feed.device_path == device_path);
+        let video = match feed {
+            Some(feed) => VideoSource::Frames { width: feed.width, height: feed.height, fps: feed.fps },
+            None => VideoSource::for_config(&self.config, device_path),
+        };
+        // Use configured device path or default ALSA device
+        let audio = self.config.audio.enabled.then(|| AudioSource::for_config(&self.config,
+            self.config.audio.device_path.as_deref().unwrap_or("default")));
 
         let mut cmd = Command::new("ffmpeg");
-        
-        cmd.arg("-f")
-           .arg("v4l2")
-           .arg("-i")
-           .arg(&quality_config.device_path)
-           .arg("-framerate")
-           .arg(quality_config.fps.to_string())
-           .arg("-video_size")
-           .arg(&quality_config.resolution)
-           .arg("-b:v")
-           .arg(quality_config.bitrate.to_string());
-
-        if self.config.audio.enabled {
+        cmd.args(ffmpeg_progress::progress_args())
+           .args(video.input_args(capture_fps, &capture_resolution));
+        if let Some(audio) = &audio {
+            cmd.args(audio.input_args());
+        }
+        let mut filter = crate::capture::split_filter(&renditions);
+        // The camera is held for the whole recording, so night mode samples the light from here
+        if self.config.night_mode.enabled && !self.config.simulation.enabled {
+            filter.push_str(&format!(";[0:v]{},nullsink",
+                crate::camera::night_mode::luminance_tap(self.config.night_mode.sample_interval_seconds)));
+        }
+        cmd.arg("-filter_complex").arg(filter);
+
+        // One output per quality, each taking its own scaled copy of the video
+        for (index, quality_config) in quality_configs.iter().enumerate() {
+            let segment = self.current_segments.get(&quality_config.quality)
+                .ok_or_else(|| MediaError::NoActiveSegment { quality: format!("{:?}", quality_config.quality) })?;
+
+            cmd.arg("-map").arg(format!("[v{}]", index));
+            if audio.is_some() {
+                cmd.args(["-map", "1:a:0"])
+                   .args(self.config.audio.processing.ffmpeg_args())
+                   .args(AudioEncodingConfig::from(&self.config.audio).ffmpeg_args());
+            }
+
+            // Start coordinates, shown by players and picked up by evidence tools
+            if let Some(location) = segment.metadata.location.as_ref() {
+                let coordinates = location_track::iso6709(location.latitude, location.longitude, location.altitude);
+                cmd.arg("-metadata").arg(format!("location={}", coordinates));
+            }
+
+            cmd.arg("-b:v")
+               .arg(quality_config.bitrate.to_string())
+               .arg("-c:v")
+               .arg(&quality_config.codec)
+               .arg("-preset")
+               .arg("ultrafast");
+            if let Some(duration) = self.duration {
+                cmd.arg("-t").arg(duration.to_string());
+            }
             cmd.arg("-f")
-               .arg("alsa")
-               .arg("-i");
-            
-            // Use configured device path or default
-            if let Some(ref device_path) = self.config.audio.device_path {
-                cmd.arg(device_path);
+               .arg("mp4")
+               .arg(&segment.file_path);
+        }
+
+        if feed.is_some() {
+            cmd.stdin(std::process::Stdio::piped());
+        }
+
+        let qualities: Vec<VideoQuality> = quality_configs.iter().map(|q| q.quality.clone()).collect();
+        let label = format!("ffmpeg {:?} recording", qualities);
+        let mut process = MonitoredProcess::spawn_ffmpeg(cmd, &label, self.exit_tx.clone())
+            .map_err(|e| MediaError::EncoderStart { label: label.clone(), message: format!("{:#}", e) })?;
+        if let (Some(feed), Some(stdin)) = (feed, process.take_stdin()) {
+            tokio::spawn(frame_pool::feed_encoder(feed.subscribe(), stdin));
+        }
+
+        self.encoders.push(Encoder { device_path: device_path.to_string(), qualities, process });
+        Ok(())
+    }
+
+    /// Audio-only recordings are tracked as a single segment under the default
+    /// quality so they go through the same encryption, integrity and upload path
+    async fn start_audio_only(&mut self, location: Option<LocationData>) -> Result<()> {
+        let segment_id = Uuid::new_v4().to_string();
+        let start_time = Utc::now();
+        let codec = self.config.audio.codec;
+
+        let storage_path = self.get_storage_path().await?;
+        let file_path = storage_path.join(format!("{}_{}_{}_audio.{}",
+            self.device_id,
+            self.incident_id,
+            segment_id,
+            codec.file_extension()
+        ));
+
+        let metadata = RecordingMetadata {
+            resolution: String::new(),
+            fps: 0,
+            bitrate: self.config.audio.bitrate,
+            codec: codec.name().to_string(),
+            audio_enabled: true,
+            audio_codec: codec.name().to_string(),
+            encryption_key: if self.encryptor.is_some() {
+                Some("AES-256-GCM".to_string())
             } else {
-                cmd.arg("default"); // Default ALSA device
-            }
-            
-            cmd.arg("-c:a")
-               .arg("aac")
-               .arg("-b:a")
-               .arg(format!("{}", self.config.audio.bitrate));
-        }
-
-        cmd.arg("-c:v")
-           .arg(&quality_config.codec)
-           .arg("-preset")
-           .arg("ultrafast")
-           .arg("-t")
-           .arg(duration_arg)
-           .arg("-f")
-           .arg("mp4")
-           .arg(file_path);
-
-        let child = cmd.spawn()
-            .context("Failed to start ffmpeg recording process")?;
-
-        self.recording_processes.insert(quality_config.quality.clone(), child);
+                None
+            },
+            location,
+            ir_periods: Vec::new(),
+            location_track: None,
+            camera: None,
+            gaps: Vec::new(),
+        };
+
+        let quality = self.config.recording.default_quality.clone();
+        let segment = RecordingSegment {
+            id: segment_id,
+            incident_id: self.incident_id.clone(),
+            device_id: self.device_id.clone(),
+            start_time,
+            end_time: None,
+            duration: None,
+            file_path: file_path.to_string_lossy().to_string(),
+            file_size: None,
+            metadata,
+            uploaded: false,
+            quality: quality.clone(),
+            pre_incident_segments: Vec::new(),
+            integrity: None,
+            previous_parts: Vec::new(),
+            audio_only: true,
+        };
+
+        self.current_segments.insert(quality.clone(), segment);
+        self.start_audio_process(&quality, &file_path).await
+    }
+
+    /// Check the recording audio settings against the codec and the capture device
+    fn validate_audio_encoding(&self) -> Result<()> {
+        let device = if self.config.simulation.enabled {
+            None
+        } else {
+            crate::audio_encoding::query_input_capability(None)
+        };
+
+        AudioEncodingConfig::from(&self.config.audio)
+            .validate(device.as_ref())
+            .map_err(|e| MediaError::InvalidAudioSettings { message: format!("{:#}", e) }.into())
+    }
+
+    async fn start_audio_process(&mut self, quality: &VideoQuality, file_path: &PathBuf) -> Result<()> {
+        if self.config.simulation.enabled && self.config.simulation.audio_source.is_none() {
+            println!("Starting simulated audio recording to: {}", file_path.display());
+            fs::write(file_path, format!("Simulated audio recording\nDevice: {}\nIncident: {}\nStart: {}",
+                self.device_id,
+                self.incident_id,
+                Utc::now().to_rfc3339()
+            )).await?;
+            return Ok(());
+        }
+
+        let mut cmd = Command::new("ffmpeg");
+
+        let audio = AudioSource::for_config(&self.config,
+            self.config.audio.device_path.as_deref().unwrap_or("default"));
+
+        cmd.args(ffmpeg_progress::progress_args())
+           .args(audio.input_args())
+           .args(self.config.audio.processing.ffmpeg_args())
+           .args(AudioEncodingConfig::from(&self.config.audio).ffmpeg_args());
+
+        if let Some(duration) = self.duration {
+            cmd.arg("-t").arg(duration.to_string());
+        }
+
+        cmd.arg(file_path);
+
+        let process = MonitoredProcess::spawn_ffmpeg(cmd, "ffmpeg audio recording", self.exit_tx.clone())
+            .map_err(|e| MediaError::EncoderStart {
+                label: "ffmpeg audio recording".to_string(),
+                message: format!("{:#}", e),
+            })?;
+
+        let device_path = self.config.audio.device_path.clone().unwrap_or_else(|| "default".to_string());
+        self.encoders.push(Encoder { device_path, qualities: vec![quality.clone()], process });
         Ok(())
     }
 
     async fn start_simulated_recording(
-        &mut self, 
-        quality_config: &crate::config::VideoQualityConfig, 
-        file_path: &PathBuf
+        &mut self,
+        device_path: &str,
+        quality_configs: &[crate::config::VideoQualityConfig],
     ) -> Result<()> {
-        println!("Starting simulated recording to: {}", file_path.display());
-        
-        // Create a dummy file for simulation
-        let dummy_content = format!("Simulated recording data\nDevice: {}\nIncident: {}\nQuality: {:?}\nStart: {}", 
-            self.device_id, 
-            self.incident_id,
-            quality_config.quality,
-            Utc::now().to_rfc3339()
-        );
-        
-        fs::write(file_path, dummy_content).await?;
+        for quality_config in quality_configs {
+            let file_path = self.current_segments.get(&quality_config.quality)
+                .map(|segment| segment.file_path.clone())
+                .ok_or_else(|| MediaError::NoActiveSegment { quality: format!("{:?}", quality_config.quality) })?;
+            println!("Starting simulated recording to: {}", file_path);
+
+            // Create a dummy file for simulation
+            let dummy_content = format!("Simulated recording data\nDevice: {}\nIncident: {}\nQuality: {:?}\nStart: {}",
+                self.device_id,
+                self.incident_id,
+                quality_config.quality,
+                Utc::now().to_rfc3339()
+            );
+
+            fs::write(&file_path, dummy_content).await?;
+        }
         
-        // Simulate recording duration
+        // A stand-in for the encoder, so supervision and injected crashes
+        // behave as they would with ffmpeg
         let duration = self.duration.unwrap_or(300); // Default 5 minutes
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let mut cmd = Command::new("sleep");
+        cmd.arg(duration.to_string());
+        let qualities: Vec<VideoQuality> = quality_configs.iter().map(|q| q.quality.clone()).collect();
+        let label = format!("simulated {:?} encoder", qualities);
+        let process = MonitoredProcess::spawn(cmd, &label, self.exit_tx.clone())?;
+        self.encoders.push(Encoder { device_path: device_path.to_string(), qualities, process });
         
         Ok(())
     }
@@ -344,20 +881,19 @@ async fn start_real_recording(
             }
         }
         
-        // If not found in current segments, check archived segments
-        let archived_path = self.get_storage_path().await?;
-        let pattern = format!("{}_{}_*_{}.mp4", self.device_id, incident_id, 
-            match quality {
-                VideoQuality::Low => "low",
-                VideoQuality::Medium => "med",
-                VideoQuality::High => "high",
-                VideoQuality::Ultra => "ultra",
-            }
-        );
-        
-        // In a real implementation, we'd search the archived files
-        // For now, return not found
-        Err(anyhow::anyhow!("Segment not found for quality {:?}", quality))
+        // If not found in current segments, check recorded segments
+        let recorded = RecordingIndex::open()?.segments(&SegmentQuery {
+            incident_id: Some(incident_id.to_string()),
+            quality: Some(quality.clone()),
+            ..Default::default()
+        })?;
+        if recorded.is_empty() {
+            return Err(anyhow::anyhow!("Segment not found for quality {:?}", quality));
+        }
+        for segment in &recorded {
+            self.upload_segment(segment).await?;
+        }
+        Ok(())
     }
 
     async fn get_storage_path(&self) -> Result<PathBuf> {
@@ -370,17 +906,7 @@ async fn start_real_recording(
     }
 
     async fn save_segment_metadata(&self, segment: &RecordingSegment) -> Result<()> {
-        let metadata_path = std::env::current_dir()?
-            .join("recordings")
-            .join("metadata")
-            .join(format!("{}.json", segment.id));
-        
-        fs::create_dir_all(metadata_path.parent().unwrap()).await?;
-        
-        let metadata_json = serde_json::to_string_pretty(segment)?;
-        fs::write(metadata_path, metadata_json).await?;
-        
-        Ok(())
+        RecordingIndex::open()?.upsert_segment(segment)
     }
 
     pub async fn verify_segment_integrity(
@@ -413,44 +939,393 @@ async fn start_real_recording(
     }
 
     async fn upload_segment(&self, segment: &RecordingSegment) -> Result<()> {
+        cancellable(&self.cancel, "segment upload", self.send_segment(segment)).await
+    }
+
+    async fn send_segment(&self, segment: &RecordingSegment) -> Result<()> {
         println!("Uploading segment {}...", segment.id);
         
-        // Simulate upload delay based on file size
-        if let Some(file_size) = segment.file_size {
-            let upload_time = file_size / self.config.network.upload_bandwidth as u64;
-            tokio::time::sleep(tokio::time::Duration::from_secs(upload_time)).await;
+        if self.config.simulation.enabled {
+            // Simulate upload delay based on file size
+            if let Some(file_size) = segment.file_size {
+                let upload_time = file_size / self.config.network.upload_bandwidth as u64;
+                tokio::time::sleep(tokio::time::Duration::from_secs(upload_time)).await;
+            }
+
+            // Nothing confirmed the upload, so the file is never deleted
+            println!("Segment {} uploaded (simulated), keeping local copy", segment.id);
+            return Ok(());
+        }
+
+        if crate::data_usage::cellular_capped() {
+            return Err(anyhow::anyhow!("Cellular data cap reached, segment {} kept until Wi-Fi", segment.id));
         }
+
+        let checksums = crate::backend::create_backend(&self.config)?
+            .upload_segment(segment, self.events.as_ref())
+            .await
+            .with_context(|| format!("Segment {} not uploaded, keeping local copy", segment.id))?;
+        
+        println!("Segment {} uploaded and verified by server", segment.id);
         
-        println!("Segment {} uploaded successfully", segment.id);
+        // The storage manager deletes it (and its location track) once the
+        // grace period has passed, unless the site keeps local copies
+        let mut related = Vec::new();
+        if let Some(track) = &segment.metadata.location_track {
+            let sidecar = Path::new(&segment.file_path).with_file_name(&track.file_name);
+            related.push(sidecar.to_string_lossy().to_string());
+        }
+        crate::storage_manager::record_confirmed_upload(
+            Path::new(&segment.file_path),
+            &checksums.sha256,
+            related,
+        ).await?;
         
-        // Auto-delete file after successful upload
-        let file_path = PathBuf::from(&segment.file_path);
-        if file_path.exists() {
-            match fs::remove_file(&file_path).await {
-                Ok(_) => {
-                    println!("Successfully deleted uploaded file: {}", segment.file_path);
-                    
-                    // Also delete associated metadata file
-                    let metadata_path = std::env::current_dir()?
-                        .join("recordings")
-                        .join("metadata")
-                        .join(format!("{}.json", segment.id));
-                    
-                    if metadata_path.exists() {
-                        let _ = fs::remove_file(&metadata_path).await;
+        Ok(())
+    }
+
+    /// Handle a capture device disappearing mid-recording. Affected qualities
+    /// continue on `fallback` when it is available, otherwise they are paused
+    /// until the device returns. Returns true if an active recording was affected.
+    pub async fn handle_device_removed(&mut self, device_path: &str, fallback: Option<&str>) -> Result<bool> {
+        if self.mode == RecordingMode::AudioOnly {
+            return Ok(false);
+        }
+
+        let affected: Vec<crate::config::VideoQualityConfig> = self.config.recording.available_qualities
+            .iter()
+            .filter(|q| q.device_path == device_path && self.is_encoding(&q.quality))
+            .cloned()
+            .collect();
+
+        if affected.is_empty() {
+            return Ok(false);
+        }
+
+        let fallback = fallback.filter(|f| *f != device_path && std::path::Path::new(f).exists());
+        self.stop_encoders(&[], Some(device_path)).await;
+
+        match fallback {
+            Some(fallback_path) => {
+                let fallback_configs = affected.into_iter()
+                    .map(|quality_config| {
+                        tracing::warn!("Camera {} removed, switching {:?} recording to {}", 
+                            device_path, quality_config.quality, fallback_path);
+                        crate::config::VideoQualityConfig { device_path: fallback_path.to_string(), ..quality_config }
+                    })
+                    .collect();
+                self.restart_into_next_parts(fallback_configs).await?;
+            }
+            None => {
+                for quality_config in affected {
+                    tracing::error!("Camera {} removed, {:?} recording paused until it returns", 
+                        device_path, quality_config.quality);
+                    self.paused_qualities.insert(quality_config.quality.clone(), device_path.to_string());
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Resume any qualities that were paused waiting for `device_path`
+    pub async fn handle_device_added(&mut self, device_path: &str) -> Result<()> {
+        let resumable: Vec<crate::config::VideoQualityConfig> = self.config.recording.available_qualities
+            .iter()
+            .filter(|q| self.paused_qualities.get(&q.quality).map(|p| p == device_path).unwrap_or(false))
+            .cloned()
+            .collect();
+        if resumable.is_empty() {
+            return Ok(());
+        }
+
+        for quality_config in &resumable {
+            tracing::info!("Camera {} reconnected, resuming {:?} recording", device_path, quality_config.quality);
+            self.paused_qualities.remove(&quality_config.quality);
+        }
+        self.restart_into_next_parts(resumable).await
+    }
+
+    /// Switch running recordings between day and night encoder settings.
+    /// Each switch starts a new part so day and IR footage never share a file,
+    /// and the IR period is recorded in the segment metadata for reviewers.
+    pub async fn set_night_mode(&mut self, overrides: Option<Vec<crate::config::VideoQualityConfig>>) -> Result<()> {
+        if self.mode == RecordingMode::AudioOnly {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let entering = overrides.is_some();
+        self.night_overrides = overrides.map(|configs| {
+            configs.into_iter().map(|c| (c.quality.clone(), c)).collect()
+        });
+
+        let running: Vec<crate::config::VideoQualityConfig> = self.config.recording.available_qualities
+            .iter()
+            .filter(|q| self.is_encoding(&q.quality))
+            .filter_map(|q| self.quality_config(&q.quality))
+            .collect();
+        if running.is_empty() {
+            return Ok(());
+        }
+
+        for quality_config in &running {
+            if let Some(segment) = self.current_segments.get_mut(&quality_config.quality) {
+                if entering {
+                    segment.metadata.ir_periods.push(IrPeriod { start: now, end: None });
+                } else {
+                    for period in segment.metadata.ir_periods.iter_mut().filter(|p| p.end.is_none()) {
+                        period.end = Some(now);
                     }
                 }
-                Err(e) => {
-                    tracing::warn!("Failed to delete uploaded file {}: {}", segment.file_path, e);
+            }
+        }
+
+        self.restart_into_next_parts(running).await
+    }
+
+    /// Handle encoders that exited on their own since the last call. A crashed
+    /// encoder is restarted into a new part of each of its segments, so the
+    /// footage before the crash stays intact, until it has crashed too many
+    /// times.
+    pub async fn supervise(&mut self) -> Vec<EncoderCrash> {
+        let mut crashes = Vec::new();
+
+        if crate::simulation::faults::take(crate::simulation::faults::Fault::FfmpegCrash) {
+            self.inject_encoder_crash().await;
+        }
+
+        if self.paused_at.is_none() {
+            self.continue_stopped_encoders();
+        }
+
+        while let Ok(exit) = self.exit_rx.try_recv() {
+            let Some(index) = self.encoders.iter().position(|encoder| encoder.process.id() == exit.process_id) else {
+                continue;
+            };
+            let qualities = self.encoders.remove(index).qualities;
+
+            // A timed recording reaching its end is not a crash
+            if exit.success() && self.duration.is_some() {
+                tracing::info!("{} finished", exit.label);
+                continue;
+            }
+
+            tracing::error!("{} exited unexpectedly ({:?}): {}",
+                exit.label, exit.status, exit.stderr_tail.last().map(String::as_str).unwrap_or(""));
+
+            let mut restarts = 0;
+            for quality in &qualities {
+                let count = self.restart_counts.entry(quality.clone()).or_insert(0);
+                *count += 1;
+                restarts = restarts.max(*count);
+            }
+            let restarted = if restarts <= MAX_ENCODER_RESTARTS {
+                match self.restart_qualities(&qualities).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::error!("Failed to restart {}: {:#}", exit.label, e);
+                        false
+                    }
                 }
+            } else {
+                tracing::error!("{} crashed {} times, giving up", exit.label, MAX_ENCODER_RESTARTS);
+                false
+            };
+
+            crashes.push(EncoderCrash {
+                qualities,
+                label: exit.label.clone(),
+                exit_code: exit.status.and_then(|s| s.code()),
+                signal: exit.signal(),
+                stderr_tail: exit.stderr_tail,
+                restarted,
+            });
+        }
+
+        crashes
+    }
+
+    /// Kill one running encoder the way a crash would, so its exit is
+    /// reported and handled on the next check
+    async fn inject_encoder_crash(&self) {
+        let Some(encoder) = self.encoders.first() else {
+            tracing::warn!("Injected encoder crash ignored: nothing is recording");
+            return;
+        };
+        let Some(pid) = encoder.process.pid() else {
+            return;
+        };
+
+        tracing::warn!("Injecting crash into {} (pid {})", encoder.process.label(), pid);
+        if let Err(e) = signal::kill(Pid::from_raw(pid as i32), Signal::SIGKILL) {
+            tracing::error!("Failed to inject encoder crash: {}", e);
+        }
+    }
+
+    /// Continue any encoder left stopped while the recording isn't paused,
+    /// so it can't sit frozen without being noticed
+    fn continue_stopped_encoders(&self) {
+        for encoder in &self.encoders {
+            let Some(pid) = encoder.process.pid() else { continue };
+            if process_state(pid) != Some('T') {
+                continue;
+            }
+            tracing::error!("{} was stopped while recording, continuing it", encoder.process.label());
+            if let Err(e) = signal::kill(Pid::from_raw(pid as i32), Signal::SIGCONT) {
+                tracing::error!("Failed to continue {}: {}", encoder.process.label(), e);
             }
         }
-        
+    }
+
+    async fn restart_qualities(&mut self, qualities: &[VideoQuality]) -> Result<()> {
+        let quality_configs = qualities.iter()
+            .map(|quality| self.quality_config(quality)
+                .ok_or_else(|| anyhow::anyhow!("No configuration for quality {:?}", quality)))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.start_next_parts(quality_configs).await
+    }
+
+    /// Latest ffmpeg progress of each running encoder, with the qualities it
+    /// writes
+    pub fn encoder_progress(&self) -> Vec<(Vec<VideoQuality>, EncoderProgress)> {
+        self.encoders
+            .iter()
+            .filter_map(|encoder| encoder.process.progress().map(|p| (encoder.qualities.clone(), p)))
+            .collect()
+    }
+
+    /// Recording performance measured from the encoders. The slowest encoder
+    /// sets the frame rate; dropped frames are summed across all of them.
+    pub fn performance(&self) -> Option<RecordingPerformance> {
+        if self.mode == RecordingMode::AudioOnly || self.paused_at.is_some() {
+            return None;
+        }
+
+        let progress = self.encoder_progress();
+        let (qualities, slowest) = progress.iter()
+            .min_by(|a, b| a.1.fps.partial_cmp(&b.1.fps).unwrap_or(std::cmp::Ordering::Equal))?;
+
+        // An encoder captures at the highest frame rate of its qualities
+        let target_fps = qualities.iter()
+            .filter_map(|quality| self.quality_config(quality))
+            .map(|q| q.fps)
+            .max()
+            .unwrap_or(self.config.recording.fps);
+
+        let bitrate_kbps: f64 = progress.iter().filter_map(|(_, p)| p.bitrate_kbps).sum();
+        let ratio = slowest.fps / target_fps.max(1) as f64;
+
+        Some(RecordingPerformance {
+            current_fps: Some(slowest.fps),
+            target_fps,
+            dropped_frames: progress.iter().map(|(_, p)| p.drop_frames).sum(),
+            encoding_latency_ms: None,
+            disk_write_speed_mbps: Some(bitrate_kbps / 8.0 / 1000.0),
+            recording_status: if slowest.frame == 0 {
+                HealthStatus::Unknown
+            } else if ratio >= 0.9 {
+                HealthStatus::Healthy
+            } else if ratio >= 0.5 {
+                HealthStatus::Warning
+            } else {
+                HealthStatus::Critical
+            },
+        })
+    }
+
+    pub fn has_paused_qualities(&self) -> bool {
+        !self.paused_qualities.is_empty()
+    }
+
+    /// Suspend the encoders, leaving a gap in every segment until `resume`.
+    /// The files stay open, so the recording carries on where it left off.
+    pub async fn pause(&mut self, reason: Option<String>) -> Result<()> {
+        if self.paused_at.is_some() {
+            return Err(anyhow::anyhow!("Recording is already paused"));
+        }
+        self.signal_encoders(Signal::SIGSTOP)?;
+        let now = Utc::now();
+        self.paused_at = Some(now);
+        for segment in self.current_segments.values_mut() {
+            segment.metadata.gaps.push(RecordingGap { start: now, end: None, reason: reason.clone() });
+        }
+        Ok(())
+    }
+
+    pub async fn resume(&mut self) -> Result<()> {
+        if self.paused_at.is_none() {
+            return Err(anyhow::anyhow!("Recording is not paused"));
+        }
+        self.signal_encoders(Signal::SIGCONT)?;
+        self.paused_at = None;
+        let now = Utc::now();
+        for segment in self.current_segments.values_mut() {
+            recording_pause::close_gaps(&mut segment.metadata.gaps, now);
+        }
+        Ok(())
+    }
+
+    /// When the current pause started
+    pub fn paused_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.paused_at
+    }
+
+    /// Each current segment with the seconds recorded into it by `at`, for
+    /// bookmarking the point being recorded
+    pub fn positions(&self, at: chrono::DateTime<Utc>) -> Vec<(String, f64)> {
+        self.current_segments.values()
+            .map(|segment| (segment.id.clone(), recording_pause::recorded_seconds(segment.start_time, at, &segment.metadata.gaps) as f64))
+            .collect()
+    }
+
+    fn signal_encoders(&self, signal: Signal) -> Result<()> {
+        for encoder in &self.encoders {
+            let Some(pid) = encoder.process.pid() else { continue };
+            signal::kill(Pid::from_raw(pid as i32), signal)
+                .with_context(|| format!("Failed to send {} to {}", signal.as_str(), encoder.process.label()))?;
+        }
         Ok(())
     }
 
+    /// Continue segments into new files so the interrupted ones stay intact
+    async fn start_next_parts(&mut self, quality_configs: Vec<crate::config::VideoQualityConfig>) -> Result<()> {
+        if crate::simulation::faults::is_active(crate::simulation::faults::Fault::DiskFull) {
+            return Err(MediaError::DiskFull.into());
+        }
+
+        for quality_config in &quality_configs {
+            let segment = self.current_segments.get_mut(&quality_config.quality)
+                .ok_or_else(|| MediaError::NoActiveSegment { quality: format!("{:?}", quality_config.quality) })?;
+
+            segment.previous_parts.push(segment.file_path.clone());
+            segment.file_path = part_path(&segment.previous_parts[0], segment.previous_parts.len() + 1);
+            self.part_starts.entry(quality_config.quality.clone()).or_default().push(Utc::now());
+        }
+
+        if self.mode == RecordingMode::AudioOnly {
+            for quality_config in &quality_configs {
+                let file_path = PathBuf::from(&self.current_segments[&quality_config.quality].file_path);
+                self.start_audio_process(&quality_config.quality, &file_path).await?;
+            }
+        } else {
+            self.start_encoders(quality_configs).await?;
+        }
+        // Encoders restarted during a pause stay paused
+        if self.paused_at.is_some() {
+            self.signal_encoders(Signal::SIGSTOP)?;
+        }
+        Ok(())
+    }
+
+    /// Frame luminance an encoder measured since the last call
+    pub fn take_frame_luminance(&self) -> Option<f64> {
+        self.encoders.iter().find_map(|encoder| encoder.process.take_luminance())
+    }
+
     pub fn is_recording(&self) -> bool {
-        !self.recording_processes.is_empty()
+        !self.encoders.is_empty()
     }
 
     pub fn get_current_segments(&self) -> &HashMap<VideoQuality, RecordingSegment> {
@@ -491,6 +1366,94 @@ pub struct MediaFileInfo {
     pub incident_id: Option<String>,
 }
 
+/// A recording on local storage, as shown in the recordings browser
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingFile {
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub modified_at: chrono::DateTime<chrono::Utc>,
+    pub incident_id: Option<String>,
+    pub segment_id: Option<String>,
+    pub legal_hold: Option<crate::legal_hold::LegalHold>,
+}
+
+/// Recordings under `recordings/<date>/`, newest first, with any legal
+/// hold that covers them
+pub async fn list_recordings(
+    recordings_dir: &Path,
+    holds: &crate::legal_hold::LegalHolds,
+) -> Result<Vec<RecordingFile>> {
+    let mut recordings = Vec::new();
+    if !recordings_dir.exists() {
+        return Ok(recordings);
+    }
+
+    let mut days = tokio::fs::read_dir(recordings_dir).await?;
+    while let Some(day) = days.next_entry().await? {
+        // Date folders only; metadata and stream copies live alongside
+        let is_date = day.file_name().to_str()
+            .map(|name| chrono::NaiveDate::parse_from_str(name, "%Y-%m-%d").is_ok())
+            .unwrap_or(false);
+        if !is_date || !day.file_type().await?.is_dir() {
+            continue;
+        }
+
+        let mut entries = tokio::fs::read_dir(day.path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if location_track::is_sidecar(&path) {
+                continue;
+            }
+            // <device>_<incident>_<segment>_<quality>.<ext>
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let parts: Vec<&str> = stem.split('_').collect();
+
+            recordings.push(RecordingFile {
+                path: path.to_string_lossy().to_string(),
+                file_name,
+                size_bytes: metadata.len(),
+                modified_at: chrono::DateTime::from(metadata.modified()?),
+                incident_id: parts.get(1).map(|s| s.to_string()),
+                segment_id: parts.get(2).map(|s| s.to_string()),
+                legal_hold: holds.hold_for(&path).cloned(),
+            });
+        }
+    }
+
+    recordings.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    Ok(recordings)
+}
+
+/// Indexed segments whose files are still on local storage, in the order
+/// given, with any legal hold that covers them
+pub fn indexed_recordings(
+    segments: &[RecordingSegment],
+    holds: &crate::legal_hold::LegalHolds,
+) -> Vec<RecordingFile> {
+    segments.iter()
+        .filter_map(|segment| {
+            let path = Path::new(&segment.file_path);
+            let metadata = std::fs::metadata(path).ok().filter(|metadata| metadata.is_file())?;
+            Some(RecordingFile {
+                path: segment.file_path.clone(),
+                file_name: path.file_name()?.to_string_lossy().to_string(),
+                size_bytes: metadata.len(),
+                modified_at: segment.end_time.unwrap_or(segment.start_time),
+                incident_id: Some(segment.incident_id.clone()),
+                segment_id: Some(segment.id.clone()),
+                legal_hold: holds.hold_for(path).cloned(),
+            })
+        })
+        .collect()
+}
+
 pub async fn analyze_storage_usage(media_dir: &Path) -> Result<Vec<StorageBreakdown>> {
     if !media_dir.exists() {
         return Ok(vec![]);
@@ -618,4 +1581,120 @@ pub async fn get_media_files(media_dir: &Path) -> Result<Vec<MediaFileInfo>> {
     }
 
     Ok(files)
-}
\ No newline at end of file
+}
+
+/// State letter of a running process from `/proc/<pid>/stat`, `T` while
+/// it's stopped by a signal
+fn process_state(pid: u32) -> Option<char> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name before the state may itself contain spaces
+    stat.rsplit_once(')')?.1.trim_start().chars().next()
+}
+
+/// File name of part `part` (from 2) of a segment, e.g. `seg_part3.mp4`
+/// for `seg.mp4`. Always derived from the first file, so names don't nest.
+fn part_path(first: &str, part: usize) -> String {
+    let first = Path::new(first);
+    let stem = first.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = first.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "mp4".to_string());
+    first.with_file_name(format!("{}_part{}.{}", stem, part, extension)).to_string_lossy().to_string()
+}
+
+/// One segment per file written, each covering the time its file was
+/// recorded. Earlier parts get their own ids; the last keeps the segment's.
+fn split_parts(segment: RecordingSegment, part_starts: &[chrono::DateTime<Utc>]) -> Vec<RecordingSegment> {
+    let mut files = segment.previous_parts.clone();
+    files.push(segment.file_path.clone());
+    let end_time = segment.end_time.unwrap_or_else(Utc::now);
+
+    let mut starts = vec![segment.start_time];
+    starts.extend(part_starts.iter().copied());
+    starts.resize(files.len(), end_time);
+
+    let last = files.len() - 1;
+    files.into_iter().enumerate().map(|(index, file_path)| {
+        let mut part = segment.clone();
+        part.start_time = starts[index];
+        part.end_time = Some(starts.get(index + 1).copied().unwrap_or(end_time));
+        part.file_path = file_path;
+        part.previous_parts = Vec::new();
+        if index < last {
+            part.id = format!("{}-part{}", segment.id, index + 1);
+        }
+        part
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_names_do_not_nest() {
+        assert_eq!(part_path("/rec/seg.mp4", 2), "/rec/seg_part2.mp4");
+        assert_eq!(part_path("/rec/seg.mp4", 3), "/rec/seg_part3.mp4");
+    }
+
+    /// State of `pid` once a signal sent to it has been delivered
+    async fn settled_state(pid: u32) -> Option<char> {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        process_state(pid)
+    }
+
+    fn recorder_with_stand_in_encoder() -> (MediaRecorder, u32) {
+        let mut recorder = MediaRecorder::new(Config::default(), "test-device".to_string(), "test-incident".to_string(), None);
+        let mut cmd = Command::new("sleep");
+        cmd.arg("30");
+        let process = MonitoredProcess::spawn(cmd, "test encoder", recorder.exit_tx.clone()).unwrap();
+        let pid = process.pid().unwrap();
+        recorder.encoders.push(Encoder { device_path: "/dev/video0".to_string(), qualities: vec![VideoQuality::High], process });
+        (recorder, pid)
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_stop_leaves_no_stopped_encoder() {
+        let (mut recorder, pid) = recorder_with_stand_in_encoder();
+
+        recorder.pause(None).await.unwrap();
+        assert_eq!(settled_state(pid).await, Some('T'));
+        recorder.resume().await.unwrap();
+        assert_ne!(settled_state(pid).await, Some('T'));
+
+        // Stopping while paused still ends the encoder
+        recorder.pause(None).await.unwrap();
+        recorder.stop().await.unwrap();
+        assert_eq!(settled_state(pid).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_supervise_continues_an_encoder_stopped_outside_a_pause() {
+        let (mut recorder, pid) = recorder_with_stand_in_encoder();
+
+        signal::kill(Pid::from_raw(pid as i32), Signal::SIGSTOP).unwrap();
+        assert_eq!(settled_state(pid).await, Some('T'));
+        assert!(recorder.supervise().await.is_empty());
+        assert_ne!(settled_state(pid).await, Some('T'));
+
+        recorder.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rotation_carries_on_into_new_segments() {
+        let mut config = Config::default();
+        config.simulation.enabled = true;
+        config.network.upload_bandwidth = 0;
+        let mut recorder = MediaRecorder::new(config, "test-device".to_string(),
+            crate::loop_recording::LOOP_INCIDENT_ID.to_string(), None);
+        recorder.start().await.unwrap();
+        let first: Vec<String> = recorder.get_current_segments().values().map(|s| s.id.clone()).collect();
+
+        let incident_id = Uuid::new_v4().to_string();
+        recorder.rotate(incident_id.clone()).await.unwrap();
+        assert!(recorder.is_recording());
+        let segments = recorder.get_current_segments();
+        assert_eq!(segments.len(), first.len());
+        assert!(segments.values().all(|s| !first.contains(&s.id) && s.incident_id == incident_id));
+
+        recorder.stop().await.unwrap();
+    }
+}
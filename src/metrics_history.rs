@@ -0,0 +1,305 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::api::DeviceMetrics;
+use crate::diagnostics::ErrorTrend;
+
+/// Growth rates need samples spanning at least this long to mean anything
+const MIN_GROWTH_SPAN_HOURS: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS metrics (
+        device_id TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        cpu_usage REAL NOT NULL,
+        memory_usage REAL NOT NULL,
+        storage_usage REAL NOT NULL,
+        battery_level REAL NOT NULL,
+        temperature REAL NOT NULL,
+        network_quality TEXT NOT NULL,
+        active_incidents INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS metrics_timestamp ON metrics (timestamp);
+    CREATE TABLE IF NOT EXISTS samples (
+        series TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        value REAL NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS samples_series_timestamp ON samples (series, timestamp);
+";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsHistoryConfig {
+    pub enabled: bool,
+    /// Metrics older than this are dropped
+    pub retention_days: u32,
+}
+
+impl Default for MetricsHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 14,
+        }
+    }
+}
+
+/// Storage used by each category, sampled alongside the metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageSeries {
+    Recordings,
+    Logs,
+    Temp,
+    System,
+}
+
+impl StorageSeries {
+    fn series(self) -> &'static str {
+        match self {
+            StorageSeries::Recordings => "storage.recordings_gb",
+            StorageSeries::Logs => "storage.logs_gb",
+            StorageSeries::Temp => "storage.temp_gb",
+            StorageSeries::System => "storage.system_gb",
+        }
+    }
+}
+
+/// Metrics kept on the device in SQLite, so trends can be worked out
+/// without the backend
+pub struct MetricsHistory {
+    connection: Mutex<Connection>,
+    retention: Duration,
+}
+
+impl MetricsHistory {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("metrics.db")
+    }
+
+    pub fn open(config: &MetricsHistoryConfig) -> Result<Self> {
+        Self::open_at(&Self::default_path(), config)
+    }
+
+    pub fn open_at(path: &Path, config: &MetricsHistoryConfig) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open metrics history {}", path.display()))?;
+        connection.execute_batch(SCHEMA).context("Failed to create metrics history tables")?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            retention: Duration::days(config.retention_days as i64),
+        })
+    }
+
+    pub fn record_metrics(&self, metrics: &DeviceMetrics) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO metrics (device_id, timestamp, cpu_usage, memory_usage, storage_usage, battery_level, temperature, network_quality, active_incidents)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                metrics.device_id,
+                metrics.timestamp.timestamp(),
+                metrics.cpu_usage,
+                metrics.memory_usage,
+                metrics.storage_usage,
+                metrics.battery_level,
+                metrics.temperature,
+                metrics.network_quality,
+                metrics.active_incidents,
+            ],
+        )?;
+        Self::prune(&connection, metrics.timestamp - self.retention)
+    }
+
+    pub fn record_storage(&self, series: StorageSeries, used_gb: f64, at: DateTime<Utc>) -> Result<()> {
+        self.record(series.series(), used_gb, at)
+    }
+
+    /// Count one error reported by `component`
+    pub fn record_error(&self, component: &str, at: DateTime<Utc>) -> Result<()> {
+        self.record(&format!("errors.{}", component), 1.0, at)
+    }
+
+    fn record(&self, series: &str, value: f64, at: DateTime<Utc>) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO samples (series, timestamp, value) VALUES (?1, ?2, ?3)",
+            params![series, at.timestamp(), value],
+        )?;
+        Self::prune(&connection, at - self.retention)
+    }
+
+    fn prune(connection: &Connection, before: DateTime<Utc>) -> Result<()> {
+        connection.execute("DELETE FROM metrics WHERE timestamp < ?1", params![before.timestamp()])?;
+        connection.execute("DELETE FROM samples WHERE timestamp < ?1", params![before.timestamp()])?;
+        Ok(())
+    }
+
+    /// Metrics recorded since `since`, oldest first
+    pub fn metrics_since(&self, since: DateTime<Utc>) -> Result<Vec<DeviceMetrics>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT device_id, timestamp, cpu_usage, memory_usage, storage_usage, battery_level, temperature, network_quality, active_incidents
+             FROM metrics WHERE timestamp >= ?1 ORDER BY timestamp",
+        )?;
+        let rows = statement.query_map(params![since.timestamp()], |row| {
+            Ok(DeviceMetrics {
+                device_id: row.get(0)?,
+                timestamp: DateTime::<Utc>::from_timestamp(row.get(1)?, 0).unwrap_or_default(),
+                cpu_usage: row.get(2)?,
+                memory_usage: row.get(3)?,
+                storage_usage: row.get(4)?,
+                battery_level: row.get(5)?,
+                temperature: row.get(6)?,
+                network_quality: row.get(7)?,
+                active_incidents: row.get(8)?,
+                data_usage: None,
+            })
+        })?;
+        let metrics = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(metrics)
+    }
+
+    /// How fast a storage category grew since `since`, in GB a day
+    pub fn storage_growth_per_day(&self, series: StorageSeries, since: DateTime<Utc>) -> Result<Option<f64>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT timestamp, value FROM samples WHERE series = ?1 AND timestamp >= ?2 ORDER BY timestamp",
+        )?;
+        let samples = statement
+            .query_map(params![series.series(), since.timestamp()], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(growth_per_day(&samples))
+    }
+
+    /// Errors per component per day since `since`, oldest day first
+    pub fn error_trends(&self, since: DateTime<Utc>) -> Result<Vec<ErrorTrend>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT date(timestamp, 'unixepoch') AS day, substr(series, 8), COUNT(*)
+             FROM samples WHERE series LIKE 'errors.%' AND timestamp >= ?1
+             GROUP BY day, series ORDER BY day, series",
+        )?;
+        let rows = statement.query_map(params![since.timestamp()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+        })?;
+
+        let mut trends = Vec::new();
+        for row in rows {
+            let (day, component, count) = row?;
+            let date = NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                .with_context(|| format!("Bad date {} in metrics history", day))?;
+            trends.push(ErrorTrend {
+                date: date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+                error_count: count as u64,
+                component,
+            });
+        }
+        Ok(trends)
+    }
+}
+
+/// Least-squares slope of `(unix seconds, value)` samples, per day
+fn growth_per_day(samples: &[(i64, f64)]) -> Option<f64> {
+    let (first, last) = (samples.first()?.0, samples.last()?.0);
+    if last - first < MIN_GROWTH_SPAN_HOURS * 3600 {
+        return None;
+    }
+    let days: Vec<f64> = samples.iter().map(|(at, _)| (at - first) as f64 / 86_400.0).collect();
+    let n = samples.len() as f64;
+    let mean_x = days.iter().sum::<f64>() / n;
+    let mean_y = samples.iter().map(|(_, value)| value).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, (_, y)) in days.iter().zip(samples) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance += (x - mean_x).powi(2);
+    }
+    Some(covariance / variance)
+}
+
+/// A look-back window like `30m`, `24h` or `7d`
+pub fn parse_window(window: &str) -> Result<Duration> {
+    let window = window.trim();
+    let split = window.find(|c: char| !c.is_ascii_digit()).unwrap_or(window.len());
+    let (amount, unit) = window.split_at(split);
+    let amount: i64 = amount.parse().with_context(|| format!("Invalid window {:?}", window))?;
+    match unit {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" | "" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(anyhow::anyhow!("Unknown unit {:?} in window {:?}, use s, m, h, d or w", unit, window)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(at: DateTime<Utc>, cpu_usage: f32) -> DeviceMetrics {
+        DeviceMetrics {
+            device_id: "dev-1".to_string(),
+            timestamp: at,
+            cpu_usage,
+            memory_usage: 40.0,
+            storage_usage: 20.0,
+            battery_level: 80.0,
+            temperature: 35.0,
+            network_quality: "good".to_string(),
+            active_incidents: 0,
+            data_usage: None,
+        }
+    }
+
+    #[test]
+    fn test_history_trends_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = MetricsHistory::open_at(&dir.path().join("metrics.db"), &MetricsHistoryConfig {
+            enabled: true,
+            retention_days: 7,
+        })
+        .unwrap();
+        let start = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+
+        history.record_metrics(&metrics(start, 10.0)).unwrap();
+        history.record_metrics(&metrics(start + Duration::days(2), 20.0)).unwrap();
+        // Pushes the first sample out of the retention window
+        history.record_metrics(&metrics(start + Duration::days(8), 30.0)).unwrap();
+        let kept = history.metrics_since(start).unwrap();
+        assert_eq!(kept.iter().map(|m| m.cpu_usage).collect::<Vec<_>>(), vec![20.0, 30.0]);
+
+        for day in 0..4 {
+            history.record_storage(StorageSeries::Recordings, 10.0 + day as f64 * 0.5, start + Duration::days(day)).unwrap();
+        }
+        let growth = history.storage_growth_per_day(StorageSeries::Recordings, start).unwrap().unwrap();
+        assert!((growth - 0.5).abs() < 1e-9);
+        assert!(history.storage_growth_per_day(StorageSeries::Logs, start).unwrap().is_none());
+
+        history.record_error("camera", start).unwrap();
+        history.record_error("camera", start + Duration::minutes(5)).unwrap();
+        history.record_error("gps", start + Duration::days(1)).unwrap();
+        let trends = history.error_trends(start - Duration::days(1)).unwrap();
+        assert_eq!(trends.len(), 2);
+        assert_eq!((trends[0].component.as_str(), trends[0].error_count), ("camera", 2));
+        assert_eq!(trends[1].date, start.date_naive().succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_window("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_window("30m").unwrap(), Duration::minutes(30));
+        assert!(parse_window("soon").is_err());
+        assert!(parse_window("3y").is_err());
+    }
+}
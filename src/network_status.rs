@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::event_bus::{BusEvent, EventBus, NetworkEvent, Topic, UploadEvent};
+
+/// How often the route table and signal level are re-read
+const LINK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Weight of the newest sample in the smoothed upload rate
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    Wifi,
+    Cellular,
+    Ethernet,
+    #[default]
+    None,
+}
+
+impl LinkKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkKind::Wifi => "wifi",
+            LinkKind::Cellular => "cellular",
+            LinkKind::Ethernet => "ethernet",
+            LinkKind::None => "none",
+        }
+    }
+
+    pub fn from_interface(name: &str) -> Self {
+        if name.starts_with("wl") {
+            LinkKind::Wifi
+        } else if ["wwan", "rmnet", "ppp", "usb"].iter().any(|prefix| name.starts_with(prefix)) {
+            LinkKind::Cellular
+        } else {
+            LinkKind::Ethernet
+        }
+    }
+}
+
+/// What the network panel shows: the link in use and how uploads are doing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSnapshot {
+    /// Has a default route and the backend hasn't been reported unreachable
+    pub online: bool,
+    /// Interface carrying the default route, e.g. `wlan0`
+    pub interface: Option<String>,
+    pub link: LinkKind,
+    /// Wi-Fi signal level in dBm
+    pub signal_dbm: Option<i32>,
+    /// Smoothed rate of bytes confirmed by the server
+    pub upload_bytes_per_second: f64,
+    /// Files queued or uploading
+    pub queue_depth: usize,
+    pub bytes_remaining: u64,
+    /// None until a rate has been measured
+    pub eta_seconds: Option<u64>,
+}
+
+/// Shared view of the network panel, read by the UI and diagnostics
+#[derive(Debug, Clone, Default)]
+pub struct NetworkStatusHandle {
+    state: Arc<Mutex<NetworkSnapshot>>,
+}
+
+impl NetworkStatusHandle {
+    pub fn get(&self) -> NetworkSnapshot {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut NetworkSnapshot)) {
+        f(&mut self.state.lock().unwrap());
+    }
+}
+
+/// Interface of the default route in `/proc/net/route` contents
+pub fn default_route_interface(route_table: &str) -> Option<String> {
+    route_table.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let interface = fields.next()?;
+        (fields.next()? == "00000000").then(|| interface.to_string())
+    })
+}
+
+/// Signal level of `interface` in `/proc/net/wireless` contents
+pub fn wireless_signal(wireless: &str, interface: &str) -> Option<i32> {
+    wireless.lines().skip(2).find_map(|line| {
+        let (name, rest) = line.split_once(':')?;
+        if name.trim() != interface {
+            return None;
+        }
+        let level = rest.split_whitespace().nth(2)?;
+        level.trim_end_matches('.').parse::<f64>().ok().map(|dbm| dbm as i32)
+    })
+}
+
+/// Upload progress per file, turned into a rate, queue depth and ETA
+#[derive(Debug, Default)]
+pub struct UploadTracker {
+    /// file id -> (bytes uploaded, bytes total)
+    files: HashMap<String, (u64, u64)>,
+    bytes_per_second: f64,
+    last_progress: Option<Instant>,
+}
+
+impl UploadTracker {
+    pub fn observe(&mut self, event: &UploadEvent, now: Instant) {
+        match event {
+            UploadEvent::Queued { file_id, .. } => {
+                self.files.entry(file_id.clone()).or_insert((0, 0));
+            }
+            UploadEvent::Progress { file_id, bytes_uploaded, bytes_total } => {
+                let previous = self.files.insert(file_id.clone(), (*bytes_uploaded, *bytes_total))
+                    .map_or(0, |(uploaded, _)| uploaded);
+                let sent = bytes_uploaded.saturating_sub(previous);
+                if let Some(last) = self.last_progress {
+                    let elapsed = now.duration_since(last).as_secs_f64();
+                    if elapsed > 0.0 {
+                        let sample = sent as f64 / elapsed;
+                        self.bytes_per_second = if self.bytes_per_second == 0.0 {
+                            sample
+                        } else {
+                            THROUGHPUT_SMOOTHING * sample + (1.0 - THROUGHPUT_SMOOTHING) * self.bytes_per_second
+                        };
+                    }
+                }
+                self.last_progress = Some(now);
+            }
+            UploadEvent::Completed { file_id } | UploadEvent::Failed { file_id, .. } => {
+                self.files.remove(file_id);
+                if self.files.is_empty() {
+                    self.last_progress = None;
+                }
+            }
+        }
+    }
+
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes_per_second
+    }
+
+    pub fn queue_depth(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Bytes still to send for files whose size is known
+    pub fn bytes_remaining(&self) -> u64 {
+        self.files.values().map(|(uploaded, total)| total.saturating_sub(*uploaded)).sum()
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        (self.bytes_per_second > 0.0)
+            .then(|| Duration::from_secs_f64(self.bytes_remaining() as f64 / self.bytes_per_second))
+    }
+}
+
+/// Keeps a `NetworkStatusHandle` current from the route table, the wireless
+/// signal and upload events on the bus
+pub struct NetworkMonitor {
+    handle: NetworkStatusHandle,
+}
+
+impl Default for NetworkMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetworkMonitor {
+    pub fn new() -> Self {
+        Self { handle: NetworkStatusHandle::default() }
+    }
+
+    pub fn handle(&self) -> NetworkStatusHandle {
+        self.handle.clone()
+    }
+
+    pub fn start(&self, events: &EventBus) {
+        let handle = self.handle.clone();
+        let mut subscription = events.subscribe_to(&[Topic::Uploads, Topic::Network]);
+        tokio::spawn(async move {
+            let mut uploads = UploadTracker::default();
+            let mut reachable = true;
+            let mut poll = tokio::time::interval(LINK_POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    event = subscription.recv() => match event {
+                        Some(BusEvent::Upload(event)) => {
+                            uploads.observe(&event, Instant::now());
+                            handle.update(|snapshot| {
+                                snapshot.upload_bytes_per_second = uploads.bytes_per_second();
+                                snapshot.queue_depth = uploads.queue_depth();
+                                snapshot.bytes_remaining = uploads.bytes_remaining();
+                                snapshot.eta_seconds = uploads.eta().map(|eta| eta.as_secs());
+                            });
+                        }
+                        Some(BusEvent::Network(event)) => {
+                            reachable = matches!(event, NetworkEvent::Online);
+                            handle.update(|snapshot| snapshot.online = reachable && snapshot.interface.is_some());
+                        }
+                        Some(_) => {}
+                        None => return,
+                    },
+                    _ = poll.tick() => {
+                        let (interface, signal_dbm) = read_link().await;
+                        handle.update(|snapshot| {
+                            snapshot.link = interface.as_deref().map_or(LinkKind::None, LinkKind::from_interface);
+                            snapshot.online = reachable && interface.is_some();
+                            snapshot.interface = interface;
+                            snapshot.signal_dbm = signal_dbm;
+                        });
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn read_link() -> (Option<String>, Option<i32>) {
+    let interface = tokio::fs::read_to_string("/proc/net/route").await.ok()
+        .and_then(|routes| default_route_interface(&routes));
+    let signal = match &interface {
+        Some(name) => tokio::fs::read_to_string("/proc/net/wireless").await.ok()
+            .and_then(|wireless| wireless_signal(&wireless, name)),
+        None => None,
+    };
+    (interface, signal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_from_proc() {
+        let routes = "Iface\tDestination\tGateway\tFlags\n\
+                      docker0\t0000FEA9\t00000000\t0001\n\
+                      wlan0\t00000000\t0101A8C0\t0003\n";
+        assert_eq!(default_route_interface(routes).as_deref(), Some("wlan0"));
+        assert_eq!(default_route_interface("Iface\tDestination\n"), None);
+
+        let wireless = "Inter-| sta-|   Quality        |   Discarded packets\n \
+                        face | tus | link level noise |  nwid  crypt\n \
+                        wlan0: 0000   54.  -56.  -256        0      0\n";
+        assert_eq!(wireless_signal(wireless, "wlan0"), Some(-56));
+        assert_eq!(wireless_signal(wireless, "eth0"), None);
+        assert_eq!(LinkKind::from_interface("wwan0"), LinkKind::Cellular);
+    }
+
+    #[test]
+    fn test_upload_rate_and_eta() {
+        let start = Instant::now();
+        let mut tracker = UploadTracker::default();
+        tracker.observe(&UploadEvent::Queued { file_id: "a".to_string(), path: "a.mp4".to_string() }, start);
+        tracker.observe(&UploadEvent::Progress { file_id: "a".to_string(), bytes_uploaded: 0, bytes_total: 3_000 }, start);
+        assert_eq!(tracker.eta(), None);
+
+        tracker.observe(
+            &UploadEvent::Progress { file_id: "a".to_string(), bytes_uploaded: 1_000, bytes_total: 3_000 },
+            start + Duration::from_secs(1),
+        );
+        assert_eq!(tracker.bytes_per_second(), 1_000.0);
+        assert_eq!(tracker.queue_depth(), 1);
+        assert_eq!(tracker.eta(), Some(Duration::from_secs(2)));
+
+        tracker.observe(&UploadEvent::Completed { file_id: "a".to_string() }, start + Duration::from_secs(3));
+        assert_eq!(tracker.queue_depth(), 0);
+        assert_eq!(tracker.bytes_remaining(), 0);
+    }
+}
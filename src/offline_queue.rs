@@ -10,6 +10,7 @@ use tracing::{info, warn, error};
 use uuid::Uuid;
 
 use crate::config::Config;
+use crate::event_bus::{BusEvent, EventBus, NetworkEvent};
 use crate::upload_manager::{UploadPriority, UploadStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +46,7 @@ pub struct OfflineQueueManager {
     queue_path: PathBuf,
     upload_queue: Arc<RwLock<HashMap<String, OfflineUploadItem>>>,
     network_monitor: Arc<NetworkMonitor>,
+    events: EventBus,
     upload_command_sender: mpsc::UnboundedSender<crate::upload_manager::UploadCommand>,
     shutdown_sender: mpsc::Sender<()>,
     shutdown_receiver: Arc<RwLock<Option<mpsc::Receiver<()>>>>,
@@ -86,12 +88,19 @@ impl OfflineQueueManager {
             queue_path,
             upload_queue: Arc::new(RwLock::new(HashMap::new())),
             network_monitor,
+            events: EventBus::default(),
             upload_command_sender,
             shutdown_sender,
             shutdown_receiver: Arc::new(RwLock::new(Some(shutdown_receiver))),
         }
     }
 
+    /// Publish connectivity changes on `events`
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
     pub async fn initialize(&self) -> Result<()> {
         // Ensure queue directory exists
         fs::create_dir_all(&self.queue_path).await
@@ -113,6 +122,7 @@ impl OfflineQueueManager {
         let network_monitor = self.network_monitor.clone();
         let upload_queue = self.upload_queue.clone();
         let upload_sender = self.upload_command_sender.clone();
+        let events = self.events.clone();
 
         // Start network monitoring
         tokio::spawn(async move {
@@ -127,10 +137,12 @@ impl OfflineQueueManager {
 
                         if !was_online && is_online {
                             info!("Network connectivity restored - resuming uploads");
+                            events.publish(BusEvent::Network(NetworkEvent::Online));
                             // Trigger upload of pending files
                             Self::trigger_pending_uploads(&upload_queue, &upload_sender).await;
                         } else if was_online && !is_online {
                             warn!("Network connectivity lost - queuing uploads");
+                            events.publish(BusEvent::Network(NetworkEvent::Offline));
                         }
                     }
                     _ = shutdown_receiver.recv() => {
@@ -265,15 +277,9 @@ impl OfflineQueueManager {
 
         queue.retain(|id, item| {
             if item.status == OfflineStatus::Completed {
-                // Try to delete the local file
-                let local_path = PathBuf::from(&item.local_path);
-                if local_path.exists() {
-                    if let Err(e) = std::fs::remove_file(&local_path) {
-                        warn!("Failed to remove completed file {}: {}", item.original_filename, e);
-                    } else {
-                        info!("Cleaned up completed upload: {}", item.original_filename);
-                    }
-                }
+                // The local file is left to the storage manager, which only
+                // deletes it once the server has confirmed its checksum
+                info!("Cleaned up completed upload: {}", item.original_filename);
                 removed_count += 1;
                 false
             } else {
@@ -388,6 +394,11 @@ impl OfflineQueueManager {
 
 impl NetworkMonitor {
     async fn check_connectivity(&self) -> bool {
+        if crate::simulation::network_down() {
+            *self.last_check.write().await = chrono::Utc::now();
+            return false;
+        }
+
         // Simple connectivity check using HTTP
         let client = reqwest::Client::new();
         let timeout = Duration::from_secs(5);
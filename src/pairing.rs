@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::convex_api::DeviceCredentials;
+
+/// A request to add this device to a site, waiting for an administrator.
+/// They approve it by scanning a QR code of `approval_url`, or by typing
+/// `code` into the console.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairingRequest {
+    pub code: String,
+    pub approval_url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PairingRequest {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PairingStatus {
+    Pending,
+    /// Approved, with the credentials to store
+    Approved { credentials: DeviceCredentials },
+    Rejected { reason: Option<String> },
+    Expired,
+}
+
+/// A Wi-Fi network seen in a scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// 0-100
+    pub signal: u8,
+    pub secured: bool,
+}
+
+/// Networks in `nmcli -t -f SSID,SIGNAL,SECURITY` output, strongest first.
+/// Hidden networks are skipped and each SSID is listed once.
+pub fn parse_wifi_list(output: &str) -> Vec<WifiNetwork> {
+    let mut networks: Vec<WifiNetwork> = Vec::new();
+    for line in output.lines() {
+        // nmcli escapes colons in the SSID, so split from the right
+        let mut fields = line.rsplitn(3, ':');
+        let (Some(security), Some(signal), Some(ssid)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let ssid = ssid.replace("\\:", ":").replace("\\\\", "\\");
+        if ssid.is_empty() {
+            continue;
+        }
+        let network = WifiNetwork {
+            ssid,
+            signal: signal.parse().unwrap_or(0),
+            secured: !security.is_empty() && security != "--",
+        };
+        match networks.iter_mut().find(|known| known.ssid == network.ssid) {
+            Some(known) if known.signal < network.signal => *known = network,
+            Some(_) => {}
+            None => networks.push(network),
+        }
+    }
+    networks.sort_by(|a, b| b.signal.cmp(&a.signal));
+    networks
+}
+
+pub async fn scan_wifi() -> Result<Vec<WifiNetwork>> {
+    let output = tokio::process::Command::new("nmcli")
+        .args(["-t", "-f", "SSID,SIGNAL,SECURITY", "device", "wifi", "list", "--rescan", "yes"])
+        .output()
+        .await
+        .context("Failed to run nmcli")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Wi-Fi scan failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_wifi_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub async fn connect_wifi(ssid: &str, password: Option<&str>) -> Result<()> {
+    let mut command = tokio::process::Command::new("nmcli");
+    command.args(["device", "wifi", "connect", ssid]);
+    if let Some(password) = password.filter(|password| !password.is_empty()) {
+        command.args(["password", password]);
+    }
+    let output = command.output().await.context("Failed to run nmcli")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Failed to join {}: {}", ssid, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Modules of a QR code, row by row
+#[derive(Debug, Clone)]
+pub struct QrMatrix {
+    pub width: usize,
+    dark: Vec<bool>,
+}
+
+impl QrMatrix {
+    pub fn encode(text: &str) -> Result<Self> {
+        let code = qrcode::QrCode::new(text.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Can't encode QR code: {}", e))?;
+        Ok(Self {
+            width: code.width(),
+            dark: code.to_colors().into_iter().map(|color| color == qrcode::Color::Dark).collect(),
+        })
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.width && self.dark[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wifi_list() {
+        let output = "Depot:72:WPA2\n\
+                      :90:WPA2\n\
+                      Guest\\:Lobby:40:\n\
+                      Depot:55:WPA2\n";
+        let networks = parse_wifi_list(output);
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0], WifiNetwork { ssid: "Depot".to_string(), signal: 72, secured: true });
+        assert_eq!(networks[1].ssid, "Guest:Lobby");
+        assert!(!networks[1].secured);
+    }
+
+    #[test]
+    fn test_pairing_status_and_qr() {
+        let status: PairingStatus = serde_json::from_value(serde_json::json!({
+            "status": "approved",
+            "credentials": {
+                "device_id": "dev-1",
+                "device_key": "key",
+                "site_id": "site-1",
+                "tenant_id": "tenant-1",
+                "auth_token": "token"
+            }
+        })).unwrap();
+        assert!(matches!(status, PairingStatus::Approved { credentials } if credentials.device_id == "dev-1"));
+
+        let qr = QrMatrix::encode("https://console.example.com/pair/ABCD-1234").unwrap();
+        assert!(qr.width >= 21);
+        // Finder pattern in the top-left corner
+        assert!(qr.is_dark(0, 0));
+        assert!(!qr.is_dark(qr.width, 0));
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+use crate::media::RecordingSegment;
+
+/// Width frames are decoded at; enough for the touchscreen and cheap to decode
+const PREVIEW_WIDTH: u32 = 640;
+
+/// A point of interest on the playback timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub offset_seconds: f64,
+    /// i18n key of the label
+    pub label: &'static str,
+}
+
+/// Timeline bookmarks for `segment`: where night mode switched on and off
+pub fn bookmarks(segment: &RecordingSegment) -> Vec<Bookmark> {
+    let offset = |at: chrono::DateTime<chrono::Utc>| (at - segment.start_time).num_milliseconds().max(0) as f64 / 1000.0;
+    let mut bookmarks: Vec<Bookmark> = segment.metadata.ir_periods.iter()
+        .flat_map(|period| {
+            let start = Bookmark { offset_seconds: offset(period.start), label: "playback.night_mode_on" };
+            let end = period.end.map(|end| Bookmark { offset_seconds: offset(end), label: "playback.night_mode_off" });
+            std::iter::once(start).chain(end)
+        })
+        .collect();
+    bookmarks.sort_by(|a, b| a.offset_seconds.total_cmp(&b.offset_seconds));
+    bookmarks
+}
+
+/// A decoded RGB frame
+#[derive(Debug)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub rgb: Vec<u8>,
+}
+
+/// What the player is showing, read by the UI
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackState {
+    pub frame: Option<Arc<Frame>>,
+    /// Bumped with every new frame, so the UI only uploads changes
+    pub frame_number: u64,
+    pub position_seconds: f64,
+    pub playing: bool,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackHandle {
+    state: Arc<Mutex<PlaybackState>>,
+}
+
+impl PlaybackHandle {
+    pub fn get(&self) -> PlaybackState {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut PlaybackState)) {
+        f(&mut self.state.lock().unwrap());
+    }
+}
+
+/// Plays a local recording by decoding it with ffmpeg at its own pace.
+/// Pausing stops the decoder; playing again restarts it at the position.
+pub struct Player {
+    segment_id: String,
+    source: PathBuf,
+    /// A decrypted copy, removed when the player is closed
+    temporary: bool,
+    width: u32,
+    height: u32,
+    fps: u32,
+    duration_seconds: f64,
+    bookmarks: Vec<Bookmark>,
+    handle: PlaybackHandle,
+    decoder: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Player {
+    pub fn new(segment: &RecordingSegment, source: PathBuf, temporary: bool) -> Self {
+        let (width, height) = preview_size(&segment.metadata.resolution);
+        let duration_seconds = segment.duration
+            .map(|seconds| seconds as f64)
+            .or_else(|| segment.end_time.map(|end| (end - segment.start_time).num_milliseconds() as f64 / 1000.0))
+            .unwrap_or(0.0);
+
+        Self {
+            segment_id: segment.id.clone(),
+            source,
+            temporary,
+            width,
+            height,
+            fps: segment.metadata.fps.max(1),
+            duration_seconds,
+            bookmarks: bookmarks(segment),
+            handle: PlaybackHandle::default(),
+            decoder: None,
+        }
+    }
+
+    pub fn segment_id(&self) -> &str {
+        &self.segment_id
+    }
+
+    pub fn handle(&self) -> PlaybackHandle {
+        self.handle.clone()
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.duration_seconds
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn play(&mut self) -> Result<()> {
+        self.stop_decoder();
+        let mut state = self.handle.get();
+        if state.finished {
+            state.position_seconds = 0.0;
+        }
+
+        let (width, height, fps) = (self.width, self.height, self.fps);
+        let start = state.position_seconds;
+        let mut decoder = Command::new("ffmpeg")
+            .arg("-re")
+            .arg("-ss").arg(format!("{:.3}", start))
+            .arg("-i").arg(&self.source)
+            .arg("-vf").arg(format!("scale={}:{}", width, height))
+            .arg("-f").arg("rawvideo")
+            .arg("-pix_fmt").arg("rgb24")
+            .arg("-loglevel").arg("error")
+            .arg("pipe:1")
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start playback decoder")?;
+        let mut frames = decoder.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("Playback decoder has no stdout"))?;
+
+        let handle = self.handle.clone();
+        handle.update(|state| {
+            state.position_seconds = start;
+            state.playing = true;
+            state.finished = false;
+        });
+        self.decoder = Some(tokio::spawn(async move {
+            // Owned by the task so aborting it kills ffmpeg
+            let _decoder = decoder;
+            let mut decoded = 0u64;
+            loop {
+                let mut rgb = vec![0u8; (width * height * 3) as usize];
+                if let Err(e) = frames.read_exact(&mut rgb).await {
+                    if e.kind() != std::io::ErrorKind::UnexpectedEof {
+                        tracing::warn!("Playback decoding failed: {}", e);
+                    }
+                    break;
+                }
+                decoded += 1;
+                let frame = Arc::new(Frame { width, height, rgb });
+                handle.update(|state| {
+                    state.frame = Some(frame);
+                    state.frame_number += 1;
+                    state.position_seconds = start + decoded as f64 / fps as f64;
+                });
+            }
+            handle.update(|state| {
+                state.playing = false;
+                state.finished = true;
+            });
+        }));
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        self.stop_decoder();
+        self.handle.update(|state| state.playing = false);
+    }
+
+    /// Jump to `position_seconds`, carrying on playing if it was
+    pub fn seek(&mut self, position_seconds: f64) -> Result<()> {
+        let playing = self.handle.get().playing;
+        self.stop_decoder();
+        let position = position_seconds.clamp(0.0, self.duration_seconds.max(0.0));
+        self.handle.update(|state| {
+            state.position_seconds = position;
+            state.playing = false;
+            state.finished = false;
+        });
+        if playing {
+            self.play()?;
+        }
+        Ok(())
+    }
+
+    /// Stop playback and remove any decrypted copy
+    pub async fn close(mut self) {
+        self.stop_decoder();
+        if self.temporary {
+            if let Err(e) = tokio::fs::remove_file(&self.source).await {
+                tracing::warn!("Failed to remove decrypted playback copy {}: {}", self.source.display(), e);
+            }
+        }
+    }
+
+    fn stop_decoder(&mut self) {
+        if let Some(decoder) = self.decoder.take() {
+            decoder.abort();
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.stop_decoder();
+    }
+}
+
+/// Frame size for `resolution` scaled to `PREVIEW_WIDTH`, kept even for the scaler
+fn preview_size(resolution: &str) -> (u32, u32) {
+    let (width, height) = resolution.split_once('x')
+        .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+        .filter(|(w, h)| *w > 0 && *h > 0)
+        .unwrap_or((16, 9));
+    let scaled = (PREVIEW_WIDTH as u64 * height as u64 / width as u64) as u32;
+    (PREVIEW_WIDTH, (scaled.max(2) + 1) & !1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_size_keeps_aspect() {
+        assert_eq!(preview_size("1920x1080"), (640, 360));
+        assert_eq!(preview_size("640x480"), (640, 480));
+        assert_eq!(preview_size("garbage"), (640, 360));
+    }
+
+    #[test]
+    fn test_bookmarks_from_night_mode_periods() {
+        let start = chrono::Utc::now();
+        let segment: RecordingSegment = serde_json::from_value(serde_json::json!({
+            "id": "seg-1",
+            "incident_id": "inc-1",
+            "device_id": "dev-1",
+            "start_time": start,
+            "end_time": null,
+            "duration": 60,
+            "file_path": "seg-1.mp4",
+            "file_size": null,
+            "metadata": {
+                "resolution": "1920x1080",
+                "fps": 30,
+                "bitrate": 4000,
+                "codec": "h264",
+                "audio_enabled": true,
+                "audio_codec": "aac",
+                "encryption_key": null,
+                "location": null,
+                "ir_periods": [
+                    { "start": start + chrono::Duration::seconds(40), "end": null },
+                    { "start": start + chrono::Duration::seconds(10), "end": start + chrono::Duration::seconds(25) }
+                ]
+            },
+            "uploaded": false,
+            "quality": "high",
+            "pre_incident_segments": [],
+            "integrity": null,
+            "previous_parts": [],
+            "audio_only": false
+        }))
+        .unwrap();
+
+        let offsets: Vec<f64> = bookmarks(&segment).iter().map(|b| b.offset_seconds).collect();
+        assert_eq!(offsets, vec![10.0, 25.0, 40.0]);
+        assert_eq!(Player::new(&segment, PathBuf::from("seg-1.mp4"), false).duration_seconds(), 60.0);
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::device::{BodycamDevice, DeviceStatus};
+use crate::event_bus::{BusEvent, EventBus, Topic};
+use crate::incident::{IncidentSeverity, IncidentType};
+
+/// A custom event handler, e.g. one that closes a door relay when an
+/// incident starts. Plugins are compiled into a binary that links
+/// `bodycam_core` and registered with `ServiceRunner::with_plugins`, so
+/// integrators don't need to fork the client.
+#[async_trait]
+pub trait Plugin: Send + Sync {
+    /// Shown in logs and used to name the plugin's service
+    fn name(&self) -> &str;
+
+    /// Topics to receive; empty receives everything
+    fn topics(&self) -> Vec<Topic> {
+        Vec::new()
+    }
+
+    /// Called before the first event, and again after a restart
+    async fn on_start(&mut self, _device: &PluginHandle) -> Result<()> {
+        Ok(())
+    }
+
+    /// An error is logged and the plugin keeps receiving events; only a
+    /// failing `on_start` restarts it
+    async fn on_event(&mut self, event: &BusEvent, device: &PluginHandle) -> Result<()>;
+}
+
+/// What a plugin may do with the device. Deliberately narrow: plugins can
+/// read status and raise incidents, but not stop recordings or change
+/// configuration.
+#[derive(Clone)]
+pub struct PluginHandle {
+    plugin: String,
+    device: BodycamDevice,
+}
+
+impl PluginHandle {
+    pub(crate) fn new(plugin: &str, device: BodycamDevice) -> Self {
+        Self {
+            plugin: plugin.to_string(),
+            device,
+        }
+    }
+
+    pub async fn status(&self) -> Result<DeviceStatus> {
+        self.device.get_status().await
+    }
+
+    /// Raise an incident, as a button press or detection rule would.
+    /// Returns the incident id.
+    pub async fn trigger_incident(&self, incident_type: IncidentType, severity: IncidentSeverity) -> Result<String> {
+        tracing::info!("Plugin {} triggered a {} incident", self.plugin, incident_type);
+        self.device.trigger_incident(incident_type, severity).await
+    }
+
+    /// Events from the device, for plugins that need more than `on_event`
+    pub async fn event_bus(&self) -> EventBus {
+        self.device.event_bus()
+    }
+}
+
+/// Plugins to run alongside the built-in services
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Arc<Mutex<Box<dyn Plugin>>>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, plugin: Box<dyn Plugin>) -> Self {
+        self.plugins.push(Arc::new(Mutex::new(plugin)));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub(crate) fn into_plugins(self) -> Vec<Arc<Mutex<Box<dyn Plugin>>>> {
+        self.plugins
+    }
+}
+
+/// Feed bus events to `plugin` until the bus closes
+pub(crate) async fn run_plugin(plugin: Arc<Mutex<Box<dyn Plugin>>>, device: BodycamDevice) -> Result<()> {
+    let mut plugin = plugin.lock().await;
+    let handle = PluginHandle::new(plugin.name(), device.clone());
+    let bus = device.event_bus();
+    let topics = plugin.topics();
+    let mut events = if topics.is_empty() {
+        bus.subscribe()
+    } else {
+        bus.subscribe_to(&topics)
+    };
+
+    plugin.on_start(&handle).await?;
+    while let Some(event) = events.recv().await {
+        if let Err(e) = plugin.on_event(&event, &handle).await {
+            tracing::warn!("Plugin {} failed to handle {:?}: {:#}", plugin.name(), event.topic(), e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter;
+
+    #[async_trait]
+    impl Plugin for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn topics(&self) -> Vec<Topic> {
+            vec![Topic::Incidents]
+        }
+
+        async fn on_event(&mut self, _event: &BusEvent, _device: &PluginHandle) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_registry_keeps_plugins_in_order() {
+        let registry = PluginRegistry::new()
+            .register(Box::new(Counter))
+            .register(Box::new(Counter));
+        assert_eq!(registry.len(), 2);
+
+        let plugins = registry.into_plugins();
+        let plugin = plugins[0].try_lock().unwrap();
+        assert_eq!(plugin.name(), "counter");
+        assert_eq!(plugin.topics(), vec![Topic::Incidents]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Checks run before a recording starts, so a missing prerequisite fails
+//! at once with a reason the operator can act on, instead of ffmpeg
+//! failing minutes into the recording.
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreflightConfig {
+    pub enabled: bool,
+    /// Refuse to start below this charge, unless charging
+    pub min_battery_percent: f32,
+    /// Space is reserved for this long when a recording has no duration
+    /// or duration limit
+    pub expected_minutes: u64,
+    /// Headroom on the estimated size, for bitrate peaks and the container
+    pub space_margin: f64,
+    /// A clock before this year was never set, e.g. after the RTC battery ran out
+    pub min_valid_year: i32,
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_battery_percent: 5.0,
+            expected_minutes: 30,
+            space_margin: 1.2,
+            min_valid_year: 2024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PreflightFailure {
+    #[error("Not enough storage: {} MB free, {} MB needed", available / 1_000_000, required / 1_000_000)]
+    InsufficientStorage { available: u64, required: u64 },
+
+    #[error("Camera {device} unavailable: {reason}")]
+    CameraUnavailable { device: String, reason: String },
+
+    #[error("Battery too low to record: {level:.0}%, at least {minimum:.0}% needed")]
+    BatteryLow { level: f32, minimum: f32 },
+
+    #[error("Clock not set (reads {now}), timestamps would be wrong")]
+    ClockNotSet { now: DateTime<Utc> },
+
+    #[error("Clock reads {now}, before the last recording at {latest}")]
+    ClockBehind { now: DateTime<Utc>, latest: DateTime<Utc> },
+}
+
+impl PreflightFailure {
+    pub fn check(&self) -> &'static str {
+        match self {
+            PreflightFailure::InsufficientStorage { .. } => "storage",
+            PreflightFailure::CameraUnavailable { .. } => "camera",
+            PreflightFailure::BatteryLow { .. } => "battery",
+            PreflightFailure::ClockNotSet { .. } | PreflightFailure::ClockBehind { .. } => "clock",
+        }
+    }
+
+    /// Whether the failure stops the recording. An incident or emergency
+    /// recording always starts and records what it can, so every failure is
+    /// only a warning; a manual start fails only without a camera.
+    pub fn blocks(&self, incident: bool) -> bool {
+        !incident && matches!(self, PreflightFailure::CameraUnavailable { .. })
+    }
+}
+
+/// The device state the checks are made against
+#[derive(Debug, Clone)]
+pub struct PreflightInputs {
+    pub recording_space: u64,
+    /// Bytes the recording is expected to take
+    pub required_bytes: u64,
+    pub battery_percent: Option<f32>,
+    pub charging: bool,
+    /// None when the camera opened, or isn't needed
+    pub camera_error: Option<(String, String)>,
+    pub now: DateTime<Utc>,
+    /// Start of the newest recording on the device
+    pub latest_recording: Option<DateTime<Utc>>,
+}
+
+/// Estimated size of `seconds` at `bitrate` bits per second, with the margin
+pub fn expected_bytes(config: &PreflightConfig, bitrate: u64, seconds: u64) -> u64 {
+    (bitrate as f64 / 8.0 * seconds as f64 * config.space_margin.max(1.0)) as u64
+}
+
+/// Every check that failed, in the order they're reported
+pub fn run_checks(config: &PreflightConfig, inputs: &PreflightInputs) -> Vec<PreflightFailure> {
+    let mut failures = Vec::new();
+    if inputs.recording_space < inputs.required_bytes {
+        failures.push(PreflightFailure::InsufficientStorage {
+            available: inputs.recording_space,
+            required: inputs.required_bytes,
+        });
+    }
+    if let Some((device, reason)) = &inputs.camera_error {
+        failures.push(PreflightFailure::CameraUnavailable { device: device.clone(), reason: reason.clone() });
+    }
+    if let Some(level) = inputs.battery_percent {
+        if !inputs.charging && level < config.min_battery_percent {
+            failures.push(PreflightFailure::BatteryLow { level, minimum: config.min_battery_percent });
+        }
+    }
+    if inputs.now.year() < config.min_valid_year {
+        failures.push(PreflightFailure::ClockNotSet { now: inputs.now });
+    } else if let Some(latest) = inputs.latest_recording.filter(|latest| *latest > inputs.now) {
+        failures.push(PreflightFailure::ClockBehind { now: inputs.now, latest });
+    }
+    failures
+}
+
+/// Open the camera's device node. Cheap, and catches a camera that's
+/// unplugged or that the process may not open.
+pub fn probe_camera(device: &Path) -> std::io::Result<()> {
+    std::fs::OpenOptions::new().read(true).open(device).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_checks_report_each_missing_prerequisite() {
+        let config = PreflightConfig::default();
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        // 30 minutes at 5 Mbit/s
+        let required_bytes = expected_bytes(&config, 5_000_000, 30 * 60);
+        assert_eq!(required_bytes, 1_350_000_000);
+        let ready = PreflightInputs {
+            recording_space: 10_000_000_000,
+            required_bytes,
+            battery_percent: Some(60.0),
+            charging: false,
+            camera_error: None,
+            now,
+            latest_recording: Some(now - chrono::Duration::hours(1)),
+        };
+        assert!(run_checks(&config, &ready).is_empty());
+
+        let failing = PreflightInputs {
+            recording_space: 1_000_000_000,
+            battery_percent: Some(3.0),
+            camera_error: Some(("/dev/video0".to_string(), "No such device".to_string())),
+            latest_recording: Some(now + chrono::Duration::days(2)),
+            ..ready.clone()
+        };
+        let failures = run_checks(&config, &failing);
+        let checks: Vec<_> = failures.iter().map(|failure| failure.check()).collect();
+        assert_eq!(checks, vec!["storage", "camera", "battery", "clock"]);
+        let blocking: Vec<_> = failures.iter().filter(|failure| failure.blocks(false)).map(|failure| failure.check()).collect();
+        assert_eq!(blocking, vec!["camera"]);
+        assert!(!failures.iter().any(|failure| failure.blocks(true)));
+
+        let charging = PreflightInputs { battery_percent: Some(3.0), charging: true, ..ready.clone() };
+        assert!(run_checks(&config, &charging).is_empty());
+        let unset = PreflightInputs { now: Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 5).unwrap(), ..ready };
+        assert_eq!(run_checks(&config, &unset)[0].check(), "clock");
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{Result, Context};
+use std::collections::VecDeque;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{ChildStdin, Command};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::ffmpeg_progress::{ProgressHandle, ProgressParser};
+
+/// Lines of stderr kept for crash reports
+const STDERR_TAIL_LINES: usize = 20;
+
+static NEXT_PROCESS_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Last few lines a child wrote to stderr
+#[derive(Debug, Clone, Default)]
+pub struct StderrTail {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl StderrTail {
+    fn push(&self, line: String) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == STDERR_TAIL_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// A supervised child that exited without being asked to
+#[derive(Debug, Clone)]
+pub struct ProcessExit {
+    pub process_id: u64,
+    pub label: String,
+    /// None when waiting on the child itself failed
+    pub status: Option<ExitStatus>,
+    pub stderr_tail: Vec<String>,
+}
+
+impl ProcessExit {
+    pub fn success(&self) -> bool {
+        self.status.map(|s| s.success()).unwrap_or(false)
+    }
+
+    /// Signal name for a child killed by a signal
+    pub fn signal(&self) -> Option<String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            self.status.and_then(|s| s.signal()).map(|sig| format!("signal {}", sig))
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+}
+
+/// Handle to a child process watched by its own monitor task. The task owns
+/// the child, collects its stderr and reports an unexpected exit on the
+/// supervisor channel; exits caused by `kill` are not reported.
+pub struct MonitoredProcess {
+    id: u64,
+    pid: Option<u32>,
+    label: String,
+    stderr: StderrTail,
+    progress: Option<ProgressHandle>,
+    /// Latest mean luma from an encoder's luminance tap, until taken
+    luminance: Arc<Mutex<Option<f64>>>,
+    kill_tx: Option<oneshot::Sender<()>>,
+    done_rx: Option<oneshot::Receiver<Option<ExitStatus>>>,
+    /// Set when the command was spawned with a piped stdin
+    stdin: Option<ChildStdin>,
+}
+
+impl MonitoredProcess {
+    pub fn spawn(cmd: Command, label: &str, exit_tx: mpsc::UnboundedSender<ProcessExit>) -> Result<Self> {
+        Self::spawn_inner(cmd, label, exit_tx, false)
+    }
+
+    /// Spawn an ffmpeg started with `ffmpeg_progress::progress_args()`,
+    /// tracking the progress it writes to stdout
+    pub fn spawn_ffmpeg(cmd: Command, label: &str, exit_tx: mpsc::UnboundedSender<ProcessExit>) -> Result<Self> {
+        Self::spawn_inner(cmd, label, exit_tx, true)
+    }
+
+    fn spawn_inner(
+        mut cmd: Command,
+        label: &str,
+        exit_tx: mpsc::UnboundedSender<ProcessExit>,
+        track_progress: bool,
+    ) -> Result<Self> {
+        if track_progress {
+            cmd.stdout(Stdio::piped());
+        }
+
+        let mut child = cmd
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", label))?;
+
+        let id = NEXT_PROCESS_ID.fetch_add(1, Ordering::Relaxed);
+        let pid = child.id();
+        let stdin = child.stdin.take();
+        let stderr = StderrTail::default();
+        let luminance = Arc::new(Mutex::new(None));
+
+        let progress = match child.stdout.take() {
+            Some(pipe) if track_progress => {
+                let handle = ProgressHandle::default();
+                let latest = handle.clone();
+                tokio::spawn(async move {
+                    let mut parser = ProgressParser::default();
+                    let mut lines = BufReader::new(pipe).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if let Some(progress) = parser.feed_line(&line) {
+                            latest.set(progress);
+                        }
+                    }
+                });
+                Some(handle)
+            }
+            _ => None,
+        };
+
+        if let Some(pipe) = child.stderr.take() {
+            let tail = stderr.clone();
+            let latest_luma = luminance.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(pipe).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    // Kept out of the tail so they don't bury the lines a crash report needs
+                    if track_progress {
+                        if let Some(luma) = crate::camera::night_mode::parse_yavg(&line) {
+                            *latest_luma.lock().unwrap() = Some(luma);
+                            continue;
+                        }
+                    }
+                    tail.push(line);
+                }
+            });
+        }
+
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        let (done_tx, done_rx) = oneshot::channel();
+        let task_label = label.to_string();
+        let tail = stderr.clone();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                status = child.wait() => {
+                    let status = status.ok();
+                    // Give the stderr reader a moment to collect the last lines
+                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                    let _ = exit_tx.send(ProcessExit {
+                        process_id: id,
+                        label: task_label,
+                        status,
+                        stderr_tail: tail.lines(),
+                    });
+                    let _ = done_tx.send(status);
+                }
+                _ = kill_rx => {
+                    let _ = child.kill().await;
+                    let _ = done_tx.send(child.wait().await.ok());
+                }
+            }
+        });
+
+        Ok(Self {
+            id,
+            pid,
+            label: label.to_string(),
+            stderr,
+            progress,
+            luminance,
+            kill_tx: Some(kill_tx),
+            done_rx: Some(done_rx),
+            stdin,
+        })
+    }
+
+    /// The process's stdin, if it was spawned with one piped
+    pub fn take_stdin(&mut self) -> Option<ChildStdin> {
+        self.stdin.take()
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// OS process id, while the child is running
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr.lines()
+    }
+
+    /// Latest encoder progress, for processes spawned with `spawn_ffmpeg`
+    pub fn progress(&self) -> Option<crate::ffmpeg_progress::EncoderProgress> {
+        self.progress.as_ref().and_then(|p| p.get())
+    }
+
+    /// Mean luma the encoder logged since the last call, if it runs a
+    /// `night_mode::luminance_tap`
+    pub fn take_luminance(&self) -> Option<f64> {
+        self.luminance.lock().unwrap().take()
+    }
+
+    /// Stop the child on purpose; the monitor won't report this exit
+    pub async fn kill(&mut self) -> Result<()> {
+        if let Some(kill_tx) = self.kill_tx.take() {
+            let _ = kill_tx.send(());
+        }
+        Ok(())
+    }
+
+    pub async fn wait(&mut self) -> Result<Option<ExitStatus>> {
+        match self.done_rx.take() {
+            Some(done_rx) => Ok(done_rx.await.ok().flatten()),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_tail_is_bounded() {
+        let tail = StderrTail::default();
+        for i in 0..30 {
+            tail.push(format!("line {}", i));
+        }
+
+        let lines = tail.lines();
+        assert_eq!(lines.len(), STDERR_TAIL_LINES);
+        assert_eq!(lines.first().unwrap(), "line 10");
+        assert_eq!(lines.last().unwrap(), "line 29");
+    }
+}
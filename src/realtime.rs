@@ -2,8 +2,6 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tokio::time::{interval, Duration};
-use std::sync::Arc;
-use tokio::sync::Mutex;
 
 use crate::config::Config;
 use crate::device::{BodycamDevice, DeviceStatus};
@@ -46,14 +44,14 @@ pub struct CommandResponse {
 
 pub struct RealtimeManager {
     config: Config,
-    device: Arc<Mutex<BodycamDevice>>,
+    device: BodycamDevice,
     checkin_interval: u64,
     update_tx: mpsc::UnboundedSender<StatusUpdate>,
     command_rx: mpsc::UnboundedReceiver<ServerCommand>,
 }
 
 impl RealtimeManager {
-    pub fn new(config: Config, device: Arc<Mutex<BodycamDevice>>) -> (Self, mpsc::UnboundedReceiver<StatusUpdate>, mpsc::UnboundedSender<ServerCommand>) {
+    pub fn new(config: Config, device: BodycamDevice) -> (Self, mpsc::UnboundedReceiver<StatusUpdate>, mpsc::UnboundedSender<ServerCommand>) {
         let checkin_interval = config.monitoring.checkin_interval_seconds;
         
         let (update_tx, update_rx) = mpsc::unbounded_channel();
@@ -91,8 +89,8 @@ impl RealtimeManager {
             loop {
                 interval.tick().await;
                 
-                if let Ok(status) = device.lock().await.get_status().await {
-                    let capabilities = device.lock().await.get_capabilities().await.ok();
+                if let Ok(status) = device.get_status().await {
+                    let capabilities = device.get_capabilities().await.ok();
                     
                     let update = StatusUpdate {
                         device_id: status.device_id.clone(),
@@ -113,29 +111,7 @@ impl RealtimeManager {
         
         tokio::spawn(async move {
             while let Some(command) = command_rx.recv().await {
-                let _transaction = sentry_integration::start_transaction("realtime.handle_command", "command");
-                
-                let response = match Self::handle_server_command(&device, command.clone()).await {
-                    Ok(result) => CommandResponse {
-                        request_id: command.request_id,
-                        device_id: device.lock().await.device_id.clone().unwrap_or_default(),
-                        status: "success".to_string(),
-                        result: Some(result),
-                        error: None,
-                        timestamp: chrono::Utc::now(),
-                    },
-                    Err(e) => CommandResponse {
-                        request_id: command.request_id,
-                        device_id: device.lock().await.device_id.clone().unwrap_or_default(),
-                        status: "error".to_string(),
-                        result: None,
-                        error: Some(e.to_string()),
-                        timestamp: chrono::Utc::now(),
-                    },
-                };
-                
-                // Send response back to server
-                let _ = Self::send_command_response(&response).await;
+                Self::execute_command(&device, command).await;
             }
         });
         
@@ -153,8 +129,8 @@ impl RealtimeManager {
             loop {
                 interval.tick().await;
                 
-                if let Ok(status) = device.lock().await.get_status().await {
-                    let capabilities = device.lock().await.get_capabilities().await.ok();
+                if let Ok(status) = device.get_status().await {
+                    let capabilities = device.get_capabilities().await.ok();
                     
                     let update = StatusUpdate {
                         device_id: status.device_id.clone(),
@@ -176,7 +152,7 @@ impl RealtimeManager {
         self.checkin_interval = new_interval;
         
         // Send immediate status update with new interval
-        let device = self.device.lock().await;
+        let device = &self.device;
         let status = device.get_status().await?;
         let capabilities = device.get_capabilities().await.ok();
         
@@ -193,36 +169,260 @@ impl RealtimeManager {
         Ok(())
     }
     
-    async fn handle_server_command(device: &Arc<Mutex<BodycamDevice>>, command: ServerCommand) -> Result<serde_json::Value> {
+    /// Run a server command against the device and send back the response
+    pub async fn execute_command(device: &BodycamDevice, command: ServerCommand) {
+        let _transaction = sentry_integration::start_transaction("realtime.handle_command", "command");
+        
+        let result = Self::handle_server_command(device, command.clone()).await;
+        let device_id = device.device_id().unwrap_or_default();
+        let response = match result {
+            Ok(result) => CommandResponse {
+                request_id: command.request_id,
+                device_id,
+                status: "success".to_string(),
+                result: Some(result),
+                error: None,
+                timestamp: chrono::Utc::now(),
+            },
+            Err(e) => CommandResponse {
+                request_id: command.request_id,
+                device_id,
+                status: "error".to_string(),
+                result: None,
+                error: Some(e.to_string()),
+                timestamp: chrono::Utc::now(),
+            },
+        };
+        
+        // Send response back to server
+        let _ = Self::send_command_response(device, &response).await;
+    }
+    
+    async fn handle_server_command(device: &BodycamDevice, command: ServerCommand) -> Result<serde_json::Value> {
         match command.command.as_str() {
             "get_status" => {
-                let status = device.lock().await.get_status().await?;
+                let status = device.get_status().await?;
                 Ok(serde_json::to_value(status)?)
             },
             "get_capabilities" => {
-                let capabilities = device.lock().await.get_capabilities().await?;
+                let capabilities = device.get_capabilities().await?;
                 Ok(serde_json::to_value(capabilities)?)
             },
             "start_recording" => {
                 let duration = command.parameters.get("duration").and_then(|v| v.as_u64());
                 let incident_id = command.parameters.get("incident_id").and_then(|v| v.as_str()).map(|s| s.to_string());
                 
-                device.lock().await.start_recording(duration, incident_id).await?;
+                device.start_recording(duration, incident_id).await?;
                 Ok(serde_json::json!({"status": "recording_started"}))
             },
             "stop_recording" => {
-                device.lock().await.stop_recording().await?;
+                device.stop_recording().await?;
                 Ok(serde_json::json!({"status": "recording_stopped"}))
             },
+            "pause_recording" => {
+                let reason = command.parameters.get("reason").and_then(|v| v.as_str()).map(|s| s.to_string());
+                device.pause_recording(reason, "remote_command").await?;
+                Ok(serde_json::json!({"status": "recording_paused"}))
+            },
+            "resume_recording" => {
+                device.resume_recording("remote_command").await?;
+                Ok(serde_json::json!({"status": "recording_resumed"}))
+            },
             "trigger_incident" => {
-                let incident_type = command.parameters.get("type").and_then(|v| v.as_str()).unwrap_or("manual");
-                let severity = command.parameters.get("severity").and_then(|v| v.as_str()).unwrap_or("medium");
+                let incident_type = command.parameters.get("type").and_then(|v| v.as_str()).unwrap_or("manual").parse()?;
+                let severity = command.parameters.get("severity").and_then(|v| v.as_str()).unwrap_or("medium").parse()?;
                 
-                let incident_id = device.lock().await.trigger_incident(incident_type, severity).await?;
+                let incident_id = device.trigger_incident(incident_type, severity).await?;
                 Ok(serde_json::json!({"incident_id": incident_id}))
             },
             "diagnose" => {
-                let report = device.lock().await.diagnose().await?;
+                let report = device.diagnose().await?;
+                Ok(serde_json::to_value(report)?)
+            },
+            "comprehensive_diagnostics" => {
+                let upload = device.upload_comprehensive_diagnostics("remote_command").await?;
+                Ok(serde_json::to_value(upload)?)
+            },
+            "self_test" => {
+                let report = device.run_self_test(crate::self_test::SelfTestTrigger::Remote, "remote_command").await?;
+                Ok(serde_json::to_value(report)?)
+            },
+            "incident_acknowledged" => {
+                let incident_id = command.parameters.get("incident_id").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'incident_id' parameter"))?;
+
+                device.acknowledge_incident(incident_id).await?;
+                Ok(serde_json::json!({"acknowledged": incident_id}))
+            },
+            "set_camera_control" => {
+                let control = command.parameters.get("control").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'control' parameter"))?;
+                let value = match command.parameters.get("value") {
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(serde_json::Value::Number(n)) => n.to_string(),
+                    _ => return Err(anyhow::anyhow!("Missing 'value' parameter")),
+                };
+
+                device.set_camera_control(control, &value).await?;
+                Ok(serde_json::json!({"control": control, "value": value}))
+            },
+            "set_pip_layout" => {
+                let layout: crate::streaming::pip::PipLayout = command.parameters.get("layout").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'layout' parameter"))?
+                    .parse()?;
+
+                device.set_pip_layout(layout, "remote_command").await?;
+                Ok(serde_json::json!({"layout": layout}))
+            },
+            "play_audio" => {
+                let source: crate::audio::AudioSource = serde_json::from_value(command.parameters.get("source").cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'source' parameter"))?)?;
+                let priority = match command.parameters.get("priority") {
+                    Some(priority) => serde_json::from_value(priority.clone())?,
+                    None => crate::audio::AudioPriority::Normal,
+                };
+                let volume = command.parameters.get("volume").and_then(|v| v.as_f64()).map(|v| v as f32);
+                let loop_playback = command.parameters.get("loop").and_then(|v| v.as_bool());
+
+                let playback_id = device.play_audio(source, volume, loop_playback, priority).await?;
+                Ok(serde_json::json!({"playback_id": playback_id, "priority": priority}))
+            },
+            "stop_playback" => {
+                let playback_id = command.parameters.get("playback_id").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'playback_id' parameter"))?;
+
+                device.stop_playback(playback_id)?;
+                Ok(serde_json::json!({"stopped": playback_id}))
+            },
+            "calibrate_speaker" => {
+                let calibration = device.calibrate_speaker("remote_command").await?;
+                Ok(serde_json::to_value(calibration)?)
+            },
+            "set_brightness" => {
+                // A number sets the brightness by hand; null returns to the schedule
+                let percent = match command.parameters.get("percent") {
+                    Some(serde_json::Value::Null) => None,
+                    Some(value) => Some(value.as_u64().filter(|percent| *percent <= 100)
+                        .ok_or_else(|| anyhow::anyhow!("'percent' must be between 0 and 100 or null"))? as u8),
+                    None => return Err(anyhow::anyhow!("Missing 'percent' parameter")),
+                };
+
+                let status = device.set_brightness_override(percent, "remote_command").await?;
+                Ok(serde_json::to_value(status)?)
+            },
+            "enter_deep_sleep" => {
+                let session = device.enter_deep_sleep("remote_command").await?;
+                Ok(serde_json::to_value(session)?)
+            },
+            "wake" => {
+                let woke = device.wake_from_deep_sleep(crate::deep_sleep::WakeReason::Command, "remote_command").await?;
+                Ok(serde_json::json!({"woke": woke}))
+            },
+            "deep_sleep_status" => {
+                Ok(serde_json::to_value(device.sleep_status().await)?)
+            },
+            "list_audio_presets" => {
+                Ok(serde_json::json!({"presets": device.audio_presets().await}))
+            },
+            "sync_audio_presets" => {
+                let report = device.sync_audio_presets(true).await?;
+                Ok(serde_json::to_value(report)?)
+            },
+            "set_stealth_mode" => {
+                let enabled = command.parameters.get("enabled").and_then(|v| v.as_bool())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'enabled' parameter"))?;
+
+                device.set_stealth_mode(enabled, "remote_command").await?;
+                Ok(serde_json::json!({"stealth_mode": enabled}))
+            },
+            "set_muted" => {
+                let muted = command.parameters.get("muted").and_then(|v| v.as_bool())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'muted' parameter"))?;
+
+                device.set_muted(muted, "remote_command").await?;
+                Ok(serde_json::json!({"muted": muted}))
+            },
+            "place_legal_hold" => {
+                let hold: crate::legal_hold::LegalHold = serde_json::from_value(serde_json::json!({
+                    "id": command.parameters.get("hold_id").cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'hold_id' parameter"))?,
+                    "incident_id": command.parameters.get("incident_id").cloned(),
+                    "segment_id": command.parameters.get("segment_id").cloned(),
+                    "reason": command.parameters.get("reason").cloned(),
+                    "placed_by": command.parameters.get("placed_by").cloned(),
+                    "placed_at": chrono::Utc::now(),
+                }))?;
+                if hold.incident_id.is_none() && hold.segment_id.is_none() {
+                    return Err(anyhow::anyhow!("Legal hold needs an 'incident_id' or 'segment_id'"));
+                }
+
+                let hold_id = hold.id.clone();
+                device.place_legal_hold(hold, "remote_command").await?;
+                Ok(serde_json::json!({"legal_hold": hold_id, "status": "placed"}))
+            },
+            "release_legal_hold" => {
+                let hold_id = command.parameters.get("hold_id").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'hold_id' parameter"))?;
+
+                let released = device.release_legal_hold(hold_id, "remote_command").await?;
+                Ok(serde_json::json!({"legal_hold": hold_id, "released": released}))
+            },
+            "sync_legal_holds" => {
+                let holds: Vec<crate::legal_hold::LegalHold> = serde_json::from_value(
+                    command.parameters.get("holds").cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'holds' parameter"))?,
+                )?;
+
+                let count = holds.len();
+                device.sync_legal_holds(holds, "remote_command").await?;
+                Ok(serde_json::json!({"legal_holds": count}))
+            },
+            "support_request" => {
+                let request: crate::remote_support::SupportRequest = serde_json::from_value(
+                    command.parameters.get("request").cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'request' parameter"))?,
+                )?;
+                let requested_by = command.parameters.get("requested_by").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'requested_by' parameter"))?;
+
+                let artifact = device.handle_support_request(request, requested_by, "remote_command").await?;
+                Ok(serde_json::to_value(artifact)?)
+            },
+            "set_tamper_policy" => {
+                let signed = crate::tamper::SignedTamperPolicy {
+                    payload: command.parameters.get("payload").and_then(|v| v.as_str())
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'payload' parameter"))?.to_string(),
+                    signature: command.parameters.get("signature").and_then(|v| v.as_str())
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'signature' parameter"))?.to_string(),
+                };
+                let policy = device.install_tamper_policy(signed, "remote_command").await?;
+                Ok(serde_json::json!({"version": policy.version}))
+            },
+            "set_recording_policy" => {
+                let signed = crate::recording_policy::SignedRecordingPolicy {
+                    payload: command.parameters.get("payload").and_then(|v| v.as_str())
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'payload' parameter"))?.to_string(),
+                    signature: command.parameters.get("signature").and_then(|v| v.as_str())
+                        .ok_or_else(|| anyhow::anyhow!("Missing 'signature' parameter"))?.to_string(),
+                };
+                let policy = device.install_recording_policy(signed, "remote_command").await?;
+                Ok(serde_json::json!({"version": policy.version, "rules": policy.rules.len()}))
+            },
+            "unlock_controls" => {
+                device.unlock_controls("remote_command").await?;
+                Ok(serde_json::json!({"controls_locked": false}))
+            },
+            "set_language" => {
+                let language = command.parameters.get("language").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'language' parameter"))?;
+                device.set_language(language, "remote_command").await?;
+                Ok(serde_json::json!({"language": language}))
+            },
+            "decommission" => {
+                let token = command.parameters.get("confirmation_token").and_then(|v| v.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'confirmation_token' parameter"))?;
+                let force = command.parameters.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+                let report = device.decommission(token, force, "remote_command").await?;
                 Ok(serde_json::to_value(report)?)
             },
             "set_checkin_interval" => {
@@ -234,9 +434,7 @@ impl RealtimeManager {
         }
     }
     
-    async fn send_command_response(response: &CommandResponse) -> Result<()> {
-        // In a real implementation, this would send the response back to the server
-        // For now, we'll log it and use the existing status reporting mechanism
+    async fn send_command_response(device: &BodycamDevice, response: &CommandResponse) -> Result<()> {
         tracing::info!(
             "Command response: request_id={}, status={}", 
             response.request_id, 
@@ -247,13 +445,13 @@ impl RealtimeManager {
             tracing::error!("Command error: {}", error);
         }
         
-        Ok(())
+        device.backend().send_command_response(response).await
     }
 }
 
 impl BodycamDevice {
     async fn get_capabilities(&self) -> Result<crate::capabilities::DeviceCapabilities> {
-        let detector = crate::capabilities::CapabilityDetector::new(self.config.simulation.enabled);
+        let detector = crate::capabilities::CapabilityDetector::new(self.config().simulation.enabled);
         detector.detect_capabilities().await
     }
 }
\ No newline at end of file
@@ -0,0 +1,617 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::communications::CommunicationEvent;
+use crate::config::VideoQuality;
+use crate::incident::{IncidentSeverity, IncidentType};
+use crate::legal_hold::LegalHold;
+use crate::media::RecordingSegment;
+use crate::storage_manager::ConfirmedUpload;
+
+/// Bumped when the schema changes; 1 is the first version, after the
+/// import of the JSON files it replaces
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS segments (
+        id TEXT PRIMARY KEY,
+        incident_id TEXT NOT NULL,
+        quality TEXT NOT NULL,
+        start_time INTEGER NOT NULL,
+        file_path TEXT NOT NULL,
+        uploaded INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS segments_incident ON segments (incident_id);
+    CREATE INDEX IF NOT EXISTS segments_start_time ON segments (start_time);
+    CREATE INDEX IF NOT EXISTS segments_file_path ON segments (file_path);
+    CREATE TABLE IF NOT EXISTS incidents (
+        id TEXT PRIMARY KEY,
+        started_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS uploads (
+        file_path TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS legal_holds (
+        id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS bookmarks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        segment_id TEXT NOT NULL,
+        offset_seconds REAL NOT NULL,
+        note TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS bookmarks_segment ON bookmarks (segment_id);
+    CREATE TABLE IF NOT EXISTS communications (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        incident_id TEXT NOT NULL,
+        at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS communications_incident ON communications (incident_id);
+    CREATE TABLE IF NOT EXISTS segment_locks (
+        segment_id TEXT PRIMARY KEY,
+        reason TEXT NOT NULL,
+        locked_at INTEGER NOT NULL
+    );
+";
+
+/// An incident raised on this device, as kept in the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncidentRecord {
+    pub id: String,
+    pub incident_type: IncidentType,
+    pub severity: IncidentSeverity,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// A point in a recording marked by an operator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedBookmark {
+    pub id: i64,
+    pub segment_id: String,
+    pub offset_seconds: f64,
+    pub note: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Which segments to list; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct SegmentQuery {
+    pub incident_id: Option<String>,
+    pub quality: Option<VideoQuality>,
+    pub uploaded: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+/// What was brought over from the JSON files the index replaces
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    pub segments: usize,
+    pub uploads: usize,
+    pub legal_holds: usize,
+}
+
+/// Segments, incidents, upload state, legal holds and bookmarks in one
+/// SQLite database, so the recordings browser, retention and uploads can
+/// query them instead of reading a JSON file per recording
+pub struct RecordingIndex {
+    connection: Connection,
+}
+
+impl RecordingIndex {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("recordings.db")
+    }
+
+    /// Open the index in the working directory, importing the JSON files
+    /// it replaces the first time
+    pub fn open() -> Result<Self> {
+        let mut index = Self::open_at(&Self::default_path())?;
+        let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        if let Some(report) = index.import_legacy(&root)? {
+            tracing::info!("Imported {} segments, {} confirmed uploads and {} legal holds into the recording index",
+                report.segments, report.uploads, report.legal_holds);
+        }
+        Ok(index)
+    }
+
+    pub fn open_at(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open recording index {}", path.display()))?;
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(SCHEMA).context("Failed to create recording index tables")?;
+        Ok(Self { connection })
+    }
+
+    /// Import `recordings/metadata/*.json`, `data/confirmed_uploads.json`
+    /// and `data/legal_holds.json` under `root`, in one transaction. Does
+    /// nothing once the index has been migrated.
+    pub fn import_legacy(&mut self, root: &Path) -> Result<Option<ImportReport>> {
+        let version: i32 = self.connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(None);
+        }
+
+        let mut report = ImportReport::default();
+        let transaction = self.connection.transaction()?;
+
+        let metadata_dir = root.join("recordings").join("metadata");
+        if let Ok(entries) = std::fs::read_dir(&metadata_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    continue;
+                }
+                let segment = std::fs::read_to_string(&path).map_err(anyhow::Error::from)
+                    .and_then(|content| Ok(serde_json::from_str::<RecordingSegment>(&content)?));
+                match segment {
+                    Ok(segment) => {
+                        upsert_segment(&transaction, &segment)?;
+                        report.segments += 1;
+                    }
+                    Err(e) => tracing::warn!("Skipping unreadable segment metadata {}: {:#}", path.display(), e),
+                }
+            }
+        }
+
+        let uploads_path = root.join("data").join("confirmed_uploads.json");
+        if uploads_path.exists() {
+            let uploads: Vec<ConfirmedUpload> = serde_json::from_str(&std::fs::read_to_string(&uploads_path)?)
+                .context("Corrupt confirmed upload ledger")?;
+            for upload in &uploads {
+                insert_upload(&transaction, upload)?;
+            }
+            report.uploads = uploads.len();
+        }
+
+        // A hold that can't be read mustn't be lost, so this fails the import
+        let holds_path = root.join("data").join("legal_holds.json");
+        if holds_path.exists() {
+            let holds: Vec<LegalHold> = serde_json::from_str(&std::fs::read_to_string(&holds_path)?)
+                .with_context(|| format!("Corrupt legal hold file {}", holds_path.display()))?;
+            for hold in &holds {
+                insert_hold(&transaction, hold)?;
+            }
+            report.legal_holds = holds.len();
+        }
+
+        transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        transaction.commit()?;
+        Ok(Some(report))
+    }
+
+    pub fn upsert_segment(&mut self, segment: &RecordingSegment) -> Result<()> {
+        upsert_segment(&self.connection, segment)
+    }
+
+    pub fn segment(&self, id: &str) -> Result<Option<RecordingSegment>> {
+        let row: Option<(String, bool)> = self.connection
+            .query_row("SELECT data, uploaded FROM segments WHERE id = ?1", params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()?;
+        row.map(|(data, uploaded)| segment_from_row(&data, uploaded)).transpose()
+    }
+
+    /// Segments matching `query`, newest first
+    pub fn segments(&self, query: &SegmentQuery) -> Result<Vec<RecordingSegment>> {
+        let mut sql = "SELECT data, uploaded FROM segments WHERE 1 = 1".to_string();
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(incident_id) = &query.incident_id {
+            sql.push_str(" AND incident_id = ?");
+            values.push(Value::Text(incident_id.clone()));
+        }
+        if let Some(quality) = &query.quality {
+            sql.push_str(" AND quality = ?");
+            values.push(Value::Text(quality_name(quality)?));
+        }
+        if let Some(uploaded) = query.uploaded {
+            sql.push_str(" AND uploaded = ?");
+            values.push(Value::Integer(uploaded as i64));
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND start_time >= ?");
+            values.push(Value::Integer(since.timestamp()));
+        }
+        sql.push_str(" ORDER BY start_time DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))?;
+        let mut segments = Vec::new();
+        for row in rows {
+            let (data, uploaded) = row?;
+            segments.push(segment_from_row(&data, uploaded)?);
+        }
+        Ok(segments)
+    }
+
+    /// Forget the segments recorded to `file_path`, e.g. once it's deleted
+    pub fn remove_file(&mut self, file_path: &str) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM bookmarks WHERE segment_id IN (SELECT id FROM segments WHERE file_path = ?1)",
+            params![file_path],
+        )?;
+        transaction.execute(
+            "DELETE FROM segment_locks WHERE segment_id IN (SELECT id FROM segments WHERE file_path = ?1)",
+            params![file_path],
+        )?;
+        transaction.execute("DELETE FROM segments WHERE file_path = ?1", params![file_path])?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn record_incident(&mut self, incident: &IncidentRecord) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO incidents (id, started_at, data) VALUES (?1, ?2, ?3)",
+            params![incident.id, incident.started_at.timestamp(), serde_json::to_string(incident)?],
+        )?;
+        Ok(())
+    }
+
+    pub fn end_incident(&mut self, id: &str, ended_at: DateTime<Utc>) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        let data: Option<String> = transaction
+            .query_row("SELECT data FROM incidents WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        if let Some(data) = data {
+            let mut incident: IncidentRecord = serde_json::from_str(&data)?;
+            incident.ended_at = Some(ended_at);
+            transaction.execute(
+                "UPDATE incidents SET data = ?2 WHERE id = ?1",
+                params![id, serde_json::to_string(&incident)?],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Incidents started since `since`, newest first
+    pub fn incidents(&self, since: DateTime<Utc>) -> Result<Vec<IncidentRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT data FROM incidents WHERE started_at >= ?1 ORDER BY started_at DESC",
+        )?;
+        let rows = statement.query_map(params![since.timestamp()], |row| row.get::<_, String>(0))?;
+        let mut incidents = Vec::new();
+        for data in rows {
+            incidents.push(serde_json::from_str(&data?)?);
+        }
+        Ok(incidents)
+    }
+
+    /// Record a confirmed upload and mark the segments in the file uploaded
+    pub fn confirm_upload(&mut self, upload: &ConfirmedUpload) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        insert_upload(&transaction, upload)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn confirmed_uploads(&self) -> Result<Vec<ConfirmedUpload>> {
+        let mut statement = self.connection.prepare("SELECT data FROM uploads ORDER BY file_path")?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+        let mut uploads = Vec::new();
+        for data in rows {
+            uploads.push(serde_json::from_str(&data?)?);
+        }
+        Ok(uploads)
+    }
+
+    /// Keep only `uploads` in the ledger, e.g. after deleting the rest
+    pub fn replace_confirmed_uploads(&mut self, uploads: &[ConfirmedUpload]) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM uploads", [])?;
+        for upload in uploads {
+            transaction.execute(
+                "INSERT INTO uploads (file_path, data) VALUES (?1, ?2)",
+                params![upload.file_path, serde_json::to_string(upload)?],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn legal_holds(&self) -> Result<Vec<LegalHold>> {
+        let mut statement = self.connection.prepare("SELECT data FROM legal_holds")?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+        let mut holds = Vec::new();
+        for data in rows {
+            holds.push(serde_json::from_str(&data?)?);
+        }
+        Ok(holds)
+    }
+
+    /// Add a hold, replacing any earlier hold with the same id
+    pub fn place_hold(&mut self, hold: &LegalHold) -> Result<()> {
+        insert_hold(&self.connection, hold)
+    }
+
+    pub fn release_hold(&mut self, id: &str) -> Result<bool> {
+        Ok(self.connection.execute("DELETE FROM legal_holds WHERE id = ?1", params![id])? > 0)
+    }
+
+    pub fn replace_holds(&mut self, holds: &[LegalHold]) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM legal_holds", [])?;
+        for hold in holds {
+            insert_hold(&transaction, hold)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn add_bookmark(&mut self, segment_id: &str, offset_seconds: f64, note: &str) -> Result<SavedBookmark> {
+        // Whole seconds, as stored
+        let created_at = DateTime::<Utc>::from_timestamp(Utc::now().timestamp(), 0).unwrap_or_default();
+        self.connection.execute(
+            "INSERT INTO bookmarks (segment_id, offset_seconds, note, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![segment_id, offset_seconds, note, created_at.timestamp()],
+        )?;
+        Ok(SavedBookmark {
+            id: self.connection.last_insert_rowid(),
+            segment_id: segment_id.to_string(),
+            offset_seconds,
+            note: note.to_string(),
+            created_at,
+        })
+    }
+
+    /// Bookmarks in `segment_id`, in timeline order
+    pub fn bookmarks(&self, segment_id: &str) -> Result<Vec<SavedBookmark>> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, segment_id, offset_seconds, note, created_at FROM bookmarks
+             WHERE segment_id = ?1 ORDER BY offset_seconds",
+        )?;
+        let rows = statement.query_map(params![segment_id], |row| {
+            Ok(SavedBookmark {
+                id: row.get(0)?,
+                segment_id: row.get(1)?,
+                offset_seconds: row.get(2)?,
+                note: row.get(3)?,
+                created_at: DateTime::<Utc>::from_timestamp(row.get(4)?, 0).unwrap_or_default(),
+            })
+        })?;
+        let bookmarks = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(bookmarks)
+    }
+
+    pub fn remove_bookmark(&mut self, id: i64) -> Result<bool> {
+        Ok(self.connection.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])? > 0)
+    }
+
+    /// Bookmarks in any segment of `incident_id`, with the time each marks
+    pub fn incident_bookmarks(&self, incident_id: &str) -> Result<Vec<(SavedBookmark, DateTime<Utc>)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT b.id, b.segment_id, b.offset_seconds, b.note, b.created_at, s.start_time
+             FROM bookmarks b JOIN segments s ON s.id = b.segment_id
+             WHERE s.incident_id = ?1 ORDER BY s.start_time, b.offset_seconds",
+        )?;
+        let rows = statement.query_map(params![incident_id], |row| {
+            let bookmark = SavedBookmark {
+                id: row.get(0)?,
+                segment_id: row.get(1)?,
+                offset_seconds: row.get(2)?,
+                note: row.get(3)?,
+                created_at: DateTime::<Utc>::from_timestamp(row.get(4)?, 0).unwrap_or_default(),
+            };
+            let segment_start = DateTime::<Utc>::from_timestamp(row.get(5)?, 0).unwrap_or_default();
+            let at = segment_start + chrono::Duration::milliseconds((bookmark.offset_seconds * 1000.0) as i64);
+            Ok((bookmark, at))
+        })?;
+        let bookmarks = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(bookmarks)
+    }
+
+    /// Keep a segment out of loop overwrite, e.g. footage around an incident
+    pub fn lock_segment(&mut self, segment_id: &str, reason: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR IGNORE INTO segment_locks (segment_id, reason, locked_at) VALUES (?1, ?2, ?3)",
+            params![segment_id, reason, Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    pub fn locked_segments(&self) -> Result<HashSet<String>> {
+        let mut statement = self.connection.prepare("SELECT segment_id FROM segment_locks")?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+        let locked = rows.collect::<rusqlite::Result<HashSet<_>>>()?;
+        Ok(locked)
+    }
+
+    /// Add a message, call or status change to its incident's timeline
+    pub fn record_communication(&mut self, event: &CommunicationEvent) -> Result<()> {
+        self.connection.execute(
+            "INSERT INTO communications (incident_id, at, data) VALUES (?1, ?2, ?3)",
+            params![event.incident_id, event.at.timestamp_millis(), serde_json::to_string(event)?],
+        )?;
+        Ok(())
+    }
+
+    /// Messages and calls for `incident_id` and their status changes, oldest first
+    pub fn communications(&self, incident_id: &str) -> Result<Vec<CommunicationEvent>> {
+        let mut statement = self.connection.prepare(
+            "SELECT data FROM communications WHERE incident_id = ?1 ORDER BY at, id",
+        )?;
+        let rows = statement.query_map(params![incident_id], |row| row.get::<_, String>(0))?;
+        let mut events = Vec::new();
+        for data in rows {
+            events.push(serde_json::from_str(&data?)?);
+        }
+        Ok(events)
+    }
+}
+
+fn quality_name(quality: &VideoQuality) -> Result<String> {
+    match serde_json::to_value(quality)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => Err(anyhow::anyhow!("Unexpected quality {}", other)),
+    }
+}
+
+/// The stored segment, with the upload state kept by the index
+fn segment_from_row(data: &str, uploaded: bool) -> Result<RecordingSegment> {
+    let mut segment: RecordingSegment = serde_json::from_str(data)?;
+    segment.uploaded = uploaded;
+    Ok(segment)
+}
+
+fn upsert_segment(connection: &Connection, segment: &RecordingSegment) -> Result<()> {
+    // An upload confirmed before the segment was indexed still counts
+    let confirmed: bool = connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM uploads WHERE file_path = ?1)",
+        params![segment.file_path],
+        |row| row.get(0),
+    )?;
+    connection.execute(
+        "INSERT OR REPLACE INTO segments (id, incident_id, quality, start_time, file_path, uploaded, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            segment.id,
+            segment.incident_id,
+            quality_name(&segment.quality)?,
+            segment.start_time.timestamp(),
+            segment.file_path,
+            segment.uploaded || confirmed,
+            serde_json::to_string(segment)?,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_upload(connection: &Connection, upload: &ConfirmedUpload) -> Result<()> {
+    connection.execute(
+        "INSERT OR REPLACE INTO uploads (file_path, data) VALUES (?1, ?2)",
+        params![upload.file_path, serde_json::to_string(upload)?],
+    )?;
+    connection.execute("UPDATE segments SET uploaded = 1 WHERE file_path = ?1", params![upload.file_path])?;
+    Ok(())
+}
+
+fn insert_hold(connection: &Connection, hold: &LegalHold) -> Result<()> {
+    connection.execute(
+        "INSERT OR REPLACE INTO legal_holds (id, data) VALUES (?1, ?2)",
+        params![hold.id, serde_json::to_string(hold)?],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(id: &str, incident_id: &str, quality: &str, start: i64, file_path: &str) -> RecordingSegment {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "incident_id": incident_id,
+            "device_id": "dev-1",
+            "start_time": DateTime::<Utc>::from_timestamp(1_700_000_000 + start, 0).unwrap(),
+            "end_time": null,
+            "duration": 30,
+            "file_path": file_path,
+            "file_size": null,
+            "metadata": {
+                "resolution": "1920x1080",
+                "fps": 30,
+                "bitrate": 4000,
+                "codec": "h264",
+                "audio_enabled": true,
+                "audio_codec": "aac",
+                "encryption_key": null,
+                "location": null,
+                "ir_periods": [],
+                "location_track": null
+            },
+            "uploaded": false,
+            "quality": quality,
+            "pre_incident_segments": [],
+            "integrity": null,
+            "previous_parts": [],
+            "audio_only": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_segment_queries_and_upload_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = RecordingIndex::open_at(&dir.path().join("recordings.db")).unwrap();
+        index.upsert_segment(&segment("seg-1", "inc-1", "high", 0, "a_high.mp4")).unwrap();
+        index.upsert_segment(&segment("seg-2", "inc-1", "low", 10, "a_low.mp4")).unwrap();
+        index.upsert_segment(&segment("seg-3", "inc-2", "high", 20, "b_high.mp4")).unwrap();
+
+        let high = index.segments(&SegmentQuery { quality: Some(VideoQuality::High), ..Default::default() }).unwrap();
+        assert_eq!(high.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["seg-3", "seg-1"]);
+        assert_eq!(index.segments(&SegmentQuery { incident_id: Some("inc-1".to_string()), ..Default::default() }).unwrap().len(), 2);
+
+        index.confirm_upload(&ConfirmedUpload {
+            file_path: "a_high.mp4".to_string(),
+            sha256: "abc".to_string(),
+            confirmed_at: Utc::now(),
+            related_files: Vec::new(),
+        })
+        .unwrap();
+        let pending = index.segments(&SegmentQuery { uploaded: Some(false), ..Default::default() }).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(index.confirmed_uploads().unwrap().len(), 1);
+
+        let bookmark = index.add_bookmark("seg-1", 12.5, "suspect enters").unwrap();
+        index.add_bookmark("seg-1", 3.0, "arrival").unwrap();
+        assert_eq!(index.bookmarks("seg-1").unwrap()[1], bookmark);
+
+        index.remove_file("a_high.mp4").unwrap();
+        assert!(index.segment("seg-1").unwrap().is_none());
+        assert!(index.bookmarks("seg-1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_legacy_json_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata_dir = dir.path().join("recordings").join("metadata");
+        std::fs::create_dir_all(&metadata_dir).unwrap();
+        std::fs::create_dir_all(dir.path().join("data")).unwrap();
+        let legacy = segment("seg-1", "inc-1", "high", 0, "a_high.mp4");
+        std::fs::write(metadata_dir.join("seg-1.json"), serde_json::to_string(&legacy).unwrap()).unwrap();
+        std::fs::write(metadata_dir.join("broken.json"), "{").unwrap();
+        std::fs::write(dir.path().join("data").join("confirmed_uploads.json"), serde_json::json!([{
+            "file_path": "a_high.mp4",
+            "sha256": "abc",
+            "confirmed_at": Utc::now(),
+            "related_files": []
+        }]).to_string()).unwrap();
+        std::fs::write(dir.path().join("data").join("legal_holds.json"), serde_json::json!([{
+            "id": "hold-1",
+            "incident_id": "inc-1",
+            "segment_id": null,
+            "reason": null,
+            "placed_by": null,
+            "placed_at": Utc::now()
+        }]).to_string()).unwrap();
+
+        let mut index = RecordingIndex::open_at(&dir.path().join("data").join("recordings.db")).unwrap();
+        let report = index.import_legacy(dir.path()).unwrap().unwrap();
+        assert_eq!(report, ImportReport { segments: 1, uploads: 1, legal_holds: 1 });
+        assert_eq!(index.segments(&SegmentQuery { uploaded: Some(true), ..Default::default() }).unwrap().len(), 1);
+        assert_eq!(index.legal_holds().unwrap()[0].id, "hold-1");
+
+        assert!(index.import_legacy(dir.path()).unwrap().is_none());
+    }
+}
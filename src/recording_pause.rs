@@ -0,0 +1,85 @@
+//! Pausing a recording, e.g. for a privacy-sensitive conversation. The
+//! encoders are suspended rather than stopped, so the recording carries on
+//! in the same files and each pause is kept in the segment's metadata as a
+//! gap. Fixes taken during a gap are left out of the location track.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::location_track::LocationTrack;
+
+/// A span of a segment where recording was paused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingGap {
+    pub start: DateTime<Utc>,
+    /// None while still paused
+    pub end: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+impl RecordingGap {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        at >= self.start && !self.end.is_some_and(|end| at >= end)
+    }
+}
+
+/// End any gap still open at `at`
+pub fn close_gaps(gaps: &mut [RecordingGap], at: DateTime<Utc>) {
+    for gap in gaps.iter_mut().filter(|gap| gap.end.is_none()) {
+        gap.end = Some(at);
+    }
+}
+
+/// Seconds between `start` and `end` that were recorded, leaving out the gaps
+pub fn recorded_seconds(start: DateTime<Utc>, end: DateTime<Utc>, gaps: &[RecordingGap]) -> u64 {
+    let paused: i64 = gaps.iter()
+        .map(|gap| {
+            let from = gap.start.max(start);
+            let to = gap.end.unwrap_or(end).min(end);
+            (to - from).num_seconds().max(0)
+        })
+        .sum();
+    ((end - start).num_seconds() - paused).max(0) as u64
+}
+
+/// Remove the fixes taken while paused
+pub fn drop_paused_fixes(track: &mut LocationTrack, gaps: &[RecordingGap]) {
+    track.points.retain(|fix| !gaps.iter().any(|gap| gap.contains(fix.timestamp)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_gaps_leave_out_paused_time_and_fixes() {
+        let start = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let mut gaps = vec![
+            RecordingGap { start: start + Duration::minutes(5), end: Some(start + Duration::minutes(7)), reason: None },
+            RecordingGap { start: start + Duration::minutes(9), end: None, reason: Some("interview".to_string()) },
+        ];
+        assert!(gaps[1].contains(start + Duration::hours(1)));
+
+        let end = start + Duration::minutes(10);
+        close_gaps(&mut gaps, end);
+        assert_eq!(gaps[1].end, Some(end));
+        assert_eq!(recorded_seconds(start, end, &gaps), 7 * 60);
+
+        let fix = |minute| crate::gps::GpsLocation {
+            latitude: 22.2783,
+            longitude: 114.1747,
+            altitude: None,
+            accuracy: Some(5.0),
+            hdop: None,
+            speed: None,
+            heading: None,
+            timestamp: start + Duration::minutes(minute),
+            satellites: None,
+        };
+        let mut track = LocationTrack { points: vec![fix(1), fix(6), fix(8), fix(9)] };
+        drop_paused_fixes(&mut track, &gaps);
+        let kept: Vec<_> = track.points.iter().map(|fix| (fix.timestamp - start).num_minutes()).collect();
+        assert_eq!(kept, vec![1, 8]);
+    }
+}
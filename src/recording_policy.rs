@@ -0,0 +1,243 @@
+//! Places and hours where the platform prohibits or requires recording,
+//! e.g. no recording in the locker room and always recording at the
+//! entrance at night. Policies are signed with the same key as the tamper
+//! policy and kept with their signature, so one edited on disk is ignored.
+
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::tamper::verify_signature;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingAction {
+    /// Recording may not start
+    Block,
+    /// Recording starts by itself and restarts if stopped
+    Force,
+}
+
+/// A circle around a point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Geofence {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_meters: f64,
+}
+
+impl Geofence {
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        const EARTH_RADIUS_M: f64 = 6_371_000.0;
+        let (lat1, lat2) = (self.latitude.to_radians(), latitude.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (longitude - self.longitude).to_radians();
+        let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_M * h.sqrt().asin() <= self.radius_meters
+    }
+}
+
+/// Local hours; `start` after `end` runs over midnight, e.g. 22:00 to 06:00
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// One restriction. Its conditions must all hold; a rule without any
+/// applies everywhere, all the time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingRule {
+    /// Shown to the operator, e.g. "Locker room"
+    pub name: String,
+    pub action: RecordingAction,
+    #[serde(default)]
+    pub geofence: Option<Geofence>,
+    #[serde(default)]
+    pub hours: Option<TimeWindow>,
+    /// Block rules only: an emergency incident records anyway
+    #[serde(default = "default_allow_emergency")]
+    pub allow_emergency: bool,
+}
+
+fn default_allow_emergency() -> bool {
+    true
+}
+
+impl RecordingRule {
+    fn applies(&self, position: Option<(f64, f64)>, time: NaiveTime) -> bool {
+        let in_zone = match (&self.geofence, position) {
+            (None, _) => true,
+            (Some(geofence), Some((latitude, longitude))) => geofence.contains(latitude, longitude),
+            // Without a position the device can't be shown to be inside
+            (Some(_), None) => false,
+        };
+        in_zone && self.hours.as_ref().map_or(true, |hours| hours.contains(time))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordingPolicy {
+    /// Increases with each policy the backend issues; older ones are refused
+    pub version: u64,
+    pub rules: Vec<RecordingRule>,
+}
+
+/// What the policy says about recording right now
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", content = "rule", rename_all = "lowercase")]
+pub enum PolicyDecision {
+    #[default]
+    Allow,
+    /// Named after the rule that decided it
+    Block(String),
+    Force(String),
+}
+
+impl RecordingPolicy {
+    /// Block rules win over force rules, so a zone the platform put off
+    /// limits stays off limits whatever else overlaps it
+    pub fn decide(&self, position: Option<(f64, f64)>, time: NaiveTime, emergency: bool) -> PolicyDecision {
+        let applying = || self.rules.iter().filter(|rule| rule.applies(position, time));
+        if let Some(rule) = applying().find(|rule| rule.action == RecordingAction::Block && !(emergency && rule.allow_emergency)) {
+            return PolicyDecision::Block(rule.name.clone());
+        }
+        applying()
+            .find(|rule| rule.action == RecordingAction::Force)
+            .map_or(PolicyDecision::Allow, |rule| PolicyDecision::Force(rule.name.clone()))
+    }
+}
+
+/// A policy as the backend sends it: the JSON exactly as signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRecordingPolicy {
+    pub payload: String,
+    /// Base64 Ed25519 signature over `payload`
+    pub signature: String,
+}
+
+impl SignedRecordingPolicy {
+    pub fn verify(&self, public_key: &str) -> Result<RecordingPolicy> {
+        verify_signature(public_key, &self.payload, &self.signature)
+            .map_err(|e| anyhow::anyhow!("Recording policy signature is not valid: {}", e))?;
+        serde_json::from_str(&self.payload).context("Invalid recording policy")
+    }
+}
+
+/// The active policy, persisted with its signature
+pub struct RecordingPolicyStore {
+    path: PathBuf,
+    public_key: Option<String>,
+    policy: RecordingPolicy,
+}
+
+impl RecordingPolicyStore {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("recording_policy.json")
+    }
+
+    pub async fn load(public_key: Option<&str>) -> Self {
+        Self::load_from(Self::default_path(), public_key).await
+    }
+
+    pub async fn load_from(path: PathBuf, public_key: Option<&str>) -> Self {
+        let stored = tokio::fs::read_to_string(&path).await.ok()
+            .and_then(|content| serde_json::from_str::<SignedRecordingPolicy>(&content).ok());
+        let policy = match (stored, public_key) {
+            (Some(signed), Some(key)) => signed.verify(key).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring stored recording policy: {:#}", e);
+                RecordingPolicy::default()
+            }),
+            _ => RecordingPolicy::default(),
+        };
+
+        Self {
+            path,
+            public_key: public_key.map(str::to_string),
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> &RecordingPolicy {
+        &self.policy
+    }
+
+    /// Verify and persist a policy from the backend
+    pub async fn install(&mut self, signed: SignedRecordingPolicy) -> Result<&RecordingPolicy> {
+        let public_key = self.public_key.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No policy public key configured; refusing unsigned policy"))?;
+        let policy = signed.verify(public_key)?;
+        if policy.version <= self.policy.version {
+            return Err(anyhow::anyhow!(
+                "Recording policy version {} is not newer than {}",
+                policy.version, self.policy.version
+            ));
+        }
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_vec_pretty(&signed)?).await
+            .context("Failed to save recording policy")?;
+        self.policy = policy;
+        Ok(&self.policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_block_zone_and_night_force() {
+        let locker_room = Geofence { latitude: 51.5007, longitude: -0.1246, radius_meters: 20.0 };
+        let entrance = Geofence { latitude: 51.5010, longitude: -0.1260, radius_meters: 30.0 };
+        let policy = RecordingPolicy {
+            version: 1,
+            rules: vec![
+                RecordingRule {
+                    name: "Locker room".to_string(),
+                    action: RecordingAction::Block,
+                    geofence: Some(locker_room),
+                    hours: None,
+                    allow_emergency: true,
+                },
+                RecordingRule {
+                    name: "Entrance at night".to_string(),
+                    action: RecordingAction::Force,
+                    geofence: Some(entrance),
+                    hours: Some(TimeWindow { start: time(22), end: time(6) }),
+                    allow_emergency: true,
+                },
+            ],
+        };
+
+        let in_locker_room = Some((51.5007, -0.1247));
+        assert_eq!(policy.decide(in_locker_room, time(12), false), PolicyDecision::Block("Locker room".to_string()));
+        assert_eq!(policy.decide(in_locker_room, time(12), true), PolicyDecision::Allow);
+
+        let at_entrance = Some((51.5010, -0.1261));
+        assert_eq!(policy.decide(at_entrance, time(23), false), PolicyDecision::Force("Entrance at night".to_string()));
+        assert_eq!(policy.decide(at_entrance, time(3), false), PolicyDecision::Force("Entrance at night".to_string()));
+        assert_eq!(policy.decide(at_entrance, time(12), false), PolicyDecision::Allow);
+        assert_eq!(policy.decide(None, time(23), false), PolicyDecision::Allow);
+    }
+}
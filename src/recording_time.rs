@@ -0,0 +1,159 @@
+//! How many more minutes the device can record, limited by whichever runs
+//! out first: storage at the ladder's combined bitrate, or the battery at
+//! the current draw.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::media::RecordingMode;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemainingTimeConfig {
+    /// Battery draw assumed while recording until one has been measured
+    pub recording_draw_ma: f32,
+    /// Weight of each new measurement in the smoothed draw
+    pub draw_smoothing: f32,
+    /// Warn once the estimate falls below this
+    pub warn_minutes: u64,
+}
+
+impl Default for RemainingTimeConfig {
+    fn default() -> Self {
+        Self {
+            recording_draw_ma: 900.0,
+            draw_smoothing: 0.2,
+            warn_minutes: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingLimit {
+    Storage,
+    Battery,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemainingRecording {
+    pub minutes: u64,
+    pub limited_by: RecordingLimit,
+    pub storage_minutes: u64,
+    /// None while charging
+    pub battery_minutes: Option<u64>,
+}
+
+/// Bits per second written while recording: every quality in the ladder
+/// is recorded at once, each with its own audio track
+pub fn recording_bitrate(config: &Config, mode: RecordingMode) -> u64 {
+    let audio = config.audio.bitrate as u64;
+    match mode {
+        RecordingMode::AudioOnly => audio,
+        RecordingMode::Video if config.recording.available_qualities.is_empty() => config.recording.bitrate as u64 + audio,
+        RecordingMode::Video => config.recording.available_qualities.iter()
+            .map(|quality| quality.bitrate as u64 + audio)
+            .sum(),
+    }
+}
+
+/// Battery draw while recording, smoothed over measurements taken while
+/// recording on battery
+#[derive(Debug, Clone)]
+pub struct DrawModel {
+    draw_ma: f32,
+    smoothing: f32,
+}
+
+impl DrawModel {
+    pub fn new(config: &RemainingTimeConfig) -> Self {
+        Self {
+            draw_ma: config.recording_draw_ma,
+            smoothing: config.draw_smoothing.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Feed a battery current reading; negative while discharging
+    pub fn observe(&mut self, current_ma: f32) {
+        if current_ma < 0.0 {
+            self.draw_ma += (current_ma.abs() - self.draw_ma) * self.smoothing;
+        }
+    }
+
+    pub fn draw_ma(&self) -> f32 {
+        self.draw_ma
+    }
+}
+
+/// Minutes of recording left. `battery_mah` is the charge remaining, or
+/// None while charging.
+pub fn estimate(recording_space: u64, bitrate: u64, battery_mah: Option<f32>, draw_ma: f32) -> RemainingRecording {
+    let bytes_per_minute = (bitrate / 8 * 60).max(1);
+    let storage_minutes = recording_space / bytes_per_minute;
+    let battery_minutes = battery_mah
+        .filter(|_| draw_ma > 0.0)
+        .map(|mah| (mah.max(0.0) / draw_ma * 60.0) as u64);
+
+    match battery_minutes {
+        Some(battery) if battery < storage_minutes => RemainingRecording {
+            minutes: battery,
+            limited_by: RecordingLimit::Battery,
+            storage_minutes,
+            battery_minutes,
+        },
+        _ => RemainingRecording {
+            minutes: storage_minutes,
+            limited_by: RecordingLimit::Storage,
+            storage_minutes,
+            battery_minutes,
+        },
+    }
+}
+
+/// Raises the low-remaining warning once per crossing below the threshold
+#[derive(Debug, Default)]
+pub struct LowRemainingAlarm {
+    raised: bool,
+}
+
+impl LowRemainingAlarm {
+    /// True when the estimate has just fallen below `warn_minutes`
+    pub fn update(&mut self, minutes: u64, warn_minutes: u64) -> bool {
+        let low = minutes < warn_minutes;
+        let raise = low && !self.raised;
+        self.raised = low;
+        raise
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_takes_the_nearer_limit() {
+        let config = Config::default();
+        let ladder = recording_bitrate(&config, RecordingMode::Video);
+        assert!(ladder > recording_bitrate(&config, RecordingMode::AudioOnly));
+
+        // 4 Mbit/s fills 30 GB in 1000 minutes; 2000 mAh at 1000 mA lasts 120
+        let remaining = estimate(30_000_000_000, 4_000_000, Some(2000.0), 1000.0);
+        assert_eq!(remaining.minutes, 120);
+        assert_eq!(remaining.limited_by, RecordingLimit::Battery);
+        assert_eq!(remaining.storage_minutes, 1000);
+        let charging = estimate(30_000_000_000, 4_000_000, None, 1000.0);
+        assert_eq!((charging.minutes, charging.limited_by), (1000, RecordingLimit::Storage));
+
+        let mut model = DrawModel::new(&RemainingTimeConfig { recording_draw_ma: 1000.0, draw_smoothing: 0.5, warn_minutes: 30 });
+        model.observe(-600.0);
+        assert_eq!(model.draw_ma(), 800.0);
+        model.observe(500.0);
+        assert_eq!(model.draw_ma(), 800.0);
+
+        let mut alarm = LowRemainingAlarm::default();
+        assert!(!alarm.update(45, 30));
+        assert!(alarm.update(29, 30));
+        assert!(!alarm.update(20, 30));
+        assert!(!alarm.update(60, 30));
+        assert!(alarm.update(10, 30));
+    }
+}
@@ -0,0 +1,415 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::integrity::IntegrityManager;
+use crate::media::RecordingSegment;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Haar cascade used to find faces
+    pub face_cascade: String,
+    /// Haar cascade used to find license plates, if any
+    pub plate_cascade: Option<String>,
+    /// Run detection on every Nth frame; boxes are carried in between
+    pub detect_every_n_frames: u32,
+    /// Frames a box stays blurred after its last detection
+    pub hold_frames: u32,
+    /// Extra margin around each detection, as a fraction of its size
+    pub padding: f32,
+    /// Pixelation block size in pixels
+    pub block_size: u32,
+    pub output_dir: String,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            face_cascade: "/usr/share/opencv4/haarcascades/haarcascade_frontalface_default.xml".to_string(),
+            plate_cascade: Some("/usr/share/opencv4/haarcascades/haarcascade_russian_plate_number.xml".to_string()),
+            detect_every_n_frames: 3,
+            hold_frames: 15,
+            padding: 0.2,
+            block_size: 16,
+            output_dir: "exports/redacted".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    /// Grow by `padding` of its size on every side, clamped to the frame
+    pub fn padded(&self, padding: f32, frame_width: u32, frame_height: u32) -> Region {
+        let pad_x = (self.width as f32 * padding) as u32;
+        let pad_y = (self.height as f32 * padding) as u32;
+        let x = self.x.saturating_sub(pad_x);
+        let y = self.y.saturating_sub(pad_y);
+        let right = (self.x + self.width + pad_x).min(frame_width);
+        let bottom = (self.y + self.height + pad_y).min(frame_height);
+
+        Region {
+            x,
+            y,
+            width: right.saturating_sub(x),
+            height: bottom.saturating_sub(y),
+        }
+    }
+}
+
+/// Keeps boxes blurred for a while after the detector loses them, so a face
+/// missed on one frame isn't exposed
+#[derive(Debug)]
+pub struct RegionTracker {
+    hold_frames: u32,
+    tracked: Vec<(Region, u32)>,
+}
+
+impl RegionTracker {
+    pub fn new(hold_frames: u32) -> Self {
+        Self { hold_frames, tracked: Vec::new() }
+    }
+
+    /// Advance one frame. `detections` is None on frames the detector
+    /// skipped. Returns the regions to blur on this frame.
+    pub fn update(&mut self, detections: Option<Vec<Region>>) -> Vec<Region> {
+        for (_, remaining) in self.tracked.iter_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        self.tracked.retain(|(_, remaining)| *remaining > 0);
+
+        if let Some(detections) = detections {
+            self.tracked.extend(detections.into_iter().map(|region| (region, self.hold_frames + 1)));
+        }
+
+        self.tracked.iter().map(|(region, _)| *region).collect()
+    }
+}
+
+/// Replace a region of a packed BGR frame with coarse blocks, each filled
+/// with its mean colour
+pub fn pixelate(frame: &mut [u8], frame_width: u32, region: &Region, block_size: u32) {
+    let block_size = block_size.max(1);
+    let stride = frame_width as usize * 3;
+
+    let mut by = region.y;
+    while by < region.y + region.height {
+        let bh = block_size.min(region.y + region.height - by);
+        let mut bx = region.x;
+        while bx < region.x + region.width {
+            let bw = block_size.min(region.x + region.width - bx);
+
+            let mut sum = [0u64; 3];
+            for y in by..by + bh {
+                for x in bx..bx + bw {
+                    let i = y as usize * stride + x as usize * 3;
+                    for c in 0..3 {
+                        sum[c] += frame[i + c] as u64;
+                    }
+                }
+            }
+
+            let count = (bw * bh) as u64;
+            for y in by..by + bh {
+                for x in bx..bx + bw {
+                    let i = y as usize * stride + x as usize * 3;
+                    for c in 0..3 {
+                        frame[i + c] = (sum[c] / count) as u8;
+                    }
+                }
+            }
+
+            bx += bw;
+        }
+        by += bh;
+    }
+}
+
+fn parse_resolution(resolution: &str) -> Result<(u32, u32)> {
+    let (width, height) = resolution.split_once('x')
+        .ok_or_else(|| anyhow::anyhow!("Invalid resolution {}", resolution))?;
+    Ok((width.trim().parse()?, height.trim().parse()?))
+}
+
+#[cfg(feature = "redaction")]
+mod detector {
+    use anyhow::{Result, Context};
+    use opencv::core::{Mat, Rect, Size, Vector};
+    use opencv::objdetect::CascadeClassifier;
+    use opencv::prelude::*;
+
+    use super::{RedactionConfig, Region};
+
+    pub struct Detector {
+        cascades: Vec<CascadeClassifier>,
+    }
+
+    impl Detector {
+        pub fn new(config: &RedactionConfig) -> Result<Self> {
+            let mut cascades = vec![CascadeClassifier::new(&config.face_cascade)
+                .context("Failed to load face cascade")?];
+            if let Some(plate_cascade) = &config.plate_cascade {
+                cascades.push(CascadeClassifier::new(plate_cascade)
+                    .context("Failed to load license plate cascade")?);
+            }
+            Ok(Self { cascades })
+        }
+
+        pub fn detect(&mut self, frame: &[u8], width: u32, height: u32) -> Result<Vec<Region>> {
+            // Cascades work on luma; BT.601 weights over BGR
+            let gray: Vec<u8> = frame.chunks_exact(3)
+                .map(|p| ((p[0] as u32 * 29 + p[1] as u32 * 150 + p[2] as u32 * 77) >> 8) as u8)
+                .collect();
+            let image = Mat::new_rows_cols_with_data(height as i32, width as i32, &gray)?;
+
+            let mut regions = Vec::new();
+            for cascade in self.cascades.iter_mut() {
+                let mut found = Vector::<Rect>::new();
+                cascade.detect_multi_scale(&*image, &mut found, 1.1, 3, 0, Size::new(24, 24), Size::default())?;
+                regions.extend(found.iter().map(|r| Region {
+                    x: r.x.max(0) as u32,
+                    y: r.y.max(0) as u32,
+                    width: r.width.max(0) as u32,
+                    height: r.height.max(0) as u32,
+                }));
+            }
+            Ok(regions)
+        }
+    }
+}
+
+#[cfg(not(feature = "redaction"))]
+mod detector {
+    use anyhow::Result;
+
+    use super::{RedactionConfig, Region};
+
+    pub struct Detector;
+
+    impl Detector {
+        pub fn new(_config: &RedactionConfig) -> Result<Self> {
+            Err(anyhow::anyhow!("This build has no redaction support (enable the `redaction` feature)"))
+        }
+
+        pub fn detect(&mut self, _frame: &[u8], _width: u32, _height: u32) -> Result<Vec<Region>> {
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// What a redaction pass produced, also written next to the export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionReport {
+    pub segment_id: String,
+    pub incident_id: String,
+    pub source_path: String,
+    pub output_path: String,
+    pub frames: u64,
+    pub frames_redacted: u64,
+    pub output_sha256: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Produces redacted copies of recordings for public-records exports. The
+/// original is only ever read; encrypted originals are decrypted to a
+/// temporary file that is removed afterwards.
+pub struct Redactor {
+    config: Config,
+}
+
+impl Redactor {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    pub async fn redact_segment(&self, segment_id: &str, output: Option<PathBuf>) -> Result<RedactionReport> {
+        let segment = crate::custody::load_segment(segment_id).await?;
+        let source = PathBuf::from(&segment.file_path);
+        let redaction = &self.config.redaction;
+
+        let output = match output {
+            Some(output) => output,
+            None => {
+                let dir = PathBuf::from(&redaction.output_dir);
+                fs::create_dir_all(&dir).await?;
+                dir.join(format!("{}_redacted.mp4", segment.id))
+            }
+        };
+
+        // Fail before decrypting anything if detection isn't available
+        let mut detector = detector::Detector::new(redaction)?;
+
+        let decrypted = self.plaintext_copy(&segment).await?;
+        let input = decrypted.as_deref().unwrap_or(&source);
+        let result = self.run(&segment, input, &output, &mut detector).await;
+
+        if let Some(decrypted) = &decrypted {
+            let _ = fs::remove_file(decrypted).await;
+        }
+        let (frames, frames_redacted) = result?;
+
+        let report = RedactionReport {
+            segment_id: segment.id.clone(),
+            incident_id: segment.incident_id.clone(),
+            source_path: segment.file_path.clone(),
+            output_path: output.to_string_lossy().to_string(),
+            frames,
+            frames_redacted,
+            output_sha256: IntegrityManager::calculate_file_hash(&output).await?,
+            created_at: chrono::Utc::now(),
+        };
+        fs::write(output.with_extension("json"), serde_json::to_string_pretty(&report)?).await?;
+
+        tracing::info!("Redacted segment {} ({} of {} frames) to {}", segment.id, frames_redacted, frames, output.display());
+        Ok(report)
+    }
+
+    /// Decrypt an encrypted original into the temp directory
+    async fn plaintext_copy(&self, segment: &RecordingSegment) -> Result<Option<PathBuf>> {
+        if segment.metadata.encryption_key.is_none() {
+            return Ok(None);
+        }
+
+        let temp_dir = std::env::current_dir()?.join("temp");
+        fs::create_dir_all(&temp_dir).await?;
+        let decrypted = temp_dir.join(format!("{}_redaction_source.mp4", segment.id));
+        crate::custody::plaintext_copy(&self.config, segment, &decrypted).await
+            .context("Failed to decrypt segment for redaction")?;
+        Ok(Some(decrypted))
+    }
+
+    /// Decode to raw frames, blur detections and re-encode, copying audio
+    async fn run(
+        &self,
+        segment: &RecordingSegment,
+        input: &Path,
+        output: &Path,
+        detector: &mut detector::Detector,
+    ) -> Result<(u64, u64)> {
+        let redaction = &self.config.redaction;
+        let (width, height) = parse_resolution(&segment.metadata.resolution)?;
+        let frame_size = (width * height * 3) as usize;
+
+        let mut decoder = Command::new("ffmpeg")
+            .arg("-i").arg(input)
+            .arg("-vf").arg(format!("scale={}:{}", width, height))
+            .arg("-f").arg("rawvideo")
+            .arg("-pix_fmt").arg("bgr24")
+            .arg("-loglevel").arg("error")
+            .arg("pipe:1")
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start redaction decoder")?;
+
+        let mut encoder = Command::new("ffmpeg")
+            .arg("-y")
+            .arg("-f").arg("rawvideo")
+            .arg("-pix_fmt").arg("bgr24")
+            .arg("-video_size").arg(format!("{}x{}", width, height))
+            .arg("-framerate").arg(segment.metadata.fps.to_string())
+            .arg("-i").arg("pipe:0")
+            .arg("-i").arg(input)
+            .arg("-map").arg("0:v")
+            .arg("-map").arg("1:a?")
+            .arg("-c:v").arg("libx264")
+            .arg("-pix_fmt").arg("yuv420p")
+            .arg("-c:a").arg("copy")
+            .arg("-loglevel").arg("error")
+            .arg(output)
+            .stdin(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start redaction encoder")?;
+
+        let mut frames_in = decoder.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("Redaction decoder has no stdout"))?;
+        let mut frames_out = encoder.stdin.take()
+            .ok_or_else(|| anyhow::anyhow!("Redaction encoder has no stdin"))?;
+
+        let mut tracker = RegionTracker::new(redaction.hold_frames);
+        let mut frame = vec![0u8; frame_size];
+        let mut frames = 0u64;
+        let mut frames_redacted = 0u64;
+
+        loop {
+            match frames_in.read_exact(&mut frame).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e).context("Failed to read decoded frame"),
+            }
+
+            let detections = if frames % redaction.detect_every_n_frames.max(1) as u64 == 0 {
+                Some(detector.detect(&frame, width, height)?
+                    .into_iter()
+                    .map(|r| r.padded(redaction.padding, width, height))
+                    .collect())
+            } else {
+                None
+            };
+
+            let regions = tracker.update(detections);
+            if !regions.is_empty() {
+                frames_redacted += 1;
+            }
+            for region in &regions {
+                pixelate(&mut frame, width, region, redaction.block_size);
+            }
+
+            frames_out.write_all(&frame).await.context("Failed to write redacted frame")?;
+            frames += 1;
+        }
+
+        drop(frames_out);
+        decoder.wait().await?;
+        let status = encoder.wait().await?;
+        if !status.success() {
+            let _ = fs::remove_file(output).await;
+            return Err(anyhow::anyhow!("Redaction encoder failed: {}", status));
+        }
+
+        Ok((frames, frames_redacted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padding_clamps_to_frame() {
+        let region = Region { x: 5, y: 10, width: 50, height: 50 };
+        let padded = region.padded(0.2, 60, 200);
+        assert_eq!(padded, Region { x: 0, y: 0, width: 60, height: 70 });
+    }
+
+    #[test]
+    fn test_tracker_holds_lost_regions() {
+        let face = Region { x: 1, y: 1, width: 2, height: 2 };
+        let mut tracker = RegionTracker::new(2);
+
+        assert_eq!(tracker.update(Some(vec![face])), vec![face]);
+        assert_eq!(tracker.update(None), vec![face]);
+        assert_eq!(tracker.update(Some(Vec::new())), vec![face]);
+        assert!(tracker.update(None).is_empty());
+    }
+
+    #[test]
+    fn test_pixelate_averages_blocks() {
+        // 4x1 frame: two 2-pixel blocks
+        let mut frame = vec![0, 0, 0, 100, 100, 100, 10, 20, 30, 30, 40, 50];
+        pixelate(&mut frame, 4, &Region { x: 0, y: 0, width: 4, height: 1 }, 2);
+        assert_eq!(frame, vec![50, 50, 50, 50, 50, 50, 20, 30, 40, 20, 30, 40]);
+    }
+}
@@ -2,11 +2,10 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tokio::time::{interval, Duration};
-use std::sync::Arc;
-use tokio::sync::Mutex;
 use reqwest::Client;
 
 use crate::config::{Config, RemoteConfig as ConfigRemote};
+use crate::error_handling::DeviceError;
 use crate::device::BodycamDevice;
 use crate::sentry_integration;
 
@@ -57,12 +56,12 @@ pub enum UpdatePriority {
 pub struct RemoteConfigManager {
     config: Config,
     client: Client,
-    device: Arc<Mutex<BodycamDevice>>,
+    device: BodycamDevice,
     update_tx: mpsc::UnboundedSender<RemoteConfigUpdate>,
 }
 
 impl RemoteConfigManager {
-    pub fn new(config: Config, device: Arc<Mutex<BodycamDevice>>) -> (Self, mpsc::UnboundedReceiver<RemoteConfigUpdate>) {
+    pub fn new(config: Config, device: BodycamDevice) -> (Self, mpsc::UnboundedReceiver<RemoteConfigUpdate>) {
         let client = Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
@@ -119,9 +118,8 @@ impl RemoteConfigManager {
     ) -> Result<Option<ConfigResponse>> {
         let _transaction = sentry_integration::start_transaction("remote_config.request_update", "config");
         
-        let device = self.device.lock().await;
-        let device_id = device.device_id.clone()
-            .ok_or_else(|| anyhow::anyhow!("Device not provisioned"))?;
+        let device_id = self.device.device_id()
+            .ok_or(DeviceError::NotProvisioned)?;
         
         let request = ConfigRequest {
             device_id,
@@ -205,12 +203,11 @@ impl RemoteConfigManager {
     async fn check_for_updates(
         config: &Config,
         client: &Client,
-        device: &Arc<Mutex<BodycamDevice>>,
+        device: &BodycamDevice,
         update_tx: &mpsc::UnboundedSender<RemoteConfigUpdate>,
     ) -> Result<()> {
-        let device_locked = device.lock().await;
-        let device_id = device_locked.device_id.clone()
-            .ok_or_else(|| anyhow::anyhow!("Device not provisioned"))?;
+        let device_id = device.device_id()
+            .ok_or(DeviceError::NotProvisioned)?;
         
         let request = ConfigRequest {
             device_id,
@@ -292,9 +289,8 @@ impl RemoteConfigManager {
     
     pub async fn send_config_status(&self, status: &str, details: Option<serde_json::Value>
     ) -> Result<()> {
-        let device = self.device.lock().await;
-        let device_id = device.device_id.clone()
-            .ok_or_else(|| anyhow::anyhow!("Device not provisioned"))?;
+        let device_id = self.device.device_id()
+            .ok_or(DeviceError::NotProvisioned)?;
         
         let status_update = serde_json::json!({
             "device_id": device_id,
@@ -322,10 +318,9 @@ impl RemoteConfigManager {
 }
 
 impl BodycamDevice {
-    pub async fn request_remote_config_update(&mut self
+    pub async fn request_remote_config_update(&self
     ) -> Result<()> {
-        let config_manager = RemoteConfigManager::new(self.config.clone(), 
-            Arc::new(tokio::sync::Mutex::new(self.clone())));
+        let config_manager = RemoteConfigManager::new(self.config(), self.clone());
         
         let (_, _) = config_manager;
         
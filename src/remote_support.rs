@@ -0,0 +1,261 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::config::Config;
+
+/// Config keys whose values never leave the device
+const SECRET_KEYS: &[&str] = &["key", "token", "secret", "password", "pin", "dsn"];
+const REDACTED: &str = "[redacted]";
+
+/// Fleet support access to this device. Off unless enabled on the device
+/// itself; the backend can only ask for what is listed here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportConfig {
+    /// Device-side consent to remote support requests
+    pub enabled: bool,
+    /// Largest artifact returned; log files are tailed to fit
+    pub max_artifact_bytes: u64,
+    /// Commands support may run, by name
+    pub allowed_commands: Vec<AllowedCommand>,
+}
+
+impl Default for SupportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_artifact_bytes: 1024 * 1024,
+            allowed_commands: vec![
+                AllowedCommand::new("disk_usage", "df", &["-h"]),
+                AllowedCommand::new("uptime", "uptime", &[]),
+                AllowedCommand::new("video_devices", "v4l2-ctl", &["--list-devices"]),
+            ],
+        }
+    }
+}
+
+/// A fixed command line; the backend picks one by name and can't add arguments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedCommand {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub timeout_seconds: u64,
+}
+
+impl AllowedCommand {
+    fn new(name: &str, program: &str, args: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            timeout_seconds: 10,
+        }
+    }
+}
+
+/// What support asked for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SupportRequest {
+    /// One file from the log directory, by file name
+    LogFile { name: String },
+    /// The running configuration with secrets redacted
+    ConfigDump,
+    /// One command from `allowed_commands`
+    Command { name: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportArtifact {
+    pub request: SupportRequest,
+    pub content: String,
+    /// Whether `content` was cut to `max_artifact_bytes`
+    pub truncated: bool,
+    pub sha256: String,
+}
+
+impl SupportArtifact {
+    fn new(request: SupportRequest, content: String, truncated: bool) -> Self {
+        Self {
+            sha256: format!("{:x}", Sha256::digest(content.as_bytes())),
+            request,
+            content,
+            truncated,
+        }
+    }
+}
+
+/// Serves support requests. Callers record every request, granted or not,
+/// to the audit log (see `BodycamDevice::handle_support_request`).
+pub struct RemoteSupport {
+    config: SupportConfig,
+    log_dir: PathBuf,
+}
+
+impl RemoteSupport {
+    pub fn new(config: SupportConfig) -> Result<Self> {
+        let log_dir = std::env::current_dir()?.join("logs");
+        Ok(Self { config, log_dir })
+    }
+
+    pub fn with_log_dir(mut self, log_dir: PathBuf) -> Self {
+        self.log_dir = log_dir;
+        self
+    }
+
+    pub async fn fetch(&self, request: &SupportRequest, device_config: &Config) -> Result<SupportArtifact> {
+        if !self.config.enabled {
+            return Err(anyhow::anyhow!("Remote support is not enabled on this device"));
+        }
+
+        match request {
+            SupportRequest::LogFile { name } => self.read_log(request, name).await,
+            SupportRequest::ConfigDump => {
+                let mut value = serde_json::to_value(device_config)?;
+                redact_secrets(&mut value);
+                let content = serde_json::to_string_pretty(&value)?;
+                Ok(self.limit(request.clone(), content))
+            }
+            SupportRequest::Command { name } => self.run_command(request, name).await,
+        }
+    }
+
+    async fn read_log(&self, request: &SupportRequest, name: &str) -> Result<SupportArtifact> {
+        // A bare file name, so requests can't reach outside the log directory
+        let invalid = name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']);
+        if invalid {
+            return Err(anyhow::anyhow!("Invalid log file name: {}", name));
+        }
+
+        let path = self.log_dir.join(name);
+        let mut file = tokio::fs::File::open(&path).await
+            .with_context(|| format!("No log file named {}", name))?;
+        let size = file.metadata().await?.len();
+
+        // Support wants the most recent lines, so keep the tail
+        let start = size.saturating_sub(self.config.max_artifact_bytes);
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).await?;
+
+        Ok(SupportArtifact::new(request.clone(), String::from_utf8_lossy(&data).into_owned(), start > 0))
+    }
+
+    async fn run_command(&self, request: &SupportRequest, name: &str) -> Result<SupportArtifact> {
+        let command = self.config.allowed_commands.iter()
+            .find(|command| command.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Command {} is not allowed", name))?;
+
+        let output = tokio::time::timeout(
+            Duration::from_secs(command.timeout_seconds),
+            tokio::process::Command::new(&command.program)
+                .args(&command.args)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("Command {} timed out", name))?
+        .with_context(|| format!("Failed to run {}", command.program))?;
+
+        let mut content = String::from_utf8_lossy(&output.stdout).into_owned();
+        if !output.stderr.is_empty() {
+            content.push_str("\n--- stderr ---\n");
+            content.push_str(&String::from_utf8_lossy(&output.stderr));
+        }
+        content.push_str(&format!("\n--- exit: {} ---\n", output.status));
+        Ok(self.limit(request.clone(), content))
+    }
+
+    fn limit(&self, request: SupportRequest, mut content: String) -> SupportArtifact {
+        let max = self.config.max_artifact_bytes as usize;
+        let truncated = content.len() > max;
+        if truncated {
+            let mut end = max;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+        }
+        SupportArtifact::new(request, content, truncated)
+    }
+}
+
+/// Replace the value of every key that looks like a credential
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                let secret = SECRET_KEYS.iter().any(|pattern| key.split('_').any(|part| part == *pattern));
+                if secret && value.is_string() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> SupportConfig {
+        SupportConfig { enabled: true, ..SupportConfig::default() }
+    }
+
+    #[test]
+    fn test_config_dump_redacts_credentials() {
+        let mut config = Config::default();
+        config.device_key = Some("device-secret".to_string());
+        config.auth_token = Some("token-secret".to_string());
+
+        let mut value = serde_json::to_value(&config).unwrap();
+        redact_secrets(&mut value);
+        let dump = value.to_string();
+        assert!(!dump.contains("device-secret"));
+        assert!(!dump.contains("token-secret"));
+        assert_eq!(value["device_key"], REDACTED);
+        // Non-secret settings survive
+        assert_eq!(value["server_url"], config.server_url);
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_consent_and_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::default();
+
+        let disabled = RemoteSupport::new(SupportConfig::default()).unwrap().with_log_dir(dir.path().to_path_buf());
+        assert!(disabled.fetch(&SupportRequest::ConfigDump, &config).await.is_err());
+
+        let support = RemoteSupport::new(enabled()).unwrap().with_log_dir(dir.path().to_path_buf());
+        let shell = SupportRequest::Command { name: "rm".to_string() };
+        assert!(support.fetch(&shell, &config).await.is_err());
+        let escape = SupportRequest::LogFile { name: "../config.toml".to_string() };
+        assert!(support.fetch(&escape, &config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_log_file_is_tailed_to_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(dir.path().join("client.log"), "old line\nnew line\n").await.unwrap();
+
+        let support = RemoteSupport::new(SupportConfig { max_artifact_bytes: 9, ..enabled() })
+            .unwrap()
+            .with_log_dir(dir.path().to_path_buf());
+        let artifact = support
+            .fetch(&SupportRequest::LogFile { name: "client.log".to_string() }, &Config::default())
+            .await
+            .unwrap();
+        assert_eq!(artifact.content, "new line\n");
+        assert!(artifact.truncated);
+    }
+}
@@ -6,15 +6,30 @@ use tokio::time::{Duration, Instant};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::diagnostics::{HealthStatus, RecordingPerformance};
+use crate::supervisor::{RestartPolicy, TaskSupervisor};
+
+/// Restart a monitoring loop that panicked, giving up if it keeps doing so
+const MONITOR_RESTART: RestartPolicy = RestartPolicy::OnFailure { max_restarts: 5, backoff: Duration::from_secs(1) };
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceStats {
     pub memory_usage: MemoryUsage,
     pub disk_usage: DiskUsage,
     pub process_stats: ProcessStats,
     pub cleanup_stats: CleanupStats,
+    /// Readings from the device's sensors and encoders
+    #[serde(default)]
+    pub sensors: SensorReadings,
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorReadings {
+    pub temperature_c: Option<f32>,
+    pub recording: Option<RecordingPerformance>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryUsage {
     pub total_kb: u64,
@@ -58,6 +73,7 @@ pub struct ResourceLimits {
     pub max_log_files_mb: u64,
     pub max_recording_age_days: u32,
     pub cleanup_interval_hours: u64,
+    pub max_temperature_c: f32,
 }
 
 impl Default for ResourceLimits {
@@ -69,10 +85,65 @@ impl Default for ResourceLimits {
             max_log_files_mb: 50,   // 50MB max log files
             max_recording_age_days: 30,  // Keep recordings for 30 days
             cleanup_interval_hours: 6,   // Cleanup every 6 hours
+            max_temperature_c: 70.0,  // SoC starts throttling itself past this
         }
     }
 }
 
+/// How much on-device inference the current load allows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InferenceBudget {
+    /// Analyse one sampled frame in every `skip + 1`
+    Run { skip: u32 },
+    Paused,
+}
+
+/// Read-only view of resource usage for background workers
+#[derive(Debug, Clone)]
+pub struct ResourceThrottle {
+    stats: Arc<RwLock<ResourceStats>>,
+    limits: ResourceLimits,
+}
+
+impl ResourceThrottle {
+    pub async fn inference_budget(&self) -> InferenceBudget {
+        inference_budget(&*self.stats.read().await, &self.limits)
+    }
+}
+
+/// Skip more frames as CPU climbs, the device heats up or the encoders fall
+/// behind, and stop entirely when the CPU is saturated, the process is over
+/// its memory limit, the device is too hot or recording is badly behind, so
+/// recording and streaming keep priority
+fn inference_budget(stats: &ResourceStats, limits: &ResourceLimits) -> InferenceBudget {
+    let cpu = stats.process_stats.cpu_usage_percent;
+    let memory_mb = stats.memory_usage.process_memory_kb / 1024;
+    let temperature = stats.sensors.temperature_c.unwrap_or(0.0);
+    let recording = stats.sensors.recording.as_ref().map(|r| &r.recording_status);
+
+    if cpu >= 90.0
+        || memory_mb >= limits.max_memory_mb
+        || temperature >= limits.max_temperature_c
+        || matches!(recording, Some(HealthStatus::Critical))
+    {
+        return InferenceBudget::Paused;
+    }
+
+    let mut skip = match cpu {
+        c if c < 50.0 => 0,
+        c if c < 65.0 => 1,
+        c if c < 80.0 => 3,
+        _ => 7,
+    };
+    if temperature >= limits.max_temperature_c - 10.0 {
+        skip = skip.max(3);
+    }
+    if matches!(recording, Some(HealthStatus::Warning)) {
+        skip = skip.max(7);
+    }
+    InferenceBudget::Run { skip }
+}
+
 pub struct ResourceManager {
     stats: Arc<RwLock<ResourceStats>>,
     limits: ResourceLimits,
@@ -140,6 +211,7 @@ impl ResourceManager {
                 last_cleanup: None,
                 cleanup_errors: 0,
             },
+            sensors: SensorReadings::default(),
             last_updated: chrono::Utc::now(),
         };
 
@@ -153,91 +225,108 @@ impl ResourceManager {
         }
     }
 
+    /// Start the stats, cleanup and memory pressure loops under the task
+    /// supervisor, so a panic in one doesn't stop it for good
     pub async fn start_monitoring(&self) -> Result<()> {
+        let supervisor = TaskSupervisor::global();
+
+        let stats = Arc::clone(&self.stats);
+        supervisor.spawn("resource_stats", MONITOR_RESTART, move || Self::monitor_stats(Arc::clone(&stats)));
+
         let stats = Arc::clone(&self.stats);
         let limits = self.limits.clone();
         let active_processes = Arc::clone(&self.active_processes);
         let temp_files = Arc::clone(&self.temp_files);
         let cleanup_tasks = Arc::clone(&self.cleanup_tasks);
+        supervisor.spawn("resource_cleanup", MONITOR_RESTART, move || Self::run_cleanup_loop(
+            Arc::clone(&stats),
+            limits.clone(),
+            Arc::clone(&active_processes),
+            Arc::clone(&temp_files),
+            Arc::clone(&cleanup_tasks),
+        ));
+
+        let stats = Arc::clone(&self.stats);
+        let limits = self.limits.clone();
+        supervisor.spawn("memory_pressure", MONITOR_RESTART, move || Self::monitor_memory_pressure(Arc::clone(&stats), limits.clone()));
+
+        Ok(())
+    }
 
-        // Start resource monitoring task with power-efficient intervals
-        let monitor_stats = Arc::clone(&stats);
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(60)); // Reduced frequency
+    /// Resource monitoring with power-efficient intervals
+    async fn monitor_stats(stats: Arc<RwLock<ResourceStats>>) -> Result<()> {
+        let mut interval = tokio::time::interval(Duration::from_secs(60)); // Reduced frequency
+        
+        loop {
+            interval.tick().await;
             
-            loop {
-                interval.tick().await;
-                
-                // Only update stats if system is not in low-power mode
-                if let Err(e) = Self::update_resource_stats(&monitor_stats).await {
-                    tracing::warn!("Failed to update resource stats: {}", e);
-                }
-                
-                // Yield to allow other tasks to run
-                tokio::task::yield_now().await;
+            // Only update stats if system is not in low-power mode
+            if let Err(e) = Self::update_resource_stats(&stats).await {
+                tracing::warn!("Failed to update resource stats: {}", e);
             }
-        });
+            
+            // Yield to allow other tasks to run
+            tokio::task::yield_now().await;
+        }
+    }
 
-        // Start cleanup task with power-efficient scheduling
-        let cleanup_stats = Arc::clone(&stats);
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(limits.cleanup_interval_hours * 3600));
+    /// Cleanup with power-efficient scheduling
+    async fn run_cleanup_loop(
+        stats: Arc<RwLock<ResourceStats>>,
+        limits: ResourceLimits,
+        active_processes: Arc<Mutex<HashMap<String, tokio::process::Child>>>,
+        temp_files: Arc<Mutex<Vec<PathBuf>>>,
+        cleanup_tasks: Arc<Mutex<Vec<CleanupTask>>>,
+    ) -> Result<()> {
+        let mut interval = tokio::time::interval(Duration::from_secs(limits.cleanup_interval_hours * 3600));
+        
+        loop {
+            interval.tick().await;
             
-            loop {
-                interval.tick().await;
-                
-                // Run cleanup during low-activity periods
-                if let Err(e) = Self::run_cleanup_tasks(
-                    &cleanup_stats,
-                    &limits,
-                    &active_processes,
-                    &temp_files,
-                    &cleanup_tasks,
-                ).await {
-                    tracing::error!("Cleanup task failed: {}", e);
-                }
-                
-                // Sleep longer between cleanup cycles to reduce CPU usage
-                tokio::time::sleep(Duration::from_millis(100)).await;
+            // Run cleanup during low-activity periods
+            if let Err(e) = Self::run_cleanup_tasks(
+                &stats,
+                &limits,
+                &active_processes,
+                &temp_files,
+                &cleanup_tasks,
+            ).await {
+                tracing::error!("Cleanup task failed: {}", e);
             }
-        });
+            
+            // Sleep longer between cleanup cycles to reduce CPU usage
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
 
-        // Start memory pressure monitoring with adaptive intervals
-        let memory_stats = Arc::clone(&stats);
-        let memory_limits = limits.clone();
-        tokio::spawn(async move {
-            let mut base_interval = Duration::from_secs(30); // Start with less frequent checks
-            let mut current_interval = base_interval;
-            let mut high_usage_count = 0u32;
+    /// Memory pressure monitoring with adaptive intervals
+    async fn monitor_memory_pressure(stats: Arc<RwLock<ResourceStats>>, limits: ResourceLimits) -> Result<()> {
+        let base_interval = Duration::from_secs(30); // Start with less frequent checks
+        let mut current_interval = base_interval;
+        
+        loop {
+            tokio::time::sleep(current_interval).await;
             
-            loop {
-                tokio::time::sleep(current_interval).await;
-                
-                match Self::check_memory_pressure(&memory_stats, &memory_limits).await {
-                    Ok(is_high_usage) => {
-                        if is_high_usage {
-                            high_usage_count += 1;
-                            // Increase monitoring frequency when under pressure
-                            current_interval = Duration::from_secs(10);
-                        } else {
-                            high_usage_count = 0;
-                            // Reduce monitoring frequency when stable
-                            current_interval = base_interval;
-                        }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Memory pressure check failed: {}", e);
-                        // Back off on errors to save power
-                        current_interval = Duration::from_secs(60);
-                    }
+            match Self::check_memory_pressure(&stats, &limits).await {
+                Ok(is_high_usage) => {
+                    current_interval = if is_high_usage {
+                        // Increase monitoring frequency when under pressure
+                        Duration::from_secs(10)
+                    } else {
+                        // Reduce monitoring frequency when stable
+                        base_interval
+                    };
+                }
+                Err(e) => {
+                    tracing::warn!("Memory pressure check failed: {}", e);
+                    // Back off on errors to save power
+                    current_interval = Duration::from_secs(60);
                 }
-                
-                // Yield CPU to other tasks
-                tokio::task::yield_now().await;
             }
-        });
-
-        Ok(())
+            
+            // Yield CPU to other tasks
+            tokio::task::yield_now().await;
+        }
     }
 
     pub async fn register_temp_file(&self, path: PathBuf) -> Result<()> {
@@ -619,11 +708,16 @@ impl ResourceManager {
         let mut files_cleaned = 0u64;
         let mut space_freed = 0f64;
         let cutoff_date = chrono::Utc::now() - chrono::Duration::days(30);
+        let holds = crate::legal_hold::LegalHolds::load().await;
+        // Age alone doesn't make a recording safe to delete; the server has to have it
+        let confirmed = crate::storage_manager::confirmed_upload_paths().await?;
 
         let mut entries = tokio::fs::read_dir(recordings_dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let metadata = entry.metadata().await?;
-            if metadata.is_file() {
+            if metadata.is_file() && !holds.is_held(&entry.path())
+                && crate::storage_manager::is_confirmed(&confirmed, &entry.path())
+            {
                 if let Ok(created) = metadata.created() {
                     let created_datetime = chrono::DateTime::<chrono::Utc>::from(created);
                     if created_datetime < cutoff_date {
@@ -651,6 +745,18 @@ impl ResourceManager {
         Ok((0, 0.0))
     }
 
+    pub fn throttle(&self) -> ResourceThrottle {
+        ResourceThrottle {
+            stats: Arc::clone(&self.stats),
+            limits: self.limits.clone(),
+        }
+    }
+
+    /// Latest temperature and encoder performance, for the throttle
+    pub async fn report_sensors(&self, temperature_c: Option<f32>, recording: Option<RecordingPerformance>) {
+        self.stats.write().await.sensors = SensorReadings { temperature_c, recording };
+    }
+
     pub async fn get_resource_stats(&self) -> ResourceStats {
         self.stats.read().await.clone()
     }
@@ -689,4 +795,39 @@ impl Drop for ResourceManager {
     fn drop(&mut self) {
         tracing::debug!("ResourceManager dropped for device: {}", self.device_id);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn performance(recording_status: HealthStatus) -> RecordingPerformance {
+        RecordingPerformance {
+            current_fps: Some(20.0),
+            target_fps: 30,
+            dropped_frames: 40,
+            encoding_latency_ms: None,
+            disk_write_speed_mbps: None,
+            recording_status,
+        }
+    }
+
+    #[test]
+    fn test_sensors_throttle_inference() {
+        let limits = ResourceLimits::default();
+        let mut stats = ResourceManager::new("test".to_string(), None).stats.try_read().unwrap().clone();
+        stats.process_stats.cpu_usage_percent = 10.0;
+        assert_eq!(inference_budget(&stats, &limits), InferenceBudget::Run { skip: 0 });
+
+        stats.sensors.temperature_c = Some(65.0);
+        assert_eq!(inference_budget(&stats, &limits), InferenceBudget::Run { skip: 3 });
+        stats.sensors.temperature_c = Some(75.0);
+        assert_eq!(inference_budget(&stats, &limits), InferenceBudget::Paused);
+
+        stats.sensors.temperature_c = Some(40.0);
+        stats.sensors.recording = Some(performance(HealthStatus::Warning));
+        assert_eq!(inference_budget(&stats, &limits), InferenceBudget::Run { skip: 7 });
+        stats.sensors.recording = Some(performance(HealthStatus::Critical));
+        assert_eq!(inference_budget(&stats, &limits), InferenceBudget::Paused);
+    }
+}
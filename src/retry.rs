@@ -0,0 +1,200 @@
+use anyhow::Result;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::config::NetworkConfig;
+
+/// Longest a server may ask us to wait via Retry-After before we give up
+/// on the request instead
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// How failed API requests are retried: capped exponential backoff with
+/// full jitter, so a fleet coming back online doesn't retry in lockstep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(network: &NetworkConfig) -> Self {
+        Self {
+            max_retries: network.retry_attempts,
+            base_delay: Duration::from_millis(network.retry_base_delay_ms),
+            max_delay: Duration::from_millis(network.retry_max_delay_ms),
+        }
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Upper bound of the delay before retry number `attempt` (from 1)
+    pub fn ceiling(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1).min(16));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// A random delay in `[0, ceiling(attempt)]`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self.ceiling(attempt).as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling))
+    }
+}
+
+/// Statuses worth retrying: timeouts, rate limiting and transient server
+/// errors. Anything else (bad request, auth, not found...) fails immediately.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 408 | 425 | 429 | 500 | 502 | 503 | 504)
+}
+
+/// The delay a 429/503 response asks for, as delta-seconds or an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+static REQUESTS: AtomicU64 = AtomicU64::new(0);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+static RETRY_AFTER_HONORED: AtomicU64 = AtomicU64::new(0);
+static NON_RETRYABLE: AtomicU64 = AtomicU64::new(0);
+static EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide retry counters, reported in diagnostics
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryStats {
+    pub requests: u64,
+    pub retries: u64,
+    /// Retries whose delay came from a Retry-After header
+    pub retry_after_honored: u64,
+    /// Requests that failed with a status not worth retrying
+    pub non_retryable_failures: u64,
+    /// Requests that still failed after the last retry
+    pub exhausted: u64,
+}
+
+pub fn stats() -> RetryStats {
+    RetryStats {
+        requests: REQUESTS.load(Ordering::Relaxed),
+        retries: RETRIES.load(Ordering::Relaxed),
+        retry_after_honored: RETRY_AFTER_HONORED.load(Ordering::Relaxed),
+        non_retryable_failures: NON_RETRYABLE.load(Ordering::Relaxed),
+        exhausted: EXHAUSTED.load(Ordering::Relaxed),
+    }
+}
+
+/// Send a request built by `make_request`, retrying network errors and
+/// retryable statuses per `policy`. The last response is returned as-is, so
+/// callers still see (and report) a final error status.
+pub async fn send_with_retry<F, Fut>(policy: &RetryPolicy, make_request: F) -> Result<Response>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Response>>,
+{
+    REQUESTS.fetch_add(1, Ordering::Relaxed);
+    let mut attempt = 0;
+
+    loop {
+        let result = match crate::simulation::faults::request_failure().await {
+            Some(e) => Err(e),
+            None => make_request().await,
+        };
+
+        let (delay, reason) = match result {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) if !is_retryable_status(response.status()) => {
+                NON_RETRYABLE.fetch_add(1, Ordering::Relaxed);
+                return Ok(response);
+            }
+            Ok(response) => {
+                attempt += 1;
+                if attempt > policy.max_retries {
+                    EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+                    return Ok(response);
+                }
+                match retry_after(response.headers()) {
+                    Some(wait) if wait > MAX_RETRY_AFTER => {
+                        EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+                        return Ok(response);
+                    }
+                    Some(wait) => {
+                        RETRY_AFTER_HONORED.fetch_add(1, Ordering::Relaxed);
+                        (wait, format!("HTTP {} (Retry-After {:?})", response.status(), wait))
+                    }
+                    None => (policy.backoff(attempt), format!("HTTP {}", response.status())),
+                }
+            }
+            Err(e) => {
+                attempt += 1;
+                if attempt > policy.max_retries {
+                    EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+                    return Err(e);
+                }
+                (policy.backoff(attempt), e.to_string())
+            }
+        };
+
+        RETRIES.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!("Request failed, retrying {}/{} in {:?}: {}", attempt, policy.max_retries, delay, reason);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_up_to_cap() {
+        let policy = policy();
+        assert_eq!(policy.ceiling(1), Duration::from_millis(500));
+        assert_eq!(policy.ceiling(3), Duration::from_secs(2));
+        assert_eq!(policy.ceiling(10), Duration::from_secs(10));
+
+        for attempt in 1..8 {
+            assert!(policy.backoff(attempt) <= policy.ceiling(attempt));
+        }
+    }
+
+    #[test]
+    fn test_only_transient_statuses_are_retried() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::CONFLICT));
+    }
+
+    #[test]
+    fn test_retry_after_parses_seconds_and_dates() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestCheck {
+    /// A frame can be grabbed from the camera
+    Camera,
+    /// The microphone hears the speaker's test tone
+    Microphone,
+    Speaker,
+    /// The operator confirms the LEDs lit up
+    Leds,
+    /// The operator presses the Record button when asked
+    Buttons,
+    Gps,
+    /// The backend answers an authenticated request
+    Api,
+}
+
+impl SelfTestCheck {
+    pub const ALL: [SelfTestCheck; 7] = [
+        SelfTestCheck::Camera,
+        SelfTestCheck::Microphone,
+        SelfTestCheck::Speaker,
+        SelfTestCheck::Leds,
+        SelfTestCheck::Buttons,
+        SelfTestCheck::Gps,
+        SelfTestCheck::Api,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SelfTestCheck::Camera => "camera",
+            SelfTestCheck::Microphone => "microphone",
+            SelfTestCheck::Speaker => "speaker",
+            SelfTestCheck::Leds => "leds",
+            SelfTestCheck::Buttons => "buttons",
+            SelfTestCheck::Gps => "gps",
+            SelfTestCheck::Api => "api",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestConfig {
+    pub enabled: bool,
+    /// Local hour the nightly test runs at; None to only run on demand
+    pub nightly_hour: Option<u32>,
+    /// Also run when the device is put on its charger
+    pub on_dock: bool,
+    /// How long the operator has to press Record during the LED and button check
+    pub operator_timeout_seconds: u64,
+    /// How long to wait for a GPS fix
+    pub gps_timeout_seconds: u64,
+    /// Checks that mark the device not ready for duty when they fail
+    pub critical: Vec<SelfTestCheck>,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            nightly_hour: Some(3),
+            on_dock: true,
+            operator_timeout_seconds: 30,
+            gps_timeout_seconds: 60,
+            critical: vec![SelfTestCheck::Camera, SelfTestCheck::Microphone, SelfTestCheck::Speaker],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestTrigger {
+    Nightly,
+    Docked,
+    Manual,
+    Remote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Couldn't be judged, e.g. nobody was there to press the button
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub check: SelfTestCheck,
+    pub status: CheckStatus,
+    pub detail: Option<String>,
+}
+
+impl CheckResult {
+    pub fn passed(check: SelfTestCheck, detail: Option<String>) -> Self {
+        Self { check, status: CheckStatus::Passed, detail }
+    }
+
+    pub fn failed(check: SelfTestCheck, detail: impl Into<String>) -> Self {
+        Self { check, status: CheckStatus::Failed, detail: Some(detail.into()) }
+    }
+
+    pub fn skipped(check: SelfTestCheck, detail: impl Into<String>) -> Self {
+        Self { check, status: CheckStatus::Skipped, detail: Some(detail.into()) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub trigger: SelfTestTrigger,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub results: Vec<CheckResult>,
+    /// Critical checks that failed; the device isn't ready for duty unless empty
+    pub critical_failures: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    pub fn new(trigger: SelfTestTrigger, started_at: DateTime<Utc>, results: Vec<CheckResult>, critical: &[SelfTestCheck]) -> Self {
+        let critical_failures = results.iter()
+            .filter(|result| result.status == CheckStatus::Failed && critical.contains(&result.check))
+            .map(|result| result.check)
+            .collect();
+        Self { trigger, started_at, finished_at: Utc::now(), results, critical_failures }
+    }
+
+    /// No check failed, critical or not
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.status != CheckStatus::Failed)
+    }
+
+    pub fn ready_for_duty(&self) -> bool {
+        self.critical_failures.is_empty()
+    }
+
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("self_test.json")
+    }
+
+    /// The last report, so a failed test still flags the device after a restart
+    pub async fn load() -> Option<Self> {
+        let content = tokio::fs::read_to_string(Self::default_path()).await.ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let path = Self::default_path();
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+/// The next time the clock reads `hour` o'clock, strictly after `now`
+pub fn next_nightly_run<Tz: TimeZone>(now: DateTime<Tz>, hour: u32) -> DateTime<Tz> {
+    let at = NaiveTime::from_hms_opt(hour.min(23), 0, 0).unwrap_or_default();
+    let today = now.date_naive().and_time(at);
+    let next = if today > now.naive_local() { today } else { today + Duration::days(1) };
+    // A DST gap can skip the hour entirely; an hour later is close enough
+    now.timezone().from_local_datetime(&next).earliest()
+        .unwrap_or_else(|| now.clone() + Duration::hours(1))
+}
+
+/// How long until the next nightly run in local time
+pub fn until_nightly_run(hour: u32) -> std::time::Duration {
+    let now = Local::now();
+    (next_nightly_run(now, hour) - now).to_std().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_critical_failures_block_duty() {
+        let results = vec![
+            CheckResult::passed(SelfTestCheck::Camera, None),
+            CheckResult::failed(SelfTestCheck::Gps, "no fix in 60s"),
+            CheckResult::skipped(SelfTestCheck::Buttons, "no operator response"),
+        ];
+        let report = SelfTestReport::new(SelfTestTrigger::Nightly, Utc::now(), results.clone(), &[SelfTestCheck::Camera]);
+        assert!(!report.passed());
+        assert!(report.ready_for_duty());
+
+        let report = SelfTestReport::new(SelfTestTrigger::Docked, Utc::now(), results, &[SelfTestCheck::Gps]);
+        assert_eq!(report.critical_failures, vec![SelfTestCheck::Gps]);
+        assert!(!report.ready_for_duty());
+    }
+
+    #[test]
+    fn test_next_nightly_run() {
+        let evening = Utc.with_ymd_and_hms(2026, 5, 1, 22, 30, 0).unwrap();
+        assert_eq!(next_nightly_run(evening, 3), Utc.with_ymd_and_hms(2026, 5, 2, 3, 0, 0).unwrap());
+        let early = Utc.with_ymd_and_hms(2026, 5, 1, 1, 0, 0).unwrap();
+        assert_eq!(next_nightly_run(early, 3), Utc.with_ymd_and_hms(2026, 5, 1, 3, 0, 0).unwrap());
+    }
+}
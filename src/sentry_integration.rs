@@ -3,6 +3,8 @@ use sentry::ClientOptions;
 use std::env;
 use tracing::{info, warn, error};
 
+use crate::error_handling::StructuredError;
+
 /// Sentry configuration for the Rust body camera client
 #[derive(Debug, Clone)]
 pub struct SentryConfig {
@@ -244,6 +246,16 @@ pub fn capture_error_with_context(
         if let Some(context) = context {
             scope.set_context("error_context", sentry::protocol::Context::Other(context));
         }
+
+        // Group structured errors by kind rather than by message text
+        if let Some(structured) = crate::error_handling::structured(error) {
+            let fingerprint = structured.fingerprint();
+            let parts: Vec<&str> = fingerprint.iter().map(String::as_str).collect();
+            scope.set_fingerprint(Some(parts.as_slice()));
+            scope.set_tag("error_category", structured.category());
+            scope.set_tag("error_kind", structured.kind());
+            scope.set_level(Some(structured.sentry_level()));
+        }
         
         // Add error chain information
         let mut error_chain = Vec::new();
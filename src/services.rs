@@ -0,0 +1,288 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+use crate::cancellation::Canceller;
+use crate::config::Config;
+use crate::convex_integration::ConvexIntegration;
+use crate::device::BodycamDevice;
+use crate::event_bus::{BusEvent, NetworkEvent, Subscription, Topic};
+use crate::realtime::{RealtimeManager, ServerCommand};
+use crate::release_manager::{ReleaseManager, UpdateChannel};
+use crate::plugins::{run_plugin, PluginRegistry};
+use crate::status_report;
+pub use crate::supervisor::RestartPolicy;
+use crate::supervisor::TaskSupervisor;
+use crate::webhooks::WebhookEmitter;
+
+const CONFIG_SYNC_INTERVAL: Duration = Duration::from_secs(300);
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Shared state handed to every service run
+#[derive(Clone)]
+pub struct ServiceContext {
+    pub device: BodycamDevice,
+    pub config: Config,
+    pub config_dir: PathBuf,
+    pub convex: Option<Arc<ConvexIntegration>>,
+    commands: Arc<Mutex<mpsc::UnboundedReceiver<ServerCommand>>>,
+}
+
+type ServiceFn = Arc<dyn Fn(ServiceContext) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+struct Service {
+    name: String,
+    policy: RestartPolicy,
+    run: ServiceFn,
+}
+
+/// Owns the device and keeps background services running for headless
+/// operation. Each service is restarted according to its own policy, so a
+/// failing upload loop doesn't take status reporting down with it.
+pub struct ServiceRunner {
+    context: ServiceContext,
+    command_tx: mpsc::UnboundedSender<ServerCommand>,
+    services: Vec<Service>,
+    /// Cancels uploads and diagnostics in flight at shutdown, so the device
+    /// doesn't wait on them
+    canceller: Canceller,
+}
+
+impl ServiceRunner {
+    pub fn new(device: BodycamDevice, config: Config, config_dir: PathBuf) -> Self {
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let canceller = device.canceller();
+        Self {
+            context: ServiceContext {
+                device,
+                config,
+                config_dir,
+                convex: None,
+                commands: Arc::new(Mutex::new(command_rx)),
+            },
+            command_tx,
+            services: Vec::new(),
+            canceller,
+        }
+    }
+
+    /// Connect to the Convex backend for uploads and config sync
+    pub async fn with_convex(mut self) -> Result<Self> {
+        if self.context.config.convex_url.is_some() {
+            let config = Arc::new(RwLock::new(self.context.config.clone()));
+            let events = self.context.device.event_bus();
+            self.context.convex = Some(Arc::new(ConvexIntegration::with_event_bus(config, events).await?));
+        }
+        Ok(self)
+    }
+
+    pub fn with_service<F>(mut self, name: &str, policy: RestartPolicy, run: F) -> Self
+    where
+        F: Fn(ServiceContext) -> BoxFuture<'static, Result<()>> + Send + Sync + 'static,
+    {
+        self.services.push(Service { name: name.to_string(), policy, run: Arc::new(run) });
+        self
+    }
+
+    /// Run each plugin as its own service, restarted if it fails to start
+    pub fn with_plugins(mut self, registry: PluginRegistry) -> Self {
+        for plugin in registry.into_plugins() {
+            let name = match plugin.try_lock() {
+                Ok(plugin) => format!("plugin:{}", plugin.name()),
+                Err(_) => "plugin".to_string(),
+            };
+            self = self.with_service(&name, RestartPolicy::OnFailure { max_restarts: 5, backoff: Duration::from_secs(5) },
+                move |ctx| Box::pin(run_plugin(plugin.clone(), ctx.device)));
+        }
+        self
+    }
+
+    /// Status reporting, journal replay, uploads, config sync, update checks
+    /// and remote commands
+    pub fn with_default_services(self) -> Self {
+        self.with_service("status", RestartPolicy::Always { backoff: Duration::from_secs(5) },
+                |ctx| Box::pin(report_status(ctx)))
+            .with_service("journal", RestartPolicy::Always { backoff: Duration::from_secs(5) },
+                |ctx| Box::pin(replay_journal(ctx)))
+            .with_service("uploads", RestartPolicy::OnFailure { max_restarts: 10, backoff: Duration::from_secs(5) },
+                |ctx| Box::pin(run_uploads(ctx)))
+            .with_service("resume_uploads", RestartPolicy::Always { backoff: Duration::from_secs(30) },
+                |ctx| Box::pin(resume_uploads(ctx)))
+            .with_service("webhooks", RestartPolicy::Always { backoff: Duration::from_secs(5) },
+                |ctx| Box::pin(emit_webhooks(ctx)))
+            .with_service("config_sync", RestartPolicy::Always { backoff: Duration::from_secs(30) },
+                |ctx| Box::pin(sync_config(ctx)))
+            .with_service("updates", RestartPolicy::Always { backoff: Duration::from_secs(60) },
+                |ctx| Box::pin(check_updates(ctx)))
+            .with_service("commands", RestartPolicy::Always { backoff: Duration::from_secs(1) },
+                |ctx| Box::pin(handle_commands(ctx)))
+    }
+
+    /// Feed remote commands to the command service
+    pub fn command_sender(&self) -> mpsc::UnboundedSender<ServerCommand> {
+        self.command_tx.clone()
+    }
+
+    pub fn device(&self) -> BodycamDevice {
+        self.context.device.clone()
+    }
+
+    /// Run every service until `shutdown` resolves, then stop them and shut
+    /// the device down
+    pub async fn run_until(self, shutdown: impl std::future::Future<Output = ()>) -> Result<()> {
+        let supervisor = TaskSupervisor::global();
+        let handles: Vec<JoinHandle<()>> = self.services.into_iter()
+            .map(|service| {
+                let context = self.context.clone();
+                let run = service.run;
+                supervisor.spawn(&service.name, service.policy, move || run(context.clone()))
+            })
+            .collect();
+        tracing::info!("Started {} background services", handles.len());
+
+        shutdown.await;
+        tracing::info!("Stopping background services");
+        self.canceller.shutdown();
+        for handle in &handles {
+            handle.abort();
+        }
+        for handle in handles {
+            let _ = handle.await;
+        }
+
+        self.context.device.shutdown().await
+    }
+}
+
+/// Report status on an interval that follows what the device is doing, and
+/// straight away when something significant happens
+async fn report_status(ctx: ServiceContext) -> Result<()> {
+    let mut events = ctx.device.event_bus().subscribe_to(status_report::PUSH_TOPICS);
+    let mut debounce = status_report::PushDebounce::default();
+    loop {
+        let status = ctx.device.report_status().await?;
+        let next = status_report::report_interval(&ctx.config.monitoring, &status);
+
+        tokio::select! {
+            _ = tokio::time::sleep(next) => {}
+            event = next_push_event(&mut events, &mut debounce) => {
+                tracing::debug!("Pushing status early after {:?}", event);
+                tokio::time::sleep(status_report::PUSH_SETTLE).await;
+            }
+        }
+    }
+}
+
+async fn next_push_event(events: &mut Subscription, debounce: &mut status_report::PushDebounce) -> BusEvent {
+    loop {
+        match events.recv().await {
+            Some(event) if debounce.should_push(&event, std::time::Instant::now()) => return event,
+            Some(_) => {}
+            // No publishers left; fall back to the periodic schedule
+            None => return std::future::pending().await,
+        }
+    }
+}
+
+/// Deliver calls journaled while offline as soon as connectivity returns,
+/// rather than waiting for the next status report
+async fn replay_journal(ctx: ServiceContext) -> Result<()> {
+    let mut events = ctx.device.event_bus().subscribe_to(&[Topic::Network]);
+    while let Some(event) = events.recv().await {
+        if let BusEvent::Network(NetworkEvent::Online) = event {
+            let report = ctx.device.flush_journal().await?;
+            if report.delivered > 0 {
+                tracing::info!("Replayed {} journaled backend calls, {} remaining", report.delivered, report.remaining);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finish segment uploads interrupted by a restart, then again each time
+/// connectivity returns
+async fn resume_uploads(ctx: ServiceContext) -> Result<()> {
+    let backend = crate::backend::create_backend(&ctx.config)?;
+    let bus = ctx.device.event_bus();
+    let mut events = bus.subscribe_to(&[Topic::Network]);
+    loop {
+        let resumed = backend.resume_uploads(&bus).await?;
+        if resumed > 0 {
+            tracing::info!("Completed {} interrupted uploads", resumed);
+        }
+        match crate::streaming::retry_gap_uploads(ctx.config.clone()).await {
+            Ok(0) => {}
+            Ok(waiting) => tracing::warn!("{} stream gap uploads still waiting", waiting),
+            Err(e) => tracing::warn!("Failed to retry stream gap uploads: {:#}", e),
+        }
+
+        loop {
+            match events.recv().await {
+                Some(BusEvent::Network(NetworkEvent::Online)) => break,
+                Some(_) => {}
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+async fn emit_webhooks(ctx: ServiceContext) -> Result<()> {
+    let Some(emitter) = WebhookEmitter::from_config(&ctx.config)? else {
+        // Nothing configured; stay idle rather than restart
+        return std::future::pending().await;
+    };
+    let events = ctx.device.event_bus().subscribe();
+    emitter.run(events).await
+}
+
+async fn run_uploads(ctx: ServiceContext) -> Result<()> {
+    let Some(convex) = ctx.convex else {
+        // Nothing to upload to; stay idle rather than restart
+        return std::future::pending().await;
+    };
+    convex.upload_manager.run().await
+}
+
+async fn sync_config(ctx: ServiceContext) -> Result<()> {
+    let Some(convex) = ctx.convex else {
+        return std::future::pending().await;
+    };
+    let mut interval = tokio::time::interval(CONFIG_SYNC_INTERVAL);
+    loop {
+        interval.tick().await;
+        convex.config_sync.sync_config_from_server().await?;
+        let button_actions = convex.config_sync.button_actions().await;
+        ctx.device.apply_button_actions(button_actions, "config_sync").await?;
+    }
+}
+
+async fn check_updates(ctx: ServiceContext) -> Result<()> {
+    let release_manager = ReleaseManager::new(
+        &ctx.config_dir,
+        "https://updates.patrolsight.com",
+        env!("CARGO_PKG_VERSION"),
+        UpdateChannel::Stable,
+    )?;
+
+    let mut interval = tokio::time::interval(UPDATE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Some(release) = release_manager.check_for_updates().await? {
+            tracing::info!("Update available: {} -> {}", release_manager.get_current_version(), release.version);
+        }
+    }
+}
+
+async fn handle_commands(ctx: ServiceContext) -> Result<()> {
+    // Held for the life of the run, so a restarted service picks the
+    // receiver back up
+    let mut commands = ctx.commands.lock().await;
+    while let Some(command) = commands.recv().await {
+        RealtimeManager::execute_command(&ctx.device, command).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::config::Config;
+
+/// What values a setting accepts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Choice(&'static [&'static str]),
+}
+
+/// A config field editable from the device's settings screen
+#[derive(Debug, Clone, Copy)]
+pub struct SettingDef {
+    /// Dotted path into `Config`, e.g. `recording.fps`
+    pub key: &'static str,
+    /// Label, looked up with `i18n::Localizer`
+    pub label: &'static str,
+    pub kind: SettingKind,
+    /// Only takes effect after the client restarts
+    pub restart_required: bool,
+}
+
+pub const SETTINGS: &[SettingDef] = &[
+    SettingDef {
+        key: "recording.resolution",
+        label: "settings.resolution",
+        kind: SettingKind::Choice(&["640x480", "1280x720", "1920x1080", "3840x2160"]),
+        restart_required: false,
+    },
+    SettingDef { key: "recording.fps", label: "settings.fps", kind: SettingKind::Integer { min: 1, max: 60 }, restart_required: false },
+    SettingDef {
+        key: "recording.segment_duration",
+        label: "settings.segment_duration",
+        kind: SettingKind::Integer { min: 10, max: 3600 },
+        restart_required: false,
+    },
+    SettingDef {
+        key: "recording.pre_incident_buffer_seconds",
+        label: "settings.pre_incident_buffer",
+        kind: SettingKind::Integer { min: 0, max: 300 },
+        restart_required: true,
+    },
+    SettingDef { key: "recording.encryption", label: "settings.encryption", kind: SettingKind::Bool, restart_required: false },
+    SettingDef { key: "audio.enabled", label: "settings.audio_enabled", kind: SettingKind::Bool, restart_required: false },
+    SettingDef { key: "storage.upload_on_wifi_only", label: "settings.upload_wifi_only", kind: SettingKind::Bool, restart_required: false },
+    SettingDef {
+        key: "storage.upload_on_charging_only",
+        label: "settings.upload_charging_only",
+        kind: SettingKind::Bool,
+        restart_required: false,
+    },
+    SettingDef { key: "storage.keep_local_copy", label: "settings.keep_local_copy", kind: SettingKind::Bool, restart_required: false },
+    SettingDef {
+        key: "storage.auto_cleanup_days",
+        label: "settings.auto_cleanup_days",
+        kind: SettingKind::Integer { min: 1, max: 365 },
+        restart_required: false,
+    },
+    SettingDef { key: "power_management.low_power_mode", label: "settings.low_power_mode", kind: SettingKind::Bool, restart_required: false },
+    SettingDef { key: "i18n.voice_prompts", label: "settings.voice_prompts", kind: SettingKind::Bool, restart_required: false },
+    SettingDef { key: "theme.large_touch_targets", label: "settings.large_touch_targets", kind: SettingKind::Bool, restart_required: true },
+];
+
+pub fn find(key: &str) -> Option<&'static SettingDef> {
+    SETTINGS.iter().find(|def| def.key == key)
+}
+
+/// Whether the backend has taken `key` out of the officer's hands
+pub fn is_locked(config: &Config, key: &str) -> bool {
+    config.remote_config.locked_settings.iter().any(|locked| locked == key)
+}
+
+fn pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
+
+/// The current value of `key` in `config`
+pub fn get(config: &Config, key: &str) -> Result<Value> {
+    let value = serde_json::to_value(config)?;
+    value.pointer(&pointer(key))
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Unknown setting {}", key))
+}
+
+/// Check `value` against the setting's kind; returns it normalized
+pub fn validate(def: &SettingDef, value: &Value) -> Result<Value> {
+    match def.kind {
+        SettingKind::Bool => value.as_bool()
+            .map(Value::Bool)
+            .ok_or_else(|| anyhow::anyhow!("{} must be on or off", def.key)),
+        SettingKind::Integer { min, max } => {
+            let number = value.as_i64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .ok_or_else(|| anyhow::anyhow!("{} must be a whole number", def.key))?;
+            if number < min || number > max {
+                return Err(anyhow::anyhow!("{} must be between {} and {}", def.key, min, max));
+            }
+            Ok(Value::from(number))
+        }
+        SettingKind::Choice(options) => value.as_str()
+            .filter(|choice| options.contains(choice))
+            .map(|choice| Value::String(choice.to_string()))
+            .ok_or_else(|| anyhow::anyhow!("{} must be one of {}", def.key, options.join(", "))),
+    }
+}
+
+/// `config` with `changes` applied. Every change is validated and checked
+/// against the backend's locks first, so nothing is applied if one fails.
+pub fn apply(config: &Config, changes: &BTreeMap<String, Value>) -> Result<Config> {
+    let mut value = serde_json::to_value(config)?;
+    for (key, new_value) in changes {
+        let def = find(key).ok_or_else(|| anyhow::anyhow!("{} can't be changed on the device", key))?;
+        if is_locked(config, key) {
+            return Err(anyhow::anyhow!("{} is locked by site policy", key));
+        }
+        let slot = value.pointer_mut(&pointer(key))
+            .ok_or_else(|| anyhow::anyhow!("Unknown setting {}", key))?;
+        *slot = validate(def, new_value)?;
+    }
+    serde_json::from_value(value).context("Settings produced an invalid configuration")
+}
+
+/// Edits made on the settings screen that haven't been applied yet
+#[derive(Debug, Clone, Default)]
+pub struct SettingsDraft {
+    changes: BTreeMap<String, Value>,
+    errors: BTreeMap<String, String>,
+}
+
+impl SettingsDraft {
+    /// Record an edit; an invalid or locked one is kept as an error for the
+    /// screen to show instead
+    pub fn edit(&mut self, config: &Config, key: &str, value: Value) -> Result<()> {
+        let result = match find(key) {
+            None => Err(anyhow::anyhow!("Unknown setting {}", key)),
+            Some(_) if is_locked(config, key) => Err(anyhow::anyhow!("{} is locked by site policy", key)),
+            Some(def) => validate(def, &value),
+        };
+
+        match result {
+            Ok(value) => {
+                self.errors.remove(key);
+                if get(config, key).ok().as_ref() == Some(&value) {
+                    self.changes.remove(key);
+                } else {
+                    self.changes.insert(key.to_string(), value);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.changes.remove(key);
+                self.errors.insert(key.to_string(), e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn changes(&self) -> &BTreeMap<String, Value> {
+        &self.changes
+    }
+
+    pub fn error(&self, key: &str) -> Option<&str> {
+        self.errors.get(key).map(String::as_str)
+    }
+
+    pub fn is_modified(&self, key: &str) -> bool {
+        self.changes.contains_key(key)
+    }
+
+    /// The value to show for `key`: the pending edit, or what's in `config`
+    pub fn value(&self, config: &Config, key: &str) -> Option<Value> {
+        self.changes.get(key).cloned().or_else(|| get(config, key).ok())
+    }
+
+    pub fn restart_required(&self) -> bool {
+        self.changes.keys().any(|key| find(key).is_some_and(|def| def.restart_required))
+    }
+
+    pub fn revert(&mut self) {
+        self.changes.clear();
+        self.errors.clear();
+    }
+
+    /// The config with the pending edits applied; the draft is cleared on success
+    pub fn apply(&mut self, config: &Config) -> Result<Config> {
+        let updated = apply(config, &self.changes)?;
+        self.revert();
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_setting_exists_in_config() {
+        let config = Config::default();
+        for def in SETTINGS {
+            let value = get(&config, def.key).unwrap();
+            assert!(validate(def, &value).is_ok(), "default for {} is invalid", def.key);
+        }
+    }
+
+    #[test]
+    fn test_draft_validates_applies_and_reverts() {
+        let mut config = Config::default();
+        config.remote_config.locked_settings = vec!["storage.keep_local_copy".to_string()];
+        let mut draft = SettingsDraft::default();
+
+        draft.edit(&config, "recording.fps", Value::from("15")).unwrap();
+        draft.edit(&config, "recording.encryption", Value::Bool(false)).unwrap();
+        assert!(draft.edit(&config, "recording.fps", Value::from(240)).is_err());
+        assert!(draft.error("recording.fps").is_some());
+        assert!(!draft.is_modified("recording.fps"));
+        assert!(draft.edit(&config, "storage.keep_local_copy", Value::Bool(true)).is_err());
+        assert!(draft.edit(&config, "device_key", Value::from("x")).is_err());
+
+        draft.edit(&config, "recording.fps", Value::from(15)).unwrap();
+        assert_eq!(draft.value(&config, "recording.fps"), Some(Value::from(15)));
+        let updated = draft.apply(&config).unwrap();
+        assert_eq!(updated.recording.fps, 15);
+        assert!(!updated.recording.encryption);
+        assert!(draft.changes().is_empty());
+
+        // Setting a value back to what's saved isn't a pending change
+        draft.edit(&updated, "recording.fps", Value::from(15)).unwrap();
+        assert!(draft.changes().is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+
+/// How long an injected API timeout hangs before failing
+const INJECTED_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Faults that can be switched on from the REPL or a scenario to exercise
+/// recovery paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Fault {
+    /// API requests fail with HTTP 500
+    ApiError,
+    /// API requests hang, then time out
+    ApiTimeout,
+    /// Storage reports no free space and new encoders refuse to start
+    DiskFull,
+    /// The next supervision check kills a running encoder; clears itself
+    FfmpegCrash,
+    /// No GPS fix is available
+    GpsLoss,
+    /// The wall clock is shifted by the configured offset
+    ClockJump,
+}
+
+impl Fault {
+    pub const ALL: [Fault; 6] = [
+        Fault::ApiError,
+        Fault::ApiTimeout,
+        Fault::DiskFull,
+        Fault::FfmpegCrash,
+        Fault::GpsLoss,
+        Fault::ClockJump,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Fault::ApiError => "api-error",
+            Fault::ApiTimeout => "api-timeout",
+            Fault::DiskFull => "disk-full",
+            Fault::FfmpegCrash => "ffmpeg-crash",
+            Fault::GpsLoss => "gps-loss",
+            Fault::ClockJump => "clock-jump",
+        }
+    }
+
+    fn bit(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+
+impl std::str::FromStr for Fault {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Fault::ALL.iter()
+            .find(|fault| fault.name() == s)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Unknown fault '{}' (expected one of: {})", s,
+                Fault::ALL.iter().map(|f| f.name()).collect::<Vec<_>>().join(", ")))
+    }
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A set of switched-on faults and the clock offset. The process runs on
+/// the global set behind the free functions; tests use their own.
+#[derive(Debug, Default)]
+pub struct FaultSet {
+    active: AtomicU32,
+    clock_offset_secs: AtomicI64,
+}
+
+static GLOBAL: FaultSet = FaultSet::new();
+
+impl FaultSet {
+    pub const fn new() -> Self {
+        Self { active: AtomicU32::new(0), clock_offset_secs: AtomicI64::new(0) }
+    }
+
+    pub fn set(&self, fault: Fault, enabled: bool) {
+        if enabled {
+            self.active.fetch_or(fault.bit(), Ordering::SeqCst);
+        } else {
+            self.active.fetch_and(!fault.bit(), Ordering::SeqCst);
+            if fault == Fault::ClockJump {
+                self.clock_offset_secs.store(0, Ordering::SeqCst);
+            }
+        }
+    }
+
+    pub fn is_active(&self, fault: Fault) -> bool {
+        self.active.load(Ordering::SeqCst) & fault.bit() != 0
+    }
+
+    /// Clear a one-shot fault, returning whether it was armed
+    pub fn take(&self, fault: Fault) -> bool {
+        self.active.fetch_and(!fault.bit(), Ordering::SeqCst) & fault.bit() != 0
+    }
+
+    pub fn active(&self) -> Vec<Fault> {
+        Fault::ALL.iter().copied().filter(|f| self.is_active(*f)).collect()
+    }
+
+    pub fn clear_all(&self) {
+        self.active.store(0, Ordering::SeqCst);
+        self.clock_offset_secs.store(0, Ordering::SeqCst);
+    }
+
+    /// Shift the clock by `seconds`, forwards or backwards
+    pub fn jump_clock(&self, seconds: i64) {
+        self.clock_offset_secs.store(seconds, Ordering::SeqCst);
+        self.set(Fault::ClockJump, seconds != 0);
+    }
+
+    /// Wall clock time, including any injected jump
+    pub fn now(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_offset()
+    }
+
+    fn clock_offset(&self) -> Duration {
+        if self.is_active(Fault::ClockJump) {
+            Duration::seconds(self.clock_offset_secs.load(Ordering::SeqCst))
+        } else {
+            Duration::zero()
+        }
+    }
+}
+
+pub fn set(fault: Fault, enabled: bool) {
+    GLOBAL.set(fault, enabled)
+}
+
+pub fn is_active(fault: Fault) -> bool {
+    GLOBAL.is_active(fault)
+}
+
+/// Clear a one-shot fault, returning whether it was armed
+pub fn take(fault: Fault) -> bool {
+    GLOBAL.take(fault)
+}
+
+pub fn active() -> Vec<Fault> {
+    GLOBAL.active()
+}
+
+pub fn clear_all() {
+    GLOBAL.clear_all()
+}
+
+/// Shift the clock by `seconds`, forwards or backwards
+pub fn jump_clock(seconds: i64) {
+    GLOBAL.jump_clock(seconds)
+}
+
+/// Wall clock time, including any injected jump
+pub fn now() -> DateTime<Utc> {
+    GLOBAL.now()
+}
+
+/// The failure an outgoing API request should see instead of being sent,
+/// if any. Injected timeouts hang first, as a real one would.
+pub async fn request_failure() -> Option<anyhow::Error> {
+    if super::network_down() {
+        return Some(anyhow::anyhow!("Network unavailable (simulated drop)"));
+    }
+    if is_active(Fault::ApiTimeout) {
+        tokio::time::sleep(INJECTED_TIMEOUT).await;
+        return Some(anyhow::anyhow!("Request timed out (injected fault)"));
+    }
+    if is_active(Fault::ApiError) {
+        return Some(anyhow::anyhow!("HTTP 500 Internal Server Error (injected fault)"));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_names_round_trip() {
+        for fault in Fault::ALL {
+            assert_eq!(fault.name().parse::<Fault>().unwrap(), fault);
+            assert_eq!(serde_json::to_string(&fault).unwrap(), format!("\"{}\"", fault.name()));
+        }
+        assert!("meteor-strike".parse::<Fault>().is_err());
+    }
+
+    #[test]
+    fn test_faults_toggle_and_one_shot_take() {
+        let faults = FaultSet::new();
+        faults.set(Fault::GpsLoss, true);
+        faults.set(Fault::FfmpegCrash, true);
+        assert_eq!(faults.active(), vec![Fault::FfmpegCrash, Fault::GpsLoss]);
+
+        assert!(faults.take(Fault::FfmpegCrash));
+        assert!(!faults.take(Fault::FfmpegCrash));
+        assert!(faults.is_active(Fault::GpsLoss));
+
+        faults.jump_clock(3600);
+        assert!((faults.now() - Utc::now() - Duration::hours(1)).num_seconds().abs() <= 1);
+        faults.set(Fault::ClockJump, false);
+        assert!((faults.now() - Utc::now()).num_seconds().abs() <= 1);
+
+        faults.clear_all();
+        assert!(faults.active().is_empty());
+    }
+}
@@ -5,18 +5,53 @@ use rustyline::completion::{Completer, Pair};
 use rustyline::hint::{Hinter, HistoryHinter};
 use rustyline::validate::{Validator, ValidationContext, ValidationResult};
 use std::collections::{HashSet, HashMap};
-use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::device::BodycamDevice;
+use crate::event_bus::{BusEvent, NetworkEvent};
 use crate::hardware::HardwareEvent;
 use crate::config::Config;
 
+pub mod faults;
+pub mod scenario;
+
+pub use scenario::{Scenario, ScenarioReport, ScenarioRunner};
+
+static NETWORK_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Simulate losing connectivity; API requests fail fast while set
+pub fn set_network_down(down: bool) {
+    NETWORK_DOWN.store(down, Ordering::Relaxed);
+}
+
+pub fn network_down() -> bool {
+    NETWORK_DOWN.load(Ordering::Relaxed)
+}
+
+/// Hand an event to the device as if its hardware had raised it
+async fn publish_hardware_event(device: &BodycamDevice, event: HardwareEvent) {
+    device.event_bus().publish(BusEvent::Hardware(event));
+}
+
+/// Switch simulated connectivity and tell subscribers about it
+async fn set_network_online(device: &BodycamDevice, online: bool) {
+    set_network_down(!online);
+    let event = if online { NetworkEvent::Online } else { NetworkEvent::Offline };
+    device.event_bus().publish(BusEvent::Network(event));
+}
+
+fn parse_button(name: &str) -> Option<crate::hardware::ButtonType> {
+    match name {
+        "record" => Some(crate::hardware::ButtonType::Record),
+        "emergency" => Some(crate::hardware::ButtonType::Emergency),
+        "power" => Some(crate::hardware::ButtonType::Power),
+        "menu" => Some(crate::hardware::ButtonType::Menu),
+        _ => None,
+    }
+}
+
 pub struct SimulationRepl {
-    device: Arc<Mutex<BodycamDevice>>,
-    event_tx: mpsc::UnboundedSender<HardwareEvent>,
-    event_rx: Option<mpsc::UnboundedReceiver<HardwareEvent>>,
+    device: BodycamDevice,
 }
 
 struct ReplHelper {
@@ -40,7 +75,15 @@ impl ReplHelper {
         commands.insert("tamper".to_string());
         commands.insert("record".to_string());
         commands.insert("stop".to_string());
+        commands.insert("pause".to_string());
+        commands.insert("resume".to_string());
         commands.insert("incident".to_string());
+        commands.insert("stealth".to_string());
+        commands.insert("vibrate".to_string());
+        commands.insert("camera".to_string());
+        commands.insert("light".to_string());
+        commands.insert("network".to_string());
+        commands.insert("fault".to_string());
         commands.insert("exit".to_string());
         commands.insert("quit".to_string());
         
@@ -95,14 +138,8 @@ impl Validator for ReplHelper {
 }
 
 impl SimulationRepl {
-    pub fn new(device: Arc<Mutex<BodycamDevice>>) -> Self {
-        let (event_tx, event_rx) = mpsc::unbounded_channel();
-        
-        Self {
-            device,
-            event_tx,
-            event_rx: Some(event_rx),
-        }
+    pub fn new(device: BodycamDevice) -> Self {
+        Self { device }
     }
 
     pub async fn run(&mut self
@@ -115,13 +152,11 @@ impl SimulationRepl {
         let mut rl = Editor::<ReplHelper>::new()?;
         rl.set_helper(Some(ReplHelper::new()));
 
-        // Spawn event listener
-        let device_clone = Arc::clone(&self.device);
-        let mut event_rx = self.event_rx.take().unwrap();
-        
+        // The device acts on events itself; the REPL only reports them
+        let mut events = self.device.event_bus().subscribe();
         tokio::spawn(async move {
-            while let Some(event) = event_rx.recv().await {
-                Self::handle_hardware_event(&device_clone, event).await;
+            while let Some(event) = events.recv().await {
+                Self::print_event(&event);
             }
         });
 
@@ -163,7 +198,7 @@ impl SimulationRepl {
                 self.print_help();
             }
             Some("status") => {
-                let device = self.device.lock().await;
+                let device = &self.device;
                 let status = device.get_status().await?;
                 println!("{}", serde_json::to_string_pretty(&status)?);
             }
@@ -171,7 +206,7 @@ impl SimulationRepl {
                 if let Some(level) = parts.get(1) {
                     if let Ok(level) = level.parse::<f32>() {
                         let event = HardwareEvent::BatteryLow { level };
-                        let _ = self.event_tx.send(event);
+                        publish_hardware_event(&self.device, event).await;
                         println!("Battery level set to {}%", level);
                     }
                 } else {
@@ -182,7 +217,7 @@ impl SimulationRepl {
                 if let Some(temp) = parts.get(1) {
                     if let Ok(temp) = temp.parse::<f32>() {
                         let event = HardwareEvent::TemperatureHigh { temp };
-                        let _ = self.event_tx.send(event);
+                        publish_hardware_event(&self.device, event).await;
                         println!("Temperature set to {}°C", temp);
                     }
                 } else {
@@ -192,41 +227,34 @@ impl SimulationRepl {
             Some("storage") => {
                 println!("Storage usage simulated");
                 let event = HardwareEvent::StorageFull;
-                let _ = self.event_tx.send(event);
+                publish_hardware_event(&self.device, event).await;
             }
             Some("press") => {
                 if let Some(button) = parts.get(1) {
-                    let button_type = match *button {
-                        "record" => crate::hardware::ButtonType::Record,
-                        "emergency" => crate::hardware::ButtonType::Emergency,
-                        "power" => crate::hardware::ButtonType::Power,
-                        "menu" => crate::hardware::ButtonType::Menu,
-                        _ => {
-                            println!("Unknown button: {}", button);
-                            return Ok(());
-                        }
+                    let Some(button_type) = parse_button(button) else {
+                        println!("Unknown button: {}", button);
+                        return Ok(());
                     };
                     
-                    let event = HardwareEvent::ButtonPressed {
-                        button: button_type,
-                        duration: None,
-                    };
-                    let _ = self.event_tx.send(event);
-                    println!("Button pressed: {}", button);
+                    // A count sends presses in quick succession, for double and triple presses
+                    let count = parts.get(2).and_then(|c| c.parse::<u32>().ok()).unwrap_or(1);
+                    for _ in 0..count {
+                        let event = HardwareEvent::ButtonPressed {
+                            button: button_type,
+                            duration: None,
+                        };
+                        publish_hardware_event(&self.device, event).await;
+                    }
+                    println!("Button pressed: {} (x{})", button, count);
                 } else {
-                    println!("Usage: press <button> (record|emergency|power|menu)");
+                    println!("Usage: press <button> [count] (record|emergency|power|menu)");
                 }
             }
             Some("longpress") => {
                 if let Some(button) = parts.get(1) {
-                    let button_type = match *button {
-                        "record" => crate::hardware::ButtonType::Record,
-                        "emergency" => crate::hardware::ButtonType::Emergency,
-                        "power" => crate::hardware::ButtonType::Power,
-                        _ => {
-                            println!("Unknown button: {}", button);
-                            return Ok(());
-                        }
+                    let Some(button_type) = parse_button(button) else {
+                        println!("Unknown button: {}", button);
+                        return Ok(());
                     };
                     
                     let duration = parts.get(2).and_then(|d| d.parse::<u64>().ok()).unwrap_or(2000);
@@ -235,7 +263,7 @@ impl SimulationRepl {
                         button: button_type,
                         duration: Some(duration),
                     };
-                    let _ = self.event_tx.send(event);
+                    publish_hardware_event(&self.device, event).await;
                     println!("Button long-pressed: {} ({}ms)", button, duration);
                 } else {
                     println!("Usage: longpress <button> [duration_ms]");
@@ -244,113 +272,160 @@ impl SimulationRepl {
             Some("motion") => {
                 let intensity = parts.get(1).and_then(|i| i.parse::<f64>().ok()).unwrap_or(5.0);
                 let event = HardwareEvent::MotionDetected { intensity };
-                let _ = self.event_tx.send(event);
+                publish_hardware_event(&self.device, event).await;
                 println!("Motion detected with intensity: {}", intensity);
             }
             Some("lowbattery") => {
                 let event = HardwareEvent::BatteryLow { level: 15.0 };
-                let _ = self.event_tx.send(event);
+                publish_hardware_event(&self.device, event).await;
                 println!("Low battery event triggered");
             }
             Some("charging") => {
                 let event = HardwareEvent::ChargingConnected;
-                let _ = self.event_tx.send(event);
+                publish_hardware_event(&self.device, event).await;
                 println!("Charging connected event triggered");
             }
             Some("tamper") => {
                 let event = HardwareEvent::TamperDetected;
-                let _ = self.event_tx.send(event);
+                publish_hardware_event(&self.device, event).await;
                 println!("Tamper detected event triggered");
             }
             Some("record") => {
-                let device = self.device.lock().await;
+                let device = &self.device;
                 device.start_recording(None, None).await?;
                 println!("Recording started");
             }
             Some("stop") => {
-                let device = self.device.lock().await;
+                let device = &self.device;
                 device.stop_recording().await?;
                 println!("Recording stopped");
             }
+            Some("pause") => {
+                let reason = (parts.len() > 1).then(|| parts[1..].join(" "));
+                self.device.pause_recording(reason, "simulation").await?;
+                println!("Recording paused");
+            }
+            Some("resume") => {
+                self.device.resume_recording("simulation").await?;
+                println!("Recording resumed");
+            }
             Some("incident") => {
-                let incident_type = parts.get(1).unwrap_or(&"manual").to_string();
-                let severity = parts.get(2).unwrap_or(&"medium").to_string();
+                let incident_type = parts.get(1).unwrap_or(&"manual").parse()?;
+                let severity = parts.get(2).unwrap_or(&"medium").parse()?;
                 
-                let device = self.device.lock().await;
-                let incident_id = device.trigger_incident(&incident_type, &severity).await?;
+                let device = &self.device;
+                let incident_id = device.trigger_incident(incident_type, severity).await?;
                 println!("Incident triggered: {}", incident_id);
             }
-            Some("exit") | Some("quit") => {
-                return Err(anyhow::anyhow!("exit"));
+            Some("stealth") => {
+                let device = &self.device;
+                let enabled = match parts.get(1).map(|s| *s) {
+                    Some("on") => true,
+                    Some("off") => false,
+                    None => !device.is_stealth_mode(),
+                    Some(other) => {
+                        println!("Usage: stealth [on|off] (got '{}')", other);
+                        return Ok(());
+                    }
+                };
+                device.set_stealth_mode(enabled, "simulation").await?;
+                println!("Stealth mode {}", if enabled { "enabled" } else { "disabled" });
             }
-            _ => {
-                println!("Unknown command. Type 'help' for available commands.");
+            Some("vibrate") => {
+                if let Some(pattern) = parts.get(1) {
+                    let pattern: crate::hardware::haptics::HapticPattern = pattern.parse()?;
+                    let device = &self.device;
+                    device.vibrate_pattern(pattern).await?;
+                    println!("Vibration pattern played: {}", pattern.name());
+                } else {
+                    println!("Usage: vibrate <pattern> (single|double|sos|heartbeat)");
+                }
             }
-        }
-        
-        Ok(())
-    }
-
-    async fn handle_hardware_event(
-        device: &Arc<Mutex<BodycamDevice>>,
-        event: HardwareEvent
-    ) {
-        let device = device.lock().await;
-        
-        match event {
-            HardwareEvent::ButtonPressed { button, duration } => {
-                match button {
-                    crate::hardware::ButtonType::Record => {
-                        if duration.is_some() {
-                            // Long press - stop recording
-                            let _ = device.stop_recording().await;
-                            println!("Long press - recording stopped");
-                        } else {
-                            // Short press - toggle recording
-                            let _ = device.start_recording(None, None).await;
-                            println!("Short press - recording started");
+            Some("camera") => {
+                let device = &self.device;
+                match (parts.get(1).map(|s| *s), parts.get(2), parts.get(3)) {
+                    (Some("list"), _, _) => {
+                        for control in device.list_camera_controls().await? {
+                            println!("  {:<24} {:<6} {}..{} (default {})", control.name, control.control_type,
+                                control.min_value, control.max_value, control.default_value);
                         }
                     }
-                    crate::hardware::ButtonType::Emergency => {
-                        let _ = device.trigger_incident("emergency", "high").await;
-                        println!("Emergency button pressed");
+                    (Some("get"), Some(control), _) => {
+                        let value = device.get_camera_control(control).await?;
+                        println!("{} = {}", control, value);
                     }
-                    crate::hardware::ButtonType::Power => {
-                        println!("Power button pressed");
+                    (Some("set"), Some(control), Some(value)) => {
+                        device.set_camera_control(control, value).await?;
+                        println!("{} set to {}", control, value);
                     }
-                    _ => {}
+                    _ => println!("Usage: camera list | camera get <control> | camera set <control> <value>"),
                 }
             }
-            HardwareEvent::BatteryLow { level } => {
-                println!("⚠️  Battery low: {}%", level);
-                let _ = device.hardware.set_led("battery", crate::hardware::LedState::Blink {
-                    on_duration: 200,
-                    off_duration: 200,
-                    repeat: Some(10),
-                }).await;
-            }
-            HardwareEvent::BatteryCritical { level } => {
-                println!("🚨 Battery critical: {}% - shutting down", level);
-                let _ = device.hardware.shutdown().await;
-            }
-            HardwareEvent::StorageFull => {
-                println!("💾 Storage full - stopping recording");
-                let _ = device.stop_recording().await;
+            Some("light") => {
+                match parts.get(1).and_then(|lux| lux.parse::<f64>().ok()) {
+                    Some(lux) => {
+                        let device = &self.device;
+                        device.observe_light(crate::camera::night_mode::LightReading::Lux(lux)).await?;
+                        println!("Light level {} lux, camera in {:?} mode", lux, device.light_condition());
+                    }
+                    None => println!("Usage: light <lux>"),
+                }
             }
-            HardwareEvent::TemperatureHigh { temp } => {
-                println!("🌡️  Temperature high: {}°C", temp);
+            Some("network") => {
+                match parts.get(1).map(|s| *s) {
+                    Some("up") => set_network_online(&self.device, true).await,
+                    Some("down") => set_network_online(&self.device, false).await,
+                    _ => {
+                        println!("Usage: network <up|down>");
+                        return Ok(());
+                    }
+                }
+                println!("Network {}", if network_down() { "down" } else { "up" });
             }
-            HardwareEvent::MotionDetected { intensity } => {
-                println!("🏃 Motion detected: intensity {}", intensity);
-                if intensity > 7.0 {
-                    let _ = device.trigger_incident("motion", "medium").await;
+            Some("fault") => {
+                match (parts.get(1).map(|s| *s), parts.get(2).map(|s| *s)) {
+                    (None, _) | (Some("list"), _) => {}
+                    (Some("clear"), _) => faults::clear_all(),
+                    (Some("clock-jump"), Some(value)) if value != "off" => {
+                        let Ok(seconds) = value.parse::<i64>() else {
+                            println!("Usage: fault clock-jump <seconds|off>");
+                            return Ok(());
+                        };
+                        faults::jump_clock(seconds);
+                    }
+                    (Some(name), Some(state @ ("on" | "off"))) => {
+                        faults::set(name.parse()?, state == "on");
+                    }
+                    _ => {
+                        println!("Usage: fault <name> <on|off> | fault clock-jump <seconds|off> | fault list | fault clear");
+                        return Ok(());
+                    }
                 }
+                let active: Vec<&str> = faults::active().iter().map(|f| f.name()).collect();
+                println!("Active faults: {}", if active.is_empty() { "none".to_string() } else { active.join(", ") });
+            }
+            Some("exit") | Some("quit") => {
+                return Err(anyhow::anyhow!("exit"));
             }
-            HardwareEvent::TamperDetected => {
-                println!("🚨 Tamper detected");
-                let _ = device.trigger_incident("tamper", "high").await;
+            _ => {
+                println!("Unknown command. Type 'help' for available commands.");
             }
-            _ => {}
+        }
+        
+        Ok(())
+    }
+
+    fn print_event(event: &BusEvent) {
+        match event {
+            BusEvent::Hardware(HardwareEvent::BatteryLow { level }) => println!("⚠️  Battery low: {}%", level),
+            BusEvent::Hardware(HardwareEvent::BatteryCritical { level }) => println!("🚨 Battery critical: {}%", level),
+            BusEvent::Hardware(HardwareEvent::StorageFull) => println!("💾 Storage full - stopping recording"),
+            BusEvent::Hardware(HardwareEvent::TemperatureHigh { temp }) => println!("🌡️  Temperature high: {}°C", temp),
+            BusEvent::Hardware(HardwareEvent::BatteryOverheating { temp }) => println!("🔥 Battery overheating while charging: {}°C", temp),
+            BusEvent::Hardware(HardwareEvent::MotionDetected { intensity }) => println!("🏃 Motion detected: intensity {}", intensity),
+            BusEvent::Hardware(HardwareEvent::TamperDetected) => println!("🚨 Tamper detected"),
+            BusEvent::Hardware(_) => {}
+            other => println!("[event] {:?}", other),
         }
     }
 
@@ -362,7 +437,7 @@ impl SimulationRepl {
         println!("  battery <level>      - Simulate battery level (0-100)");
         println!("  temperature <temp>  - Simulate temperature (°C)");
         println!("  storage             - Simulate storage full");
-        println!("  press <button>      - Simulate button press (record|emergency|power|menu)");
+        println!("  press <button> [n]  - Simulate n button presses (record|emergency|power|menu)");
         println!("  longpress <button>  - Simulate long button press");
         println!("  motion [intensity]  - Simulate motion detection");
         println!("  lowbattery          - Simulate low battery");
@@ -370,7 +445,17 @@ impl SimulationRepl {
         println!("  tamper              - Simulate tamper detection");
         println!("  record              - Start recording");
         println!("  stop                - Stop recording");
+        println!("  pause [reason]      - Pause the recording");
+        println!("  resume              - Resume a paused recording");
         println!("  incident [type] [sev] - Trigger incident");
+        println!("  stealth [on|off]    - Toggle stealth mode (LEDs, audio, vibration off)");
+        println!("  vibrate <pattern>   - Play haptic pattern (single|double|sos|heartbeat)");
+        println!("  camera set <control> <value> - Set brightness|exposure|gain|focus|zoom|ir_cut (value or auto)");
+        println!("  camera get <control> / camera list - Read camera controls");
+        println!("  light <lux>         - Simulate an ambient light reading (drives night mode)");
+        println!("  network <up|down>   - Simulate losing or regaining connectivity");
+        println!("  fault <name> <on|off> - Inject api-error|api-timeout|disk-full|ffmpeg-crash|gps-loss");
+        println!("  fault clock-jump <seconds|off> - Shift the clock; fault list / fault clear");
         println!("  exit/quit           - Exit simulation");
     }
 }
\ No newline at end of file
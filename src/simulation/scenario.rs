@@ -0,0 +1,497 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::time::{Duration, Instant};
+
+use crate::device::BodycamDevice;
+use crate::gps::GpsLocation;
+use crate::hardware::HardwareEvent;
+use crate::incident::{IncidentSeverity, IncidentType};
+
+use super::faults::{self, Fault};
+
+/// A reproducible sequence of simulated events, loaded from YAML or TOML
+///
+/// ```yaml
+/// name: battery drains during an incident
+/// steps:
+///   - { at: 0, action: press, button: record }
+///   - { at: 5, action: battery_drain, from: 40, to: 5, over_seconds: 60 }
+///   - { at: 10, action: network_drop, duration_seconds: 30 }
+///   - { at: 70, action: expect, recording: true }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub steps: Vec<ScenarioStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// Seconds from the start of the scenario
+    pub at: f64,
+    #[serde(flatten)]
+    pub action: ScenarioAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ScenarioAction {
+    Press {
+        button: String,
+        /// Held for this long; a plain press when absent
+        #[serde(default)]
+        duration_ms: Option<u64>,
+    },
+    Battery { level: f32 },
+    /// Linear drain, reported every `interval_seconds`
+    BatteryDrain {
+        from: f32,
+        to: f32,
+        over_seconds: f64,
+        #[serde(default = "default_interval")]
+        interval_seconds: f64,
+    },
+    Temperature { celsius: f32 },
+    Motion { intensity: f64 },
+    Tamper,
+    Charging { connected: bool },
+    StorageFull,
+    Light { lux: f64 },
+    Location { latitude: f64, longitude: f64, #[serde(default)] speed_mps: Option<f64> },
+    /// Travel along the waypoints at constant speed
+    GpsRoute {
+        waypoints: Vec<Waypoint>,
+        over_seconds: f64,
+        #[serde(default = "default_interval")]
+        interval_seconds: f64,
+    },
+    Network { online: bool },
+    NetworkDrop { duration_seconds: f64 },
+    /// Switch an injected fault on or off; `clock-jump` takes `seconds`
+    Fault {
+        fault: Fault,
+        #[serde(default = "default_true")]
+        enabled: bool,
+        #[serde(default)]
+        seconds: Option<i64>,
+    },
+    Record,
+    Stop,
+    Incident {
+        incident_type: IncidentType,
+        #[serde(default = "default_severity")]
+        severity: IncidentSeverity,
+    },
+    /// Check device state; any mismatch fails the scenario
+    Expect(Expectation),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Expectation {
+    #[serde(default)]
+    pub recording: Option<bool>,
+    #[serde(default)]
+    pub incident_active: Option<bool>,
+    #[serde(default)]
+    pub stealth_mode: Option<bool>,
+    #[serde(default)]
+    pub online: Option<bool>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_interval() -> f64 {
+    5.0
+}
+
+fn default_severity() -> IncidentSeverity {
+    IncidentSeverity::Medium
+}
+
+/// A single event at an absolute offset into the scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedAction {
+    pub at: Duration,
+    pub action: ScenarioAction,
+}
+
+impl Scenario {
+    /// Load a scenario, picking the format from the file extension
+    pub async fn load(path: &Path) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await
+            .with_context(|| format!("Failed to read scenario {}", path.display()))?;
+
+        let scenario: Scenario = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content)
+                .context("Failed to parse YAML scenario")?,
+            _ => toml::from_str(&content).context("Failed to parse TOML scenario")?,
+        };
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (index, step) in self.steps.iter().enumerate() {
+            if !step.at.is_finite() || step.at < 0.0 {
+                return Err(anyhow::anyhow!("Step {} has an invalid time {}", index + 1, step.at));
+            }
+            match &step.action {
+                ScenarioAction::BatteryDrain { over_seconds, interval_seconds, .. }
+                | ScenarioAction::GpsRoute { over_seconds, interval_seconds, .. }
+                    if *over_seconds <= 0.0 || *interval_seconds <= 0.0 =>
+                {
+                    return Err(anyhow::anyhow!("Step {} needs positive durations", index + 1));
+                }
+                ScenarioAction::GpsRoute { waypoints, .. } if waypoints.len() < 2 => {
+                    return Err(anyhow::anyhow!("Step {} needs at least two waypoints", index + 1));
+                }
+                ScenarioAction::Press { button, .. } if super::parse_button(button).is_none() => {
+                    return Err(anyhow::anyhow!("Step {} presses unknown button '{}'", index + 1, button));
+                }
+                ScenarioAction::Fault { fault: Fault::ClockJump, enabled: true, seconds: None } => {
+                    return Err(anyhow::anyhow!("Step {} needs `seconds` for a clock jump", index + 1));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Expand curves, routes and drops into single events, in time order
+    pub fn timeline(&self) -> Vec<TimedAction> {
+        let mut timeline = Vec::new();
+
+        for step in &self.steps {
+            let start = step.at;
+            match &step.action {
+                ScenarioAction::BatteryDrain { from, to, over_seconds, interval_seconds } => {
+                    for offset in sample_offsets(*over_seconds, *interval_seconds) {
+                        let progress = (offset / over_seconds) as f32;
+                        let level = from + (to - from) * progress;
+                        timeline.push(timed(start + offset, ScenarioAction::Battery { level }));
+                    }
+                }
+                ScenarioAction::GpsRoute { waypoints, over_seconds, interval_seconds } => {
+                    let total = route_length(waypoints);
+                    let speed = total / over_seconds;
+                    for offset in sample_offsets(*over_seconds, *interval_seconds) {
+                        let point = point_along(waypoints, total * offset / over_seconds);
+                        timeline.push(timed(start + offset, ScenarioAction::Location {
+                            latitude: point.latitude,
+                            longitude: point.longitude,
+                            speed_mps: Some(speed),
+                        }));
+                    }
+                }
+                ScenarioAction::NetworkDrop { duration_seconds } => {
+                    timeline.push(timed(start, ScenarioAction::Network { online: false }));
+                    timeline.push(timed(start + duration_seconds, ScenarioAction::Network { online: true }));
+                }
+                action => timeline.push(timed(start, action.clone())),
+            }
+        }
+
+        // Stable, so same-time steps keep file order
+        timeline.sort_by_key(|t| t.at);
+        timeline
+    }
+}
+
+fn timed(at: f64, action: ScenarioAction) -> TimedAction {
+    TimedAction { at: Duration::from_secs_f64(at), action }
+}
+
+/// 0, interval, 2*interval, ... always ending exactly at `over`
+fn sample_offsets(over: f64, interval: f64) -> Vec<f64> {
+    let mut offsets = Vec::new();
+    let mut offset = 0.0;
+    while offset < over {
+        offsets.push(offset);
+        offset += interval;
+    }
+    offsets.push(over);
+    offsets
+}
+
+/// Great-circle distance in metres
+fn distance_m(a: Waypoint, b: Waypoint) -> f64 {
+    const EARTH_RADIUS_M: f64 = 6_371_000.0;
+    let (lat1, lat2) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.longitude - a.longitude).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+fn route_length(waypoints: &[Waypoint]) -> f64 {
+    waypoints.windows(2).map(|pair| distance_m(pair[0], pair[1])).sum()
+}
+
+/// Position `distance` metres along the route; legs are short enough to
+/// interpolate linearly
+fn point_along(waypoints: &[Waypoint], distance: f64) -> Waypoint {
+    let mut remaining = distance;
+    for pair in waypoints.windows(2) {
+        let leg = distance_m(pair[0], pair[1]);
+        if remaining <= leg && leg > 0.0 {
+            let t = remaining / leg;
+            return Waypoint {
+                latitude: pair[0].latitude + (pair[1].latitude - pair[0].latitude) * t,
+                longitude: pair[0].longitude + (pair[1].longitude - pair[0].longitude) * t,
+            };
+        }
+        remaining -= leg;
+    }
+    *waypoints.last().expect("route has waypoints")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub events_run: usize,
+    pub failures: Vec<String>,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Plays a scenario against a device in real time. Hardware actions are
+/// published on the device's event bus and handled asynchronously, so an
+/// `expect` should come a moment after the action it checks.
+pub struct ScenarioRunner {
+    device: BodycamDevice,
+}
+
+impl ScenarioRunner {
+    pub fn new(device: BodycamDevice) -> Self {
+        Self { device }
+    }
+
+    pub async fn run(&self, scenario: &Scenario) -> Result<ScenarioReport> {
+        println!("=== Scenario: {} ===", scenario.name);
+        if let Some(description) = &scenario.description {
+            println!("{}", description);
+        }
+
+        let timeline = scenario.timeline();
+        let started = Instant::now();
+        let mut failures = Vec::new();
+
+        for event in &timeline {
+            tokio::time::sleep_until(started + event.at).await;
+            println!("[{:>7.1}s] {:?}", event.at.as_secs_f64(), event.action);
+
+            if let Err(e) = self.apply(&event.action, &mut failures, event.at).await {
+                failures.push(format!("{:.1}s: {:?} failed: {}", event.at.as_secs_f64(), event.action, e));
+            }
+        }
+
+        // Never leave the process offline or faulty after the script ends
+        super::set_network_down(false);
+        faults::clear_all();
+
+        let report = ScenarioReport {
+            name: scenario.name.clone(),
+            events_run: timeline.len(),
+            failures,
+        };
+        if report.passed() {
+            println!("Scenario passed ({} events)", report.events_run);
+        } else {
+            println!("Scenario failed:");
+            for failure in &report.failures {
+                println!("  {}", failure);
+            }
+        }
+        Ok(report)
+    }
+
+    async fn apply(&self, action: &ScenarioAction, failures: &mut Vec<String>, at: Duration) -> Result<()> {
+        let hardware_event = match action {
+            ScenarioAction::Press { button, duration_ms } => {
+                let button = super::parse_button(button)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown button: {}", button))?;
+                Some(HardwareEvent::ButtonPressed { button, duration: *duration_ms })
+            }
+            ScenarioAction::Battery { level } => Some(HardwareEvent::BatteryLow { level: *level }),
+            ScenarioAction::Temperature { celsius } => Some(HardwareEvent::TemperatureHigh { temp: *celsius }),
+            ScenarioAction::Motion { intensity } => Some(HardwareEvent::MotionDetected { intensity: *intensity }),
+            ScenarioAction::Tamper => Some(HardwareEvent::TamperDetected),
+            ScenarioAction::Charging { connected: true } => Some(HardwareEvent::ChargingConnected),
+            ScenarioAction::Charging { connected: false } => Some(HardwareEvent::ChargingDisconnected),
+            ScenarioAction::StorageFull => Some(HardwareEvent::StorageFull),
+            _ => None,
+        };
+        if let Some(event) = hardware_event {
+            super::publish_hardware_event(&self.device, event).await;
+            return Ok(());
+        }
+        if let ScenarioAction::Network { online } = action {
+            super::set_network_online(&self.device, *online).await;
+            return Ok(());
+        }
+
+        let device = &self.device;
+        match action {
+            ScenarioAction::Light { lux } => {
+                device.observe_light(crate::camera::night_mode::LightReading::Lux(*lux)).await?;
+            }
+            ScenarioAction::Location { latitude, longitude, speed_mps } => {
+                device.simulate_location(GpsLocation {
+                    latitude: *latitude,
+                    longitude: *longitude,
+                    altitude: None,
+                    accuracy: Some(5.0),
+                    hdop: None,
+                    speed: *speed_mps,
+                    heading: None,
+                    timestamp: chrono::Utc::now(),
+                    satellites: Some(8),
+                }).await;
+            }
+            ScenarioAction::Fault { fault: Fault::ClockJump, enabled: true, seconds } => {
+                faults::jump_clock(seconds.unwrap_or(0));
+            }
+            ScenarioAction::Fault { fault, enabled, .. } => faults::set(*fault, *enabled),
+            ScenarioAction::Record => device.start_recording(None, None).await?,
+            ScenarioAction::Stop => device.stop_recording().await?,
+            ScenarioAction::Incident { incident_type, severity } => {
+                device.trigger_incident(*incident_type, *severity).await?;
+            }
+            ScenarioAction::Expect(expected) => {
+                let status = device.get_status().await?;
+                let checks = [
+                    ("recording", expected.recording, status.recording),
+                    ("incident_active", expected.incident_active, status.incident_active),
+                    ("stealth_mode", expected.stealth_mode, status.stealth_mode),
+                    ("online", expected.online, status.online),
+                ];
+                for (field, want, actual) in checks {
+                    if let Some(want) = want {
+                        if want != actual {
+                            failures.push(format!("{:.1}s: expected {} = {}, was {}", at.as_secs_f64(), field, want, actual));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_yaml(yaml: &str) -> Scenario {
+        let scenario: Scenario = serde_yaml::from_str(yaml).unwrap();
+        scenario.validate().unwrap();
+        scenario
+    }
+
+    #[test]
+    fn test_yaml_and_toml_describe_the_same_scenario() {
+        let yaml = parse_yaml(r#"
+name: drop
+steps:
+  - { at: 1, action: press, button: emergency }
+  - { at: 2, action: expect, incident_active: true }
+"#);
+        let toml: Scenario = toml::from_str(r#"
+name = "drop"
+
+[[steps]]
+at = 1
+action = "press"
+button = "emergency"
+
+[[steps]]
+at = 2
+action = "expect"
+incident_active = true
+"#).unwrap();
+
+        assert_eq!(yaml.timeline(), toml.timeline());
+        assert_eq!(yaml.steps[1].action, ScenarioAction::Expect(Expectation {
+            incident_active: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_timeline_expands_drain_and_drop_in_order() {
+        let scenario = parse_yaml(r#"
+name: drain
+steps:
+  - { at: 10, action: battery_drain, from: 50, to: 20, over_seconds: 30, interval_seconds: 10 }
+  - { at: 15, action: network_drop, duration_seconds: 20 }
+"#);
+        let timeline = scenario.timeline();
+        let summary: Vec<(u64, ScenarioAction)> = timeline.iter()
+            .map(|t| (t.at.as_secs(), t.action.clone()))
+            .collect();
+
+        assert_eq!(summary, vec![
+            (10, ScenarioAction::Battery { level: 50.0 }),
+            (15, ScenarioAction::Network { online: false }),
+            (20, ScenarioAction::Battery { level: 40.0 }),
+            (30, ScenarioAction::Battery { level: 30.0 }),
+            (35, ScenarioAction::Network { online: true }),
+            (40, ScenarioAction::Battery { level: 20.0 }),
+        ]);
+    }
+
+    #[test]
+    fn test_gps_route_moves_at_constant_speed() {
+        let scenario = parse_yaml(r#"
+name: route
+steps:
+  - action: gps_route
+    at: 0
+    over_seconds: 100
+    interval_seconds: 50
+    waypoints:
+      - { latitude: 0.0, longitude: 0.0 }
+      - { latitude: 0.0, longitude: 0.01 }
+"#);
+        let timeline = scenario.timeline();
+        assert_eq!(timeline.len(), 3);
+
+        let ScenarioAction::Location { longitude, speed_mps, .. } = &timeline[1].action else {
+            panic!("expected a location");
+        };
+        assert!((longitude - 0.005).abs() < 1e-9);
+        // 0.01 degrees at the equator is about 1112m
+        assert!((speed_mps.unwrap() - 11.12).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_validation_rejects_bad_steps() {
+        let unknown_button: Scenario = serde_yaml::from_str(
+            "name: x\nsteps:\n  - { at: 0, action: press, button: volume }\n").unwrap();
+        assert!(unknown_button.validate().is_err());
+
+        let clock_jump: Scenario = serde_yaml::from_str(
+            "name: x\nsteps:\n  - { at: 0, action: fault, fault: clock-jump }\n").unwrap();
+        assert!(clock_jump.validate().is_err());
+
+        let single_waypoint: Scenario = serde_yaml::from_str(
+            "name: x\nsteps:\n  - { at: 0, action: gps_route, over_seconds: 5, waypoints: [{ latitude: 1, longitude: 1 }] }\n").unwrap();
+        assert!(single_waypoint.validate().is_err());
+    }
+}
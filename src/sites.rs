@@ -0,0 +1,97 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Credentials the device was provisioned with at one customer site.
+/// Contract guards move between sites, so the device keeps one of these per
+/// site it has been registered at and switches between them without
+/// re-provisioning.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteProfile {
+    pub site_id: String,
+    pub tenant_id: String,
+    pub device_id: String,
+    pub device_key: String,
+    pub auth_token: Option<String>,
+}
+
+impl SiteProfile {
+    /// The site the device is currently scoped to, if it is provisioned
+    pub fn active(config: &Config) -> Option<Self> {
+        Some(Self {
+            site_id: config.site_id.clone()?,
+            tenant_id: config.tenant_id.clone()?,
+            device_id: config.device_id.clone()?,
+            device_key: config.device_key.clone()?,
+            auth_token: config.auth_token.clone(),
+        })
+    }
+
+    /// Scope `config`, and so every backend built from it, to this site
+    pub fn apply(&self, config: &mut Config) {
+        config.site_id = Some(self.site_id.clone());
+        config.tenant_id = Some(self.tenant_id.clone());
+        config.device_id = Some(self.device_id.clone());
+        config.device_key = Some(self.device_key.clone());
+        config.auth_token = self.auth_token.clone();
+    }
+}
+
+/// Keep the active site's credentials in `config.sites`, replacing any
+/// older entry for the same site
+pub fn remember_active(config: &mut Config) {
+    let Some(active) = SiteProfile::active(config) else {
+        return;
+    };
+    config.sites.retain(|site| site.site_id != active.site_id);
+    config.sites.push(active);
+}
+
+/// Credentials held for `site_id`
+pub fn find<'a>(config: &'a Config, site_id: &str) -> Result<&'a SiteProfile> {
+    config.sites.iter()
+        .find(|site| site.site_id == site_id)
+        .ok_or_else(|| anyhow::anyhow!("No credentials for site {}; register the device there first", site_id))
+}
+
+/// Sites the device holds credentials for, the active one included
+pub fn known_sites(config: &Config) -> Vec<SiteProfile> {
+    let mut sites = config.sites.clone();
+    if let Some(active) = SiteProfile::active(config) {
+        sites.retain(|site| site.site_id != active.site_id);
+        sites.insert(0, active);
+    }
+    sites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(site_id: &str) -> SiteProfile {
+        SiteProfile {
+            site_id: site_id.to_string(),
+            tenant_id: format!("tenant-{}", site_id),
+            device_id: format!("dev-{}", site_id),
+            device_key: "key".to_string(),
+            auth_token: None,
+        }
+    }
+
+    #[test]
+    fn test_switching_keeps_every_site() {
+        let mut config = Config::default();
+        site("mall").apply(&mut config);
+        config.sites.push(site("depot"));
+
+        remember_active(&mut config);
+        find(&config, "depot").unwrap().clone().apply(&mut config);
+        assert_eq!(config.tenant_id.as_deref(), Some("tenant-depot"));
+        assert_eq!(config.device_id.as_deref(), Some("dev-depot"));
+
+        let known: Vec<_> = known_sites(&config).into_iter().map(|site| site.site_id).collect();
+        assert_eq!(known, vec!["depot", "mall"]);
+        assert!(find(&config, "airport").is_err());
+    }
+}
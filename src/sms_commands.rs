@@ -0,0 +1,258 @@
+//! Commands texted to the device's platform number by supervisors, for
+//! areas with SMS-only coverage. Only allowlisted senders are obeyed, and
+//! with a shared secret each command must carry a code that expires and
+//! works once. Commands that change what the device is doing always need
+//! the code, since a sender number alone is easy to spoof.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use crate::communications::normalize_number;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Codes are valid for the window they were made in and the one before
+const CODE_WINDOW_SECONDS: i64 = 600;
+const CODE_LENGTH: usize = 8;
+/// Message ids remembered so a command is never run twice
+const REMEMBERED_IDS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsCommandConfig {
+    pub enabled: bool,
+    /// Numbers allowed to send commands, e.g. `+15550100`
+    pub allowed_senders: Vec<String>,
+    /// Shared with supervisors' tooling; when set, each command ends with
+    /// the code from `command_code`. Without it only STATUS and LOCATE are
+    /// obeyed.
+    pub secret: Option<String>,
+    pub poll_interval_seconds: u64,
+    /// Text a reply with the result
+    pub reply: bool,
+}
+
+impl Default for SmsCommandConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_senders: Vec::new(),
+            secret: None,
+            poll_interval_seconds: 30,
+            reply: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmsCommand {
+    Status,
+    Locate,
+    StreamOn,
+    StreamOff,
+    RecordOn,
+    RecordOff,
+}
+
+impl SmsCommand {
+    pub const ALL: [SmsCommand; 6] = [
+        SmsCommand::Status,
+        SmsCommand::Locate,
+        SmsCommand::StreamOn,
+        SmsCommand::StreamOff,
+        SmsCommand::RecordOn,
+        SmsCommand::RecordOff,
+    ];
+
+    /// The words a supervisor texts
+    pub fn text(self) -> &'static str {
+        match self {
+            SmsCommand::Status => "STATUS",
+            SmsCommand::Locate => "LOCATE",
+            SmsCommand::StreamOn => "STREAM ON",
+            SmsCommand::StreamOff => "STREAM OFF",
+            SmsCommand::RecordOn => "RECORD ON",
+            SmsCommand::RecordOff => "RECORD OFF",
+        }
+    }
+
+    /// Whether the command changes what the device is doing, rather than
+    /// only reporting on it
+    pub fn changes_state(self) -> bool {
+        !matches!(self, SmsCommand::Status | SmsCommand::Locate)
+    }
+}
+
+/// Split a message into its command and trailing code. Case and spacing
+/// don't matter; anything outside the grammar is None.
+pub fn parse(text: &str) -> Option<(SmsCommand, Option<String>)> {
+    let words: Vec<String> = text.split_whitespace().map(|word| word.to_uppercase()).collect();
+    SmsCommand::ALL.iter().find_map(|command| {
+        let expected: Vec<&str> = command.text().split(' ').collect();
+        if words.len() < expected.len() || words[..expected.len()] != expected[..] {
+            return None;
+        }
+        match &words[expected.len()..] {
+            [] => Some((*command, None)),
+            [code] => Some((*command, Some(code.to_lowercase()))),
+            _ => None,
+        }
+    })
+}
+
+/// The code for `command` from `sender` in the window containing `at`
+pub fn command_code(secret: &str, sender: &str, command: SmsCommand, at: DateTime<Utc>) -> String {
+    code_for_window(secret, sender, command, at.timestamp().div_euclid(CODE_WINDOW_SECONDS))
+}
+
+fn code_for_window(secret: &str, sender: &str, command: SmsCommand, window: i64) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(format!("{}:{}:{}", normalize_number(sender), command.text(), window).as_bytes());
+    hex::encode(mac.finalize().into_bytes())[..CODE_LENGTH].to_string()
+}
+
+/// The command in a message, if the sender may give it. A code that is
+/// accepted is remembered in `state` so it can't be replayed.
+pub fn verify(
+    config: &SmsCommandConfig,
+    state: &mut SmsCommandState,
+    sender: &str,
+    text: &str,
+    now: DateTime<Utc>,
+) -> Result<SmsCommand, &'static str> {
+    let sender_number = normalize_number(sender);
+    if !config.allowed_senders.iter().any(|allowed| normalize_number(allowed) == sender_number) {
+        return Err("sender not allowed");
+    }
+    let (command, code) = parse(text).ok_or("not a command")?;
+    let Some(secret) = &config.secret else {
+        if command.changes_state() {
+            return Err("no secret configured for this command");
+        }
+        return Ok(command);
+    };
+    let code = code.ok_or("missing code")?;
+    let window = now.timestamp().div_euclid(CODE_WINDOW_SECONDS);
+    if ![window, window - 1].iter().any(|w| code_for_window(secret, sender, command, *w) == code) {
+        return Err("wrong or expired code");
+    }
+    if !state.use_code(&code, now) {
+        return Err("code already used");
+    }
+    Ok(command)
+}
+
+/// Which inbound messages have been handled, kept across restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SmsCommandState {
+    /// Messages before this were sent before commands were being read
+    pub since: Option<DateTime<Utc>>,
+    processed: VecDeque<String>,
+    /// Codes accepted while they could still be valid, with when they were used
+    #[serde(default)]
+    used_codes: VecDeque<(String, DateTime<Utc>)>,
+}
+
+impl SmsCommandState {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("sms_commands.json")
+    }
+
+    pub async fn load() -> Self {
+        tokio::fs::read_to_string(Self::default_path()).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let path = Self::default_path();
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    pub fn is_processed(&self, id: &str) -> bool {
+        self.processed.iter().any(|processed| processed == id)
+    }
+
+    pub fn mark_processed(&mut self, id: &str) {
+        self.processed.push_back(id.to_string());
+        while self.processed.len() > REMEMBERED_IDS {
+            self.processed.pop_front();
+        }
+    }
+
+    /// Record a code as used, or false if it already was. Codes older than
+    /// two windows have expired anyway and are forgotten.
+    fn use_code(&mut self, code: &str, now: DateTime<Utc>) -> bool {
+        let expiry = chrono::Duration::seconds(2 * CODE_WINDOW_SECONDS);
+        self.used_codes.retain(|(_, used_at)| now - *used_at < expiry);
+        if self.used_codes.iter().any(|(used, _)| used == code) {
+            return false;
+        }
+        self.used_codes.push_back((code.to_string(), now));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_parse_grammar() {
+        assert_eq!(parse("status"), Some((SmsCommand::Status, None)));
+        assert_eq!(parse("  Stream   on 1A2b3C4d "), Some((SmsCommand::StreamOn, Some("1a2b3c4d".to_string()))));
+        assert_eq!(parse("STREAM"), None);
+        assert_eq!(parse("RECORD OFF now please"), None);
+        assert_eq!(parse("reboot"), None);
+    }
+
+    #[test]
+    fn test_verify_checks_sender_and_code() {
+        let now = Utc.with_ymd_and_hms(2026, 5, 1, 12, 0, 0).unwrap();
+        let config = SmsCommandConfig {
+            enabled: true,
+            allowed_senders: vec!["+1 555-0100".to_string()],
+            secret: Some("shared".to_string()),
+            ..SmsCommandConfig::default()
+        };
+        let code = command_code("shared", "+15550100", SmsCommand::Locate, now - Duration::minutes(5));
+        let mut state = SmsCommandState::default();
+
+        assert_eq!(verify(&config, &mut state, "+15550199", &format!("LOCATE {}", code), now), Err("sender not allowed"));
+        assert_eq!(verify(&config, &mut state, "+15550100", "LOCATE", now), Err("missing code"));
+        assert_eq!(verify(&config, &mut state, "+15550100", &format!("STATUS {}", code), now), Err("wrong or expired code"));
+        let later = now + Duration::minutes(30);
+        assert_eq!(verify(&config, &mut state, "+15550100", &format!("LOCATE {}", code), later), Err("wrong or expired code"));
+        assert_eq!(verify(&config, &mut state, "+15550100", &format!("LOCATE {}", code), now), Ok(SmsCommand::Locate));
+        assert_eq!(verify(&config, &mut state, "+15550100", &format!("LOCATE {}", code), now), Err("code already used"));
+    }
+
+    #[test]
+    fn test_state_changes_need_a_secret() {
+        let now = Utc.with_ymd_and_hms(2026, 5, 1, 12, 0, 0).unwrap();
+        let config = SmsCommandConfig {
+            enabled: true,
+            allowed_senders: vec!["+15550100".to_string()],
+            ..SmsCommandConfig::default()
+        };
+        let mut state = SmsCommandState::default();
+
+        assert_eq!(verify(&config, &mut state, "+15550100", "STATUS", now), Ok(SmsCommand::Status));
+        assert_eq!(verify(&config, &mut state, "+15550100", "RECORD OFF", now), Err("no secret configured for this command"));
+        assert_eq!(verify(&config, &mut state, "+15550100", "STREAM ON", now), Err("no secret configured for this command"));
+    }
+}
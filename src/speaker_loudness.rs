@@ -0,0 +1,229 @@
+//! Keeps alerts audible whatever the surroundings and whatever the unit.
+//! The microphone measures the ambient noise while the speaker is quiet,
+//! and alerts play louder in a noisy place and softer in a quiet one,
+//! within configured bounds. A calibration measures how loud the device's
+//! speaker actually is, so units with weaker or stronger speakers are
+//! evened out.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::audio_meter::AudioLevelHandle;
+
+/// Calibration never corrects a speaker by more than this
+pub const MAX_CALIBRATION_DB: f32 = 12.0;
+
+/// Tones played during calibration
+pub const CALIBRATION_FREQUENCIES_HZ: [u32; 3] = [500, 1000, 2000];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerLoudnessConfig {
+    /// Follow the ambient noise level
+    pub adaptive: bool,
+    /// Ambient level at or below which alerts play at `min_gain_db`
+    pub quiet_ambient_db: f32,
+    /// Ambient level at or above which alerts play at `max_gain_db`
+    pub loud_ambient_db: f32,
+    pub min_gain_db: f32,
+    pub max_gain_db: f32,
+    /// Ambient is the median level over this window
+    pub ambient_window_seconds: u64,
+    /// Level the calibration tones should reach at the microphone
+    pub calibration_target_db: f32,
+}
+
+impl Default for SpeakerLoudnessConfig {
+    fn default() -> Self {
+        Self {
+            adaptive: true,
+            quiet_ambient_db: -55.0,
+            loud_ambient_db: -20.0,
+            min_gain_db: -6.0,
+            max_gain_db: 12.0,
+            ambient_window_seconds: 30,
+            calibration_target_db: -24.0,
+        }
+    }
+}
+
+impl SpeakerLoudnessConfig {
+    /// Gain for alerts at `ambient_db`, rising linearly from the quiet to
+    /// the loud level
+    pub fn adaptive_gain_db(&self, ambient_db: f32) -> f32 {
+        let span = self.loud_ambient_db - self.quiet_ambient_db;
+        if span <= 0.0 {
+            return self.min_gain_db;
+        }
+        let fraction = ((ambient_db - self.quiet_ambient_db) / span).clamp(0.0, 1.0);
+        self.min_gain_db + fraction * (self.max_gain_db - self.min_gain_db)
+    }
+}
+
+/// Average of levels in dB, taken over their power rather than the dB values
+pub fn mean_db(levels: &[f32]) -> Option<f32> {
+    if levels.is_empty() {
+        return None;
+    }
+    let power = levels.iter().map(|db| 10f32.powf(db / 10.0)).sum::<f32>() / levels.len() as f32;
+    Some(10.0 * power.log10())
+}
+
+/// Mean input level over `duration`, sampled every 100ms
+pub async fn sample_level(meter: &AudioLevelHandle, duration: Duration) -> Option<f32> {
+    let mut levels = Vec::new();
+    let started = Instant::now();
+    while started.elapsed() < duration {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        levels.push(meter.level().rms_db);
+    }
+    mean_db(&levels)
+}
+
+/// A calibration tone at full scale, rendered to a temporary file
+pub async fn render_tone(frequency_hz: u32, duration: Duration) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!("calibration_{}_{}.wav", frequency_hz, Uuid::new_v4()));
+    let status = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-nostdin", "-y", "-f", "lavfi"])
+        .arg("-i").arg(format!("sine=frequency={}:duration={:.1}:sample_rate=48000", frequency_hz, duration.as_secs_f32()))
+        .arg(&path)
+        .status()
+        .await
+        .context("Failed to run ffmpeg for the calibration tone")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Failed to render {} Hz calibration tone", frequency_hz));
+    }
+    Ok(path)
+}
+
+/// Ambient noise over a sliding window. Fed only while the speaker is
+/// silent, so alerts don't raise their own volume.
+#[derive(Debug)]
+pub struct AmbientTracker {
+    window: Duration,
+    samples: VecDeque<(Instant, f32)>,
+}
+
+impl AmbientTracker {
+    pub fn new(window: Duration) -> Self {
+        Self { window, samples: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, now: Instant, level_db: f32) {
+        self.samples.push_back((now, level_db));
+        while self.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > self.window) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The median of the window, so a door slam or a shout doesn't count
+    pub fn ambient_db(&self) -> Option<f32> {
+        let mut levels: Vec<f32> = self.samples.iter().map(|(_, db)| *db).collect();
+        if levels.is_empty() {
+            return None;
+        }
+        levels.sort_by(|a, b| a.total_cmp(b));
+        Some(levels[levels.len() / 2])
+    }
+}
+
+/// The microphone level one calibration tone reached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToneResponse {
+    pub frequency_hz: u32,
+    /// At the microphone
+    pub level_db: f32,
+    /// Gain the tone was played at
+    pub gain_db: f32,
+}
+
+/// This unit's measured speaker response, kept across restarts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpeakerCalibration {
+    pub calibrated_at: Option<DateTime<Utc>>,
+    pub ambient_db: Option<f32>,
+    pub response: Vec<ToneResponse>,
+    /// Added to every alert so this unit reaches the calibration target
+    pub offset_db: f32,
+}
+
+impl SpeakerCalibration {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("speaker_calibration.json")
+    }
+
+    pub async fn load() -> Self {
+        tokio::fs::read_to_string(Self::default_path()).await.ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let path = Self::default_path();
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Calibration from the measured tones. A tone that barely rose above
+    /// the ambient noise is unreliable, so the calibration fails rather
+    /// than boost the speaker to the limit.
+    pub fn from_response(response: Vec<ToneResponse>, ambient_db: f32, target_db: f32, now: DateTime<Utc>) -> Result<Self> {
+        let heard: Vec<f32> = response.iter().map(|tone| tone.level_db).collect();
+        let heard = mean_db(&heard)
+            .ok_or_else(|| anyhow::anyhow!("No calibration tones were measured"))?;
+        if heard < ambient_db + 6.0 {
+            return Err(anyhow::anyhow!(
+                "Calibration tones ({:.1} dB) were not clearly above the ambient noise ({:.1} dB)", heard, ambient_db
+            ));
+        }
+        // What the speaker reaches at 0 dB gain
+        let unity: Vec<f32> = response.iter().map(|tone| tone.level_db - tone.gain_db).collect();
+        let measured = mean_db(&unity).unwrap_or(heard);
+
+        Ok(Self {
+            calibrated_at: Some(now),
+            ambient_db: Some(ambient_db),
+            response,
+            offset_db: (target_db - measured).clamp(-MAX_CALIBRATION_DB, MAX_CALIBRATION_DB),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gain_follows_ambient_and_calibration_evens_out_speakers() {
+        let config = SpeakerLoudnessConfig::default();
+        assert_eq!(config.adaptive_gain_db(-70.0), -6.0);
+        assert_eq!(config.adaptive_gain_db(-10.0), 12.0);
+        assert!((config.adaptive_gain_db(-37.5) - 3.0).abs() < 0.01);
+
+        // A shout in an otherwise quiet room doesn't move the ambient level
+        let start = Instant::now();
+        let mut tracker = AmbientTracker::new(Duration::from_secs(30));
+        for (second, level) in [-50.0, -52.0, -10.0, -51.0, -49.0].into_iter().enumerate() {
+            tracker.push(start + Duration::from_secs(second as u64), level);
+        }
+        assert_eq!(tracker.ambient_db(), Some(-50.0));
+        tracker.push(start + Duration::from_secs(60), -30.0);
+        assert_eq!(tracker.ambient_db(), Some(-30.0));
+
+        let tone = |frequency_hz, level_db| ToneResponse { frequency_hz, level_db, gain_db: 0.0 };
+        let quiet_unit = SpeakerCalibration::from_response(vec![tone(500, -30.0), tone(1000, -30.0), tone(2000, -30.0)], -55.0, -24.0, Utc::now()).unwrap();
+        assert!((quiet_unit.offset_db - 6.0).abs() < 0.01);
+        assert!(SpeakerCalibration::from_response(vec![tone(1000, -52.0)], -55.0, -24.0, Utc::now()).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Which device subsystems start with the device. Long-running modes start
+//! everything up front; one-shot CLI commands start a subsystem the first
+//! time something needs it, so `status` doesn't wait on GPS or camera probes.
+
+use anyhow::Result;
+use std::future::Future;
+use tokio::sync::OnceCell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    /// Hardware events, the network monitor, status reporting and the
+    /// microphone level meter
+    Monitoring,
+    /// GPS fixes for recordings, incidents and status
+    Gps,
+    /// Resource limits and temp file cleanup
+    Resources,
+    /// The pre-incident buffer, restored from disk and kept rolling
+    PreIncidentBuffer,
+    /// Probing each camera and lowering qualities it can't capture
+    CameraLimits,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::Monitoring,
+        Subsystem::Gps,
+        Subsystem::Resources,
+        Subsystem::PreIncidentBuffer,
+        Subsystem::CameraLimits,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::Monitoring => "monitoring",
+            Subsystem::Gps => "gps",
+            Subsystem::Resources => "resources",
+            Subsystem::PreIncidentBuffer => "pre_incident_buffer",
+            Subsystem::CameraLimits => "camera_limits",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The subsystems to start while the device is being built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subsystems(u8);
+
+impl Subsystems {
+    pub fn all() -> Self {
+        Subsystem::ALL.iter().fold(Self::on_demand(), |set, subsystem| set.with(*subsystem))
+    }
+
+    /// Start nothing up front
+    pub fn on_demand() -> Self {
+        Self(0)
+    }
+
+    pub fn with(self, subsystem: Subsystem) -> Self {
+        Self(self.0 | subsystem.bit())
+    }
+
+    pub fn contains(&self, subsystem: Subsystem) -> bool {
+        self.0 & subsystem.bit() != 0
+    }
+}
+
+/// Starts each subsystem at most once, however many callers need it
+#[derive(Default)]
+pub struct LazyStart {
+    started: [OnceCell<()>; Subsystem::ALL.len()],
+}
+
+impl LazyStart {
+    /// Run `start` unless the subsystem is already up. Callers racing to
+    /// start it wait for the first; a start that fails is retried next time.
+    pub async fn ensure<F, Fut>(&self, subsystem: Subsystem, start: F) -> Result<()>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        self.started[subsystem as usize].get_or_try_init(start).await?;
+        Ok(())
+    }
+
+    /// Record a subsystem started by other means
+    pub fn mark_started(&self, subsystem: Subsystem) {
+        let _ = self.started[subsystem as usize].set(());
+    }
+
+    pub fn is_started(&self, subsystem: Subsystem) -> bool {
+        self.started[subsystem as usize].initialized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_subsystem_sets() {
+        let set = Subsystems::on_demand().with(Subsystem::Monitoring);
+        assert!(set.contains(Subsystem::Monitoring));
+        assert!(!set.contains(Subsystem::Gps));
+        assert!(Subsystem::ALL.iter().all(|subsystem| Subsystems::all().contains(*subsystem)));
+    }
+
+    #[tokio::test]
+    async fn test_subsystem_starts_once_and_retries_failures() {
+        let lazy = LazyStart::default();
+        let attempts = AtomicUsize::new(0);
+
+        let failed = lazy.ensure(Subsystem::Gps, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            anyhow::bail!("no GPS device")
+        }).await;
+        assert!(failed.is_err());
+        assert!(!lazy.is_started(Subsystem::Gps));
+
+        for _ in 0..2 {
+            lazy.ensure(Subsystem::Gps, || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }).await.unwrap();
+        }
+        assert!(lazy.is_started(Subsystem::Gps));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -50,7 +50,7 @@ impl StatusReporter {
         
         let heartbeat = serde_json::json!({
             "device_id": device_id,
-            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "timestamp": crate::simulation::faults::now().to_rfc3339(),
             "uptime": self.get_uptime(),
         });
 
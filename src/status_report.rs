@@ -0,0 +1,290 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::MonitoringConfig;
+use crate::device::DeviceStatus;
+use crate::event_bus::{BusEvent, IncidentEvent, RecordingEvent, Topic};
+use crate::hardware::HardwareEvent;
+
+/// Topics carrying events that warrant an immediate status push
+pub const PUSH_TOPICS: &[Topic] = &[Topic::Hardware, Topic::Incidents, Topic::Recording];
+
+/// Pause after a significant event before pushing, so a burst (incident
+/// triggered, then recording started) goes out as one report
+pub const PUSH_SETTLE: Duration = Duration::from_secs(1);
+
+/// Conditions the hardware monitor re-emits while they last (low battery and
+/// full storage, every few seconds) push at most once per this long
+pub const REPEATED_PUSH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Changes smaller than this don't count, so sensor noise doesn't turn
+/// every report into a battery/temperature update
+const NOISE_TOLERANCE: &[(&str, f64)] = &[
+    ("battery_level", 1.0),
+    ("temperature", 0.5),
+];
+
+/// Fields of `DeviceStatus` that changed since the last acknowledged report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusDelta {
+    pub device_id: String,
+    /// `last_seen` of the acknowledged report this applies on top of; the
+    /// server answers with a conflict if it doesn't have that report
+    pub base: DateTime<Utc>,
+    pub changed: Map<String, Value>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatusReport {
+    Full(DeviceStatus),
+    Delta(StatusDelta),
+}
+
+/// Turns successive statuses into deltas against the last report the
+/// backend acknowledged, with a full report every `full_every` reports to
+/// recover from any drift
+#[derive(Debug)]
+pub struct StatusDeltaEncoder {
+    acked: Option<(DateTime<Utc>, Map<String, Value>)>,
+    deltas_since_full: u32,
+    full_every: u32,
+}
+
+impl StatusDeltaEncoder {
+    pub fn new(full_every: u32) -> Self {
+        Self {
+            acked: None,
+            deltas_since_full: 0,
+            full_every,
+        }
+    }
+
+    pub fn encode(&self, status: &DeviceStatus) -> Result<StatusReport> {
+        let Some((base, acked)) = &self.acked else {
+            return Ok(StatusReport::Full(status.clone()));
+        };
+        if self.deltas_since_full >= self.full_every {
+            return Ok(StatusReport::Full(status.clone()));
+        }
+
+        let current = to_map(status)?;
+        let changed = current.into_iter()
+            .filter(|(key, value)| key == "last_seen" || acked.get(key).map_or(true, |old| significant(key, old, value)))
+            .collect();
+        Ok(StatusReport::Delta(StatusDelta {
+            device_id: status.device_id.clone(),
+            base: *base,
+            changed,
+        }))
+    }
+
+    /// Record that the backend accepted `report`
+    pub fn acknowledge(&mut self, report: &StatusReport) -> Result<()> {
+        match report {
+            StatusReport::Full(status) => {
+                self.acked = Some((status.last_seen, to_map(status)?));
+                self.deltas_since_full = 0;
+            }
+            StatusReport::Delta(delta) => {
+                let Some((base, acked)) = &mut self.acked else {
+                    return Ok(());
+                };
+                // Fields inside the noise tolerance keep their acked value,
+                // so slow drift still shows up once it adds up
+                acked.extend(delta.changed.clone());
+                if let Some(last_seen) = delta.changed.get("last_seen") {
+                    *base = serde_json::from_value(last_seen.clone())?;
+                }
+                self.deltas_since_full += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the acknowledged report, so the next one is sent in full
+    pub fn reset(&mut self) {
+        self.acked = None;
+        self.deltas_since_full = 0;
+    }
+}
+
+fn to_map(status: &DeviceStatus) -> Result<Map<String, Value>> {
+    match serde_json::to_value(status).context("Failed to serialize status")? {
+        Value::Object(map) => Ok(map),
+        _ => Err(anyhow::anyhow!("Device status didn't serialize to an object")),
+    }
+}
+
+fn significant(key: &str, old: &Value, new: &Value) -> bool {
+    let tolerance = NOISE_TOLERANCE.iter().find(|(field, _)| *field == key).map(|(_, t)| *t);
+    match (tolerance, old.as_f64(), new.as_f64()) {
+        (Some(tolerance), Some(old), Some(new)) => (old - new).abs() >= tolerance,
+        _ => old != new,
+    }
+}
+
+/// How long to wait before the next status report: often while recording or
+/// during an incident, rarely while idle on battery
+pub fn report_interval(monitoring: &MonitoringConfig, status: &DeviceStatus) -> Duration {
+    let seconds = if status.recording || status.incident_active {
+        monitoring.active_checkin_interval_seconds
+    } else if !status.is_charging {
+        monitoring.idle_checkin_interval_seconds
+    } else {
+        monitoring.checkin_interval_seconds
+    };
+    Duration::from_secs(seconds.max(5))
+}
+
+/// Whether `event` changes the status enough that dashboards shouldn't wait
+/// for the next periodic report
+pub fn warrants_push(event: &BusEvent) -> bool {
+    matches!(
+        event,
+        BusEvent::Recording(RecordingEvent::Started { .. } | RecordingEvent::Stopped | RecordingEvent::Paused | RecordingEvent::Resumed | RecordingEvent::RemainingLow { .. })
+            | BusEvent::Incident(IncidentEvent::Triggered { .. } | IncidentEvent::Ended { .. })
+            | BusEvent::Hardware(
+                HardwareEvent::BatteryLow { .. }
+                    | HardwareEvent::BatteryCritical { .. }
+                    | HardwareEvent::StorageFull
+                    | HardwareEvent::TamperDetected
+            )
+    )
+}
+
+/// Decides which events push a report, letting a repeated hardware
+/// condition through once rather than on every emission
+#[derive(Debug, Default)]
+pub struct PushDebounce {
+    last_pushed: HashMap<&'static str, Instant>,
+}
+
+impl PushDebounce {
+    pub fn should_push(&mut self, event: &BusEvent, now: Instant) -> bool {
+        if !warrants_push(event) {
+            return false;
+        }
+        let Some(kind) = repeated_condition(event) else {
+            return true;
+        };
+        match self.last_pushed.get(kind) {
+            Some(at) if now.duration_since(*at) < REPEATED_PUSH_INTERVAL => false,
+            _ => {
+                self.last_pushed.insert(kind, now);
+                true
+            }
+        }
+    }
+}
+
+fn repeated_condition(event: &BusEvent) -> Option<&'static str> {
+    match event {
+        BusEvent::Hardware(HardwareEvent::BatteryLow { .. }) => Some("battery_low"),
+        BusEvent::Hardware(HardwareEvent::BatteryCritical { .. }) => Some("battery_critical"),
+        BusEvent::Hardware(HardwareEvent::StorageFull) => Some("storage_full"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::device::StorageInfo;
+
+    fn status(battery_level: f32, recording: bool) -> DeviceStatus {
+        DeviceStatus {
+            device_id: "dev-1".to_string(),
+            online: true,
+            recording,
+            battery_level,
+            storage_info: StorageInfo { total: 100, used: 10, available: 90, recording_space: 80 },
+            temperature: 35.0,
+            is_charging: false,
+            last_seen: Utc::now(),
+            location: None,
+            incident_active: false,
+            stealth_mode: false,
+            night_mode: false,
+            recording_performance: None,
+            unhealthy_services: Vec::new(),
+            not_ready_for_duty: Vec::new(),
+            hardware_mismatch: false,
+            deep_sleep: false,
+            recording_remaining: None,
+            recording_paused: false,
+            muted: false,
+        }
+    }
+
+    #[test]
+    fn test_delta_carries_only_changed_fields() {
+        let mut encoder = StatusDeltaEncoder::new(10);
+        let first = encoder.encode(&status(80.0, false)).unwrap();
+        assert!(matches!(first, StatusReport::Full(_)));
+        encoder.acknowledge(&first).unwrap();
+
+        let StatusReport::Delta(delta) = encoder.encode(&status(79.6, true)).unwrap() else {
+            panic!("expected a delta after an acknowledged report");
+        };
+        let mut keys: Vec<_> = delta.changed.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["last_seen", "recording"]);
+    }
+
+    #[test]
+    fn test_full_report_after_reset_or_limit() {
+        let mut encoder = StatusDeltaEncoder::new(2);
+        encoder.acknowledge(&StatusReport::Full(status(80.0, false))).unwrap();
+        for _ in 0..2 {
+            let report = encoder.encode(&status(80.0, false)).unwrap();
+            assert!(matches!(report, StatusReport::Delta(_)));
+            encoder.acknowledge(&report).unwrap();
+        }
+        assert!(matches!(encoder.encode(&status(80.0, false)).unwrap(), StatusReport::Full(_)));
+
+        encoder.acknowledge(&StatusReport::Full(status(80.0, false))).unwrap();
+        encoder.reset();
+        assert!(matches!(encoder.encode(&status(80.0, false)).unwrap(), StatusReport::Full(_)));
+    }
+
+    #[test]
+    fn test_interval_adapts_to_activity() {
+        let monitoring = Config::default().monitoring;
+        let idle = report_interval(&monitoring, &status(80.0, false));
+        let active = report_interval(&monitoring, &status(80.0, true));
+        assert!(active < idle);
+
+        let mut charging = status(80.0, false);
+        charging.is_charging = true;
+        assert_eq!(report_interval(&monitoring, &charging), Duration::from_secs(monitoring.checkin_interval_seconds));
+    }
+
+    #[test]
+    fn test_only_significant_events_push() {
+        assert!(warrants_push(&BusEvent::Recording(RecordingEvent::Started { incident_id: None })));
+        assert!(warrants_push(&BusEvent::Hardware(HardwareEvent::BatteryLow { level: 15.0 })));
+        assert!(warrants_push(&BusEvent::Hardware(HardwareEvent::StorageFull)));
+        assert!(!warrants_push(&BusEvent::Hardware(HardwareEvent::MotionDetected { intensity: 0.4 })));
+        assert!(!warrants_push(&BusEvent::Incident(IncidentEvent::Acknowledged { incident_id: "i-1".to_string() })));
+    }
+
+    #[test]
+    fn test_repeated_conditions_push_once() {
+        let mut debounce = PushDebounce::default();
+        let start = Instant::now();
+        let low = BusEvent::Hardware(HardwareEvent::BatteryLow { level: 15.0 });
+        let started = BusEvent::Recording(RecordingEvent::Started { incident_id: None });
+
+        assert!(debounce.should_push(&low, start));
+        assert!(!debounce.should_push(&low, start + Duration::from_secs(5)));
+        assert!(debounce.should_push(&BusEvent::Hardware(HardwareEvent::StorageFull), start + Duration::from_secs(5)));
+        assert!(debounce.should_push(&started, start + Duration::from_secs(5)));
+        assert!(debounce.should_push(&started, start + Duration::from_secs(6)));
+        assert!(debounce.should_push(&low, start + REPEATED_PUSH_INTERVAL));
+    }
+}
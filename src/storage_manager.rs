@@ -2,13 +2,16 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use std::path::{Path, PathBuf};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::device::BodycamDevice;
-use crate::media::{MediaFileInfo, StorageBreakdown};
+use crate::media::{MediaFileInfo, RecordingSegment, StorageBreakdown};
+use crate::legal_hold::LegalHolds;
+use crate::integrity::IntegrityManager;
+use crate::recording_index::RecordingIndex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeletedFileRecord {
@@ -21,6 +24,62 @@ pub struct DeletedFileRecord {
     pub device_id: String,
 }
 
+/// An uploaded file the server has confirmed by checksum. It becomes
+/// deletable once the configured grace period has passed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmedUpload {
+    pub file_path: String,
+    pub sha256: String,
+    pub confirmed_at: DateTime<Utc>,
+    /// Sidecar files (e.g. segment metadata) removed along with it
+    pub related_files: Vec<String>,
+}
+
+/// Serializes access to the confirmed upload ledger across upload paths
+static CONFIRMED_UPLOADS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Record that the server verified `file_path` with the given SHA-256. This
+/// is the only way an uploaded recording becomes eligible for deletion.
+pub async fn record_confirmed_upload(file_path: &Path, sha256: &str, related_files: Vec<String>) -> Result<()> {
+    let _guard = CONFIRMED_UPLOADS_LOCK.lock().await;
+    RecordingIndex::open()?
+        .confirm_upload(&ConfirmedUpload {
+            file_path: file_path.to_string_lossy().to_string(),
+            sha256: sha256.to_string(),
+            confirmed_at: Utc::now(),
+            related_files,
+        })
+        .context("Failed to record confirmed upload")
+}
+
+/// Paths of every upload the server has confirmed, resolved so they match
+/// however the caller spells them. Anything else may be the only copy.
+pub async fn confirmed_upload_paths() -> Result<HashSet<PathBuf>> {
+    let _guard = CONFIRMED_UPLOADS_LOCK.lock().await;
+    Ok(RecordingIndex::open()?
+        .confirmed_uploads()?
+        .into_iter()
+        .map(|upload| resolve(Path::new(&upload.file_path)))
+        .collect())
+}
+
+pub fn is_confirmed(confirmed: &HashSet<PathBuf>, path: &Path) -> bool {
+    confirmed.contains(&resolve(path))
+}
+
+fn resolve(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Split confirmed uploads into those past the grace period and those still waiting
+fn due_for_deletion(
+    uploads: Vec<ConfirmedUpload>,
+    grace: chrono::Duration,
+    now: DateTime<Utc>,
+) -> (Vec<ConfirmedUpload>, Vec<ConfirmedUpload>) {
+    uploads.into_iter().partition(|u| now - u.confirmed_at >= grace)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageManager {
     device_id: String,
@@ -28,6 +87,11 @@ pub struct StorageManager {
     deleted_files: Vec<DeletedFileRecord>,
     max_storage_gb: u64,
     cleanup_threshold_gb: u64,
+    /// Over the threshold with nothing confirmed left to delete
+    #[serde(skip)]
+    full: bool,
+    #[serde(skip)]
+    full_reported: bool,
 }
 
 impl StorageManager {
@@ -41,13 +105,23 @@ impl StorageManager {
             deleted_files: Vec::new(),
             max_storage_gb,
             cleanup_threshold_gb,
+            full: false,
+            full_reported: false,
         }
     }
 
     pub async fn check_storage_and_cleanup(&mut self) -> Result<Vec<DeletedFileRecord>> {
+        let mut deleted = match self.delete_confirmed_uploads().await {
+            Ok(deleted) => deleted,
+            Err(e) => {
+                tracing::error!("Failed to clean up confirmed uploads: {}", e);
+                Vec::new()
+            }
+        };
+
         let media_dir = std::env::current_dir()?.join("media");
         if !media_dir.exists() {
-            return Ok(Vec::new());
+            return Ok(deleted);
         }
 
         let total_storage = self.get_total_storage_usage(&media_dir).await?;
@@ -56,10 +130,89 @@ impl StorageManager {
 
         if total_storage > cleanup_bytes {
             let bytes_to_free = total_storage - cleanup_bytes + (100 * 1024 * 1024); // Free extra 100MB
-            self.cleanup_oldest_files(bytes_to_free).await
+            deleted.extend(self.cleanup_oldest_files(bytes_to_free).await?);
         } else {
-            Ok(Vec::new())
+            self.full = false;
+            self.full_reported = false;
+        }
+
+        Ok(deleted)
+    }
+
+    /// True the first time cleanup finds storage over the threshold with
+    /// only unconfirmed recordings left, so StorageFull is raised once
+    /// rather than on every pass
+    pub fn take_full_alert(&mut self) -> bool {
+        if self.full && !self.full_reported {
+            self.full_reported = true;
+            return true;
         }
+        false
+    }
+
+    /// Delete uploads the server confirmed at least `deletion_grace_hours`
+    /// ago, unless the site keeps local copies or the file is on legal hold.
+    /// A file that no longer matches its confirmed checksum is kept.
+    pub async fn delete_confirmed_uploads(&mut self) -> Result<Vec<DeletedFileRecord>> {
+        if self.config.storage.keep_local_copy {
+            return Ok(Vec::new());
+        }
+
+        let _guard = CONFIRMED_UPLOADS_LOCK.lock().await;
+        let mut index = RecordingIndex::open()?;
+        let grace = chrono::Duration::hours(self.config.storage.deletion_grace_hours as i64);
+        let (due, mut remaining) = due_for_deletion(index.confirmed_uploads()?, grace, Utc::now());
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let holds = LegalHolds::load().await;
+        let mut deleted = Vec::new();
+
+        for upload in due {
+            let path = PathBuf::from(&upload.file_path);
+            if !path.exists() {
+                continue;
+            }
+
+            if holds.is_held(&path) {
+                remaining.push(upload);
+                continue;
+            }
+
+            match IntegrityManager::calculate_file_hash(&path).await {
+                Ok(hash) if hash.eq_ignore_ascii_case(&upload.sha256) => {}
+                Ok(hash) => {
+                    tracing::warn!("Keeping {}: changed since upload was confirmed ({} != {})",
+                        upload.file_path, hash, upload.sha256);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("Keeping {} for now, can't verify it: {}", upload.file_path, e);
+                    remaining.push(upload);
+                    continue;
+                }
+            }
+
+            match self.delete_uploaded_file(&upload.file_path).await {
+                Ok(record) => {
+                    for related in &upload.related_files {
+                        let _ = fs::remove_file(related).await;
+                    }
+                    if let Err(e) = index.remove_file(&upload.file_path) {
+                        tracing::warn!("Failed to remove {} from the recording index: {:#}", upload.file_path, e);
+                    }
+                    deleted.push(record);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to delete confirmed upload {}: {}", upload.file_path, e);
+                    remaining.push(upload);
+                }
+            }
+        }
+
+        index.replace_confirmed_uploads(&remaining)?;
+        Ok(deleted)
     }
 
     async fn get_total_storage_usage(&self, media_dir: &Path) -> Result<u64> {
@@ -79,10 +232,13 @@ impl StorageManager {
 
     async fn cleanup_oldest_files(&mut self, bytes_to_free: u64) -> Result<Vec<DeletedFileRecord>> {
         let media_dir = std::env::current_dir()?.join("media");
-        let mut files = self.get_sorted_media_files(&media_dir).await?;
+        let files = self.get_sorted_media_files(&media_dir).await?;
+        let holds = LegalHolds::load().await;
+        let confirmed = confirmed_upload_paths().await?;
         
         let mut deleted_records = Vec::new();
         let mut freed_bytes = 0;
+        let mut unconfirmed = 0;
 
         for file_info in files {
             if freed_bytes >= bytes_to_free {
@@ -90,6 +246,15 @@ impl StorageManager {
             }
 
             let file_path = PathBuf::from(&file_info.path);
+            if holds.is_held(&file_path) {
+                tracing::debug!("Skipping {} during storage cleanup: under legal hold", file_info.path);
+                continue;
+            }
+            if !is_confirmed(&confirmed, &file_path) {
+                unconfirmed += 1;
+                continue;
+            }
+
             if file_path.exists() {
                 let record = DeletedFileRecord {
                     file_path: file_info.path.clone(),
@@ -115,6 +280,13 @@ impl StorageManager {
             }
         }
 
+        self.full = freed_bytes < bytes_to_free;
+        if self.full {
+            tracing::warn!("Storage over its cleanup threshold; kept {} recordings the server hasn't confirmed", unconfirmed);
+        } else {
+            self.full_reported = false;
+        }
+
         Ok(deleted_records)
     }
 
@@ -134,6 +306,10 @@ impl StorageManager {
             return Err(anyhow::anyhow!("File not found: {}", file_path));
         }
 
+        if LegalHolds::load().await.is_held(&path) {
+            return Err(anyhow::anyhow!("File {} is under legal hold", file_path));
+        }
+
         let metadata = fs::metadata(&path).await?;
         
         // Parse incident_id and quality from filename
@@ -179,6 +355,52 @@ impl StorageManager {
         Ok(record)
     }
 
+    /// Delete loop footage to make room in the continuous recording loop.
+    /// Footage under legal hold is kept even though it isn't locked.
+    pub async fn overwrite_loop_segments(&mut self, segments: &[&RecordingSegment], index: &mut RecordingIndex) -> Result<Vec<DeletedFileRecord>> {
+        let holds = LegalHolds::load().await;
+        let mut deleted = Vec::new();
+
+        for segment in segments {
+            let path = PathBuf::from(&segment.file_path);
+            if holds.is_held(&path) {
+                tracing::debug!("Not overwriting {}: under legal hold", segment.file_path);
+                continue;
+            }
+
+            let size_bytes = match fs::metadata(&path).await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => segment.file_size.unwrap_or(0),
+            };
+            if let Err(e) = fs::remove_file(&path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::error!("Failed to overwrite loop segment {}: {}", segment.file_path, e);
+                    continue;
+                }
+            }
+            for part in &segment.previous_parts {
+                let _ = fs::remove_file(part).await;
+            }
+            if let Err(e) = index.remove_file(&segment.file_path) {
+                tracing::warn!("Failed to remove {} from the recording index: {:#}", segment.file_path, e);
+            }
+
+            let record = DeletedFileRecord {
+                file_path: segment.file_path.clone(),
+                incident_id: None,
+                quality: format!("{:?}", segment.quality),
+                size_bytes,
+                deleted_at: Utc::now(),
+                deletion_reason: "loop_overwrite".to_string(),
+                device_id: self.device_id.clone(),
+            };
+            self.deleted_files.push(record.clone());
+            deleted.push(record);
+        }
+
+        Ok(deleted)
+    }
+
     pub fn get_deleted_files(&self) -> &[DeletedFileRecord] {
         &self.deleted_files
     }
@@ -219,4 +441,43 @@ impl StorageManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upload(path: &str, hours_ago: i64, now: DateTime<Utc>) -> ConfirmedUpload {
+        ConfirmedUpload {
+            file_path: path.to_string(),
+            sha256: "abc".to_string(),
+            confirmed_at: now - chrono::Duration::hours(hours_ago),
+            related_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_grace_period() {
+        let now = Utc::now();
+        let uploads = vec![upload("old.mp4", 30, now), upload("new.mp4", 2, now), upload("edge.mp4", 24, now)];
+
+        let (due, waiting) = due_for_deletion(uploads, chrono::Duration::hours(24), now);
+        let due: Vec<&str> = due.iter().map(|u| u.file_path.as_str()).collect();
+        assert_eq!(due, vec!["old.mp4", "edge.mp4"]);
+        assert_eq!(waiting.len(), 1);
+
+        // No grace period deletes as soon as the server confirms
+        let (due, _) = due_for_deletion(vec![upload("now.mp4", 0, now)], chrono::Duration::zero(), now);
+        assert_eq!(due.len(), 1);
+    }
+
+    #[test]
+    fn test_full_alert_raised_once() {
+        let mut manager = StorageManager::new("device".to_string(), Config::default());
+        assert!(!manager.take_full_alert());
+
+        manager.full = true;
+        assert!(manager.take_full_alert());
+        assert!(!manager.take_full_alert());
+    }
+}
@@ -0,0 +1,146 @@
+//! Glass-to-glass latency of a live stream. Outputs keep the capture's
+//! timestamps, so a media time names the moment a frame was captured once
+//! the capture's start is known. The server reports the media time its
+//! viewers are being sent, and a clock sync puts that on the device clock.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::ffmpeg_progress::EncoderProgress;
+
+/// Clock sync exchanges kept; the one with the shortest round trip is used
+const CLOCK_SAMPLES: usize = 8;
+const CLOCK_SYNC_INTERVAL_SECS: u64 = 30;
+/// A measurement older than this no longer describes the stream
+const STALE_AFTER_SECS: i64 = 30;
+
+#[derive(Debug, Clone, Copy)]
+struct ClockSample {
+    round_trip: Duration,
+    /// Server clock minus device clock
+    offset: Duration,
+}
+
+#[derive(Debug, Default)]
+struct LatencyState {
+    clock: VecDeque<ClockSample>,
+    /// Device time of media time zero
+    epoch: Option<DateTime<Utc>>,
+    latest: Option<(i64, DateTime<Utc>)>,
+}
+
+impl LatencyState {
+    fn offset(&self) -> Option<Duration> {
+        self.clock.iter().min_by_key(|sample| sample.round_trip).map(|sample| sample.offset)
+    }
+}
+
+/// Shared latency state, fed by the capture encoder, the clock sync and the
+/// server's playhead reports
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHandle {
+    state: Arc<Mutex<LatencyState>>,
+}
+
+impl LatencyHandle {
+    /// Progress from the capture encoder. Each report comes a little after
+    /// the packet it describes, so the earliest epoch is the closest.
+    pub fn observe_progress(&self, progress: &EncoderProgress) {
+        let Some(updated_at) = progress.updated_at else { return };
+        if progress.out_time_us == 0 {
+            return;
+        }
+        let epoch = updated_at - Duration::microseconds(progress.out_time_us as i64);
+        let mut state = self.state.lock().unwrap();
+        if state.epoch.map_or(true, |current| epoch < current) {
+            state.epoch = Some(epoch);
+        }
+    }
+
+    /// An NTP-style exchange: `server` was read between `sent` and `received`
+    pub fn record_clock_sample(&self, sent: DateTime<Utc>, server: DateTime<Utc>, received: DateTime<Utc>) {
+        let round_trip = received - sent;
+        let midpoint = sent + round_trip / 2;
+        let mut state = self.state.lock().unwrap();
+        state.clock.push_back(ClockSample { round_trip, offset: server - midpoint });
+        while state.clock.len() > CLOCK_SAMPLES {
+            state.clock.pop_front();
+        }
+    }
+
+    /// The server sent viewers the frame at `media_time_ms` at `server_time`.
+    /// Ignored until both the capture epoch and the clock offset are known.
+    pub fn record_playhead(&self, media_time_ms: u64, server_time: DateTime<Utc>) -> Option<i64> {
+        let mut state = self.state.lock().unwrap();
+        let captured_at = state.epoch? + Duration::milliseconds(media_time_ms as i64);
+        let delivered_at = server_time - state.offset()?;
+        let latency_ms = (delivered_at - captured_at).num_milliseconds().max(0);
+        state.latest = Some((latency_ms, Utc::now()));
+        Some(latency_ms)
+    }
+
+    /// The last glass-to-glass measurement, unless it's stale
+    pub fn latency_ms(&self) -> Option<u64> {
+        let (latency_ms, measured_at) = self.state.lock().unwrap().latest?;
+        (Utc::now() - measured_at < Duration::seconds(STALE_AFTER_SECS)).then_some(latency_ms as u64)
+    }
+
+    /// Forget the epoch and measurements when a stream ends; the clock
+    /// offset still holds
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.epoch = None;
+        state.latest = None;
+    }
+}
+
+/// Keep the server clock offset current while a stream runs
+pub fn spawn_clock_sync(config: Config, latency: LatencyHandle) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let api_client = ApiClient::new(config);
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(CLOCK_SYNC_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let sent = Utc::now();
+            match api_client.get_server_time().await {
+                Ok(server) => latency.record_clock_sample(sent, server, Utc::now()),
+                Err(e) => tracing::debug!("Clock sync for stream latency failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_latency_from_playhead_uses_best_clock_sample() {
+        let latency = LatencyHandle::default();
+        let start = Utc.with_ymd_and_hms(2026, 5, 1, 12, 0, 0).unwrap();
+        let ms = Duration::milliseconds;
+
+        // Encoded 10 s of media by 10.05 s, then 20 s by 20.2 s
+        for (out_time_ms, reported_ms) in [(10_000, 10_050), (20_000, 20_200)] {
+            latency.observe_progress(&EncoderProgress {
+                out_time_us: out_time_ms * 1000,
+                updated_at: Some(start + ms(reported_ms as i64)),
+                ..EncoderProgress::default()
+            });
+        }
+        assert!(latency.record_playhead(0, start).is_none());
+
+        // Server clock runs 2 s ahead; the slow exchange is off by 400 ms
+        latency.record_clock_sample(start, start + ms(2_400), start + ms(1_000));
+        latency.record_clock_sample(start, start + ms(2_050), start + ms(100));
+
+        // Frame captured at 30.05 s on the device, sent to viewers at 31.25 s
+        let sent_at = start + ms(2_000 + 31_250);
+        assert_eq!(latency.record_playhead(30_000, sent_at), Some(1_200));
+    }
+}
@@ -0,0 +1,374 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::fs;
+use tokio::process::{Child, Command};
+
+use crate::api::{ApiClient, StreamGapUploadRequest};
+
+/// Settings for the local copy written alongside a live stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalStreamCopyConfig {
+    pub enabled: bool,
+    pub segment_seconds: u32,
+    /// Segments kept while the stream is healthy. Segments overlapping an
+    /// outage are always kept until they have been reconciled.
+    pub max_segments: usize,
+}
+
+impl Default for LocalStreamCopyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            segment_seconds: 10,
+            max_segments: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    fn overlaps(&self, other: &TimeRange) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalSegment {
+    pub path: PathBuf,
+    pub range: TimeRange,
+}
+
+/// Writes the outgoing encoded stream to short local segments so that
+/// nothing is lost while the network is down
+pub struct LocalStreamCopy {
+    config: LocalStreamCopyConfig,
+    dir: PathBuf,
+    process: Option<Child>,
+    /// Periods the platform output was down, open-ended while still down
+    outages: Vec<(DateTime<Utc>, Option<DateTime<Utc>>)>,
+}
+
+impl LocalStreamCopy {
+    pub async fn start(stream_id: &str, input_url: &str, config: LocalStreamCopyConfig) -> Result<Self> {
+        let dir = std::env::current_dir()?
+            .join("recordings")
+            .join("streams")
+            .join(stream_id);
+        fs::create_dir_all(&dir).await
+            .context("Failed to create local stream copy directory")?;
+
+        // Segment names carry their wall-clock start time so they can be
+        // matched against what the server received
+        let process = Command::new("ffmpeg")
+            .arg("-f").arg("mpegts")
+            .arg("-i").arg(input_url)
+            .arg("-c").arg("copy")
+            .arg("-f").arg("segment")
+            .arg("-segment_time").arg(config.segment_seconds.to_string())
+            .arg("-segment_format").arg("mpegts")
+            .arg("-reset_timestamps").arg("1")
+            .arg("-strftime").arg("1")
+            .arg(dir.join("%s.ts"))
+            .arg("-loglevel").arg("warning")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start local stream copy")?;
+
+        tracing::info!("Writing local copy of stream {} to {}", stream_id, dir.display());
+
+        Ok(Self {
+            config,
+            dir,
+            process: Some(process),
+            outages: Vec::new(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn outage_started(&mut self) {
+        if !matches!(self.outages.last(), Some((_, None))) {
+            self.outages.push((Utc::now(), None));
+        }
+    }
+
+    pub fn outage_ended(&mut self) {
+        if let Some((_, end @ None)) = self.outages.last_mut() {
+            *end = Some(Utc::now());
+        }
+    }
+
+    /// Outages seen so far, with any ongoing one closed at `now`
+    pub fn outages(&self) -> Vec<TimeRange> {
+        let now = Utc::now();
+        self.outages
+            .iter()
+            .map(|(start, end)| TimeRange { start: *start, end: end.unwrap_or(now) })
+            .collect()
+    }
+
+    /// Drop the oldest segments beyond the ring size, keeping any that
+    /// overlap an outage
+    pub async fn prune(&self) -> Result<()> {
+        let segments = self.segments().await?;
+        if segments.len() <= self.config.max_segments {
+            return Ok(());
+        }
+
+        let outages = self.outages();
+        let excess = segments.len() - self.config.max_segments;
+        for segment in segments.iter().take(excess) {
+            if outages.iter().any(|o| o.overlaps(&segment.range)) {
+                continue;
+            }
+            let _ = fs::remove_file(&segment.path).await;
+        }
+
+        Ok(())
+    }
+
+    /// Completed segments ordered by start time. Each runs until the next
+    /// one starts; the last (still being written) is left out.
+    pub async fn segments(&self) -> Result<Vec<LocalSegment>> {
+        let mut starts = Vec::new();
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if let Some(start) = segment_start(&path) {
+                starts.push((start, path));
+            }
+        }
+        starts.sort_by_key(|(start, _)| *start);
+
+        Ok(starts
+            .windows(2)
+            .map(|pair| LocalSegment {
+                path: pair[0].1.clone(),
+                range: TimeRange { start: pair[0].0, end: pair[1].0 },
+            })
+            .collect())
+    }
+
+    /// Stop writing and return every segment, including the final one
+    pub async fn finish(&mut self) -> Result<Vec<LocalSegment>> {
+        if let Some(mut process) = self.process.take() {
+            let _ = process.kill().await;
+            let _ = process.wait().await;
+        }
+        self.outage_ended();
+
+        let mut segments = self.segments().await?;
+        let mut last = None;
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if let Some(start) = segment_start(&path) {
+                if last.as_ref().map(|(s, _)| start > *s).unwrap_or(true) {
+                    last = Some((start, path));
+                }
+            }
+        }
+        if let Some((start, path)) = last {
+            segments.push(LocalSegment { path, range: TimeRange { start, end: Utc::now() } });
+        }
+
+        Ok(segments)
+    }
+}
+
+fn segment_start(path: &Path) -> Option<DateTime<Utc>> {
+    if path.extension()? != "ts" {
+        return None;
+    }
+    let seconds: i64 = path.file_stem()?.to_str()?.parse().ok()?;
+    Utc.timestamp_opt(seconds, 0).single()
+}
+
+/// Local segments the server is missing: anything not fully covered by a
+/// single received range
+pub fn find_gaps(segments: &[LocalSegment], received: &[TimeRange]) -> Vec<LocalSegment> {
+    segments
+        .iter()
+        .filter(|segment| {
+            !received
+                .iter()
+                .any(|r| r.start <= segment.range.start && r.end >= segment.range.end)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Server view reconstructed from local outage records, for when the
+/// backend can't report what it received
+pub fn received_from_outages(stream: TimeRange, outages: &[TimeRange]) -> Vec<TimeRange> {
+    let mut received = Vec::new();
+    let mut cursor = stream.start;
+    for outage in outages {
+        if outage.start > cursor {
+            received.push(TimeRange { start: cursor, end: outage.start });
+        }
+        cursor = cursor.max(outage.end);
+    }
+    if stream.end > cursor {
+        received.push(TimeRange { start: cursor, end: stream.end });
+    }
+    received
+}
+
+/// A segment the server is missing, queued on disk so a failed upload is
+/// retried later, including after a restart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingGap {
+    pub request: StreamGapUploadRequest,
+    pub path: PathBuf,
+    /// Kept on the device after upload, for an incident under legal hold
+    pub keep_local: bool,
+}
+
+/// Stream gap uploads waiting to be sent, one file per gap under `dir`
+pub struct GapUploadQueue {
+    dir: PathBuf,
+}
+
+impl GapUploadQueue {
+    pub fn default_dir() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("stream_gaps")
+    }
+
+    pub fn new() -> Self {
+        Self::at(Self::default_dir())
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub async fn enqueue(&self, gap: &PendingGap) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(self.state_path(gap), serde_json::to_vec(gap)?).await
+            .context("Failed to queue stream gap upload")
+    }
+
+    pub async fn pending(&self) -> Result<Vec<PendingGap>> {
+        let mut gaps = Vec::new();
+        let mut entries = match fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(gaps),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            match serde_json::from_slice::<PendingGap>(&fs::read(entry.path()).await?) {
+                Ok(gap) => gaps.push(gap),
+                Err(e) => tracing::warn!("Ignoring unreadable stream gap {}: {}", entry.path().display(), e),
+            }
+        }
+        gaps.sort_by_key(|gap| gap.request.start);
+        Ok(gaps)
+    }
+
+    /// Upload every queued gap, dropping each once the server has it.
+    /// Returns how many are still waiting.
+    pub async fn upload_pending(&self, api_client: &ApiClient) -> Result<usize> {
+        let mut remaining = 0;
+        for gap in self.pending().await? {
+            if !gap.path.exists() {
+                tracing::warn!("Dropping stream gap upload of {}: file no longer exists", gap.path.display());
+                let _ = fs::remove_file(self.state_path(&gap)).await;
+                continue;
+            }
+            if let Err(e) = api_client.upload_stream_gap(&gap.request, &gap.path).await {
+                tracing::warn!("Failed to upload stream gap {}, will retry: {:#}", gap.path.display(), e);
+                remaining += 1;
+                continue;
+            }
+            if !gap.keep_local {
+                let _ = fs::remove_file(&gap.path).await;
+            }
+            let _ = fs::remove_file(self.state_path(&gap)).await;
+        }
+        Ok(remaining)
+    }
+
+    fn state_path(&self, gap: &PendingGap) -> PathBuf {
+        self.dir.join(format!("{}-{}.json", gap.request.stream_id, gap.request.start.timestamp()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    fn segment(start: i64, end: i64) -> LocalSegment {
+        LocalSegment {
+            path: PathBuf::from(format!("{}.ts", 1_700_000_000 + start)),
+            range: TimeRange { start: at(start), end: at(end) },
+        }
+    }
+
+    #[test]
+    fn test_segment_start_from_name() {
+        assert_eq!(segment_start(Path::new("/tmp/1700000000.ts")), Some(at(0)));
+        assert_eq!(segment_start(Path::new("/tmp/notes.txt")), None);
+    }
+
+    #[test]
+    fn test_gaps_from_outages() {
+        let segments = vec![segment(0, 10), segment(10, 20), segment(20, 30), segment(30, 40)];
+        let outages = vec![TimeRange { start: at(12), end: at(25) }];
+
+        let received = received_from_outages(TimeRange { start: at(0), end: at(40) }, &outages);
+        assert_eq!(received, vec![
+            TimeRange { start: at(0), end: at(12) },
+            TimeRange { start: at(25), end: at(40) },
+        ]);
+
+        let gaps = find_gaps(&segments, &received);
+        assert_eq!(gaps, vec![segment(10, 20), segment(20, 30)]);
+    }
+
+    #[tokio::test]
+    async fn test_gap_queue_keeps_uploads_until_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = GapUploadQueue::at(dir.path().join("stream_gaps"));
+        assert!(queue.pending().await.unwrap().is_empty());
+
+        let gap = PendingGap {
+            request: StreamGapUploadRequest {
+                stream_id: "stream-1".to_string(),
+                incident_id: None,
+                start: at(10),
+                end: at(20),
+                file_size: 4,
+                checksum: "abc".to_string(),
+            },
+            path: dir.path().join("segment.ts"),
+            keep_local: false,
+        };
+        queue.enqueue(&gap).await.unwrap();
+        queue.enqueue(&gap).await.unwrap();
+
+        let pending = queue.pending().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].request.start, at(10));
+        assert_eq!(pending[0].path, gap.path);
+    }
+}
@@ -0,0 +1,843 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::cancellation::Canceller;
+use crate::capture::{AudioSource, VideoSource};
+use crate::config::Config;
+use crate::error_handling::DeviceError;
+use crate::validation::InputValidator;
+use crate::api::{ApiClient, StreamGapUploadRequest};
+use crate::integrity::IntegrityManager;
+
+pub mod outputs;
+pub mod srt;
+pub mod relay;
+pub mod local_copy;
+pub mod viewers;
+pub mod latency;
+pub mod pip;
+
+use outputs::{OutputHealth, OutputStatus, StreamDestinationKind, StreamOutput};
+use relay::RelayFanout;
+use local_copy::{GapUploadQueue, LocalSegment, LocalStreamCopy, PendingGap, TimeRange};
+use viewers::{ViewerPresence, ViewerPresenceHandle};
+use latency::LatencyHandle;
+use srt::{StreamProtocol, StreamProtocolPreference};
+use pip::PipLayout;
+
+/// Live stream quality presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamQuality {
+    Low,
+    Medium,
+    High,
+    Ultra,
+}
+
+impl StreamQuality {
+    pub const ALL: &'static [StreamQuality] = &[
+        StreamQuality::Low,
+        StreamQuality::Medium,
+        StreamQuality::High,
+        StreamQuality::Ultra,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamQuality::Low => "low",
+            StreamQuality::Medium => "medium",
+            StreamQuality::High => "high",
+            StreamQuality::Ultra => "ultra",
+        }
+    }
+}
+
+impl Default for StreamQuality {
+    fn default() -> Self {
+        StreamQuality::Medium
+    }
+}
+
+impl std::fmt::Display for StreamQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for StreamQuality {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL.iter()
+            .copied()
+            .find(|quality| quality.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Invalid quality setting: {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingConfig {
+    pub quality: StreamQuality,
+    pub include_audio: bool,
+    pub bitrate: u32,
+    pub fps: u32,
+    pub resolution: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub stream_id: String,
+    pub incident_id: Option<String>,
+    pub rtmp_url: String,
+    pub stream_key: String,
+    pub srt_url: Option<String>,
+    /// Protocol used for the platform output
+    pub protocol: StreamProtocol,
+    pub status: StreamStatus,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub config: StreamingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StreamStatus {
+    Starting,
+    Active,
+    Stopping,
+    Stopped,
+    Error(String),
+}
+
+pub struct StreamingManager {
+    config: Config,
+    api_client: ApiClient,
+    current_stream: Option<StreamInfo>,
+    /// Captures once and feeds every output through a local UDP relay
+    ffmpeg_process: Option<Child>,
+    outputs: Vec<StreamOutput>,
+    relay: Option<RelayFanout>,
+    /// Capture settings, kept so outputs can be restarted
+    relay_config: Option<StreamingConfig>,
+    /// Local segments of the outgoing stream, reconciled with the server on stop
+    local_copy: Option<LocalStreamCopy>,
+    /// Dispatchers watching and talking back, fed by the server event stream
+    presence: ViewerPresenceHandle,
+    presence_task: Option<tokio::task::JoinHandle<()>>,
+    /// Glass-to-glass latency, from the server's playhead reports
+    latency: LatencyHandle,
+    clock_sync_task: Option<tokio::task::JoinHandle<()>>,
+    event_tx: Option<mpsc::UnboundedSender<StreamEvent>>,
+    /// How a second camera is composed into the stream; changes at runtime
+    pip_layout: PipLayout,
+    /// Tokens for the uploads that reconcile the local copy
+    canceller: Canceller,
+}
+
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    StreamStarted { stream_id: String },
+    StreamStopped { stream_id: String },
+    StreamError { stream_id: String, error: String },
+    OutputFailed { stream_id: String, output: String, error: String },
+    ViewersChanged { stream_id: String, count: u32 },
+    TalkbackStarted { stream_id: String, dispatcher: Option<String> },
+    TalkbackEnded { stream_id: String },
+    BitrateChanged { bitrate: u32 },
+}
+
+impl StreamingManager {
+    pub fn new(config: Config) -> Self {
+        let api_client = ApiClient::new(config.clone());
+        
+        Self {
+            pip_layout: config.streaming.pip.layout,
+            config,
+            api_client,
+            current_stream: None,
+            ffmpeg_process: None,
+            outputs: Vec::new(),
+            relay: None,
+            relay_config: None,
+            local_copy: None,
+            presence: ViewerPresenceHandle::default(),
+            presence_task: None,
+            latency: LatencyHandle::default(),
+            clock_sync_task: None,
+            event_tx: None,
+            canceller: Canceller::new(),
+        }
+    }
+
+    /// Let `canceller` interrupt local copy uploads, e.g. at shutdown
+    pub fn with_canceller(mut self, canceller: Canceller) -> Self {
+        self.canceller = canceller;
+        self
+    }
+
+    pub async fn start_streaming(
+        &mut self,
+        incident_id: Option<String>,
+        quality: StreamQuality,
+        include_audio: bool,
+    ) -> Result<StreamInfo> {
+        // Validate inputs
+        if let Some(ref incident_id) = incident_id {
+            InputValidator::validate_uuid(incident_id)?;
+        }
+        
+        if self.is_streaming() {
+            return Err(anyhow::anyhow!("Already streaming"));
+        }
+
+        if !self.config.is_provisioned() {
+            return Err(DeviceError::NotProvisioned.into());
+        }
+
+        // Get streaming configuration from quality setting
+        let streaming_config = self.get_streaming_config(quality, include_audio);
+
+        // Request streaming URL from server
+        let streaming_response = self.api_client
+            .start_streaming(incident_id.clone(), quality, include_audio)
+            .await
+            .context("Failed to start streaming session")?;
+
+        let protocol = srt::select_protocol(
+            self.config.streaming.protocol,
+            streaming_response.srt_url.is_some(),
+        )?;
+        tracing::info!("Publishing stream over {:?}", protocol);
+
+        let stream_info = StreamInfo {
+            stream_id: streaming_response.stream_id.clone(),
+            incident_id: incident_id.clone(),
+            rtmp_url: streaming_response.rtmp_url,
+            stream_key: streaming_response.stream_key,
+            srt_url: streaming_response.srt_url,
+            protocol,
+            status: StreamStatus::Starting,
+            started_at: chrono::Utc::now(),
+            config: streaming_config.clone(),
+        };
+
+        // Platform output plus any configured extra destinations
+        self.outputs = self.build_outputs(&stream_info, include_audio)?;
+        let relay_config = outputs::relay_config(&self.outputs)
+            .ok_or_else(|| anyhow::anyhow!("No stream outputs configured"))?;
+
+        // Each output keeps a few seconds of encoded stream to replay after a drop
+        let mut relay = RelayFanout::start().await?;
+        let buffer_bytes = (relay_config.bitrate as usize / 8) * self.config.streaming.buffer_size_seconds as usize;
+        for output in &mut self.outputs {
+            output.set_input(relay.add_tap(buffer_bytes)?);
+        }
+
+        if self.config.streaming.local_copy.enabled {
+            let tap = relay.add_tap(buffer_bytes)?;
+            match LocalStreamCopy::start(&stream_info.stream_id, &tap, self.config.streaming.local_copy.clone()).await {
+                Ok(copy) => self.local_copy = Some(copy),
+                Err(e) => tracing::warn!("Streaming without a local copy: {:#}", e),
+            }
+        }
+
+        self.start_ffmpeg_stream(&relay_config, &relay.input_url()).await?;
+
+        let audio = self.config.streaming.audio.clone();
+        for output in &mut self.outputs {
+            if let Err(e) = output.start(&relay_config, &audio).await {
+                // Only the platform output is essential
+                if output.kind() == StreamDestinationKind::Platform {
+                    if let Some(mut process) = self.ffmpeg_process.take() {
+                        let _ = process.kill().await;
+                    }
+                    self.local_copy = None;
+                    return Err(e);
+                }
+                tracing::warn!("{:#}", e);
+            }
+        }
+        self.relay = Some(relay);
+        self.relay_config = Some(relay_config);
+        
+        self.presence_task = Some(viewers::spawn_listener(
+            self.config.clone(),
+            stream_info.stream_id.clone(),
+            self.presence.clone(),
+            self.latency.clone(),
+            self.event_tx.clone(),
+        ));
+        self.clock_sync_task = Some(latency::spawn_clock_sync(self.config.clone(), self.latency.clone()));
+
+        // Update status to active
+        let mut active_stream = stream_info.clone();
+        active_stream.status = StreamStatus::Active;
+        self.current_stream = Some(active_stream.clone());
+
+        // Emit stream started event
+        if let Some(ref event_tx) = self.event_tx {
+            let _ = event_tx.send(StreamEvent::StreamStarted {
+                stream_id: streaming_response.stream_id,
+            });
+        }
+
+        tracing::info!("Live streaming started: {}", active_stream.stream_id);
+        Ok(active_stream)
+    }
+
+    pub async fn stop_streaming(&mut self) -> Result<()> {
+        if !self.is_streaming() {
+            return Err(anyhow::anyhow!("Not currently streaming"));
+        }
+
+        let stream_id = self.current_stream
+            .as_ref()
+            .map(|s| s.stream_id.clone())
+            .unwrap_or_default();
+        let started_at = self.current_stream
+            .as_ref()
+            .map(|s| s.started_at)
+            .unwrap_or_else(chrono::Utc::now);
+        let incident_id = self.current_stream
+            .as_ref()
+            .and_then(|s| s.incident_id.clone());
+
+        if let Some(task) = self.presence_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.clock_sync_task.take() {
+            task.abort();
+        }
+        self.presence.reset();
+        self.latency.reset();
+
+        // Stop outputs, then the capture relay
+        for output in &mut self.outputs {
+            output.stop().await;
+        }
+        self.outputs.clear();
+        self.relay = None;
+        self.relay_config = None;
+
+        if let Some(mut process) = self.ffmpeg_process.take() {
+            tracing::info!("Stopping FFmpeg streaming process");
+            let _ = process.kill().await;
+        }
+
+        // Notify server that streaming has stopped
+        if let Err(e) = self.api_client.stop_streaming(&stream_id).await {
+            tracing::warn!("Failed to notify server of streaming stop: {}", e);
+        }
+
+        if let Some(mut local_copy) = self.local_copy.take() {
+            match local_copy.finish().await {
+                Ok(segments) => {
+                    let stream = TimeRange { start: started_at, end: chrono::Utc::now() };
+                    let outages = local_copy.outages();
+                    let config = self.config.clone();
+                    let stream_id = stream_id.clone();
+                    let cancel = self.canceller.token();
+                    tokio::spawn(async move {
+                        if let Err(e) = reconcile_local_copy(config, stream_id, incident_id, stream, outages, segments, cancel).await {
+                            tracing::error!("Failed to reconcile local stream copy: {:#}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::error!("Failed to finish local stream copy: {:#}", e),
+            }
+        }
+
+        // Update stream status
+        if let Some(ref mut stream) = self.current_stream {
+            stream.status = StreamStatus::Stopped;
+        }
+
+        // Emit stream stopped event
+        if let Some(ref event_tx) = self.event_tx {
+            let _ = event_tx.send(StreamEvent::StreamStopped { stream_id: stream_id.clone() });
+        }
+
+        self.current_stream = None;
+        tracing::info!("Live streaming stopped: {}", stream_id);
+        Ok(())
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.current_stream.is_some() && 
+        matches!(
+            self.current_stream.as_ref().map(|s| &s.status),
+            Some(StreamStatus::Active) | Some(StreamStatus::Starting)
+        )
+    }
+
+    pub fn get_current_stream(&self) -> Option<&StreamInfo> {
+        self.current_stream.as_ref()
+    }
+
+    pub fn set_event_channel(&mut self, tx: mpsc::UnboundedSender<StreamEvent>) {
+        self.event_tx = Some(tx);
+    }
+
+    /// Current viewers and talk-back; empty when not streaming
+    pub fn presence(&self) -> ViewerPresence {
+        if self.is_streaming() {
+            self.presence.get()
+        } else {
+            ViewerPresence::default()
+        }
+    }
+
+    /// Glass-to-glass latency of the current stream, if measured recently
+    pub fn latency_ms(&self) -> Option<u64> {
+        if self.is_streaming() {
+            self.latency.latency_ms()
+        } else {
+            None
+        }
+    }
+
+    pub fn presence_handle(&self) -> ViewerPresenceHandle {
+        self.presence.clone()
+    }
+
+    /// Status of every output of the current stream
+    pub fn get_output_status(&self) -> Vec<OutputStatus> {
+        self.outputs.iter().map(|output| output.status()).collect()
+    }
+
+    /// Check each output's health independently. Dropped outputs are
+    /// reconnected with backoff against the same destination and stream key;
+    /// the stream only ends once the platform output runs out of attempts.
+    pub async fn check_outputs(&mut self) -> Vec<OutputStatus> {
+        let stream_id = self.current_stream.as_ref().map(|s| s.stream_id.clone()).unwrap_or_default();
+        let relay_config = match &self.relay_config {
+            Some(config) => config.clone(),
+            None => return self.get_output_status(),
+        };
+        let audio = self.config.streaming.audio.clone();
+        let max_attempts = self.config.streaming.reconnect_attempts;
+
+        for output in &mut self.outputs {
+            if let Some(error) = output.check() {
+                tracing::warn!("Stream output {} dropped: {}", output.name(), error);
+                if let Some(ref event_tx) = self.event_tx {
+                    let _ = event_tx.send(StreamEvent::OutputFailed {
+                        stream_id: stream_id.clone(),
+                        output: output.name().to_string(),
+                        error,
+                    });
+                }
+            }
+        }
+
+        self.fall_back_to_rtmp();
+
+        if let Some(ref mut local_copy) = self.local_copy {
+            let platform_down = self.outputs.iter().any(|o| {
+                o.kind() == StreamDestinationKind::Platform && matches!(o.health(), OutputHealth::Failed(_))
+            });
+            if platform_down {
+                local_copy.outage_started();
+            } else {
+                local_copy.outage_ended();
+            }
+            if let Err(e) = local_copy.prune().await {
+                tracing::warn!("Failed to prune local stream copy: {}", e);
+            }
+        }
+
+        for output in &mut self.outputs {
+            if output.reconnect_due(max_attempts) {
+                if let Err(e) = output.reconnect(&relay_config, &audio).await {
+                    tracing::warn!("{:#}", e);
+                }
+            }
+        }
+
+        let platform_lost = self.outputs.iter().any(|o| {
+            o.kind() == StreamDestinationKind::Platform && o.reconnects_exhausted(max_attempts)
+        });
+
+        if platform_lost {
+            if let Some(ref mut stream) = self.current_stream {
+                if matches!(stream.status, StreamStatus::Active) {
+                    let error = format!("Platform output lost after {} reconnect attempts", max_attempts);
+                    tracing::error!("{}", error);
+                    stream.status = StreamStatus::Error(error.clone());
+                    if let Some(ref event_tx) = self.event_tx {
+                        let _ = event_tx.send(StreamEvent::StreamError { stream_id, error });
+                    }
+                }
+            }
+        }
+
+        self.get_output_status()
+    }
+
+    /// When SRT was picked automatically and the platform output drops, the
+    /// reconnect goes to the RTMP endpoint instead
+    fn fall_back_to_rtmp(&mut self) {
+        let stream = match self.current_stream.as_mut() {
+            Some(stream) if stream.protocol == StreamProtocol::Srt
+                && self.config.streaming.protocol == StreamProtocolPreference::Auto => stream,
+            _ => return,
+        };
+
+        let platform = match self.outputs.iter_mut().find(|o| {
+            o.kind() == StreamDestinationKind::Platform && matches!(o.health(), OutputHealth::Failed(_))
+        }) {
+            Some(platform) => platform,
+            None => return,
+        };
+
+        tracing::warn!("SRT platform output dropped, falling back to RTMP for stream {}", stream.stream_id);
+        stream.protocol = StreamProtocol::Rtmp;
+        platform.redirect(format!("{}/{}", stream.rtmp_url, stream.stream_key));
+    }
+
+    fn build_outputs(&self, stream_info: &StreamInfo, include_audio: bool) -> Result<Vec<StreamOutput>> {
+        let platform_url = match (stream_info.protocol, &stream_info.srt_url) {
+            (StreamProtocol::Srt, Some(srt_url)) => self.config.streaming.srt.build_url(srt_url)?,
+            _ => format!("{}/{}", stream_info.rtmp_url, stream_info.stream_key),
+        };
+
+        let mut outputs = vec![StreamOutput::new(
+            "platform".to_string(),
+            StreamDestinationKind::Platform,
+            platform_url,
+            stream_info.config.clone(),
+        )];
+
+        for destination in self.config.streaming.destinations.iter().filter(|d| d.enabled) {
+            let config = match &destination.quality {
+                Some(quality) => self.get_streaming_config(quality, include_audio),
+                None => stream_info.config.clone(),
+            };
+            let url = match destination.kind {
+                StreamDestinationKind::Srt => self.config.streaming.srt.build_url(&destination.url)?,
+                _ => destination.url.clone(),
+            };
+            outputs.push(StreamOutput::new(
+                destination.name.clone(),
+                destination.kind,
+                url,
+                config,
+            ));
+        }
+
+        Ok(outputs)
+    }
+
+    async fn start_ffmpeg_stream(&mut self, relay_config: &StreamingConfig, relay_url: &str) -> Result<()> {
+        let mut cmd = Command::new("ffmpeg");
+        
+        // Input source: the camera, or a test pattern or looped media file
+        // in simulation
+        let video = VideoSource::for_config(&self.config, "/dev/video0");
+        cmd.args(video.input_args(relay_config.fps, &relay_config.resolution));
+
+        // With a second camera attached, both are composed into one picture
+        let composed = pip::secondary_camera(&self.config).and_then(|device| {
+            let filter = pip::compose_filter(self.pip_layout, &relay_config.resolution, self.config.streaming.pip.inset_percent)?;
+            Some((device, filter))
+        });
+        if let Some((device, filter)) = &composed {
+            cmd.args(VideoSource::for_config(&self.config, device).input_args(relay_config.fps, &relay_config.resolution))
+               .arg("-filter_complex").arg(filter)
+               .args(["-map", "[v]"]);
+            tracing::info!("Composing {} into the stream as {:?}", device, self.pip_layout);
+        }
+
+        if relay_config.include_audio {
+            cmd.args(AudioSource::for_config(&self.config, "hw:0,0").input_args());
+            if composed.is_some() {
+                cmd.args(["-map", "2:a:0"]);
+            } else {
+                cmd.args(crate::capture::map_video_and_audio_args());
+            }
+        }
+        if composed.is_none() {
+            cmd.args(video.output_args(relay_config.fps, &relay_config.resolution));
+        }
+
+        // Video encoding settings
+        cmd.arg("-c:v").arg("libx264")
+           .arg("-preset").arg("ultrafast")
+           .arg("-tune").arg("zerolatency")
+           .arg("-b:v").arg(format!("{}k", relay_config.bitrate / 1000))
+           .arg("-maxrate").arg(format!("{}k", relay_config.bitrate / 1000))
+           .arg("-bufsize").arg(format!("{}k", relay_config.bitrate / 500))
+           .arg("-g").arg((relay_config.fps * 2).to_string()) // Keyframe interval
+           .arg("-r").arg(relay_config.fps.to_string());
+
+        // Audio is carried at high quality on the relay; each output
+        // encodes it with the streaming audio settings
+        if relay_config.include_audio {
+            self.config.streaming.audio.validate(None).context("Invalid streaming audio settings")?;
+            if !self.config.simulation.enabled {
+                cmd.args(self.config.audio.processing.ffmpeg_args());
+            }
+            cmd.arg("-c:a").arg("aac")
+               .arg("-b:a").arg("192k");
+        } else {
+            cmd.arg("-an"); // No audio
+        }
+
+        // Local relay that the outputs read from. Without the mux delay,
+        // relay timestamps match the encoder's progress, which places each
+        // frame in time for latency measurement.
+        cmd.arg("-f").arg("mpegts")
+           .arg("-muxdelay").arg("0")
+           .arg("-muxpreload").arg("0")
+           .arg(relay_url);
+
+        // Logging
+        cmd.arg("-loglevel").arg("warning")
+           .args(crate::ffmpeg_progress::progress_args());
+
+        // Redirect streams
+        cmd.stdout(Stdio::piped())
+           .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn()
+            .context("Failed to start FFmpeg streaming process")?;
+
+        if let Some(stdout) = child.stdout.take() {
+            self.latency.reset();
+            let latency = self.latency.clone();
+            tokio::spawn(async move {
+                use tokio::io::AsyncBufReadExt;
+                let mut parser = crate::ffmpeg_progress::ProgressParser::default();
+                let mut lines = tokio::io::BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(progress) = parser.feed_line(&line) {
+                        latency.observe_progress(&progress);
+                    }
+                }
+            });
+        }
+
+        // Keep reading warnings so a full pipe can't stall the capture
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(async move {
+                use tokio::io::AsyncBufReadExt;
+                let mut lines = tokio::io::BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::warn!("Stream capture ffmpeg: {}", line);
+                }
+            });
+        }
+
+        self.ffmpeg_process = Some(child);
+        
+        tracing::info!("FFmpeg capture relay started: {}", relay_url);
+        Ok(())
+    }
+
+    pub fn pip_layout(&self) -> PipLayout {
+        self.pip_layout
+    }
+
+    /// Switch the picture-in-picture layout. A running stream restarts its
+    /// capture with the new layout; the outputs stay connected to the relay.
+    pub async fn set_pip_layout(&mut self, layout: PipLayout) -> Result<()> {
+        if layout != PipLayout::Off && self.config.streaming.pip.secondary_device.is_none() {
+            return Err(anyhow::anyhow!("No second camera configured for picture-in-picture"));
+        }
+        self.pip_layout = layout;
+
+        let (Some(relay_config), Some(relay_url)) = (self.relay_config.clone(), self.relay.as_ref().map(|r| r.input_url())) else {
+            return Ok(());
+        };
+        if let Some(mut process) = self.ffmpeg_process.take() {
+            let _ = process.kill().await;
+            let _ = process.wait().await;
+        }
+        self.start_ffmpeg_stream(&relay_config, &relay_url).await
+    }
+
+    fn get_streaming_config(&self, quality: StreamQuality, include_audio: bool) -> StreamingConfig {
+        let (resolution, bitrate, fps) = match quality {
+            StreamQuality::Low => ("640x480", 500_000, 15),
+            StreamQuality::Medium => ("1280x720", 1_500_000, 30),
+            StreamQuality::High => ("1920x1080", 3_000_000, 30),
+            StreamQuality::Ultra => ("1920x1080", 5_000_000, 60),
+        };
+
+        StreamingConfig {
+            quality,
+            include_audio,
+            bitrate,
+            fps,
+            resolution: resolution.to_string(),
+        }
+    }
+
+    pub async fn update_bitrate(&mut self, new_bitrate: u32) -> Result<()> {
+        if !self.is_streaming() {
+            return Err(anyhow::anyhow!("Not currently streaming"));
+        }
+
+        // For adaptive bitrate, we would need to restart the stream
+        // This is a simplified implementation
+        tracing::info!("Bitrate update requested: {} kbps", new_bitrate / 1000);
+        
+        if let Some(ref event_tx) = self.event_tx {
+            let _ = event_tx.send(StreamEvent::BitrateChanged { bitrate: new_bitrate });
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_stream_stats(&self) -> Result<StreamStats> {
+        if let Some(ref stream) = self.current_stream {
+            let uptime = chrono::Utc::now() - stream.started_at;
+            
+            Ok(StreamStats {
+                stream_id: stream.stream_id.clone(),
+                uptime_seconds: uptime.num_seconds() as u64,
+                current_bitrate: stream.config.bitrate,
+                fps: stream.config.fps,
+                resolution: stream.config.resolution.clone(),
+                status: stream.status.clone(),
+                drop_count: self.outputs.iter().map(|o| o.drop_count()).sum(),
+                reconnect_count: self.outputs.iter().map(|o| o.reconnect_count()).sum(),
+                outputs: self.get_output_status(),
+                viewers: self.presence.get(),
+                latency_ms: self.latency.latency_ms(),
+            })
+        } else {
+            Err(anyhow::anyhow!("No active stream"))
+        }
+    }
+}
+
+/// Compare the local copy with what the server received and upload the
+/// segments it is missing. Segments the server already has are removed
+/// unless the incident is under legal hold. Missing segments go through the
+/// gap upload queue, so failed uploads are retried when the device is next
+/// online.
+async fn reconcile_local_copy(
+    config: Config,
+    stream_id: String,
+    incident_id: Option<String>,
+    stream: TimeRange,
+    outages: Vec<TimeRange>,
+    segments: Vec<LocalSegment>,
+    cancel: CancellationToken,
+) -> Result<()> {
+    let api_client = ApiClient::new(config).with_cancellation(cancel);
+
+    let received = match api_client.get_stream_received_ranges(&stream_id).await {
+        Ok(received) => received,
+        Err(e) => {
+            tracing::warn!("Server can't report received ranges, using local outage log: {}", e);
+            local_copy::received_from_outages(stream, &outages)
+        }
+    };
+
+    let gaps = local_copy::find_gaps(&segments, &received);
+    tracing::info!("Stream {}: {} of {} local segments missing on the server", stream_id, gaps.len(), segments.len());
+
+    let held = match &incident_id {
+        Some(incident_id) => crate::legal_hold::LegalHolds::load().await.is_incident_held(incident_id),
+        None => false,
+    };
+
+    // Queued before the first attempt, so a failure is retried with the
+    // next upload pass instead of being lost
+    let queue = GapUploadQueue::new();
+    for segment in &segments {
+        if gaps.contains(segment) {
+            let file_size = tokio::fs::metadata(&segment.path).await?.len();
+            let request = StreamGapUploadRequest {
+                stream_id: stream_id.clone(),
+                incident_id: incident_id.clone(),
+                start: segment.range.start,
+                end: segment.range.end,
+                file_size,
+                checksum: IntegrityManager::calculate_file_hash(&segment.path).await?,
+            };
+            queue.enqueue(&PendingGap { request, path: segment.path.clone(), keep_local: held }).await?;
+        } else if !held {
+            let _ = tokio::fs::remove_file(&segment.path).await;
+        }
+    }
+
+    let failed = queue.upload_pending(&api_client).await?;
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} stream gap segments failed to upload, queued for retry", failed));
+    }
+    Ok(())
+}
+
+/// Retry stream gap uploads that failed earlier, e.g. once back online
+pub async fn retry_gap_uploads(config: Config) -> Result<usize> {
+    GapUploadQueue::new().upload_pending(&ApiClient::new(config)).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamStats {
+    pub stream_id: String,
+    pub uptime_seconds: u64,
+    pub current_bitrate: u32,
+    pub fps: u32,
+    pub resolution: String,
+    pub status: StreamStatus,
+    /// Times any output lost its connection, and times one was re-established
+    pub drop_count: u32,
+    pub reconnect_count: u32,
+    pub outputs: Vec<OutputStatus>,
+    pub viewers: ViewerPresence,
+    /// Glass-to-glass latency; None until the server reports what its
+    /// viewers are seeing
+    pub latency_ms: Option<u64>,
+}
+
+impl Drop for StreamingManager {
+    fn drop(&mut self) {
+        for output in &mut self.outputs {
+            futures::executor::block_on(output.stop());
+        }
+        if let Some(mut relay) = self.relay.take() {
+            relay.stop();
+        }
+        if let Some(task) = self.presence_task.take() {
+            task.abort();
+        }
+        if let Some(task) = self.clock_sync_task.take() {
+            task.abort();
+        }
+        if let Some(mut process) = self.ffmpeg_process.take() {
+            let _ = futures::executor::block_on(process.kill());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaming_config_creation() {
+        let config = Config::default();
+        let manager = StreamingManager::new(config);
+        
+        let streaming_config = manager.get_streaming_config(StreamQuality::High, true);
+        assert_eq!(streaming_config.quality, StreamQuality::High);
+        assert_eq!(streaming_config.resolution, "1920x1080");
+        assert_eq!(streaming_config.fps, 30);
+        assert!(streaming_config.include_audio);
+    }
+
+    #[test]
+    fn test_invalid_quality() {
+        assert!("invalid".parse::<StreamQuality>().is_err());
+        assert_eq!("ultra".parse::<StreamQuality>().unwrap(), StreamQuality::Ultra);
+        assert_eq!(serde_json::to_value(StreamQuality::Low).unwrap(), "low");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,334 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+
+use crate::audio_encoding::AudioEncodingConfig;
+use super::{StreamQuality, StreamingConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamDestinationKind {
+    /// RTMP ingest handed out by the platform for this stream
+    Platform,
+    Rtmp,
+    /// Publish to an RTSP server (e.g. a local mediamtx for in-vehicle viewing)
+    Rtsp,
+    Srt,
+}
+
+/// An extra place to send the live stream, alongside the platform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamDestinationConfig {
+    pub name: String,
+    pub kind: StreamDestinationKind,
+    pub url: String,
+    /// Quality for this output; None uses the quality requested for the stream
+    pub quality: Option<StreamQuality>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputHealth {
+    Starting,
+    Healthy,
+    Failed(String),
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputStatus {
+    pub name: String,
+    pub kind: StreamDestinationKind,
+    pub quality: StreamQuality,
+    pub bitrate: u32,
+    pub health: OutputHealth,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub drop_count: u32,
+    pub reconnect_count: u32,
+}
+
+const MAX_BACKOFF_SECS: u64 = 30;
+/// An output that stays up this long counts as recovered
+const STABLE_AFTER_SECS: u64 = 30;
+
+/// One destination of a fanned-out stream. Each output runs its own ffmpeg
+/// reading the capture relay, so a dead secondary never takes down the others.
+pub struct StreamOutput {
+    name: String,
+    kind: StreamDestinationKind,
+    url: String,
+    config: StreamingConfig,
+    health: OutputHealth,
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+    process: Option<Child>,
+    /// Where this output reads the relayed capture from
+    input_url: String,
+    drop_count: u32,
+    reconnect_count: u32,
+    /// Consecutive failed reconnects since the output was last stable
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    running_since: Option<Instant>,
+}
+
+impl StreamOutput {
+    pub fn new(name: String, kind: StreamDestinationKind, url: String, config: StreamingConfig) -> Self {
+        Self {
+            name,
+            kind,
+            url,
+            config,
+            health: OutputHealth::Stopped,
+            started_at: None,
+            process: None,
+            input_url: String::new(),
+            drop_count: 0,
+            reconnect_count: 0,
+            attempts: 0,
+            next_attempt: None,
+            running_since: None,
+        }
+    }
+
+    pub fn set_input(&mut self, input_url: String) {
+        self.input_url = input_url;
+    }
+
+    /// Point a dropped output at a different destination and retry right away
+    pub fn redirect(&mut self, url: String) {
+        self.url = url;
+        self.attempts = 0;
+        self.next_attempt = Some(Instant::now());
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> StreamDestinationKind {
+        self.kind
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn config(&self) -> &StreamingConfig {
+        &self.config
+    }
+
+    pub fn health(&self) -> &OutputHealth {
+        &self.health
+    }
+
+    pub fn status(&self) -> OutputStatus {
+        OutputStatus {
+            name: self.name.clone(),
+            kind: self.kind,
+            quality: self.config.quality,
+            bitrate: self.config.bitrate,
+            health: self.health.clone(),
+            started_at: self.started_at,
+            drop_count: self.drop_count,
+            reconnect_count: self.reconnect_count,
+        }
+    }
+
+    pub fn drop_count(&self) -> u32 {
+        self.drop_count
+    }
+
+    pub fn reconnect_count(&self) -> u32 {
+        self.reconnect_count
+    }
+
+    pub async fn start(&mut self, relay_config: &StreamingConfig, audio: &AudioEncodingConfig) -> Result<()> {
+        self.health = OutputHealth::Starting;
+
+        let mut cmd = self.build_command(relay_config, audio);
+        match cmd.spawn() {
+            Ok(child) => {
+                self.process = Some(child);
+                self.running_since = Some(Instant::now());
+                self.started_at.get_or_insert_with(chrono::Utc::now);
+                self.health = OutputHealth::Healthy;
+                tracing::info!("Stream output {} started", self.name);
+                Ok(())
+            }
+            Err(e) => {
+                self.health = OutputHealth::Failed(e.to_string());
+                Err(e).with_context(|| format!("Failed to start stream output {}", self.name))
+            }
+        }
+    }
+
+    pub async fn stop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            let _ = process.kill().await;
+            let _ = process.wait().await;
+        }
+        self.health = OutputHealth::Stopped;
+    }
+
+    /// Check whether the output's ffmpeg is still running. Returns the error
+    /// when the output has just dropped, and schedules a reconnect.
+    pub fn check(&mut self) -> Option<String> {
+        let process = self.process.as_mut()?;
+        let error = match process.try_wait() {
+            Ok(None) => {
+                if self.running_since.map(|t| t.elapsed() >= Duration::from_secs(STABLE_AFTER_SECS)).unwrap_or(false) {
+                    self.attempts = 0;
+                }
+                return None;
+            }
+            Ok(Some(status)) => format!("ffmpeg exited with {}", status),
+            Err(e) => e.to_string(),
+        };
+
+        self.process = None;
+        self.running_since = None;
+        self.drop_count += 1;
+        self.health = OutputHealth::Failed(error.clone());
+        self.next_attempt = Some(Instant::now() + self.backoff());
+        Some(error)
+    }
+
+    /// Exponential backoff from 1s, capped at 30s
+    fn backoff(&self) -> Duration {
+        Duration::from_secs((1u64 << self.attempts.min(5)).min(MAX_BACKOFF_SECS))
+    }
+
+    /// Whether a dropped output is due another reconnect attempt
+    pub fn reconnect_due(&self, max_attempts: u32) -> bool {
+        self.process.is_none()
+            && matches!(self.health, OutputHealth::Failed(_))
+            && self.attempts < max_attempts
+            && self.next_attempt.map(|t| Instant::now() >= t).unwrap_or(false)
+    }
+
+    pub fn reconnects_exhausted(&self, max_attempts: u32) -> bool {
+        self.process.is_none() && matches!(self.health, OutputHealth::Failed(_)) && self.attempts >= max_attempts
+    }
+
+    /// Restart the output against the same destination (and stream key)
+    pub async fn reconnect(&mut self, relay_config: &StreamingConfig, audio: &AudioEncodingConfig) -> Result<()> {
+        self.attempts += 1;
+        tracing::info!("Reconnecting stream output {} (attempt {})", self.name, self.attempts);
+
+        match self.start(relay_config, audio).await {
+            Ok(()) => {
+                self.reconnect_count += 1;
+                Ok(())
+            }
+            Err(e) => {
+                self.next_attempt = Some(Instant::now() + self.backoff());
+                Err(e)
+            }
+        }
+    }
+
+    fn build_command(&self, relay_config: &StreamingConfig, audio: &AudioEncodingConfig) -> Command {
+        let mut cmd = Command::new("ffmpeg");
+
+        // Keep the capture's timestamps, even after a reconnect, so the
+        // server's playhead reports can be matched to capture time
+        cmd.arg("-copyts")
+           .arg("-f").arg("mpegts")
+           .arg("-i").arg(&self.input_url);
+
+        // The relay is already at the highest requested quality, so outputs
+        // asking for the same settings pass the video straight through
+        if self.config.bitrate == relay_config.bitrate && self.config.resolution == relay_config.resolution {
+            cmd.arg("-c:v").arg("copy");
+        } else {
+            cmd.arg("-c:v").arg("libx264")
+               .arg("-preset").arg("ultrafast")
+               .arg("-tune").arg("zerolatency")
+               .arg("-s").arg(&self.config.resolution)
+               .arg("-r").arg(self.config.fps.to_string())
+               .arg("-b:v").arg(format!("{}k", self.config.bitrate / 1000))
+               .arg("-maxrate").arg(format!("{}k", self.config.bitrate / 1000))
+               .arg("-bufsize").arg(format!("{}k", self.config.bitrate / 500))
+               .arg("-g").arg((self.config.fps * 2).to_string());
+        }
+
+        if self.config.include_audio {
+            cmd.args(audio.ffmpeg_args());
+        } else {
+            cmd.arg("-an");
+        }
+
+        let srt = self.kind == StreamDestinationKind::Srt || self.url.starts_with("srt://");
+        match self.kind {
+            StreamDestinationKind::Rtsp => {
+                cmd.arg("-f").arg("rtsp")
+                   .arg("-rtsp_transport").arg("tcp");
+            }
+            _ if srt => {
+                cmd.arg("-f").arg("mpegts");
+            }
+            _ => {
+                cmd.arg("-f").arg("flv")
+                   .arg("-flvflags").arg("no_duration_filesize");
+            }
+        }
+
+        cmd.arg(&self.url)
+           .arg("-loglevel").arg("warning")
+           .stdout(Stdio::null())
+           .stderr(Stdio::null())
+           .kill_on_drop(true);
+
+        cmd
+    }
+}
+
+/// Pick the settings for the shared capture relay: the largest of the outputs
+pub fn relay_config(outputs: &[StreamOutput]) -> Option<StreamingConfig> {
+    outputs
+        .iter()
+        .map(|output| output.config())
+        .max_by_key(|config| (config.bitrate, config.fps))
+        .map(|config| StreamingConfig {
+            include_audio: outputs.iter().any(|o| o.config().include_audio),
+            ..config.clone()
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(quality: StreamQuality, bitrate: u32, include_audio: bool) -> StreamingConfig {
+        StreamingConfig {
+            quality,
+            include_audio,
+            bitrate,
+            fps: 30,
+            resolution: "1280x720".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_relay_uses_highest_quality() {
+        let outputs = vec![
+            StreamOutput::new("platform".into(), StreamDestinationKind::Platform, "rtmp://a/live".into(), config(StreamQuality::Medium, 1_500_000, false)),
+            StreamOutput::new("vehicle".into(), StreamDestinationKind::Rtsp, "rtsp://127.0.0.1:8554/cam".into(), config(StreamQuality::High, 3_000_000, true)),
+        ];
+
+        let relay = relay_config(&outputs).unwrap();
+        assert_eq!(relay.quality, StreamQuality::High);
+        assert!(relay.include_audio);
+        assert!(relay_config(&[]).is_none());
+    }
+
+    #[test]
+    fn test_new_output_is_stopped() {
+        let output = StreamOutput::new("backup".into(), StreamDestinationKind::Rtmp, "rtmp://b/live".into(), config(StreamQuality::Low, 500_000, true));
+        assert_eq!(output.status().health, OutputHealth::Stopped);
+    }
+}
@@ -0,0 +1,137 @@
+//! Picture-in-picture for devices with a second camera, e.g. a body camera
+//! docked in a vehicle with a dash camera. The live stream shows both
+//! cameras composed into one picture. Each camera is still recorded at full
+//! size on its own.
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::quality_ladder::parse_resolution;
+use crate::config::Config;
+use crate::gop_buffer::BufferStorage;
+
+/// Gap between a corner inset and the edge of the picture, in pixels
+const INSET_MARGIN: u32 = 16;
+
+/// How the two cameras share the streamed picture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipLayout {
+    /// The second camera inset in a corner of the first
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    /// Side by side, each at half width
+    Split,
+    /// The first camera only
+    Off,
+}
+
+impl std::str::FromStr for PipLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+            .map_err(|_| anyhow::anyhow!("Invalid picture-in-picture layout: {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipConfig {
+    /// The second camera, e.g. /dev/video2; None on single-camera devices
+    pub secondary_device: Option<String>,
+    pub layout: PipLayout,
+    /// Width of a corner inset, as a percentage of the picture
+    pub inset_percent: u32,
+    /// Record the second camera at full size alongside the first
+    pub record_secondary: bool,
+}
+
+impl Default for PipConfig {
+    fn default() -> Self {
+        Self {
+            secondary_device: None,
+            layout: PipLayout::default(),
+            inset_percent: 30,
+            record_secondary: true,
+        }
+    }
+}
+
+/// The second camera's device, if it's configured and plugged in
+pub fn secondary_camera(config: &Config) -> Option<String> {
+    let device = config.streaming.pip.secondary_device.as_ref()?;
+    (config.simulation.enabled || std::path::Path::new(device).exists()).then(|| device.clone())
+}
+
+/// `-filter_complex` graph composing the first input's video with the
+/// second's at `resolution`, labelled `[v]`. None for `PipLayout::Off`.
+pub fn compose_filter(layout: PipLayout, resolution: &str, inset_percent: u32) -> Option<String> {
+    let (width, height) = parse_resolution(resolution).unwrap_or((1280, 720));
+    let even = |value: u32| value.max(2) & !1;
+    match layout {
+        PipLayout::Off => None,
+        PipLayout::Split => {
+            let half = even(width / 2);
+            let fit = format!("scale={half}:{height}:force_original_aspect_ratio=decrease,pad={half}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1");
+            Some(format!("[0:v]{fit}[left];[1:v]{fit}[right];[left][right]hstack=inputs=2[v]"))
+        }
+        corner => {
+            let inset_width = even(width * inset_percent.clamp(10, 50) / 100);
+            let inset_height = even(inset_width * height / width);
+            let x = match corner {
+                PipLayout::TopLeft | PipLayout::BottomLeft => INSET_MARGIN,
+                _ => width - inset_width - INSET_MARGIN,
+            };
+            let y = match corner {
+                PipLayout::TopLeft | PipLayout::TopRight => INSET_MARGIN,
+                _ => height - inset_height - INSET_MARGIN,
+            };
+            Some(format!(
+                "[0:v]scale={width}:{height},setsar=1[main];[1:v]scale={inset_width}:{inset_height},setsar=1[inset];[main][inset]overlay={x}:{y}[v]"
+            ))
+        }
+    }
+}
+
+/// Configuration for recording the second camera: its largest quality,
+/// captured from `device`, without a pre-incident buffer of its own
+pub fn secondary_recording_config(config: &Config, device: &str) -> Option<Config> {
+    let mut quality = config.recording.available_qualities.iter()
+        .max_by_key(|q| parse_resolution(&q.resolution).map(|(w, h)| w as u64 * h as u64).unwrap_or(0))?
+        .clone();
+    quality.device_path = device.to_string();
+
+    let mut secondary = config.clone();
+    secondary.recording.default_quality = quality.quality.clone();
+    secondary.recording.available_qualities = vec![quality];
+    secondary.recording.pre_incident_buffer_seconds = 0;
+    // The vehicle profile's pre-roll doesn't apply to the secondary camera
+    secondary.vehicle.enabled = false;
+    secondary.recording.pre_incident_buffer.storage = BufferStorage::Disk;
+    Some(secondary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts_and_secondary_recording() {
+        assert_eq!("top_left".parse::<PipLayout>().unwrap(), PipLayout::TopLeft);
+        assert!("middle".parse::<PipLayout>().is_err());
+
+        assert_eq!(compose_filter(PipLayout::BottomRight, "1280x720", 30).unwrap(),
+            "[0:v]scale=1280:720,setsar=1[main];[1:v]scale=384:216,setsar=1[inset];[main][inset]overlay=880:488[v]");
+        assert!(compose_filter(PipLayout::Split, "1280x720", 30).unwrap().ends_with("hstack=inputs=2[v]"));
+        assert_eq!(compose_filter(PipLayout::Off, "1280x720", 30), None);
+
+        let config = Config::default();
+        let secondary = secondary_recording_config(&config, "/dev/video2").unwrap();
+        assert_eq!(secondary.recording.available_qualities.len(), 1);
+        assert_eq!(secondary.recording.available_qualities[0].resolution, "1920x1080");
+        assert_eq!(secondary.recording.available_qualities[0].device_path, "/dev/video2");
+        assert_eq!(secondary.pre_incident_buffer_seconds(), 0);
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::{Result, Context};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+const MAX_DATAGRAM: usize = 65536;
+const CONNECT_RETRY_MS: u64 = 200;
+
+/// Bounded queue of encoded packets for one output. While the output is
+/// down the newest `max_bytes` are kept and replayed once it reconnects.
+#[derive(Debug)]
+struct PacketQueue {
+    packets: VecDeque<Vec<u8>>,
+    bytes: usize,
+    max_bytes: usize,
+    dropped_bytes: u64,
+}
+
+impl PacketQueue {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            packets: VecDeque::new(),
+            bytes: 0,
+            max_bytes,
+            dropped_bytes: 0,
+        }
+    }
+
+    fn push(&mut self, packet: Vec<u8>) {
+        self.bytes += packet.len();
+        self.packets.push_back(packet);
+
+        while self.bytes > self.max_bytes {
+            match self.packets.pop_front() {
+                Some(old) => {
+                    self.bytes -= old.len();
+                    self.dropped_bytes += old.len() as u64;
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<Vec<u8>> {
+        let packet = self.packets.pop_front()?;
+        self.bytes -= packet.len();
+        Some(packet)
+    }
+}
+
+struct Tap {
+    queue: Mutex<PacketQueue>,
+    notify: Notify,
+}
+
+/// Receives the capture relay and fans it out to each output's ffmpeg over
+/// loopback TCP, buffering a few seconds per output across reconnects.
+pub struct RelayFanout {
+    input_port: u16,
+    taps: Arc<Mutex<Vec<Arc<Tap>>>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl RelayFanout {
+    pub async fn start() -> Result<Self> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await
+            .context("Failed to bind stream relay socket")?;
+        let input_port = socket.local_addr()?.port();
+        let taps: Arc<Mutex<Vec<Arc<Tap>>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let receiver_taps = Arc::clone(&taps);
+        let receiver = tokio::spawn(async move {
+            let mut buf = vec![0u8; MAX_DATAGRAM];
+            loop {
+                let len = match socket.recv(&mut buf).await {
+                    Ok(len) => len,
+                    Err(e) => {
+                        tracing::warn!("Stream relay receive error: {}", e);
+                        continue;
+                    }
+                };
+
+                let taps = receiver_taps.lock().unwrap().clone();
+                for tap in taps {
+                    tap.queue.lock().unwrap().push(buf[..len].to_vec());
+                    tap.notify.notify_one();
+                }
+            }
+        });
+
+        Ok(Self {
+            input_port,
+            taps,
+            tasks: vec![receiver],
+        })
+    }
+
+    /// URL the capture ffmpeg should send mpegts to
+    pub fn input_url(&self) -> String {
+        format!("udp://127.0.0.1:{}?pkt_size=1316", self.input_port)
+    }
+
+    /// Add an output fed from the relay. Returns the URL its ffmpeg should
+    /// listen on; the tap keeps reconnecting to it whenever it restarts.
+    pub fn add_tap(&mut self, buffer_bytes: usize) -> Result<String> {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .context("Failed to reserve a local port for a stream output")?
+            .local_addr()?
+            .port();
+
+        let tap = Arc::new(Tap {
+            queue: Mutex::new(PacketQueue::new(buffer_bytes)),
+            notify: Notify::new(),
+        });
+        self.taps.lock().unwrap().push(Arc::clone(&tap));
+        self.tasks.push(tokio::spawn(Self::feed(tap, port)));
+
+        Ok(format!("tcp://127.0.0.1:{}?listen=1", port))
+    }
+
+    async fn feed(tap: Arc<Tap>, port: u16) {
+        loop {
+            let mut stream = match TcpStream::connect(("127.0.0.1", port)).await {
+                Ok(stream) => stream,
+                Err(_) => {
+                    // Output not listening (yet); packets keep queueing meanwhile
+                    tokio::time::sleep(tokio::time::Duration::from_millis(CONNECT_RETRY_MS)).await;
+                    continue;
+                }
+            };
+            let _ = stream.set_nodelay(true);
+
+            loop {
+                let packet = tap.queue.lock().unwrap().pop();
+                match packet {
+                    Some(packet) => {
+                        if stream.write_all(&packet).await.is_err() {
+                            // Output went away; the unsent packet is lost but
+                            // everything after it stays queued for the restart
+                            break;
+                        }
+                    }
+                    None => tap.notify.notified().await,
+                }
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.taps.lock().unwrap().clear();
+    }
+}
+
+impl Drop for RelayFanout {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_keeps_newest_packets() {
+        let mut queue = PacketQueue::new(10);
+        queue.push(vec![1; 4]);
+        queue.push(vec![2; 4]);
+        queue.push(vec![3; 4]);
+
+        assert_eq!(queue.bytes, 8);
+        assert_eq!(queue.dropped_bytes, 4);
+        assert_eq!(queue.pop(), Some(vec![2; 4]));
+        assert_eq!(queue.pop(), Some(vec![3; 4]));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamProtocol {
+    Rtmp,
+    Srt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamProtocolPreference {
+    /// SRT when the backend offers it, RTMP otherwise
+    Auto,
+    Rtmp,
+    Srt,
+}
+
+/// SRT caller settings. Latency is the window SRT has to retransmit lost
+/// packets, so lossy cellular links want it well above the round-trip time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SrtConfig {
+    pub latency_ms: u32,
+    /// Bandwidth headroom for retransmissions, as a percentage of the stream (5-100)
+    pub overhead_bandwidth_percent: u32,
+    pub passphrase: Option<String>,
+}
+
+impl Default for SrtConfig {
+    fn default() -> Self {
+        Self {
+            latency_ms: 2000,
+            overhead_bandwidth_percent: 25,
+            passphrase: None,
+        }
+    }
+}
+
+impl SrtConfig {
+    /// Add the caller options to an SRT endpoint URL for ffmpeg's srt protocol
+    pub fn build_url(&self, endpoint: &str) -> Result<String> {
+        if !endpoint.starts_with("srt://") {
+            return Err(anyhow::anyhow!("Not an SRT endpoint: {}", endpoint));
+        }
+
+        if !(5..=100).contains(&self.overhead_bandwidth_percent) {
+            return Err(anyhow::anyhow!("SRT overhead bandwidth must be between 5 and 100 percent"));
+        }
+
+        // ffmpeg takes SRT latency in microseconds
+        let mut params = vec![
+            "mode=caller".to_string(),
+            format!("latency={}", self.latency_ms as u64 * 1000),
+            format!("oheadbw={}", self.overhead_bandwidth_percent),
+        ];
+
+        if let Some(passphrase) = &self.passphrase {
+            if passphrase.len() < 10 || passphrase.len() > 79 {
+                return Err(anyhow::anyhow!("SRT passphrase must be 10-79 characters"));
+            }
+            params.push(format!("passphrase={}", passphrase));
+        }
+
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        Ok(format!("{}{}{}", endpoint, separator, params.join("&")))
+    }
+}
+
+pub fn select_protocol(
+    preference: StreamProtocolPreference,
+    srt_available: bool,
+) -> Result<StreamProtocol> {
+    match (preference, srt_available) {
+        (StreamProtocolPreference::Rtmp, _) => Ok(StreamProtocol::Rtmp),
+        (StreamProtocolPreference::Srt, true) | (StreamProtocolPreference::Auto, true) => Ok(StreamProtocol::Srt),
+        (StreamProtocolPreference::Srt, false) => Err(anyhow::anyhow!("SRT requested but the backend offered no SRT endpoint")),
+        (StreamProtocolPreference::Auto, false) => Ok(StreamProtocol::Rtmp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_protocol() {
+        assert_eq!(select_protocol(StreamProtocolPreference::Auto, true).unwrap(), StreamProtocol::Srt);
+        assert_eq!(select_protocol(StreamProtocolPreference::Auto, false).unwrap(), StreamProtocol::Rtmp);
+        assert_eq!(select_protocol(StreamProtocolPreference::Rtmp, true).unwrap(), StreamProtocol::Rtmp);
+        assert!(select_protocol(StreamProtocolPreference::Srt, false).is_err());
+    }
+
+    #[test]
+    fn test_build_url() {
+        let config = SrtConfig::default();
+        assert_eq!(
+            config.build_url("srt://ingest.example.com:9000?streamid=publish:abc").unwrap(),
+            "srt://ingest.example.com:9000?streamid=publish:abc&mode=caller&latency=2000000&oheadbw=25"
+        );
+        assert!(config.build_url("rtmp://ingest.example.com/live").is_err());
+        assert!(SrtConfig { passphrase: Some("short".into()), ..config }.build_url("srt://h:1").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::api::ApiClient;
+use crate::config::Config;
+use super::StreamEvent;
+use super::latency::LatencyHandle;
+
+const MAX_BACKOFF_SECS: u64 = 30;
+
+/// Who is on the other end of a live stream
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewerPresence {
+    pub viewers: u32,
+    /// Dispatcher currently talking back to the officer
+    pub talkback: Option<String>,
+}
+
+/// Shared view of the latest presence, updated by the event listener
+#[derive(Debug, Clone, Default)]
+pub struct ViewerPresenceHandle {
+    state: Arc<Mutex<ViewerPresence>>,
+}
+
+impl ViewerPresenceHandle {
+    pub fn get(&self) -> ViewerPresence {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn apply(&self, event: &ViewerEvent) {
+        let mut state = self.state.lock().unwrap();
+        match event {
+            ViewerEvent::Viewers { count } => state.viewers = *count,
+            ViewerEvent::TalkbackStarted { dispatcher } => {
+                state.talkback = Some(dispatcher.clone().unwrap_or_else(|| "dispatch".to_string()));
+            }
+            ViewerEvent::TalkbackEnded => state.talkback = None,
+            ViewerEvent::Playhead { .. } => {}
+        }
+    }
+
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = ViewerPresence::default();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ViewerEvent {
+    Viewers { count: u32 },
+    TalkbackStarted { dispatcher: Option<String> },
+    TalkbackEnded,
+    /// The media time of the frame the server was sending viewers, and when
+    Playhead { media_time_ms: u64, server_time_ms: i64 },
+}
+
+/// Incremental parser for a `text/event-stream` body
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: String,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk of the body, returning each complete (event, data) pair
+    fn feed(&mut self, chunk: &str) -> Vec<(String, String)> {
+        self.buffer.push_str(chunk);
+        let mut events = Vec::new();
+
+        while let Some(newline) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=newline).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    let event = self.event.take().unwrap_or_else(|| "message".to_string());
+                    events.push((event, self.data.join("\n")));
+                }
+                self.event = None;
+                self.data.clear();
+                continue;
+            }
+
+            // Comment lines are keep-alives
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        events
+    }
+}
+
+/// Turn a server-sent event into a viewer event. The event name carries the
+/// type; the data is its JSON payload.
+fn parse_event(event: &str, data: &str) -> Option<ViewerEvent> {
+    let mut payload: serde_json::Value = serde_json::from_str(data).ok()?;
+    payload.as_object_mut()?.insert("type".to_string(), serde_json::Value::String(event.to_string()));
+    serde_json::from_value(payload).ok()
+}
+
+/// Listen for dispatcher presence and playhead reports on a live stream,
+/// reconnecting with backoff until the stream ends
+pub fn spawn_listener(
+    config: Config,
+    stream_id: String,
+    presence: ViewerPresenceHandle,
+    latency: LatencyHandle,
+    event_tx: Option<mpsc::UnboundedSender<StreamEvent>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let api_client = ApiClient::new(config);
+        let mut attempts = 0u32;
+
+        loop {
+            match listen(&api_client, &stream_id, &presence, &latency, event_tx.as_ref(), &mut attempts).await {
+                Ok(()) => tracing::info!("Stream event feed closed for {}", stream_id),
+                Err(e) => tracing::warn!("Stream event feed for {} failed: {}", stream_id, e),
+            }
+
+            // Counts shown while disconnected would be stale
+            presence.reset();
+
+            let backoff = Duration::from_secs((1u64 << attempts.min(5)).min(MAX_BACKOFF_SECS));
+            attempts += 1;
+            tokio::time::sleep(backoff).await;
+        }
+    })
+}
+
+async fn listen(
+    api_client: &ApiClient,
+    stream_id: &str,
+    presence: &ViewerPresenceHandle,
+    latency: &LatencyHandle,
+    event_tx: Option<&mpsc::UnboundedSender<StreamEvent>>,
+    attempts: &mut u32,
+) -> Result<()> {
+    let mut response = api_client.open_stream_events(stream_id).await?;
+    *attempts = 0;
+
+    let mut parser = SseParser::default();
+    while let Some(chunk) = response.chunk().await? {
+        for (event, data) in parser.feed(&String::from_utf8_lossy(&chunk)) {
+            let Some(viewer_event) = parse_event(&event, &data) else {
+                tracing::debug!("Ignoring stream event {}", event);
+                continue;
+            };
+
+            if let ViewerEvent::Playhead { media_time_ms, server_time_ms } = viewer_event {
+                if let Some(server_time) = chrono::DateTime::from_timestamp_millis(server_time_ms) {
+                    latency.record_playhead(media_time_ms, server_time);
+                }
+                continue;
+            }
+
+            presence.apply(&viewer_event);
+
+            if let Some(event_tx) = event_tx {
+                let stream_id = stream_id.to_string();
+                let _ = event_tx.send(match viewer_event {
+                    ViewerEvent::Viewers { count } => StreamEvent::ViewersChanged { stream_id, count },
+                    ViewerEvent::TalkbackStarted { dispatcher } => StreamEvent::TalkbackStarted { stream_id, dispatcher },
+                    ViewerEvent::TalkbackEnded => StreamEvent::TalkbackEnded { stream_id },
+                    // Handled above
+                    ViewerEvent::Playhead { .. } => continue,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_handles_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.feed(": keep-alive\n\nevent: viewers\nda").is_empty());
+
+        let events = parser.feed("ta: {\"count\": 2}\n\nevent: talkback_ended\r\ndata: {}\r\n\r\n");
+        assert_eq!(events, vec![
+            ("viewers".to_string(), "{\"count\": 2}".to_string()),
+            ("talkback_ended".to_string(), "{}".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_presence_updates() {
+        let presence = ViewerPresenceHandle::default();
+
+        presence.apply(&parse_event("viewers", "{\"count\": 3}").unwrap());
+        presence.apply(&parse_event("talkback_started", "{\"dispatcher\": \"Sgt. Lee\"}").unwrap());
+        assert_eq!(presence.get(), ViewerPresence { viewers: 3, talkback: Some("Sgt. Lee".to_string()) });
+
+        presence.apply(&parse_event("talkback_ended", "{}").unwrap());
+        assert_eq!(presence.get().talkback, None);
+        assert!(parse_event("unknown", "{}").is_none());
+    }
+}
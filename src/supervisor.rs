@@ -0,0 +1,248 @@
+//! Supervision for background tasks. Each loop is registered by name and
+//! restarted with backoff when it fails or panics, instead of dying
+//! silently. Its health is reported in diagnostics and device status.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// What to do when a service's task ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// Run once
+    Never,
+    /// Restart after errors, up to `max_restarts` times in a row
+    OnFailure { max_restarts: u32, backoff: Duration },
+    /// Restart whenever it ends, after `backoff`
+    Always { backoff: Duration },
+}
+
+impl RestartPolicy {
+    /// Delay before restart number `attempt` (from 1), or None to give up.
+    /// The backoff doubles with each consecutive failure.
+    pub fn restart_delay(&self, failed: bool, attempt: u32) -> Option<Duration> {
+        let (backoff, allowed) = match *self {
+            RestartPolicy::Never => return None,
+            RestartPolicy::OnFailure { max_restarts, backoff } => (backoff, failed && attempt <= max_restarts),
+            RestartPolicy::Always { backoff } => (backoff, true),
+        };
+        if !allowed {
+            return None;
+        }
+        if !failed {
+            return Some(backoff);
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1).min(16));
+        Some(backoff.saturating_mul(factor).min(MAX_BACKOFF))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// Waiting to be restarted
+    Restarting,
+    /// Ended and not restarted
+    Stopped,
+    /// Gave up after failing too often
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskHealth {
+    pub name: String,
+    pub state: TaskState,
+    /// Failures and panics in a row; reset by a clean run
+    pub consecutive_failures: u32,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub last_failure: Option<DateTime<Utc>>,
+}
+
+impl TaskHealth {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: TaskState::Running,
+            consecutive_failures: 0,
+            restarts: 0,
+            last_error: None,
+            last_failure: None,
+        }
+    }
+
+    /// Down after a failure, or given up on
+    pub fn is_healthy(&self) -> bool {
+        match self.state {
+            TaskState::Running => true,
+            TaskState::Failed => false,
+            TaskState::Restarting | TaskState::Stopped => self.consecutive_failures == 0,
+        }
+    }
+}
+
+/// Runs background tasks and tracks their health. Cloned handles share the
+/// same registry.
+#[derive(Clone, Default)]
+pub struct TaskSupervisor {
+    tasks: Arc<Mutex<BTreeMap<String, TaskHealth>>>,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The supervisor for the device's and the service runner's loops
+    pub fn global() -> &'static TaskSupervisor {
+        static GLOBAL: OnceLock<TaskSupervisor> = OnceLock::new();
+        GLOBAL.get_or_init(TaskSupervisor::new)
+    }
+
+    /// Run `run` as the task `name`, starting it again according to
+    /// `policy`. Aborting the returned handle stops the task for good.
+    pub fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, run: F) -> JoinHandle<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.tasks.lock().unwrap().insert(name.to_string(), TaskHealth::new(name));
+        let supervisor = self.clone();
+        let name = name.to_string();
+        tokio::spawn(async move { supervisor.supervise(&name, policy, run).await })
+    }
+
+    async fn supervise<F, Fut>(&self, name: &str, policy: RestartPolicy, run: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut attempt = 0u32;
+        loop {
+            let error = match AssertUnwindSafe(run()).catch_unwind().await {
+                Ok(Ok(())) => {
+                    tracing::info!("Task {} finished", name);
+                    None
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Task {} failed: {:#}", name, e);
+                    Some(format!("{:#}", e))
+                }
+                Err(panic) => {
+                    let message = panic_message(panic.as_ref());
+                    tracing::error!("Task {} panicked: {}", name, message);
+                    Some(format!("panicked: {}", message))
+                }
+            };
+            let failed = error.is_some();
+            attempt = if failed { attempt + 1 } else { 0 };
+
+            let delay = policy.restart_delay(failed, attempt.max(1));
+            self.update(name, |health| {
+                health.consecutive_failures = attempt;
+                if let Some(error) = error {
+                    health.last_error = Some(error);
+                    health.last_failure = Some(Utc::now());
+                }
+                health.state = match (&delay, failed) {
+                    (Some(_), _) => TaskState::Restarting,
+                    (None, true) => TaskState::Failed,
+                    (None, false) => TaskState::Stopped,
+                };
+            });
+
+            let Some(delay) = delay else {
+                if failed {
+                    tracing::error!("Task {} stopped after {} failures", name, attempt);
+                }
+                return;
+            };
+            tracing::debug!("Restarting task {} in {:?}", name, delay);
+            tokio::time::sleep(delay).await;
+            self.update(name, |health| {
+                health.state = TaskState::Running;
+                health.restarts += 1;
+            });
+        }
+    }
+
+    fn update(&self, name: &str, change: impl FnOnce(&mut TaskHealth)) {
+        if let Some(health) = self.tasks.lock().unwrap().get_mut(name) {
+            change(health);
+        }
+    }
+
+    /// Every registered task, by name
+    pub fn health(&self) -> Vec<TaskHealth> {
+        self.tasks.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn unhealthy(&self) -> Vec<TaskHealth> {
+        self.health().into_iter().filter(|task| !task.is_healthy()).collect()
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_restart_delays_follow_policy() {
+        let backoff = Duration::from_secs(2);
+
+        assert_eq!(RestartPolicy::Never.restart_delay(true, 1), None);
+
+        let on_failure = RestartPolicy::OnFailure { max_restarts: 3, backoff };
+        assert_eq!(on_failure.restart_delay(false, 1), None);
+        assert_eq!(on_failure.restart_delay(true, 1), Some(Duration::from_secs(2)));
+        assert_eq!(on_failure.restart_delay(true, 3), Some(Duration::from_secs(8)));
+        assert_eq!(on_failure.restart_delay(true, 4), None);
+
+        let always = RestartPolicy::Always { backoff };
+        assert_eq!(always.restart_delay(false, 1), Some(backoff));
+        assert_eq!(always.restart_delay(true, 30), Some(MAX_BACKOFF));
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_is_restarted_then_reported() {
+        let supervisor = TaskSupervisor::new();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        let policy = RestartPolicy::OnFailure { max_restarts: 2, backoff: Duration::from_millis(1) };
+        supervisor.spawn("flaky", policy, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                panic!("sensor gone");
+            }
+        }).await.unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let unhealthy = supervisor.unhealthy();
+        assert_eq!(unhealthy.len(), 1);
+        assert_eq!(unhealthy[0].state, TaskState::Failed);
+        assert_eq!(unhealthy[0].restarts, 2);
+        assert_eq!(unhealthy[0].last_error.as_deref(), Some("panicked: sensor gone"));
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// What the device does when its enclosure is opened or it is pried off the
+/// mount, beyond raising a critical incident
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TamperPolicy {
+    /// Increases with each policy the backend issues; older ones are refused
+    pub version: u64,
+    pub start_recording: bool,
+    pub start_streaming: bool,
+    /// Ignore every button except emergency until unlocked remotely
+    pub lock_controls: bool,
+    /// Stills grabbed before recording takes the camera; 0 for none
+    pub snapshot_burst: u32,
+    /// Text the configured emergency contacts
+    pub alert_contacts: bool,
+    /// Shred local keys once this many tamper events fall within `window_minutes`
+    pub wipe_keys_after: Option<u32>,
+    pub window_minutes: u64,
+}
+
+impl Default for TamperPolicy {
+    /// Until the backend sends a policy, tampering only raises an incident
+    fn default() -> Self {
+        Self {
+            version: 0,
+            start_recording: false,
+            start_streaming: false,
+            lock_controls: false,
+            snapshot_burst: 0,
+            alert_contacts: false,
+            wipe_keys_after: None,
+            window_minutes: 10,
+        }
+    }
+}
+
+/// A policy as the backend sends it: the JSON exactly as signed, so
+/// verification doesn't depend on how it would be re-serialized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTamperPolicy {
+    pub payload: String,
+    /// Base64 Ed25519 signature over `payload`
+    pub signature: String,
+}
+
+impl SignedTamperPolicy {
+    /// The policy, if `public_key` (base64 Ed25519) signed it
+    pub fn verify(&self, public_key: &str) -> Result<TamperPolicy> {
+        verify_signature(public_key, &self.payload, &self.signature)
+            .map_err(|e| anyhow::anyhow!("Tamper policy signature is not valid: {}", e))?;
+        serde_json::from_str(&self.payload).context("Invalid tamper policy")
+    }
+}
+
+/// Check a base64 Ed25519 `signature` over `payload` against the backend's
+/// policy key, `security.policy_public_key`
+pub fn verify_signature(public_key: &str, payload: &str, signature: &str) -> Result<()> {
+    let key_bytes: [u8; 32] = general_purpose::STANDARD.decode(public_key)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid policy public key length"))?;
+    let signature_bytes: [u8; 64] = general_purpose::STANDARD.decode(signature)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid policy signature length"))?;
+
+    VerifyingKey::from_bytes(&key_bytes)?
+        .verify(payload.as_bytes(), &Signature::from_bytes(&signature_bytes))
+        .map_err(|_| anyhow::anyhow!("signature does not match"))
+}
+
+/// What to do about one tamper event
+#[derive(Debug, Clone, PartialEq)]
+pub struct TamperResponse {
+    pub policy: TamperPolicy,
+    /// Tamper events within the policy window, this one included
+    pub events_in_window: u32,
+    pub wipe_keys: bool,
+}
+
+/// The active policy, persisted with its signature so it survives restarts
+/// and a policy edited on disk is ignored
+pub struct TamperResponder {
+    path: PathBuf,
+    public_key: Option<String>,
+    policy: TamperPolicy,
+    recent: VecDeque<DateTime<Utc>>,
+}
+
+impl TamperResponder {
+    pub fn default_path() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("tamper_policy.json")
+    }
+
+    pub async fn load(public_key: Option<&str>) -> Self {
+        Self::load_from(Self::default_path(), public_key).await
+    }
+
+    pub async fn load_from(path: PathBuf, public_key: Option<&str>) -> Self {
+        let stored = tokio::fs::read_to_string(&path).await.ok()
+            .and_then(|content| serde_json::from_str::<SignedTamperPolicy>(&content).ok());
+        let policy = match (stored, public_key) {
+            (Some(signed), Some(key)) => signed.verify(key).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring stored tamper policy: {:#}", e);
+                TamperPolicy::default()
+            }),
+            _ => TamperPolicy::default(),
+        };
+
+        Self {
+            path,
+            public_key: public_key.map(str::to_string),
+            policy,
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &TamperPolicy {
+        &self.policy
+    }
+
+    /// Verify and persist a policy from the backend
+    pub async fn install(&mut self, signed: SignedTamperPolicy) -> Result<&TamperPolicy> {
+        let public_key = self.public_key.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("No policy public key configured; refusing unsigned policy"))?;
+        let policy = signed.verify(public_key)?;
+        if policy.version <= self.policy.version {
+            return Err(anyhow::anyhow!(
+                "Tamper policy version {} is not newer than {}",
+                policy.version, self.policy.version
+            ));
+        }
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_vec_pretty(&signed)?).await
+            .context("Failed to save tamper policy")?;
+        self.policy = policy;
+        Ok(&self.policy)
+    }
+
+    /// Count a tamper event at `now` and decide the response
+    pub fn record_event(&mut self, now: DateTime<Utc>) -> TamperResponse {
+        let window = Duration::minutes(self.policy.window_minutes as i64);
+        self.recent.push_back(now);
+        while self.recent.front().is_some_and(|at| now - *at > window) {
+            self.recent.pop_front();
+        }
+
+        let events_in_window = self.recent.len() as u32;
+        TamperResponse {
+            wipe_keys: self.policy.wipe_keys_after.is_some_and(|limit| events_in_window >= limit),
+            policy: self.policy.clone(),
+            events_in_window,
+        }
+    }
+}
+
+/// Grab `count` stills from the camera into `dir`. Only usable while the
+/// camera isn't held open by a recording.
+pub async fn capture_burst(device_path: &str, count: u32, dir: &Path) -> Result<Vec<PathBuf>> {
+    tokio::fs::create_dir_all(dir).await?;
+    let prefix = format!("tamper_{}", Utc::now().format("%Y%m%d_%H%M%S"));
+    let pattern = dir.join(format!("{}_%02d.jpg", prefix));
+
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-y", "-f", "v4l2", "-i", device_path])
+        .args(["-vf", "fps=4", "-q:v", "2", "-frames:v", &count.to_string()])
+        .arg(&pattern)
+        .output()
+        .await
+        .context("Failed to run ffmpeg for snapshot burst")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Snapshot burst failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok((1..=count)
+        .map(|n| dir.join(format!("{}_{:02}.jpg", prefix, n)))
+        .filter(|path| path.exists())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use rand::rngs::OsRng;
+
+    fn sign(key: &SigningKey, policy: &TamperPolicy) -> SignedTamperPolicy {
+        let payload = serde_json::to_string(policy).unwrap();
+        SignedTamperPolicy {
+            signature: general_purpose::STANDARD.encode(key.sign(payload.as_bytes()).to_bytes()),
+            payload,
+        }
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        general_purpose::STANDARD.encode(key.verifying_key().as_bytes())
+    }
+
+    #[tokio::test]
+    async fn test_only_newer_signed_policies_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = SigningKey::generate(&mut OsRng);
+        let path = dir.path().join("tamper_policy.json");
+        let mut responder = TamperResponder::load_from(path.clone(), Some(&public_key(&key))).await;
+
+        let policy = TamperPolicy { version: 2, lock_controls: true, ..TamperPolicy::default() };
+        responder.install(sign(&key, &policy)).await.unwrap();
+        assert!(responder.policy().lock_controls);
+
+        let older = TamperPolicy { version: 1, ..TamperPolicy::default() };
+        assert!(responder.install(sign(&key, &older)).await.is_err());
+
+        let mut forged = sign(&key, &TamperPolicy { version: 3, ..TamperPolicy::default() });
+        forged.payload = forged.payload.replace("\"lock_controls\":false", "\"lock_controls\":true");
+        assert!(responder.install(forged).await.is_err());
+
+        // The installed policy survives a restart, but only with its key
+        let reloaded = TamperResponder::load_from(path.clone(), Some(&public_key(&key))).await;
+        assert_eq!(reloaded.policy(), &policy);
+        let other = SigningKey::generate(&mut OsRng);
+        let untrusted = TamperResponder::load_from(path, Some(&public_key(&other))).await;
+        assert_eq!(untrusted.policy(), &TamperPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn test_keys_wiped_after_repeated_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut responder = TamperResponder::load_from(dir.path().join("policy.json"), None).await;
+        responder.policy = TamperPolicy { wipe_keys_after: Some(3), window_minutes: 10, ..TamperPolicy::default() };
+
+        let start = Utc::now();
+        assert!(!responder.record_event(start).wipe_keys);
+        assert!(!responder.record_event(start + Duration::minutes(5)).wipe_keys);
+        // The first event has aged out of the window
+        let response = responder.record_event(start + Duration::minutes(12));
+        assert_eq!(response.events_in_window, 2);
+        assert!(!response.wipe_keys);
+        assert!(responder.record_event(start + Duration::minutes(13)).wipe_keys);
+    }
+}
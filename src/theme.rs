@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// Colour scheme for the touchscreen UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Dark,
+    #[default]
+    Light,
+    /// Black on white with heavy borders, readable in direct sunlight
+    HighContrast,
+}
+
+/// A fixed theme, or `Auto` to follow the ambient light sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    Auto,
+    Dark,
+    Light,
+    HighContrast,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    pub mode: ThemeMode,
+    /// Switch to high contrast above this light level, and back below the exit level
+    pub sunlight_enter_lux: f64,
+    pub sunlight_exit_lux: f64,
+    /// Switch to the dark theme below this light level, and back above the exit level
+    pub dark_enter_lux: f64,
+    pub dark_exit_lux: f64,
+    /// Bigger buttons and sliders for use with gloves
+    pub large_touch_targets: bool,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            mode: ThemeMode::Auto,
+            sunlight_enter_lux: 10_000.0,
+            sunlight_exit_lux: 5_000.0,
+            dark_enter_lux: 10.0,
+            dark_exit_lux: 30.0,
+            large_touch_targets: false,
+        }
+    }
+}
+
+/// Shared view of the current theme, read by the UI
+#[derive(Debug, Clone, Default)]
+pub struct ThemeHandle {
+    state: Arc<Mutex<Theme>>,
+}
+
+impl ThemeHandle {
+    pub fn get(&self) -> Theme {
+        *self.state.lock().unwrap()
+    }
+
+    fn set(&self, theme: Theme) {
+        *self.state.lock().unwrap() = theme;
+    }
+}
+
+/// Picks the theme from the configured mode, or from ambient light in
+/// `Auto`. The enter/exit bands stop the screen flipping in passing shade.
+pub struct ThemeController {
+    config: ThemeConfig,
+    handle: ThemeHandle,
+}
+
+impl ThemeController {
+    pub fn new(config: ThemeConfig) -> Self {
+        let controller = Self { config, handle: ThemeHandle::default() };
+        if let Some(theme) = controller.fixed_theme() {
+            controller.handle.set(theme);
+        }
+        controller
+    }
+
+    pub fn handle(&self) -> ThemeHandle {
+        self.handle.clone()
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.handle.get()
+    }
+
+    pub fn mode(&self) -> ThemeMode {
+        self.config.mode
+    }
+
+    pub fn large_touch_targets(&self) -> bool {
+        self.config.large_touch_targets
+    }
+
+    /// Change the mode, e.g. from the settings screen; returns the theme now in use
+    pub fn set_mode(&mut self, mode: ThemeMode) -> Theme {
+        self.config.mode = mode;
+        if let Some(theme) = self.fixed_theme() {
+            self.handle.set(theme);
+        }
+        self.theme()
+    }
+
+    fn fixed_theme(&self) -> Option<Theme> {
+        match self.config.mode {
+            ThemeMode::Auto => None,
+            ThemeMode::Dark => Some(Theme::Dark),
+            ThemeMode::Light => Some(Theme::Light),
+            ThemeMode::HighContrast => Some(Theme::HighContrast),
+        }
+    }
+
+    /// Feed an ambient light reading; returns the new theme when it changes
+    pub fn observe_lux(&mut self, lux: f64) -> Option<Theme> {
+        if self.config.mode != ThemeMode::Auto {
+            return None;
+        }
+
+        let current = self.theme();
+        let wants = match current {
+            Theme::HighContrast if lux > self.config.sunlight_exit_lux => Theme::HighContrast,
+            Theme::Dark if lux < self.config.dark_exit_lux => Theme::Dark,
+            _ if lux >= self.config.sunlight_enter_lux => Theme::HighContrast,
+            _ if lux <= self.config.dark_enter_lux => Theme::Dark,
+            _ => Theme::Light,
+        };
+
+        if wants == current {
+            return None;
+        }
+        self.handle.set(wants);
+        Some(wants)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_theme_follows_light_with_hysteresis() {
+        let mut controller = ThemeController::new(ThemeConfig::default());
+        let handle = controller.handle();
+
+        assert_eq!(controller.observe_lux(500.0), None);
+        assert_eq!(controller.observe_lux(20_000.0), Some(Theme::HighContrast));
+        // Passing shade inside the band keeps high contrast
+        assert_eq!(controller.observe_lux(7_000.0), None);
+        assert_eq!(controller.observe_lux(2_000.0), Some(Theme::Light));
+        assert_eq!(controller.observe_lux(5.0), Some(Theme::Dark));
+        assert_eq!(controller.observe_lux(20.0), None);
+        assert_eq!(handle.get(), Theme::Dark);
+    }
+
+    #[test]
+    fn test_fixed_mode_ignores_light() {
+        let mut controller = ThemeController::new(ThemeConfig { mode: ThemeMode::Dark, ..ThemeConfig::default() });
+        assert_eq!(controller.theme(), Theme::Dark);
+        assert_eq!(controller.observe_lux(50_000.0), None);
+
+        assert_eq!(controller.set_mode(ThemeMode::HighContrast), Theme::HighContrast);
+        controller.set_mode(ThemeMode::Auto);
+        assert_eq!(controller.observe_lux(50_000.0), None);
+        assert_eq!(controller.observe_lux(500.0), Some(Theme::Light));
+    }
+}
@@ -3,18 +3,55 @@ use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
 use tokio::sync::mpsc;
 
+use crate::access::Credential;
+use crate::backend::BackendKind;
 use crate::config::Config;
 use crate::device::BodycamDevice;
+use crate::i18n::Localizer;
+use crate::incident::{IncidentSeverity, IncidentType, OPERATOR_INCIDENT_TYPES};
+use crate::pairing::{self, PairingRequest, QrMatrix};
+use crate::playback::{Frame, Player};
+use crate::recording_policy::PolicyDecision;
+use crate::settings::{SettingKind, SettingsDraft, SETTINGS};
+use crate::theme::{Theme, ThemeMode};
 use crate::camera::{CameraDevice, AudioDevice, CameraManager};
+use crate::camera::frame_pool::FramePool;
 
 // slint::include_modules!(); // Disabled for compilation
 
+/// Build the main window for `device` and block until it is closed.
+pub async fn run_ui(config: Config, device: BodycamDevice, config_path: Option<PathBuf>) -> Result<()> {
+    let ui = BodycamUI::new(config, device, config_path)?;
+    ui.run().await
+}
+
 pub struct BodycamUI {
     // ui: MainWindow, // Disabled for compilation
     config: Arc<Mutex<Config>>,
-    device: Arc<Mutex<BodycamDevice>>,
+    device: BodycamDevice,
     camera_manager: Arc<Mutex<CameraManager>>,
     config_path: PathBuf,
+    /// Shared with timers that build localized text
+    strings: Arc<Mutex<Localizer>>,
+    /// Settings page edits not yet applied
+    settings_draft: Arc<Mutex<SettingsDraft>>,
+    level_timer: slint::Timer,
+    presence_timer: slint::Timer,
+    /// Keeps the no-record / must-record banner current
+    policy_timer: slint::Timer,
+    /// Keeps the recording time left in the header current
+    remaining_timer: slint::Timer,
+    theme_timer: slint::Timer,
+    incident_timer: slint::Timer,
+    network_timer: slint::Timer,
+    pairing_timer: slint::Timer,
+    /// Pairing request shown on the setup screen, polled until approved
+    pairing: Arc<Mutex<Option<PairingRequest>>>,
+    playback_timer: slint::Timer,
+    /// Shows the newest captured frame
+    preview_timer: slint::Timer,
+    /// Recording open in the review player
+    player: Arc<Mutex<Option<Player>>>,
 }
 
 impl BodycamUI {
@@ -31,39 +68,63 @@ impl BodycamUI {
         
         let camera_manager = CameraManager::new()?;
         
+        if config.kiosk.enabled {
+            crate::kiosk::prefer_direct_display();
+        }
         let ui = MainWindow::new()?;
+        if config.kiosk.enabled {
+            // Stay up until a supervisor lets the UI go
+            ui.set_kiosk(true);
+            ui.set_kiosk_exit_hold((config.kiosk.exit_hold_seconds * 1000) as i64);
+            ui.window().set_fullscreen(true);
+            ui.window().on_close_requested(|| slint::CloseRequestResponse::KeepWindowShown);
+        }
+        let strings = Arc::new(Mutex::new((*device.localizer()).clone()));
         let config = Arc::new(Mutex::new(config));
-        let device = Arc::new(Mutex::new(device));
         let camera_manager = Arc::new(Mutex::new(camera_manager));
         
         let mut ui_instance = Self {
             ui,
             config: Arc::clone(&config),
-            device: Arc::clone(&device),
+            device,
             camera_manager: Arc::clone(&camera_manager),
             config_path,
+            strings,
+            settings_draft: Arc::new(Mutex::new(SettingsDraft::default())),
+            level_timer: slint::Timer::default(),
+            presence_timer: slint::Timer::default(),
+            policy_timer: slint::Timer::default(),
+            remaining_timer: slint::Timer::default(),
+            theme_timer: slint::Timer::default(),
+            incident_timer: slint::Timer::default(),
+            network_timer: slint::Timer::default(),
+            pairing_timer: slint::Timer::default(),
+            pairing: Arc::new(Mutex::new(None)),
+            playback_timer: slint::Timer::default(),
+            preview_timer: slint::Timer::default(),
+            player: Arc::new(Mutex::new(None)),
         };
         
         ui_instance.setup_ui_callbacks()?;
         ui_instance.load_initial_settings()?;
+        ui_instance.start_preview();
         
         Ok(ui_instance)
     }
 
     fn setup_ui_callbacks(&mut self
     ) -> Result<()> {
-        let device = Arc::clone(&self.device);
+        let device = self.device.clone();
         let config = Arc::clone(&self.config);
         let config_path = self.config_path.clone();
         
         // Record button
         self.ui.on_record_button_pressed({
-            let device = Arc::clone(&device);
+            let device = device.clone();
             move || {
                 let device = device.clone();
                 tokio::spawn(async move {
-                    let device = device.lock().unwrap();
-                    if device.is_recording {
+                    if device.is_recording() {
                         let _ = device.stop_recording().await;
                     } else {
                         let _ = device.start_recording(None, None).await;
@@ -72,14 +133,35 @@ impl BodycamUI {
             }
         });
         
+        // Pause button, toggling between pause and resume
+        self.ui.on_pause_button_pressed({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            move || {
+                let device = device.clone();
+                let ui = ui.clone();
+                tokio::spawn(async move {
+                    let result = if device.is_recording_paused() {
+                        device.resume_recording("ui").await
+                    } else {
+                        device.pause_recording(None, "ui").await
+                    };
+                    if let Err(e) = result {
+                        tracing::warn!("Pause button: {:#}", e);
+                    }
+                    let paused = device.is_recording_paused();
+                    let _ = ui.upgrade_in_event_loop(move |ui| ui.set_is_paused(paused));
+                });
+            }
+        });
+        
         // Emergency button
         self.ui.on_emergency_button_pressed({
-            let device = Arc::clone(&device);
+            let device = device.clone();
             move || {
                 let device = device.clone();
                 tokio::spawn(async move {
-                    let device = device.lock().unwrap();
-                    let _ = device.trigger_incident("emergency", "high").await;
+                    let _ = device.trigger_incident(IncidentType::Emergency, IncidentSeverity::High).await;
                 });
             }
         });
@@ -141,9 +223,989 @@ impl BodycamUI {
             }
         });
         
+        self.ui.on_camera_control_changed({
+            let device = device.clone();
+            move |control, value| {
+                let device = device.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = device.set_camera_control(&control, &value).await {
+                        tracing::warn!("Failed to set camera control {}: {}", control, e);
+                    }
+                });
+            }
+        });
+        
+        // Mic level meter
+        if let Some(meter) = device.audio_level_handle() {
+            let ui = self.ui.as_weak();
+            self.level_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(100), move || {
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_audio_level(meter.level().normalized());
+                    ui.set_mic_silent(meter.silent_for().map(|d| d.as_secs() >= 10).unwrap_or(false));
+                }
+            });
+        }
+        
+        // Live viewers and dispatcher talk-back
+        let presence = device.stream_presence_handle();
+        let ui = self.ui.as_weak();
+        let strings = Arc::clone(&self.strings);
+        self.presence_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(500), move || {
+            if let Some(ui) = ui.upgrade() {
+                let presence = presence.get();
+                let strings = strings.lock().unwrap();
+                let text = match &presence.talkback {
+                    Some(name) => strings.format("ui.talking", &[("name", name)]),
+                    None if presence.viewers > 0 => strings.format("ui.live_watching", &[("count", &presence.viewers.to_string())]),
+                    None => strings.get("ui.live_no_viewers"),
+                };
+                ui.set_presence_text(text.into());
+                ui.set_viewer_count(presence.viewers as i32);
+                ui.set_talkback_from(presence.talkback.unwrap_or_default().into());
+            }
+        });
+        
+        // Recording policy for where the operator is now
+        let ui = self.ui.as_weak();
+        let strings = Arc::clone(&self.strings);
+        let policy_device = device.clone();
+        self.policy_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(1), move || {
+            if let Some(ui) = ui.upgrade() {
+                let strings = strings.lock().unwrap();
+                let (text, blocked) = match policy_device.recording_restriction() {
+                    PolicyDecision::Allow => (String::new(), false),
+                    PolicyDecision::Block(rule) => (strings.format("ui.recording_blocked", &[("rule", &rule)]), true),
+                    PolicyDecision::Force(rule) => (strings.format("ui.recording_required", &[("rule", &rule)]), false),
+                };
+                ui.set_recording_policy(text.into());
+                ui.set_recording_blocked(blocked);
+            }
+        });
+
+        // Recording time left, red once it's below the warning threshold
+        let ui = self.ui.as_weak();
+        let strings = Arc::clone(&self.strings);
+        let remaining_device = device.clone();
+        self.remaining_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(5), move || {
+            let Some(ui) = ui.upgrade() else { return };
+            let Some(remaining) = remaining_device.recording_remaining() else { return };
+            let time = format!("{}h {:02}m", remaining.minutes / 60, remaining.minutes % 60);
+            let warn_minutes = remaining_device.config().recording.remaining.warn_minutes;
+            ui.set_recording_remaining(strings.lock().unwrap().format("ui.recording_remaining", &[("time", &time)]).into());
+            ui.set_recording_remaining_low(remaining.minutes < warn_minutes);
+        });
+        
+        self.ui.on_refresh_recordings({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            move || Self::refresh_recordings(device.clone(), ui.clone())
+        });
+        Self::refresh_recordings(device.clone(), self.ui.as_weak());
+
+        // Emergency contacts come from the cache, so they work offline
+        self.ui.on_refresh_contacts({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            move || Self::refresh_contacts(device.clone(), ui.clone(), Arc::clone(&strings), true)
+        });
+        for call in [false, true] {
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            let handler = move |contact_id: slint::SharedString| {
+                let device = device.clone();
+                let ui = ui.clone();
+                let strings = Arc::clone(&strings);
+                tokio::spawn(async move {
+                    let name = device.contacts().await.contacts.into_iter()
+                        .find(|contact| contact.id == contact_id.as_str())
+                        .map(|contact| contact.name)
+                        .unwrap_or_else(|| contact_id.to_string());
+                    let result = device.contact_emergency(&contact_id, call, "ui").await;
+                    let message = {
+                        let strings = strings.lock().unwrap();
+                        match result {
+                            Ok(()) if call => strings.format("ui.contact_calling", &[("name", &name)]),
+                            Ok(()) => strings.format("ui.contact_sms_sent", &[("name", &name)]),
+                            Err(e) => strings.format("ui.contact_failed", &[("name", &name), ("error", &e.to_string())]),
+                        }
+                    };
+                    let _ = ui.upgrade_in_event_loop(move |ui| ui.set_contacts_message(message.into()));
+                    Self::refresh_contacts(device, ui, strings, false);
+                });
+            };
+            if call {
+                self.ui.on_contact_call(handler);
+            } else {
+                self.ui.on_contact_sms(handler);
+            }
+        }
+        Self::refresh_contacts(device.clone(), self.ui.as_weak(), Arc::clone(&self.strings), false);
+
+        // Recording review; every opening is audited with its reason
+        self.ui.on_playback_open({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            let player = Arc::clone(&self.player);
+            move |segment_id, reason, pin| {
+                let device = device.clone();
+                let ui = ui.clone();
+                let strings = strings.lock().unwrap().clone();
+                let player = player.clone();
+                tokio::spawn(async move {
+                    let credential = (!pin.is_empty()).then(|| Credential::Pin(pin.to_string()));
+                    let result = device
+                        .open_playback(&segment_id, credential.as_ref(), &reason, "ui").await
+                        .and_then(|mut opened| opened.play().map(|_| opened));
+                    match result {
+                        Ok(opened) => {
+                            let duration = opened.duration_seconds() as f32;
+                            let bookmarks: Vec<PlaybackBookmark> = opened.bookmarks().iter().map(|bookmark| PlaybackBookmark {
+                                offset: bookmark.offset_seconds as f32,
+                                label: strings.get(bookmark.label).into(),
+                            }).collect();
+                            if let Some(previous) = player.lock().unwrap().replace(opened) {
+                                tokio::spawn(previous.close());
+                            }
+                            let _ = ui.upgrade_in_event_loop(move |ui| {
+                                ui.set_playback_duration(duration);
+                                ui.set_playback_bookmarks(slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(bookmarks))));
+                                ui.set_playback_prompt(false);
+                                ui.set_playback_active(true);
+                            });
+                        }
+                        Err(e) => {
+                            tracing::warn!("Playback of {} refused: {}", segment_id, e);
+                            let message = strings.format("playback.failed", &[("error", &e.to_string())]);
+                            let _ = ui.upgrade_in_event_loop(move |ui| ui.set_playback_message(message.into()));
+                        }
+                    }
+                });
+            }
+        });
+        
+        self.ui.on_playback_toggle({
+            let player = Arc::clone(&self.player);
+            move || {
+                let mut player = player.lock().unwrap();
+                let Some(player) = player.as_mut() else { return };
+                if player.handle().get().playing {
+                    player.pause();
+                } else if let Err(e) = player.play() {
+                    tracing::warn!("Failed to resume playback: {}", e);
+                }
+            }
+        });
+        
+        self.ui.on_playback_seek({
+            let player = Arc::clone(&self.player);
+            move |position| {
+                if let Some(player) = player.lock().unwrap().as_mut() {
+                    if let Err(e) = player.seek(position as f64) {
+                        tracing::warn!("Failed to seek: {}", e);
+                    }
+                }
+            }
+        });
+        
+        self.ui.on_playback_close({
+            let player = Arc::clone(&self.player);
+            let ui = self.ui.as_weak();
+            move || {
+                if let Some(player) = player.lock().unwrap().take() {
+                    tokio::spawn(player.close());
+                }
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_playback_active(false);
+                    ui.set_playback_playing(false);
+                }
+            }
+        });
+        
+        // Show new frames and the position while the player is open
+        let player = Arc::clone(&self.player);
+        let ui = self.ui.as_weak();
+        let mut shown_frame = 0;
+        self.playback_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(40), move || {
+            let Some(handle) = player.lock().unwrap().as_ref().map(Player::handle) else { return };
+            let Some(ui) = ui.upgrade() else { return };
+            let state = handle.get();
+            if state.frame_number != shown_frame {
+                if let Some(frame) = &state.frame {
+                    ui.set_playback_frame(Self::frame_image(frame));
+                }
+                shown_frame = state.frame_number;
+            }
+            ui.set_playback_position(state.position_seconds as f32);
+            ui.set_playback_playing(state.playing);
+        });
+        
+        // Clearing storage needs a supervisor logged in on the device
+        self.ui.on_clear_storage({
+            let device = device.clone();
+            move || {
+                let device = device.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = device.clear_storage(None, "ui").await {
+                        tracing::warn!("Clear storage failed: {}", e);
+                    }
+                });
+            }
+        });
+        
+        // Follow the theme chosen in settings or by the light sensor
+        let theme = device.theme_handle();
+        let large_touch_targets = config.lock().unwrap().theme.large_touch_targets;
+        let ui = self.ui.as_weak();
+        let mut applied = None;
+        self.theme_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(1), move || {
+            let current = theme.get();
+            if applied == Some(current) {
+                return;
+            }
+            if let Some(ui) = ui.upgrade() {
+                Self::apply_theme(&ui, current, large_touch_targets);
+                applied = Some(current);
+            }
+        });
+        
+        self.ui.on_theme_mode_changed({
+            let device = device.clone();
+            move |mode| {
+                let device = device.clone();
+                tokio::spawn(async move {
+                    let mode: ThemeMode = match serde_json::from_value(serde_json::json!(mode.as_str())) {
+                        Ok(mode) => mode,
+                        Err(_) => return,
+                    };
+                    if let Err(e) = device.set_theme_mode(mode, "ui").await {
+                        tracing::warn!("Failed to change theme: {}", e);
+                    }
+                });
+            }
+        });
+        
+        // Incident panel; the confirmation for critical incidents happens in the UI
+        self.ui.on_incident_requested({
+            let device = device.clone();
+            move |incident_type, severity| {
+                let device = device.clone();
+                tokio::spawn(async move {
+                    let parsed = incident_type.parse::<IncidentType>()
+                        .and_then(|incident_type| Ok((incident_type, severity.parse::<IncidentSeverity>()?)));
+                    let result = match parsed {
+                        Ok((incident_type, severity)) => device.trigger_incident(incident_type, severity).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to raise {} incident: {}", incident_type, e);
+                    }
+                });
+            }
+        });
+        
+        self.ui.on_end_incident({
+            let device = device.clone();
+            move || {
+                let device = device.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = device.end_incident("ui").await {
+                        tracing::warn!("Failed to end incident: {}", e);
+                    }
+                });
+            }
+        });
+        
+        // Cancels whatever long operation the device is running
+        self.ui.on_cancel_operation({
+            let canceller = device.canceller();
+            move || canceller.cancel()
+        });
+        
+        // Incident banner and elapsed time
+        let incident = device.active_incident_handle();
+        let ui = self.ui.as_weak();
+        let strings = Arc::clone(&self.strings);
+        self.incident_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(1), move || {
+            let Some(ui) = ui.upgrade() else { return };
+            match incident.get() {
+                Some(active) => {
+                    let strings = strings.lock().unwrap();
+                    let elapsed = active.elapsed(chrono::Utc::now()).num_seconds();
+                    ui.set_incident_label(strings.format("ui.incident_banner", &[
+                        ("type", &strings.get(&format!("incident.{}", active.incident_type))),
+                        ("severity", &strings.get(&format!("severity.{}", active.severity))),
+                    ]).into());
+                    ui.set_incident_elapsed(format!("{:02}:{:02}:{:02}", elapsed / 3600, elapsed / 60 % 60, elapsed % 60).into());
+                    ui.set_incident_active(true);
+                }
+                None => ui.set_incident_active(false),
+            }
+        });
+        
+        // Network panel: link and signal, upload progress, and stream
+        // health while live
+        let network = device.network_status_handle();
+        let ui = self.ui.as_weak();
+        let strings = Arc::clone(&self.strings);
+        let device_for_stats = device.clone();
+        self.network_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(2), move || {
+            let Some(ui) = ui.upgrade() else { return };
+            let network = network.get();
+            let strings = strings.lock().unwrap();
+
+            let link = match &network.interface {
+                Some(interface) if network.online => strings.format("ui.network_link", &[
+                    ("link", &strings.get(&format!("ui.link_{}", network.link.as_str()))),
+                    ("interface", interface),
+                ]),
+                _ => strings.get("ui.network_offline"),
+            };
+            ui.set_network_online(network.online);
+            ui.set_network_link(link.into());
+            ui.set_network_signal(network.signal_dbm
+                .map(|dbm| strings.format("ui.signal", &[("dbm", &dbm.to_string())]))
+                .unwrap_or_default()
+                .into());
+            ui.set_upload_rate(strings.format("ui.upload_rate", &[
+                ("rate", &format!("{:.1}", network.upload_bytes_per_second * 8.0 / 1_000_000.0)),
+            ]).into());
+            let queue = match (network.queue_depth, network.eta_seconds) {
+                (0, _) => strings.get("ui.upload_queue_empty"),
+                (count, Some(eta)) => strings.format("ui.upload_queue_eta", &[
+                    ("count", &count.to_string()),
+                    ("eta", &format!("{}:{:02}", eta / 60, eta % 60)),
+                ]),
+                (count, None) => strings.format("ui.upload_queue", &[("count", &count.to_string())]),
+            };
+            ui.set_upload_queue(queue.into());
+            ui.set_operation_running(crate::cancellation::running() > 0);
+
+            if !ui.get_is_streaming() {
+                ui.set_stream_health("".into());
+                return;
+            }
+            let device = device_for_stats.clone();
+            let ui = ui.as_weak();
+            let healthy_text = strings.get("ui.stream_health");
+            let degraded_text = strings.get("ui.stream_degraded");
+            tokio::spawn(async move {
+                let Ok(stats) = device.get_streaming_stats().await else { return };
+                let healthy = matches!(stats.status, crate::streaming::StreamStatus::Active)
+                    && stats.outputs.iter().all(|output| matches!(output.health, crate::streaming::outputs::OutputHealth::Healthy));
+                let text = if healthy { healthy_text } else { degraded_text }
+                    .replace("{bitrate}", &stats.current_bitrate.to_string())
+                    .replace("{drops}", &stats.drop_count.to_string());
+                let _ = ui.upgrade_in_event_loop(move |ui| {
+                    ui.set_stream_healthy(healthy);
+                    ui.set_stream_health(text.into());
+                });
+            });
+        });
+        
+        // Kiosk mode is left only with a supervisor PIN
+        self.ui.on_kiosk_exit({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            move |pin| {
+                let device = device.clone();
+                let ui = ui.clone();
+                let denied = strings.lock().unwrap().get("ui.kiosk_denied");
+                tokio::spawn(async move {
+                    let result = device.authorize_kiosk_exit(&pin, "ui").await;
+                    let _ = ui.upgrade_in_event_loop(move |ui| match result {
+                        Ok(operator) => {
+                            tracing::info!("{} left kiosk mode", operator.name);
+                            ui.set_kiosk_exit_prompt(false);
+                            let _ = slint::quit_event_loop();
+                        }
+                        Err(e) => {
+                            tracing::warn!("Kiosk exit refused: {}", e);
+                            ui.set_kiosk_message(denied.into());
+                        }
+                    });
+                });
+            }
+        });
+        
+        // First-run setup: Wi-Fi, platform address, then pairing
+        self.ui.on_wifi_scan({
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            move || Self::refresh_wifi(ui.clone(), strings.lock().unwrap().clone())
+        });
+        
+        self.ui.on_wifi_connect({
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            move |ssid, password| {
+                let ui = ui.clone();
+                let strings = strings.lock().unwrap().clone();
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_setup_busy(true);
+                    ui.set_setup_message(strings.format("setup.connecting", &[("ssid", &ssid)]).into());
+                }
+                tokio::spawn(async move {
+                    let result = pairing::connect_wifi(&ssid, Some(&password)).await;
+                    let _ = ui.upgrade_in_event_loop(move |ui| {
+                        ui.set_setup_busy(false);
+                        match result {
+                            Ok(()) => {
+                                ui.set_setup_message("".into());
+                                ui.set_setup_step("backend".into());
+                            }
+                            Err(e) => ui.set_setup_message(strings.format("setup.connect_failed", &[
+                                ("ssid", &ssid),
+                                ("error", &e.to_string()),
+                            ]).into()),
+                        }
+                    });
+                });
+            }
+        });
+        
+        self.ui.on_backend_url_entered({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            move |url| {
+                let device = device.clone();
+                let ui = ui.clone();
+                let strings = strings.lock().unwrap().clone();
+                tokio::spawn(async move {
+                    let url = url.trim().to_string();
+                    let result = device.set_backend_url(&url, "ui").await;
+                    let _ = ui.upgrade_in_event_loop(move |ui| match result {
+                        Ok(()) => {
+                            ui.set_backend_url(url.into());
+                            ui.set_setup_message("".into());
+                            ui.set_setup_step("pair".into());
+                        }
+                        Err(e) => ui.set_setup_message(strings.format("setup.invalid_url", &[("error", &e.to_string())]).into()),
+                    });
+                });
+            }
+        });
+        
+        self.ui.on_pairing_requested({
+            let device = device.clone();
+            let ui = self.ui.as_weak();
+            let strings = Arc::clone(&self.strings);
+            let pairing = Arc::clone(&self.pairing);
+            move |name| {
+                let device = device.clone();
+                let ui = ui.clone();
+                let strings = strings.lock().unwrap().clone();
+                let pairing = pairing.clone();
+                tokio::spawn(async move {
+                    let result = device.request_pairing(name.trim()).await
+                        .and_then(|request| QrMatrix::encode(&request.approval_url).map(|qr| (request, qr)));
+                    let _ = ui.upgrade_in_event_loop(move |ui| match result {
+                        Ok((request, qr)) => {
+                            ui.set_device_name(name);
+                            ui.set_pairing_code(request.code.clone().into());
+                            ui.set_pairing_qr(Self::qr_image(&qr));
+                            ui.set_setup_message(strings.get("setup.waiting").into());
+                            *pairing.lock().unwrap() = Some(request);
+                        }
+                        Err(e) => ui.set_setup_message(strings.format("setup.pair_failed", &[("error", &e.to_string())]).into()),
+                    });
+                });
+            }
+        });
+        
+        self.ui.on_setup_finished({
+            let ui = self.ui.as_weak();
+            move || {
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_setup_step("".into());
+                    ui.set_setup_message("".into());
+                }
+            }
+        });
+        
+        // Poll the pairing request until it is approved, rejected or expires
+        let ui = self.ui.as_weak();
+        let strings = Arc::clone(&self.strings);
+        let pairing = Arc::clone(&self.pairing);
+        let device_for_pairing = device.clone();
+        self.pairing_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(3), move || {
+            let Some(request) = pairing.lock().unwrap().clone() else { return };
+            let device = device_for_pairing.clone();
+            let pairing = pairing.clone();
+            let ui = ui.clone();
+            let strings = strings.lock().unwrap().clone();
+            tokio::spawn(async move {
+                let result = device.check_pairing(&request, "ui").await;
+                if matches!(result, Ok(false)) {
+                    return;
+                }
+                *pairing.lock().unwrap() = None;
+                let _ = ui.upgrade_in_event_loop(move |ui| {
+                    ui.set_pairing_code("".into());
+                    match result {
+                        Ok(_) => {
+                            ui.set_setup_message("".into());
+                            ui.set_setup_step("done".into());
+                        }
+                        Err(e) => ui.set_setup_message(strings.format("setup.pair_failed", &[("error", &e.to_string())]).into()),
+                    }
+                });
+            });
+        });
+        
+        // Settings page: edits are validated as they're made and only
+        // saved on apply
+        self.ui.on_setting_edited({
+            let config = Arc::clone(&config);
+            let draft = Arc::clone(&self.settings_draft);
+            let strings = Arc::clone(&self.strings);
+            let ui = self.ui.as_weak();
+            move |key, value| {
+                let Some(def) = SETTINGS.iter().find(|def| def.key == key.as_str()) else { return };
+                let value = match def.kind {
+                    SettingKind::Bool => serde_json::Value::Bool(value == "true"),
+                    _ => serde_json::Value::String(value.to_string()),
+                };
+                let config = config.lock().unwrap();
+                let _ = draft.lock().unwrap().edit(&config, &key, value);
+                if let Some(ui) = ui.upgrade() {
+                    Self::refresh_settings(&ui, &config, &draft.lock().unwrap(), &strings.lock().unwrap());
+                }
+            }
+        });
+        
+        self.ui.on_settings_revert({
+            let config = Arc::clone(&config);
+            let draft = Arc::clone(&self.settings_draft);
+            let strings = Arc::clone(&self.strings);
+            let ui = self.ui.as_weak();
+            move || {
+                let mut draft = draft.lock().unwrap();
+                draft.revert();
+                if let Some(ui) = ui.upgrade() {
+                    ui.set_settings_message("".into());
+                    Self::refresh_settings(&ui, &config.lock().unwrap(), &draft, &strings.lock().unwrap());
+                }
+            }
+        });
+        
+        self.ui.on_settings_apply({
+            let device = device.clone();
+            let config = Arc::clone(&config);
+            let config_path = config_path.clone();
+            let draft = Arc::clone(&self.settings_draft);
+            let strings = Arc::clone(&self.strings);
+            let ui = self.ui.as_weak();
+            move || {
+                let device = device.clone();
+                let config = config.clone();
+                let config_path = config_path.clone();
+                let draft = draft.clone();
+                let strings = strings.clone();
+                let ui = ui.clone();
+                tokio::spawn(async move {
+                    let (changes, restart_required) = {
+                        let draft = draft.lock().unwrap();
+                        (draft.changes().clone(), draft.restart_required())
+                    };
+                    let result = device.apply_settings(&changes, "ui").await;
+                    let message = match result {
+                        Ok(updated) => {
+                            draft.lock().unwrap().revert();
+                            let mut config = config.lock().unwrap();
+                            *config = updated;
+                            let _ = config.save(&config_path).await;
+                            String::new()
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to apply settings: {}", e);
+                            e.to_string()
+                        }
+                    };
+
+                    let config = config.lock().unwrap().clone();
+                    let draft = draft.lock().unwrap().clone();
+                    let strings = strings.lock().unwrap().clone();
+                    let _ = ui.upgrade_in_event_loop(move |ui| {
+                        ui.set_settings_message(message.as_str().into());
+                        ui.set_settings_restart_required(restart_required && message.is_empty());
+                        Self::refresh_settings(&ui, &config, &draft, &strings);
+                    });
+                });
+            }
+        });
+        
+        // Switching language applies to the UI, prompts and alerts at once
+        self.ui.on_language_changed({
+            let device = device.clone();
+            let config = Arc::clone(&config);
+            let draft = Arc::clone(&self.settings_draft);
+            let strings = Arc::clone(&self.strings);
+            let ui = self.ui.as_weak();
+            move |language| {
+                let device = device.clone();
+                let config = config.clone();
+                let draft = draft.clone();
+                let strings = strings.clone();
+                let ui = ui.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = device.set_language(&language, "ui").await {
+                        tracing::warn!("Failed to switch language to {}: {}", language, e);
+                        return;
+                    }
+                    let localizer = (*device.localizer()).clone();
+                    *strings.lock().unwrap() = localizer.clone();
+                    let config = config.lock().unwrap().clone();
+                    let draft = draft.lock().unwrap().clone();
+                    let _ = ui.upgrade_in_event_loop(move |ui| {
+                        Self::apply_strings(&ui, &localizer);
+                        Self::refresh_settings(&ui, &config, &draft, &localizer);
+                    });
+                });
+            }
+        });
+        
+        self.ui.on_audio_only_changed({
+            let device = device.clone();
+            let config = Arc::clone(&config);
+            let config_path = config_path.clone();
+            move |audio_only| {
+                let device = device.clone();
+                let config = config.clone();
+                let config_path = config_path.clone();
+                tokio::spawn(async move {
+                    device.set_audio_only_recording(audio_only);
+                    let mut config = config.lock().unwrap();
+                    config.recording.audio_only = audio_only;
+                    let _ = config.save(&config_path).await;
+                });
+            }
+        });
+        
         Ok(())
     }
 
+    /// Reload the recordings browser, marking files under legal hold
+    fn refresh_recordings(device: BodycamDevice, ui: slint::Weak<MainWindow>) {
+        tokio::spawn(async move {
+            let recordings = match device.list_recordings().await {
+                Ok(recordings) => recordings,
+                Err(e) => {
+                    tracing::warn!("Failed to list recordings: {}", e);
+                    return;
+                }
+            };
+
+            let items: Vec<RecordingItem> = recordings.into_iter().map(|r| RecordingItem {
+                segment_id: r.segment_id.unwrap_or_default().into(),
+                file_name: r.file_name.into(),
+                incident_id: r.incident_id.unwrap_or_default().into(),
+                size_mb: (r.size_bytes / 1_000_000) as i32,
+                recorded_at: r.modified_at.format("%Y-%m-%d %H:%M").to_string().into(),
+                held: r.legal_hold.is_some(),
+                hold_reason: r.legal_hold.and_then(|h| h.reason).unwrap_or_default().into(),
+            }).collect();
+
+            let _ = ui.upgrade_in_event_loop(move |ui| {
+                ui.set_recordings(slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(items))));
+            });
+        });
+    }
+
+    /// Reload the contact picker from the cached directory, downloading it
+    /// first when forced. The age line warns when the cache is stale, and
+    /// the quota line when a quota is nearly used up.
+    fn refresh_contacts(device: BodycamDevice, ui: slint::Weak<MainWindow>, strings: Arc<Mutex<Localizer>>, force: bool) {
+        tokio::spawn(async move {
+            if force {
+                device.refresh_contacts(true).await;
+            }
+            let list = device.contacts().await;
+            let quotas = device.communication_quotas().await;
+            let warn_at = f64::from(device.config().communication_policy.warn_at_percent);
+            let quota_warning = quotas.iter().any(|usage| usage.percent() >= warn_at);
+            let (age, quota) = {
+                let strings = strings.lock().unwrap();
+                let quota = quotas.iter().map(|usage| {
+                    let (used, limit) = match usage.quota {
+                        crate::communication_policy::Quota::MonthlyCost => (format!("{:.2}", usage.used), format!("{:.2}", usage.limit)),
+                        _ => (format!("{}", usage.used), format!("{}", usage.limit)),
+                    };
+                    strings.format(&format!("ui.quota_{}", usage.quota.as_str()), &[("used", &used), ("limit", &limit)])
+                }).collect::<Vec<_>>().join(" · ");
+                let age = match list.fetched_at {
+                    Some(fetched_at) => {
+                        let age = crate::contact_directory::format_age(chrono::Utc::now() - fetched_at);
+                        let key = if list.stale { "ui.contacts_stale" } else { "ui.contacts_age" };
+                        strings.format(key, &[("age", &age)])
+                    }
+                    None => strings.get("ui.contacts_never"),
+                };
+                (age, quota)
+            };
+
+            let items: Vec<ContactItem> = list.contacts.into_iter().map(|c| ContactItem {
+                id: c.id.into(),
+                name: c.name.into(),
+                kind: c.contact_type.into(),
+                number: c.phone_number.into(),
+                can_sms: c.can_receive_sms,
+                can_call: c.can_receive_calls,
+            }).collect();
+
+            let _ = ui.upgrade_in_event_loop(move |ui| {
+                ui.set_contacts(slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(items))));
+                ui.set_contacts_age(age.into());
+                ui.set_contacts_stale(list.stale);
+                ui.set_contacts_quota(quota.into());
+                ui.set_contacts_quota_warning(quota_warning);
+            });
+        });
+    }
+
+    fn apply_theme(ui: &MainWindow, theme: Theme, large_touch_targets: bool) {
+        let (background, text, muted, accent, border) = match theme {
+            Theme::Dark => ((0x1e, 0x27, 0x2e), (0xec, 0xf0, 0xf1), (0x95, 0xa5, 0xa6), (0x54, 0xa0, 0xff), 2.0),
+            Theme::Light => ((0xff, 0xff, 0xff), (0x2c, 0x3e, 0x50), (0x7f, 0x8c, 0x8d), (0x34, 0x98, 0xdb), 2.0),
+            Theme::HighContrast => ((0xff, 0xff, 0xff), (0x00, 0x00, 0x00), (0x00, 0x00, 0x00), (0x00, 0x00, 0x00), 4.0),
+        };
+        let color = |(r, g, b): (u8, u8, u8)| slint::Color::from_rgb_u8(r, g, b);
+
+        let palette = ui.global::<Palette>();
+        palette.set_background(color(background));
+        palette.set_text(color(text));
+        palette.set_muted(color(muted));
+        palette.set_accent(color(accent));
+        palette.set_border_width(border);
+        palette.set_touch_target(if large_touch_targets { 56.0 } else { 32.0 });
+        palette.set_font_size(match (theme, large_touch_targets) {
+            (_, true) => 18.0,
+            (Theme::HighContrast, false) => 16.0,
+            _ => 14.0,
+        });
+    }
+
+    /// Rebuild the settings page from the saved config and pending edits
+    fn refresh_settings(ui: &MainWindow, config: &Config, draft: &SettingsDraft, strings: &Localizer) {
+        let items: Vec<SettingItem> = SETTINGS.iter().map(|def| {
+            let value = match draft.value(config, def.key) {
+                Some(serde_json::Value::String(value)) => value,
+                Some(value) => value.to_string(),
+                None => String::new(),
+            };
+            let (kind, options): (&str, &[&str]) = match def.kind {
+                SettingKind::Bool => ("bool", &[]),
+                SettingKind::Integer { .. } => ("integer", &[]),
+                SettingKind::Choice(options) => ("choice", options),
+            };
+            let options: Vec<slint::SharedString> = options.iter().map(|o| (*o).into()).collect();
+
+            SettingItem {
+                key: def.key.into(),
+                label: strings.get(def.label).into(),
+                kind: kind.into(),
+                value: value.into(),
+                options: slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(options))),
+                locked: crate::settings::is_locked(config, def.key),
+                modified: draft.is_modified(def.key),
+                error: draft.error(def.key).unwrap_or_default().into(),
+            }
+        }).collect();
+
+        ui.set_settings(slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(items))));
+        ui.set_settings_dirty(!draft.changes().is_empty());
+    }
+
+    /// Push every UI string from `localizer` into the `Strings` global
+    fn apply_strings(ui: &MainWindow, localizer: &Localizer) {
+        let strings = ui.global::<Strings>();
+        strings.set_title(localizer.get("ui.title").into());
+        strings.set_recording(localizer.get("ui.recording").into());
+        strings.set_recording_audio(localizer.get("ui.recording_audio").into());
+        strings.set_camera_off(localizer.get("ui.camera_off").into());
+        strings.set_start_recording(localizer.get("ui.start_recording").into());
+        strings.set_stop_recording(localizer.get("ui.stop_recording").into());
+        strings.set_pause_recording(localizer.get("ui.pause_recording").into());
+        strings.set_resume_recording(localizer.get("ui.resume_recording").into());
+        strings.set_emergency(localizer.get("ui.emergency").into());
+        strings.set_cancel_emergency(localizer.get("ui.cancel_emergency").into());
+        strings.set_status(localizer.get("ui.status").into());
+        strings.set_battery(localizer.get("ui.battery").into());
+        strings.set_storage(localizer.get("ui.storage").into());
+        strings.set_time(localizer.get("ui.time").into());
+        strings.set_mic(localizer.get("ui.mic").into());
+        strings.set_camera_settings(localizer.get("ui.camera_settings").into());
+        strings.set_camera_device(localizer.get("ui.camera_device").into());
+        strings.set_audio_device(localizer.get("ui.audio_device").into());
+        strings.set_resolution(localizer.get("ui.resolution").into());
+        strings.set_fps(localizer.get("ui.fps").into());
+        strings.set_brightness(localizer.get("ui.brightness").into());
+        strings.set_zoom(localizer.get("ui.zoom").into());
+        strings.set_auto_exposure(localizer.get("ui.auto_exposure").into());
+        strings.set_auto_focus(localizer.get("ui.auto_focus").into());
+        strings.set_focus(localizer.get("ui.focus").into());
+        strings.set_ir_mode(localizer.get("ui.ir_mode").into());
+        strings.set_recording_settings(localizer.get("ui.recording_settings").into());
+        strings.set_simulation_mode(localizer.get("ui.simulation_mode").into());
+        strings.set_audio_only(localizer.get("ui.audio_only").into());
+        strings.set_recordings(localizer.get("ui.recordings").into());
+        strings.set_hold(localizer.get("ui.hold").into());
+        strings.set_refresh(localizer.get("ui.refresh").into());
+        strings.set_network_status(localizer.get("ui.network_status").into());
+        strings.set_language(localizer.get("ui.language").into());
+        strings.set_theme(localizer.get("ui.theme").into());
+        strings.set_footer(localizer.get("ui.footer").into());
+        strings.set_settings(localizer.get("ui.settings").into());
+        strings.set_apply(localizer.get("ui.apply").into());
+        strings.set_revert(localizer.get("ui.revert").into());
+        strings.set_locked(localizer.get("ui.locked").into());
+        strings.set_restart_required(localizer.get("ui.restart_required").into());
+        strings.set_incident(localizer.get("ui.incident").into());
+        strings.set_incident_type(localizer.get("ui.incident_type").into());
+        strings.set_severity(localizer.get("ui.severity").into());
+        strings.set_raise_incident(localizer.get("ui.raise_incident").into());
+        strings.set_end_incident(localizer.get("ui.end_incident").into());
+        strings.set_confirm_critical(localizer.get("ui.confirm_critical").into());
+        strings.set_confirm(localizer.get("ui.confirm").into());
+        strings.set_cancel(localizer.get("ui.cancel").into());
+        strings.set_setup_title(localizer.get("setup.title").into());
+        strings.set_setup_wifi(localizer.get("setup.wifi").into());
+        strings.set_setup_scan(localizer.get("setup.scan").into());
+        strings.set_setup_password(localizer.get("setup.password").into());
+        strings.set_setup_connect(localizer.get("setup.connect").into());
+        strings.set_setup_skip(localizer.get("setup.skip").into());
+        strings.set_setup_backend(localizer.get("setup.backend").into());
+        strings.set_setup_next(localizer.get("setup.next").into());
+        strings.set_setup_device_name(localizer.get("setup.device_name").into());
+        strings.set_setup_pair(localizer.get("setup.pair").into());
+        strings.set_setup_pair_hint(localizer.get("setup.pair_hint").into());
+        strings.set_setup_done(localizer.get("setup.done").into());
+        strings.set_setup_finish(localizer.get("setup.finish").into());
+        strings.set_kiosk_exit(localizer.get("ui.kiosk_exit").into());
+        strings.set_play(localizer.get("ui.play").into());
+        strings.set_pause(localizer.get("ui.pause").into());
+        strings.set_close(localizer.get("ui.close").into());
+        strings.set_playback_title(localizer.get("playback.title").into());
+        strings.set_playback_reason(localizer.get("playback.reason").into());
+        strings.set_pin(localizer.get("ui.pin").into());
+        strings.set_contacts(localizer.get("ui.contacts").into());
+        strings.set_call(localizer.get("ui.call").into());
+        strings.set_sms(localizer.get("ui.sms").into());
+
+        let choices = |prefix: &str, values: &[&str]| -> slint::ModelRc<Choice> {
+            let choices: Vec<Choice> = values.iter().map(|value| Choice {
+                value: (*value).into(),
+                label: localizer.get(&format!("{}.{}", prefix, value)).into(),
+            }).collect();
+            slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(choices)))
+        };
+        let incident_types: Vec<&str> = OPERATOR_INCIDENT_TYPES.iter().map(|t| t.as_str()).collect();
+        let severities: Vec<&str> = IncidentSeverity::ALL.iter().map(|s| s.as_str()).collect();
+        ui.set_incident_types(choices("incident", &incident_types));
+        ui.set_incident_severities(choices("severity", &severities));
+        ui.set_language(localizer.language().into());
+    }
+
+    /// Fill the language picker from the installed packs
+    fn refresh_languages(config: &Config, ui: slint::Weak<MainWindow>) {
+        let dir = Localizer::packs_dir(&config.i18n);
+        tokio::spawn(async move {
+            let languages: Vec<slint::SharedString> = Localizer::available(&dir).await
+                .into_iter()
+                .map(Into::into)
+                .collect();
+            let _ = ui.upgrade_in_event_loop(move |ui| {
+                ui.set_languages(slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(languages))));
+            });
+        });
+    }
+
+    /// Fill the setup screen's network list from a Wi-Fi scan
+    fn refresh_wifi(ui: slint::Weak<MainWindow>, strings: Localizer) {
+        if let Some(ui) = ui.upgrade() {
+            ui.set_setup_busy(true);
+            ui.set_setup_message(strings.get("setup.scanning").into());
+        }
+        tokio::spawn(async move {
+            let result = pairing::scan_wifi().await;
+            let _ = ui.upgrade_in_event_loop(move |ui| {
+                ui.set_setup_busy(false);
+                match result {
+                    Ok(networks) => {
+                        let items: Vec<WifiItem> = networks.into_iter().map(|network| WifiItem {
+                            ssid: network.ssid.into(),
+                            signal: network.signal as i32,
+                            secured: network.secured,
+                        }).collect();
+                        ui.set_setup_message("".into());
+                        ui.set_wifi_networks(slint::ModelRc::from(std::rc::Rc::new(slint::VecModel::from(items))));
+                    }
+                    Err(e) => ui.set_setup_message(strings.format("setup.scan_failed", &[("error", &e.to_string())]).into()),
+                }
+            });
+        });
+    }
+
+    /// Capture the configured camera for the live preview and hand the same
+    /// frames to the device, so recording encodes them rather than opening
+    /// the camera again
+    fn start_preview(&mut self) {
+        let (camera_index, resolution, fps, pool_size) = {
+            let config = self.config.lock().unwrap();
+            if config.simulation.enabled {
+                return;
+            }
+            let resolution = crate::camera::quality_ladder::parse_resolution(&config.camera.resolution)
+                .unwrap_or((1280, 720));
+            (config.camera.device_index, resolution, config.camera.fps.max(1), config.camera.frame_pool_size)
+        };
+
+        let pool = FramePool::new(pool_size);
+        let feed = match self.camera_manager.lock().unwrap().start_capture(camera_index, resolution, fps, pool) {
+            Ok(feed) => feed,
+            Err(e) => {
+                tracing::warn!("Live preview unavailable: {:#}", e);
+                return;
+            }
+        };
+        self.device.attach_frame_feed(Some(feed.clone()));
+
+        let mut frames = feed.subscribe();
+        let ui = self.ui.as_weak();
+        self.preview_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_millis(1000 / fps as u64), move || {
+            let Some(frame) = frames.try_latest() else { return };
+            let Some(ui) = ui.upgrade() else { return };
+            // The one copy on this path, into the texture slint uploads
+            let buffer = slint::SharedPixelBuffer::<slint::Rgb8Pixel>::clone_from_slice(frame.data(), frame.width, frame.height);
+            ui.set_camera_preview(slint::Image::from_rgb8(buffer));
+            ui.set_preview_active(true);
+        });
+    }
+
+    fn frame_image(frame: &Frame) -> slint::Image {
+        let buffer = slint::SharedPixelBuffer::<slint::Rgb8Pixel>::clone_from_slice(&frame.rgb, frame.width, frame.height);
+        slint::Image::from_rgb8(buffer)
+    }
+
+    /// One pixel per module with the standard four-module quiet zone; the
+    /// Image element scales it up
+    fn qr_image(qr: &QrMatrix) -> slint::Image {
+        const QUIET_ZONE: usize = 4;
+        let size = qr.width + QUIET_ZONE * 2;
+        let mut buffer = slint::SharedPixelBuffer::<slint::Rgb8Pixel>::new(size as u32, size as u32);
+        for (i, pixel) in buffer.make_mut_slice().iter_mut().enumerate() {
+            let (x, y) = (i % size, i / size);
+            let dark = x >= QUIET_ZONE && y >= QUIET_ZONE && qr.is_dark(x - QUIET_ZONE, y - QUIET_ZONE);
+            let level = if dark { 0 } else { 255 };
+            *pixel = slint::Rgb8Pixel { r: level, g: level, b: level };
+        }
+        slint::Image::from_rgb8(buffer)
+    }
+
     fn load_initial_settings(&mut self
     ) -> Result<()> {
         let config = self.config.lock().unwrap();
@@ -164,6 +1226,22 @@ impl BodycamUI {
         self.ui.set_audio_devices(slint::ModelRc::from(slint::VecModel::from(audio_names)));
         
         self.ui.set_is_simulation(config.simulation.enabled);
+        self.ui.set_audio_only(config.recording.audio_only);
+        Self::apply_strings(&self.ui, &self.strings.lock().unwrap());
+        self.ui.set_theme_mode(serde_json::to_value(config.theme.mode)?.as_str().unwrap_or("auto").into());
+        Self::refresh_languages(&config, self.ui.as_weak());
+        Self::refresh_settings(&self.ui, &config, &self.settings_draft.lock().unwrap(), &self.strings.lock().unwrap());
+        
+        // Unpaired devices start in the setup screen
+        if !config.is_provisioned() {
+            let backend_url = match config.backend.resolve(&config) {
+                BackendKind::Convex => config.convex_url.clone().unwrap_or_default(),
+                _ => config.server_url.clone(),
+            };
+            self.ui.set_backend_url(backend_url.into());
+            self.ui.set_setup_step("wifi".into());
+            Self::refresh_wifi(self.ui.as_weak(), self.strings.lock().unwrap().clone());
+        }
         
         Ok(())
     }
@@ -194,6 +1272,14 @@ impl BodycamUI {
     pub fn update_recording_status(&self, is_recording: bool
     ) {
         self.ui.set_is_recording(is_recording);
+        if !is_recording {
+            self.ui.set_is_paused(false);
+        }
+    }
+
+    pub fn update_streaming_status(&self, is_streaming: bool
+    ) {
+        self.ui.set_is_streaming(is_streaming);
     }
 
     pub fn update_time(&self, time: &str
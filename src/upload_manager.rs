@@ -1,4 +1,4 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -7,11 +7,19 @@ use tokio::sync::{RwLock, mpsc, Semaphore};
 use tokio::fs;
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::time::{interval, Duration};
-use tracing::{info, warn, error};
+use tracing::{debug, info, warn, error};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::convex_api::ConvexApiClient;
+use crate::event_bus::{BusEvent, EventBus, UploadEvent};
+use crate::integrity::IntegrityManager;
+use crate::storage_manager::record_confirmed_upload;
+use sha2::{Digest, Sha256};
+
+/// Wait before a failed upload is tried again, doubling with each failure
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(300);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UploadFile {
@@ -22,6 +30,8 @@ pub struct UploadFile {
     pub chunk_size: u64,
     pub total_chunks: u64,
     pub uploaded_chunks: Vec<u32>,
+    /// Server session of a chunked upload in progress, reused on retry
+    pub upload_session_id: Option<String>,
     pub status: UploadStatus,
     pub priority: UploadPriority,
     pub metadata: serde_json::Value,
@@ -30,9 +40,12 @@ pub struct UploadFile {
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub retry_count: u32,
     pub max_retries: u32,
+    /// A failed upload isn't dispatched again before this
+    #[serde(default)]
+    pub retry_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum UploadStatus {
     Pending,
@@ -58,16 +71,117 @@ pub struct UploadChunk {
     pub chunk_index: u32,
     pub data: Vec<u8>,
     pub size: u64,
-    pub md5_hash: String,
+    pub sha256_hash: String,
+}
+
+/// Token bucket shared by every upload connection, so the total upload
+/// rate stays under the configured cap however many uploads run at once
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_second: u64,
+    tokens: f64,
+    last_refill: std::time::Instant,
+}
+
+impl BandwidthLimiter {
+    /// A rate of 0 disables the cap
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second,
+            tokens: bytes_per_second as f64,
+            last_refill: std::time::Instant::now(),
+        }
+    }
+
+    /// Take `bytes` from the bucket, returning how long the caller must wait
+    /// before sending them
+    fn reserve(&mut self, bytes: u64, now: std::time::Instant) -> Duration {
+        if self.bytes_per_second == 0 {
+            return Duration::ZERO;
+        }
+
+        let rate = self.bytes_per_second as f64;
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+        self.tokens -= bytes as f64;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+/// Picks the next upload so incident footage goes first without starving
+/// routine segments: after `incident_weight` incident uploads in a row, a
+/// waiting routine upload gets the next slot.
+#[derive(Debug)]
+pub struct FairScheduler {
+    incident_weight: u32,
+    incidents_in_a_row: u32,
+}
+
+impl FairScheduler {
+    pub fn new(incident_weight: u32) -> Self {
+        Self {
+            incident_weight: incident_weight.max(1),
+            incidents_in_a_row: 0,
+        }
+    }
+
+    fn is_incident(file: &UploadFile) -> bool {
+        file.priority <= UploadPriority::High || file.incident_id.is_some()
+    }
+
+    /// Choose among uploads ready to start
+    fn next<'a>(&mut self, ready: &[&'a UploadFile]) -> Option<&'a UploadFile> {
+        let oldest = |incident: bool| {
+            ready.iter()
+                .filter(|f| Self::is_incident(f) == incident)
+                .min_by(|a, b| a.priority.cmp(&b.priority).then(a.created_at.cmp(&b.created_at)))
+                .copied()
+        };
+
+        let incident = oldest(true);
+        let routine = oldest(false);
+
+        let pick_routine = routine.is_some()
+            && (incident.is_none() || self.incidents_in_a_row >= self.incident_weight);
+
+        if pick_routine {
+            self.incidents_in_a_row = 0;
+            routine
+        } else {
+            if incident.is_some() {
+                self.incidents_in_a_row += 1;
+            }
+            incident
+        }
+    }
+}
+
+/// Live transfer counters for one upload
+#[derive(Debug, Clone)]
+struct TransferStats {
+    started_at: std::time::Instant,
+    bytes_sent: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct UploadManager {
     api_client: Arc<RwLock<ConvexApiClient>>,
     upload_queue: Arc<RwLock<HashMap<String, UploadFile>>>,
-    active_uploads: Arc<RwLock<HashMap<String, Semaphore>>>,
+    active_uploads: Arc<RwLock<HashMap<String, TransferStats>>>,
     upload_sender: mpsc::UnboundedSender<UploadCommand>,
-    upload_receiver: Arc<RwLock<Option<mpsc::UnboundedReceiver<UploadCommand>>>>,
+    /// Held by the running dispatch loop; free again once it stops
+    upload_receiver: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<UploadCommand>>>,
+    /// One permit per upload connection
+    upload_slots: Arc<Semaphore>,
+    bandwidth: Arc<tokio::sync::Mutex<BandwidthLimiter>>,
+    scheduler: Arc<tokio::sync::Mutex<FairScheduler>>,
+    events: EventBus,
     max_concurrent_uploads: usize,
     max_retries: u32,
     chunk_size: u64,
@@ -129,78 +243,98 @@ impl UploadManager {
             upload_queue: Arc::new(RwLock::new(HashMap::new())),
             active_uploads: Arc::new(RwLock::new(HashMap::new())),
             upload_sender,
-            upload_receiver: Arc::new(RwLock::new(Some(upload_receiver))),
-            max_concurrent_uploads,
+            upload_receiver: Arc::new(tokio::sync::Mutex::new(upload_receiver)),
+            upload_slots: Arc::new(Semaphore::new(max_concurrent_uploads.max(1))),
+            bandwidth: Arc::new(tokio::sync::Mutex::new(BandwidthLimiter::new(0))),
+            scheduler: Arc::new(tokio::sync::Mutex::new(FairScheduler::new(3))),
+            events: EventBus::default(),
+            max_concurrent_uploads: max_concurrent_uploads.max(1),
             max_retries,
             chunk_size,
         }
     }
 
-    pub async fn start(&self) -> Result<()> {
-        let mut receiver = self.upload_receiver.write().await.take()
-            .context("Upload receiver already taken")?;
+    /// Cap the combined rate of all uploads, in bytes per second (0 = no cap)
+    pub fn with_bandwidth_limit(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth = Arc::new(tokio::sync::Mutex::new(BandwidthLimiter::new(bytes_per_second)));
+        self
+    }
 
-        let api_client = self.api_client.clone();
-        let upload_queue = self.upload_queue.clone();
-        let active_uploads = self.active_uploads.clone();
+    /// Incident uploads started in a row before a waiting routine upload gets a turn
+    pub fn with_incident_weight(mut self, incident_weight: u32) -> Self {
+        self.scheduler = Arc::new(tokio::sync::Mutex::new(FairScheduler::new(incident_weight)));
+        self
+    }
 
-        let max_concurrent_uploads = self.max_concurrent_uploads;
-        let max_retries = self.max_retries;
-        let chunk_size = self.chunk_size;
+    /// Publish queued, completed and failed uploads on `events`
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
 
+    pub async fn start(&self) -> Result<()> {
+        let manager = self.clone();
         tokio::spawn(async move {
-            let semaphore = Arc::new(Semaphore::new(max_concurrent_uploads));
-            let mut interval = interval(Duration::from_secs(5));
-
-            loop {
-                tokio::select! {
-                    Some(command) = receiver.recv() => {
-                        match command {
-                            UploadCommand::AddFile { file_path, priority, metadata, incident_id } => {
-                                if let Err(e) = self.add_file_to_queue(&file_path, priority, metadata, incident_id).await {
-                                    error!("Failed to add file to queue: {}", e);
-                                }
-                            }
-                            UploadCommand::StartUpload { file_id } => {
-                                self.start_upload_worker(&file_id, semaphore.clone()).await;
-                            }
-                            UploadCommand::PauseUpload { file_id } => {
-                                self.pause_upload(&file_id).await;
-                            }
-                            UploadCommand::ResumeUpload { file_id } => {
-                                self.resume_upload(&file_id).await;
-                            }
-                            UploadCommand::CancelUpload { file_id } => {
-                                self.cancel_upload(&file_id).await;
-                            }
-                            UploadCommand::RetryUpload { file_id } => {
-                                self.retry_upload(&file_id).await;
-                            }
-                            UploadCommand::UpdateStatus { file_id, status } => {
-                                self.update_upload_status(&file_id, status).await;
-                            }
-                            UploadCommand::Shutdown => {
-                                info!("Upload manager shutting down...");
-                                break;
+            if let Err(e) = manager.run().await {
+                error!("Upload manager stopped: {}", e);
+            }
+        });
+
+        info!("Upload manager started with {} concurrent uploads", self.max_concurrent_uploads);
+        Ok(())
+    }
+
+    /// Process upload commands and dispatch queued uploads until shut down.
+    /// Only one loop runs at a time; a second call waits for the first.
+    pub async fn run(&self) -> Result<()> {
+        let mut receiver = self.upload_receiver.lock().await;
+        let mut interval = interval(Duration::from_secs(5));
+
+        loop {
+            tokio::select! {
+                command = receiver.recv() => {
+                    match command {
+                        Some(UploadCommand::AddFile { file_path, priority, metadata, incident_id }) => {
+                            if let Err(e) = self.add_file_to_queue(&file_path, priority, metadata, incident_id).await {
+                                error!("Failed to add file to queue: {}", e);
                             }
                         }
-                    }
-                    _ = interval.tick() => {
-                        self.process_pending_uploads().await;
+                        Some(UploadCommand::StartUpload { file_id }) => {
+                            self.update_status(&file_id, UploadStatus::Pending).await;
+                        }
+                        Some(UploadCommand::PauseUpload { file_id }) => {
+                            self.pause_upload(&file_id).await;
+                        }
+                        Some(UploadCommand::ResumeUpload { file_id }) => {
+                            self.resume_upload(&file_id).await;
+                        }
+                        Some(UploadCommand::CancelUpload { file_id }) => {
+                            self.cancel_upload(&file_id).await;
+                        }
+                        Some(UploadCommand::RetryUpload { file_id }) => {
+                            self.retry_upload(&file_id).await;
+                        }
+                        Some(UploadCommand::UpdateStatus { file_id, status }) => {
+                            self.update_status(&file_id, status).await;
+                        }
+                        Some(UploadCommand::Shutdown) | None => {
+                            info!("Upload manager shutting down...");
+                            return Ok(());
+                        }
                     }
                 }
+                _ = interval.tick() => {}
             }
-        });
 
-        info!("Upload manager started with {} concurrent uploads", self.max_concurrent_uploads);
-        Ok(())
+            self.dispatch_uploads().await;
+        }
     }
 
     pub fn get_sender(&self) -> mpsc::UnboundedSender<UploadCommand> {
         self.upload_sender.clone()
     }
 
-    async fn add_file_to_queue(
+    pub async fn add_file_to_queue(
         &self,
         file_path: &str,
         priority: UploadPriority,
@@ -225,6 +359,7 @@ impl UploadManager {
             chunk_size: self.chunk_size,
             total_chunks,
             uploaded_chunks: Vec::new(),
+            upload_session_id: None,
             status: UploadStatus::Pending,
             priority,
             metadata,
@@ -233,6 +368,7 @@ impl UploadManager {
             updated_at: chrono::Utc::now(),
             retry_count: 0,
             max_retries: self.max_retries,
+            retry_at: None,
         };
 
         {
@@ -241,120 +377,184 @@ impl UploadManager {
         }
 
         info!("Added file {} to upload queue with ID: {}", file_path, file_id);
-        
-        // Automatically start upload for high priority files
-        if priority >= UploadPriority::High {
-            self.start_upload_worker(&file_id, Arc::new(Semaphore::new(1))).await;
-        }
+        self.events.publish(BusEvent::Upload(UploadEvent::Queued {
+            file_id: file_id.clone(),
+            path: file_path.to_string(),
+        }));
+        self.dispatch_uploads().await;
 
         Ok(file_id)
     }
 
-    async fn start_upload_worker(
-        &self,
-        file_id: &str,
-        semaphore: Arc<Semaphore>,
-    ) {
-        let file_id = file_id.to_string();
-        let convex_integration = self.convex_integration.clone();
-        let upload_queue = self.upload_queue.clone();
-        let active_uploads = self.active_uploads.clone();
+    /// Start queued uploads while connection slots are free, choosing each
+    /// one with the fair scheduler
+    async fn dispatch_uploads(&self) {
+        loop {
+            let permit = match self.upload_slots.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
 
-        tokio::spawn(async move {
-            let _permit = semaphore.acquire().await.unwrap();
-            
             let upload_file = {
-                let mut queue = upload_queue.write().await;
-                if let Some(file) = queue.get_mut(&file_id) {
-                    file.status = UploadStatus::Uploading;
-                    file.updated_at = chrono::Utc::now();
-                    file.clone()
-                } else {
-                    error!("Upload file {} not found in queue", file_id);
-                    return;
-                }
+                let mut queue = self.upload_queue.write().await;
+                let now = chrono::Utc::now();
+                let ready: Vec<&UploadFile> = queue.values()
+                    .filter(|f| f.status == UploadStatus::Pending && f.retry_count < f.max_retries)
+                    .filter(|f| f.retry_at.map_or(true, |at| at <= now))
+                    .collect();
+
+                let file_id = match self.scheduler.lock().await.next(&ready) {
+                    Some(file) => file.id.clone(),
+                    None => return,
+                };
+
+                let file = queue.get_mut(&file_id).expect("scheduled file is queued");
+                file.status = UploadStatus::Uploading;
+                file.updated_at = chrono::Utc::now();
+                file.clone()
             };
 
-            let semaphore = Arc::new(Semaphore::new(3)); // Allow 3 concurrent chunk uploads
-            active_uploads.write().await.insert(file_id.clone(), semaphore.clone());
+            self.spawn_upload_worker(upload_file, permit);
+        }
+    }
+
+    fn spawn_upload_worker(&self, upload_file: UploadFile, permit: tokio::sync::OwnedSemaphorePermit) {
+        let manager = self.clone();
 
-            match Self::upload_file_chunks(&upload_file, &api_client, semaphore).await {
-                Ok(_) => {
+        tokio::spawn(async move {
+            let _permit = permit;
+            let file_id = upload_file.id.clone();
+
+            manager.active_uploads.write().await.insert(file_id.clone(), TransferStats {
+                started_at: std::time::Instant::now(),
+                bytes_sent: 0,
+            });
+
+            match manager.upload_file_chunks(&upload_file).await {
+                Ok(sha256) => {
                     info!("Successfully uploaded file: {}", upload_file.filename);
-                    Self::update_upload_status(&upload_queue, &file_id, UploadStatus::Completed).await;
+                    manager.update_status(&file_id, UploadStatus::Completed).await;
+                    manager.events.publish(BusEvent::Upload(UploadEvent::Completed { file_id: file_id.clone() }));
                     
-                    // Clean up local file after successful upload
-                    if let Err(e) = fs::remove_file(&upload_file.local_path).await {
-                        warn!("Failed to remove local file {}: {}", upload_file.local_path, e);
+                    // The storage manager removes the local file after the grace period
+                    if let Err(e) = record_confirmed_upload(Path::new(&upload_file.local_path), &sha256, Vec::new()).await {
+                        warn!("Failed to record confirmed upload {}: {}", upload_file.local_path, e);
                     }
                 }
                 Err(e) => {
                     error!("Failed to upload file {}: {}", upload_file.filename, e);
-                    Self::handle_upload_failure(&upload_queue, &file_id, e).await;
+                    let error = e.to_string();
+                    if Self::handle_upload_failure(&manager.upload_queue, &file_id, e).await {
+                        manager.events.publish(BusEvent::Upload(UploadEvent::Failed { file_id: file_id.clone(), error }));
+                    }
                 }
             }
 
-            active_uploads.write().await.remove(&file_id);
+            manager.active_uploads.write().await.remove(&file_id);
+
+            // Hand the freed slot to the next waiting upload
+            drop(_permit);
+            manager.dispatch_uploads().await;
         });
     }
 
-    async fn upload_file_chunks(
-        upload_file: &UploadFile,
-        api_client: &Arc<RwLock<ConvexApiClient>>,
-        semaphore: Arc<Semaphore>,
-    ) -> Result<()> {
-        let mut tasks = Vec::new();
-        let client = api_client.read().await;
+    /// Upload the chunks of one file in order over a single connection,
+    /// skipping chunks already sent by an earlier attempt. Returns the
+    /// SHA-256 the server verified.
+    async fn upload_file_chunks(&self, upload_file: &UploadFile) -> Result<String> {
+        let upload_session_id = match &upload_file.upload_session_id {
+            Some(session_id) => session_id.clone(),
+            None => {
+                let session_id = self.api_client.read().await.start_chunked_upload(
+                    &upload_file.filename,
+                    upload_file.file_size,
+                    upload_file.chunk_size,
+                    upload_file.metadata.clone(),
+                    upload_file.incident_id.clone(),
+                ).await?;
+
+                if let Some(file) = self.upload_queue.write().await.get_mut(&upload_file.id) {
+                    file.upload_session_id = Some(session_id.clone());
+                }
+                session_id
+            }
+        };
 
-        // Start chunked upload session
-        let upload_session_id = client.start_chunked_upload(
-            &upload_file.filename,
-            upload_file.file_size,
+        // Hashed up front so a file modified mid-upload is caught, and so the
+        // server can verify every chunk and the assembled file
+        let checksums = IntegrityManager::calculate_upload_checksums(
+            Path::new(&upload_file.local_path),
             upload_file.chunk_size,
-            upload_file.metadata.clone(),
-            upload_file.incident_id.clone(),
         ).await?;
 
-        // Upload each chunk
-        for chunk_index in 0..upload_file.total_chunks {
-            let permit = semaphore.acquire().await.unwrap();
-            
-            let task = Self::upload_single_chunk(
-                upload_file.clone(),
-                chunk_index as u32,
-                upload_session_id.clone(),
-                client.clone(),
-                permit,
-            );
-            
-            tasks.push(task);
-        }
+        let mut file = fs::File::open(&upload_file.local_path).await?;
 
-        // Wait for all chunks to complete
-        let results = futures::future::join_all(tasks).await;
-        
-        // Check for any failures
-        for (index, result) in results.iter().enumerate() {
-            if let Err(e) = result {
-                error!("Failed to upload chunk {}: {}", index, e);
-                return Err(anyhow::anyhow!("Chunk upload failed: {}", e));
+        for chunk_index in 0..upload_file.total_chunks as u32 {
+            if upload_file.uploaded_chunks.contains(&chunk_index) {
+                continue;
+            }
+
+            // Stop between chunks when paused or cancelled
+            let status = self.upload_queue.read().await
+                .get(&upload_file.id)
+                .map(|f| f.status.clone());
+            if !matches!(status, Some(UploadStatus::Uploading)) {
+                return Err(anyhow::anyhow!("Upload {} interrupted", upload_file.filename));
+            }
+
+            let chunk = Self::read_chunk(&mut file, upload_file, chunk_index).await?;
+            if checksums.chunk_sha256.get(chunk_index as usize) != Some(&chunk.sha256_hash) {
+                return Err(anyhow::anyhow!("File {} changed during upload", upload_file.filename));
             }
+
+            let wait = self.bandwidth.lock().await.reserve(chunk.size, std::time::Instant::now());
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+
+            self.api_client.read().await.upload_chunk(
+                &upload_session_id,
+                chunk.chunk_index,
+                &chunk.data,
+                &chunk.sha256_hash,
+            ).await?;
+
+            if let Some(file) = self.upload_queue.write().await.get_mut(&upload_file.id) {
+                file.uploaded_chunks.push(chunk_index);
+                file.updated_at = chrono::Utc::now();
+            }
+            if let Some(stats) = self.active_uploads.write().await.get_mut(&upload_file.id) {
+                stats.bytes_sent += chunk.size;
+            }
+
+            debug!("Uploaded chunk {}/{} for file {}",
+                  chunk_index + 1, upload_file.total_chunks, upload_file.filename);
         }
 
-        // Complete the upload
-        client.complete_chunked_upload(&upload_session_id).await?;
+        // Complete the upload; the file only counts as uploaded (and may be
+        // deleted) once the server has verified the checksums
+        let (_, confirmation) = self.api_client.read().await
+            .complete_chunked_upload(&upload_session_id, &checksums).await?;
+
+        if let Err(e) = confirmation.ensure_matches(&checksums) {
+            let mut queue = self.upload_queue.write().await;
+            if let Some(file) = queue.get_mut(&upload_file.id) {
+                if confirmation.mismatched_chunks.is_empty() {
+                    // Nothing to pin it on, so start the upload over
+                    file.uploaded_chunks.clear();
+                    file.upload_session_id = None;
+                } else {
+                    file.uploaded_chunks.retain(|c| !confirmation.mismatched_chunks.contains(c));
+                }
+            }
+            return Err(e);
+        }
         
-        info!("Completed chunked upload for file: {}", upload_file.filename);
-        Ok(())
+        info!("Completed chunked upload for file: {} (sha256 {})", upload_file.filename, checksums.sha256);
+        Ok(checksums.sha256)
     }
 
-    async fn upload_single_chunk(
-        upload_file: UploadFile,
-        chunk_index: u32,
-        upload_session_id: String,
-        client: Arc<RwLock<ConvexApiClient>>,
-        _permit: tokio::sync::SemaphorePermit<'_>,
-    ) -> Result<()> {
+    async fn read_chunk(file: &mut fs::File, upload_file: &UploadFile, chunk_index: u32) -> Result<UploadChunk> {
         let start_offset = (chunk_index as u64) * upload_file.chunk_size;
         let end_offset = std::cmp::min(
             start_offset + upload_file.chunk_size,
@@ -362,79 +562,42 @@ impl UploadManager {
         );
         let chunk_size = end_offset - start_offset;
 
-        // Read chunk data
-        let mut file = fs::File::open(&upload_file.local_path).await?;
         file.seek(std::io::SeekFrom::Start(start_offset)).await?;
-        
-        let mut chunk_data = vec![0u8; chunk_size as usize];
-        file.read_exact(&mut chunk_data).await?;
+        let mut data = vec![0u8; chunk_size as usize];
+        file.read_exact(&mut data).await?;
 
-        // Calculate MD5 hash
-        let md5_hash = format!("{:x}", md5::compute(&chunk_data));
-
-        // Upload chunk
-        let client_guard = client.read().await;
-        client_guard.upload_chunk(
-            &upload_session_id,
+        Ok(UploadChunk {
+            file_id: upload_file.id.clone(),
             chunk_index,
-            &chunk_data,
-            &md5_hash,
-        ).await?;
-
-        info!("Uploaded chunk {}/{} for file {}", 
-              chunk_index + 1, upload_file.total_chunks, upload_file.filename);
-
-        Ok(())
-    }
-
-    async fn process_pending_uploads(&self) {
-        let queue = self.upload_queue.read().await;
-        let mut pending_files: Vec<_> = queue.values()
-            .filter(|f| f.status == UploadStatus::Pending || f.status == UploadStatus::Failed)
-            .collect();
-
-        // Sort by priority and creation time
-        pending_files.sort_by(|a, b| {
-            match a.priority.cmp(&b.priority) {
-                std::cmp::Ordering::Equal => a.created_at.cmp(&b.created_at),
-                other => other,
-            }
-        });
-
-        drop(queue);
-
-        for file in pending_files {
-            if file.retry_count < file.max_retries {
-                self.upload_sender.send(UploadCommand::StartUpload {
-                    file_id: file.id.clone(),
-                }).unwrap();
-            }
-        }
+            sha256_hash: format!("{:x}", Sha256::digest(&data)),
+            size: chunk_size,
+            data,
+        })
     }
 
     async fn pause_upload(&self, file_id: &str) {
-        Self::update_upload_status(&self.upload_queue, file_id, UploadStatus::Paused).await;
+        self.update_status(file_id, UploadStatus::Paused).await;
     }
 
     async fn resume_upload(&self, file_id: &str) {
-        Self::update_upload_status(&self.upload_queue, file_id, UploadStatus::Pending).await;
-        self.upload_sender.send(UploadCommand::StartUpload {
-            file_id: file_id.to_string(),
-        }).unwrap();
+        self.update_status(file_id, UploadStatus::Pending).await;
     }
 
     async fn cancel_upload(&self, file_id: &str) {
-        Self::update_upload_status(&self.upload_queue, file_id, UploadStatus::Cancelled).await;
+        self.update_status(file_id, UploadStatus::Cancelled).await;
     }
 
     async fn retry_upload(&self, file_id: &str) {
-        Self::update_upload_status(&self.upload_queue, file_id, UploadStatus::Pending).await;
-        self.upload_sender.send(UploadCommand::StartUpload {
-            file_id: file_id.to_string(),
-        }).unwrap();
+        let mut queue = self.upload_queue.write().await;
+        if let Some(file) = queue.get_mut(file_id) {
+            file.retry_count = 0;
+            file.retry_at = None;
+            file.status = UploadStatus::Pending;
+            file.updated_at = chrono::Utc::now();
+        }
     }
 
-    async fn update_upload_status(&self, file_id: &str, status: UploadStatus) {
+    async fn update_status(&self, file_id: &str, status: UploadStatus) {
         Self::update_upload_status(&self.upload_queue, file_id, status).await;
     }
 
@@ -450,51 +613,86 @@ impl UploadManager {
         }
     }
 
+    /// Returns true once the upload has used up its retries. Otherwise it
+    /// waits out a backoff before it is dispatched again.
     async fn handle_upload_failure(
         queue: &Arc<RwLock<HashMap<String, UploadFile>>>,
         file_id: &str,
         error: anyhow::Error,
-    ) {
+    ) -> bool {
         let mut queue = queue.write().await;
-        if let Some(file) = queue.get_mut(file_id) {
-            file.retry_count += 1;
-            file.status = if file.retry_count >= file.max_retries {
-                UploadStatus::Failed
-            } else {
-                UploadStatus::Pending
-            };
-            file.updated_at = chrono::Utc::now();
+        let Some(file) = queue.get_mut(file_id) else {
+            return false;
+        };
+        // Paused and cancelled uploads stop on purpose
+        if file.status != UploadStatus::Uploading {
+            return false;
+        }
+        file.retry_count += 1;
+        file.updated_at = chrono::Utc::now();
+        if file.retry_count >= file.max_retries {
+            warn!("Upload {} attempt {} failed, giving up: {}", file.filename, file.retry_count, error);
+            file.status = UploadStatus::Failed;
+            return true;
+        }
+        let delay = retry_delay(file.retry_count);
+        warn!("Upload {} attempt {} failed, retrying in {:?}: {}", file.filename, file.retry_count, delay, error);
+        file.status = UploadStatus::Pending;
+        file.retry_at = chrono::Duration::from_std(delay).ok().map(|delay| file.updated_at + delay);
+        false
+    }
+
+    fn progress_for(file: &UploadFile, stats: Option<&TransferStats>) -> UploadProgress {
+        let uploaded_chunks = file.uploaded_chunks.len() as u64;
+        let bytes_uploaded = (uploaded_chunks * file.chunk_size).min(file.file_size);
+        let progress = if file.file_size > 0 {
+            (bytes_uploaded as f64 / file.file_size as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        // Speed of the current connection, from what it has sent this attempt
+        let speed = stats
+            .map(|s| s.bytes_sent as f64 / s.started_at.elapsed().as_secs_f64().max(0.001))
+            .unwrap_or(0.0);
+        let eta = if speed > 0.0 {
+            Some(Duration::from_secs_f64((file.file_size - bytes_uploaded) as f64 / speed))
+        } else {
+            None
+        };
+
+        UploadProgress {
+            file_id: file.id.clone(),
+            filename: file.filename.clone(),
+            progress,
+            status: file.status.clone(),
+            bytes_uploaded,
+            bytes_total: file.file_size,
+            speed,
+            eta,
         }
     }
 
     pub async fn get_upload_progress(&self, file_id: &str) -> Option<UploadProgress> {
         let queue = self.upload_queue.read().await;
-        queue.get(file_id).map(|file| {
-            let uploaded_chunks = file.uploaded_chunks.len() as u64;
-            let total_chunks = file.total_chunks;
-            let progress = if total_chunks > 0 {
-                (uploaded_chunks as f64 / total_chunks as f64) * 100.0
-            } else {
-                0.0
-            };
-
-            UploadProgress {
-                file_id: file.id.clone(),
-                filename: file.filename.clone(),
-                progress,
-                status: file.status.clone(),
-                bytes_uploaded: uploaded_chunks * file.chunk_size,
-                bytes_total: file.file_size,
-                speed: 0.0, // TODO: Implement speed calculation
-                eta: None,  // TODO: Implement ETA calculation
-            }
-        })
+        let active = self.active_uploads.read().await;
+        queue.get(file_id).map(|file| Self::progress_for(file, active.get(file_id)))
     }
 
     pub async fn get_all_uploads(&self) -> Vec<UploadProgress> {
         let queue = self.upload_queue.read().await;
+        let active = self.active_uploads.read().await;
         queue.values()
-            .filter_map(|file| self.get_upload_progress(&file.id).await)
+            .map(|file| Self::progress_for(file, active.get(&file.id)))
+            .collect()
+    }
+
+    /// Progress of the uploads currently holding a connection
+    pub async fn get_active_uploads(&self) -> Vec<UploadProgress> {
+        let queue = self.upload_queue.read().await;
+        let active = self.active_uploads.read().await;
+        active.iter()
+            .filter_map(|(file_id, stats)| queue.get(file_id).map(|file| Self::progress_for(file, Some(stats))))
             .collect()
     }
 
@@ -502,8 +700,7 @@ impl UploadManager {
         self.upload_sender.send(UploadCommand::Shutdown)?;
         
         // Wait for active uploads to complete
-        let mut active_uploads = self.active_uploads.write().await;
-        while !active_uploads.is_empty() {
+        while !self.active_uploads.read().await.is_empty() {
             tokio::time::sleep(Duration::from_millis(100)).await;
         }
         
@@ -512,6 +709,12 @@ impl UploadManager {
     }
 }
 
+/// Backoff before retry number `retry_count` (from 1) of a failed upload
+fn retry_delay(retry_count: u32) -> Duration {
+    let factor = 2u32.saturating_pow(retry_count.saturating_sub(1).min(16));
+    RETRY_BASE_DELAY.saturating_mul(factor).min(RETRY_MAX_DELAY)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -531,6 +734,72 @@ mod tests {
         // Test priority-based sorting
     }
 
+    fn upload(id: &str, priority: UploadPriority, incident_id: Option<&str>) -> UploadFile {
+        UploadFile {
+            id: id.to_string(),
+            local_path: format!("/tmp/{}.mp4", id),
+            filename: format!("{}.mp4", id),
+            file_size: 1_000_000,
+            chunk_size: 1_000_000,
+            total_chunks: 1,
+            uploaded_chunks: Vec::new(),
+            upload_session_id: None,
+            status: UploadStatus::Pending,
+            priority,
+            metadata: serde_json::Value::Null,
+            incident_id: incident_id.map(str::to_string),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            retry_count: 0,
+            max_retries: 5,
+            retry_at: None,
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_cap() {
+        assert_eq!(retry_delay(1), RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(2), RETRY_BASE_DELAY * 2);
+        assert_eq!(retry_delay(3), RETRY_BASE_DELAY * 4);
+        assert_eq!(retry_delay(30), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_bandwidth_limiter_paces_uploads() {
+        let start = std::time::Instant::now();
+        let mut limiter = BandwidthLimiter::new(1_000_000);
+        limiter.last_refill = start;
+
+        // A full second of budget is available up front
+        assert_eq!(limiter.reserve(1_000_000, start), Duration::ZERO);
+        // Two connections sharing the cap each wait their turn
+        assert_eq!(limiter.reserve(500_000, start), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(500_000, start), Duration::from_secs(1));
+        // Time passing refills the bucket
+        assert_eq!(limiter.reserve(0, start + Duration::from_secs(2)), Duration::ZERO);
+
+        let mut unlimited = BandwidthLimiter::new(0);
+        assert_eq!(unlimited.reserve(u64::MAX, start), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_fair_scheduler_interleaves_routine_uploads() {
+        let incident_a = upload("a", UploadPriority::High, Some("incident-1"));
+        let incident_b = upload("b", UploadPriority::Critical, None);
+        let routine = upload("c", UploadPriority::Medium, None);
+        let ready = vec![&incident_a, &incident_b, &routine];
+
+        let mut scheduler = FairScheduler::new(2);
+        assert_eq!(scheduler.next(&ready).unwrap().id, "b");
+        assert_eq!(scheduler.next(&ready).unwrap().id, "b");
+        assert_eq!(scheduler.next(&ready).unwrap().id, "c");
+        assert_eq!(scheduler.next(&ready).unwrap().id, "b");
+
+        // Routine uploads run straight away when no incident is waiting
+        assert_eq!(scheduler.next(&[&routine]).unwrap().id, "c");
+        assert!(scheduler.next(&[]).is_none());
+    }
+
     #[tokio::test]
     async fn test_chunk_calculation() {
         let file_size = 10_000_000; // 10MB
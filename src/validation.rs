@@ -60,7 +60,7 @@ impl InputValidator {
         // Validate file extension for media files
         if let Some(extension) = path.extension() {
             let ext = extension.to_string_lossy().to_lowercase();
-            let allowed_extensions = ["mp4", "avi", "mov", "mkv", "wav", "mp3", "flac"];
+            let allowed_extensions = ["mp4", "avi", "mov", "mkv", "wav", "mp3", "flac", "m4a", "ogg"];
             if !allowed_extensions.contains(&ext.as_str()) {
                 return Err(anyhow::anyhow!("File extension '{}' is not allowed", ext));
             }
@@ -90,39 +90,6 @@ impl InputValidator {
         Ok(())
     }
     
-    /// Validate incident type
-    pub fn validate_incident_type(incident_type: &str) -> Result<()> {
-        if incident_type.is_empty() {
-            return Err(anyhow::anyhow!("Incident type cannot be empty"));
-        }
-        
-        let allowed_types = [
-            "emergency", "manual", "motion", "sound", "tamper", 
-            "battery_low", "storage_full", "button_press", "panic"
-        ];
-        
-        if !allowed_types.contains(&incident_type) {
-            return Err(anyhow::anyhow!("Invalid incident type: {}", incident_type));
-        }
-        
-        Ok(())
-    }
-    
-    /// Validate incident severity
-    pub fn validate_incident_severity(severity: &str) -> Result<()> {
-        if severity.is_empty() {
-            return Err(anyhow::anyhow!("Incident severity cannot be empty"));
-        }
-        
-        let allowed_severities = ["low", "medium", "high", "critical"];
-        
-        if !allowed_severities.contains(&severity) {
-            return Err(anyhow::anyhow!("Invalid incident severity: {}", severity));
-        }
-        
-        Ok(())
-    }
-    
     /// Validate device name
     pub fn validate_device_name(device_name: &str) -> Result<()> {
         if device_name.is_empty() {
@@ -0,0 +1,367 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::gps::GpsLocation;
+
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// Settings for a camera mounted in a patrol vehicle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VehicleProfileConfig {
+    pub enabled: bool,
+    /// IIO accelerometer directory, e.g. /sys/bus/iio/devices/iio:device0
+    pub imu_device: String,
+    pub imu_sample_rate_hz: u32,
+    pub harsh_braking_g: f64,
+    /// How long deceleration must last to count as harsh braking
+    pub harsh_braking_min_ms: u64,
+    pub collision_g: f64,
+    pub speed_limit_kmh: f64,
+    pub speeding_tolerance_kmh: f64,
+    /// How long the limit must be exceeded before it is reported
+    pub speeding_hold_seconds: u64,
+    /// Pre-incident buffer kept while the profile is active, so vehicle
+    /// incidents can include the run-up to a crash
+    pub pre_roll_seconds: u64,
+}
+
+impl Default for VehicleProfileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            imu_device: "/sys/bus/iio/devices/iio:device0".to_string(),
+            imu_sample_rate_hz: 50,
+            harsh_braking_g: 0.45,
+            harsh_braking_min_ms: 300,
+            collision_g: 2.5,
+            speed_limit_kmh: 100.0,
+            speeding_tolerance_kmh: 10.0,
+            speeding_hold_seconds: 10,
+            pre_roll_seconds: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VehicleEvent {
+    HarshBraking { peak_g: f64 },
+    Collision { peak_g: f64 },
+    Speeding { speed_kmh: f64, limit_kmh: f64 },
+}
+
+impl VehicleEvent {
+    /// Signal name used by the incident rules
+    pub fn signal(&self) -> &'static str {
+        match self {
+            Self::HarshBraking { .. } => "harsh_braking",
+            Self::Collision { .. } => "collision",
+            Self::Speeding { .. } => "speeding",
+        }
+    }
+}
+
+/// Acceleration in m/s², including gravity
+#[derive(Debug, Clone, Copy)]
+pub struct AccelSample {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+fn norm(v: [f64; 3]) -> f64 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+/// Finds collisions and harsh braking in accelerometer samples. Gravity is
+/// tracked with a slow low-pass filter so the mount's orientation doesn't
+/// matter.
+#[derive(Debug)]
+pub struct ImpactDetector {
+    config: VehicleProfileConfig,
+    gravity: Option<[f64; 3]>,
+    braking_since: Option<DateTime<Utc>>,
+    braking_peak: f64,
+    braking_reported: bool,
+    last_collision: Option<DateTime<Utc>>,
+}
+
+impl ImpactDetector {
+    /// Weight of each new sample in the gravity estimate; at 50 Hz gravity
+    /// settles over roughly ten seconds, longer than any braking manoeuvre
+    const GRAVITY_ALPHA: f64 = 0.002;
+
+    pub fn new(config: VehicleProfileConfig) -> Self {
+        Self {
+            config,
+            gravity: None,
+            braking_since: None,
+            braking_peak: 0.0,
+            braking_reported: false,
+            last_collision: None,
+        }
+    }
+
+    /// `decelerating` is the GPS speed trend when known; the horizontal
+    /// force of braking looks the same as hard acceleration or cornering
+    pub fn observe(&mut self, sample: AccelSample, decelerating: Option<bool>) -> Option<VehicleEvent> {
+        let raw = [sample.x, sample.y, sample.z];
+        let gravity = match self.gravity {
+            Some(g) => {
+                let g = [0, 1, 2].map(|i| g[i] + Self::GRAVITY_ALPHA * (raw[i] - g[i]));
+                self.gravity = Some(g);
+                g
+            }
+            None => {
+                self.gravity = Some(raw);
+                return None;
+            }
+        };
+
+        let linear = [0, 1, 2].map(|i| raw[i] - gravity[i]);
+        let total_g = norm(linear) / STANDARD_GRAVITY;
+
+        if total_g >= self.config.collision_g {
+            let recent = self.last_collision
+                .map(|t| sample.timestamp - t < Duration::seconds(5))
+                .unwrap_or(false);
+            self.last_collision = Some(sample.timestamp);
+            if !recent {
+                return Some(VehicleEvent::Collision { peak_g: total_g });
+            }
+            return None;
+        }
+
+        // Force in the horizontal plane, i.e. perpendicular to gravity
+        let g_norm = norm(gravity).max(f64::EPSILON);
+        let unit = gravity.map(|v| v / g_norm);
+        let vertical = linear[0] * unit[0] + linear[1] * unit[1] + linear[2] * unit[2];
+        let horizontal_g = norm([0, 1, 2].map(|i| linear[i] - vertical * unit[i])) / STANDARD_GRAVITY;
+
+        if horizontal_g < self.config.harsh_braking_g || decelerating == Some(false) {
+            self.braking_since = None;
+            self.braking_peak = 0.0;
+            self.braking_reported = false;
+            return None;
+        }
+
+        let since = *self.braking_since.get_or_insert(sample.timestamp);
+        self.braking_peak = self.braking_peak.max(horizontal_g);
+        let sustained = sample.timestamp - since >= Duration::milliseconds(self.config.harsh_braking_min_ms as i64);
+
+        if sustained && !self.braking_reported {
+            self.braking_reported = true;
+            return Some(VehicleEvent::HarshBraking { peak_g: self.braking_peak });
+        }
+        None
+    }
+}
+
+/// Watches GPS speed against the configured limit
+#[derive(Debug)]
+pub struct SpeedMonitor {
+    config: VehicleProfileConfig,
+    over_since: Option<DateTime<Utc>>,
+    reported: bool,
+    last_fix: Option<(f64, DateTime<Utc>)>,
+    trend: Option<bool>,
+}
+
+impl SpeedMonitor {
+    pub fn new(config: VehicleProfileConfig) -> Self {
+        Self {
+            config,
+            over_since: None,
+            reported: false,
+            last_fix: None,
+            trend: None,
+        }
+    }
+
+    /// Whether speed fell between the last two fixes
+    pub fn decelerating(&self) -> Option<bool> {
+        self.trend
+    }
+
+    /// Feed a GPS fix with speed in m/s
+    pub fn observe(&mut self, speed_mps: f64, timestamp: DateTime<Utc>) -> Option<VehicleEvent> {
+        if let Some((_, last)) = self.last_fix {
+            if timestamp <= last {
+                return None;
+            }
+        }
+        let previous = self.last_fix.replace((speed_mps, timestamp));
+        self.trend = previous.map(|(prev, _)| speed_mps < prev);
+
+        let speed_kmh = speed_mps * 3.6;
+        if speed_kmh <= self.config.speed_limit_kmh + self.config.speeding_tolerance_kmh {
+            // Re-arm once back within the limit
+            if speed_kmh <= self.config.speed_limit_kmh {
+                self.reported = false;
+            }
+            self.over_since = None;
+            return None;
+        }
+
+        let since = *self.over_since.get_or_insert(timestamp);
+        if !self.reported && timestamp - since >= Duration::seconds(self.config.speeding_hold_seconds as i64) {
+            self.reported = true;
+            return Some(VehicleEvent::Speeding {
+                speed_kmh,
+                limit_kmh: self.config.speed_limit_kmh,
+            });
+        }
+        None
+    }
+}
+
+/// Accelerometer exposed through the Linux IIO subsystem
+struct IioAccelerometer {
+    dir: PathBuf,
+    scale: f64,
+}
+
+impl IioAccelerometer {
+    async fn open(dir: &Path) -> Result<Self> {
+        let scale = read_value(&dir.join("in_accel_scale")).await
+            .context("Accelerometer not available")?;
+        Ok(Self { dir: dir.to_path_buf(), scale })
+    }
+
+    async fn read(&self) -> Result<AccelSample> {
+        let x = read_value(&self.dir.join("in_accel_x_raw")).await?;
+        let y = read_value(&self.dir.join("in_accel_y_raw")).await?;
+        let z = read_value(&self.dir.join("in_accel_z_raw")).await?;
+        Ok(AccelSample {
+            x: x * self.scale,
+            y: y * self.scale,
+            z: z * self.scale,
+            timestamp: Utc::now(),
+        })
+    }
+}
+
+async fn read_value(path: &Path) -> Result<f64> {
+    let content = tokio::fs::read_to_string(path).await?;
+    Ok(content.trim().parse()?)
+}
+
+/// Sample the accelerometer and GPS speed, sending vehicle events as they
+/// are detected
+pub fn spawn_monitor(
+    config: VehicleProfileConfig,
+    location: Arc<Mutex<Option<GpsLocation>>>,
+) -> (mpsc::UnboundedReceiver<VehicleEvent>, JoinHandle<()>) {
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+    let handle = tokio::spawn(async move {
+        let imu = match IioAccelerometer::open(Path::new(&config.imu_device)).await {
+            Ok(imu) => Some(imu),
+            Err(e) => {
+                tracing::warn!("Vehicle profile running on GPS only: {}", e);
+                None
+            }
+        };
+
+        let period_ms = 1000 / config.imu_sample_rate_hz.max(1) as u64;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(period_ms));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut impacts = ImpactDetector::new(config.clone());
+        let mut speed = SpeedMonitor::new(config.clone());
+
+        loop {
+            interval.tick().await;
+            let mut events = Vec::new();
+
+            let fix = location.lock().await.clone();
+            if let Some(fix) = fix {
+                if let Some(event) = fix.speed.and_then(|s| speed.observe(s, fix.timestamp)) {
+                    events.push(event);
+                }
+            }
+
+            if let Some(imu) = &imu {
+                match imu.read().await {
+                    Ok(sample) => events.extend(impacts.observe(sample, speed.decelerating())),
+                    Err(e) => tracing::debug!("Accelerometer read failed: {}", e),
+                }
+            }
+
+            for event in events {
+                tracing::info!("Vehicle event: {:?}", event);
+                if event_tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    (event_rx, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(t: &DateTime<Utc>, ms: i64, x: f64, z: f64) -> AccelSample {
+        AccelSample { x, y: 0.0, z, timestamp: *t + Duration::milliseconds(ms) }
+    }
+
+    #[test]
+    fn test_collision_reported_once() {
+        let start = Utc::now();
+        let mut detector = ImpactDetector::new(VehicleProfileConfig::default());
+
+        assert!(detector.observe(sample(&start, 0, 0.0, STANDARD_GRAVITY), None).is_none());
+        let event = detector.observe(sample(&start, 20, 40.0, STANDARD_GRAVITY), None);
+        assert!(matches!(event, Some(VehicleEvent::Collision { peak_g }) if peak_g > 2.5));
+        assert!(detector.observe(sample(&start, 40, 40.0, STANDARD_GRAVITY), None).is_none());
+    }
+
+    #[test]
+    fn test_harsh_braking_must_be_sustained() {
+        let start = Utc::now();
+        let mut detector = ImpactDetector::new(VehicleProfileConfig::default());
+        detector.observe(sample(&start, 0, 0.0, STANDARD_GRAVITY), None);
+
+        // 0.6 g sideways for 200 ms: too short
+        for ms in (20..=200).step_by(20) {
+            assert!(detector.observe(sample(&start, ms, 6.0, STANDARD_GRAVITY), Some(true)).is_none());
+        }
+        // Accelerating, not braking
+        assert!(detector.observe(sample(&start, 220, 6.0, STANDARD_GRAVITY), Some(false)).is_none());
+
+        let mut events = Vec::new();
+        for ms in (240..=800).step_by(20) {
+            events.extend(detector.observe(sample(&start, ms, 6.0, STANDARD_GRAVITY), Some(true)));
+        }
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].signal(), "harsh_braking");
+    }
+
+    #[test]
+    fn test_speeding_needs_hold_time_and_rearms() {
+        let start = Utc::now();
+        let at = |secs: i64| start + Duration::seconds(secs);
+        let mut monitor = SpeedMonitor::new(VehicleProfileConfig::default());
+        let kmh = |v: f64| v / 3.6;
+
+        assert!(monitor.observe(kmh(105.0), at(0)).is_none()); // within tolerance
+        assert!(monitor.observe(kmh(120.0), at(1)).is_none());
+        assert!(monitor.observe(kmh(120.0), at(10)).is_none());
+        assert!(matches!(monitor.observe(kmh(121.0), at(11)), Some(VehicleEvent::Speeding { .. })));
+        assert!(monitor.observe(kmh(125.0), at(30)).is_none());
+        assert_eq!(monitor.decelerating(), Some(false));
+
+        monitor.observe(kmh(90.0), at(40));
+        assert_eq!(monitor.decelerating(), Some(true));
+        monitor.observe(kmh(120.0), at(41));
+        assert!(monitor.observe(kmh(120.0), at(52)).is_some());
+    }
+}
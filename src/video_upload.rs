@@ -0,0 +1,405 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::config::Config;
+use crate::convex_api::{ConvexApiClient, VideoCreateRequest};
+use crate::event_bus::{BusEvent, EventBus, UploadEvent};
+use crate::integrity::{IntegrityManager, UploadChecksums};
+use crate::retry::RetryPolicy;
+
+/// Bounds on the chunk size the server may pick
+const MIN_CHUNK_SIZE: u64 = 256 * 1024;
+const MAX_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoUploadState {
+    /// Video record created, no chunk confirmed yet
+    Created,
+    Uploading,
+    /// Every chunk confirmed; waiting for the server to verify the file
+    Completing,
+}
+
+/// One chunked video upload, saved after every confirmed chunk so an
+/// interrupted upload resumes where it stopped, even after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoUpload {
+    pub video_id: String,
+    pub file_path: String,
+    pub file_size: u64,
+    /// As negotiated with the server when the upload started
+    pub chunk_size: u64,
+    pub total_chunks: u32,
+    /// Chunks the server has confirmed; they're confirmed in order, so this
+    /// is also the index of the next chunk to send
+    pub confirmed_chunks: u32,
+    /// Of the whole file, to spot a file that changed between attempts
+    pub sha256: String,
+    pub state: VideoUploadState,
+    pub updated_at: DateTime<Utc>,
+    /// Set when server verification failed and `confirmed_chunks` was
+    /// wound back; the server's count would undo that, so it isn't asked
+    #[serde(default)]
+    pub verification_failed: bool,
+}
+
+impl VideoUpload {
+    pub fn progress(&self) -> VideoUploadProgress {
+        VideoUploadProgress {
+            video_id: self.video_id.clone(),
+            confirmed_chunks: self.confirmed_chunks,
+            total_chunks: self.total_chunks,
+            bytes_confirmed: (self.confirmed_chunks as u64 * self.chunk_size).min(self.file_size),
+            bytes_total: self.file_size,
+        }
+    }
+}
+
+/// A video the server has received and verified
+#[derive(Debug, Clone)]
+pub struct CompletedVideoUpload {
+    pub video_id: String,
+    pub file_path: String,
+    pub checksums: UploadChecksums,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoUploadProgress {
+    pub video_id: String,
+    pub confirmed_chunks: u32,
+    pub total_chunks: u32,
+    pub bytes_confirmed: u64,
+    pub bytes_total: u64,
+}
+
+/// The chunk size to use given what we proposed and what the server offered
+pub fn negotiate_chunk_size(preferred: u64, offered: Option<u64>) -> u64 {
+    offered.unwrap_or(preferred).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE)
+}
+
+/// Drives uploads through create, chunk-by-chunk upload with retries and
+/// server-side verification, persisting progress under `state_dir`
+/// Files being uploaded by this process. A live upload and `resume_pending`
+/// use separate uploaders, so the claim is process-wide.
+fn claimed_files() -> &'static Mutex<HashSet<String>> {
+    static CLAIMED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    CLAIMED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Exclusive use of one file's upload state, released on drop
+struct UploadClaim {
+    file_path: String,
+}
+
+impl UploadClaim {
+    fn take(file_path: &str) -> Option<Self> {
+        claimed_files().lock().unwrap().insert(file_path.to_string())
+            .then(|| Self { file_path: file_path.to_string() })
+    }
+}
+
+impl Drop for UploadClaim {
+    fn drop(&mut self) {
+        claimed_files().lock().unwrap().remove(&self.file_path);
+    }
+}
+
+pub struct VideoUploader<'a> {
+    client: &'a ConvexApiClient,
+    state_dir: PathBuf,
+    preferred_chunk_size: u64,
+    retry: RetryPolicy,
+    events: Option<EventBus>,
+}
+
+impl<'a> VideoUploader<'a> {
+    pub fn default_state_dir() -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("data")
+            .join("video_uploads")
+    }
+
+    pub fn new(client: &'a ConvexApiClient, config: &Config) -> Self {
+        Self {
+            client,
+            state_dir: Self::default_state_dir(),
+            preferred_chunk_size: config.network.upload_chunk_size,
+            retry: RetryPolicy::from_config(&config.network),
+            events: None,
+        }
+    }
+
+    pub fn with_state_dir(mut self, state_dir: PathBuf) -> Self {
+        self.state_dir = state_dir;
+        self
+    }
+
+    /// Publish per-chunk progress on `events`
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Upload `file_path`, picking up an interrupted upload of the same file
+    /// if there is one. Returns once the server has verified the file.
+    pub async fn upload(&self, file_path: &str, request: &VideoCreateRequest) -> Result<CompletedVideoUpload> {
+        if crate::data_usage::cellular_capped() {
+            return Err(anyhow::anyhow!("Cellular data cap reached, {} kept until Wi-Fi", file_path));
+        }
+        let _claim = UploadClaim::take(file_path)
+            .ok_or_else(|| anyhow::anyhow!("{} is already being uploaded", file_path))?;
+        let upload = match self.load(file_path).await? {
+            Some(upload) => upload,
+            None => self.create(file_path, request).await?,
+        };
+        self.drive(upload).await
+    }
+
+    /// Finish uploads left over from before a restart
+    pub async fn resume_pending(&self) -> Result<Vec<CompletedVideoUpload>> {
+        let mut completed = Vec::new();
+        for upload in self.pending().await? {
+            let file_path = upload.file_path.clone();
+            let Some(_claim) = UploadClaim::take(&file_path) else {
+                tracing::debug!("Not resuming {}: an upload of it is in progress", file_path);
+                continue;
+            };
+            if !Path::new(&file_path).exists() {
+                tracing::warn!("Dropping upload of {}: file no longer exists", file_path);
+                self.remove(&file_path).await?;
+                continue;
+            }
+            match self.drive(upload).await {
+                Ok(upload) => completed.push(upload),
+                Err(e) => tracing::warn!("Resumed upload of {} failed: {:#}", file_path, e),
+            }
+        }
+        Ok(completed)
+    }
+
+    /// Uploads started but not completed
+    pub async fn pending(&self) -> Result<Vec<VideoUpload>> {
+        let mut uploads = Vec::new();
+        let mut entries = match fs::read_dir(&self.state_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(uploads),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match serde_json::from_slice::<VideoUpload>(&fs::read(entry.path()).await?) {
+                Ok(upload) => uploads.push(upload),
+                Err(e) => tracing::warn!("Ignoring unreadable upload state {}: {}", entry.path().display(), e),
+            }
+        }
+        uploads.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+        Ok(uploads)
+    }
+
+    async fn create(&self, file_path: &str, request: &VideoCreateRequest) -> Result<VideoUpload> {
+        let ticket = self.client.start_video_upload(request, self.preferred_chunk_size).await?;
+        let chunk_size = negotiate_chunk_size(self.preferred_chunk_size, ticket.chunk_size);
+        let checksums = IntegrityManager::calculate_upload_checksums(Path::new(file_path), chunk_size).await?;
+
+        let upload = VideoUpload {
+            video_id: ticket.video_id,
+            file_path: file_path.to_string(),
+            file_size: checksums.file_size,
+            chunk_size,
+            total_chunks: checksums.chunk_sha256.len() as u32,
+            confirmed_chunks: 0,
+            sha256: checksums.sha256,
+            state: VideoUploadState::Created,
+            updated_at: Utc::now(),
+            verification_failed: false,
+        };
+        self.save(&upload).await?;
+        tracing::info!("Started upload of {} as video {} ({} chunks of {} bytes)",
+            file_path, upload.video_id, upload.total_chunks, chunk_size);
+        Ok(upload)
+    }
+
+    async fn drive(&self, mut upload: VideoUpload) -> Result<CompletedVideoUpload> {
+        let checksums = IntegrityManager::calculate_upload_checksums(Path::new(&upload.file_path), upload.chunk_size).await?;
+        if checksums.sha256 != upload.sha256 {
+            // Chunks the server holds no longer match the file
+            self.remove(&upload.file_path).await?;
+            return Err(anyhow::anyhow!("{} changed since its upload started; it will be uploaded again", upload.file_path));
+        }
+
+        // The server is authoritative for what it has; our count may lag by
+        // a chunk confirmed just before a crash. After a failed verification
+        // it still counts the bad chunks, so the local count wins.
+        if !upload.verification_failed {
+            match self.client.get_video_upload_progress(&upload.video_id).await {
+                Ok(confirmed) => upload.confirmed_chunks = confirmed.min(upload.total_chunks),
+                Err(e) => tracing::debug!("Resuming {} from local progress: {:#}", upload.video_id, e),
+            }
+        }
+        if upload.confirmed_chunks > 0 {
+            tracing::info!("Resuming upload of {} at chunk {}/{}", upload.file_path, upload.confirmed_chunks, upload.total_chunks);
+        }
+
+        let mut file = fs::File::open(&upload.file_path).await
+            .with_context(|| format!("Failed to open {}", upload.file_path))?;
+        while upload.confirmed_chunks < upload.total_chunks {
+            let index = upload.confirmed_chunks;
+            let data = read_chunk(&mut file, &upload, index).await?;
+            let sha256 = format!("{:x}", Sha256::digest(&data));
+            if checksums.chunk_sha256.get(index as usize) != Some(&sha256) {
+                return Err(anyhow::anyhow!("{} changed during upload", upload.file_path));
+            }
+
+            self.send_chunk(&upload, index, &data, &sha256).await?;
+
+            upload.confirmed_chunks += 1;
+            upload.state = VideoUploadState::Uploading;
+            upload.updated_at = Utc::now();
+            self.save(&upload).await?;
+            self.report_progress(&upload);
+        }
+
+        upload.state = VideoUploadState::Completing;
+        self.save(&upload).await?;
+
+        let confirmation = self.client.complete_video_upload(&upload.video_id, &checksums).await?;
+        if let Err(e) = confirmation.ensure_matches(&checksums) {
+            // Send again from the first chunk the server got wrong
+            upload.confirmed_chunks = confirmation.mismatched_chunks.iter().copied().min().unwrap_or(0);
+            upload.state = VideoUploadState::Uploading;
+            upload.verification_failed = true;
+            self.save(&upload).await?;
+            return Err(e.context(format!("Video {} failed server verification", upload.video_id)));
+        }
+
+        self.remove(&upload.file_path).await?;
+        tracing::info!("Uploaded {} as video {} (sha256 {})", upload.file_path, upload.video_id, upload.sha256);
+        Ok(CompletedVideoUpload {
+            video_id: upload.video_id,
+            file_path: upload.file_path,
+            checksums,
+        })
+    }
+
+    /// Send one chunk, retrying it on its own before giving up on the upload
+    async fn send_chunk(&self, upload: &VideoUpload, index: u32, data: &[u8], sha256: &str) -> Result<()> {
+        let is_last = index + 1 == upload.total_chunks;
+        let mut attempt = 0;
+        loop {
+            match self.client.upload_video_chunk(&upload.video_id, index, data, sha256, is_last).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < self.retry.max_retries => {
+                    attempt += 1;
+                    let delay = self.retry.backoff(attempt);
+                    tracing::warn!("Chunk {}/{} of video {} failed, retrying {}/{} in {:?}: {:#}",
+                        index + 1, upload.total_chunks, upload.video_id, attempt, self.retry.max_retries, delay, e);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    return Err(e.context(format!("Chunk {}/{} of video {} not uploaded", index + 1, upload.total_chunks, upload.video_id)));
+                }
+            }
+        }
+    }
+
+    fn report_progress(&self, upload: &VideoUpload) {
+        let progress = upload.progress();
+        tracing::debug!("Video {}: {}/{} chunks confirmed", progress.video_id, progress.confirmed_chunks, progress.total_chunks);
+        if let Some(events) = &self.events {
+            events.publish(BusEvent::Upload(UploadEvent::Progress {
+                file_id: progress.video_id,
+                bytes_uploaded: progress.bytes_confirmed,
+                bytes_total: progress.bytes_total,
+            }));
+        }
+    }
+
+    fn state_path(&self, file_path: &str) -> PathBuf {
+        let key = format!("{:x}", Sha256::digest(file_path.as_bytes()));
+        self.state_dir.join(format!("{}.json", &key[..16]))
+    }
+
+    async fn load(&self, file_path: &str) -> Result<Option<VideoUpload>> {
+        match fs::read(self.state_path(file_path)).await {
+            Ok(content) => Ok(serde_json::from_slice(&content).ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, upload: &VideoUpload) -> Result<()> {
+        fs::create_dir_all(&self.state_dir).await?;
+        let path = self.state_path(&upload.file_path);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(upload)?).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, file_path: &str) -> Result<()> {
+        match fs::remove_file(self.state_path(file_path)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+async fn read_chunk(file: &mut fs::File, upload: &VideoUpload, index: u32) -> Result<Vec<u8>> {
+    let start = index as u64 * upload.chunk_size;
+    let len = upload.chunk_size.min(upload.file_size.saturating_sub(start));
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let mut data = vec![0u8; len as usize];
+    file.read_exact(&mut data).await?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_size_negotiation_respects_bounds() {
+        assert_eq!(negotiate_chunk_size(1024 * 1024, None), 1024 * 1024);
+        assert_eq!(negotiate_chunk_size(1024 * 1024, Some(2 * 1024 * 1024)), 2 * 1024 * 1024);
+        assert_eq!(negotiate_chunk_size(1024 * 1024, Some(1024)), MIN_CHUNK_SIZE);
+        assert_eq!(negotiate_chunk_size(1024 * 1024, Some(u64::MAX)), MAX_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_progress_never_exceeds_file_size() {
+        let upload = VideoUpload {
+            video_id: "video-1".to_string(),
+            file_path: "/tmp/segment.mp4".to_string(),
+            file_size: 2_500_000,
+            chunk_size: 1_000_000,
+            total_chunks: 3,
+            confirmed_chunks: 3,
+            sha256: String::new(),
+            state: VideoUploadState::Completing,
+            updated_at: Utc::now(),
+            verification_failed: false,
+        };
+        assert_eq!(upload.progress().bytes_confirmed, 2_500_000);
+    }
+
+    #[test]
+    fn test_upload_claim_is_exclusive() {
+        let claim = UploadClaim::take("/tmp/claimed.mp4");
+        assert!(claim.is_some());
+        assert!(UploadClaim::take("/tmp/claimed.mp4").is_none());
+        assert!(UploadClaim::take("/tmp/other.mp4").is_some());
+        drop(claim);
+        assert!(UploadClaim::take("/tmp/claimed.mp4").is_some());
+    }
+}
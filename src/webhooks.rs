@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::event_bus::{BusEvent, IncidentEvent, RecordingEvent, Subscription};
+use crate::hardware::HardwareEvent;
+use crate::retry::{send_with_retry, RetryPolicy};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNATURE_HEADER: &str = "X-PatrolSight-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-PatrolSight-Timestamp";
+
+/// Webhook receivers sit on the site network and should answer quickly
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    IncidentCreated,
+    RecordingStarted,
+    RecordingStopped,
+    DeviceFault,
+}
+
+/// A local endpoint, e.g. a building alarm panel or an on-site Slack relay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Shared secret the receiver checks the signature header with
+    pub secret: String,
+    /// Events to send; empty sends every kind
+    pub events: Vec<WebhookEventKind>,
+}
+
+impl WebhookConfig {
+    fn wants(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// The JSON body POSTed to each webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// Unique per event, so receivers can drop retried deliveries
+    pub id: String,
+    pub kind: WebhookEventKind,
+    pub device_id: String,
+    pub occurred_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+impl WebhookPayload {
+    /// The webhook event for a bus event, if it's one receivers care about
+    pub fn from_event(event: &BusEvent, device_id: &str) -> Option<Self> {
+        let (kind, data) = match event {
+            BusEvent::Incident(IncidentEvent::Triggered { incident_id, incident_type, severity }) => (
+                WebhookEventKind::IncidentCreated,
+                json!({ "incident_id": incident_id, "incident_type": incident_type, "severity": severity }),
+            ),
+            BusEvent::Recording(RecordingEvent::Started { incident_id }) => (
+                WebhookEventKind::RecordingStarted,
+                json!({ "incident_id": incident_id }),
+            ),
+            BusEvent::Recording(RecordingEvent::Stopped) => (WebhookEventKind::RecordingStopped, json!({})),
+            BusEvent::Recording(RecordingEvent::RemainingLow { minutes, limited_by }) => (
+                WebhookEventKind::DeviceFault,
+                json!({ "fault": "recording_time_low", "minutes": minutes, "limited_by": limited_by }),
+            ),
+            BusEvent::Hardware(HardwareEvent::SensorError { sensor, error }) => (
+                WebhookEventKind::DeviceFault,
+                json!({ "fault": "sensor_error", "sensor": sensor, "error": error }),
+            ),
+            BusEvent::Hardware(HardwareEvent::TamperDetected) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "tamper_detected" }))
+            }
+            BusEvent::Hardware(HardwareEvent::StorageFull) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "storage_full" }))
+            }
+            BusEvent::Hardware(HardwareEvent::BatteryCritical { level }) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "battery_critical", "level": level }))
+            }
+            BusEvent::Hardware(HardwareEvent::TemperatureHigh { temp }) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "temperature_high", "temperature": temp }))
+            }
+            BusEvent::Hardware(HardwareEvent::BatteryOverheating { temp }) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "battery_overheating", "temperature": temp }))
+            }
+            BusEvent::Hardware(HardwareEvent::ChargeCurrentAnomaly { current_ma }) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "charge_current_anomaly", "current_ma": current_ma }))
+            }
+            BusEvent::Hardware(HardwareEvent::BatteryCapacityDrop { drop_percent }) => {
+                (WebhookEventKind::DeviceFault, json!({ "fault": "battery_capacity_drop", "drop_percent": drop_percent }))
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            device_id: device_id.to_string(),
+            occurred_at: Utc::now(),
+            data,
+        })
+    }
+}
+
+/// `sha256=<hex HMAC-SHA256 of "{timestamp}.{body}">`. The timestamp is
+/// covered so a captured delivery can't be replayed later.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POSTs signed events from the bus to the configured webhooks
+pub struct WebhookEmitter {
+    hooks: Vec<WebhookConfig>,
+    device_id: String,
+    client: Client,
+    retry: RetryPolicy,
+}
+
+impl WebhookEmitter {
+    /// None when no webhooks are configured
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        if config.webhooks.is_empty() {
+            return Ok(None);
+        }
+
+        // Receivers are on the local network, often without TLS
+        let client = Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .context("Failed to create webhook HTTP client")?;
+
+        Ok(Some(Self {
+            hooks: config.webhooks.clone(),
+            device_id: config.device_id.clone().unwrap_or_default(),
+            client,
+            retry: RetryPolicy::from_config(&config.network),
+        }))
+    }
+
+    /// Deliver events until the bus closes. Each delivery runs on its own so
+    /// a slow or unreachable receiver doesn't hold up the others.
+    pub async fn run(self, mut events: Subscription) -> Result<()> {
+        let emitter = Arc::new(self);
+        while let Some(event) = events.recv().await {
+            let Some(payload) = WebhookPayload::from_event(&event, &emitter.device_id) else {
+                continue;
+            };
+
+            for index in 0..emitter.hooks.len() {
+                if !emitter.hooks[index].wants(payload.kind) {
+                    continue;
+                }
+                let emitter = emitter.clone();
+                let payload = payload.clone();
+                tokio::spawn(async move {
+                    let hook = &emitter.hooks[index];
+                    if let Err(e) = emitter.deliver(hook, &payload).await {
+                        tracing::warn!("Webhook {:?} to {} not delivered: {:#}", payload.kind, hook.url, e);
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn deliver(&self, hook: &WebhookConfig, payload: &WebhookPayload) -> Result<()> {
+        let body = serde_json::to_vec(payload)?;
+        let response = send_with_retry(&self.retry, || async {
+            // Signed per attempt so the timestamp stays fresh across retries
+            let timestamp = Utc::now().timestamp();
+            self.client
+                .post(&hook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, sign(&hook.secret, timestamp, &body))
+                .body(body.clone())
+                .send()
+                .await
+                .context("Failed to reach webhook")
+        }).await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Webhook answered HTTP {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incident::{IncidentSeverity, IncidentType};
+
+    #[test]
+    fn test_signature_covers_timestamp_and_body() {
+        let signature = sign("secret", 1_700_000_000, b"{}");
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_eq!(signature, sign("secret", 1_700_000_000, b"{}"));
+        assert_ne!(signature, sign("secret", 1_700_000_001, b"{}"));
+        assert_ne!(signature, sign("other", 1_700_000_000, b"{}"));
+    }
+
+    #[test]
+    fn test_only_relevant_events_become_webhooks() {
+        let incident = BusEvent::Incident(IncidentEvent::Triggered {
+            incident_id: "inc-1".to_string(),
+            incident_type: IncidentType::Panic,
+            severity: IncidentSeverity::Critical,
+        });
+        let payload = WebhookPayload::from_event(&incident, "dev-1").unwrap();
+        assert_eq!(payload.kind, WebhookEventKind::IncidentCreated);
+        assert_eq!(payload.data["incident_id"], "inc-1");
+        assert_eq!(payload.data["incident_type"], "panic");
+
+        let fault = WebhookPayload::from_event(&BusEvent::Hardware(HardwareEvent::TamperDetected), "dev-1").unwrap();
+        assert_eq!(fault.kind, WebhookEventKind::DeviceFault);
+
+        assert!(WebhookPayload::from_event(&BusEvent::Hardware(HardwareEvent::MotionDetected { intensity: 0.3 }), "dev-1").is_none());
+    }
+
+    #[test]
+    fn test_hook_filters_by_kind() {
+        let hook = WebhookConfig {
+            url: "http://alarm-panel.local/hook".to_string(),
+            secret: "secret".to_string(),
+            events: vec![WebhookEventKind::IncidentCreated],
+        };
+        assert!(hook.wants(WebhookEventKind::IncidentCreated));
+        assert!(!hook.wants(WebhookEventKind::RecordingStarted));
+        assert!(WebhookConfig { events: Vec::new(), ..hook }.wants(WebhookEventKind::DeviceFault));
+    }
+}
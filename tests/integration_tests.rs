@@ -1,4 +1,4 @@
-use bodycam_client::*;
+use bodycam_core::*;
 use tempfile::TempDir;
 use tokio;
 
@@ -57,6 +57,88 @@ async fn test_resource_manager() {
     let _ = std::fs::remove_file(&temp_file);
 }
 
+#[tokio::test]
+async fn test_device_on_mock_hardware() {
+    let mut config = config::Config::default();
+    config.simulation.enabled = true;
+
+    let hardware = hardware::mock::MockHardware::new();
+    hardware.set_battery_level(42.0);
+    hardware.set_charging(true);
+    let device = device::BodycamDevice::with_hardware(config, Box::new(hardware.clone())).await.unwrap();
+
+    let status = device.get_status().await.unwrap();
+    assert_eq!(status.battery_level, 42.0);
+    assert!(status.is_charging);
+
+    hardware.fail(hardware::mock::MockOperation::Temperature);
+    assert!(device.get_status().await.is_err());
+}
+
+/// A provisioned device recording to simulated encoders
+async fn provisioned_simulated_device() -> device::BodycamDevice {
+    let mut config = config::Config::default();
+    config.simulation.enabled = true;
+    config.recording.preflight.enabled = false;
+    config.device_id = Some("test-device-001".to_string());
+    config.device_key = Some("test-key".to_string());
+    config.site_id = Some("test-site".to_string());
+    config.tenant_id = Some("test-tenant".to_string());
+
+    let hardware = hardware::mock::MockHardware::new();
+    device::BodycamDevice::with_hardware(config, Box::new(hardware)).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_detection_fires_after_a_recording_has_stopped() {
+    let device = provisioned_simulated_device().await;
+
+    device.start_recording(None, None).await.unwrap();
+    device.stop_recording().await.unwrap();
+    assert!(device.active_incident_handle().get().is_none());
+
+    for _ in 0..2 {
+        device.simulate_detection(detection::DetectionEvent {
+            class: detection::DetectionClass::Weapon,
+            label: "knife".to_string(),
+            confidence: 0.9,
+            bbox: [0.1, 0.1, 0.4, 0.4],
+            timestamp: chrono::Utc::now(),
+        }).await;
+    }
+    assert!(device.active_incident_handle().get().is_some());
+
+    let _ = device.stop_recording().await;
+}
+
+#[tokio::test]
+async fn test_recording_cant_be_paused_during_an_incident() {
+    let device = provisioned_simulated_device().await;
+
+    let _ = device.trigger_incident(incident::IncidentType::Emergency, incident::IncidentSeverity::High).await;
+    let error = device.pause_recording(None, "test").await.unwrap_err();
+    assert!(error.to_string().contains("incident"));
+    assert!(!device.is_recording_paused());
+
+    let _ = device.stop_recording().await;
+}
+
+#[tokio::test]
+async fn test_loop_footage_isnt_reported_as_an_incident() {
+    let device = provisioned_simulated_device().await;
+    let mut events = device.event_bus().subscribe();
+
+    device.start_recording(None, Some(loop_recording::LOOP_INCIDENT_ID.to_string())).await.unwrap();
+    let incident_id = loop {
+        if let Some(event_bus::BusEvent::Recording(event_bus::RecordingEvent::Started { incident_id })) = events.recv().await {
+            break incident_id;
+        }
+    };
+    assert_eq!(incident_id, None);
+
+    let _ = device.stop_recording().await;
+}
+
 #[cfg(test)]
 mod validation_tests {
     use super::*;
@@ -1,4 +1,4 @@
-use bodycam_client::*;
+use bodycam_core::*;
 use std::time::{Duration, Instant};
 use tokio::time::timeout;
 